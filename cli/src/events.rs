@@ -0,0 +1,835 @@
+//! Decoding the events emitted by the contract into a readable form.
+//!
+//! Event payloads are taken as they're served by the node's event API -
+//! either hex or base64 encoded - and printed field by field, with keys in
+//! base58 as they're shown everywhere else in the CLI.
+
+use std::fmt::Write;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+
+use multisig_contract_types::*;
+use multisig_wallet::decode_event;
+
+use crate::Result;
+
+/// Decodes a hex or base64 encoded event payload into its raw bytes.
+pub fn parse_payload(payload: &str) -> Result<Vec<u8>> {
+    let payload = payload.trim();
+    let hex = payload.strip_prefix("0x").unwrap_or(payload);
+
+    if let Ok(bytes) = hex::decode(hex) {
+        return Ok(bytes);
+    }
+    BASE64
+        .decode(payload)
+        .map_err(|_| "The payload should be hex or base64 encoded".into())
+}
+
+/// Decodes the payload of an event emitted with the given topic, and renders
+/// it in a human-readable form.
+pub fn render_event(topic: &str, data: &[u8]) -> Result<String> {
+    let mut out = String::new();
+
+    match topic {
+        "create_account" => {
+            let event: CreateAccountEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            writeln!(out, "threshold:     {}", event.threshold)?;
+            writeln!(out, "description:   {:?}", event.description)?;
+            match event.alias {
+                Some(alias) => writeln!(out, "alias:         {alias}")?,
+                None => writeln!(out, "alias:         none")?,
+            }
+            match event.template_id {
+                Some(id) => writeln!(out, "template_id:   {id}")?,
+                None => writeln!(out, "template_id:   none")?,
+            }
+            write_keys(&mut out, "keys", &event.keys)?;
+            writeln!(out, "key_weights:")?;
+            for w in &event.key_weights {
+                writeln!(out, "  - {}:{}", key(&w.key), w.weight)?;
+            }
+            writeln!(out, "key_roles:")?;
+            for r in &event.key_roles {
+                writeln!(out, "  - {}:{}", key(&r.key), r.role.name())?;
+            }
+        }
+        "register_template" => {
+            let event: RegisterTemplateEvent = decode_event(data)?;
+            let template = &event.template;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "template_id:   {}", event.template_id)?;
+            writeln!(out, "threshold:     {}", template.threshold)?;
+            writeln!(out, "description:   {:?}", template.description)?;
+            writeln!(out, "private:       {}", template.private_events)?;
+            match &template.velocity_limit {
+                Some(limit) => {
+                    writeln!(out, "velocity:      {}", velocity_limit(limit))?
+                }
+                None => writeln!(out, "velocity:      none")?,
+            }
+            write_cosigners(&mut out, "cosigners", &template.cosigners)?;
+            write_keys(&mut out, "guardians", &template.guardians)?;
+        }
+        "deposit" => {
+            let event: DepositEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            writeln!(out, "amount:        {}", event.amount)?;
+            writeln!(out, "balance_after: {}", event.balance_after)?;
+            writeln!(out, "memo:          {}", memo(&event.memo))?;
+            writeln!(
+                out,
+                "reference:     {}",
+                memo(&event.depositor_reference)
+            )?;
+            writeln!(out, "nonce:         {}", event.nonce)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+        }
+        "transfer" => {
+            let event: TransferEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            let receiver = match &event.receiver {
+                Disclosure::Revealed(receiver) => key(receiver),
+                Disclosure::Hashed(hash) => hashed(hash),
+            };
+            writeln!(out, "receiver:      {receiver}")?;
+            writeln!(out, "amount:        {}", event.amount)?;
+            writeln!(out, "balance_after: {}", event.balance_after)?;
+            writeln!(out, "memo:          {}", memo(&event.memo))?;
+            let reference = match &event.reference {
+                Some(Disclosure::Revealed(reference)) => {
+                    format!("0x{}", hex::encode(reference))
+                }
+                Some(Disclosure::Hashed(hash)) => hashed(hash),
+                None => String::from("none"),
+            };
+            writeln!(out, "reference:     {reference}")?;
+            let category = event.category.as_deref().unwrap_or("none");
+            writeln!(out, "category:      {category}")?;
+            match event.claim_id {
+                Some(claim_id) => writeln!(out, "claim_id:      {claim_id}")?,
+                None => writeln!(out, "claim_id:      none (paid)")?,
+            }
+            writeln!(out, "nonce:         {}", event.nonce)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+            writeln!(out, "group_signed:  {}", event.group_signed)?;
+            write_keys(&mut out, "keys", &event.keys)?;
+            write_cosigners(&mut out, "cosigners", &event.cosigners)?;
+        }
+        "payroll" => {
+            let event: PayrollEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            writeln!(
+                out,
+                "row:           {} of {}",
+                event.row + 1,
+                event.rows
+            )?;
+            let receiver = match &event.receiver {
+                Disclosure::Revealed(receiver) => key(receiver),
+                Disclosure::Hashed(hash) => hashed(hash),
+            };
+            writeln!(out, "receiver:      {receiver}")?;
+            writeln!(out, "amount:        {}", event.amount)?;
+            writeln!(out, "balance_after: {}", event.balance_after)?;
+            writeln!(out, "memo:          {}", memo(&event.memo))?;
+            writeln!(out, "nonce:         {}", event.nonce)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+            writeln!(out, "group_signed:  {}", event.group_signed)?;
+            write_keys(&mut out, "keys", &event.keys)?;
+            write_cosigners(&mut out, "cosigners", &event.cosigners)?;
+        }
+        "gas_refund" => {
+            let event: GasRefundEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            let submitter = match &event.submitter {
+                Disclosure::Revealed(submitter) => key(submitter),
+                Disclosure::Hashed(hash) => hashed(hash),
+            };
+            writeln!(out, "submitter:     {submitter}")?;
+            writeln!(out, "amount:        {}", event.amount)?;
+            writeln!(out, "balance_after: {}", event.balance_after)?;
+            writeln!(out, "nonce:         {}", event.nonce)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+        }
+        "change_account" => {
+            let event: ChangeAccountEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            let previous = event.previous_threshold;
+            match event.threshold {
+                Some(threshold) => {
+                    writeln!(out, "threshold:     {previous} -> {threshold}")?
+                }
+                None => writeln!(out, "threshold:     {previous} (unchanged)")?,
+            }
+            match event.description {
+                Some(description) => {
+                    writeln!(out, "description:   {description:?}")?
+                }
+                None => writeln!(out, "description:   unchanged")?,
+            }
+            match event.private_events {
+                Some(private) => writeln!(out, "private:       {private}")?,
+                None => writeln!(out, "private:       unchanged")?,
+            }
+            match event.group_key {
+                Some(Some(group_key)) => {
+                    writeln!(out, "group_key:     {}", key(&group_key))?
+                }
+                Some(None) => writeln!(out, "group_key:     removed")?,
+                None => writeln!(out, "group_key:     unchanged")?,
+            }
+            match event.velocity_limit {
+                Some(Some(limit)) => {
+                    writeln!(out, "velocity:      {}", velocity_limit(&limit))?
+                }
+                Some(None) => writeln!(out, "velocity:      removed")?,
+                None => writeln!(out, "velocity:      unchanged")?,
+            }
+            writeln!(out, "unlocked:      {}", event.unlocked)?;
+            writeln!(out, "whitelist_off: {}", event.whitelist_lifted)?;
+            match event.archived {
+                Some(true) => writeln!(out, "archival:      archived")?,
+                Some(false) => writeln!(out, "archival:      reactivated")?,
+                None => writeln!(out, "archival:      unchanged")?,
+            }
+            match event.claimable_withdrawals {
+                Some(claimable) => writeln!(out, "claimable:     {claimable}")?,
+                None => writeln!(out, "claimable:     unchanged")?,
+            }
+            match event.history_retention {
+                Some(Some(records)) => {
+                    writeln!(out, "retention:     {records}")?
+                }
+                Some(None) => writeln!(out, "retention:     default")?,
+                None => writeln!(out, "retention:     unchanged")?,
+            }
+            writeln!(out, "storage_fee:   {}", event.storage_fee)?;
+            match &event.compliance {
+                Some(Some(compliance)) => {
+                    writeln!(out, "compliance:    {}", key(&compliance.key))?;
+                    let remediation = key(&compliance.remediation);
+                    writeln!(out, "remediation:   {remediation}")?;
+                }
+                Some(None) => writeln!(out, "compliance:    removed")?,
+                None => writeln!(out, "compliance:    unchanged")?,
+            }
+            writeln!(out, "hold_lifted:   {}", event.hold_lifted)?;
+            match event.license_policy {
+                Some(Some(policy)) => {
+                    let contract = hex::encode(policy.contract);
+                    writeln!(out, "license:       0x{contract}")?;
+                    writeln!(out, "license_keys:  {}", policy.signers)?;
+                }
+                Some(None) => writeln!(out, "license:       removed")?,
+                None => writeln!(out, "license:       unchanged")?,
+            }
+            writeln!(out, "set_operation_thresholds:")?;
+            for t in &event.set_operation_thresholds {
+                writeln!(out, "  - {}:{}", t.op.fn_name(), t.threshold)?;
+            }
+            writeln!(out, "removed_operation_thresholds:")?;
+            for op in &event.removed_operation_thresholds {
+                writeln!(out, "  - {}", op.fn_name())?;
+            }
+            match &event.transfer_tiers {
+                Some(tiers) => {
+                    writeln!(out, "transfer_tiers:")?;
+                    for tier in tiers {
+                        writeln!(
+                            out,
+                            "  - {}:{}",
+                            tier.ceiling, tier.threshold
+                        )?;
+                    }
+                }
+                None => writeln!(out, "transfer_tiers: unchanged")?,
+            }
+            writeln!(out, "with_transfer: {}", event.with_transfer)?;
+            match event.alias {
+                Some(Some(alias)) => writeln!(out, "alias:         {alias}")?,
+                Some(None) => writeln!(out, "alias:         released")?,
+                None => writeln!(out, "alias:         unchanged")?,
+            }
+            match event.balance_cap {
+                Some(Some(cap)) => writeln!(out, "balance_cap:   {cap}")?,
+                Some(None) => writeln!(out, "balance_cap:   removed")?,
+                None => writeln!(out, "balance_cap:   unchanged")?,
+            }
+            match event.liveness_policy {
+                Some(Some(policy)) => writeln!(
+                    out,
+                    "liveness:      {}",
+                    liveness_policy(&policy)
+                )?,
+                Some(None) => writeln!(out, "liveness:      removed")?,
+                None => writeln!(out, "liveness:      unchanged")?,
+            }
+            match event.supermajority {
+                Some(Some(supermajority)) => writeln!(
+                    out,
+                    "supermajority: {}",
+                    self::supermajority(&supermajority)
+                )?,
+                Some(None) => writeln!(out, "supermajority: removed")?,
+                None => writeln!(out, "supermajority: unchanged")?,
+            }
+            match event.key_addition_policy {
+                Some(Some(policy)) => writeln!(
+                    out,
+                    "key_addition:  {}",
+                    key_addition_policy(&policy)
+                )?,
+                Some(None) => writeln!(out, "key_addition:  removed")?,
+                None => writeln!(out, "key_addition:  unchanged")?,
+            }
+            match event.transfer_window {
+                Some(Some(window)) => writeln!(
+                    out,
+                    "window:        {}",
+                    transfer_window(&window)
+                )?,
+                Some(None) => writeln!(out, "window:        removed")?,
+                None => writeln!(out, "window:        unchanged")?,
+            }
+            match event.recovery_policy {
+                Some(Some(policy)) => writeln!(
+                    out,
+                    "recovery:      {}",
+                    recovery_policy(&policy)
+                )?,
+                Some(None) => writeln!(out, "recovery:      removed")?,
+                None => writeln!(out, "recovery:      unchanged")?,
+            }
+            match event.inactivity_policy {
+                Some(Some(policy)) => writeln!(
+                    out,
+                    "inactivity:    {}",
+                    inactivity_policy(&policy)
+                )?,
+                Some(None) => writeln!(out, "inactivity:    removed")?,
+                None => writeln!(out, "inactivity:    unchanged")?,
+            }
+            match event.queue_policy {
+                Some(Some(policy)) => {
+                    writeln!(out, "queue:         {}", queue_policy(&policy))?
+                }
+                Some(None) => writeln!(out, "queue:         removed")?,
+                None => writeln!(out, "queue:         unchanged")?,
+            }
+            writeln!(out, "nonce:         {}", event.nonce)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+            writeln!(out, "group_signed:  {}", event.group_signed)?;
+            write_keys(&mut out, "keys", &event.keys)?;
+            write_keys(&mut out, "added_keys", &event.added_keys)?;
+            write_keys(&mut out, "removed_keys", &event.removed_keys)?;
+            write_cosigners(&mut out, "cosigners", &event.cosigners)?;
+            let added = &event.added_cosigners;
+            write_cosigners(&mut out, "added_cosigners", added)?;
+            let removed = &event.removed_cosigners;
+            write_cosigners(&mut out, "removed_cosigners", removed)?;
+            writeln!(out, "whitelisted:")?;
+            for entry in &event.whitelisted {
+                let receiver = key(&entry.receiver);
+                writeln!(out, "  - {receiver} (from {})", entry.usable_from)?;
+            }
+            write_keys(&mut out, "unwhitelisted", &event.unwhitelisted)?;
+            let added = &event.added_guardians;
+            write_keys(&mut out, "added_guardians", added)?;
+            let removed = &event.removed_guardians;
+            write_keys(&mut out, "removed_guardians", removed)?;
+            let added = &event.added_observers;
+            write_keys(&mut out, "added_observers", added)?;
+            let removed = &event.removed_observers;
+            write_keys(&mut out, "removed_observers", removed)?;
+            writeln!(out, "added_tags:")?;
+            for tag in &event.added_tags {
+                writeln!(out, "  - {tag}")?;
+            }
+            writeln!(out, "removed_tags:")?;
+            for tag in &event.removed_tags {
+                writeln!(out, "  - {tag}")?;
+            }
+            writeln!(out, "set_categories:")?;
+            for category in &event.set_categories {
+                writeln!(out, "  - {}", spending_category(category))?;
+            }
+            writeln!(out, "removed_categories:")?;
+            for name in &event.removed_categories {
+                writeln!(out, "  - {name}")?;
+            }
+            writeln!(out, "set_child_budgets:")?;
+            for budget in &event.set_child_budgets {
+                writeln!(out, "  - {}", child_budget(budget))?;
+            }
+            writeln!(out, "removed_child_budgets:")?;
+            for child_id in &event.removed_child_budgets {
+                writeln!(out, "  - {child_id}")?;
+            }
+            writeln!(out, "set_key_expiries:")?;
+            for e in &event.set_key_expiries {
+                writeln!(out, "  - {}:{}", key(&e.key), e.valid_until)?;
+            }
+            let removed = &event.removed_key_expiries;
+            write_keys(&mut out, "removed_key_expiries", removed)?;
+            writeln!(out, "set_key_weights:")?;
+            for w in &event.set_key_weights {
+                writeln!(out, "  - {}:{}", key(&w.key), w.weight)?;
+            }
+            writeln!(out, "set_key_roles:")?;
+            for r in &event.set_key_roles {
+                writeln!(out, "  - {}:{}", key(&r.key), r.role.name())?;
+            }
+            writeln!(out, "set_allowances:")?;
+            for a in &event.set_allowances {
+                writeln!(out, "  - {}:{}", key(&a.key), a.amount)?;
+            }
+        }
+        "account_locked" => {
+            let event: AccountLockedEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            writeln!(out, "key:           {}", key(&event.key))?;
+            writeln!(out, "nonce:         {}", event.nonce)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+        }
+        "hold_placed" => {
+            let event: HoldPlacedEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            writeln!(out, "key:           {}", key(&event.key))?;
+            writeln!(out, "remediation:   {}", key(&event.remediation))?;
+            writeln!(out, "nonce:         {}", event.nonce)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+        }
+        "liveness_attested" => {
+            let event: LivenessAttestedEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            writeln!(out, "key:           {}", key(&event.key))?;
+            writeln!(out, "attested_at:   {}", event.attested_at)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+        }
+        "key_delegated" => {
+            let event: KeyDelegatedEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            writeln!(out, "key:           {}", key(&event.key))?;
+            match event.delegation {
+                Some(d) => {
+                    writeln!(out, "delegate:      {}", key(&d.delegate))?;
+                    writeln!(out, "from_height:   {}", d.from_height)?;
+                    writeln!(out, "until_height:  {}", d.until_height)?;
+                }
+                None => writeln!(out, "delegate:      revoked")?,
+            }
+            writeln!(out, "nonce:         {}", event.nonce)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+        }
+        "guardian_action" => {
+            let event: GuardianActionEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            writeln!(out, "guardian:      {}", key(&event.key))?;
+            match event.action {
+                GuardianAction::Lock => writeln!(out, "action:        lock")?,
+                GuardianAction::ExtendWhitelistDelay {
+                    receiver,
+                    usable_from,
+                } => {
+                    writeln!(out, "action:        extend whitelist delay")?;
+                    writeln!(out, "receiver:      {}", key(&receiver))?;
+                    writeln!(out, "usable_from:   {usable_from}")?;
+                }
+                GuardianAction::VetoWhitelisted { receiver } => {
+                    writeln!(out, "action:        veto whitelisted")?;
+                    writeln!(out, "receiver:      {}", key(&receiver))?;
+                }
+            }
+            writeln!(out, "nonce:         {}", event.nonce)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+        }
+        "recovery_started" => {
+            let event: RecoveryStartedEvent = decode_event(data)?;
+            let recovery = &event.recovery;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            writeln!(out, "threshold:     {}", recovery.threshold)?;
+            writeln!(out, "executable:    {}", recovery.executable_from)?;
+            writeln!(out, "nonce:         {}", event.nonce)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+            write_keys(&mut out, "guardians", &recovery.guardians)?;
+            write_keys(&mut out, "keys", &recovery.keys)?;
+        }
+        "recovery_cancelled" => {
+            let event: RecoveryCancelledEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            writeln!(out, "key:           {}", key(&event.key))?;
+            writeln!(out, "nonce:         {}", event.nonce)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+        }
+        "recovery_finalized" => {
+            let event: RecoveryFinalizedEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            writeln!(out, "threshold:     {}", event.threshold)?;
+            writeln!(out, "nonce:         {}", event.nonce)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+            write_keys(&mut out, "keys", &event.keys)?;
+            write_keys(&mut out, "removed_keys", &event.removed_keys)?;
+        }
+        "inactive_claimed" => {
+            let event: InactiveClaimedEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            writeln!(out, "key:           {}", key(&event.key))?;
+            writeln!(out, "last_active:   {}", event.last_active)?;
+            writeln!(out, "nonce:         {}", event.nonce)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+            match event.claim {
+                InactiveClaim::Sweep { receiver } => {
+                    writeln!(out, "claim:         sweep")?;
+                    writeln!(out, "receiver:      {}", key(&receiver))?;
+                }
+                InactiveClaim::RotateKeys { keys, threshold } => {
+                    writeln!(out, "claim:         rotate keys")?;
+                    writeln!(out, "threshold:     {threshold}")?;
+                    write_keys(&mut out, "keys", &keys)?;
+                    write_keys(&mut out, "removed_keys", &event.removed_keys)?;
+                }
+            }
+        }
+        "transfer_queued" => {
+            let event: TransferQueuedEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            writeln!(out, "amount:        {}", event.amount)?;
+            writeln!(out, "executable:    {}", event.executable_from)?;
+            writeln!(out, "nonce:         {}", event.nonce)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+            writeln!(out, "group_signed:  {}", event.group_signed)?;
+            write_keys(&mut out, "keys", &event.keys)?;
+            write_cosigners(&mut out, "cosigners", &event.cosigners)?;
+        }
+        "queued_cancelled" => {
+            let event: QueuedCancelledEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            writeln!(out, "key:           {}", key(&event.key))?;
+            writeln!(out, "nonce:         {}", event.nonce)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+        }
+        "allowance_spent" => {
+            let event: AllowanceSpentEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            writeln!(out, "key:           {}", key(&event.key))?;
+            writeln!(out, "amount:        {}", event.amount)?;
+            writeln!(out, "remaining:     {}", event.remaining)?;
+            writeln!(out, "nonce:         {}", event.nonce)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+        }
+        "queued_executed" => {
+            let event: QueuedExecutedEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            writeln!(out, "nonce:         {}", event.nonce)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+        }
+        "queued_skipped" => {
+            let event: QueuedSkippedEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            writeln!(out, "nonce:         {}", event.nonce)?;
+            writeln!(out, "amount:        {}", event.amount)?;
+            writeln!(out, "balance:       {}", event.balance)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+        }
+        "queued_expired" => {
+            let event: QueuedExpiredEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            writeln!(out, "nonce:         {}", event.nonce)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+        }
+        "merge_accounts" => {
+            let event: MergeAccountsEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            writeln!(out, "merged_id:     {}", event.merged_id)?;
+            writeln!(out, "amount:        {}", event.amount)?;
+            writeln!(out, "balance_after: {}", event.balance_after)?;
+            writeln!(out, "nonce:         {}", event.nonce)?;
+            writeln!(out, "merged_nonce:  {}", event.merged_nonce)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+            write_keys(&mut out, "added_keys", &event.added_keys)?;
+            let added = &event.added_cosigners;
+            write_cosigners(&mut out, "added_cosigners", added)?;
+        }
+        "budget_drawn" => {
+            let event: BudgetDrawnEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            writeln!(out, "parent_id:     {}", event.parent_id)?;
+            writeln!(out, "amount:        {}", event.amount)?;
+            writeln!(out, "balance_after: {}", event.balance_after)?;
+            writeln!(
+                out,
+                "parent_balance_after: {}",
+                event.parent_balance_after
+            )?;
+            writeln!(out, "nonce:         {}", event.nonce)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+        }
+        "budget_clawed_back" => {
+            let event: BudgetClawedBackEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            writeln!(out, "child_id:      {}", event.child_id)?;
+            writeln!(out, "amount:        {}", event.amount)?;
+            writeln!(out, "balance_after: {}", event.balance_after)?;
+            writeln!(
+                out,
+                "child_balance_after: {}",
+                event.child_balance_after
+            )?;
+            writeln!(out, "nonce:         {}", event.nonce)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+        }
+        "checkpoint" => {
+            let event: CheckpointEvent = decode_event(data)?;
+            let checkpoint = &event.checkpoint;
+            writeln!(out, "sequence:      {}", checkpoint.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            writeln!(out, "balance:       {}", checkpoint.balance)?;
+            writeln!(out, "nonce:         {}", checkpoint.nonce)?;
+            let keys_hash = hex::encode(checkpoint.keys_hash);
+            writeln!(out, "keys_hash:     0x{keys_hash}")?;
+            writeln!(out, "block_height:  {}", checkpoint.block_height)?;
+        }
+        "configure" => {
+            let event: ConfigureEvent = decode_event(data)?;
+            let config = &event.config;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            match config.max_keys {
+                Some(max) => writeln!(out, "max_keys:      {max}")?,
+                None => writeln!(out, "max_keys:      none")?,
+            }
+            match config.max_memo_len {
+                Some(max) => writeln!(out, "max_memo_len:  {max}")?,
+                None => writeln!(out, "max_memo_len:  none")?,
+            }
+            writeln!(out, "paused:        {}", config.paused)?;
+            writeln!(out, "record_fee:    {}", config.history_record_fee)?;
+            writeln!(out, "max_refund:    {}", config.max_gas_refund)?;
+            writeln!(out, "nonce:         {}", event.nonce)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+            writeln!(out, "group_signed:  {}", event.group_signed)?;
+            write_keys(&mut out, "keys", &event.keys)?;
+            write_cosigners(&mut out, "cosigners", &event.cosigners)?;
+        }
+        "withdrawal_claimed" => {
+            let event: WithdrawalClaimedEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "claim_id:      {}", event.claim_id)?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            let receiver = match &event.receiver {
+                Disclosure::Revealed(receiver) => key(receiver),
+                Disclosure::Hashed(hash) => hashed(hash),
+            };
+            writeln!(out, "receiver:      {receiver}")?;
+            writeln!(out, "amount:        {}", event.amount)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+        }
+        "payout_deferred" => {
+            let event: PayoutDeferredEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "claim_id:      {}", event.claim_id)?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            let receiver = match &event.receiver {
+                Disclosure::Revealed(receiver) => key(receiver),
+                Disclosure::Hashed(hash) => hashed(hash),
+            };
+            writeln!(out, "receiver:      {receiver}")?;
+            writeln!(out, "amount:        {}", event.amount)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+        }
+        "split_account" => {
+            let event: SplitAccountEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            writeln!(out, "new_account_id: {}", event.new_account_id)?;
+            writeln!(out, "amount:        {}", event.amount)?;
+            writeln!(out, "balance_after: {}", event.balance_after)?;
+            writeln!(out, "nonce:         {}", event.nonce)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+        }
+        "velocity_warning" => {
+            let event: VelocityWarningEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "account_id:    {}", event.account_id)?;
+            writeln!(out, "limit:         {}", velocity_limit(&event.limit))?;
+            writeln!(out, "window_start:  {}", event.window_start)?;
+            writeln!(out, "outflow:       {}", event.outflow)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+        }
+        "key_compromised" => {
+            let event: KeyCompromisedEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "key:           {}", key(&event.key))?;
+            writeln!(out, "account_ids:   {:?}", event.account_ids)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+        }
+        "cosigner_compromised" => {
+            let event: CosignerCompromisedEvent = decode_event(data)?;
+            writeln!(out, "sequence:      {}", event.sequence)?;
+            writeln!(out, "event_id:      0x{}", hex::encode(event.event_id))?;
+            writeln!(out, "key:           {}", event.key)?;
+            writeln!(out, "account_ids:   {:?}", event.account_ids)?;
+            writeln!(out, "block_height:  {}", event.block_height)?;
+        }
+        _ => return Err(format!("Unknown event topic: {topic}").into()),
+    }
+
+    Ok(out)
+}
+
+/// Shows a velocity limit as it's given to the CLI.
+fn velocity_limit(limit: &VelocityLimit) -> String {
+    format!("{}/{}", limit.amount, limit.window)
+}
+
+/// Formats a liveness policy as it's given to the CLI.
+fn liveness_policy(policy: &LivenessPolicy) -> String {
+    format!("{}/{}", policy.window, policy.min_threshold)
+}
+
+/// Formats a key addition policy as its delay, followed by whether it needs
+/// the supermajority.
+fn key_addition_policy(policy: &KeyAdditionPolicy) -> String {
+    match policy.supermajority {
+        true => format!("{} blocks, supermajority", policy.delay),
+        false => format!("{} blocks", policy.delay),
+    }
+}
+
+/// Formats a recovery policy as its delay, followed by the guardians it
+/// needs.
+fn recovery_policy(policy: &RecoveryPolicy) -> String {
+    format!("{} blocks, {} guardians", policy.delay, policy.guardians)
+}
+
+/// Formats an inactivity policy as its fallback key, followed by its period.
+fn inactivity_policy(policy: &InactivityPolicy) -> String {
+    format!("{}, {} blocks", key(&policy.fallback), policy.period)
+}
+
+/// Formats a queue policy as the smallest amount queued, followed by its
+/// delay.
+fn queue_policy(policy: &QueuePolicy) -> String {
+    format!("from {}, {} blocks", policy.min_amount, policy.delay)
+}
+
+/// Formats a spending category as it's given to the CLI.
+fn spending_category(category: &SpendingCategory) -> String {
+    format!("{}:{}/{}", category.name, category.budget, category.window)
+}
+
+/// Formats a child budget as it's given to the CLI.
+fn child_budget(budget: &ChildBudget) -> String {
+    format!("{}:{}/{}", budget.child_id, budget.amount, budget.window)
+}
+
+/// Formats a transfer window as it's given to the CLI.
+fn transfer_window(window: &TransferWindow) -> String {
+    format!("{}/{}/{}", window.period, window.offset, window.length)
+}
+
+/// Formats a supermajority as it's given to the CLI.
+fn supermajority(supermajority: &Supermajority) -> String {
+    format!("{}/{}", supermajority.numerator, supermajority.denominator)
+}
+
+/// Writes a list of keys, one per line.
+fn write_keys(
+    out: &mut String,
+    name: &str,
+    keys: &[bls::PublicKey],
+) -> Result<()> {
+    writeln!(out, "{name}:")?;
+    for k in keys {
+        writeln!(out, "  - {}", key(k))?;
+    }
+    Ok(())
+}
+
+/// Writes a list of co-signers, one per line.
+fn write_cosigners(
+    out: &mut String,
+    name: &str,
+    keys: &[CosignerKey],
+) -> Result<()> {
+    writeln!(out, "{name}:")?;
+    for k in keys {
+        writeln!(out, "  - {k}")?;
+    }
+    Ok(())
+}
+
+fn key(key: &bls::PublicKey) -> String {
+    encoding::encode_public_key(key)
+}
+
+fn memo(memo: &Disclosure<String>) -> String {
+    match memo {
+        Disclosure::Revealed(memo) => format!("{memo:?}"),
+        Disclosure::Hashed(hash) => hashed(hash),
+    }
+}
+
+/// Renders the hash of a value kept private by its account.
+fn hashed(hash: &[u8; 32]) -> String {
+    format!("hashed 0x{}", hex::encode(hash))
+}
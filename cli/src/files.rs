@@ -0,0 +1,304 @@
+//! Reading and writing the files and textual forms the CLI works with.
+//!
+//! Keys and signatures take the canonical textual forms of the types crate's
+//! [`encoding`] module - base58, as in the rest of the Dusk ecosystem, and
+//! `ed25519:<hex>` for co-signer keys - while operations and partial
+//! signatures are stored as their archived bytes.
+
+use std::fs;
+use std::path::Path;
+
+use bytecheck::CheckBytes;
+use dusk_bytes::Serializable;
+use rkyv::de::deserializers::SharedDeserializeMap;
+use rkyv::ser::serializers::AllocSerializer;
+use rkyv::validation::validators::DefaultValidator;
+use rkyv::{Archive, Deserialize, Serialize};
+
+use multisig_contract_types::{
+    bls, ed25519, encoding, ChildBudget, CosignerKey, GasRefund, KeyAllowance,
+    KeyExpiry, KeyRole, KeyWeight, LivenessPolicy, OperationKind,
+    OperationThreshold, PayrollRow, RoleAssignment, SpendingCategory,
+    Supermajority, TransferTier, TransferWindow, VelocityLimit,
+};
+use multisig_wallet::{decode, encode};
+
+use crate::Result;
+
+/// Parses a base58 encoded public key.
+pub fn parse_public_key(s: &str) -> Result<bls::PublicKey> {
+    encoding::decode_public_key(s)
+        .map_err(|err| format!("{err} public key: {s}").into())
+}
+
+/// Parses a base58 encoded signature.
+pub fn parse_signature(s: &str) -> Result<bls::Signature> {
+    encoding::decode_signature(s)
+        .map_err(|err| format!("{err} signature: {s}").into())
+}
+
+/// Parses a base58 encoded multisignature.
+pub fn parse_multisig_signature(s: &str) -> Result<bls::MultisigSignature> {
+    encoding::decode_multisig_signature(s)
+        .map_err(|err| format!("{err} signature: {s}").into())
+}
+
+/// Parses a co-signer key, prefixed by its scheme, as it is displayed.
+pub fn parse_cosigner_key(s: &str) -> Result<CosignerKey> {
+    encoding::decode_cosigner_key(s)
+        .map_err(|err| format!("{err} co-signer key: {s}").into())
+}
+
+/// Parses a velocity limit, given as `<amount>/<blocks>`.
+pub fn parse_velocity_limit(s: &str) -> Result<VelocityLimit> {
+    let (amount, window) = s
+        .split_once('/')
+        .ok_or_else(|| format!("Invalid velocity limit: {s}"))?;
+    Ok(VelocityLimit {
+        amount: amount.parse()?,
+        window: window.parse()?,
+    })
+}
+
+/// Parses a liveness policy, given as `<blocks>/<min threshold>`.
+pub fn parse_liveness_policy(s: &str) -> Result<LivenessPolicy> {
+    let (window, min_threshold) = s
+        .split_once('/')
+        .ok_or_else(|| format!("Invalid liveness policy: {s}"))?;
+    Ok(LivenessPolicy {
+        window: window.parse()?,
+        min_threshold: min_threshold.parse()?,
+    })
+}
+
+/// Parses a supermajority, given as `<numerator>/<denominator>`.
+pub fn parse_supermajority(s: &str) -> Result<Supermajority> {
+    let (numerator, denominator) = s
+        .split_once('/')
+        .ok_or_else(|| format!("Invalid supermajority: {s}"))?;
+    Ok(Supermajority {
+        numerator: numerator.parse()?,
+        denominator: denominator.parse()?,
+    })
+}
+
+/// Parses a transfer window, given as `<period>/<offset>/<length>` in blocks.
+pub fn parse_transfer_window(s: &str) -> Result<TransferWindow> {
+    let mut parts = s.splitn(3, '/');
+    let (Some(period), Some(offset), Some(length)) =
+        (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(format!("Invalid transfer window: {s}").into());
+    };
+    Ok(TransferWindow {
+        period: period.parse()?,
+        offset: offset.parse()?,
+        length: length.parse()?,
+    })
+}
+
+/// Parses a spending category, given as `<name>:<budget>/<blocks>`.
+pub fn parse_category(s: &str) -> Result<SpendingCategory> {
+    let (name, budget) = s
+        .split_once(':')
+        .ok_or_else(|| format!("Invalid spending category: {s}"))?;
+    let (budget, window) = budget
+        .split_once('/')
+        .ok_or_else(|| format!("Invalid spending category: {s}"))?;
+    Ok(SpendingCategory {
+        name: name.into(),
+        budget: budget.parse()?,
+        window: window.parse()?,
+    })
+}
+
+/// Parses a child budget, given as `<child id>:<amount>/<blocks>`.
+pub fn parse_child_budget(s: &str) -> Result<ChildBudget> {
+    let (child_id, amount) = s
+        .split_once(':')
+        .ok_or_else(|| format!("Invalid child budget: {s}"))?;
+    let (amount, window) = amount
+        .split_once('/')
+        .ok_or_else(|| format!("Invalid child budget: {s}"))?;
+    Ok(ChildBudget {
+        child_id: child_id.parse()?,
+        amount: amount.parse()?,
+        window: window.parse()?,
+    })
+}
+
+/// Parses a row of a payroll, given as `<receiver>:<amount>[:<memo>]`, with
+/// the receiver base58 encoded. The memo is everything after the second colon.
+pub fn parse_payroll_row(s: &str) -> Result<PayrollRow> {
+    let mut parts = s.splitn(3, ':');
+    let (Some(receiver), Some(amount)) = (parts.next(), parts.next()) else {
+        return Err(format!("Invalid payroll row: {s}").into());
+    };
+    Ok(PayrollRow {
+        receiver: parse_public_key(receiver)?,
+        amount: amount.parse()?,
+        memo: parts.next().unwrap_or_default().into(),
+    })
+}
+
+/// Parses a gas refund, given as `<submitter>:<amount>`, with the submitter
+/// base58 encoded.
+pub fn parse_gas_refund(s: &str) -> Result<GasRefund> {
+    let Some((submitter, amount)) = s.split_once(':') else {
+        return Err(format!("Invalid gas refund: {s}").into());
+    };
+    Ok(GasRefund {
+        submitter: parse_public_key(submitter)?,
+        amount: amount.parse()?,
+    })
+}
+
+/// Parses a key expiry, given as `<key>:<height>`, with the key base58
+/// encoded.
+pub fn parse_key_expiry(s: &str) -> Result<KeyExpiry> {
+    let Some((key, valid_until)) = s.split_once(':') else {
+        return Err(format!("Invalid key expiry: {s}").into());
+    };
+    Ok(KeyExpiry {
+        key: parse_public_key(key)?,
+        valid_until: valid_until.parse()?,
+    })
+}
+
+/// Parses a key weight, given as `<key>:<weight>`, with the key base58
+/// encoded.
+pub fn parse_key_weight(s: &str) -> Result<KeyWeight> {
+    let Some((key, weight)) = s.split_once(':') else {
+        return Err(format!("Invalid key weight: {s}").into());
+    };
+    Ok(KeyWeight {
+        key: parse_public_key(key)?,
+        weight: weight.parse()?,
+    })
+}
+
+/// Parses the allowance of a key, given as `<key>:<amount>`, with the key
+/// base58 encoded.
+pub fn parse_key_allowance(s: &str) -> Result<KeyAllowance> {
+    let Some((key, amount)) = s.split_once(':') else {
+        return Err(format!("Invalid allowance: {s}").into());
+    };
+    Ok(KeyAllowance {
+        key: parse_public_key(key)?,
+        amount: amount.parse()?,
+    })
+}
+
+/// Parses the role of a key, given by its name, such as `spender`.
+pub fn parse_key_role(s: &str) -> Result<KeyRole> {
+    KeyRole::ALL
+        .into_iter()
+        .find(|role| role.name() == s)
+        .ok_or_else(|| format!("Invalid key role: {s}").into())
+}
+
+/// Parses the role of a key, given as `<key>:<role>`, with the key base58
+/// encoded.
+pub fn parse_role_assignment(s: &str) -> Result<RoleAssignment> {
+    let Some((key, role)) = s.split_once(':') else {
+        return Err(format!("Invalid key role: {s}").into());
+    };
+    Ok(RoleAssignment {
+        key: parse_public_key(key)?,
+        role: parse_key_role(role)?,
+    })
+}
+
+/// Parses a kind of operation, given as the name of the contract function
+/// performing it, such as `transfer`.
+pub fn parse_operation_kind(s: &str) -> Result<OperationKind> {
+    OperationKind::ALL
+        .into_iter()
+        .find(|op| op.fn_name() == s)
+        .ok_or_else(|| format!("Invalid operation: {s}").into())
+}
+
+/// Parses the threshold of a kind of operation, given as `<op>:<threshold>`.
+pub fn parse_operation_threshold(s: &str) -> Result<OperationThreshold> {
+    let Some((op, threshold)) = s.split_once(':') else {
+        return Err(format!("Invalid operation threshold: {s}").into());
+    };
+    Ok(OperationThreshold {
+        op: parse_operation_kind(op)?,
+        threshold: threshold.parse()?,
+    })
+}
+
+/// Parses a tier of an account's transfers, given as `<ceiling>:<threshold>`.
+pub fn parse_transfer_tier(s: &str) -> Result<TransferTier> {
+    let Some((ceiling, threshold)) = s.split_once(':') else {
+        return Err(format!("Invalid transfer tier: {s}").into());
+    };
+    Ok(TransferTier {
+        ceiling: ceiling.parse()?,
+        threshold: threshold.parse()?,
+    })
+}
+
+/// Parses the ID of a contract, given as 32 hex encoded bytes.
+pub fn parse_contract_id(s: &str) -> Result<[u8; 32]> {
+    hex::decode(s)?
+        .try_into()
+        .map_err(|_| format!("Invalid contract ID length: {s}").into())
+}
+
+/// Parses the reference of a transfer, given as 32 hex encoded bytes.
+pub fn parse_reference(s: &str) -> Result<[u8; 32]> {
+    hex::decode(s)?
+        .try_into()
+        .map_err(|_| format!("Invalid reference length: {s}").into())
+}
+
+/// Reads a hex encoded Ed25519 secret key from the file at the given path.
+pub fn read_ed25519_key(path: &Path) -> Result<ed25519::SigningKey> {
+    let contents = fs::read_to_string(path)?;
+
+    let bytes = hex::decode(contents.trim())?
+        .try_into()
+        .map_err(|_| format!("Invalid secret key length in {path:?}"))?;
+
+    Ok(ed25519::SigningKey::from_bytes(&bytes))
+}
+
+/// Reads a base58 encoded secret key from the file at the given path.
+pub fn read_secret_key(path: &Path) -> Result<bls::SecretKey> {
+    let contents = fs::read_to_string(path)?;
+
+    let bytes = bs58::decode(contents.trim()).into_vec()?;
+    let bytes = bytes
+        .try_into()
+        .map_err(|_| format!("Invalid secret key length in {path:?}"))?;
+
+    bls::SecretKey::from_bytes(&bytes)
+        .map_err(|_| format!("Invalid secret key in {path:?}").into())
+}
+
+/// Writes a secret key, base58 encoded, to the file at the given path.
+pub fn write_secret_key(path: &Path, sk: &bls::SecretKey) -> Result<()> {
+    fs::write(path, bs58::encode(sk.to_bytes()).into_string())?;
+    Ok(())
+}
+
+/// Reads an archived value from the file at the given path.
+pub fn read<T>(path: &Path) -> Result<T>
+where
+    T: Archive,
+    T::Archived: Deserialize<T, SharedDeserializeMap>
+        + for<'b> CheckBytes<DefaultValidator<'b>>,
+{
+    Ok(decode(&fs::read(path)?)?)
+}
+
+/// Writes a value, in its archived form, to the file at the given path.
+pub fn write<T>(path: &Path, value: &T) -> Result<()>
+where
+    T: Serialize<AllocSerializer<1024>>,
+{
+    fs::write(path, encode(value)?)?;
+    Ok(())
+}
@@ -0,0 +1,74 @@
+//! Rendering the ledger of an account as flat records for bookkeeping.
+//!
+//! Movements are written as CSV, one per line after a header, with every
+//! field free of commas so that the output can be imported as is. Keys are in
+//! base58, and hashes in hex. Large transfers are written the same way, led by
+//! the account whose ledger they're in.
+
+use std::fmt::Write;
+
+use multisig_contract_types::*;
+
+use crate::Result;
+
+/// The header of the rendered ledger, naming its columns.
+const HEADER: &str =
+    "block_height,sequence,direction,counterparty,amount,memo_hash,balance_after";
+
+/// Renders movements as CSV, in the order given.
+pub fn render_movements(movements: &[Movement]) -> Result<String> {
+    let mut out = String::new();
+    writeln!(out, "{HEADER}")?;
+
+    for movement in movements {
+        writeln!(out, "{}", render_movement(movement))?;
+    }
+
+    Ok(out)
+}
+
+/// Renders large transfers as CSV, in the order given.
+pub fn render_large_transfers(transfers: &[LargeTransfer]) -> Result<String> {
+    let mut out = String::new();
+    writeln!(out, "account_id,{HEADER}")?;
+
+    for transfer in transfers {
+        let movement = render_movement(&transfer.movement);
+        writeln!(out, "{},{movement}", transfer.account_id)?;
+    }
+
+    Ok(out)
+}
+
+/// Renders a single movement as a CSV line, without its line ending.
+fn render_movement(movement: &Movement) -> String {
+    let direction = match movement.direction {
+        Direction::In => "in",
+        Direction::Out => "out",
+    };
+    let counterparty = match &movement.counterparty {
+        Counterparty::Depositor => String::from("depositor"),
+        Counterparty::Moonlight(Disclosure::Revealed(key)) => {
+            encoding::encode_public_key(key)
+        }
+        Counterparty::Moonlight(Disclosure::Hashed(hash)) => {
+            format!("hashed:0x{}", hex::encode(hash))
+        }
+        Counterparty::Account(account_id) => {
+            format!("account:{account_id}")
+        }
+        Counterparty::StorageFee => String::from("storage-fee"),
+    };
+    let memo_hash = movement
+        .memo_hash
+        .map(|hash| format!("0x{}", hex::encode(hash)))
+        .unwrap_or_default();
+
+    format!(
+        "{},{},{direction},{counterparty},{},{memo_hash},{}",
+        movement.block_height,
+        movement.sequence,
+        movement.amount,
+        movement.balance_after,
+    )
+}
@@ -0,0 +1,3062 @@
+//! Command-line client for the `multisig-contract`.
+//!
+//! The CLI covers the whole workflow of the signers of an account:
+//!
+//! 1. One of them builds an unsigned operation, and shares it with the others
+//! 2. Each signer produces a partial signature of it with their own key, and
+//!    each co-signer a signature with their Ed25519 key
+//! 3. The signatures are merged into a signed operation
+//! 4. The signed operation is submitted to a node in a Moonlight transaction
+
+mod events;
+mod files;
+mod ledger;
+
+use std::path::PathBuf;
+
+use clap::{ArgGroup, Args, Parser, Subcommand};
+
+use execution_core::ContractId;
+
+use multisig_contract_types::*;
+use multisig_wallet::{Node, Wallet};
+
+use crate::events::{parse_payload, render_event};
+use crate::files::{
+    parse_category, parse_child_budget, parse_contract_id, parse_cosigner_key,
+    parse_gas_refund, parse_key_allowance, parse_key_expiry, parse_key_weight,
+    parse_liveness_policy, parse_multisig_signature, parse_operation_kind,
+    parse_operation_threshold, parse_payroll_row, parse_public_key,
+    parse_reference, parse_role_assignment, parse_signature,
+    parse_supermajority, parse_transfer_tier, parse_transfer_window,
+    parse_velocity_limit, read, read_ed25519_key, read_secret_key, write,
+    write_secret_key,
+};
+use crate::ledger::{render_large_transfers, render_movements};
+
+type Result<T, E = Box<dyn std::error::Error + Send + Sync>> =
+    std::result::Result<T, E>;
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// URL of the rusk node to communicate with.
+    #[arg(
+        long,
+        global = true,
+        env = "RUSK_NODE",
+        default_value = "http://127.0.0.1:8080"
+    )]
+    node: String,
+    /// Hex encoded ID of the multisig contract.
+    #[arg(long, global = true, env = "MULTISIG_CONTRACT")]
+    contract: Option<String>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create a new multisig account.
+    CreateAccount {
+        /// Base58 encoded public keys owning the account.
+        #[arg(long = "key", required = true)]
+        keys: Vec<String>,
+        /// Number of keys that need to sign to effect an operation.
+        #[arg(long)]
+        threshold: u32,
+        /// Description of the account.
+        #[arg(long, default_value = "")]
+        description: String,
+        /// Alias to claim for the account, unique across accounts.
+        #[arg(long)]
+        alias: Option<String>,
+        /// Number of signers a key counts for towards the threshold, as
+        /// `<key>:<weight>` with the key base58 encoded. Keys given no weight
+        /// count for one.
+        #[arg(long)]
+        key_weight: Vec<String>,
+        /// Role of a key, as `<key>:<role>` with the key base58 encoded and
+        /// the role either `admin` or `spender`. Keys given no role are
+        /// admins.
+        #[arg(long)]
+        key_role: Vec<String>,
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// Register a template, under which identical multisig accounts can then
+    /// be created with `create-account-from-template`.
+    RegisterTemplate {
+        /// Number of keys and co-signers that need to sign to effect an
+        /// operation on the accounts created.
+        #[arg(long)]
+        threshold: u32,
+        /// Description of the accounts created.
+        #[arg(long, default_value = "")]
+        description: String,
+        /// Whether the events of the accounts created carry hashes of memos
+        /// and receivers, instead of the values themselves.
+        #[arg(long)]
+        private_events: bool,
+        /// Soft limit on the amount the accounts created transfer within a
+        /// window of blocks, as `<amount>/<blocks>`.
+        #[arg(long)]
+        velocity_limit: Option<String>,
+        /// Co-signer of the accounts created, as `ed25519:<hex key>`.
+        #[arg(long = "cosigner")]
+        cosigners: Vec<String>,
+        /// Base58 encoded guardian of the accounts created.
+        #[arg(long = "guardian")]
+        guardians: Vec<String>,
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// Create a new multisig account under a registered template.
+    CreateAccountFromTemplate {
+        /// The template to create the account under.
+        #[arg(long)]
+        template_id: u64,
+        /// Base58 encoded public keys owning the account.
+        #[arg(long = "key", required = true)]
+        keys: Vec<String>,
+        /// Alias to claim for the account, unique across accounts.
+        #[arg(long)]
+        alias: Option<String>,
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// Deposit to a multisig account.
+    #[command(group(ArgGroup::new("account").required(true)))]
+    Deposit {
+        /// The account to deposit to.
+        #[arg(long, group = "account")]
+        account_id: Option<u64>,
+        /// Alias of the account to deposit to, instead of its ID.
+        #[arg(long, group = "account")]
+        alias: Option<String>,
+        /// The amount to deposit.
+        #[arg(long)]
+        amount: u64,
+        /// Memo to include with the deposit.
+        #[arg(long, default_value = "")]
+        memo: String,
+        /// Reference of the depositor, such as an invoice or customer ID, to
+        /// reconcile the deposit by.
+        #[arg(long, default_value = "")]
+        reference: String,
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// Build an unsigned transfer from a multisig account.
+    #[command(group(ArgGroup::new("value").required(true)))]
+    Transfer {
+        /// The account to transfer from.
+        #[arg(long)]
+        account_id: u64,
+        /// Base58 encoded Moonlight account to transfer to.
+        #[arg(long)]
+        receiver: String,
+        /// The amount to transfer.
+        #[arg(long, group = "value")]
+        amount: Option<u64>,
+        /// Transfer the whole balance of the account, as of the transfer's
+        /// execution.
+        #[arg(long, group = "value")]
+        all: bool,
+        /// Memo to include with the transfer.
+        #[arg(long, default_value = "")]
+        memo: String,
+        /// Hex encoded 32 byte reference of the transfer, such as an invoice
+        /// number, to reconcile it by.
+        #[arg(long)]
+        reference: Option<String>,
+        /// Spending category of the account to count the transfer against.
+        #[arg(long)]
+        category: Option<String>,
+        /// Reimburse the submitter of the operation for its gas, as
+        /// `<SUBMITTER>:<AMOUNT>`, with the submitter a base58 encoded
+        /// Moonlight account.
+        #[arg(long)]
+        gas_refund: Option<String>,
+        /// ID of the chain the operation is meant for, refused on any other.
+        #[arg(long, env = "RUSK_CHAIN_ID")]
+        chain_id: Option<u8>,
+        /// Nonce to use, instead of the next nonce of the account.
+        #[arg(long)]
+        nonce: Option<u64>,
+        /// File to write the unsigned operation to.
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Build an unsigned payroll from a multisig account, paying every row
+    /// on a single signature.
+    Payroll {
+        /// The account to pay from.
+        #[arg(long)]
+        account_id: u64,
+        /// A row to pay, as `<RECEIVER>:<AMOUNT>[:<MEMO>]`, with the receiver
+        /// a base58 encoded Moonlight account. Rows are paid in the order
+        /// given.
+        #[arg(long = "row", required = true)]
+        rows: Vec<String>,
+        /// Reimburse the submitter of the operation for its gas, as
+        /// `<SUBMITTER>:<AMOUNT>`, with the submitter a base58 encoded
+        /// Moonlight account.
+        #[arg(long)]
+        gas_refund: Option<String>,
+        /// ID of the chain the operation is meant for, refused on any other.
+        #[arg(long, env = "RUSK_CHAIN_ID")]
+        chain_id: Option<u8>,
+        /// Nonce to use, instead of the next nonce of the account.
+        #[arg(long)]
+        nonce: Option<u64>,
+        /// File to write the unsigned operation to.
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Build an unsigned change to a multisig account.
+    ///
+    /// Changes are applied by adding keys, setting the threshold, removing
+    /// keys, setting the description, setting whether events are private,
+    /// adding duress keys, removing duress keys, adding co-signers, removing
+    /// co-signers, setting the group key, whitelisting receivers, removing
+    /// whitelisted receivers, setting the velocity limit, unlocking the
+    /// account, adding guardians, removing guardians, setting the alias,
+    /// setting the balance cap, adding tags, removing tags and setting the
+    /// liveness policy, in that order.
+    ChangeAccount {
+        /// The account to change.
+        #[arg(long)]
+        account_id: u64,
+        #[command(flatten)]
+        changes: Box<ChangeArgs>,
+        /// Reimburse the submitter of the operation for its gas, as
+        /// `<SUBMITTER>:<AMOUNT>`, with the submitter a base58 encoded
+        /// Moonlight account.
+        #[arg(long)]
+        gas_refund: Option<String>,
+        /// ID of the chain the operation is meant for, refused on any other.
+        #[arg(long, env = "RUSK_CHAIN_ID")]
+        chain_id: Option<u8>,
+        /// Nonce to use, instead of the next nonce of the account.
+        #[arg(long)]
+        nonce: Option<u64>,
+        /// File to write the unsigned operation to.
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Build an unsigned transfer from a multisig account together with a
+    /// change to it, applied atomically under a single nonce.
+    ///
+    /// The transfer is made first, then the changes are applied in the same
+    /// order as with `change-account`.
+    #[command(group(ArgGroup::new("value").required(true)))]
+    TransferAndChange {
+        /// The account to transfer from and change.
+        #[arg(long)]
+        account_id: u64,
+        /// Base58 encoded Moonlight account to transfer to.
+        #[arg(long)]
+        receiver: String,
+        /// The amount to transfer.
+        #[arg(long, group = "value")]
+        amount: Option<u64>,
+        /// Transfer the whole balance of the account, as of the transfer's
+        /// execution.
+        #[arg(long, group = "value")]
+        all: bool,
+        /// Memo to include with the transfer.
+        #[arg(long, default_value = "")]
+        memo: String,
+        #[command(flatten)]
+        changes: Box<ChangeArgs>,
+        /// Reimburse the submitter of the operation for its gas, as
+        /// `<SUBMITTER>:<AMOUNT>`, with the submitter a base58 encoded
+        /// Moonlight account.
+        #[arg(long)]
+        gas_refund: Option<String>,
+        /// ID of the chain the operation is meant for, refused on any other.
+        #[arg(long, env = "RUSK_CHAIN_ID")]
+        chain_id: Option<u8>,
+        /// Nonce to use, instead of the next nonce of the account.
+        #[arg(long)]
+        nonce: Option<u64>,
+        /// File to write the unsigned operation to.
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Build an unsigned split of a new multisig account off an existing one,
+    /// with some of its keys and part of its balance.
+    SplitAccount {
+        /// The account to split.
+        #[arg(long)]
+        account_id: u64,
+        /// Base58 encoded public keys of the account to give the new one.
+        #[arg(long = "key", required = true)]
+        keys: Vec<String>,
+        /// Number of keys that need to sign to effect an operation on the new
+        /// account.
+        #[arg(long)]
+        threshold: u32,
+        /// Description of the new account.
+        #[arg(long, default_value = "")]
+        description: String,
+        /// The amount to move to the new account.
+        #[arg(long)]
+        amount: u64,
+        /// Reimburse the submitter of the operation for its gas, as
+        /// `<SUBMITTER>:<AMOUNT>`, with the submitter a base58 encoded
+        /// Moonlight account.
+        #[arg(long)]
+        gas_refund: Option<String>,
+        /// ID of the chain the operation is meant for, refused on any other.
+        #[arg(long, env = "RUSK_CHAIN_ID")]
+        chain_id: Option<u8>,
+        /// Nonce to use, instead of the next nonce of the account.
+        #[arg(long)]
+        nonce: Option<u64>,
+        /// File to write the unsigned operation to.
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Produce a partial signature of an operation with a local key.
+    Sign {
+        /// File containing the operation to sign.
+        #[arg(long)]
+        operation: PathBuf,
+        /// File containing the base58 encoded secret key to sign with.
+        #[arg(long)]
+        key: PathBuf,
+        /// File to write the partial signature to.
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Sign an operation as a co-signer, with a local Ed25519 key.
+    Cosign {
+        /// File containing the operation to sign.
+        #[arg(long)]
+        operation: PathBuf,
+        /// File containing the hex encoded Ed25519 secret key to sign with.
+        #[arg(long)]
+        key: PathBuf,
+        /// File to write the co-signature to.
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Merge partial signatures and co-signatures into a signed operation.
+    Merge {
+        /// File containing the operation the signatures are for.
+        #[arg(long)]
+        operation: PathBuf,
+        /// File containing a partial signature.
+        #[arg(long = "partial")]
+        partials: Vec<PathBuf>,
+        /// File containing a co-signature.
+        #[arg(long = "cosignature")]
+        cosignatures: Vec<PathBuf>,
+        /// Base58 encoded signature by the group key of the account, as
+        /// combined by the signers' threshold scheme.
+        #[arg(long, conflicts_with_all = ["partials", "cosignatures"])]
+        group_signature: Option<String>,
+        /// File to write the signed operation to.
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Verify a partial signature of an operation, without merging it.
+    ///
+    /// Exits with an error if the signature is not valid.
+    Verify {
+        /// File containing the operation the signature is for.
+        #[arg(long)]
+        operation: PathBuf,
+        /// File containing the partial signature.
+        #[arg(long)]
+        partial: PathBuf,
+        /// Base58 encoded public key the signature is claimed to be from.
+        #[arg(long)]
+        key: Option<String>,
+    },
+    /// Submit a signed operation to the network.
+    Submit {
+        /// File containing the signed operation.
+        #[arg(long)]
+        operation: PathBuf,
+        /// Name the signers by a bitmap over the account's keys, shrinking
+        /// the transaction for large accounts.
+        #[arg(long)]
+        certified: bool,
+        /// Queue the transfer, to be executed with `execute-queued` once the
+        /// delay of the account's queue policy has passed.
+        #[arg(long, conflicts_with = "certified")]
+        queue: bool,
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// Build an unsigned merge of a multisig account into another, to be
+    /// signed by the threshold of both with `sign-merge`.
+    MergeAccounts {
+        /// The account that remains.
+        #[arg(long)]
+        account_id: u64,
+        /// The account merged into it, and retired.
+        #[arg(long)]
+        merged_id: u64,
+        /// File to write the unsigned merge to.
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Produce a partial signature of a merge with a local key, of a signer
+    /// of either account.
+    SignMerge {
+        /// File containing the merge to sign.
+        #[arg(long)]
+        merge: PathBuf,
+        /// File containing the base58 encoded secret key to sign with.
+        #[arg(long)]
+        key: PathBuf,
+        /// File to write the partial signature to.
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Submit a merge to the network, signed with the partial signatures of
+    /// both accounts' signers.
+    SubmitMerge {
+        /// File containing the merge the signatures are for.
+        #[arg(long)]
+        merge: PathBuf,
+        /// File containing a partial signature by a key of the remaining
+        /// account.
+        #[arg(long = "partial", required = true)]
+        partials: Vec<PathBuf>,
+        /// File containing a partial signature by a key of the merged
+        /// account.
+        #[arg(long = "merged-partial", required = true)]
+        merged_partials: Vec<PathBuf>,
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// Build an unsigned draw of a multisig account on the budget its parent
+    /// allocated to it, to be signed with `sign-draw`.
+    DrawBudget {
+        /// The child account drawing.
+        #[arg(long)]
+        account_id: u64,
+        /// The parent account drawn on.
+        #[arg(long)]
+        parent_id: u64,
+        /// The amount to draw.
+        #[arg(long)]
+        amount: u64,
+        /// File to write the unsigned draw to.
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Produce a partial signature of a draw with a local key, of a signer
+    /// of the child account.
+    SignDraw {
+        /// File containing the draw to sign.
+        #[arg(long)]
+        draw: PathBuf,
+        /// File containing the base58 encoded secret key to sign with.
+        #[arg(long)]
+        key: PathBuf,
+        /// File to write the partial signature to.
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Submit a draw to the network, signed with the partial signatures of
+    /// the child account's signers.
+    SubmitDraw {
+        /// File containing the draw the signatures are for.
+        #[arg(long)]
+        draw: PathBuf,
+        /// File containing a partial signature by a key of the child account.
+        #[arg(long = "partial", required = true)]
+        partials: Vec<PathBuf>,
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// Build an unsigned checkpoint of a multisig account, to be signed with
+    /// `sign-checkpoint`.
+    RecordCheckpoint {
+        /// The account to record a checkpoint of.
+        #[arg(long)]
+        account_id: u64,
+        /// File to write the unsigned checkpoint to.
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Produce a partial signature of a checkpoint with a local key.
+    SignCheckpoint {
+        /// File containing the checkpoint to sign.
+        #[arg(long)]
+        checkpoint: PathBuf,
+        /// File containing the base58 encoded secret key to sign with.
+        #[arg(long)]
+        key: PathBuf,
+        /// File to write the partial signature to.
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Submit a checkpoint to the network, signed with the partial
+    /// signatures of the account's signers.
+    SubmitCheckpoint {
+        /// File containing the checkpoint the signatures are for.
+        #[arg(long)]
+        checkpoint: PathBuf,
+        /// File containing a partial signature by a key of the account.
+        #[arg(long = "partial", required = true)]
+        partials: Vec<PathBuf>,
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// Build an unsigned configuration of the contract-wide parameters, to
+    /// be signed by the governance account with `sign-configure`.
+    ///
+    /// Parameters left out keep their current value.
+    Configure {
+        #[command(flatten)]
+        config: ConfigArgs,
+        /// File to write the unsigned configuration to.
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Produce a partial signature of a configuration with a local key.
+    SignConfigure {
+        /// File containing the configuration to sign.
+        #[arg(long)]
+        configure: PathBuf,
+        /// File containing the base58 encoded secret key to sign with.
+        #[arg(long)]
+        key: PathBuf,
+        /// File to write the partial signature to.
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Submit a configuration to the network, signed with the partial
+    /// signatures of the governance account's signers.
+    SubmitConfigure {
+        /// File containing the configuration the signatures are for.
+        #[arg(long)]
+        configure: PathBuf,
+        /// File containing a partial signature by a key of the governance
+        /// account.
+        #[arg(long = "partial", required = true)]
+        partials: Vec<PathBuf>,
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// Lock the transfers of a multisig account with a single one of its
+    /// keys, until a change signed by the threshold unlocks them.
+    LockAccount {
+        /// The account to lock.
+        #[arg(long)]
+        account_id: u64,
+        /// File containing the base58 encoded secret key of the account
+        /// to lock it with.
+        #[arg(long)]
+        key: PathBuf,
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// Place a multisig account on hold with its compliance key, restricting
+    /// its transfers to its remediation address.
+    PlaceHold {
+        /// The account to place on hold.
+        #[arg(long)]
+        account_id: u64,
+        /// File containing the base58 encoded compliance secret key of the
+        /// account.
+        #[arg(long)]
+        key: PathBuf,
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// Sign the lifting of the hold on a multisig account with its
+    /// compliance key, printing the base58 encoded signature to pass to
+    /// `change-account --lift-hold`.
+    SignLiftHold {
+        /// The account to lift the hold on.
+        #[arg(long)]
+        account_id: u64,
+        /// File containing the base58 encoded compliance secret key of the
+        /// account.
+        #[arg(long)]
+        key: PathBuf,
+        /// Nonce of the change lifting the hold, instead of the next nonce
+        /// of the account.
+        #[arg(long)]
+        nonce: Option<u64>,
+    },
+    /// Attest that a key of a multisig account with a liveness policy is
+    /// alive, keeping it counted towards the threshold.
+    AttestLiveness {
+        /// The account the key belongs to.
+        #[arg(long)]
+        account_id: u64,
+        /// File containing the base58 encoded secret key attesting.
+        #[arg(long)]
+        key: PathBuf,
+        /// Height the key is alive at, no later than the current one.
+        #[arg(long)]
+        block_height: u64,
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// Hand the signing power of a key of a multisig account to another key
+    /// for a range of blocks, or revoke its delegation.
+    #[command(group(ArgGroup::new("delegation").required(true)))]
+    DelegateKey {
+        /// The account the key belongs to.
+        #[arg(long)]
+        account_id: u64,
+        /// File containing the base58 encoded secret key delegating.
+        #[arg(long)]
+        key: PathBuf,
+        /// Base58 encoded key to sign in place of the delegating one.
+        #[arg(
+            long,
+            group = "delegation",
+            requires_all = ["from_height", "until_height"]
+        )]
+        delegate: Option<String>,
+        /// Height of the first block the delegate can sign in.
+        #[arg(long, requires = "delegate")]
+        from_height: Option<u64>,
+        /// Height of the last block the delegate can sign in.
+        #[arg(long, requires = "delegate")]
+        until_height: Option<u64>,
+        /// Revoke the delegation of the key.
+        #[arg(long, group = "delegation")]
+        revoke: bool,
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// Act on a multisig account as one of its guardians, either locking it
+    /// or holding back one of its pending whitelisted receivers.
+    #[command(group(ArgGroup::new("action").required(true)))]
+    Guard {
+        /// The account to act on.
+        #[arg(long)]
+        account_id: u64,
+        /// File containing the base58 encoded secret key of the guardian.
+        #[arg(long)]
+        key: PathBuf,
+        /// Lock the transfers of the account.
+        #[arg(long, group = "action")]
+        lock: bool,
+        /// Base58 encoded pending receiver to remove from the whitelist.
+        #[arg(long, group = "action")]
+        veto: Option<String>,
+        /// Base58 encoded pending receiver whose whitelist delay to extend,
+        /// until the height given with `--until`.
+        #[arg(long, group = "action", requires = "until")]
+        extend: Option<String>,
+        /// Height from which the receiver can be transferred to.
+        #[arg(long, requires = "extend")]
+        until: Option<u64>,
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// Build an unsigned recovery of a multisig account, replacing its keys
+    /// and threshold, to be signed by its guardians with `sign-recovery`.
+    StartRecovery {
+        /// The account to recover.
+        #[arg(long)]
+        account_id: u64,
+        /// Base58 encoded public keys to own the account once recovered.
+        #[arg(long = "key", required = true)]
+        keys: Vec<String>,
+        /// Number of keys that need to sign to effect an operation, once
+        /// recovered.
+        #[arg(long)]
+        threshold: u32,
+        /// File to write the unsigned recovery to.
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Produce a partial signature of a recovery with the local key of a
+    /// guardian.
+    SignRecovery {
+        /// File containing the recovery to sign.
+        #[arg(long)]
+        recovery: PathBuf,
+        /// File containing the base58 encoded secret key of the guardian.
+        #[arg(long)]
+        key: PathBuf,
+        /// File to write the partial signature to.
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Submit a recovery to the network, signed with the partial signatures
+    /// of the account's guardians.
+    SubmitRecovery {
+        /// File containing the recovery the signatures are for.
+        #[arg(long)]
+        recovery: PathBuf,
+        /// File containing a partial signature by a guardian of the account.
+        #[arg(long = "partial", required = true)]
+        partials: Vec<PathBuf>,
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// Cancel the pending recovery of a multisig account with a single one
+    /// of its keys.
+    CancelRecovery {
+        /// The account whose recovery to cancel.
+        #[arg(long)]
+        account_id: u64,
+        /// File containing the base58 encoded secret key of the account to
+        /// cancel it with.
+        #[arg(long)]
+        key: PathBuf,
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// Finalize the pending recovery of a multisig account once its delay
+    /// has passed, replacing the keys of the account.
+    FinalizeRecovery {
+        /// The account whose recovery to finalize.
+        #[arg(long)]
+        account_id: u64,
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// Claim a multisig account that performed no operation for the period
+    /// of its inactivity policy, with the policy's fallback key, either
+    /// sweeping its balance or replacing its keys.
+    #[command(group(ArgGroup::new("claim").required(true)))]
+    ClaimInactive {
+        /// The account to claim.
+        #[arg(long)]
+        account_id: u64,
+        /// File containing the base58 encoded secret key of the fallback key.
+        #[arg(long)]
+        key: PathBuf,
+        /// Base58 encoded receiver to sweep the balance of the account to.
+        #[arg(long, group = "claim")]
+        sweep_to: Option<String>,
+        /// Base58 encoded public keys to replace the keys of the account
+        /// with.
+        #[arg(long = "new-key", group = "claim", requires = "threshold")]
+        new_keys: Vec<String>,
+        /// Number of the new keys that need to sign to effect an operation.
+        #[arg(long, requires = "new_keys")]
+        threshold: Option<u32>,
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// Cancel a transfer queued by a multisig account with a single one of
+    /// its keys.
+    CancelQueued {
+        /// The account that queued the transfer.
+        #[arg(long)]
+        account_id: u64,
+        /// The nonce the transfer was queued with.
+        #[arg(long)]
+        nonce: u64,
+        /// File containing the base58 encoded secret key of the account to
+        /// cancel it with.
+        #[arg(long)]
+        key: PathBuf,
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// Execute a transfer queued by a multisig account once its delay has
+    /// passed.
+    ExecuteQueued {
+        /// The account that queued the transfer.
+        #[arg(long)]
+        account_id: u64,
+        /// The nonce the transfer was queued with.
+        #[arg(long)]
+        nonce: u64,
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// Transfer from a multisig account on the signature of a single one of
+    /// its keys, out of the allowance the account gave the key.
+    TransferWithAllowance {
+        /// The account to transfer from.
+        #[arg(long)]
+        account_id: u64,
+        /// File containing the base58 encoded secret key of the account to
+        /// transfer with.
+        #[arg(long)]
+        key: PathBuf,
+        /// Base58 encoded Moonlight account to transfer to.
+        #[arg(long)]
+        receiver: String,
+        /// The amount to transfer.
+        #[arg(long)]
+        amount: u64,
+        /// Memo to include with the transfer.
+        #[arg(long, default_value = "")]
+        memo: String,
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// Claim a withdrawal recorded by a transfer of an account with claimable
+    /// withdrawals, paying it to the receiver.
+    ClaimWithdrawal {
+        /// The claim ID of the withdrawal, as carried by the transfer event.
+        #[arg(long)]
+        claim_id: u64,
+        /// File containing the base58 encoded secret key of the receiver.
+        #[arg(long)]
+        key: PathBuf,
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// Mark a key as compromised, so that the contract refuses it as a signer
+    /// in any account.
+    MarkCompromised {
+        /// File containing the base58 encoded secret key to mark.
+        #[arg(long)]
+        key: PathBuf,
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// Mark a co-signer key as compromised, so that the contract refuses it
+    /// as a co-signer in any account.
+    MarkCosignerCompromised {
+        /// File containing the hex encoded Ed25519 secret key to mark.
+        #[arg(long)]
+        key: PathBuf,
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// Replace a local key with a new one in every multisig account using it,
+    /// with a signed change of each account built by
+    /// `change-account --add-key <NEW> --remove-key <OLD>`.
+    ReplaceKey {
+        /// File containing the base58 encoded secret key to replace.
+        #[arg(long)]
+        key: PathBuf,
+        /// Base58 encoded key to replace it with.
+        #[arg(long)]
+        new_key: String,
+        /// File containing the signed change of an account using the key.
+        #[arg(long = "operation", required = true)]
+        operations: Vec<PathBuf>,
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// Derive a secret key from a BIP39 mnemonic.
+    ///
+    /// The same mnemonic and index always derive the same key, allowing a set
+    /// of signers to be reproduced.
+    DeriveKey {
+        /// The mnemonic to derive the key from.
+        #[arg(long, env = "MULTISIG_MNEMONIC", hide_env_values = true)]
+        mnemonic: String,
+        /// Passphrase protecting the mnemonic.
+        #[arg(long, default_value = "")]
+        passphrase: String,
+        /// Index of the key to derive.
+        #[arg(long, default_value_t = 0)]
+        index: u8,
+        /// File to write the base58 encoded secret key to.
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Show the data of a multisig account.
+    Account {
+        /// The account to show.
+        #[arg(long)]
+        account_id: u64,
+    },
+    /// Show the keys of a multisig account.
+    AccountKeys {
+        /// The account whose keys to show.
+        #[arg(long)]
+        account_id: u64,
+    },
+    /// Show the observers of a multisig account.
+    Observers {
+        /// The account whose observers to show.
+        #[arg(long)]
+        account_id: u64,
+    },
+    /// Show the height each key of a multisig account with a liveness policy
+    /// last attested it's alive at.
+    KeyLiveness {
+        /// The account whose keys to show.
+        #[arg(long)]
+        account_id: u64,
+    },
+    /// Show the delegations registered by the keys of a multisig account.
+    Delegations {
+        /// The account whose delegations to show.
+        #[arg(long)]
+        account_id: u64,
+    },
+    /// Show the keys of a multisig account that were added under a delay
+    /// and can't sign yet.
+    PendingKeys {
+        /// The account whose pending keys to show.
+        #[arg(long)]
+        account_id: u64,
+    },
+    /// Show the keys of a multisig account that lapse, with the height of
+    /// the last block they can sign in. Lapsed keys are shown until removed.
+    KeyExpiries {
+        /// The account whose key expiries to show.
+        #[arg(long)]
+        account_id: u64,
+    },
+    /// Show the keys of a multisig account counting for other than one
+    /// signer, with the number of signers each counts for.
+    KeyWeights {
+        /// The account whose key weights to show.
+        #[arg(long)]
+        account_id: u64,
+    },
+    /// Show the keys of a multisig account with a role other than admin,
+    /// with the role of each.
+    KeyRoles {
+        /// The account whose key roles to show.
+        #[arg(long)]
+        account_id: u64,
+    },
+    /// Show the recovery pending on a multisig account, if any, with the
+    /// height it can be finalized from.
+    PendingRecovery {
+        /// The account whose pending recovery to show.
+        #[arg(long)]
+        account_id: u64,
+    },
+    /// Show the keys of a multisig account with an allowance left, with what
+    /// is left of each.
+    Allowances {
+        /// The account whose allowances to show.
+        #[arg(long)]
+        account_id: u64,
+    },
+    /// Show the transfers queued by a multisig account, each with the height
+    /// it can be executed from.
+    QueuedTransfers {
+        /// The account whose queued transfers to show.
+        #[arg(long)]
+        account_id: u64,
+    },
+    /// Show the tiers of a multisig account's transfers, each as the largest
+    /// amount a transfer in it can move and the keys it needs.
+    TransferTiers {
+        /// The account whose transfer tiers to show.
+        #[arg(long)]
+        account_id: u64,
+    },
+    /// Show how many operations each key of a multisig account signed, and
+    /// the height it last signed at, or `-` if it never did.
+    KeyUsage {
+        /// The account whose key usage to show.
+        #[arg(long)]
+        account_id: u64,
+        /// Only show the keys that signed nothing from this height on.
+        #[arg(long)]
+        inactive_since: Option<u64>,
+    },
+    /// Show the amount transferred under each spending category of a
+    /// multisig account within the current window of its budget.
+    CategorySpend {
+        /// The account whose spending to show.
+        #[arg(long)]
+        account_id: u64,
+    },
+    /// Show how much the children of a multisig account drew on their budgets
+    /// within the current window, and how much they hold of them.
+    BudgetDraws {
+        /// The parent account whose children to show.
+        #[arg(long)]
+        account_id: u64,
+    },
+    /// Show the checkpoints recorded of a multisig account, in the order
+    /// they were.
+    Checkpoints {
+        /// The account whose checkpoints to show.
+        #[arg(long)]
+        account_id: u64,
+    },
+    /// Show the withdrawals awaiting claim by a receiver, with their claim
+    /// IDs.
+    Withdrawals {
+        /// Base58 encoded key of the receiver.
+        #[arg(long)]
+        receiver: String,
+    },
+    /// Show the IDs of the multisig accounts carrying a tag.
+    TaggedAccounts {
+        /// The tag to look the accounts up by.
+        #[arg(long)]
+        tag: String,
+    },
+    /// Export the movements of a multisig account within a range of blocks,
+    /// as CSV for bookkeeping.
+    Movements {
+        /// The account whose movements to export.
+        #[arg(long)]
+        account_id: u64,
+        /// Height of the first block to export the movements of.
+        #[arg(long, default_value_t = 0)]
+        from_height: u64,
+        /// Height of the last block to export the movements of.
+        #[arg(long, default_value_t = u64::MAX)]
+        to_height: u64,
+    },
+    /// Export the movements of funds into or out of the contract of at least
+    /// an amount within a range of blocks, across all accounts, as CSV for
+    /// compliance reporting.
+    LargeTransfers {
+        /// The smallest amount of the movements to export.
+        #[arg(long)]
+        min_amount: u64,
+        /// Height of the first block to export the movements of.
+        #[arg(long, default_value_t = 0)]
+        from_height: u64,
+        /// Height of the last block to export the movements of.
+        #[arg(long, default_value_t = u64::MAX)]
+        to_height: u64,
+    },
+    /// Show how a multisig account changed between two of its nonces.
+    StateDiff {
+        /// The account to show the changes of.
+        #[arg(long)]
+        account_id: u64,
+        /// Nonce of the first operation whose changes to show.
+        #[arg(long)]
+        from_nonce: u64,
+        /// Nonce of the last operation whose changes to show.
+        #[arg(long)]
+        to_nonce: u64,
+    },
+    /// Preview the outcome of an unsigned change of a multisig account.
+    SimulateChange {
+        /// File containing the change to preview.
+        #[arg(long)]
+        operation: PathBuf,
+        /// Base58 encoded public key expected to sign the change. All the
+        /// keys of the account are assumed to sign it if none is given.
+        #[arg(long = "signer")]
+        signers: Vec<String>,
+    },
+    /// Compare the total balance of all accounts against the balance the
+    /// contract holds in the transfer contract.
+    Custody,
+    /// Show how many times each entrypoint of the contract was called, and
+    /// the signatures verified by them.
+    Stats,
+    /// Show the contract-wide parameters and the account governing them.
+    Config,
+    /// Show a registered account template.
+    Template {
+        /// The template to show.
+        #[arg(long)]
+        template_id: u64,
+    },
+    /// Decode an event emitted by the contract, as served by the node.
+    DecodeEvent {
+        /// The topic the event was emitted with.
+        #[arg(long)]
+        topic: String,
+        /// Hex or base64 encoded payload of the event.
+        payload: String,
+    },
+}
+
+/// The changes to make to an account, boxed in its command since they
+/// outweigh every other command's arguments.
+#[derive(Args)]
+struct ChangeArgs {
+    /// Base58 encoded public key to add to the account.
+    #[arg(long)]
+    add_key: Vec<String>,
+    /// New threshold of the account.
+    #[arg(long)]
+    threshold: Option<u32>,
+    /// Base58 encoded public key to remove from the account.
+    #[arg(long)]
+    remove_key: Vec<String>,
+    /// New description of the account.
+    #[arg(long)]
+    description: Option<String>,
+    /// Whether the account's events should carry hashes of memos and
+    /// receivers, instead of the values themselves.
+    #[arg(long)]
+    private_events: Option<bool>,
+    /// Base58 encoded duress key to register, whose signature freezes the
+    /// account instead of effecting the operation.
+    #[arg(long)]
+    add_duress_key: Vec<String>,
+    /// Base58 encoded duress key to remove from the account.
+    #[arg(long)]
+    remove_duress_key: Vec<String>,
+    /// Co-signer to add to the account, as `ed25519:<hex key>`.
+    #[arg(long)]
+    add_cosigner: Vec<String>,
+    /// Co-signer to remove from the account, as `ed25519:<hex key>`.
+    #[arg(long)]
+    remove_cosigner: Vec<String>,
+    /// Base58 encoded group key of a threshold scheme run by the signers,
+    /// which is then the only one signing for the account.
+    #[arg(long, conflicts_with = "remove_group_key")]
+    group_key: Option<String>,
+    /// Remove the group key of the account, returning to signing with its
+    /// keys.
+    #[arg(long)]
+    remove_group_key: bool,
+    /// Base58 encoded receiver to whitelist. Once an account whitelists a
+    /// receiver it may only transfer to whitelisted ones, and only after
+    /// they've been whitelisted for a while.
+    #[arg(long)]
+    add_whitelisted: Vec<String>,
+    /// Base58 encoded receiver to remove from the whitelist.
+    #[arg(long)]
+    remove_whitelisted: Vec<String>,
+    /// Soft limit on the amount transferred within a window of blocks,
+    /// past which a warning event is emitted, as `<amount>/<blocks>`.
+    #[arg(long, conflicts_with = "remove_velocity_limit")]
+    velocity_limit: Option<String>,
+    /// Remove the velocity limit of the account.
+    #[arg(long)]
+    remove_velocity_limit: bool,
+    /// Unlock the transfers of an account locked by one of its keys.
+    #[arg(long)]
+    unlock: bool,
+    /// Base58 encoded guardian to add, who can't sign for the account but
+    /// can lock it and hold back its pending whitelisted receivers.
+    #[arg(long)]
+    add_guardian: Vec<String>,
+    /// Base58 encoded guardian to remove.
+    #[arg(long)]
+    remove_guardian: Vec<String>,
+    /// Alias to claim for the account, releasing the one it had.
+    #[arg(long, conflicts_with = "remove_alias")]
+    alias: Option<String>,
+    /// Release the alias of the account.
+    #[arg(long)]
+    remove_alias: bool,
+    /// Most the account may hold, past which deposits are refused.
+    #[arg(long, conflicts_with = "remove_balance_cap")]
+    balance_cap: Option<u64>,
+    /// Remove the balance cap of the account.
+    #[arg(long)]
+    remove_balance_cap: bool,
+    /// Tag to add to the account, grouping it with the others carrying it.
+    #[arg(long)]
+    add_tag: Vec<String>,
+    /// Tag to remove from the account.
+    #[arg(long)]
+    remove_tag: Vec<String>,
+    /// Spending category to add to the account, or whose budget to replace,
+    /// as `<name>:<budget>/<blocks>`.
+    #[arg(long)]
+    set_category: Vec<String>,
+    /// Spending category to remove from the account.
+    #[arg(long)]
+    remove_category: Vec<String>,
+    /// Keys not attesting their liveness within a window of blocks stop
+    /// counting towards the threshold, down to a minimum, as
+    /// `<blocks>/<min threshold>`.
+    #[arg(long, conflicts_with = "remove_liveness_policy")]
+    liveness_policy: Option<String>,
+    /// Remove the liveness policy of the account.
+    #[arg(long)]
+    remove_liveness_policy: bool,
+    /// Share of the keys and co-signers needed to change the threshold or
+    /// the supermajority, as `<numerator>/<denominator>`.
+    #[arg(long, conflicts_with = "remove_supermajority")]
+    supermajority: Option<String>,
+    /// Remove the supermajority of the account.
+    #[arg(long)]
+    remove_supermajority: bool,
+    /// Number of blocks keys added to the account wait before they can sign.
+    #[arg(long, conflicts_with = "remove_key_addition_policy")]
+    key_addition_delay: Option<u64>,
+    /// Require the supermajority of the account to add keys to it.
+    #[arg(long, conflicts_with = "remove_key_addition_policy")]
+    key_addition_supermajority: bool,
+    /// Remove the key addition policy of the account.
+    #[arg(long)]
+    remove_key_addition_policy: bool,
+    /// Restrict transfers to a recurring window of blocks, outside of which
+    /// they need the supermajority, as `<period>/<offset>/<length>`.
+    #[arg(long, conflicts_with = "remove_transfer_window")]
+    transfer_window: Option<String>,
+    /// Remove the transfer window of the account.
+    #[arg(long)]
+    remove_transfer_window: bool,
+    /// Number of blocks a recovery of the account by its guardians waits
+    /// before it can be finalized.
+    #[arg(
+        long,
+        requires = "recovery_guardians",
+        conflicts_with = "remove_recovery_policy"
+    )]
+    recovery_delay: Option<u64>,
+    /// Number of guardians needed to start a recovery of the account.
+    #[arg(long, requires = "recovery_delay")]
+    recovery_guardians: Option<u32>,
+    /// Remove the recovery policy of the account.
+    #[arg(long)]
+    remove_recovery_policy: bool,
+    /// Base58 encoded fallback key able to claim the account once it
+    /// performed no operation for the period given with
+    /// `--inactivity-period`.
+    #[arg(
+        long,
+        requires = "inactivity_period",
+        conflicts_with = "remove_inactivity_policy"
+    )]
+    inactivity_fallback: Option<String>,
+    /// Number of blocks without an operation after which the fallback key
+    /// can claim the account.
+    #[arg(long, requires = "inactivity_fallback")]
+    inactivity_period: Option<u64>,
+    /// Remove the inactivity policy of the account.
+    #[arg(long)]
+    remove_inactivity_policy: bool,
+    /// Smallest amount of the transfers that must be queued for the delay
+    /// given with `--queue-delay` before they're executed.
+    #[arg(
+        long,
+        requires = "queue_delay",
+        conflicts_with = "remove_queue_policy"
+    )]
+    queue_min_amount: Option<u64>,
+    /// Number of blocks a queued transfer waits before it can be executed.
+    #[arg(long, requires = "queue_min_amount")]
+    queue_delay: Option<u64>,
+    /// Remove the queue policy of the account.
+    #[arg(long)]
+    remove_queue_policy: bool,
+    /// Base58 encoded observer to add, who can't sign for the account but
+    /// is listed with it.
+    #[arg(long)]
+    add_observer: Vec<String>,
+    /// Base58 encoded observer to remove.
+    #[arg(long)]
+    remove_observer: Vec<String>,
+    /// Budget to allocate to a child account, or to replace, renewed every
+    /// window of blocks, as `<child id>:<amount>/<blocks>`.
+    #[arg(long)]
+    set_child_budget: Vec<String>,
+    /// ID of the child account whose budget to remove.
+    #[arg(long)]
+    remove_child_budget: Vec<u64>,
+    /// ID of the child account to claw back what it holds of its budget from.
+    #[arg(long)]
+    claw_back_budget: Vec<u64>,
+    /// Height of the last block a key of the account can sign in, after
+    /// which it lapses, as `<key>:<height>` with the key base58 encoded.
+    #[arg(long)]
+    key_expiry: Vec<String>,
+    /// Base58 encoded key of the account to never lapse.
+    #[arg(long)]
+    remove_key_expiry: Vec<String>,
+    /// Archive the account, so that it refuses deposits and transfers until
+    /// reactivated.
+    #[arg(long, conflicts_with = "reactivate")]
+    archive: bool,
+    /// Reactivate an archived account.
+    #[arg(long)]
+    reactivate: bool,
+    /// Whether the account's transfers should only record a withdrawal for
+    /// the receiver to claim with `claim-withdrawal`, instead of paying it at
+    /// once.
+    #[arg(long)]
+    claimable_withdrawals: Option<bool>,
+    /// Number of movements the account should retain in its ledger, paying
+    /// the storage fee for any raise past the default.
+    #[arg(long, conflicts_with = "default_history_retention")]
+    history_retention: Option<u32>,
+    /// Have the account retain the default number of movements.
+    #[arg(long)]
+    default_history_retention: bool,
+    /// Base58 encoded compliance key that can place the account on hold,
+    /// needing the supermajority of the account.
+    #[arg(
+        long,
+        requires = "remediation",
+        conflicts_with = "remove_compliance"
+    )]
+    compliance_key: Option<String>,
+    /// Base58 encoded Moonlight account the account can still transfer to
+    /// while on hold.
+    #[arg(long, requires = "compliance_key")]
+    remediation: Option<String>,
+    /// Remove the compliance key of the account.
+    #[arg(long)]
+    remove_compliance: bool,
+    /// Lift the hold on the account, with the base58 encoded signature of
+    /// its compliance key made with `sign-lift-hold`.
+    #[arg(long)]
+    lift_hold: Option<String>,
+    /// Hex encoded ID of a license contract the receivers of the account's
+    /// transfers must be licensed by.
+    #[arg(long, conflicts_with = "remove_license_policy")]
+    license_contract: Option<String>,
+    /// Require the keys of the account to be licensed by the license
+    /// contract too.
+    #[arg(long, requires = "license_contract")]
+    license_keys: bool,
+    /// Remove the license policy of the account.
+    #[arg(long)]
+    remove_license_policy: bool,
+    /// Number of keys needed for operations of a kind, in place of the
+    /// threshold, as `<op>:<threshold>` with the operation named after its
+    /// contract function, such as `transfer:2`. Needs the supermajority of
+    /// the account.
+    #[arg(long)]
+    operation_threshold: Vec<String>,
+    /// Kind of operations to fall back to the threshold of the account for.
+    #[arg(long)]
+    remove_operation_threshold: Vec<String>,
+    /// Tier of the account's transfers, as `<ceiling>:<threshold>`, letting
+    /// transfers of up to the ceiling be signed by the threshold of the tier.
+    /// Replaces all tiers, and needs the supermajority of the account.
+    #[arg(long, conflicts_with = "remove_transfer_tiers")]
+    transfer_tier: Vec<String>,
+    /// Remove all tiers of the account's transfers.
+    #[arg(long)]
+    remove_transfer_tiers: bool,
+    /// Number of signers a key of the account counts for towards the
+    /// thresholds, as `<key>:<weight>` with the key base58 encoded. A weight
+    /// of one is that of keys given none. Needs the supermajority of the
+    /// account.
+    #[arg(long)]
+    key_weight: Vec<String>,
+    /// Role of a key of the account, as `<key>:<role>` with the key base58
+    /// encoded and the role either `admin` or `spender`. Spender keys sign
+    /// only transfers, payrolls and budget draws. Needs the supermajority of
+    /// the account.
+    #[arg(long)]
+    key_role: Vec<String>,
+    /// Amount a key of the account can transfer on its own signature, as
+    /// `<key>:<amount>` with the key base58 encoded, replacing what is left
+    /// of its allowance. An amount of zero takes the allowance away. Needs
+    /// the supermajority of the account.
+    #[arg(long)]
+    allowance: Vec<String>,
+}
+
+impl ChangeArgs {
+    /// The changes to the account, in the order they're applied.
+    fn into_changes(self) -> Result<Vec<AccountChange>> {
+        let ChangeArgs {
+            add_key,
+            threshold,
+            remove_key,
+            description,
+            private_events,
+            add_duress_key,
+            remove_duress_key,
+            add_cosigner,
+            remove_cosigner,
+            group_key,
+            remove_group_key,
+            add_whitelisted,
+            remove_whitelisted,
+            velocity_limit,
+            remove_velocity_limit,
+            unlock,
+            add_guardian,
+            remove_guardian,
+            alias,
+            remove_alias,
+            balance_cap,
+            remove_balance_cap,
+            add_tag,
+            remove_tag,
+            set_category,
+            remove_category,
+            liveness_policy,
+            remove_liveness_policy,
+            supermajority,
+            remove_supermajority,
+            key_addition_delay,
+            key_addition_supermajority,
+            remove_key_addition_policy,
+            transfer_window,
+            remove_transfer_window,
+            recovery_delay,
+            recovery_guardians,
+            remove_recovery_policy,
+            inactivity_fallback,
+            inactivity_period,
+            remove_inactivity_policy,
+            queue_min_amount,
+            queue_delay,
+            remove_queue_policy,
+            add_observer,
+            remove_observer,
+            set_child_budget,
+            remove_child_budget,
+            claw_back_budget,
+            key_expiry,
+            remove_key_expiry,
+            archive,
+            reactivate,
+            claimable_withdrawals,
+            history_retention,
+            default_history_retention,
+            compliance_key,
+            remediation,
+            remove_compliance,
+            lift_hold,
+            license_contract,
+            license_keys,
+            remove_license_policy,
+            operation_threshold,
+            remove_operation_threshold,
+            transfer_tier,
+            remove_transfer_tiers,
+            key_weight,
+            key_role,
+            allowance,
+        } = self;
+
+        let mut changes = Vec::new();
+        for key in add_key {
+            let key = parse_public_key(&key)?;
+            changes.push(AccountChange::AddKey { key });
+        }
+        if let Some(threshold) = threshold {
+            changes.push(AccountChange::SetThreshold { threshold });
+        }
+        for key in remove_key {
+            let key = parse_public_key(&key)?;
+            changes.push(AccountChange::RemoveKey { key });
+        }
+        if let Some(description) = description {
+            changes.push(AccountChange::SetDescription { description });
+        }
+        if let Some(private_events) = private_events {
+            changes.push(AccountChange::SetPrivateEvents { private_events });
+        }
+        for key in add_duress_key {
+            let key = parse_public_key(&key)?;
+            changes.push(AccountChange::AddDuressKey { key });
+        }
+        for key in remove_duress_key {
+            let key = parse_public_key(&key)?;
+            changes.push(AccountChange::RemoveDuressKey { key });
+        }
+        for key in add_cosigner {
+            let key = parse_cosigner_key(&key)?;
+            changes.push(AccountChange::AddCosigner { key });
+        }
+        for key in remove_cosigner {
+            let key = parse_cosigner_key(&key)?;
+            changes.push(AccountChange::RemoveCosigner { key });
+        }
+        if let Some(key) = group_key {
+            let key = Some(parse_public_key(&key)?);
+            changes.push(AccountChange::SetGroupKey { key });
+        }
+        if remove_group_key {
+            changes.push(AccountChange::SetGroupKey { key: None });
+        }
+        for receiver in add_whitelisted {
+            let receiver = parse_public_key(&receiver)?;
+            changes.push(AccountChange::AddWhitelisted { receiver });
+        }
+        for receiver in remove_whitelisted {
+            let receiver = parse_public_key(&receiver)?;
+            changes.push(AccountChange::RemoveWhitelisted { receiver });
+        }
+        if let Some(limit) = velocity_limit {
+            let limit = Some(parse_velocity_limit(&limit)?);
+            changes.push(AccountChange::SetVelocityLimit { limit });
+        }
+        if remove_velocity_limit {
+            changes.push(AccountChange::SetVelocityLimit { limit: None });
+        }
+        if unlock {
+            changes.push(AccountChange::Unlock);
+        }
+        for key in add_guardian {
+            let key = parse_public_key(&key)?;
+            changes.push(AccountChange::AddGuardian { key });
+        }
+        for key in remove_guardian {
+            let key = parse_public_key(&key)?;
+            changes.push(AccountChange::RemoveGuardian { key });
+        }
+        if let Some(alias) = alias {
+            let alias = Some(alias);
+            changes.push(AccountChange::SetAlias { alias });
+        }
+        if remove_alias {
+            changes.push(AccountChange::SetAlias { alias: None });
+        }
+        if let Some(cap) = balance_cap {
+            let cap = Some(cap);
+            changes.push(AccountChange::SetBalanceCap { cap });
+        }
+        if remove_balance_cap {
+            changes.push(AccountChange::SetBalanceCap { cap: None });
+        }
+        for tag in add_tag {
+            changes.push(AccountChange::AddTag { tag });
+        }
+        for tag in remove_tag {
+            changes.push(AccountChange::RemoveTag { tag });
+        }
+        for category in set_category {
+            let category = parse_category(&category)?;
+            changes.push(AccountChange::SetCategory { category });
+        }
+        for name in remove_category {
+            changes.push(AccountChange::RemoveCategory { name });
+        }
+        if let Some(policy) = liveness_policy {
+            let policy = Some(parse_liveness_policy(&policy)?);
+            changes.push(AccountChange::SetLivenessPolicy { policy });
+        }
+        if remove_liveness_policy {
+            changes.push(AccountChange::SetLivenessPolicy { policy: None });
+        }
+        if let Some(supermajority) = supermajority {
+            let supermajority = Some(parse_supermajority(&supermajority)?);
+            changes.push(AccountChange::SetSupermajority { supermajority });
+        }
+        if remove_supermajority {
+            changes.push(AccountChange::SetSupermajority {
+                supermajority: None,
+            });
+        }
+        if key_addition_delay.is_some() || key_addition_supermajority {
+            let policy = Some(KeyAdditionPolicy {
+                delay: key_addition_delay.unwrap_or(0),
+                supermajority: key_addition_supermajority,
+            });
+            changes.push(AccountChange::SetKeyAdditionPolicy { policy });
+        }
+        if remove_key_addition_policy {
+            changes.push(AccountChange::SetKeyAdditionPolicy { policy: None });
+        }
+        if let Some(window) = transfer_window {
+            let window = Some(parse_transfer_window(&window)?);
+            changes.push(AccountChange::SetTransferWindow { window });
+        }
+        if remove_transfer_window {
+            changes.push(AccountChange::SetTransferWindow { window: None });
+        }
+        if let Some((delay, guardians)) = recovery_delay.zip(recovery_guardians)
+        {
+            let policy = Some(RecoveryPolicy { delay, guardians });
+            changes.push(AccountChange::SetRecoveryPolicy { policy });
+        }
+        if remove_recovery_policy {
+            changes.push(AccountChange::SetRecoveryPolicy { policy: None });
+        }
+        if let Some((fallback, period)) =
+            inactivity_fallback.zip(inactivity_period)
+        {
+            let fallback = parse_public_key(&fallback)?;
+            let policy = Some(InactivityPolicy { fallback, period });
+            changes.push(AccountChange::SetInactivityPolicy { policy });
+        }
+        if remove_inactivity_policy {
+            changes.push(AccountChange::SetInactivityPolicy { policy: None });
+        }
+        if let Some((min_amount, delay)) = queue_min_amount.zip(queue_delay) {
+            let policy = Some(QueuePolicy { min_amount, delay });
+            changes.push(AccountChange::SetQueuePolicy { policy });
+        }
+        if remove_queue_policy {
+            changes.push(AccountChange::SetQueuePolicy { policy: None });
+        }
+        for key in add_observer {
+            let key = parse_public_key(&key)?;
+            changes.push(AccountChange::AddObserver { key });
+        }
+        for key in remove_observer {
+            let key = parse_public_key(&key)?;
+            changes.push(AccountChange::RemoveObserver { key });
+        }
+        for budget in set_child_budget {
+            let budget = parse_child_budget(&budget)?;
+            changes.push(AccountChange::SetChildBudget { budget });
+        }
+        for child_id in claw_back_budget {
+            changes.push(AccountChange::ClawBackBudget { child_id });
+        }
+        for child_id in remove_child_budget {
+            changes.push(AccountChange::RemoveChildBudget { child_id });
+        }
+        for expiry in key_expiry {
+            let KeyExpiry { key, valid_until } = parse_key_expiry(&expiry)?;
+            changes.push(AccountChange::SetKeyExpiry {
+                key,
+                valid_until: Some(valid_until),
+            });
+        }
+        for key in remove_key_expiry {
+            let key = parse_public_key(&key)?;
+            changes.push(AccountChange::SetKeyExpiry {
+                key,
+                valid_until: None,
+            });
+        }
+        if archive {
+            changes.push(AccountChange::Archive);
+        }
+        if reactivate {
+            changes.push(AccountChange::Reactivate);
+        }
+        if let Some(claimable_withdrawals) = claimable_withdrawals {
+            changes.push(AccountChange::SetClaimableWithdrawals {
+                claimable_withdrawals,
+            });
+        }
+        if let Some(records) = history_retention {
+            let records = Some(records);
+            changes.push(AccountChange::SetHistoryRetention { records });
+        }
+        if default_history_retention {
+            changes.push(AccountChange::SetHistoryRetention { records: None });
+        }
+        if let (Some(key), Some(remediation)) = (compliance_key, remediation) {
+            let compliance = Some(ComplianceControl {
+                key: parse_public_key(&key)?,
+                remediation: parse_public_key(&remediation)?,
+            });
+            changes.push(AccountChange::SetCompliance { compliance });
+        }
+        if remove_compliance {
+            changes.push(AccountChange::SetCompliance { compliance: None });
+        }
+        if let Some(signature) = lift_hold {
+            let signature = parse_multisig_signature(&signature)?;
+            changes.push(AccountChange::LiftHold { signature });
+        }
+        if let Some(contract) = license_contract {
+            let policy = Some(LicensePolicy {
+                contract: parse_contract_id(&contract)?,
+                signers: license_keys,
+            });
+            changes.push(AccountChange::SetLicensePolicy { policy });
+        }
+        if remove_license_policy {
+            changes.push(AccountChange::SetLicensePolicy { policy: None });
+        }
+        for threshold in operation_threshold {
+            let OperationThreshold { op, threshold } =
+                parse_operation_threshold(&threshold)?;
+            changes.push(AccountChange::SetOperationThreshold {
+                op,
+                threshold: Some(threshold),
+            });
+        }
+        for op in remove_operation_threshold {
+            let op = parse_operation_kind(&op)?;
+            changes.push(AccountChange::SetOperationThreshold {
+                op,
+                threshold: None,
+            });
+        }
+        if !transfer_tier.is_empty() || remove_transfer_tiers {
+            let tiers = transfer_tier
+                .iter()
+                .map(|tier| parse_transfer_tier(tier))
+                .collect::<Result<_>>()?;
+            changes.push(AccountChange::SetTransferTiers { tiers });
+        }
+        for weight in key_weight {
+            let KeyWeight { key, weight } = parse_key_weight(&weight)?;
+            changes.push(AccountChange::SetKeyWeight { key, weight });
+        }
+        for role in key_role {
+            let RoleAssignment { key, role } = parse_role_assignment(&role)?;
+            changes.push(AccountChange::SetKeyRole { key, role });
+        }
+        for allowance in allowance {
+            let KeyAllowance { key, amount } = parse_key_allowance(&allowance)?;
+            changes.push(AccountChange::SetAllowance { key, amount });
+        }
+
+        Ok(changes)
+    }
+}
+
+/// The contract-wide parameters to set.
+#[derive(Args)]
+struct ConfigArgs {
+    /// Most keys an account may have.
+    #[arg(long, conflicts_with = "unbounded_keys")]
+    max_keys: Option<u32>,
+    /// Let accounts have any number of keys.
+    #[arg(long)]
+    unbounded_keys: bool,
+    /// Longest memo an operation may carry, in bytes.
+    #[arg(long, conflicts_with = "unbounded_memos")]
+    max_memo_len: Option<u32>,
+    /// Let operations carry memos of any length.
+    #[arg(long)]
+    unbounded_memos: bool,
+    /// Pause every operation moving funds.
+    #[arg(long, conflicts_with = "unpause")]
+    pause: bool,
+    /// Resume the operations moving funds.
+    #[arg(long)]
+    unpause: bool,
+    /// Storage fee for every movement an account retains past the default,
+    /// in LUX.
+    #[arg(long)]
+    history_record_fee: Option<u64>,
+    /// Largest gas refund an operation can designate, in LUX.
+    #[arg(long)]
+    max_gas_refund: Option<u64>,
+}
+
+impl ConfigArgs {
+    /// Applies the arguments given to the current parameters.
+    fn apply(self, mut config: ContractConfig) -> ContractConfig {
+        if let Some(max_keys) = self.max_keys {
+            config.max_keys = Some(max_keys);
+        }
+        if self.unbounded_keys {
+            config.max_keys = None;
+        }
+        if let Some(max_memo_len) = self.max_memo_len {
+            config.max_memo_len = Some(max_memo_len);
+        }
+        if self.unbounded_memos {
+            config.max_memo_len = None;
+        }
+        if self.pause {
+            config.paused = true;
+        }
+        if self.unpause {
+            config.paused = false;
+        }
+        if let Some(fee) = self.history_record_fee {
+            config.history_record_fee = fee;
+        }
+        if let Some(max) = self.max_gas_refund {
+            config.max_gas_refund = max;
+        }
+        config
+    }
+}
+
+/// Arguments for the Moonlight transaction used to call the contract.
+#[derive(Args)]
+struct TxArgs {
+    /// File containing the base58 encoded secret key of the Moonlight account
+    /// paying for the transaction.
+    #[arg(long)]
+    sender: PathBuf,
+    /// ID of the chain the transaction is meant for.
+    #[arg(long, env = "RUSK_CHAIN_ID")]
+    chain_id: u8,
+    /// Maximum amount of gas the transaction may spend.
+    #[arg(long, default_value_t = 100_000_000)]
+    gas_limit: u64,
+    /// Price to pay per unit of gas.
+    #[arg(long, default_value_t = 1)]
+    gas_price: u64,
+}
+
+impl TxArgs {
+    /// Builds a wallet paying for transactions as specified.
+    fn wallet(&self, node: Node, contract: ContractId) -> Result<Wallet> {
+        let sender = read_secret_key(&self.sender)?;
+        Ok(Wallet::new(node, contract, self.chain_id, sender)
+            .with_gas(self.gas_limit, self.gas_price))
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let node = Node::new(cli.node);
+
+    let contract = || -> Result<ContractId> {
+        let contract = cli
+            .contract
+            .as_deref()
+            .ok_or("The multisig contract ID must be specified")?;
+        let bytes = hex::decode(contract)?
+            .try_into()
+            .map_err(|_| "The contract ID must be 32 bytes long")?;
+        Ok(ContractId::from_bytes(bytes))
+    };
+
+    match cli.command {
+        Command::CreateAccount {
+            keys,
+            threshold,
+            description,
+            alias,
+            key_weight,
+            key_role,
+            tx,
+        } => {
+            let create_account = CreateAccount {
+                keys: keys
+                    .iter()
+                    .map(|key| parse_public_key(key))
+                    .collect::<Result<_>>()?,
+                threshold,
+                description,
+                alias,
+                key_weights: key_weight
+                    .iter()
+                    .map(|weight| parse_key_weight(weight))
+                    .collect::<Result<_>>()?,
+                key_roles: key_role
+                    .iter()
+                    .map(|role| parse_role_assignment(role))
+                    .collect::<Result<_>>()?,
+            };
+
+            tx.wallet(node, contract()?)?
+                .create_account(&create_account)
+                .await?;
+            println!("Transaction submitted");
+        }
+        Command::RegisterTemplate {
+            threshold,
+            description,
+            private_events,
+            velocity_limit,
+            cosigners,
+            guardians,
+            tx,
+        } => {
+            let template = AccountTemplate {
+                threshold,
+                description,
+                private_events,
+                velocity_limit: velocity_limit
+                    .as_deref()
+                    .map(parse_velocity_limit)
+                    .transpose()?,
+                cosigners: cosigners
+                    .iter()
+                    .map(|key| parse_cosigner_key(key))
+                    .collect::<Result<_>>()?,
+                guardians: guardians
+                    .iter()
+                    .map(|key| parse_public_key(key))
+                    .collect::<Result<_>>()?,
+            };
+
+            tx.wallet(node, contract()?)?
+                .register_template(&template)
+                .await?;
+            println!("Transaction submitted");
+        }
+        Command::CreateAccountFromTemplate {
+            template_id,
+            keys,
+            alias,
+            tx,
+        } => {
+            let create = CreateAccountFromTemplate {
+                template_id,
+                keys: keys
+                    .iter()
+                    .map(|key| parse_public_key(key))
+                    .collect::<Result<_>>()?,
+                alias,
+            };
+
+            tx.wallet(node, contract()?)?
+                .create_account_from_template(&create)
+                .await?;
+            println!("Transaction submitted");
+        }
+        Command::Deposit {
+            account_id,
+            alias,
+            amount,
+            memo,
+            reference,
+            tx,
+        } => {
+            let mut wallet = tx.wallet(node, contract()?)?;
+            let account_id = match (account_id, alias) {
+                (Some(account_id), _) => account_id,
+                (None, Some(alias)) => wallet
+                    .resolve_alias(&alias)
+                    .await?
+                    .ok_or_else(|| format!("No account claims {alias:?}"))?,
+                (None, None) => unreachable!("clap requires an account"),
+            };
+
+            wallet.deposit(account_id, amount, memo, reference).await?;
+            println!("Transaction submitted");
+        }
+        Command::Transfer {
+            account_id,
+            receiver,
+            amount,
+            all,
+            memo,
+            reference,
+            category,
+            gas_refund,
+            chain_id,
+            nonce,
+            output,
+        } => {
+            let nonce = match nonce {
+                Some(nonce) => nonce,
+                None => next_nonce(&node, contract()?, account_id).await?,
+            };
+
+            let operation = Operation::Transfer(Transfer {
+                account_id,
+                keys: Vec::new(),
+                signature: bls::MultisigSignature::default(),
+                cosignatures: Vec::new(),
+                group_signature: None,
+                receiver: parse_public_key(&receiver)?,
+                amount: match all {
+                    true => TRANSFER_ALL,
+                    false => amount.expect("clap requires an amount"),
+                },
+                nonce,
+                memo,
+                reference: reference
+                    .as_deref()
+                    .map(parse_reference)
+                    .transpose()?,
+                category,
+                gas_refund: gas_refund
+                    .as_deref()
+                    .map(parse_gas_refund)
+                    .transpose()?,
+                chain_id,
+            });
+
+            write(&output, &operation)?;
+        }
+        Command::Payroll {
+            account_id,
+            rows,
+            gas_refund,
+            chain_id,
+            nonce,
+            output,
+        } => {
+            let nonce = match nonce {
+                Some(nonce) => nonce,
+                None => next_nonce(&node, contract()?, account_id).await?,
+            };
+
+            let operation = Operation::Payroll(Payroll {
+                account_id,
+                keys: Vec::new(),
+                signature: bls::MultisigSignature::default(),
+                cosignatures: Vec::new(),
+                group_signature: None,
+                rows: rows
+                    .iter()
+                    .map(|row| parse_payroll_row(row))
+                    .collect::<Result<_>>()?,
+                nonce,
+                gas_refund: gas_refund
+                    .as_deref()
+                    .map(parse_gas_refund)
+                    .transpose()?,
+                chain_id,
+            });
+
+            write(&output, &operation)?;
+        }
+        Command::ChangeAccount {
+            account_id,
+            changes,
+            gas_refund,
+            chain_id,
+            nonce,
+            output,
+        } => {
+            let changes = changes.into_changes()?;
+
+            let nonce = match nonce {
+                Some(nonce) => nonce,
+                None => next_nonce(&node, contract()?, account_id).await?,
+            };
+
+            let operation = Operation::ChangeAccount(ChangeAccount {
+                account_id,
+                keys: Vec::new(),
+                signature: bls::MultisigSignature::default(),
+                cosignatures: Vec::new(),
+                group_signature: None,
+                changes,
+                nonce,
+                gas_refund: gas_refund
+                    .as_deref()
+                    .map(parse_gas_refund)
+                    .transpose()?,
+                chain_id,
+            });
+
+            write(&output, &operation)?;
+        }
+        Command::TransferAndChange {
+            account_id,
+            receiver,
+            amount,
+            all,
+            memo,
+            changes,
+            gas_refund,
+            chain_id,
+            nonce,
+            output,
+        } => {
+            let changes = changes.into_changes()?;
+
+            let nonce = match nonce {
+                Some(nonce) => nonce,
+                None => next_nonce(&node, contract()?, account_id).await?,
+            };
+
+            let operation = Operation::TransferAndChange(TransferAndChange {
+                account_id,
+                keys: Vec::new(),
+                signature: bls::MultisigSignature::default(),
+                cosignatures: Vec::new(),
+                group_signature: None,
+                receiver: parse_public_key(&receiver)?,
+                amount: match all {
+                    true => TRANSFER_ALL,
+                    false => amount.expect("clap requires an amount"),
+                },
+                memo,
+                changes,
+                nonce,
+                gas_refund: gas_refund
+                    .as_deref()
+                    .map(parse_gas_refund)
+                    .transpose()?,
+                chain_id,
+            });
+
+            write(&output, &operation)?;
+        }
+        Command::SplitAccount {
+            account_id,
+            keys,
+            threshold,
+            description,
+            amount,
+            gas_refund,
+            chain_id,
+            nonce,
+            output,
+        } => {
+            let nonce = match nonce {
+                Some(nonce) => nonce,
+                None => next_nonce(&node, contract()?, account_id).await?,
+            };
+
+            let operation = Operation::SplitAccount(SplitAccount {
+                account_id,
+                keys: Vec::new(),
+                signature: bls::MultisigSignature::default(),
+                cosignatures: Vec::new(),
+                group_signature: None,
+                new_keys: keys
+                    .iter()
+                    .map(|key| parse_public_key(key))
+                    .collect::<Result<_>>()?,
+                threshold,
+                description,
+                amount,
+                nonce,
+                gas_refund: gas_refund
+                    .as_deref()
+                    .map(parse_gas_refund)
+                    .transpose()?,
+                chain_id,
+            });
+
+            write(&output, &operation)?;
+        }
+        Command::Sign {
+            operation,
+            key,
+            output,
+        } => {
+            let operation: Operation = read(&operation)?;
+            let sk = read_secret_key(&key)?;
+
+            write(&output, &PartialSignature::sign(&sk, &operation))?;
+        }
+        Command::Cosign {
+            operation,
+            key,
+            output,
+        } => {
+            let operation: Operation = read(&operation)?;
+            let sk = read_ed25519_key(&key)?;
+
+            write(&output, &Cosignature::sign_ed25519(&sk, &operation))?;
+        }
+        Command::Merge {
+            operation,
+            partials,
+            cosignatures,
+            group_signature,
+            output,
+        } => {
+            if let Some(signature) = group_signature {
+                let mut operation: Operation = read(&operation)?;
+                operation.set_group_signature(parse_signature(&signature)?);
+                write(&output, &operation)?;
+                return Ok(());
+            }
+            if partials.is_empty() && cosignatures.is_empty() {
+                return Err("At least one signature must be given".into());
+            }
+
+            let mut operation: Operation = read(&operation)?;
+
+            // Reject bad partials upfront, rather than producing an operation
+            // the contract would refuse
+            let mut signatures = Vec::with_capacity(partials.len());
+            for path in &partials {
+                let partial: PartialSignature = read(path)?;
+                if !partial.verify(&operation) {
+                    return Err(format!(
+                        "Invalid partial signature in {path:?}"
+                    )
+                    .into());
+                }
+                signatures.push(partial);
+            }
+
+            operation.aggregate(&signatures);
+            for path in &cosignatures {
+                let cosignature: Cosignature = read(path)?;
+                if !cosignature.verify(&operation) {
+                    return Err(
+                        format!("Invalid co-signature in {path:?}").into()
+                    );
+                }
+                operation.cosign(cosignature);
+            }
+            write(&output, &operation)?;
+        }
+        Command::Verify {
+            operation,
+            partial,
+            key,
+        } => {
+            let operation: Operation = read(&operation)?;
+            let partial: PartialSignature = read(&partial)?;
+
+            if let Some(key) = key {
+                if parse_public_key(&key)? != partial.key {
+                    return Err("The signature is by a different key".into());
+                }
+            }
+            if !partial.verify(&operation) {
+                return Err("The signature is not valid".into());
+            }
+            println!(
+                "Valid signature by {}",
+                encoding::encode_public_key(&partial.key)
+            );
+        }
+        Command::Submit {
+            operation,
+            certified,
+            queue,
+            tx,
+        } => {
+            let operation: Operation = read(&operation)?;
+
+            let mut wallet = tx.wallet(node, contract()?)?;
+            if queue {
+                let Operation::Transfer(transfer) = &operation else {
+                    return Err("Only transfers can be queued".into());
+                };
+                wallet.queue_transfer(transfer).await?;
+            } else if certified {
+                wallet.submit_certified(&operation).await?;
+            } else {
+                wallet.submit(&operation).await?;
+            }
+            println!("Transaction submitted");
+        }
+        Command::MergeAccounts {
+            account_id,
+            merged_id,
+            output,
+        } => {
+            let merge = MergeAccounts {
+                account_id,
+                keys: Vec::new(),
+                signature: bls::MultisigSignature::default(),
+                cosignatures: Vec::new(),
+                group_signature: None,
+                nonce: next_nonce(&node, contract()?, account_id).await?,
+                merged_id,
+                merged_keys: Vec::new(),
+                merged_signature: bls::MultisigSignature::default(),
+                merged_cosignatures: Vec::new(),
+                merged_group_signature: None,
+                merged_nonce: next_nonce(&node, contract()?, merged_id).await?,
+            };
+
+            write(&output, &merge)?;
+        }
+        Command::SignMerge { merge, key, output } => {
+            let merge: MergeAccounts = read(&merge)?;
+            let sk = read_secret_key(&key)?;
+
+            write(&output, &merge.sign(&sk))?;
+        }
+        Command::SubmitMerge {
+            merge,
+            partials,
+            merged_partials,
+            tx,
+        } => {
+            let mut merge: MergeAccounts = read(&merge)?;
+
+            let read_partials = |paths: &[PathBuf]| -> Result<Vec<_>> {
+                let mut signatures = Vec::with_capacity(paths.len());
+                for path in paths {
+                    let partial: PartialSignature = read(path)?;
+                    if !merge.verify(&partial) {
+                        return Err(format!(
+                            "Invalid partial signature in {path:?}"
+                        )
+                        .into());
+                    }
+                    signatures.push(partial);
+                }
+                Ok(signatures)
+            };
+            let partials = read_partials(&partials)?;
+            let merged_partials = read_partials(&merged_partials)?;
+            merge.aggregate(&partials, &merged_partials);
+
+            tx.wallet(node, contract()?)?.submit_merge(&merge).await?;
+            println!("Transaction submitted");
+        }
+        Command::DrawBudget {
+            account_id,
+            parent_id,
+            amount,
+            output,
+        } => {
+            let draw = DrawBudget {
+                account_id,
+                parent_id,
+                keys: Vec::new(),
+                signature: bls::MultisigSignature::default(),
+                cosignatures: Vec::new(),
+                group_signature: None,
+                amount,
+                nonce: next_nonce(&node, contract()?, account_id).await?,
+            };
+
+            write(&output, &draw)?;
+        }
+        Command::SignDraw { draw, key, output } => {
+            let draw: DrawBudget = read(&draw)?;
+            let sk = read_secret_key(&key)?;
+
+            write(&output, &draw.sign(&sk))?;
+        }
+        Command::SubmitDraw { draw, partials, tx } => {
+            let mut draw: DrawBudget = read(&draw)?;
+
+            let mut signatures = Vec::with_capacity(partials.len());
+            for path in &partials {
+                let partial: PartialSignature = read(path)?;
+                if !draw.verify(&partial) {
+                    return Err(format!(
+                        "Invalid partial signature in {path:?}"
+                    )
+                    .into());
+                }
+                signatures.push(partial);
+            }
+            draw.aggregate(&signatures);
+
+            tx.wallet(node, contract()?)?.submit_draw(&draw).await?;
+            println!("Transaction submitted");
+        }
+        Command::RecordCheckpoint { account_id, output } => {
+            let checkpoint = RecordCheckpoint {
+                account_id,
+                keys: Vec::new(),
+                signature: bls::MultisigSignature::default(),
+                cosignatures: Vec::new(),
+                group_signature: None,
+                nonce: next_nonce(&node, contract()?, account_id).await?,
+            };
+
+            write(&output, &checkpoint)?;
+        }
+        Command::SignCheckpoint {
+            checkpoint,
+            key,
+            output,
+        } => {
+            let checkpoint: RecordCheckpoint = read(&checkpoint)?;
+            let sk = read_secret_key(&key)?;
+
+            write(&output, &checkpoint.sign(&sk))?;
+        }
+        Command::SubmitCheckpoint {
+            checkpoint,
+            partials,
+            tx,
+        } => {
+            let mut checkpoint: RecordCheckpoint = read(&checkpoint)?;
+
+            let mut signatures = Vec::with_capacity(partials.len());
+            for path in &partials {
+                let partial: PartialSignature = read(path)?;
+                if !checkpoint.verify(&partial) {
+                    return Err(format!(
+                        "Invalid partial signature in {path:?}"
+                    )
+                    .into());
+                }
+                signatures.push(partial);
+            }
+            checkpoint.aggregate(&signatures);
+
+            tx.wallet(node, contract()?)?
+                .submit_checkpoint(&checkpoint)
+                .await?;
+            println!("Transaction submitted");
+        }
+        Command::Configure { config, output } => {
+            let governance: Option<u64> =
+                node.query(contract()?, "governance", &()).await?;
+            let account_id = governance.ok_or(Error::NoGovernance.as_str())?;
+            let current: ContractConfig =
+                node.query(contract()?, "config", &()).await?;
+            let configure = Configure {
+                config: config.apply(current),
+                keys: Vec::new(),
+                signature: bls::MultisigSignature::default(),
+                cosignatures: Vec::new(),
+                group_signature: None,
+                nonce: next_nonce(&node, contract()?, account_id).await?,
+            };
+
+            write(&output, &configure)?;
+        }
+        Command::SignConfigure {
+            configure,
+            key,
+            output,
+        } => {
+            let configure: Configure = read(&configure)?;
+            let sk = read_secret_key(&key)?;
+
+            write(&output, &configure.sign(&sk))?;
+        }
+        Command::SubmitConfigure {
+            configure,
+            partials,
+            tx,
+        } => {
+            let mut configure: Configure = read(&configure)?;
+
+            let mut signatures = Vec::with_capacity(partials.len());
+            for path in &partials {
+                let partial: PartialSignature = read(path)?;
+                if !configure.verify(&partial) {
+                    return Err(format!(
+                        "Invalid partial signature in {path:?}"
+                    )
+                    .into());
+                }
+                signatures.push(partial);
+            }
+            configure.aggregate(&signatures);
+
+            tx.wallet(node, contract()?)?
+                .submit_configure(&configure)
+                .await?;
+            println!("Transaction submitted");
+        }
+        Command::LockAccount {
+            account_id,
+            key,
+            tx,
+        } => {
+            let sk = read_secret_key(&key)?;
+
+            tx.wallet(node, contract()?)?
+                .lock_account(&sk, account_id)
+                .await?;
+            println!("Transaction submitted");
+        }
+        Command::PlaceHold {
+            account_id,
+            key,
+            tx,
+        } => {
+            let sk = read_secret_key(&key)?;
+
+            tx.wallet(node, contract()?)?
+                .place_hold(&sk, account_id)
+                .await?;
+            println!("Transaction submitted");
+        }
+        Command::SignLiftHold {
+            account_id,
+            key,
+            nonce,
+        } => {
+            let sk = read_secret_key(&key)?;
+            let nonce = match nonce {
+                Some(nonce) => nonce,
+                None => next_nonce(&node, contract()?, account_id).await?,
+            };
+
+            let signature = PlaceHold::sign_lift(&sk, account_id, nonce);
+            println!("{}", encoding::encode_multisig_signature(&signature));
+        }
+        Command::AttestLiveness {
+            account_id,
+            key,
+            block_height,
+            tx,
+        } => {
+            let sk = read_secret_key(&key)?;
+
+            tx.wallet(node, contract()?)?
+                .attest_liveness(&sk, account_id, block_height)
+                .await?;
+            println!("Transaction submitted");
+        }
+        Command::DelegateKey {
+            account_id,
+            key,
+            delegate,
+            from_height,
+            until_height,
+            revoke,
+            tx,
+        } => {
+            let sk = read_secret_key(&key)?;
+
+            let delegation = match (delegate, from_height.zip(until_height)) {
+                (Some(delegate), Some((from_height, until_height))) => {
+                    Some(Delegation {
+                        delegate: parse_public_key(&delegate)?,
+                        from_height,
+                        until_height,
+                    })
+                }
+                _ if revoke => None,
+                _ => unreachable!("clap requires a delegation"),
+            };
+
+            tx.wallet(node, contract()?)?
+                .delegate_key(&sk, account_id, delegation)
+                .await?;
+            println!("Transaction submitted");
+        }
+        Command::Guard {
+            account_id,
+            key,
+            lock,
+            veto,
+            extend,
+            until,
+            tx,
+        } => {
+            let sk = read_secret_key(&key)?;
+
+            let action = match (veto, extend.zip(until)) {
+                (Some(receiver), _) => GuardianAction::VetoWhitelisted {
+                    receiver: parse_public_key(&receiver)?,
+                },
+                (_, Some((receiver, usable_from))) => {
+                    GuardianAction::ExtendWhitelistDelay {
+                        receiver: parse_public_key(&receiver)?,
+                        usable_from,
+                    }
+                }
+                _ if lock => GuardianAction::Lock,
+                _ => unreachable!("clap requires an action"),
+            };
+
+            tx.wallet(node, contract()?)?
+                .guard_account(&sk, account_id, action)
+                .await?;
+            println!("Transaction submitted");
+        }
+        Command::StartRecovery {
+            account_id,
+            keys,
+            threshold,
+            output,
+        } => {
+            let keys = keys
+                .iter()
+                .map(|key| parse_public_key(key))
+                .collect::<Result<_>>()?;
+            let account: AccountData =
+                node.query(contract()?, "account", &account_id).await?;
+            let recovery = StartRecovery {
+                account_id,
+                guardians: Vec::new(),
+                signature: bls::MultisigSignature::default(),
+                keys,
+                threshold,
+                nonce: account.recovery_nonce + 1,
+            };
+
+            write(&output, &recovery)?;
+        }
+        Command::SignRecovery {
+            recovery,
+            key,
+            output,
+        } => {
+            let recovery: StartRecovery = read(&recovery)?;
+            let sk = read_secret_key(&key)?;
+
+            write(&output, &recovery.sign(&sk))?;
+        }
+        Command::SubmitRecovery {
+            recovery,
+            partials,
+            tx,
+        } => {
+            let mut recovery: StartRecovery = read(&recovery)?;
+
+            let mut signatures = Vec::with_capacity(partials.len());
+            for path in &partials {
+                let partial: PartialSignature = read(path)?;
+                if !recovery.verify(&partial) {
+                    return Err(format!(
+                        "Invalid partial signature in {path:?}"
+                    )
+                    .into());
+                }
+                signatures.push(partial);
+            }
+            recovery.aggregate(&signatures);
+
+            tx.wallet(node, contract()?)?
+                .submit_recovery(&recovery)
+                .await?;
+            println!("Transaction submitted");
+        }
+        Command::CancelRecovery {
+            account_id,
+            key,
+            tx,
+        } => {
+            let sk = read_secret_key(&key)?;
+
+            tx.wallet(node, contract()?)?
+                .cancel_recovery(&sk, account_id)
+                .await?;
+            println!("Transaction submitted");
+        }
+        Command::FinalizeRecovery { account_id, tx } => {
+            tx.wallet(node, contract()?)?
+                .finalize_recovery(account_id)
+                .await?;
+            println!("Transaction submitted");
+        }
+        Command::ClaimInactive {
+            account_id,
+            key,
+            sweep_to,
+            new_keys,
+            threshold,
+            tx,
+        } => {
+            let sk = read_secret_key(&key)?;
+
+            let claim = match (sweep_to, threshold) {
+                (Some(receiver), _) => InactiveClaim::Sweep {
+                    receiver: parse_public_key(&receiver)?,
+                },
+                (_, Some(threshold)) => InactiveClaim::RotateKeys {
+                    keys: new_keys
+                        .iter()
+                        .map(|key| parse_public_key(key))
+                        .collect::<Result<_>>()?,
+                    threshold,
+                },
+                _ => unreachable!("clap requires a claim"),
+            };
+
+            tx.wallet(node, contract()?)?
+                .claim_inactive(&sk, account_id, claim)
+                .await?;
+            println!("Transaction submitted");
+        }
+        Command::CancelQueued {
+            account_id,
+            nonce,
+            key,
+            tx,
+        } => {
+            let sk = read_secret_key(&key)?;
+
+            tx.wallet(node, contract()?)?
+                .cancel_queued(&sk, account_id, nonce)
+                .await?;
+            println!("Transaction submitted");
+        }
+        Command::ExecuteQueued {
+            account_id,
+            nonce,
+            tx,
+        } => {
+            tx.wallet(node, contract()?)?
+                .execute_queued(account_id, nonce)
+                .await?;
+            println!("Transaction submitted");
+        }
+        Command::TransferWithAllowance {
+            account_id,
+            key,
+            receiver,
+            amount,
+            memo,
+            tx,
+        } => {
+            let sk = read_secret_key(&key)?;
+            let receiver = parse_public_key(&receiver)?;
+
+            tx.wallet(node, contract()?)?
+                .transfer_with_allowance(
+                    &sk, account_id, receiver, amount, memo,
+                )
+                .await?;
+            println!("Transaction submitted");
+        }
+        Command::ClaimWithdrawal { claim_id, key, tx } => {
+            let sk = read_secret_key(&key)?;
+
+            tx.wallet(node, contract()?)?
+                .claim_withdrawal(&sk, claim_id)
+                .await?;
+            println!("Transaction submitted");
+        }
+        Command::MarkCompromised { key, tx } => {
+            let sk = read_secret_key(&key)?;
+
+            tx.wallet(node, contract()?)?.mark_compromised(&sk).await?;
+            println!("Transaction submitted");
+        }
+        Command::MarkCosignerCompromised { key, tx } => {
+            let sk = read_ed25519_key(&key)?;
+
+            tx.wallet(node, contract()?)?
+                .mark_cosigner_compromised(&sk)
+                .await?;
+            println!("Transaction submitted");
+        }
+        Command::ReplaceKey {
+            key,
+            new_key,
+            operations,
+            tx,
+        } => {
+            let sk = read_secret_key(&key)?;
+            let new_key = parse_public_key(&new_key)?;
+
+            let mut changes = Vec::with_capacity(operations.len());
+            for path in &operations {
+                let Operation::ChangeAccount(change) = read(path)? else {
+                    return Err(format!(
+                        "Not a change of an account in {path:?}"
+                    )
+                    .into());
+                };
+                changes.push(change);
+            }
+
+            tx.wallet(node, contract()?)?
+                .replace_key(&sk, new_key, changes)
+                .await?;
+            println!("Transaction submitted");
+        }
+        Command::DeriveKey {
+            mnemonic,
+            passphrase,
+            index,
+            output,
+        } => {
+            let seed = keys::seed(&mnemonic, &passphrase)
+                .map_err(|err| format!("Invalid mnemonic: {err}"))?;
+            let sk = keys::derive_secret_key(&seed, index);
+
+            write_secret_key(&output, &sk)?;
+            let pk = bls::PublicKey::from(&sk);
+            println!("{}", encoding::encode_public_key(&pk));
+        }
+        Command::Account { account_id } => {
+            let account: AccountData =
+                node.query(contract()?, "account", &account_id).await?;
+            println!("{account:#?}");
+        }
+        Command::AccountKeys { account_id } => {
+            let keys: Vec<bls::PublicKey> = node
+                .feeder_query(contract()?, "account_keys", &account_id)
+                .await?;
+            for key in keys {
+                println!("{}", encoding::encode_public_key(&key));
+            }
+        }
+        Command::Observers { account_id } => {
+            let keys: Vec<bls::PublicKey> = node
+                .feeder_query(contract()?, "observers", &account_id)
+                .await?;
+            for key in keys {
+                println!("{}", encoding::encode_public_key(&key));
+            }
+        }
+        Command::KeyLiveness { account_id } => {
+            let liveness: Vec<KeyLiveness> = node
+                .feeder_query(contract()?, "key_liveness", &account_id)
+                .await?;
+            for l in liveness {
+                let key = encoding::encode_public_key(&l.key);
+                println!("{key} {}", l.attested_at);
+            }
+        }
+        Command::Delegations { account_id } => {
+            let delegations: Vec<KeyDelegation> = node
+                .feeder_query(contract()?, "delegations", &account_id)
+                .await?;
+            for kd in delegations {
+                let key = encoding::encode_public_key(&kd.key);
+                match kd.delegation {
+                    Some(d) => {
+                        let delegate = encoding::encode_public_key(&d.delegate);
+                        println!(
+                            "{key} {delegate} {}-{} nonce {}",
+                            d.from_height, d.until_height, kd.nonce
+                        );
+                    }
+                    None => println!("{key} revoked nonce {}", kd.nonce),
+                }
+            }
+        }
+        Command::PendingKeys { account_id } => {
+            let pending_keys: Vec<PendingKey> = node
+                .feeder_query(contract()?, "pending_keys", &account_id)
+                .await?;
+            for p in pending_keys {
+                let key = encoding::encode_public_key(&p.key);
+                println!("{key} {}", p.usable_from);
+            }
+        }
+        Command::KeyExpiries { account_id } => {
+            let key_expiries: Vec<KeyExpiry> = node
+                .feeder_query(contract()?, "key_expiries", &account_id)
+                .await?;
+            for e in key_expiries {
+                let key = encoding::encode_public_key(&e.key);
+                println!("{key} {}", e.valid_until);
+            }
+        }
+        Command::KeyWeights { account_id } => {
+            let key_weights: Vec<KeyWeight> = node
+                .feeder_query(contract()?, "key_weights", &account_id)
+                .await?;
+            for w in key_weights {
+                let key = encoding::encode_public_key(&w.key);
+                println!("{key} {}", w.weight);
+            }
+        }
+        Command::KeyRoles { account_id } => {
+            let key_roles: Vec<RoleAssignment> = node
+                .feeder_query(contract()?, "key_roles", &account_id)
+                .await?;
+            for r in key_roles {
+                let key = encoding::encode_public_key(&r.key);
+                println!("{key} {}", r.role.name());
+            }
+        }
+        Command::PendingRecovery { account_id } => {
+            let recovery: Option<PendingRecovery> = node
+                .query(contract()?, "pending_recovery", &account_id)
+                .await?;
+            if let Some(r) = recovery {
+                let guardians: Vec<_> = r
+                    .guardians
+                    .iter()
+                    .map(encoding::encode_public_key)
+                    .collect();
+                println!("executable from {}", r.executable_from);
+                println!("guardians {}", guardians.join(" "));
+                println!("threshold {}", r.threshold);
+                for key in &r.keys {
+                    println!("{}", encoding::encode_public_key(key));
+                }
+            }
+        }
+        Command::Allowances { account_id } => {
+            let allowances: Vec<KeyAllowance> =
+                node.query(contract()?, "allowances", &account_id).await?;
+            for a in allowances {
+                let key = encoding::encode_public_key(&a.key);
+                println!("{key} {}", a.amount);
+            }
+        }
+        Command::QueuedTransfers { account_id } => {
+            let queued: Vec<QueuedTransfer> = node
+                .query(contract()?, "queued_transfers", &account_id)
+                .await?;
+            for q in queued {
+                println!(
+                    "{} {} {} executable from {}",
+                    q.nonce,
+                    encoding::encode_public_key(&q.receiver),
+                    q.amount,
+                    q.executable_from
+                );
+            }
+        }
+        Command::TransferTiers { account_id } => {
+            let tiers: Vec<TransferTier> = node
+                .feeder_query(contract()?, "transfer_tiers", &account_id)
+                .await?;
+            for tier in tiers {
+                println!("{} {}", tier.ceiling, tier.threshold);
+            }
+        }
+        Command::KeyUsage {
+            account_id,
+            inactive_since,
+        } => {
+            let key_usage: Vec<KeyUsage> = node
+                .feeder_query(contract()?, "key_usage", &account_id)
+                .await?;
+            for u in key_usage {
+                if inactive_since.is_some_and(|h| !u.is_inactive_since(h)) {
+                    continue;
+                }
+                let key = encoding::encode_public_key(&u.key);
+                let last_signed = u
+                    .last_signed
+                    .map_or_else(|| String::from("-"), |h| h.to_string());
+                println!("{key} {} {last_signed}", u.operations);
+            }
+        }
+        Command::CategorySpend { account_id } => {
+            let spending: Vec<CategorySpend> = node
+                .feeder_query(contract()?, "category_spend", &account_id)
+                .await?;
+            for s in spending {
+                println!("{} {}", s.name, s.spent);
+            }
+        }
+        Command::BudgetDraws { account_id } => {
+            let draws: Vec<BudgetDraw> = node
+                .feeder_query(contract()?, "budget_draws", &account_id)
+                .await?;
+            for d in draws {
+                println!("{} {} {}", d.child_id, d.drawn, d.held);
+            }
+        }
+        Command::Checkpoints { account_id } => {
+            let checkpoints: Vec<Checkpoint> = node
+                .feeder_query(contract()?, "checkpoints", &account_id)
+                .await?;
+            for c in checkpoints {
+                println!(
+                    "{} {} {} 0x{} {}",
+                    c.sequence,
+                    c.block_height,
+                    c.balance,
+                    hex::encode(c.keys_hash),
+                    c.nonce
+                );
+            }
+        }
+        Command::Withdrawals { receiver } => {
+            let receiver = parse_public_key(&receiver)?;
+            let withdrawals: Vec<PendingWithdrawal> = node
+                .feeder_query(contract()?, "withdrawals", &receiver)
+                .await?;
+            for w in withdrawals {
+                println!(
+                    "{} {} {} {}",
+                    w.claim_id, w.account_id, w.amount, w.block_height
+                );
+            }
+        }
+        Command::TaggedAccounts { tag } => {
+            let account_ids: Vec<u64> = node
+                .feeder_query(contract()?, "tagged_accounts", &tag)
+                .await?;
+            for account_id in account_ids {
+                println!("{account_id}");
+            }
+        }
+        Command::Movements {
+            account_id,
+            from_height,
+            to_height,
+        } => {
+            let query = MovementQuery {
+                account_id,
+                from_height,
+                to_height,
+            };
+            let movements: Vec<Movement> =
+                node.feeder_query(contract()?, "movements", &query).await?;
+            print!("{}", render_movements(&movements)?);
+        }
+        Command::LargeTransfers {
+            min_amount,
+            from_height,
+            to_height,
+        } => {
+            let query = LargeTransferQuery {
+                min_amount,
+                from_height,
+                to_height,
+            };
+            let transfers: Vec<LargeTransfer> = node
+                .feeder_query(contract()?, "large_transfers", &query)
+                .await?;
+            print!("{}", render_large_transfers(&transfers)?);
+        }
+        Command::StateDiff {
+            account_id,
+            from_nonce,
+            to_nonce,
+        } => {
+            let query = StateDiffQuery {
+                account_id,
+                from_nonce,
+                to_nonce,
+            };
+            let diff: Option<StateDiff> =
+                node.query(contract()?, "state_diff", &query).await?;
+            let diff = diff.ok_or("Nonces not retained by the account")?;
+            println!(
+                "balance:   {} -> {} ({:+})",
+                diff.balance_before,
+                diff.balance_after,
+                diff.balance_delta()
+            );
+            println!(
+                "threshold: {} -> {}",
+                diff.threshold_before, diff.threshold_after
+            );
+            for key in diff.added_keys {
+                println!("+ {}", encoding::encode_public_key(&key));
+            }
+            for key in diff.removed_keys {
+                println!("- {}", encoding::encode_public_key(&key));
+            }
+        }
+        Command::SimulateChange { operation, signers } => {
+            let Operation::ChangeAccount(mut change) = read(&operation)? else {
+                return Err(format!(
+                    "Not a change of an account in {operation:?}"
+                )
+                .into());
+            };
+            change.keys = if signers.is_empty() {
+                node.feeder_query(
+                    contract()?,
+                    "account_keys",
+                    &change.account_id,
+                )
+                .await?
+            } else {
+                signers
+                    .iter()
+                    .map(|key| parse_public_key(key))
+                    .collect::<Result<_>>()?
+            };
+
+            let simulation: ChangeSimulation =
+                node.query(contract()?, "simulate_change", &change).await?;
+            println!("threshold: {}", simulation.threshold);
+            for key in simulation.keys {
+                println!("{}", encoding::encode_public_key(&key));
+            }
+            for violation in simulation.violations {
+                println!("! {violation}");
+            }
+        }
+        Command::Custody => {
+            let report: CustodyReport =
+                node.query(contract()?, "custody", &()).await?;
+            println!("total balance: {}", report.total_balance);
+            println!("withdrawals:   {}", report.pending_withdrawals);
+            println!("held:          {}", report.held);
+            println!("delta:         {}", report.delta);
+        }
+        Command::Stats => {
+            let stats: UsageStats =
+                node.query(contract()?, "stats", &()).await?;
+            for calls in &stats.calls {
+                println!("{:<30} {}", calls.entrypoint, calls.calls);
+            }
+            println!("total calls:           {}", stats.total_calls());
+            println!("keys verified:         {}", stats.keys_verified);
+            println!("cosignatures verified: {}", stats.cosignatures_verified);
+        }
+        Command::Config => {
+            let governance: Option<u64> =
+                node.query(contract()?, "governance", &()).await?;
+            let config: ContractConfig =
+                node.query(contract()?, "config", &()).await?;
+            match governance {
+                Some(account_id) => println!("governance:    {account_id}"),
+                None => println!("governance:    none"),
+            }
+            match config.max_keys {
+                Some(max) => println!("max keys:      {max}"),
+                None => println!("max keys:      none"),
+            }
+            match config.max_memo_len {
+                Some(max) => println!("max memo len:  {max}"),
+                None => println!("max memo len:  none"),
+            }
+            println!("paused:        {}", config.paused);
+            println!("record fee:    {}", config.history_record_fee);
+            println!("max refund:    {}", config.max_gas_refund);
+        }
+        Command::Template { template_id } => {
+            let template: Option<AccountTemplate> =
+                node.query(contract()?, "template", &template_id).await?;
+            let template = template.ok_or(Error::TemplateNotFound.as_str())?;
+            println!("{template:#?}");
+        }
+        Command::DecodeEvent { topic, payload } => {
+            let data = parse_payload(&payload)?;
+            print!("{}", render_event(&topic, &data)?);
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the nonce the next operation on the given account should use.
+async fn next_nonce(
+    node: &Node,
+    contract: ContractId,
+    account_id: u64,
+) -> Result<u64> {
+    let account: AccountData =
+        node.query(contract, "account", &account_id).await?;
+    Ok(account.nonce + 1)
+}
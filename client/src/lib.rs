@@ -0,0 +1,290 @@
+//! Typed client for querying the `multisig-contract` on a rusk node.
+//!
+//! The [`MultisigClient`] wraps the node's HTTP API, decoding the responses
+//! of the contract's queries - feeder queries included - into the types they
+//! return, and subscribes to the events the contract emits over the node's
+//! RUES interface.
+//!
+//! ```no_run
+//! # use execution_core::ContractId;
+//! # use multisig_client::MultisigClient;
+//! # async fn example(contract: ContractId) -> multisig_client::Result<()> {
+//! let client = MultisigClient::new("http://127.0.0.1:8080", contract);
+//!
+//! if let Some(account) = client.account(1).await? {
+//!     let keys = client.account_keys(1).await?;
+//!     println!("{} of {} keys", account.threshold, keys.len());
+//! }
+//!
+//! let mut subscription = client.subscribe(&["transfer"]).await?;
+//! while let Some(record) = subscription.next_record().await? {
+//!     println!("{:?}", record.account_ids());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+#![deny(missing_docs)]
+
+mod record;
+mod rues;
+
+use core::fmt;
+
+use execution_core::ContractId;
+
+use bytecheck::CheckBytes;
+use rkyv::de::deserializers::SharedDeserializeMap;
+use rkyv::ser::serializers::AllocSerializer;
+use rkyv::validation::validators::DefaultValidator;
+use rkyv::{Archive, Deserialize, Serialize};
+
+use multisig_contract_types::*;
+use multisig_wallet::Node;
+
+pub use record::Record;
+pub use rues::Subscription;
+
+/// Errors that may occur when querying the contract.
+#[derive(Debug)]
+pub enum Error {
+    /// Querying the node failed, or its response couldn't be decoded.
+    Node(multisig_wallet::Error),
+    /// Subscribing to the events of the contract failed.
+    Http(reqwest::Error),
+    /// The websocket of a subscription failed.
+    WebSocket(tokio_tungstenite::tungstenite::Error),
+    /// The node broke the RUES protocol.
+    Protocol(&'static str),
+    /// An event was received with a topic the contract doesn't emit, or data
+    /// that couldn't be decoded, carrying its topic and the version of its
+    /// envelope.
+    UndecodableEvent(String, Option<u32>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Node(err) => write!(f, "{err}"),
+            Error::Http(err) => write!(f, "Subscribing to events: {err}"),
+            Error::WebSocket(err) => write!(f, "Receiving events: {err}"),
+            Error::Protocol(msg) => write!(f, "{msg}"),
+            Error::UndecodableEvent(topic, Some(EVENT_VERSION)) => {
+                write!(f, "Undecodable `{topic}` event")
+            }
+            Error::UndecodableEvent(topic, version) => {
+                write!(f, "`{topic}` event of unsupported version {version:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<multisig_wallet::Error> for Error {
+    fn from(err: multisig_wallet::Error) -> Self {
+        Error::Node(err)
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        Error::Http(err)
+    }
+}
+
+impl From<tokio_tungstenite::tungstenite::Error> for Error {
+    fn from(err: tokio_tungstenite::tungstenite::Error) -> Self {
+        Error::WebSocket(err)
+    }
+}
+
+/// Result of querying the contract.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A client querying the contract on a rusk node.
+pub struct MultisigClient {
+    node: Node,
+    url: String,
+    contract: ContractId,
+}
+
+impl MultisigClient {
+    /// Connects to the contract with the given ID, on the node at the given
+    /// URL.
+    pub fn new(url: impl Into<String>, contract: ContractId) -> Self {
+        let url = url.into();
+        Self {
+            node: Node::new(url.clone()),
+            url,
+            contract,
+        }
+    }
+
+    /// The ID of the contract queried.
+    pub fn contract(&self) -> ContractId {
+        self.contract
+    }
+
+    /// Returns the data of an account, or `None` if it doesn't exist.
+    pub async fn account(
+        &self,
+        account_id: u64,
+    ) -> Result<Option<AccountData>> {
+        let account: AccountData = self
+            .node
+            .query(self.contract, "account", &account_id)
+            .await?;
+
+        // Accounts that don't exist are reported with a zero threshold, which
+        // no existing account can have
+        Ok((account.threshold != 0).then_some(account))
+    }
+
+    /// Returns the keys of an account.
+    pub async fn account_keys(
+        &self,
+        account_id: u64,
+    ) -> Result<Vec<bls::PublicKey>> {
+        self.feeder_query("account_keys", &account_id).await
+    }
+
+    /// Returns the IDs of the accounts using a key.
+    pub async fn key_accounts(&self, key: &bls::PublicKey) -> Result<Vec<u64>> {
+        self.feeder_query("key_accounts", key).await
+    }
+
+    /// Returns the co-signers of an account.
+    pub async fn cosigners(&self, account_id: u64) -> Result<Vec<CosignerKey>> {
+        self.feeder_query("cosigners", &account_id).await
+    }
+
+    /// Returns the duress keys of an account.
+    pub async fn duress_keys(
+        &self,
+        account_id: u64,
+    ) -> Result<Vec<bls::PublicKey>> {
+        self.feeder_query("duress_keys", &account_id).await
+    }
+
+    /// Returns the guardians of an account.
+    pub async fn guardians(
+        &self,
+        account_id: u64,
+    ) -> Result<Vec<bls::PublicKey>> {
+        self.feeder_query("guardians", &account_id).await
+    }
+
+    /// Returns the observers of an account.
+    pub async fn observers(
+        &self,
+        account_id: u64,
+    ) -> Result<Vec<bls::PublicKey>> {
+        self.feeder_query("observers", &account_id).await
+    }
+
+    /// Returns the ID of the account claiming an alias, if any.
+    pub async fn resolve_alias(&self, alias: &str) -> Result<Option<u64>> {
+        let alias = String::from(alias);
+        Ok(self
+            .node
+            .query(self.contract, "resolve_alias", &alias)
+            .await?)
+    }
+
+    /// Returns the withdrawal awaiting claim under a claim ID, if any.
+    pub async fn withdrawal(
+        &self,
+        claim_id: u64,
+    ) -> Result<Option<PendingWithdrawal>> {
+        Ok(self
+            .node
+            .query(self.contract, "withdrawal", &claim_id)
+            .await?)
+    }
+
+    /// Returns the operations of an account still retained, oldest first.
+    pub async fn operations(
+        &self,
+        account_id: u64,
+    ) -> Result<Vec<OperationRecord>> {
+        Ok(self
+            .node
+            .query(self.contract, "operations", &account_id)
+            .await?)
+    }
+
+    /// Returns the changes to an account between two of its nonces, or `None`
+    /// if either isn't retained.
+    pub async fn state_diff(
+        &self,
+        query: &StateDiffQuery,
+    ) -> Result<Option<StateDiff>> {
+        Ok(self.node.query(self.contract, "state_diff", query).await?)
+    }
+
+    /// Previews a change to an account, taking the keys and co-signers it
+    /// lists as its signers, without their signature being checked.
+    pub async fn simulate_change(
+        &self,
+        change: &ChangeAccount,
+    ) -> Result<ChangeSimulation> {
+        Ok(self
+            .node
+            .query(self.contract, "simulate_change", change)
+            .await?)
+    }
+
+    /// Returns the sequence number of the last event the contract emitted.
+    pub async fn event_sequence(&self) -> Result<u64> {
+        Ok(self
+            .node
+            .query(self.contract, "event_sequence", &())
+            .await?)
+    }
+
+    /// Returns the custody report of the contract.
+    pub async fn custody(&self) -> Result<CustodyReport> {
+        Ok(self.node.query(self.contract, "custody", &()).await?)
+    }
+
+    /// Returns the usage of the contract since it was deployed.
+    pub async fn stats(&self) -> Result<UsageStats> {
+        Ok(self.node.query(self.contract, "stats", &()).await?)
+    }
+
+    /// Returns the governance account of the contract, if any.
+    pub async fn governance(&self) -> Result<Option<u64>> {
+        Ok(self.node.query(self.contract, "governance", &()).await?)
+    }
+
+    /// Returns the contract-wide parameters.
+    pub async fn config(&self) -> Result<ContractConfig> {
+        Ok(self.node.query(self.contract, "config", &()).await?)
+    }
+
+    /// Subscribes to the events the contract emits with the given topics,
+    /// all of them being followed with [`Record::TOPICS`].
+    pub async fn subscribe(&self, topics: &[&str]) -> Result<Subscription> {
+        Subscription::new(&self.url, self.contract, topics).await
+    }
+
+    /// Queries a feeder function of the contract, returning all the items
+    /// it fed.
+    async fn feeder_query<A, R>(
+        &self,
+        fn_name: &str,
+        fn_arg: &A,
+    ) -> Result<Vec<R>>
+    where
+        A: Serialize<AllocSerializer<1024>>,
+        R: Archive,
+        R::Archived: Deserialize<R, SharedDeserializeMap>
+            + for<'b> CheckBytes<DefaultValidator<'b>>,
+    {
+        Ok(self
+            .node
+            .feeder_query(self.contract, fn_name, fn_arg)
+            .await?)
+    }
+}
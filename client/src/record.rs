@@ -0,0 +1,350 @@
+//! Typed records of the events emitted by the contract.
+
+use multisig_contract_types::*;
+use multisig_wallet::decode_event;
+
+/// An event emitted by the contract.
+#[derive(Debug, Clone)]
+pub enum Record {
+    /// An account was created.
+    CreateAccount(CreateAccountEvent),
+    /// An account received a deposit.
+    Deposit(DepositEvent),
+    /// An account transferred funds.
+    Transfer(TransferEvent),
+    /// An account paid a row of a payroll.
+    Payroll(PayrollEvent),
+    /// An account reimbursed the submitter of an operation for its gas.
+    GasRefund(GasRefundEvent),
+    /// An account was changed. Boxed, as it's much larger than the other
+    /// events.
+    ChangeAccount(Box<ChangeAccountEvent>),
+    /// An account was locked by one of its keys.
+    AccountLocked(AccountLockedEvent),
+    /// An account was placed on hold by its compliance key.
+    HoldPlaced(HoldPlacedEvent),
+    /// A key of an account attested it's alive.
+    LivenessAttested(LivenessAttestedEvent),
+    /// A key of an account registered or revoked its delegation.
+    KeyDelegated(KeyDelegatedEvent),
+    /// A guardian acted on an account.
+    GuardianAction(GuardianActionEvent),
+    /// Guardians of an account started its recovery.
+    RecoveryStarted(RecoveryStartedEvent),
+    /// A key of an account cancelled its pending recovery.
+    RecoveryCancelled(RecoveryCancelledEvent),
+    /// The recovery of an account was finalized, replacing its keys.
+    RecoveryFinalized(RecoveryFinalizedEvent),
+    /// The fallback key of an account claimed it while inactive.
+    InactiveClaimed(InactiveClaimedEvent),
+    /// An account queued a transfer.
+    TransferQueued(TransferQueuedEvent),
+    /// A key of an account cancelled a transfer it queued.
+    QueuedCancelled(QueuedCancelledEvent),
+    /// A transfer an account queued was executed.
+    QueuedExecuted(QueuedExecutedEvent),
+    /// A transfer an account queued was due, but the balance fell short of it.
+    QueuedSkipped(QueuedSkippedEvent),
+    /// A transfer an account queued expired unexecuted.
+    QueuedExpired(QueuedExpiredEvent),
+    /// A key of an account transferred out of its allowance.
+    AllowanceSpent(AllowanceSpentEvent),
+    /// An account was merged into another.
+    MergeAccounts(MergeAccountsEvent),
+    /// A new account was split off an account.
+    SplitAccount(SplitAccountEvent),
+    /// A child account drew on the budget its parent allocated to it.
+    BudgetDrawn(BudgetDrawnEvent),
+    /// A parent account clawed back what a child held of its budget.
+    BudgetClawedBack(BudgetClawedBackEvent),
+    /// A checkpoint of an account was recorded.
+    Checkpoint(CheckpointEvent),
+    /// The governance account set the contract-wide parameters.
+    Configure(ConfigureEvent),
+    /// The receiver of a withdrawal claimed it.
+    WithdrawalClaimed(WithdrawalClaimedEvent),
+    /// A payment the transfer contract refused was deferred as a withdrawal.
+    PayoutDeferred(PayoutDeferredEvent),
+    /// An account crossed its velocity limit.
+    VelocityWarning(VelocityWarningEvent),
+    /// A key was marked as compromised.
+    KeyCompromised(KeyCompromisedEvent),
+    /// A co-signer key was marked as compromised.
+    CosignerCompromised(CosignerCompromisedEvent),
+    /// An account template was registered. It's about no account, but is
+    /// still followed to keep the sequence of events whole.
+    RegisterTemplate(RegisterTemplateEvent),
+}
+
+impl Record {
+    /// The topics of the events the contract emits.
+    pub const TOPICS: [&'static str; 33] = [
+        "create_account",
+        "deposit",
+        "transfer",
+        "payroll",
+        "gas_refund",
+        "change_account",
+        "account_locked",
+        "hold_placed",
+        "liveness_attested",
+        "key_delegated",
+        "guardian_action",
+        "recovery_started",
+        "recovery_cancelled",
+        "recovery_finalized",
+        "inactive_claimed",
+        "transfer_queued",
+        "queued_cancelled",
+        "queued_executed",
+        "queued_skipped",
+        "queued_expired",
+        "allowance_spent",
+        "merge_accounts",
+        "split_account",
+        "budget_drawn",
+        "budget_clawed_back",
+        "checkpoint",
+        "configure",
+        "withdrawal_claimed",
+        "payout_deferred",
+        "velocity_warning",
+        "key_compromised",
+        "cosigner_compromised",
+        "register_template",
+    ];
+
+    /// Decodes the data of an event emitted with the given topic, returning
+    /// `None` if the topic is unknown or the data is malformed.
+    pub fn decode(topic: &str, data: &[u8]) -> Option<Self> {
+        let record = match topic {
+            "create_account" => Record::CreateAccount(decode_event(data).ok()?),
+            "deposit" => Record::Deposit(decode_event(data).ok()?),
+            "transfer" => Record::Transfer(decode_event(data).ok()?),
+            "payroll" => Record::Payroll(decode_event(data).ok()?),
+            "gas_refund" => Record::GasRefund(decode_event(data).ok()?),
+            "change_account" => {
+                Record::ChangeAccount(Box::new(decode_event(data).ok()?))
+            }
+            "account_locked" => Record::AccountLocked(decode_event(data).ok()?),
+            "hold_placed" => Record::HoldPlaced(decode_event(data).ok()?),
+            "liveness_attested" => {
+                Record::LivenessAttested(decode_event(data).ok()?)
+            }
+            "key_delegated" => Record::KeyDelegated(decode_event(data).ok()?),
+            "guardian_action" => {
+                Record::GuardianAction(decode_event(data).ok()?)
+            }
+            "recovery_started" => {
+                Record::RecoveryStarted(decode_event(data).ok()?)
+            }
+            "recovery_cancelled" => {
+                Record::RecoveryCancelled(decode_event(data).ok()?)
+            }
+            "recovery_finalized" => {
+                Record::RecoveryFinalized(decode_event(data).ok()?)
+            }
+            "inactive_claimed" => {
+                Record::InactiveClaimed(decode_event(data).ok()?)
+            }
+            "transfer_queued" => {
+                Record::TransferQueued(decode_event(data).ok()?)
+            }
+            "queued_cancelled" => {
+                Record::QueuedCancelled(decode_event(data).ok()?)
+            }
+            "queued_executed" => {
+                Record::QueuedExecuted(decode_event(data).ok()?)
+            }
+            "queued_skipped" => Record::QueuedSkipped(decode_event(data).ok()?),
+            "queued_expired" => Record::QueuedExpired(decode_event(data).ok()?),
+            "allowance_spent" => {
+                Record::AllowanceSpent(decode_event(data).ok()?)
+            }
+            "merge_accounts" => Record::MergeAccounts(decode_event(data).ok()?),
+            "split_account" => Record::SplitAccount(decode_event(data).ok()?),
+            "budget_drawn" => Record::BudgetDrawn(decode_event(data).ok()?),
+            "budget_clawed_back" => {
+                Record::BudgetClawedBack(decode_event(data).ok()?)
+            }
+            "checkpoint" => Record::Checkpoint(decode_event(data).ok()?),
+            "configure" => Record::Configure(decode_event(data).ok()?),
+            "withdrawal_claimed" => {
+                Record::WithdrawalClaimed(decode_event(data).ok()?)
+            }
+            "payout_deferred" => {
+                Record::PayoutDeferred(decode_event(data).ok()?)
+            }
+            "velocity_warning" => {
+                Record::VelocityWarning(decode_event(data).ok()?)
+            }
+            "key_compromised" => {
+                Record::KeyCompromised(decode_event(data).ok()?)
+            }
+            "cosigner_compromised" => {
+                Record::CosignerCompromised(decode_event(data).ok()?)
+            }
+            "register_template" => {
+                Record::RegisterTemplate(decode_event(data).ok()?)
+            }
+            _ => return None,
+        };
+        Some(record)
+    }
+
+    /// The topic the event was emitted with.
+    pub fn topic(&self) -> &'static str {
+        match self {
+            Record::CreateAccount(_) => "create_account",
+            Record::Deposit(_) => "deposit",
+            Record::Transfer(_) => "transfer",
+            Record::Payroll(_) => "payroll",
+            Record::GasRefund(_) => "gas_refund",
+            Record::ChangeAccount(_) => "change_account",
+            Record::AccountLocked(_) => "account_locked",
+            Record::HoldPlaced(_) => "hold_placed",
+            Record::LivenessAttested(_) => "liveness_attested",
+            Record::KeyDelegated(_) => "key_delegated",
+            Record::GuardianAction(_) => "guardian_action",
+            Record::RecoveryStarted(_) => "recovery_started",
+            Record::RecoveryCancelled(_) => "recovery_cancelled",
+            Record::RecoveryFinalized(_) => "recovery_finalized",
+            Record::InactiveClaimed(_) => "inactive_claimed",
+            Record::TransferQueued(_) => "transfer_queued",
+            Record::QueuedCancelled(_) => "queued_cancelled",
+            Record::QueuedExecuted(_) => "queued_executed",
+            Record::QueuedSkipped(_) => "queued_skipped",
+            Record::QueuedExpired(_) => "queued_expired",
+            Record::AllowanceSpent(_) => "allowance_spent",
+            Record::MergeAccounts(_) => "merge_accounts",
+            Record::SplitAccount(_) => "split_account",
+            Record::BudgetDrawn(_) => "budget_drawn",
+            Record::BudgetClawedBack(_) => "budget_clawed_back",
+            Record::Checkpoint(_) => "checkpoint",
+            Record::Configure(_) => "configure",
+            Record::WithdrawalClaimed(_) => "withdrawal_claimed",
+            Record::PayoutDeferred(_) => "payout_deferred",
+            Record::VelocityWarning(_) => "velocity_warning",
+            Record::KeyCompromised(_) => "key_compromised",
+            Record::CosignerCompromised(_) => "cosigner_compromised",
+            Record::RegisterTemplate(_) => "register_template",
+        }
+    }
+
+    /// The IDs of the accounts the event is about.
+    pub fn account_ids(&self) -> Vec<u64> {
+        match self {
+            Record::CreateAccount(e) => vec![e.account_id],
+            Record::Deposit(e) => vec![e.account_id],
+            Record::Transfer(e) => vec![e.account_id],
+            Record::Payroll(e) => vec![e.account_id],
+            Record::GasRefund(e) => vec![e.account_id],
+            Record::ChangeAccount(e) => vec![e.account_id],
+            Record::AccountLocked(e) => vec![e.account_id],
+            Record::HoldPlaced(e) => vec![e.account_id],
+            Record::LivenessAttested(e) => vec![e.account_id],
+            Record::KeyDelegated(e) => vec![e.account_id],
+            Record::GuardianAction(e) => vec![e.account_id],
+            Record::RecoveryStarted(e) => vec![e.account_id],
+            Record::RecoveryCancelled(e) => vec![e.account_id],
+            Record::RecoveryFinalized(e) => vec![e.account_id],
+            Record::InactiveClaimed(e) => vec![e.account_id],
+            Record::TransferQueued(e) => vec![e.account_id],
+            Record::QueuedCancelled(e) => vec![e.account_id],
+            Record::QueuedExecuted(e) => vec![e.account_id],
+            Record::QueuedSkipped(e) => vec![e.account_id],
+            Record::QueuedExpired(e) => vec![e.account_id],
+            Record::AllowanceSpent(e) => vec![e.account_id],
+            Record::MergeAccounts(e) => vec![e.account_id, e.merged_id],
+            Record::SplitAccount(e) => vec![e.account_id, e.new_account_id],
+            Record::BudgetDrawn(e) => vec![e.account_id, e.parent_id],
+            Record::BudgetClawedBack(e) => vec![e.account_id, e.child_id],
+            Record::Checkpoint(e) => vec![e.account_id],
+            Record::Configure(e) => vec![e.account_id],
+            Record::WithdrawalClaimed(e) => vec![e.account_id],
+            Record::PayoutDeferred(e) => vec![e.account_id],
+            Record::VelocityWarning(e) => vec![e.account_id],
+            Record::KeyCompromised(e) => e.account_ids.clone(),
+            Record::CosignerCompromised(e) => e.account_ids.clone(),
+            Record::RegisterTemplate(_) => Vec::new(),
+        }
+    }
+
+    /// The contract-wide sequence number of the event.
+    pub fn sequence(&self) -> u64 {
+        match self {
+            Record::CreateAccount(e) => e.sequence,
+            Record::Deposit(e) => e.sequence,
+            Record::Transfer(e) => e.sequence,
+            Record::Payroll(e) => e.sequence,
+            Record::GasRefund(e) => e.sequence,
+            Record::ChangeAccount(e) => e.sequence,
+            Record::AccountLocked(e) => e.sequence,
+            Record::HoldPlaced(e) => e.sequence,
+            Record::LivenessAttested(e) => e.sequence,
+            Record::KeyDelegated(e) => e.sequence,
+            Record::GuardianAction(e) => e.sequence,
+            Record::RecoveryStarted(e) => e.sequence,
+            Record::RecoveryCancelled(e) => e.sequence,
+            Record::RecoveryFinalized(e) => e.sequence,
+            Record::InactiveClaimed(e) => e.sequence,
+            Record::TransferQueued(e) => e.sequence,
+            Record::QueuedCancelled(e) => e.sequence,
+            Record::QueuedExecuted(e) => e.sequence,
+            Record::QueuedSkipped(e) => e.sequence,
+            Record::QueuedExpired(e) => e.sequence,
+            Record::AllowanceSpent(e) => e.sequence,
+            Record::MergeAccounts(e) => e.sequence,
+            Record::SplitAccount(e) => e.sequence,
+            Record::BudgetDrawn(e) => e.sequence,
+            Record::BudgetClawedBack(e) => e.sequence,
+            Record::Checkpoint(e) => e.checkpoint.sequence,
+            Record::Configure(e) => e.sequence,
+            Record::WithdrawalClaimed(e) => e.sequence,
+            Record::PayoutDeferred(e) => e.sequence,
+            Record::VelocityWarning(e) => e.sequence,
+            Record::KeyCompromised(e) => e.sequence,
+            Record::CosignerCompromised(e) => e.sequence,
+            Record::RegisterTemplate(e) => e.sequence,
+        }
+    }
+
+    /// The deterministic ID of the event, the same whenever it's delivered.
+    pub fn event_id(&self) -> [u8; 32] {
+        match self {
+            Record::CreateAccount(e) => e.event_id,
+            Record::Deposit(e) => e.event_id,
+            Record::Transfer(e) => e.event_id,
+            Record::Payroll(e) => e.event_id,
+            Record::GasRefund(e) => e.event_id,
+            Record::ChangeAccount(e) => e.event_id,
+            Record::AccountLocked(e) => e.event_id,
+            Record::HoldPlaced(e) => e.event_id,
+            Record::LivenessAttested(e) => e.event_id,
+            Record::KeyDelegated(e) => e.event_id,
+            Record::GuardianAction(e) => e.event_id,
+            Record::RecoveryStarted(e) => e.event_id,
+            Record::RecoveryCancelled(e) => e.event_id,
+            Record::RecoveryFinalized(e) => e.event_id,
+            Record::InactiveClaimed(e) => e.event_id,
+            Record::TransferQueued(e) => e.event_id,
+            Record::QueuedCancelled(e) => e.event_id,
+            Record::QueuedExecuted(e) => e.event_id,
+            Record::QueuedSkipped(e) => e.event_id,
+            Record::QueuedExpired(e) => e.event_id,
+            Record::AllowanceSpent(e) => e.event_id,
+            Record::MergeAccounts(e) => e.event_id,
+            Record::SplitAccount(e) => e.event_id,
+            Record::BudgetDrawn(e) => e.event_id,
+            Record::BudgetClawedBack(e) => e.event_id,
+            Record::Checkpoint(e) => e.event_id,
+            Record::Configure(e) => e.event_id,
+            Record::WithdrawalClaimed(e) => e.event_id,
+            Record::PayoutDeferred(e) => e.event_id,
+            Record::VelocityWarning(e) => e.event_id,
+            Record::KeyCompromised(e) => e.event_id,
+            Record::CosignerCompromised(e) => e.event_id,
+            Record::RegisterTemplate(e) => e.event_id,
+        }
+    }
+}
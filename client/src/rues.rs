@@ -0,0 +1,116 @@
+//! Subscription to contract events over the node's RUES interface.
+//!
+//! A session is opened by connecting a websocket to the node, which sends back
+//! the session's ID. Subscriptions to topics are then made over HTTP using the
+//! ID, and events are delivered over the websocket as a length prefixed JSON
+//! header followed by the event's data.
+
+use futures_util::StreamExt;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use execution_core::ContractId;
+
+use multisig_contract_types::event_version;
+
+use crate::record::Record;
+use crate::{Error, Result};
+
+/// A session receiving the events of a contract.
+pub struct Subscription {
+    socket: WebSocketStream<MaybeTlsStream<TcpStream>>,
+}
+
+impl Subscription {
+    /// Opens a session with the node at the given URL, and subscribes to the
+    /// events the given contract emits with each of the given topics.
+    pub(crate) async fn new(
+        node: &str,
+        contract: ContractId,
+        topics: &[&str],
+    ) -> Result<Self> {
+        let ws_url = match node.split_once("://") {
+            Some(("https", rest)) => format!("wss://{rest}/on"),
+            Some((_, rest)) => format!("ws://{rest}/on"),
+            None => format!("ws://{node}/on"),
+        };
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(ws_url).await?;
+
+        let session_id = match socket.next().await {
+            Some(Ok(Message::Text(id))) => id,
+            _ => {
+                return Err(Error::Protocol(
+                    "The node should send the session ID",
+                ))
+            }
+        };
+
+        let client = reqwest::Client::new();
+        let contract = hex::encode(contract.to_bytes());
+
+        for topic in topics {
+            client
+                .get(format!("{node}/on/contracts:{contract}/{topic}"))
+                .header("Rusk-Session-Id", session_id.as_str())
+                .send()
+                .await?
+                .error_for_status()?;
+        }
+
+        Ok(Self { socket })
+    }
+
+    /// Waits for the next event, returning its topic and data, or `None` if
+    /// the node closed the session.
+    pub async fn next(&mut self) -> Result<Option<(String, Vec<u8>)>> {
+        while let Some(message) = self.socket.next().await {
+            match message? {
+                Message::Binary(frame) => {
+                    let event = parse_event(&frame).ok_or(Error::Protocol(
+                        "The node sent a malformed event",
+                    ))?;
+                    return Ok(Some(event));
+                }
+                Message::Close(_) => break,
+                _ => {}
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Waits for the next event, decoding it into its record, or returns
+    /// `None` if the node closed the session.
+    pub async fn next_record(&mut self) -> Result<Option<Record>> {
+        let Some((topic, data)) = self.next().await? else {
+            return Ok(None);
+        };
+
+        match Record::decode(&topic, &data) {
+            Some(record) => Ok(Some(record)),
+            None => Err(Error::UndecodableEvent(topic, event_version(&data))),
+        }
+    }
+}
+
+/// Parses an event frame into the topic of the event and its data.
+fn parse_event(frame: &[u8]) -> Option<(String, Vec<u8>)> {
+    if frame.len() < 4 {
+        return None;
+    }
+    let (len, rest) = frame.split_at(4);
+    let len = u32::from_le_bytes(len.try_into().ok()?) as usize;
+
+    if rest.len() < len {
+        return None;
+    }
+    let (header, data) = rest.split_at(len);
+
+    let header: serde_json::Value = serde_json::from_slice(header).ok()?;
+    let location = header.get("Content-Location")?.as_str()?;
+    let topic = location.rsplit('/').next()?;
+
+    Some((topic.to_string(), data.to_vec()))
+}
@@ -6,9 +6,11 @@ use core::cmp::Ordering;
 
 use alloc::collections::{BTreeMap, BTreeSet};
 use alloc::string::String;
+use alloc::vec;
 use alloc::vec::Vec;
 
 use execution_core::transfer::{ContractToAccount, TRANSFER_CONTRACT};
+use execution_core::ContractId;
 
 use bytecheck::CheckBytes;
 use rkyv::{Archive, Deserialize, Serialize};
@@ -40,34 +42,705 @@ impl Ord for WrappedPublicKey {
 }
 
 /// The state consists of the balance and nonce of each account, together with
-/// each account's keys. It also holds an index of the accounts to which each
-/// key belongs to.
+/// each account's keys and settings. It also holds an index of the accounts to
+/// which each key, alias and tag belongs, and the contract-wide records.
+///
+/// The free functions working on the state take the parts of it they need
+/// rather than the state itself, so that they can be called while an account
+/// is borrowed.
 struct ContractState {
     accounts: BTreeMap<u64, AccountData>,
     account_keys: BTreeMap<u64, BTreeSet<WrappedPublicKey>>,
+    duress_keys: BTreeMap<u64, BTreeSet<WrappedPublicKey>>,
+    cosigners: BTreeMap<u64, BTreeSet<CosignerKey>>,
+    whitelists: BTreeMap<u64, BTreeMap<WrappedPublicKey, u64>>,
+    guardians: BTreeMap<u64, BTreeSet<WrappedPublicKey>>,
+    observers: BTreeMap<u64, BTreeSet<WrappedPublicKey>>,
     key_accounts: BTreeMap<WrappedPublicKey, BTreeSet<u64>>,
+    aliases: BTreeMap<String, u64>,
+    tags: BTreeMap<String, BTreeSet<u64>>,
+    liveness: BTreeMap<u64, BTreeMap<WrappedPublicKey, u64>>,
+    delegations: BTreeMap<u64, BTreeMap<WrappedPublicKey, KeyDelegation>>,
+    pending_keys: BTreeMap<u64, BTreeMap<WrappedPublicKey, u64>>,
+    key_expiries: BTreeMap<u64, BTreeMap<WrappedPublicKey, u64>>,
+    key_usage: BTreeMap<u64, BTreeMap<WrappedPublicKey, (u64, u64)>>,
+    key_weights: BTreeMap<u64, BTreeMap<WrappedPublicKey, u32>>,
+    key_roles: BTreeMap<u64, BTreeMap<WrappedPublicKey, KeyRole>>,
+    transfer_tiers: BTreeMap<u64, Vec<TransferTier>>,
+    pending_recoveries: BTreeMap<u64, PendingRecovery>,
+    queued_transfers: BTreeMap<u64, BTreeMap<u64, QueuedTransfer>>,
+    allowances: BTreeMap<u64, BTreeMap<WrappedPublicKey, u64>>,
+    templates: BTreeMap<u64, AccountTemplate>,
+    compromised_keys: BTreeSet<WrappedPublicKey>,
+    compromised_cosigners: BTreeSet<CosignerKey>,
+    /// The start of each account's current window, and the amount it
+    /// transferred within it. Outflows aren't migrated, so the window a
+    /// migration happens in starts anew.
+    outflows: BTreeMap<u64, (u64, u64)>,
+    /// The start of the current window of each spending category of each
+    /// account, and the amount transferred under it within it. As with
+    /// outflows, spending isn't migrated.
+    category_spending: BTreeMap<u64, BTreeMap<String, (u64, u64)>>,
+    /// The start of the current window of the budget of each child account,
+    /// the amount it drew within it and the amount it holds, by parent.
+    /// Unlike outflows, the amounts held are migrated, so that they can still
+    /// be clawed back.
+    budget_draws: BTreeMap<u64, BTreeMap<u64, (u64, u64, u64)>>,
+    /// The ledger of each account, kept for bookkeeping.
+    movements: BTreeMap<u64, Vec<Movement>>,
+    /// The checkpoints of each account, retained as the ledger is.
+    checkpoints: BTreeMap<u64, Vec<Checkpoint>>,
+    /// The state each operation left each account in, by nonce, retained as
+    /// the ledger is.
+    operations: BTreeMap<u64, Vec<OperationRecord>>,
+    /// The withdrawals awaiting claim, by claim ID. Their amounts are held by
+    /// the contract, but no longer part of any account's balance.
+    withdrawals: BTreeMap<u64, PendingWithdrawal>,
+    withdrawal_count: u64,
+    /// The storage fees paid by accounts for raising their history
+    /// retention, held by the contract but part of no account's balance.
+    storage_fees: u64,
+    total_balance: u64,
+    custody_cap: Option<u64>,
+    min_deposit: Option<u64>,
+    governance: Option<u64>,
+    config: ContractConfig,
+    event_sequence: u64,
+    /// The nonce of each account as of the last event of each topic emitted
+    /// about it, and the number of events of the topic emitted at that nonce,
+    /// from which event IDs are derived. Like outflows, these aren't
+    /// migrated, as the IDs are derived anew from the new contract ID.
+    event_indices: BTreeMap<(u64, &'static str), (u64, u32)>,
+    /// The number of successful calls of each entrypoint changing the
+    /// state, and the number of keys and co-signatures verified by them.
+    /// Like outflows, these aren't migrated, counting the usage of a single
+    /// deployment.
+    calls: BTreeMap<&'static str, u64>,
+    keys_verified: u64,
+    cosignatures_verified: u64,
 }
 
 /// The state starts out all empty.
 static mut STATE: ContractState = ContractState {
     accounts: BTreeMap::new(),
     account_keys: BTreeMap::new(),
+    duress_keys: BTreeMap::new(),
+    cosigners: BTreeMap::new(),
+    whitelists: BTreeMap::new(),
+    guardians: BTreeMap::new(),
+    observers: BTreeMap::new(),
     key_accounts: BTreeMap::new(),
+    aliases: BTreeMap::new(),
+    tags: BTreeMap::new(),
+    liveness: BTreeMap::new(),
+    delegations: BTreeMap::new(),
+    pending_keys: BTreeMap::new(),
+    key_expiries: BTreeMap::new(),
+    key_usage: BTreeMap::new(),
+    key_weights: BTreeMap::new(),
+    key_roles: BTreeMap::new(),
+    transfer_tiers: BTreeMap::new(),
+    pending_recoveries: BTreeMap::new(),
+    queued_transfers: BTreeMap::new(),
+    allowances: BTreeMap::new(),
+    templates: BTreeMap::new(),
+    compromised_keys: BTreeSet::new(),
+    compromised_cosigners: BTreeSet::new(),
+    outflows: BTreeMap::new(),
+    category_spending: BTreeMap::new(),
+    budget_draws: BTreeMap::new(),
+    movements: BTreeMap::new(),
+    checkpoints: BTreeMap::new(),
+    operations: BTreeMap::new(),
+    withdrawals: BTreeMap::new(),
+    withdrawal_count: 0,
+    storage_fees: 0,
+    total_balance: 0,
+    custody_cap: None,
+    min_deposit: None,
+    governance: None,
+    config: ContractConfig::DEFAULT,
+    event_sequence: 0,
+    event_indices: BTreeMap::new(),
+    calls: BTreeMap::new(),
+    keys_verified: 0,
+    cosignatures_verified: 0,
 };
 
+/// Increments the event sequence number, returning the number of the event
+/// about to be emitted.
+fn next_sequence(event_sequence: &mut u64) -> u64 {
+    *event_sequence += 1;
+    *event_sequence
+}
+
+/// Derives the ID of the next event of a topic about an account at its nonce.
+fn next_event_id(
+    event_indices: &mut BTreeMap<(u64, &'static str), (u64, u32)>,
+    topic: &'static str,
+    account_id: u64,
+    nonce: u64,
+) -> [u8; 32] {
+    let entry = event_indices
+        .entry((account_id, topic))
+        .or_insert((nonce, 0));
+    if entry.0 != nonce {
+        *entry = (nonce, 0);
+    }
+    let index = entry.1;
+    entry.1 += 1;
+
+    let contract_id = rusk_abi::self_id().to_bytes();
+    event_id(&contract_id, topic, account_id, nonce, index)
+}
+
+/// Verifies a multisignature, counting its keys towards the keys verified.
+fn verify_multisig(
+    keys_verified: &mut u64,
+    msg: Vec<u8>,
+    keys: Vec<bls::PublicKey>,
+    signature: bls::MultisigSignature,
+) -> bool {
+    *keys_verified += keys.len() as u64;
+    rusk_abi::verify_bls_multisig(msg, keys, signature)
+}
+
+/// Verifies a signature by the group key of an account, counting it towards
+/// the keys verified by the contract.
+fn verify_group(
+    keys_verified: &mut u64,
+    msg: Vec<u8>,
+    group_key: bls::PublicKey,
+    signature: bls::Signature,
+) -> bool {
+    *keys_verified += 1;
+    rusk_abi::verify_bls(msg, group_key, signature)
+}
+
+/// Verifies the signature of a co-signer, counting it towards the
+/// co-signatures verified by the contract.
+fn verify_cosignature(
+    cosignatures_verified: &mut u64,
+    msg: &[u8],
+    cosignature: &Cosignature,
+) -> bool {
+    *cosignatures_verified += 1;
+    cosignature.key.verify(msg, &cosignature.signature)
+}
+
+/// Returns the signers a threshold needs, as lowered by a liveness policy.
+fn required_signers(
+    account: &AccountData,
+    threshold: u32,
+    liveness: Option<&BTreeMap<WrappedPublicKey, u64>>,
+    weights: Option<&BTreeMap<WrappedPublicKey, u32>>,
+    cosigners: usize,
+) -> usize {
+    let threshold = threshold as usize;
+    let (Some(policy), Some(liveness)) = (account.liveness_policy, liveness)
+    else {
+        return threshold;
+    };
+
+    let block_height = rusk_abi::block_height();
+    let live = liveness
+        .iter()
+        .filter(|(_, at)| at.saturating_add(policy.window) >= block_height)
+        .map(|(key, _)| key);
+
+    let min_threshold = threshold.min(policy.min_threshold as usize);
+    threshold
+        .min(weight_of(weights, live, cosigners))
+        .max(min_threshold)
+}
+
+/// Returns the weight of the given keys, plus a number of signers weighing one.
+fn weight_of<'a>(
+    weights: Option<&BTreeMap<WrappedPublicKey, u32>>,
+    keys: impl IntoIterator<Item = &'a WrappedPublicKey>,
+    others: usize,
+) -> usize {
+    keys.into_iter()
+        .map(|key| weights.and_then(|w| w.get(key)).map_or(1, |w| *w as usize))
+        .sum::<usize>()
+        + others
+}
+
+/// Returns the key of an account a signing key signs for: the key itself, if
+/// it's one of the account's keys, or else the key delegating to it, if the
+/// delegation is in effect.
+fn principal_of(
+    account_keys: &BTreeSet<WrappedPublicKey>,
+    delegations: Option<&BTreeMap<WrappedPublicKey, KeyDelegation>>,
+    key: WrappedPublicKey,
+) -> Option<WrappedPublicKey> {
+    if account_keys.contains(&key) {
+        return Some(key);
+    }
+
+    let block_height = rusk_abi::block_height();
+    delegations?.iter().find_map(|(delegator, d)| {
+        let delegation = d.delegation.as_ref()?;
+        let active = WrappedPublicKey(delegation.delegate) == key
+            && delegation.is_active(block_height)
+            && account_keys.contains(delegator);
+        active.then_some(*delegator)
+    })
+}
+
+/// Returns the highest number of signers any operation on an account needs,
+/// transfers falling in its tiers included, below which its signers can't be
+/// brought.
+fn max_required(
+    account: &AccountData,
+    tiers: Option<&Vec<TransferTier>>,
+) -> usize {
+    tiers
+        .into_iter()
+        .flatten()
+        .map(|tier| tier.threshold)
+        .fold(account.max_threshold(), u32::max) as usize
+}
+
+/// Returns whether the signers, or the group key, meet the supermajority.
+fn supermajority_met(
+    account: &AccountData,
+    account_signers: usize,
+    signers: &Signers,
+) -> bool {
+    signers.group_signed
+        || account.supermajority.is_none_or(|supermajority| {
+            let required = supermajority
+                .of(account_signers)
+                .max(account.threshold as usize);
+            signers.weight >= required
+        })
+}
+
+/// Panics if the transfer window is closed and the supermajority isn't met.
+fn check_transfer_window(
+    account: &AccountData,
+    account_signers: usize,
+    signers: &Signers,
+) {
+    if account
+        .transfer_window
+        .is_some_and(|window| !window.contains(rusk_abi::block_height()))
+        && !supermajority_met(account, account_signers, signers)
+    {
+        panic!("{}", Error::OutsideTransferWindow);
+    }
+}
+
+/// Panics if the account holds back transfers of the amount, which must then
+/// be queued.
+fn check_unqueued(account: &AccountData, amount: u64) {
+    if account
+        .queue_policy
+        .is_some_and(|policy| amount >= policy.min_amount)
+    {
+        panic!("{}", Error::TransferQueueRequired);
+    }
+}
+
+/// Counts a transfer against the budget of the spending category it declares.
+fn spend_category(
+    account: &AccountData,
+    spending: &mut BTreeMap<String, (u64, u64)>,
+    name: &str,
+    amount: u64,
+) {
+    let Some(category) = account.categories.iter().find(|c| c.name == name)
+    else {
+        panic!("{}", Error::UnknownCategory);
+    };
+
+    let block_height = rusk_abi::block_height();
+    let window_start = block_height - block_height % category.window;
+
+    let spent = spending
+        .entry(category.name.clone())
+        .or_insert((window_start, 0));
+    if spent.0 != window_start {
+        *spent = (window_start, 0);
+    }
+    match spent.1.checked_add(amount) {
+        Some(total) if total <= category.budget => spent.1 = total,
+        _ => panic!("{}", Error::CategoryBudgetExceeded),
+    }
+}
+
+/// Removes an account from a tag, dropping the tag once no account has it.
+fn release_tag(tags: &mut BTreeMap<String, BTreeSet<u64>>, tag: &str, id: u64) {
+    if let Some(ids) = tags.get_mut(tag) {
+        ids.remove(&id);
+        if ids.is_empty() {
+            tags.remove(tag);
+        }
+    }
+}
+
+/// Returns the amount of a gas refund, panicking if it's over the bound.
+fn gas_refund_amount(refund: Option<&GasRefund>, max: u64) -> u64 {
+    let amount = refund.map_or(0, |refund| refund.amount);
+    if amount > max {
+        panic!("{}", Error::GasRefundTooLarge);
+    }
+    amount
+}
+
+/// Panics if the contract is paused.
+fn check_unpaused(config: &ContractConfig) {
+    if config.paused {
+        panic!("{}", Error::ContractPaused);
+    }
+}
+
+/// Panics if a memo is longer than the contract allows.
+fn check_memo(config: &ContractConfig, memo: &str) {
+    if config
+        .max_memo_len
+        .is_some_and(|max| memo.len() > max as usize)
+    {
+        panic!("{}", Error::MemoTooLong);
+    }
+}
+
+/// Panics if an account would have more keys than the contract allows.
+fn check_key_count(config: &ContractConfig, count: usize) {
+    if config.max_keys.is_some_and(|max| count > max as usize) {
+        panic!("{}", Error::TooManyKeys);
+    }
+}
+
+/// Panics if an operation declares a chain other than the one its account was
+/// created on.
+fn check_chain(account: &AccountData, chain_id: Option<u8>) {
+    if chain_id.is_some_and(|chain_id| chain_id != account.chain_id) {
+        panic!("{}", Error::WrongChain);
+    }
+}
+
+/// Panics unless the receiver is on the whitelist, and already usable.
+fn check_whitelisted(
+    whitelist: &BTreeMap<WrappedPublicKey, u64>,
+    receiver: bls::PublicKey,
+) {
+    match whitelist.get(&WrappedPublicKey(receiver)) {
+        None => panic!("{}", Error::ReceiverNotWhitelisted),
+        Some(&usable_from) if rusk_abi::block_height() < usable_from => {
+            panic!("{}", Error::ReceiverNotYetUsable)
+        }
+        Some(_) => {}
+    }
+}
+
+/// Panics if the account is on hold, unless the funds go to its remediation
+/// address - movements of funds not going to a single receiver, such as
+/// splits, being checked with none.
+fn check_hold(account: &AccountData, receiver: Option<&bls::PublicKey>) {
+    let remediation = account.compliance.as_ref().map(|c| &c.remediation);
+    if account.on_hold && remediation != receiver {
+        panic!("{}", Error::AccountOnHold);
+    }
+}
+
+/// Returns true if the license contract of the policy attests the key holds
+/// a license, a contract failing the query not licensing it.
+fn is_licensed(policy: &LicensePolicy, key: &bls::PublicKey) -> bool {
+    let contract = ContractId::from_bytes(policy.contract);
+    rusk_abi::call(contract, LICENSED_QUERY, key).unwrap_or(false)
+}
+
+/// Panics unless the receiver holds the license the account requires, if it
+/// requires any.
+fn check_licensed(account: &AccountData, receiver: &bls::PublicKey) {
+    if let Some(policy) = &account.license_policy {
+        if !is_licensed(policy, receiver) {
+            panic!("{}", Error::ReceiverNotLicensed);
+        }
+    }
+}
+
+/// Panics if the account restricts its receivers, by a whitelist or a license
+/// policy, which an account its funds are handed to wouldn't be held to.
+fn check_unrestricted(account: &AccountData) {
+    if account.whitelist_enforced {
+        panic!("{}", Error::ReceiverNotWhitelisted);
+    }
+    if account.license_policy.is_some() {
+        panic!("{}", Error::ReceiverNotLicensed);
+    }
+}
+
+/// Panics if the key was added to its account under a delay that hasn't yet
+/// passed.
+fn check_usable(
+    pending_keys: Option<&BTreeMap<WrappedPublicKey, u64>>,
+    key: &WrappedPublicKey,
+) {
+    let usable_from = pending_keys.and_then(|pending| pending.get(key));
+    if usable_from.is_some_and(|&h| rusk_abi::block_height() < h) {
+        panic!("{}", Error::KeyNotYetUsable);
+    }
+}
+
+/// Panics if the key lapsed, its expiry being past.
+fn check_unexpired(
+    key_expiries: Option<&BTreeMap<WrappedPublicKey, u64>>,
+    key: &WrappedPublicKey,
+) {
+    let valid_until = key_expiries.and_then(|expiries| expiries.get(key));
+    if valid_until.is_some_and(|&h| rusk_abi::block_height() > h) {
+        panic!("{}", Error::KeyExpired);
+    }
+}
+
+/// Panics if the role of the key doesn't let it sign operations of the given
+/// kind, keys given no role being admins.
+fn check_role(
+    key_roles: Option<&BTreeMap<WrappedPublicKey, KeyRole>>,
+    key: &WrappedPublicKey,
+    op: OperationKind,
+) {
+    let role = key_roles.and_then(|roles| roles.get(key));
+    if role.is_some_and(|role| !role.can_sign(op)) {
+        panic!("{}", Error::KeyRoleNotAllowed);
+    }
+}
+
+/// Counts an operation towards the usage of each key of the account it was
+/// signed for, at the current height.
+fn record_usage(
+    key_usage: &mut BTreeMap<u64, BTreeMap<WrappedPublicKey, (u64, u64)>>,
+    account_id: u64,
+    principals: &[WrappedPublicKey],
+) {
+    if principals.is_empty() {
+        return;
+    }
+
+    let block_height = rusk_abi::block_height();
+    let usage = key_usage.entry(account_id).or_default();
+    for principal in principals {
+        let (operations, last_signed) = usage.entry(*principal).or_default();
+        *operations += 1;
+        *last_signed = block_height;
+    }
+}
+
+/// Adds a movement to the ledger of an account, dropping its oldest movements
+/// beyond the number the account retains.
+fn record_movement(
+    movements: &mut BTreeMap<u64, Vec<Movement>>,
+    account_id: u64,
+    retention: Option<u32>,
+    movement: Movement,
+) {
+    let ledger = movements.entry(account_id).or_default();
+    ledger.push(movement);
+    retain_history(ledger, retention);
+}
+
+/// Drops the oldest entries of a ledger beyond the number retained.
+fn retain_history<T>(ledger: &mut Vec<T>, retention: Option<u32>) {
+    let retention = retention.unwrap_or(DEFAULT_HISTORY_RETENTION) as usize;
+    ledger.drain(..ledger.len().saturating_sub(retention));
+}
+
+/// The signers of an operation, as checked by `ContractState::authorize`.
+///
+/// The principals are the keys of the account signed for, the signing keys
+/// being either those or their delegates.
+#[derive(Clone)]
+struct Signers {
+    keys: Vec<bls::PublicKey>,
+    principals: Vec<WrappedPublicKey>,
+    cosigners: Vec<CosignerKey>,
+    /// The number of signers the keys and co-signers count for.
+    weight: usize,
+    group_signed: bool,
+    duress: bool,
+}
+
+/// A copy of an account and its keys, as `ContractState::check_changes`
+/// walks changes over it.
+struct AccountDraft {
+    account: AccountData,
+    keys: BTreeSet<WrappedPublicKey>,
+    duress_keys: BTreeSet<WrappedPublicKey>,
+    cosigners: BTreeSet<CosignerKey>,
+    whitelist: BTreeSet<WrappedPublicKey>,
+    guardians: BTreeSet<WrappedPublicKey>,
+    observers: BTreeSet<WrappedPublicKey>,
+    weights: BTreeMap<WrappedPublicKey, u32>,
+    roles: BTreeMap<WrappedPublicKey, KeyRole>,
+    tiers: Vec<TransferTier>,
+}
+
+impl AccountDraft {
+    /// Returns the number of signers the keys and co-signers count for.
+    fn signers(&self) -> usize {
+        weight_of(Some(&self.weights), &self.keys, self.cosigners.len())
+    }
+
+    /// Returns the highest number of signers any operation needs.
+    fn max_required(&self) -> usize {
+        max_required(&self.account, Some(&self.tiers))
+    }
+}
+
+/// A payment out of an account to a Moonlight account, as signed for by a
+/// transfer.
+struct Payment {
+    receiver: bls::PublicKey,
+    amount: u64,
+    memo: String,
+    reference: Option<[u8; 32]>,
+    category: Option<String>,
+}
+
 impl ContractState {
+    /// Counts a call of an entrypoint changing the state. Calls that fail are
+    /// left uncounted, as the count is reverted with the rest of their
+    /// changes.
+    fn count_call(&mut self, entrypoint: &'static str) {
+        *self.calls.entry(entrypoint).or_default() += 1;
+    }
+
+    /// Creates the accounts the contract is deployed with.
+    fn init(&mut self, genesis: Genesis) {
+        for account in genesis.accounts {
+            let account_id = self.create_account(CreateAccount {
+                keys: account.keys,
+                threshold: account.threshold,
+                description: account.description,
+                alias: None,
+                key_weights: Vec::new(),
+                key_roles: Vec::new(),
+            });
+
+            if let Some(data) = self.accounts.get_mut(&account_id) {
+                data.balance = account.balance;
+            }
+            // The account is created holding its genesis balance
+            if let Some(ops) = self.operations.get_mut(&account_id) {
+                ops[0].balance = account.balance;
+            }
+            self.total_balance += account.balance;
+        }
+
+        if genesis
+            .custody_cap
+            .is_some_and(|cap| self.total_balance > cap)
+        {
+            panic!("{}", Error::CustodyCapExceeded);
+        }
+        self.custody_cap = genesis.custody_cap;
+        self.min_deposit = genesis.min_deposit;
+
+        if genesis
+            .governance
+            .is_some_and(|id| !self.accounts.contains_key(&id))
+        {
+            panic!("{}", Error::AccountNotFound);
+        }
+        self.governance = genesis.governance;
+    }
+
     /// Creates an account with the given public keys, returning the new
     /// account's ID.
     fn create_account(&mut self, ca: CreateAccount) -> u64 {
-        if ca.keys.len() < 1 {
-            panic!("There must be at least one key to create an account");
+        self.open_account(ca, None)
+    }
+
+    /// Registers an account template, returning its ID.
+    fn register_template(&mut self, template: AccountTemplate) -> u64 {
+        if template.threshold < 1 {
+            panic!("{}", Error::ZeroThreshold);
         }
-        if ca.threshold < 1 {
-            panic!("Thresold must be at least 1");
+        if template
+            .velocity_limit
+            .is_some_and(|limit| limit.window == 0)
+        {
+            panic!("{}", Error::ZeroVelocityWindow);
+        }
+        let cosigners: BTreeSet<_> = template.cosigners.iter().collect();
+        let guardians: BTreeSet<_> = template
+            .guardians
+            .iter()
+            .map(|key| WrappedPublicKey(*key))
+            .collect();
+        if cosigners.len() != template.cosigners.len()
+            || guardians.len() != template.guardians.len()
+        {
+            panic!("{}", Error::DuplicateKey);
+        }
+
+        let template_id = self
+            .templates
+            .last_key_value()
+            .map_or(0, |(template_id, _)| *template_id)
+            + 1;
+        self.templates.insert(template_id, template.clone());
+
+        rusk_abi::emit(
+            "register_template",
+            EventEnvelope::new(RegisterTemplateEvent {
+                template_id,
+                template,
+                sequence: next_sequence(&mut self.event_sequence),
+                event_id: next_event_id(
+                    &mut self.event_indices,
+                    "register_template",
+                    0,
+                    0,
+                ),
+            }),
+        );
+
+        template_id
+    }
+
+    /// Creates an account with the given public keys under the policy of a
+    /// registered template, returning the new account's ID.
+    fn create_account_from_template(
+        &mut self,
+        c: CreateAccountFromTemplate,
+    ) -> u64 {
+        let Some(template) = self.templates.get(&c.template_id).cloned() else {
+            panic!("{}", Error::TemplateNotFound);
+        };
+        let ca = CreateAccount {
+            keys: c.keys,
+            threshold: template.threshold,
+            description: template.description.clone(),
+            alias: c.alias,
+            key_weights: Vec::new(),
+            key_roles: Vec::new(),
+        };
+        self.open_account(ca, Some((c.template_id, template)))
+    }
+
+    /// Creates an account, under the policy of the given template if any.
+    ///
+    /// The template's co-signers count towards the signers the threshold is
+    /// checked against, as they do once the account is created.
+    fn open_account(
+        &mut self,
+        ca: CreateAccount,
+        template: Option<(u64, AccountTemplate)>,
+    ) -> u64 {
+        let (template_id, template) = template.unzip();
+        let cosigners = template.as_ref().map_or(0, |t| t.cosigners.len());
+
+        if ca.keys.is_empty() {
+            panic!("{}", Error::NoKeys);
         }
-        if ca.threshold as usize > ca.keys.len() {
-            panic!("Cannot use a threshold larger than the number of keys");
+        if ca.threshold < 1 {
+            panic!("{}", Error::ZeroThreshold);
         }
+        check_key_count(&self.config, ca.keys.len());
 
         let account_id = self
             .accounts
@@ -80,15 +753,117 @@ impl ContractState {
         let mut account_keys = BTreeSet::new();
         for key in &ca.keys {
             if !account_keys.insert(WrappedPublicKey(*key)) {
-                panic!("Cannot use duplicate keys to create an account");
+                panic!("{}", Error::DuplicateKey);
+            }
+            if self.compromised_keys.contains(&WrappedPublicKey(*key)) {
+                panic!("{}", Error::KeyCompromised);
             }
 
             self.key_accounts
                 .entry(WrappedPublicKey(*key))
-                .or_insert(BTreeSet::new())
+                .or_default()
                 .insert(account_id);
         }
+
+        // Keys count for one unless given another weight, so weights of one
+        // are dropped rather than recorded
+        let mut key_weights = BTreeMap::new();
+        for kw in &ca.key_weights {
+            let key = WrappedPublicKey(kw.key);
+            if !account_keys.contains(&key) {
+                panic!("{}", Error::UnknownKey);
+            }
+            if kw.weight < 1 {
+                panic!("{}", Error::ZeroWeight);
+            }
+            if key_weights.insert(key, kw.weight).is_some() {
+                panic!("{}", Error::DuplicateKey);
+            }
+        }
+        key_weights.retain(|_, weight| *weight != 1);
+        if ca.threshold as usize
+            > weight_of(Some(&key_weights), &account_keys, cosigners)
+        {
+            panic!("{}", Error::ThresholdTooLarge);
+        }
+
+        // As with weights, only the roles of keys that aren't admins are
+        // recorded
+        let mut key_roles = BTreeMap::new();
+        for assignment in &ca.key_roles {
+            let key = WrappedPublicKey(assignment.key);
+            if !account_keys.contains(&key) {
+                panic!("{}", Error::UnknownKey);
+            }
+            if key_roles.insert(key, assignment.role).is_some() {
+                panic!("{}", Error::DuplicateKey);
+            }
+        }
+        key_roles.retain(|_, role| *role != KeyRole::Admin);
+        let admins = account_keys.iter().filter(|k| !key_roles.contains_key(k));
+        if ca.threshold as usize
+            > weight_of(Some(&key_weights), admins, cosigners)
+        {
+            panic!("{}", Error::AdminsBelowThreshold);
+        }
+
+        // A template's guardians are held to the same rules as guardians
+        // added by a change
+        let mut guardians = BTreeSet::new();
+        let mut cosigners = BTreeSet::new();
+        let mut private_events = false;
+        let mut velocity_limit = None;
+        if let Some(template) = template {
+            for key in template.guardians {
+                let key = WrappedPublicKey(key);
+                if self.compromised_keys.contains(&key) {
+                    panic!("{}", Error::KeyCompromised);
+                }
+                if account_keys.contains(&key) {
+                    panic!("{}", Error::KeyAlreadyUsed);
+                }
+                guardians.insert(key);
+            }
+            cosigners.extend(template.cosigners);
+            private_events = template.private_events;
+            velocity_limit = template.velocity_limit;
+        }
+
+        if let Some(alias) = &ca.alias {
+            if !is_valid_alias(alias) {
+                panic!("{}", Error::InvalidAlias);
+            }
+            if self.aliases.insert(alias.clone(), account_id).is_some() {
+                panic!("{}", Error::AliasTaken);
+            }
+        }
+
         self.account_keys.insert(account_id, account_keys);
+        self.duress_keys.insert(account_id, BTreeSet::new());
+        self.cosigners.insert(account_id, cosigners);
+        self.whitelists.insert(account_id, BTreeMap::new());
+        self.guardians.insert(account_id, guardians);
+        self.observers.insert(account_id, BTreeSet::new());
+        let weighted = key_weights
+            .iter()
+            .map(|(key, weight)| KeyWeight {
+                key: key.0,
+                weight: *weight,
+            })
+            .collect();
+        if !key_weights.is_empty() {
+            self.key_weights.insert(account_id, key_weights);
+        }
+        let roles = key_roles
+            .iter()
+            .map(|(key, role)| RoleAssignment {
+                key: key.0,
+                role: *role,
+            })
+            .collect();
+        if !key_roles.is_empty() {
+            self.key_roles.insert(account_id, key_roles);
+        }
         self.accounts.insert(
             account_id,
             AccountData {
@@ -96,17 +871,59 @@ impl ContractState {
                 threshold: ca.threshold,
                 nonce: 0,
                 description: ca.description.clone(),
+                private_events,
+                frozen: false,
+                group_key: None,
+                whitelist_enforced: false,
+                velocity_limit,
+                locked: false,
+                alias: ca.alias.clone(),
+                merged_into: None,
+                chain_id: rusk_abi::chain_id(),
+                balance_cap: None,
+                tags: Vec::new(),
+                liveness_policy: None,
+                supermajority: None,
+                key_addition_policy: None,
+                transfer_window: None,
+                categories: Vec::new(),
+                child_budgets: Vec::new(),
+                archived: false,
+                claimable_withdrawals: false,
+                history_retention: None,
+                compliance: None,
+                on_hold: false,
+                license_policy: None,
+                operation_thresholds: Vec::new(),
+                recovery_policy: None,
+                recovery_nonce: 0,
+                inactivity_policy: None,
+                last_active: rusk_abi::block_height(),
+                queue_policy: None,
             },
         );
 
+        self.log_operation(account_id, ca.keys.clone(), Vec::new());
+
         rusk_abi::emit(
             "create_account",
-            CreateAccountEvent {
+            EventEnvelope::new(CreateAccountEvent {
                 account_id,
                 keys: ca.keys,
                 threshold: ca.threshold,
                 description: ca.description,
-            },
+                alias: ca.alias,
+                key_weights: weighted,
+                key_roles: roles,
+                template_id,
+                sequence: next_sequence(&mut self.event_sequence),
+                event_id: next_event_id(
+                    &mut self.event_indices,
+                    "create_account",
+                    account_id,
+                    0,
+                ),
+            }),
         );
 
         account_id
@@ -118,225 +935,5684 @@ impl ContractState {
     ///       nothing stops us from including more complex logic, such as an
     ///       identity check.
     fn deposit(&mut self, d: Deposit) {
-        let account = self
-            .accounts
-            .get_mut(&d.account_id)
-            .expect("The account must exist when depositing funds");
+        check_unpaused(&self.config);
+        check_memo(&self.config, &d.memo);
+
+        let Some(account) = self.accounts.get_mut(&d.account_id) else {
+            panic!("{}", Error::AccountNotFound);
+        };
+        if account.merged_into.is_some() {
+            panic!("{}", Error::AccountMerged);
+        }
+        if account.archived {
+            panic!("{}", Error::AccountArchived);
+        }
+        if d.depositor_reference.len() > MAX_DEPOSITOR_REFERENCE_LEN {
+            panic!("{}", Error::DepositorReferenceTooLong);
+        }
+        if self.min_deposit.is_some_and(|min| d.amount < min) {
+            panic!("{}", Error::DepositTooSmall);
+        }
+
+        let total_balance = self.total_balance + d.amount;
+        if self.custody_cap.is_some_and(|cap| total_balance > cap) {
+            panic!("{}", Error::CustodyCapExceeded);
+        }
+        if account
+            .balance_cap
+            .is_some_and(|cap| d.amount > cap.saturating_sub(account.balance))
+        {
+            panic!("{}", Error::BalanceCapExceeded);
+        }
 
-        rusk_abi::call::<_, ()>(TRANSFER_CONTRACT, "deposit", &d.amount)
-            .expect("Retrieving deposit should succeed");
+        if rusk_abi::call::<_, ()>(TRANSFER_CONTRACT, "deposit", &d.amount)
+            .is_err()
+        {
+            panic!("{}", Error::DepositFailed);
+        }
 
         account.balance += d.amount;
+        self.total_balance = total_balance;
+
+        let sequence = next_sequence(&mut self.event_sequence);
+        record_movement(
+            &mut self.movements,
+            d.account_id,
+            account.history_retention,
+            Movement {
+                block_height: rusk_abi::block_height(),
+                sequence,
+                direction: Direction::In,
+                counterparty: Counterparty::Depositor,
+                amount: d.amount,
+                memo_hash: Some(hash_memo(sequence, &d.memo)),
+                balance_after: account.balance,
+            },
+        );
+
+        let (memo, depositor_reference) = match account.private_events {
+            true => (
+                Disclosure::Hashed(hash_memo(sequence, &d.memo)),
+                Disclosure::Hashed(hash_memo(sequence, &d.depositor_reference)),
+            ),
+            false => (
+                Disclosure::Revealed(d.memo),
+                Disclosure::Revealed(d.depositor_reference),
+            ),
+        };
 
         rusk_abi::emit(
             "deposit",
-            DepositEvent {
+            EventEnvelope::new(DepositEvent {
                 account_id: d.account_id,
                 amount: d.amount,
-                memo: d.memo,
-            },
+                memo,
+                depositor_reference,
+                nonce: account.nonce,
+                block_height: rusk_abi::block_height(),
+                balance_after: account.balance,
+                sequence,
+                event_id: next_event_id(
+                    &mut self.event_indices,
+                    "deposit",
+                    d.account_id,
+                    account.nonce,
+                ),
+            }),
         );
     }
 
     /// Transfers an amount from an account to the given Moonlight account.
     fn transfer(&mut self, t: Transfer) {
-        let account = self
-            .accounts
-            .get_mut(&t.account_id)
-            .expect("The account must exist when transferring from it");
+        self.submit_transfer(t, false);
+    }
+
+    /// Queues a transfer from an account, to be executed with
+    /// `execute_queued` once the delay of its queue policy passes.
+    ///
+    /// The transfer is checked and signed for as by `transfer`, using up its
+    /// nonce, but its amount stays with the account until it's executed.
+    fn queue_transfer(&mut self, t: Transfer) {
+        self.submit_transfer(t, true);
+    }
 
-        if t.amount > account.balance {
-            panic!("The account doesn't have enough balance to transfer");
+    /// Makes a transfer, or queues it if `queue` is set.
+    fn submit_transfer(&mut self, t: Transfer, queue: bool) {
+        check_unpaused(&self.config);
+        check_memo(&self.config, &t.memo);
+
+        let Some(account) = self.accounts.get_mut(&t.account_id) else {
+            panic!("{}", Error::AccountNotFound);
+        };
+        if account.merged_into.is_some() {
+            panic!("{}", Error::AccountMerged);
+        }
+
+        if account.frozen {
+            panic!("{}", Error::AccountFrozen);
+        }
+        if account.locked {
+            panic!("{}", Error::AccountLocked);
+        }
+        if account.archived {
+            panic!("{}", Error::AccountArchived);
+        }
+        check_hold(account, Some(&t.receiver));
+        check_licensed(account, &t.receiver);
+        // A transfer of everything takes the balance as of its execution,
+        // less what's left for the gas refund
+        let refund = gas_refund_amount(
+            t.gas_refund.as_ref(),
+            self.config.max_gas_refund,
+        );
+        let Some(available) = account.balance.checked_sub(refund) else {
+            panic!("{}", Error::InsufficientBalance);
+        };
+        let amount = match t.amount {
+            TRANSFER_ALL => available,
+            amount => amount,
+        };
+        if amount > available {
+            panic!("{}", Error::InsufficientBalance);
+        }
+        // Transfers the account holds back must be queued, and only those can
+        // be
+        if !queue {
+            check_unqueued(account, amount);
+        } else if account
+            .queue_policy
+            .is_none_or(|policy| amount < policy.min_amount)
+        {
+            panic!("{}", Error::TransferNotQueueable);
         }
         if t.nonce != account.nonce + 1 {
-            panic!("The nonce must be the current value incremented");
+            panic!("{}", Error::InvalidNonce);
         }
+        check_chain(account, t.chain_id);
+
+        // Accounts with a group key are signed for by it alone, which leaves
+        // no keys or co-signers to check
+        let msg = t.signature_msg();
+        let group_signed = match (account.group_key, t.group_signature) {
+            (Some(group_key), Some(signature)) => {
+                if !t.keys.is_empty() || !t.cosignatures.is_empty() {
+                    panic!("{}", Error::GroupSignatureRequired);
+                }
+                if !verify_group(
+                    &mut self.keys_verified,
+                    msg.clone(),
+                    group_key,
+                    signature,
+                ) {
+                    panic!("{}", Error::InvalidSignature);
+                }
+                true
+            }
+            (None, Some(_)) => panic!("{}", Error::NoGroupKey),
+            (Some(_), None) => panic!("{}", Error::GroupSignatureRequired),
+            (None, None) => false,
+        };
 
         let mut key_set = BTreeSet::new();
+        let mut duress = false;
         let account_keys = self.account_keys.get(&t.account_id).unwrap();
+        let duress_keys = self.duress_keys.get(&t.account_id).unwrap();
+        let cosigners = self.cosigners.get(&t.account_id).unwrap();
 
+        let delegations = self.delegations.get(&t.account_id);
+        let pending_keys = self.pending_keys.get(&t.account_id);
+        let key_expiries = self.key_expiries.get(&t.account_id);
+        let key_roles = self.key_roles.get(&t.account_id);
+        let mut principals = BTreeSet::new();
         for key in &t.keys {
             let key = WrappedPublicKey(*key);
 
             if !key_set.insert(key) {
-                panic!("Cannot use duplicate keys to transfer");
+                panic!("{}", Error::DuplicateKey);
             }
 
-            if !account_keys.contains(&key) {
-                panic!("Signing key must be used by account");
+            if duress_keys.contains(&key) {
+                duress = true;
+                continue;
+            }
+
+            let Some(principal) = principal_of(account_keys, delegations, key)
+            else {
+                panic!("{}", Error::UnknownKey);
+            };
+
+            if self.compromised_keys.contains(&key)
+                || self.compromised_keys.contains(&principal)
+            {
+                panic!("{}", Error::KeyCompromised);
+            }
+            check_usable(pending_keys, &principal);
+            check_unexpired(key_expiries, &principal);
+            check_role(key_roles, &principal, OperationKind::Transfer);
+
+            // A key and its delegate sign for the same key of the account
+            if !principals.insert(principal) {
+                panic!("{}", Error::DuplicateKey);
             }
         }
 
-        if t.keys.len() < account.threshold as usize {
-            panic!("Threshold number of keys not met");
+        let mut cosigner_set = BTreeSet::new();
+        for cosignature in &t.cosignatures {
+            if !cosigner_set.insert(cosignature.key) {
+                panic!("{}", Error::DuplicateKey);
+            }
+            if !cosigners.contains(&cosignature.key) {
+                panic!("{}", Error::UnknownKey);
+            }
+            if self.compromised_cosigners.contains(&cosignature.key) {
+                panic!("{}", Error::KeyCompromised);
+            }
         }
 
-        let msg = t.signature_msg();
-        if !rusk_abi::verify_bls_multisig(msg, t.keys, t.signature) {
-            panic!("The signature should be valid to effect the transfer");
+        // Duress keys count for one, as keys given no weight do, so as not to
+        // stand out
+        let liveness = self.liveness.get(&t.account_id);
+        let weights = self.key_weights.get(&t.account_id);
+        let weight = weight_of(
+            weights,
+            &principals,
+            t.keys.len() - principals.len() + t.cosignatures.len(),
+        );
+        // Transfers falling in a tier are held to its threshold instead
+        let threshold = self
+            .transfer_tiers
+            .get(&t.account_id)
+            .and_then(|tiers| TransferTier::threshold_for(tiers, amount))
+            .unwrap_or(account.threshold_of(OperationKind::Transfer));
+        let required = required_signers(
+            account,
+            threshold,
+            liveness,
+            weights,
+            cosigners.len(),
+        );
+        if !group_signed && weight < required {
+            panic!("{}", Error::ThresholdNotMet);
         }
 
-        // NOTE: Here we simply immediately give the amount to the specified
-        //       Moonlight account, however, it would also be possible - in a
-        //       different type of contract - to keep the funds until a
-        //       withdrawal is made.
-        //       In such a case, it would be possible to withdraw the funds to
-        //       either Moonlight *or* Phoenix.
-        rusk_abi::call::<_, ()>(
-            TRANSFER_CONTRACT,
-            "contract_to_account",
-            &ContractToAccount {
-                account: t.receiver,
-                value: t.amount,
-            },
-        )
-        .expect("Transferring to the given account should succeed");
+        for cosignature in &t.cosignatures {
+            if !verify_cosignature(
+                &mut self.cosignatures_verified,
+                &msg,
+                cosignature,
+            ) {
+                panic!("{}", Error::InvalidSignature);
+            }
+        }
+        // An operation signed only by co-signers carries no BLS signature
+        if !t.keys.is_empty()
+            && !verify_multisig(
+                &mut self.keys_verified,
+                msg,
+                t.keys,
+                t.signature,
+            )
+        {
+            panic!("{}", Error::InvalidSignature);
+        }
 
-        account.balance -= t.amount;
-        account.nonce += 1;
+        // A signature by a duress key freezes the account instead, with the
+        // call succeeding so that the coercer is shown nothing out of order
+        if duress {
+            account.frozen = true;
+            return;
+        }
 
-        rusk_abi::emit(
-            "transfer",
-            TransferEvent {
-                account_id: t.account_id,
-                keys: key_set.into_iter().map(|k| k.0).collect(),
-                receiver: t.receiver,
-                amount: t.amount,
-                memo: t.memo,
-            },
+        let signers = Signers {
+            keys: key_set.into_iter().map(|k| k.0).collect(),
+            principals: principals.into_iter().collect(),
+            cosigners: cosigner_set.into_iter().collect(),
+            weight,
+            group_signed,
+            duress,
+        };
+        record_usage(&mut self.key_usage, t.account_id, &signers.principals);
+        check_transfer_window(
+            account,
+            weight_of(weights, account_keys, cosigners.len()),
+            &signers,
         );
-    }
-
-    fn change_account(&mut self, c: ChangeAccount) {
-        let account = self
-            .accounts
-            .get_mut(&c.account_id)
-            .expect("The account must exist when changing it");
 
-        if c.nonce != account.nonce + 1 {
-            panic!("The nonce must be the current value incremented");
+        if account.whitelist_enforced {
+            let whitelist = self.whitelists.get(&t.account_id).unwrap();
+            check_whitelisted(whitelist, t.receiver);
+        }
+        // A queued transfer counts against its category once it's executed
+        if let Some(category) = t.category.as_ref().filter(|_| !queue) {
+            let spending =
+                self.category_spending.entry(t.account_id).or_default();
+            spend_category(account, spending, category, amount);
         }
 
-        let mut key_set = BTreeSet::new();
-        let account_keys = self.account_keys.get_mut(&c.account_id).unwrap();
+        account.nonce += 1;
+        if queue {
+            let block_height = rusk_abi::block_height();
+            let delay = account.queue_policy.unwrap().delay;
+            let queued = QueuedTransfer {
+                nonce: t.nonce,
+                keys: signers.keys,
+                cosigners: signers.cosigners,
+                group_signed: signers.group_signed,
+                receiver: t.receiver,
+                amount,
+                memo: t.memo,
+                reference: t.reference,
+                category: t.category,
+                executable_from: block_height.saturating_add(delay),
+            };
+            rusk_abi::emit(
+                "transfer_queued",
+                EventEnvelope::new(TransferQueuedEvent {
+                    account_id: t.account_id,
+                    keys: queued.keys.clone(),
+                    cosigners: queued.cosigners.clone(),
+                    group_signed: queued.group_signed,
+                    amount,
+                    executable_from: queued.executable_from,
+                    nonce: t.nonce,
+                    block_height,
+                    sequence: next_sequence(&mut self.event_sequence),
+                    event_id: next_event_id(
+                        &mut self.event_indices,
+                        "transfer_queued",
+                        t.account_id,
+                        t.nonce,
+                    ),
+                }),
+            );
+            self.queued_transfers
+                .entry(t.account_id)
+                .or_default()
+                .insert(t.nonce, queued);
 
-        for key in &c.keys {
-            let key = WrappedPublicKey(*key);
+            self.refund_gas(t.account_id, t.nonce, t.gas_refund);
+            self.log_operation(t.account_id, Vec::new(), Vec::new());
+            return;
+        }
+        let payment = Payment {
+            receiver: t.receiver,
+            amount,
+            memo: t.memo,
+            reference: t.reference,
+            category: t.category,
+        };
+        self.pay_out(t.account_id, payment, t.nonce, signers);
 
-            if !key_set.insert(key) {
-                panic!("Cannot use duplicate keys to transfer");
-            }
+        self.record_outflow(t.account_id, amount);
+        self.refund_gas(t.account_id, t.nonce, t.gas_refund);
+        self.log_operation(t.account_id, Vec::new(), Vec::new());
+    }
 
-            if !account_keys.contains(&key) {
-                panic!("Signing key must be used by account");
-            }
-        }
+    /// Pays an amount out of an account to a Moonlight account, or records it
+    /// as a withdrawal for the receiver to claim if the account has claimable
+    /// withdrawals, emitting the `transfer` event.
+    fn pay_out(
+        &mut self,
+        account_id: u64,
+        payment: Payment,
+        nonce: u64,
+        signers: Signers,
+    ) {
+        let Payment {
+            receiver,
+            amount,
+            memo,
+            reference,
+            category,
+        } = payment;
+        let account = self.accounts.get(&account_id).unwrap();
 
-        if c.keys.len() < account.threshold as usize {
-            panic!("Threshold number of keys not met");
-        }
+        // NOTE: Unless the account has claimable withdrawals, we simply
+        //       immediately give the amount to the specified Moonlight
+        //       account. Otherwise the funds are kept until the receiver
+        //       claims them, which is where it would also be possible to pay
+        //       them out to Phoenix instead.
+        let claim_id = match account.claimable_withdrawals {
+            true => Some(self.record_withdrawal(account_id, receiver, amount)),
+            false => self.pay_or_defer(account_id, receiver, amount),
+        };
 
-        let msg = c.signature_msg();
-        if !rusk_abi::verify_bls_multisig(msg, c.keys, c.signature) {
-            panic!("The signature should be valid to effect the change");
-        }
+        let account = self.accounts.get_mut(&account_id).unwrap();
+        account.balance -= amount;
+        self.total_balance -= amount;
 
+        // The full receiver, memo and reference remain in the signed
+        // transfer, only the event is kept from showing them
+        let sequence = next_sequence(&mut self.event_sequence);
+        let memo_hash = hash_memo(sequence, &memo);
+        let (receiver, memo, reference) = match account.private_events {
+            true => (
+                Disclosure::Hashed(hash_receiver(sequence, &receiver)),
+                Disclosure::Hashed(memo_hash),
+                reference.map(|reference| {
+                    Disclosure::Hashed(hash_reference(sequence, &reference))
+                }),
+            ),
+            false => (
+                Disclosure::Revealed(receiver),
+                Disclosure::Revealed(memo),
+                reference.map(Disclosure::Revealed),
+            ),
+        };
+        record_movement(
+            &mut self.movements,
+            account_id,
+            account.history_retention,
+            Movement {
+                block_height: rusk_abi::block_height(),
+                sequence,
+                direction: Direction::Out,
+                counterparty: Counterparty::Moonlight(receiver.clone()),
+                amount,
+                memo_hash: Some(memo_hash),
+                balance_after: account.balance,
+            },
+        );
+
+        rusk_abi::emit(
+            "transfer",
+            EventEnvelope::new(TransferEvent {
+                account_id,
+                keys: signers.keys,
+                cosigners: signers.cosigners,
+                group_signed: signers.group_signed,
+                receiver,
+                amount,
+                memo,
+                reference,
+                category,
+                claim_id,
+                nonce,
+                block_height: rusk_abi::block_height(),
+                balance_after: account.balance,
+                sequence,
+                event_id: next_event_id(
+                    &mut self.event_indices,
+                    "transfer",
+                    account_id,
+                    nonce,
+                ),
+            }),
+        );
+    }
+
+    /// Records an amount paid out of an account as a withdrawal for the
+    /// receiver to claim, returning its claim ID.
+    fn record_withdrawal(
+        &mut self,
+        account_id: u64,
+        receiver: bls::PublicKey,
+        amount: u64,
+    ) -> u64 {
+        self.withdrawal_count += 1;
+        self.withdrawals.insert(
+            self.withdrawal_count,
+            PendingWithdrawal {
+                claim_id: self.withdrawal_count,
+                account_id,
+                receiver,
+                amount,
+                block_height: rusk_abi::block_height(),
+            },
+        );
+        self.withdrawal_count
+    }
+
+    /// Pays an amount out of an account to a Moonlight account.
+    ///
+    /// Should the transfer contract refuse the payment, the operation isn't
+    /// reverted over it. The amount is recorded as a withdrawal for the
+    /// receiver to claim instead, emitting the `payout_deferred` event right
+    /// before the event of the payment, and its claim ID is returned.
+    fn pay_or_defer(
+        &mut self,
+        account_id: u64,
+        receiver: bls::PublicKey,
+        amount: u64,
+    ) -> Option<u64> {
+        if rusk_abi::call::<_, ()>(
+            TRANSFER_CONTRACT,
+            "contract_to_account",
+            &ContractToAccount {
+                account: receiver,
+                value: amount,
+            },
+        )
+        .is_ok()
+        {
+            return None;
+        }
+
+        let claim_id = self.record_withdrawal(account_id, receiver, amount);
+
+        let account = self.accounts.get(&account_id).unwrap();
+        let sequence = next_sequence(&mut self.event_sequence);
+        let disclosed = match account.private_events {
+            true => Disclosure::Hashed(hash_receiver(sequence, &receiver)),
+            false => Disclosure::Revealed(receiver),
+        };
+
+        rusk_abi::emit(
+            "payout_deferred",
+            EventEnvelope::new(PayoutDeferredEvent {
+                claim_id,
+                account_id,
+                receiver: disclosed,
+                amount,
+                block_height: rusk_abi::block_height(),
+                sequence,
+                event_id: next_event_id(
+                    &mut self.event_indices,
+                    "payout_deferred",
+                    account_id,
+                    account.nonce,
+                ),
+            }),
+        );
+
+        Some(claim_id)
+    }
+
+    /// Adds an amount moved out of an account to its outflow in the current
+    /// window of its velocity limit, if it has one.
+    ///
+    /// Crossing the limit doesn't refuse the outflow, only warns of it.
+    fn record_outflow(&mut self, account_id: u64, amount: u64) {
+        let account = self.accounts.get(&account_id).unwrap();
+        let Some(limit) = account.velocity_limit else {
+            return;
+        };
+
+        let block_height = rusk_abi::block_height();
+        let window_start = block_height - block_height % limit.window;
+
+        let outflow =
+            self.outflows.entry(account_id).or_insert((window_start, 0));
+        if outflow.0 != window_start {
+            *outflow = (window_start, 0);
+        }
+        let previous = outflow.1;
+        outflow.1 = outflow.1.saturating_add(amount);
+
+        if previous <= limit.amount && outflow.1 > limit.amount {
+            rusk_abi::emit(
+                "velocity_warning",
+                EventEnvelope::new(VelocityWarningEvent {
+                    account_id,
+                    limit,
+                    window_start,
+                    outflow: outflow.1,
+                    block_height,
+                    sequence: next_sequence(&mut self.event_sequence),
+                    event_id: next_event_id(
+                        &mut self.event_indices,
+                        "velocity_warning",
+                        account_id,
+                        account.nonce,
+                    ),
+                }),
+            );
+        }
+    }
+
+    fn change_account(&mut self, c: ChangeAccount) {
+        let Some(account) = self.accounts.get_mut(&c.account_id) else {
+            panic!("{}", Error::AccountNotFound);
+        };
+        if account.merged_into.is_some() {
+            panic!("{}", Error::AccountMerged);
+        }
+
+        if c.nonce != account.nonce + 1 {
+            panic!("{}", Error::InvalidNonce);
+        }
+        check_chain(account, c.chain_id);
+        gas_refund_amount(c.gas_refund.as_ref(), self.config.max_gas_refund);
+
+        // A frozen account may still be changed by all its keys, even if it
+        // has a group key
+        let msg = c.signature_msg();
+        let group_signed = match (account.group_key, c.group_signature) {
+            (Some(group_key), Some(signature)) => {
+                if !c.keys.is_empty() || !c.cosignatures.is_empty() {
+                    panic!("{}", Error::GroupSignatureRequired);
+                }
+                if !verify_group(
+                    &mut self.keys_verified,
+                    msg.clone(),
+                    group_key,
+                    signature,
+                ) {
+                    panic!("{}", Error::InvalidSignature);
+                }
+                true
+            }
+            (None, Some(_)) => panic!("{}", Error::NoGroupKey),
+            (Some(_), None) if !account.frozen => {
+                panic!("{}", Error::GroupSignatureRequired)
+            }
+            _ => false,
+        };
+
+        let mut key_set = BTreeSet::new();
+        let mut duress = false;
+        let account_keys = self.account_keys.get(&c.account_id).unwrap();
+        let duress_keys = self.duress_keys.get(&c.account_id).unwrap();
+        let cosigners = self.cosigners.get(&c.account_id).unwrap();
+
+        let delegations = self.delegations.get(&c.account_id);
+        let pending_keys = self.pending_keys.get(&c.account_id);
+        let key_expiries = self.key_expiries.get(&c.account_id);
+        let key_roles = self.key_roles.get(&c.account_id);
+        let mut principals = BTreeSet::new();
+        for key in &c.keys {
+            let key = WrappedPublicKey(*key);
+
+            if !key_set.insert(key) {
+                panic!("{}", Error::DuplicateKey);
+            }
+
+            if duress_keys.contains(&key) {
+                duress = true;
+                continue;
+            }
+
+            let Some(principal) = principal_of(account_keys, delegations, key)
+            else {
+                panic!("{}", Error::UnknownKey);
+            };
+
+            if self.compromised_keys.contains(&key)
+                || self.compromised_keys.contains(&principal)
+            {
+                panic!("{}", Error::KeyCompromised);
+            }
+            check_usable(pending_keys, &principal);
+            check_unexpired(key_expiries, &principal);
+            check_role(key_roles, &principal, OperationKind::ChangeAccount);
+
+            // A key and its delegate sign for the same key of the account
+            if !principals.insert(principal) {
+                panic!("{}", Error::DuplicateKey);
+            }
+        }
+
+        let mut cosigner_set = BTreeSet::new();
+        for cosignature in &c.cosignatures {
+            if !cosigner_set.insert(cosignature.key) {
+                panic!("{}", Error::DuplicateKey);
+            }
+            if !cosigners.contains(&cosignature.key) {
+                panic!("{}", Error::UnknownKey);
+            }
+            if self.compromised_cosigners.contains(&cosignature.key) {
+                panic!("{}", Error::KeyCompromised);
+            }
+        }
+
+        let liveness = self.liveness.get(&c.account_id);
+        let weights = self.key_weights.get(&c.account_id);
+        let weight = weight_of(
+            weights,
+            &principals,
+            c.keys.len() - principals.len() + c.cosignatures.len(),
+        );
+        let required = required_signers(
+            account,
+            account.threshold_of(OperationKind::ChangeAccount),
+            liveness,
+            weights,
+            cosigners.len(),
+        );
+        if !group_signed && weight < required {
+            panic!("{}", Error::ThresholdNotMet);
+        }
+
+        for cosignature in &c.cosignatures {
+            if !verify_cosignature(
+                &mut self.cosignatures_verified,
+                &msg,
+                cosignature,
+            ) {
+                panic!("{}", Error::InvalidSignature);
+            }
+        }
+        if !c.keys.is_empty()
+            && !verify_multisig(
+                &mut self.keys_verified,
+                msg,
+                c.keys,
+                c.signature,
+            )
+        {
+            panic!("{}", Error::InvalidSignature);
+        }
+
+        if duress {
+            account.frozen = true;
+            return;
+        }
+
+        // A frozen account is only changed by all its keys and co-signers
+        // together, which unfreezes it
+        if account.frozen
+            && (group_signed
+                || key_set.len() + cosigner_set.len()
+                    != account_keys.len() + cosigners.len())
+        {
+            panic!("{}", Error::AccountFrozen);
+        }
+        account.frozen = false;
+
+        let signers = Signers {
+            keys: key_set.into_iter().map(|k| k.0).collect(),
+            principals: principals.into_iter().collect(),
+            cosigners: cosigner_set.into_iter().collect(),
+            weight,
+            group_signed,
+            duress,
+        };
+        record_usage(&mut self.key_usage, c.account_id, &signers.principals);
+        let (added_keys, removed_keys) = self.apply_changes(
+            c.account_id,
+            c.nonce,
+            c.changes,
+            signers,
+            false,
+        );
+
+        self.refund_gas(c.account_id, c.nonce, c.gas_refund);
+        self.log_operation(c.account_id, added_keys, removed_keys);
+    }
+
+    /// Checks changes to an account signed for by the given signers, walking
+    /// them over a draft of the account and reporting every rule they break.
+    ///
+    /// Returns the draft of the account as the changes would leave it.
+    fn check_changes(
+        &self,
+        account_id: u64,
+        changes: &[AccountChange],
+        signers: &Signers,
+        violate: &mut impl FnMut(Error),
+    ) -> AccountDraft {
+        let account = self.accounts.get(&account_id).unwrap();
+
+        // Budgets are only allocated to other accounts that exist, and aren't
+        // retired, by accounts leaving their receivers to anyone
+        for change in changes {
+            if let AccountChange::SetChildBudget { budget } = change {
+                if budget.child_id == account_id {
+                    violate(Error::InvalidChildBudget);
+                }
+                match self.accounts.get(&budget.child_id) {
+                    None => violate(Error::AccountNotFound),
+                    Some(child) if child.merged_into.is_some() => {
+                        violate(Error::AccountMerged)
+                    }
+                    Some(_) => {}
+                }
+                if account.whitelist_enforced {
+                    violate(Error::ReceiverNotWhitelisted);
+                }
+                if account.license_policy.is_some() {
+                    violate(Error::ReceiverNotLicensed);
+                }
+
+                // The child draws without the parent's keys, so the budget
+                // is held to the tier of its amount when it's allocated
+                if let Some(threshold) =
+                    self.tier_threshold(account_id, budget.amount)
+                {
+                    let required = required_signers(
+                        account,
+                        threshold,
+                        self.liveness.get(&account_id),
+                        self.key_weights.get(&account_id),
+                        self.cosigners.get(&account_id).unwrap().len(),
+                    );
+                    if !signers.group_signed && signers.weight < required {
+                        violate(Error::ThresholdNotMet);
+                    }
+                }
+            }
+        }
+
+        let mut draft = AccountDraft {
+            account: account.clone(),
+            keys: self.account_keys.get(&account_id).unwrap().clone(),
+            duress_keys: self.duress_keys.get(&account_id).unwrap().clone(),
+            cosigners: self.cosigners.get(&account_id).unwrap().clone(),
+            whitelist: self
+                .whitelists
+                .get(&account_id)
+                .unwrap()
+                .keys()
+                .copied()
+                .collect(),
+            guardians: self.guardians.get(&account_id).unwrap().clone(),
+            observers: self.observers.get(&account_id).unwrap().clone(),
+            weights: self
+                .key_weights
+                .get(&account_id)
+                .cloned()
+                .unwrap_or_default(),
+            roles: self.key_roles.get(&account_id).cloned().unwrap_or_default(),
+            tiers: self
+                .transfer_tiers
+                .get(&account_id)
+                .cloned()
+                .unwrap_or_default(),
+        };
         let mut added_keys = Vec::new();
-        let mut removed_keys = Vec::new();
-        let mut changed_threshold = false;
-        let mut changed_description = false;
+        let mut changed_license_policy = false;
+
+        // The thresholds, the supermajority, the key addition policy and the
+        // transfer window are changed only by a supermajority of the account
+        // as it was signed for, and keys are added under the policy it was
+        // signed for
+        let supermajority_met =
+            supermajority_met(account, draft.signers(), signers);
+        let key_addition_policy = account.key_addition_policy;
 
-        for change in c.changes {
+        for change in changes {
             match change {
                 AccountChange::AddKey { key } => {
-                    let key = WrappedPublicKey(key);
+                    let key = WrappedPublicKey(*key);
 
-                    if !account_keys.insert(key) {
-                        panic!("Key to add already used by account");
+                    if key_addition_policy.is_some_and(|p| p.supermajority)
+                        && !supermajority_met
+                    {
+                        violate(Error::SupermajorityNotMet);
+                    }
+                    if self.compromised_keys.contains(&key) {
+                        violate(Error::KeyCompromised);
+                    }
+                    if draft.duress_keys.contains(&key)
+                        || draft.guardians.contains(&key)
+                        || draft.observers.contains(&key)
+                        || !draft.keys.insert(key)
+                    {
+                        violate(Error::KeyAlreadyUsed);
                     }
-
-                    let key_accounts =
-                        self.key_accounts.entry(key).or_insert(BTreeSet::new());
-
-                    key_accounts.insert(c.account_id);
                     added_keys.push(key.0);
                 }
+                // Since the threshold is at least one, this also ensures that
+                // the account is never left without signers.
                 AccountChange::RemoveKey { key } => {
-                    if account.threshold as usize > account_keys.len() {
-                        panic!("Removing key from account leaves key number below threshold");
+                    let key = WrappedPublicKey(*key);
+
+                    if !draft.keys.remove(&key) {
+                        violate(Error::KeyNotUsed);
+                    }
+                    draft.weights.remove(&key);
+                    draft.roles.remove(&key);
+                    if draft.signers() < draft.max_required() {
+                        violate(Error::KeysBelowThreshold);
+                    }
+                }
+                AccountChange::SetThreshold { threshold } => {
+                    if !supermajority_met {
+                        violate(Error::SupermajorityNotMet);
+                    }
+                    if *threshold < 1 {
+                        violate(Error::ZeroThreshold);
                     }
-                    if account_keys.len() == 1 {
-                        panic!("Removing key from account leaves no keys left");
+                    if *threshold as usize > draft.signers() {
+                        violate(Error::ThresholdTooLarge);
                     }
+                    draft.account.threshold = *threshold;
+                }
+                AccountChange::SetDescription { .. }
+                | AccountChange::SetPrivateEvents { .. }
+                | AccountChange::SetClaimableWithdrawals { .. }
+                | AccountChange::SetBalanceCap { .. } => {}
+                AccountChange::SetHistoryRetention { records } => {
+                    if records.is_some_and(|r| r > MAX_HISTORY_RETENTION) {
+                        violate(Error::HistoryRetentionTooLong);
+                    }
+                    let fee = history_retention_fee(
+                        draft.account.history_retention,
+                        *records,
+                        self.config.history_record_fee,
+                    );
+                    match draft.account.balance.checked_sub(fee) {
+                        Some(balance) => draft.account.balance = balance,
+                        None => violate(Error::InsufficientBalance),
+                    }
+                    draft.account.history_retention = *records;
+                }
+                AccountChange::AddDuressKey { key } => {
+                    let key = WrappedPublicKey(*key);
 
-                    let key = WrappedPublicKey(key);
+                    if self.compromised_keys.contains(&key) {
+                        violate(Error::KeyCompromised);
+                    }
+                    if draft.keys.contains(&key)
+                        || draft.guardians.contains(&key)
+                        || draft.observers.contains(&key)
+                        || !draft.duress_keys.insert(key)
+                    {
+                        violate(Error::KeyAlreadyUsed);
+                    }
+                }
+                AccountChange::RemoveDuressKey { key } => {
+                    if !draft.duress_keys.remove(&WrappedPublicKey(*key)) {
+                        violate(Error::KeyNotUsed);
+                    }
+                }
+                AccountChange::AddCosigner { key } => {
+                    if !supermajority_met {
+                        violate(Error::SupermajorityNotMet);
+                    }
+                    if self.compromised_cosigners.contains(key) {
+                        violate(Error::KeyCompromised);
+                    }
+                    if !draft.cosigners.insert(*key) {
+                        violate(Error::KeyAlreadyUsed);
+                    }
+                }
+                AccountChange::RemoveCosigner { key } => {
+                    if !draft.cosigners.remove(key) {
+                        violate(Error::KeyNotUsed);
+                    }
+                    if draft.signers() < draft.max_required() {
+                        violate(Error::KeysBelowThreshold);
+                    }
+                }
+                AccountChange::SetGroupKey { key } => {
+                    if !supermajority_met {
+                        violate(Error::SupermajorityNotMet);
+                    }
+                    let compromised = key.is_some_and(|key| {
+                        self.compromised_keys.contains(&WrappedPublicKey(key))
+                    });
+                    if compromised {
+                        violate(Error::KeyCompromised);
+                    }
+                    draft.account.group_key = *key;
+                }
+                AccountChange::AddWhitelisted { receiver } => {
+                    if !draft.whitelist.insert(WrappedPublicKey(*receiver)) {
+                        violate(Error::KeyAlreadyUsed);
+                    }
+                    draft.account.whitelist_enforced = true;
+                }
+                // Emptying the whitelist lifts the enforcement with it
+                AccountChange::RemoveWhitelisted { receiver } => {
+                    if !draft.whitelist.remove(&WrappedPublicKey(*receiver)) {
+                        violate(Error::KeyNotUsed);
+                    }
+                    if draft.whitelist.is_empty() {
+                        if !supermajority_met {
+                            violate(Error::SupermajorityNotMet);
+                        }
+                        draft.account.whitelist_enforced = false;
+                    }
+                }
+                AccountChange::SetVelocityLimit { limit } => {
+                    if limit.is_some_and(|limit| limit.window == 0) {
+                        violate(Error::ZeroVelocityWindow);
+                    }
+                }
+                // The compliance control can't be dropped or swapped while
+                // it holds the account
+                AccountChange::SetCompliance { compliance } => {
+                    if !supermajority_met {
+                        violate(Error::SupermajorityNotMet);
+                    }
+                    if draft.account.on_hold {
+                        violate(Error::AccountOnHold);
+                    }
+                    draft.account.compliance = compliance.clone();
+                }
+                // The signature of the compliance control is checked as the
+                // hold is lifted
+                AccountChange::LiftHold { .. } => {
+                    if !supermajority_met {
+                        violate(Error::SupermajorityNotMet);
+                    }
+                    if !draft.account.on_hold {
+                        violate(Error::NotOnHold);
+                    }
+                    draft.account.on_hold = false;
+                }
+                AccountChange::SetLicensePolicy { policy } => {
+                    if !supermajority_met {
+                        violate(Error::SupermajorityNotMet);
+                    }
+                    draft.account.license_policy = *policy;
+                    changed_license_policy = true;
+                }
+                AccountChange::SetOperationThreshold { op, threshold } => {
+                    if !supermajority_met {
+                        violate(Error::SupermajorityNotMet);
+                    }
+                    if let Some(threshold) = threshold {
+                        if *threshold < 1 {
+                            violate(Error::ZeroThreshold);
+                        }
+                        if *threshold as usize > draft.signers() {
+                            violate(Error::ThresholdTooLarge);
+                        }
+                    }
+                    let thresholds = &mut draft.account.operation_thresholds;
+                    let index = thresholds.binary_search_by_key(op, |t| t.op);
+                    match (threshold, index) {
+                        (Some(threshold), Ok(i)) => {
+                            thresholds[i].threshold = *threshold
+                        }
+                        (Some(threshold), Err(i)) => thresholds.insert(
+                            i,
+                            OperationThreshold {
+                                op: *op,
+                                threshold: *threshold,
+                            },
+                        ),
+                        (None, Ok(i)) => {
+                            thresholds.remove(i);
+                        }
+                        (None, Err(_)) => {}
+                    }
+                }
+                AccountChange::SetTransferTiers { tiers } => {
+                    if !supermajority_met {
+                        violate(Error::SupermajorityNotMet);
+                    }
+                    if !are_valid_transfer_tiers(tiers, draft.signers()) {
+                        violate(Error::InvalidTransferTiers);
+                    }
+                    if tiers.iter().any(|tier| tier.threshold < 1) {
+                        violate(Error::ZeroThreshold);
+                    }
+                    draft.tiers = tiers.clone();
+                }
+                AccountChange::Unlock => {
+                    if !draft.account.locked {
+                        violate(Error::NotLocked);
+                    }
+                    draft.account.locked = false;
+                }
+                AccountChange::Archive => {
+                    if draft.account.archived {
+                        violate(Error::AccountArchived);
+                    }
+                    if self.governance == Some(account_id) {
+                        violate(Error::GovernanceAccount);
+                    }
+                    draft.account.archived = true;
+                }
+                AccountChange::Reactivate => {
+                    if !draft.account.archived {
+                        violate(Error::NotArchived);
+                    }
+                    draft.account.archived = false;
+                }
+                // Guardians are kept apart from the keys, so that they can
+                // never sign for the account
+                AccountChange::AddGuardian { key } => {
+                    let key = WrappedPublicKey(*key);
 
-                    if !account_keys.remove(&key) {
-                        panic!("Key to remove not used by account");
+                    if self.compromised_keys.contains(&key) {
+                        violate(Error::KeyCompromised);
+                    }
+                    if draft.keys.contains(&key)
+                        || draft.duress_keys.contains(&key)
+                        || draft.observers.contains(&key)
+                        || !draft.guardians.insert(key)
+                    {
+                        violate(Error::KeyAlreadyUsed);
+                    }
+                }
+                AccountChange::RemoveGuardian { key } => {
+                    if !draft.guardians.remove(&WrappedPublicKey(*key)) {
+                        violate(Error::KeyNotUsed);
                     }
+                }
+                // Observers are kept apart from every key that can sign or act
+                // on the account, so they're never counted toward a threshold
+                AccountChange::AddObserver { key } => {
+                    let key = WrappedPublicKey(*key);
 
-                    let key_accounts = self.key_accounts.get_mut(&key).unwrap();
+                    if self.compromised_keys.contains(&key) {
+                        violate(Error::KeyCompromised);
+                    }
+                    if draft.keys.contains(&key)
+                        || draft.duress_keys.contains(&key)
+                        || draft.guardians.contains(&key)
+                        || !draft.observers.insert(key)
+                    {
+                        violate(Error::KeyAlreadyUsed);
+                    }
+                }
+                AccountChange::RemoveObserver { key } => {
+                    if !draft.observers.remove(&WrappedPublicKey(*key)) {
+                        violate(Error::KeyNotUsed);
+                    }
+                }
+                AccountChange::SetAlias { alias } => {
+                    if let Some(alias) = alias {
+                        if !is_valid_alias(alias) {
+                            violate(Error::InvalidAlias);
+                        }
+                        let claimed_by = self.aliases.get(alias);
+                        if claimed_by.is_some_and(|id| *id != account_id) {
+                            violate(Error::AliasTaken);
+                        }
+                    }
+                }
+                AccountChange::AddTag { tag } => {
+                    if !is_valid_tag(tag) {
+                        violate(Error::InvalidTag);
+                    }
+                    if draft.account.tags.contains(tag) {
+                        violate(Error::TagAlreadyAdded);
+                    } else if draft.account.tags.len() == MAX_TAGS {
+                        violate(Error::TooManyTags);
+                    } else {
+                        draft.account.tags.push(tag.clone());
+                    }
+                }
+                AccountChange::RemoveTag { tag } => {
+                    match draft.account.tags.iter().position(|t| t == tag) {
+                        Some(i) => {
+                            draft.account.tags.remove(i);
+                        }
+                        None => violate(Error::TagNotFound),
+                    }
+                }
+                AccountChange::SetLivenessPolicy { policy } => {
+                    if !supermajority_met {
+                        violate(Error::SupermajorityNotMet);
+                    }
+                    if let Some(policy) = policy {
+                        if policy.window == 0 {
+                            violate(Error::ZeroLivenessWindow);
+                        }
+                        if policy.min_threshold < 1 {
+                            violate(Error::ZeroThreshold);
+                        }
+                    }
+                    draft.account.liveness_policy = *policy;
+                }
+                AccountChange::SetSupermajority { supermajority } => {
+                    if !supermajority_met {
+                        violate(Error::SupermajorityNotMet);
+                    }
+                    if supermajority.is_some_and(|s| !s.is_valid()) {
+                        violate(Error::InvalidSupermajority);
+                    }
+                    draft.account.supermajority = *supermajority;
+                }
+                AccountChange::SetKeyAdditionPolicy { policy } => {
+                    if !supermajority_met {
+                        violate(Error::SupermajorityNotMet);
+                    }
+                    draft.account.key_addition_policy = *policy;
+                }
+                AccountChange::SetRecoveryPolicy { policy } => {
+                    if !supermajority_met {
+                        violate(Error::SupermajorityNotMet);
+                    }
+                    if policy.is_some_and(|p| p.guardians < 1) {
+                        violate(Error::InvalidRecoveryPolicy);
+                    }
+                    draft.account.recovery_policy = *policy;
+                }
+                AccountChange::SetInactivityPolicy { policy } => {
+                    if !supermajority_met {
+                        violate(Error::SupermajorityNotMet);
+                    }
+                    if policy.as_ref().is_some_and(|p| p.period < 1) {
+                        violate(Error::InvalidInactivityPolicy);
+                    }
+                    draft.account.inactivity_policy = policy.clone();
+                }
+                AccountChange::SetQueuePolicy { policy } => {
+                    if !supermajority_met {
+                        violate(Error::SupermajorityNotMet);
+                    }
+                    if policy.is_some_and(|p| p.delay < 1) {
+                        violate(Error::InvalidQueuePolicy);
+                    }
+                    draft.account.queue_policy = *policy;
+                }
+                AccountChange::SetTransferWindow { window } => {
+                    if !supermajority_met {
+                        violate(Error::SupermajorityNotMet);
+                    }
+                    if window.is_some_and(|w| !w.is_valid()) {
+                        violate(Error::InvalidTransferWindow);
+                    }
+                    draft.account.transfer_window = *window;
+                }
+                AccountChange::SetCategory { category } => {
+                    if !category.is_valid() {
+                        violate(Error::InvalidCategory);
+                    }
+                    let categories = &mut draft.account.categories;
+                    if categories.iter().any(|c| c.name == category.name) {
+                        continue;
+                    }
+                    if categories.len() == MAX_CATEGORIES {
+                        violate(Error::TooManyCategories);
+                    } else {
+                        categories.push(category.clone());
+                    }
+                }
+                AccountChange::RemoveCategory { name } => {
+                    let categories = &mut draft.account.categories;
+                    match categories.iter().position(|c| c.name == *name) {
+                        Some(i) => {
+                            categories.remove(i);
+                        }
+                        None => violate(Error::UnknownCategory),
+                    }
+                }
+                AccountChange::SetChildBudget { budget } => {
+                    if !budget.is_valid() {
+                        violate(Error::InvalidChildBudget);
+                    }
+                    let budgets = &mut draft.account.child_budgets;
+                    if budgets.iter().any(|b| b.child_id == budget.child_id) {
+                        continue;
+                    }
+                    if budgets.len() == MAX_CHILD_BUDGETS {
+                        violate(Error::TooManyChildBudgets);
+                    } else {
+                        budgets.push(*budget);
+                    }
+                }
+                AccountChange::RemoveChildBudget { child_id } => {
+                    let budgets = &mut draft.account.child_budgets;
+                    match budgets.iter().position(|b| b.child_id == *child_id) {
+                        Some(i) => {
+                            budgets.remove(i);
+                        }
+                        None => violate(Error::UnknownChildBudget),
+                    }
+                }
+                AccountChange::ClawBackBudget { child_id } => {
+                    let drawn = self
+                        .budget_draws
+                        .get(&account_id)
+                        .is_some_and(|draws| draws.contains_key(child_id));
+                    if !drawn {
+                        violate(Error::UnknownChildBudget);
+                    }
+                }
+                AccountChange::SetKeyExpiry { key, .. }
+                | AccountChange::SetAllowance { key, .. } => {
+                    if !supermajority_met {
+                        violate(Error::SupermajorityNotMet);
+                    }
+                    if !draft.keys.contains(&WrappedPublicKey(*key)) {
+                        violate(Error::KeyNotUsed);
+                    }
+                }
+                AccountChange::SetKeyWeight { key, weight } => {
+                    let key = WrappedPublicKey(*key);
 
-                    key_accounts.remove(&c.account_id);
-                    removed_keys.push(key.0);
+                    if !supermajority_met {
+                        violate(Error::SupermajorityNotMet);
+                    }
+                    if !draft.keys.contains(&key) {
+                        violate(Error::KeyNotUsed);
+                    }
+                    match weight {
+                        0 => violate(Error::ZeroWeight),
+                        1 => {
+                            draft.weights.remove(&key);
+                        }
+                        weight => {
+                            draft.weights.insert(key, *weight);
+                        }
+                    }
+                    if draft.signers() < draft.max_required() {
+                        violate(Error::KeysBelowThreshold);
+                    }
                 }
-                AccountChange::SetThreshold { threshold } => {
-                    if threshold < 1 {
-                        panic!("Threshold must be at least 1");
+                AccountChange::SetKeyRole { key, role } => {
+                    let key = WrappedPublicKey(*key);
+
+                    if !supermajority_met {
+                        violate(Error::SupermajorityNotMet);
                     }
-                    if threshold as usize > account_keys.len() {
-                        panic!(
-                            "Threshold too large for number of keys in account"
-                        );
+                    if !draft.keys.contains(&key) {
+                        violate(Error::KeyNotUsed);
                     }
+                    match role {
+                        KeyRole::Admin => draft.roles.remove(&key),
+                        role => draft.roles.insert(key, *role),
+                    };
+                }
+            }
+        }
+
+        let account = &draft.account;
+        if (account.key_addition_policy.is_some_and(|p| p.supermajority)
+            || account.transfer_window.is_some()
+            || account.compliance.is_some())
+            && account.supermajority.is_none()
+        {
+            violate(Error::NoSupermajority);
+        }
+
+        // The admin keys and co-signers must be able to meet the threshold
+        // for changes between them, so that the account can always be changed
+        if !draft.roles.is_empty() {
+            let admins =
+                draft.keys.iter().filter(|k| !draft.roles.contains_key(k));
+            if weight_of(Some(&draft.weights), admins, draft.cosigners.len())
+                < account.threshold_of(OperationKind::ChangeAccount) as usize
+            {
+                violate(Error::AdminsBelowThreshold);
+            }
+        }
+
+        // Accounts over the bound keep their keys, but can't gain any
+        if !added_keys.is_empty()
+            && self
+                .config
+                .max_keys
+                .is_some_and(|max| draft.keys.len() > max as usize)
+        {
+            violate(Error::TooManyKeys);
+        }
+
+        // Setting a policy licensing keys checks every key the account holds,
+        // and one already set the keys added under it
+        if let Some(policy) = account.license_policy.filter(|p| p.signers) {
+            let unlicensed = if changed_license_policy {
+                draft.keys.iter().any(|key| !is_licensed(&policy, &key.0))
+            } else {
+                added_keys.iter().any(|key| !is_licensed(&policy, key))
+            };
+            if unlicensed {
+                violate(Error::KeyNotLicensed);
+            }
+        }
+
+        draft
+    }
+
+    /// Applies changes to an account signed for by the given signers, using
+    /// up its nonce, and emits the `change_account` event.
+    ///
+    /// The changes are checked with `check_changes` before any is applied.
+    ///
+    /// Returns the keys added to the account and removed from it.
+    fn apply_changes(
+        &mut self,
+        account_id: u64,
+        nonce: u64,
+        changes: Vec<AccountChange>,
+        signers: Signers,
+        with_transfer: bool,
+    ) -> (Vec<bls::PublicKey>, Vec<bls::PublicKey>) {
+        self.check_changes(account_id, &changes, &signers, &mut |error| {
+            panic!("{}", error)
+        });
+
+        let account = self.accounts.get_mut(&account_id).unwrap();
+        let account_keys = self.account_keys.get_mut(&account_id).unwrap();
+        let duress_keys = self.duress_keys.get_mut(&account_id).unwrap();
+        let cosigners = self.cosigners.get_mut(&account_id).unwrap();
+        let whitelist = self.whitelists.get_mut(&account_id).unwrap();
+        let guardians = self.guardians.get_mut(&account_id).unwrap();
+        let observers = self.observers.get_mut(&account_id).unwrap();
+
+        let previous_threshold = account.threshold;
+        let mut added_keys = Vec::new();
+        let mut removed_keys = Vec::new();
+        let mut added_cosigners = Vec::new();
+        let mut removed_cosigners = Vec::new();
+        let mut changed_threshold = false;
+        let mut changed_description = false;
+        let mut changed_private_events = false;
+        let mut changed_group_key = false;
+        let mut whitelisted = Vec::new();
+        let mut unwhitelisted = Vec::new();
+        let mut whitelist_lifted = false;
+        let mut changed_velocity_limit = false;
+        let mut unlocked = false;
+        let mut changed_archived = false;
+        let mut changed_claimable_withdrawals = false;
+        let mut changed_history_retention = false;
+        let mut storage_fee = 0;
+        let mut changed_compliance = false;
+        let mut hold_lifted = false;
+        let mut changed_license_policy = false;
+        let mut set_operation_thresholds = Vec::new();
+        let mut removed_operation_thresholds = Vec::new();
+        let mut changed_transfer_tiers = false;
+        let mut added_guardians = Vec::new();
+        let mut removed_guardians = Vec::new();
+        let mut added_observers = Vec::new();
+        let mut removed_observers = Vec::new();
+        let mut changed_alias = false;
+        let mut changed_balance_cap = false;
+        let mut added_tags = Vec::new();
+        let mut removed_tags = Vec::new();
+        let mut changed_liveness_policy = false;
+        let mut changed_supermajority = false;
+        let mut changed_key_addition_policy = false;
+        let mut changed_transfer_window = false;
+        let mut changed_recovery_policy = false;
+        let mut changed_inactivity_policy = false;
+        let mut changed_queue_policy = false;
+        let mut set_categories = Vec::new();
+        let mut removed_categories = Vec::new();
+        let mut set_child_budgets = Vec::new();
+        let mut removed_child_budgets = Vec::new();
+        let mut set_key_expiries = Vec::new();
+        let mut removed_key_expiries = Vec::new();
+        let mut set_key_weights = Vec::new();
+        let mut set_key_roles = Vec::new();
+        let mut set_allowances = Vec::new();
+        let mut clawbacks = Vec::new();
+
+        // Keys are added under the policy the change was signed for
+        let key_addition_policy = account.key_addition_policy;
+
+        for change in changes {
+            match change {
+                AccountChange::AddKey { key } => {
+                    let key = WrappedPublicKey(key);
+
+                    account_keys.insert(key);
+
+                    let key_accounts =
+                        self.key_accounts.entry(key).or_default();
+
+                    key_accounts.insert(account_id);
+                    added_keys.push(key.0);
+
+                    if let Some(policy) = key_addition_policy {
+                        if policy.delay > 0 {
+                            let usable_from = rusk_abi::block_height()
+                                .saturating_add(policy.delay);
+                            self.pending_keys
+                                .entry(account_id)
+                                .or_default()
+                                .insert(key, usable_from);
+                        }
+                    }
+                }
+                AccountChange::RemoveKey { key } => {
+                    let key = WrappedPublicKey(key);
+
+                    account_keys.remove(&key);
+                    if let Some(weights) = self.key_weights.get_mut(&account_id)
+                    {
+                        weights.remove(&key);
+                    }
+                    if let Some(roles) = self.key_roles.get_mut(&account_id) {
+                        roles.remove(&key);
+                    }
+                    if let Some(allowances) =
+                        self.allowances.get_mut(&account_id)
+                    {
+                        allowances.remove(&key);
+                    }
+
+                    let key_accounts = self.key_accounts.get_mut(&key).unwrap();
+
+                    key_accounts.remove(&account_id);
+                    removed_keys.push(key.0);
+
+                    if let Some(pending) =
+                        self.pending_keys.get_mut(&account_id)
+                    {
+                        pending.remove(&key);
+                    }
+                    if let Some(expiries) =
+                        self.key_expiries.get_mut(&account_id)
+                    {
+                        expiries.remove(&key);
+                    }
+                    if let Some(usage) = self.key_usage.get_mut(&account_id) {
+                        usage.remove(&key);
+                    }
+                }
+                AccountChange::SetThreshold { threshold } => {
+                    account.threshold = threshold;
+                    changed_threshold = true;
+                }
+                AccountChange::SetDescription { description } => {
+                    account.description = description;
+                    changed_description = true;
+                }
+                AccountChange::SetPrivateEvents { private_events } => {
+                    account.private_events = private_events;
+                    changed_private_events = true;
+                }
+                // Withdrawals already recorded remain claimable whatever the
+                // setting
+                AccountChange::SetClaimableWithdrawals {
+                    claimable_withdrawals,
+                } => {
+                    account.claimable_withdrawals = claimable_withdrawals;
+                    changed_claimable_withdrawals = true;
+                }
+                // Only raising the retention is charged for, the fee staying
+                // with the contract
+                AccountChange::SetHistoryRetention { records } => {
+                    let fee = history_retention_fee(
+                        account.history_retention,
+                        records,
+                        self.config.history_record_fee,
+                    );
+                    account.balance -= fee;
+                    self.total_balance -= fee;
+                    self.storage_fees += fee;
+                    storage_fee += fee;
+                    account.history_retention = records;
+                    changed_history_retention = true;
+                }
+                // Duress keys are left out of the event, so as not to point
+                // them out
+                AccountChange::AddDuressKey { key } => {
+                    duress_keys.insert(WrappedPublicKey(key));
+                }
+                AccountChange::RemoveDuressKey { key } => {
+                    duress_keys.remove(&WrappedPublicKey(key));
+                }
+                AccountChange::AddCosigner { key } => {
+                    cosigners.insert(key);
+                    added_cosigners.push(key);
+                }
+                AccountChange::RemoveCosigner { key } => {
+                    cosigners.remove(&key);
+                    removed_cosigners.push(key);
+                }
+                AccountChange::SetGroupKey { key } => {
+                    account.group_key = key;
+                    changed_group_key = true;
+                }
+                AccountChange::AddWhitelisted { receiver } => {
+                    let usable_from =
+                        rusk_abi::block_height() + WHITELIST_DELAY;
+                    whitelist.insert(WrappedPublicKey(receiver), usable_from);
+                    account.whitelist_enforced = true;
+                    whitelisted.push(WhitelistEntry {
+                        receiver,
+                        usable_from,
+                    });
+                }
+                // Removing a receiver only restricts the account further, so
+                // it needs no delay
+                AccountChange::RemoveWhitelisted { receiver } => {
+                    whitelist.remove(&WrappedPublicKey(receiver));
+                    unwhitelisted.push(receiver);
+                    if whitelist.is_empty() {
+                        account.whitelist_enforced = false;
+                        whitelist_lifted = true;
+                    }
+                }
+                // The outflow is counted anew against the new limit
+                AccountChange::SetVelocityLimit { limit } => {
+                    account.velocity_limit = limit;
+                    self.outflows.remove(&account_id);
+                    changed_velocity_limit = true;
+                }
+                // The compliance control can't be dropped or swapped while
+                // it holds the account
+                AccountChange::SetCompliance { compliance } => {
+                    account.compliance = compliance;
+                    changed_compliance = true;
+                }
+                AccountChange::LiftHold { signature } => {
+                    let compliance = account.compliance.as_ref().unwrap();
+                    let msg = PlaceHold::lift_msg(account_id, nonce);
+                    if !verify_multisig(
+                        &mut self.keys_verified,
+                        msg,
+                        vec![compliance.key],
+                        signature,
+                    ) {
+                        panic!("{}", Error::InvalidSignature);
+                    }
+                    account.on_hold = false;
+                    hold_lifted = true;
+                }
+                AccountChange::SetLicensePolicy { policy } => {
+                    account.license_policy = policy;
+                    changed_license_policy = true;
+                }
+                AccountChange::SetOperationThreshold { op, threshold } => {
+                    let thresholds = &mut account.operation_thresholds;
+                    let index = thresholds.binary_search_by_key(&op, |t| t.op);
+                    match threshold {
+                        Some(threshold) => {
+                            let set = OperationThreshold { op, threshold };
+                            match index {
+                                Ok(i) => thresholds[i] = set,
+                                Err(i) => thresholds.insert(i, set),
+                            }
+                            set_operation_thresholds.push(set);
+                        }
+                        None => {
+                            if let Ok(i) = index {
+                                thresholds.remove(i);
+                            }
+                            removed_operation_thresholds.push(op);
+                        }
+                    }
+                }
+                AccountChange::SetTransferTiers { tiers } => {
+                    if tiers.is_empty() {
+                        self.transfer_tiers.remove(&account_id);
+                    } else {
+                        self.transfer_tiers.insert(account_id, tiers);
+                    }
+                    changed_transfer_tiers = true;
+                }
+                AccountChange::Unlock => {
+                    account.locked = false;
+                    unlocked = true;
+                }
+                AccountChange::Archive => {
+                    account.archived = true;
+                    changed_archived = true;
+                }
+                AccountChange::Reactivate => {
+                    account.archived = false;
+                    changed_archived = true;
+                }
+                AccountChange::AddGuardian { key } => {
+                    guardians.insert(WrappedPublicKey(key));
+                    added_guardians.push(key);
+                }
+                AccountChange::RemoveGuardian { key } => {
+                    guardians.remove(&WrappedPublicKey(key));
+                    removed_guardians.push(key);
+                }
+                AccountChange::AddObserver { key } => {
+                    observers.insert(WrappedPublicKey(key));
+                    added_observers.push(key);
+                }
+                AccountChange::RemoveObserver { key } => {
+                    observers.remove(&WrappedPublicKey(key));
+                    removed_observers.push(key);
+                }
+                // The previous alias is released, and can be claimed by any
+                // account from then on
+                AccountChange::SetAlias { alias } => {
+                    if let Some(previous) = account.alias.take() {
+                        self.aliases.remove(&previous);
+                    }
+                    if let Some(alias) = &alias {
+                        self.aliases.insert(alias.clone(), account_id);
+                    }
+                    account.alias = alias;
+                    changed_alias = true;
+                }
+                // A cap below the balance only refuses further deposits, the
+                // balance is left as it is
+                AccountChange::SetBalanceCap { cap } => {
+                    account.balance_cap = cap;
+                    changed_balance_cap = true;
+                }
+                AccountChange::AddTag { tag } => {
+                    self.tags
+                        .entry(tag.clone())
+                        .or_default()
+                        .insert(account_id);
+                    account.tags.push(tag.clone());
+                    added_tags.push(tag);
+                }
+                AccountChange::RemoveTag { tag } => {
+                    account.tags.retain(|t| *t != tag);
+                    release_tag(&mut self.tags, &tag, account_id);
+                    removed_tags.push(tag);
+                }
+                AccountChange::SetLivenessPolicy { policy } => {
+                    account.liveness_policy = policy;
+                    changed_liveness_policy = true;
+                }
+                AccountChange::SetSupermajority { supermajority } => {
+                    account.supermajority = supermajority;
+                    changed_supermajority = true;
+                }
+                AccountChange::SetKeyAdditionPolicy { policy } => {
+                    account.key_addition_policy = policy;
+                    changed_key_addition_policy = true;
+                }
+                AccountChange::SetRecoveryPolicy { policy } => {
+                    account.recovery_policy = policy;
+                    changed_recovery_policy = true;
+                }
+                AccountChange::SetInactivityPolicy { policy } => {
+                    account.inactivity_policy = policy;
+                    changed_inactivity_policy = true;
+                }
+                AccountChange::SetQueuePolicy { policy } => {
+                    account.queue_policy = policy;
+                    changed_queue_policy = true;
+                }
+                AccountChange::SetTransferWindow { window } => {
+                    account.transfer_window = window;
+                    changed_transfer_window = true;
+                }
+                // The spending is counted anew against the new budget
+                AccountChange::SetCategory { category } => {
+                    match account
+                        .categories
+                        .iter_mut()
+                        .find(|c| c.name == category.name)
+                    {
+                        Some(existing) => *existing = category.clone(),
+                        None => account.categories.push(category.clone()),
+                    }
+                    if let Some(spending) =
+                        self.category_spending.get_mut(&account_id)
+                    {
+                        spending.remove(&category.name);
+                    }
+                    set_categories.push(category);
+                }
+                AccountChange::RemoveCategory { name } => {
+                    account.categories.retain(|c| c.name != name);
+                    if let Some(spending) =
+                        self.category_spending.get_mut(&account_id)
+                    {
+                        spending.remove(&name);
+                    }
+                    removed_categories.push(name);
+                }
+                AccountChange::SetChildBudget { budget } => {
+                    match account
+                        .child_budgets
+                        .iter_mut()
+                        .find(|b| b.child_id == budget.child_id)
+                    {
+                        Some(existing) => *existing = budget,
+                        None => account.child_budgets.push(budget),
+                    }
+                    // The draws are counted anew, but the child keeps what it
+                    // holds
+                    if let Some(draws) = self
+                        .budget_draws
+                        .get_mut(&account_id)
+                        .and_then(|draws| draws.get_mut(&budget.child_id))
+                    {
+                        draws.1 = 0;
+                    }
+                    set_child_budgets.push(budget);
+                }
+                AccountChange::RemoveChildBudget { child_id } => {
+                    account.child_budgets.retain(|b| b.child_id != child_id);
+                    removed_child_budgets.push(child_id);
+                }
+                // The funds are moved once the change is applied, since they
+                // belong to another account
+                AccountChange::ClawBackBudget { child_id } => {
+                    let draws = self
+                        .budget_draws
+                        .get_mut(&account_id)
+                        .and_then(|draws| draws.get_mut(&child_id))
+                        .unwrap();
+                    clawbacks.push((child_id, core::mem::take(&mut draws.2)));
+                }
+                AccountChange::SetKeyExpiry { key, valid_until } => {
+                    let wrapped = WrappedPublicKey(key);
+                    let expiries =
+                        self.key_expiries.entry(account_id).or_default();
+                    match valid_until {
+                        Some(valid_until) => {
+                            expiries.insert(wrapped, valid_until);
+                            set_key_expiries
+                                .push(KeyExpiry { key, valid_until });
+                        }
+                        None => {
+                            expiries.remove(&wrapped);
+                            removed_key_expiries.push(key);
+                        }
+                    }
+                }
+                // Keys count for one unless given another weight, so a weight
+                // of one is dropped rather than recorded
+                AccountChange::SetKeyWeight { key, weight } => {
+                    let wrapped = WrappedPublicKey(key);
+                    let weights =
+                        self.key_weights.entry(account_id).or_default();
+                    if weight == 1 {
+                        weights.remove(&wrapped);
+                    } else {
+                        weights.insert(wrapped, weight);
+                    }
+                    set_key_weights.push(KeyWeight { key, weight });
+                }
+                // As with weights, only the roles of keys that aren't admins
+                // are recorded
+                AccountChange::SetKeyRole { key, role } => {
+                    let wrapped = WrappedPublicKey(key);
+                    let roles = self.key_roles.entry(account_id).or_default();
+                    match role {
+                        KeyRole::Admin => roles.remove(&wrapped),
+                        role => roles.insert(wrapped, role),
+                    };
+                    set_key_roles.push(RoleAssignment { key, role });
+                }
+                // As with roles, only the keys with an allowance left are
+                // recorded
+                AccountChange::SetAllowance { key, amount } => {
+                    let wrapped = WrappedPublicKey(key);
+                    let allowances =
+                        self.allowances.entry(account_id).or_default();
+                    match amount {
+                        0 => allowances.remove(&wrapped),
+                        amount => allowances.insert(wrapped, amount),
+                    };
+                    set_allowances.push(KeyAllowance { key, amount });
+                }
+            }
+        }
+
+        // A pending recovery was started under the guardians and recovery
+        // policy as they were, so it's dropped when either changes
+        if changed_recovery_policy
+            || !added_guardians.is_empty()
+            || !removed_guardians.is_empty()
+        {
+            self.pending_recoveries.remove(&account_id);
+        }
+
+        // Keys are live as of the change that set the policy, or added them
+        // under it
+        match account.liveness_policy {
+            Some(_) => {
+                let block_height = rusk_abi::block_height();
+                let liveness = self.liveness.entry(account_id).or_default();
+                liveness.retain(|key, _| account_keys.contains(key));
+                for key in account_keys.iter() {
+                    liveness.entry(*key).or_insert(block_height);
+                }
+            }
+            None => {
+                self.liveness.remove(&account_id);
+            }
+        }
+
+        // Keys removed from the account lose their delegation, but keep their
+        // nonce so that it can't be replayed if they're added back
+        if let Some(delegations) = self.delegations.get_mut(&account_id) {
+            for (key, kd) in delegations.iter_mut() {
+                if !account_keys.contains(key) {
+                    kd.delegation = None;
+                }
+            }
+        }
+
+        // A lowered retention cuts the ledger and checkpoints down at once
+        if changed_history_retention {
+            if let Some(ledger) = self.movements.get_mut(&account_id) {
+                retain_history(ledger, account.history_retention);
+            }
+            if let Some(checkpoints) = self.checkpoints.get_mut(&account_id) {
+                retain_history(checkpoints, account.history_retention);
+            }
+        }
+
+        account.nonce += 1;
+
+        // The storage fee is recorded in the ledger under the change's event
+        let sequence = next_sequence(&mut self.event_sequence);
+        if storage_fee > 0 {
+            record_movement(
+                &mut self.movements,
+                account_id,
+                account.history_retention,
+                Movement {
+                    block_height: rusk_abi::block_height(),
+                    sequence,
+                    direction: Direction::Out,
+                    counterparty: Counterparty::StorageFee,
+                    amount: storage_fee,
+                    memo_hash: None,
+                    balance_after: account.balance,
+                },
+            );
+        }
+
+        let key_changes = (added_keys.clone(), removed_keys.clone());
+        rusk_abi::emit(
+            "change_account",
+            EventEnvelope::new(ChangeAccountEvent {
+                account_id,
+                keys: signers.keys,
+                cosigners: signers.cosigners,
+                group_signed: signers.group_signed,
+                added_keys,
+                removed_keys,
+                added_cosigners,
+                removed_cosigners,
+                previous_threshold,
+                threshold: changed_threshold.then_some(account.threshold),
+                description: changed_description
+                    .then_some(account.description.clone()),
+                private_events: changed_private_events
+                    .then_some(account.private_events),
+                group_key: changed_group_key.then_some(account.group_key),
+                whitelisted,
+                unwhitelisted,
+                whitelist_lifted,
+                velocity_limit: changed_velocity_limit
+                    .then_some(account.velocity_limit),
+                unlocked,
+                archived: changed_archived.then_some(account.archived),
+                claimable_withdrawals: changed_claimable_withdrawals
+                    .then_some(account.claimable_withdrawals),
+                history_retention: changed_history_retention
+                    .then_some(account.history_retention),
+                storage_fee,
+                compliance: changed_compliance
+                    .then(|| account.compliance.clone()),
+                hold_lifted,
+                license_policy: changed_license_policy
+                    .then_some(account.license_policy),
+                set_operation_thresholds,
+                removed_operation_thresholds,
+                transfer_tiers: changed_transfer_tiers.then(|| {
+                    self.transfer_tiers
+                        .get(&account_id)
+                        .cloned()
+                        .unwrap_or_default()
+                }),
+                added_guardians,
+                removed_guardians,
+                added_observers,
+                removed_observers,
+                alias: changed_alias.then(|| account.alias.clone()),
+                balance_cap: changed_balance_cap.then_some(account.balance_cap),
+                added_tags,
+                removed_tags,
+                liveness_policy: changed_liveness_policy
+                    .then_some(account.liveness_policy),
+                supermajority: changed_supermajority
+                    .then_some(account.supermajority),
+                key_addition_policy: changed_key_addition_policy
+                    .then_some(account.key_addition_policy),
+                transfer_window: changed_transfer_window
+                    .then_some(account.transfer_window),
+                recovery_policy: changed_recovery_policy
+                    .then_some(account.recovery_policy),
+                inactivity_policy: changed_inactivity_policy
+                    .then(|| account.inactivity_policy.clone()),
+                queue_policy: changed_queue_policy
+                    .then_some(account.queue_policy),
+                set_categories,
+                removed_categories,
+                set_child_budgets,
+                removed_child_budgets,
+                set_key_expiries,
+                removed_key_expiries,
+                set_key_weights,
+                set_key_roles,
+                set_allowances,
+                with_transfer,
+                nonce,
+                block_height: rusk_abi::block_height(),
+                sequence,
+                event_id: next_event_id(
+                    &mut self.event_indices,
+                    "change_account",
+                    account_id,
+                    nonce,
+                ),
+            }),
+        );
+
+        for (child_id, held) in clawbacks {
+            self.claw_back_budget(account_id, child_id, held, nonce);
+        }
+
+        key_changes
+    }
+
+    /// Moves back to a parent account what a child account held of the
+    /// amount it drew from it, up to the child's balance, and emits the
+    /// `budget_clawed_back` event.
+    fn claw_back_budget(
+        &mut self,
+        account_id: u64,
+        child_id: u64,
+        held: u64,
+        nonce: u64,
+    ) {
+        let child = self.accounts.get_mut(&child_id).unwrap();
+        let amount = held.min(child.balance);
+        child.balance -= amount;
+        let child_balance_after = child.balance;
+        let child_retention = child.history_retention;
+
+        let account = self.accounts.get_mut(&account_id).unwrap();
+        if account
+            .balance_cap
+            .is_some_and(|cap| amount > cap.saturating_sub(account.balance))
+        {
+            panic!("{}", Error::BalanceCapExceeded);
+        }
+        account.balance += amount;
+
+        // Both ledgers record the move, under the claw back's event
+        let sequence = next_sequence(&mut self.event_sequence);
+        let block_height = rusk_abi::block_height();
+        record_movement(
+            &mut self.movements,
+            child_id,
+            child_retention,
+            Movement {
+                block_height,
+                sequence,
+                direction: Direction::Out,
+                counterparty: Counterparty::Account(account_id),
+                amount,
+                memo_hash: None,
+                balance_after: child_balance_after,
+            },
+        );
+        record_movement(
+            &mut self.movements,
+            account_id,
+            account.history_retention,
+            Movement {
+                block_height,
+                sequence,
+                direction: Direction::In,
+                counterparty: Counterparty::Account(child_id),
+                amount,
+                memo_hash: None,
+                balance_after: account.balance,
+            },
+        );
+
+        rusk_abi::emit(
+            "budget_clawed_back",
+            EventEnvelope::new(BudgetClawedBackEvent {
+                account_id,
+                child_id,
+                amount,
+                balance_after: account.balance,
+                child_balance_after,
+                nonce,
+                block_height,
+                sequence,
+                event_id: next_event_id(
+                    &mut self.event_indices,
+                    "budget_clawed_back",
+                    account_id,
+                    nonce,
+                ),
+            }),
+        );
+    }
+
+    /// Locks the outgoing transfers of an account, on the signature of any
+    /// single one of its keys.
+    ///
+    /// Locking leaves the nonce untouched, and only a change signed as usual
+    /// unlocks the account.
+    fn lock_account(&mut self, l: LockAccount) {
+        let Some(account) = self.accounts.get_mut(&l.account_id) else {
+            panic!("{}", Error::AccountNotFound);
+        };
+        if account.merged_into.is_some() {
+            panic!("{}", Error::AccountMerged);
+        }
+
+        let key = WrappedPublicKey(l.key);
+        if !self.account_keys.get(&l.account_id).unwrap().contains(&key) {
+            panic!("{}", Error::UnknownKey);
+        }
+        if self.compromised_keys.contains(&key) {
+            panic!("{}", Error::KeyCompromised);
+        }
+        check_usable(self.pending_keys.get(&l.account_id), &key);
+        check_unexpired(self.key_expiries.get(&l.account_id), &key);
+        if account.locked {
+            panic!("{}", Error::AccountLocked);
+        }
+
+        let msg = LockAccount::signature_msg(l.account_id, account.nonce);
+        if !verify_multisig(
+            &mut self.keys_verified,
+            msg,
+            vec![l.key],
+            l.signature,
+        ) {
+            panic!("{}", Error::InvalidSignature);
+        }
+
+        account.locked = true;
+
+        rusk_abi::emit(
+            "account_locked",
+            EventEnvelope::new(AccountLockedEvent {
+                account_id: l.account_id,
+                key: l.key,
+                nonce: account.nonce,
+                block_height: rusk_abi::block_height(),
+                sequence: next_sequence(&mut self.event_sequence),
+                event_id: next_event_id(
+                    &mut self.event_indices,
+                    "account_locked",
+                    l.account_id,
+                    account.nonce,
+                ),
+            }),
+        );
+    }
+
+    /// Places an account on hold on the signature of its compliance key,
+    /// restricting its transfers to its remediation address until a change
+    /// signed by its supermajority and the compliance key lifts it.
+    ///
+    /// As with locking, the nonce is left untouched.
+    fn place_hold(&mut self, h: PlaceHold) {
+        let Some(account) = self.accounts.get_mut(&h.account_id) else {
+            panic!("{}", Error::AccountNotFound);
+        };
+        if account.merged_into.is_some() {
+            panic!("{}", Error::AccountMerged);
+        }
+        let Some(compliance) = account.compliance.clone() else {
+            panic!("{}", Error::NoComplianceControl);
+        };
+        if self
+            .compromised_keys
+            .contains(&WrappedPublicKey(compliance.key))
+        {
+            panic!("{}", Error::KeyCompromised);
+        }
+        if account.on_hold {
+            panic!("{}", Error::AccountOnHold);
+        }
+
+        let msg = PlaceHold::signature_msg(h.account_id, account.nonce);
+        if !verify_multisig(
+            &mut self.keys_verified,
+            msg,
+            vec![compliance.key],
+            h.signature,
+        ) {
+            panic!("{}", Error::InvalidSignature);
+        }
+
+        account.on_hold = true;
+
+        rusk_abi::emit(
+            "hold_placed",
+            EventEnvelope::new(HoldPlacedEvent {
+                account_id: h.account_id,
+                key: compliance.key,
+                remediation: compliance.remediation,
+                nonce: account.nonce,
+                block_height: rusk_abi::block_height(),
+                sequence: next_sequence(&mut self.event_sequence),
+                event_id: next_event_id(
+                    &mut self.event_indices,
+                    "hold_placed",
+                    h.account_id,
+                    account.nonce,
+                ),
+            }),
+        );
+    }
+
+    /// Records a key of an account with a liveness policy attesting it's alive,
+    /// keeping it counted among the account's signers for another window.
+    ///
+    /// As with locking, the nonce is left untouched, and the attestation can
+    /// be made whatever the state of the account.
+    fn attest_liveness(&mut self, a: AttestLiveness) {
+        let Some(account) = self.accounts.get(&a.account_id) else {
+            panic!("{}", Error::AccountNotFound);
+        };
+        if account.merged_into.is_some() {
+            panic!("{}", Error::AccountMerged);
+        }
+        if account.liveness_policy.is_none() {
+            panic!("{}", Error::NoLivenessPolicy);
+        }
+
+        let key = WrappedPublicKey(a.key);
+        if !self.account_keys.get(&a.account_id).unwrap().contains(&key) {
+            panic!("{}", Error::UnknownKey);
+        }
+        if self.compromised_keys.contains(&key) {
+            panic!("{}", Error::KeyCompromised);
+        }
+        let block_height = rusk_abi::block_height();
+        if a.block_height > block_height {
+            panic!("{}", Error::AttestationInFuture);
+        }
+
+        let msg = AttestLiveness::signature_msg(a.account_id, a.block_height);
+        if !verify_multisig(
+            &mut self.keys_verified,
+            msg,
+            vec![a.key],
+            a.signature,
+        ) {
+            panic!("{}", Error::InvalidSignature);
+        }
+
+        // An older attestation, replayed or late, never sets the key back
+        let liveness = self.liveness.entry(a.account_id).or_default();
+        let attested_at = liveness.entry(key).or_default();
+        *attested_at = a.block_height.max(*attested_at);
+
+        rusk_abi::emit(
+            "liveness_attested",
+            EventEnvelope::new(LivenessAttestedEvent {
+                account_id: a.account_id,
+                key: a.key,
+                attested_at: *attested_at,
+                block_height,
+                sequence: next_sequence(&mut self.event_sequence),
+                event_id: next_event_id(
+                    &mut self.event_indices,
+                    "liveness_attested",
+                    a.account_id,
+                    account.nonce,
+                ),
+            }),
+        );
+    }
+
+    /// Registers, or revokes, the delegation of a key of an account, on the
+    /// signature of the key alone.
+    ///
+    /// The delegation replaces the key's current one. The account's nonce is
+    /// left untouched, each key keeping a nonce of its own.
+    fn delegate_key(&mut self, d: DelegateKey) {
+        let Some(account) = self.accounts.get(&d.account_id) else {
+            panic!("{}", Error::AccountNotFound);
+        };
+        if account.merged_into.is_some() {
+            panic!("{}", Error::AccountMerged);
+        }
+
+        let key = WrappedPublicKey(d.key);
+        let account_keys = self.account_keys.get(&d.account_id).unwrap();
+        if !account_keys.contains(&key) {
+            panic!("{}", Error::UnknownKey);
+        }
+        if self.compromised_keys.contains(&key) {
+            panic!("{}", Error::KeyCompromised);
+        }
+
+        let delegations = self.delegations.entry(d.account_id).or_default();
+        let nonce = delegations.get(&key).map_or(0, |kd| kd.nonce);
+        if d.nonce != nonce + 1 {
+            panic!("{}", Error::InvalidNonce);
+        }
+
+        let block_height = rusk_abi::block_height();
+        if let Some(delegation) = &d.delegation {
+            if delegation.from_height > delegation.until_height {
+                panic!("{}", Error::InvalidDelegationRange);
+            }
+
+            // The delegate can't already sign for the account in any other
+            // way, nor be in effect for another of its keys
+            let delegate = WrappedPublicKey(delegation.delegate);
+            if self.compromised_keys.contains(&delegate) {
+                panic!("{}", Error::KeyCompromised);
+            }
+            let duress_keys = self.duress_keys.get(&d.account_id).unwrap();
+            let guardians = self.guardians.get(&d.account_id).unwrap();
+            let observers = self.observers.get(&d.account_id).unwrap();
+            let delegated = delegations.iter().any(|(other, kd)| {
+                *other != key
+                    && kd.delegation.as_ref().is_some_and(|other| {
+                        other.delegate == delegation.delegate
+                            && other.until_height >= block_height
+                    })
+            });
+            if account_keys.contains(&delegate)
+                || duress_keys.contains(&delegate)
+                || guardians.contains(&delegate)
+                || observers.contains(&delegate)
+                || delegated
+            {
+                panic!("{}", Error::KeyAlreadyUsed);
+            }
+        }
+
+        let msg = DelegateKey::signature_msg(
+            d.account_id,
+            d.delegation.as_ref(),
+            d.nonce,
+        );
+        if !verify_multisig(
+            &mut self.keys_verified,
+            msg,
+            vec![d.key],
+            d.signature,
+        ) {
+            panic!("{}", Error::InvalidSignature);
+        }
+
+        delegations.insert(
+            key,
+            KeyDelegation {
+                key: d.key,
+                delegation: d.delegation.clone(),
+                nonce: d.nonce,
+            },
+        );
+
+        rusk_abi::emit(
+            "key_delegated",
+            EventEnvelope::new(KeyDelegatedEvent {
+                account_id: d.account_id,
+                key: d.key,
+                delegation: d.delegation,
+                nonce: d.nonce,
+                block_height,
+                sequence: next_sequence(&mut self.event_sequence),
+                event_id: next_event_id(
+                    &mut self.event_indices,
+                    "key_delegated",
+                    d.account_id,
+                    d.nonce,
+                ),
+            }),
+        );
+    }
+
+    /// Takes an action on an account, on the signature of any single one of
+    /// its guardians.
+    ///
+    /// Guardians can lock the account, and hold back receivers whitelisted by
+    /// it while they're still pending, but can't move its funds. As with
+    /// locking, the nonce is left untouched.
+    fn guard_account(&mut self, g: GuardAccount) {
+        let Some(account) = self.accounts.get_mut(&g.account_id) else {
+            panic!("{}", Error::AccountNotFound);
+        };
+        if account.merged_into.is_some() {
+            panic!("{}", Error::AccountMerged);
+        }
+
+        let key = WrappedPublicKey(g.key);
+        if !self.guardians.get(&g.account_id).unwrap().contains(&key) {
+            panic!("{}", Error::NotGuardian);
+        }
+        if self.compromised_keys.contains(&key) {
+            panic!("{}", Error::KeyCompromised);
+        }
+
+        let msg =
+            GuardAccount::signature_msg(g.account_id, &g.action, account.nonce);
+        if !verify_multisig(
+            &mut self.keys_verified,
+            msg,
+            vec![g.key],
+            g.signature,
+        ) {
+            panic!("{}", Error::InvalidSignature);
+        }
+
+        let block_height = rusk_abi::block_height();
+        let whitelist = self.whitelists.get_mut(&g.account_id).unwrap();
+
+        match &g.action {
+            GuardianAction::Lock => {
+                if account.locked {
+                    panic!("{}", Error::AccountLocked);
+                }
+                account.locked = true;
+            }
+            GuardianAction::ExtendWhitelistDelay {
+                receiver,
+                usable_from,
+            } => {
+                let Some(current) =
+                    whitelist.get_mut(&WrappedPublicKey(*receiver))
+                else {
+                    panic!("{}", Error::KeyNotUsed);
+                };
+                if *current <= block_height {
+                    panic!("{}", Error::ReceiverAlreadyUsable);
+                }
+                if *usable_from <= *current {
+                    panic!("{}", Error::DelayNotExtended);
+                }
+                *current = *usable_from;
+            }
+            GuardianAction::VetoWhitelisted { receiver } => {
+                let receiver = WrappedPublicKey(*receiver);
+                let Some(&usable_from) = whitelist.get(&receiver) else {
+                    panic!("{}", Error::KeyNotUsed);
+                };
+                if usable_from <= block_height {
+                    panic!("{}", Error::ReceiverAlreadyUsable);
+                }
+                whitelist.remove(&receiver);
+            }
+        }
+
+        rusk_abi::emit(
+            "guardian_action",
+            EventEnvelope::new(GuardianActionEvent {
+                account_id: g.account_id,
+                key: g.key,
+                action: g.action,
+                nonce: account.nonce,
+                block_height,
+                sequence: next_sequence(&mut self.event_sequence),
+                event_id: next_event_id(
+                    &mut self.event_indices,
+                    "guardian_action",
+                    g.account_id,
+                    account.nonce,
+                ),
+            }),
+        );
+    }
+
+    /// Starts recovering an account on the signatures of enough of its
+    /// guardians, replacing its keys and threshold once the delay of its
+    /// recovery policy passes, unless a key of the account cancels it first.
+    ///
+    /// The recovery uses up the recovery nonce of the account, leaving the
+    /// nonce its keys sign with untouched.
+    fn start_recovery(&mut self, sr: StartRecovery) {
+        let Some(account) = self.accounts.get(&sr.account_id) else {
+            panic!("{}", Error::AccountNotFound);
+        };
+        if account.merged_into.is_some() {
+            panic!("{}", Error::AccountMerged);
+        }
+        if sr.nonce != account.recovery_nonce + 1 {
+            panic!("{}", Error::InvalidNonce);
+        }
+        let Some(policy) = account.recovery_policy else {
+            panic!("{}", Error::NoRecoveryPolicy);
+        };
+        if self.pending_recoveries.contains_key(&sr.account_id) {
+            panic!("{}", Error::RecoveryPending);
+        }
+
+        let guardians = self.guardians.get(&sr.account_id).unwrap();
+        let mut signers = BTreeSet::new();
+        for key in &sr.guardians {
+            let key = WrappedPublicKey(*key);
+            if !guardians.contains(&key) {
+                panic!("{}", Error::NotGuardian);
+            }
+            if self.compromised_keys.contains(&key) {
+                panic!("{}", Error::KeyCompromised);
+            }
+            if !signers.insert(key) {
+                panic!("{}", Error::DuplicateKey);
+            }
+        }
+        if signers.len() < policy.guardians as usize {
+            panic!("{}", Error::ThresholdNotMet);
+        }
+        self.check_replacement(sr.account_id, &sr.keys, sr.threshold);
+
+        let msg = sr.signature_msg();
+        if !verify_multisig(
+            &mut self.keys_verified,
+            msg,
+            sr.guardians.clone(),
+            sr.signature,
+        ) {
+            panic!("{}", Error::InvalidSignature);
+        }
+
+        let block_height = rusk_abi::block_height();
+        let recovery = PendingRecovery {
+            guardians: sr.guardians,
+            keys: sr.keys,
+            threshold: sr.threshold,
+            executable_from: block_height.saturating_add(policy.delay),
+        };
+        self.pending_recoveries
+            .insert(sr.account_id, recovery.clone());
+
+        let account = self.accounts.get_mut(&sr.account_id).unwrap();
+        account.recovery_nonce += 1;
+        account.last_active = block_height;
+
+        rusk_abi::emit(
+            "recovery_started",
+            EventEnvelope::new(RecoveryStartedEvent {
+                account_id: sr.account_id,
+                recovery,
+                nonce: sr.nonce,
+                block_height,
+                sequence: next_sequence(&mut self.event_sequence),
+                event_id: next_event_id(
+                    &mut self.event_indices,
+                    "recovery_started",
+                    sr.account_id,
+                    sr.nonce,
+                ),
+            }),
+        );
+    }
+
+    /// Cancels the recovery of an account on the signature of any single one
+    /// of its keys, whose holders evidently haven't lost access to it.
+    ///
+    /// The key signs over the recovery nonce of the account, which is left
+    /// untouched, as is its nonce.
+    fn cancel_recovery(&mut self, cr: CancelRecovery) {
+        let Some(account) = self.accounts.get(&cr.account_id) else {
+            panic!("{}", Error::AccountNotFound);
+        };
+        if account.merged_into.is_some() {
+            panic!("{}", Error::AccountMerged);
+        }
+
+        let key = WrappedPublicKey(cr.key);
+        if !self
+            .account_keys
+            .get(&cr.account_id)
+            .unwrap()
+            .contains(&key)
+        {
+            panic!("{}", Error::UnknownKey);
+        }
+        if self.compromised_keys.contains(&key) {
+            panic!("{}", Error::KeyCompromised);
+        }
+        check_usable(self.pending_keys.get(&cr.account_id), &key);
+        check_unexpired(self.key_expiries.get(&cr.account_id), &key);
+        if !self.pending_recoveries.contains_key(&cr.account_id) {
+            panic!("{}", Error::NoRecoveryPending);
+        }
+
+        let nonce = account.recovery_nonce;
+        let msg = CancelRecovery::signature_msg(cr.account_id, nonce);
+        if !verify_multisig(
+            &mut self.keys_verified,
+            msg,
+            vec![cr.key],
+            cr.signature,
+        ) {
+            panic!("{}", Error::InvalidSignature);
+        }
+
+        self.pending_recoveries.remove(&cr.account_id);
+
+        rusk_abi::emit(
+            "recovery_cancelled",
+            EventEnvelope::new(RecoveryCancelledEvent {
+                account_id: cr.account_id,
+                key: cr.key,
+                nonce,
+                block_height: rusk_abi::block_height(),
+                sequence: next_sequence(&mut self.event_sequence),
+                event_id: next_event_id(
+                    &mut self.event_indices,
+                    "recovery_cancelled",
+                    cr.account_id,
+                    nonce,
+                ),
+            }),
+        );
+    }
+
+    /// Finalizes the recovery of an account once its delay passed, replacing
+    /// the keys and threshold of the account with those of the recovery.
+    fn finalize_recovery(&mut self, fr: FinalizeRecovery) {
+        let Some(account) = self.accounts.get(&fr.account_id) else {
+            panic!("{}", Error::AccountNotFound);
+        };
+        if account.merged_into.is_some() {
+            panic!("{}", Error::AccountMerged);
+        }
+        let Some(recovery) = self.pending_recoveries.get(&fr.account_id) else {
+            panic!("{}", Error::NoRecoveryPending);
+        };
+        let block_height = rusk_abi::block_height();
+        if block_height < recovery.executable_from {
+            panic!("{}", Error::RecoveryNotReady);
+        }
+
+        // The guardians are checked anew against the policy, since they may
+        // have been compromised in the meantime
+        let Some(policy) = account.recovery_policy else {
+            panic!("{}", Error::NoRecoveryPolicy);
+        };
+        let guardians = self.guardians.get(&fr.account_id).unwrap();
+        for key in &recovery.guardians {
+            let key = WrappedPublicKey(*key);
+            if !guardians.contains(&key) {
+                panic!("{}", Error::NotGuardian);
+            }
+            if self.compromised_keys.contains(&key) {
+                panic!("{}", Error::KeyCompromised);
+            }
+        }
+        if recovery.guardians.len() < policy.guardians as usize {
+            panic!("{}", Error::ThresholdNotMet);
+        }
+
+        // The keys are checked anew, since they may have been compromised or
+        // made guardians in the meantime
+        let recovery = self.pending_recoveries.remove(&fr.account_id).unwrap();
+        self.check_replacement(
+            fr.account_id,
+            &recovery.keys,
+            recovery.threshold,
+        );
+
+        let (added_keys, removed_keys) = self.replace_keys(
+            fr.account_id,
+            &recovery.keys,
+            recovery.threshold,
+        );
+        let account = self.accounts.get_mut(&fr.account_id).unwrap();
+        account.nonce += 1;
+
+        rusk_abi::emit(
+            "recovery_finalized",
+            EventEnvelope::new(RecoveryFinalizedEvent {
+                account_id: fr.account_id,
+                removed_keys: removed_keys.clone(),
+                keys: recovery.keys,
+                threshold: recovery.threshold,
+                nonce: account.nonce,
+                block_height,
+                sequence: next_sequence(&mut self.event_sequence),
+                event_id: next_event_id(
+                    &mut self.event_indices,
+                    "recovery_finalized",
+                    fr.account_id,
+                    account.nonce,
+                ),
+            }),
+        );
+
+        self.log_operation(fr.account_id, added_keys, removed_keys);
+    }
+
+    /// Replaces the keys and threshold of an account, returning the keys
+    /// added and removed. The nonce is left to the caller.
+    ///
+    /// Whatever was tied to the keys replaced goes with them - their weights,
+    /// roles, expiries, delays, liveness, usage and delegations - together
+    /// with the duress keys and group key of their holders, and the
+    /// thresholds and tiers set with them in mind, and the transfers they
+    /// queued. The guardians, co-signers and the rest of the settings of the
+    /// account are kept.
+    fn replace_keys(
+        &mut self,
+        account_id: u64,
+        keys: &[bls::PublicKey],
+        threshold: u32,
+    ) -> (Vec<bls::PublicKey>, Vec<bls::PublicKey>) {
+        let keys: BTreeSet<_> =
+            keys.iter().copied().map(WrappedPublicKey).collect();
+        let old_keys =
+            self.account_keys.insert(account_id, keys.clone()).unwrap();
+        let removed_keys: Vec<_> =
+            old_keys.difference(&keys).map(|key| key.0).collect();
+        let added_keys: Vec<_> =
+            keys.difference(&old_keys).map(|key| key.0).collect();
+        for key in &old_keys {
+            self.key_accounts.get_mut(key).unwrap().remove(&account_id);
+        }
+        for key in &keys {
+            self.key_accounts
+                .entry(*key)
+                .or_default()
+                .insert(account_id);
+        }
+
+        self.duress_keys.insert(account_id, BTreeSet::new());
+        self.pending_keys.remove(&account_id);
+        self.key_expiries.remove(&account_id);
+        self.key_usage.remove(&account_id);
+        self.key_weights.remove(&account_id);
+        self.key_roles.remove(&account_id);
+        self.transfer_tiers.remove(&account_id);
+        self.liveness.remove(&account_id);
+        self.queued_transfers.remove(&account_id);
+        self.allowances.remove(&account_id);
+        // As with keys removed by a change, the delegations lapse but keep
+        // their nonce
+        if let Some(delegations) = self.delegations.get_mut(&account_id) {
+            for kd in delegations.values_mut() {
+                kd.delegation = None;
+            }
+        }
+
+        let account = self.accounts.get_mut(&account_id).unwrap();
+        account.threshold = threshold;
+        account.group_key = None;
+        account.operation_thresholds.clear();
+
+        // As with keys added by a change, the keys are live as of the
+        // replacement
+        if account.liveness_policy.is_some() {
+            let block_height = rusk_abi::block_height();
+            let liveness = self.liveness.entry(account_id).or_default();
+            for key in &keys {
+                liveness.insert(*key, block_height);
+            }
+        }
+
+        (added_keys, removed_keys)
+    }
+
+    /// Checks the keys and threshold a recovery or a claim of the account
+    /// while inactive would give it, as those of a new account are checked,
+    /// and that the keys aren't the guardians or observers of the account.
+    fn check_replacement(
+        &self,
+        account_id: u64,
+        keys: &[bls::PublicKey],
+        threshold: u32,
+    ) {
+        if keys.is_empty() {
+            panic!("{}", Error::NoKeys);
+        }
+        if threshold < 1 {
+            panic!("{}", Error::ZeroThreshold);
+        }
+        check_key_count(&self.config, keys.len());
+        let cosigners = self.cosigners.get(&account_id).unwrap();
+        if threshold as usize > keys.len() + cosigners.len() {
+            panic!("{}", Error::ThresholdTooLarge);
+        }
+
+        let guardians = self.guardians.get(&account_id).unwrap();
+        let observers = self.observers.get(&account_id).unwrap();
+        let mut unique = BTreeSet::new();
+        for key in keys {
+            let key = WrappedPublicKey(*key);
+            if !unique.insert(key) {
+                panic!("{}", Error::DuplicateKey);
+            }
+            if self.compromised_keys.contains(&key) {
+                panic!("{}", Error::KeyCompromised);
+            }
+            if guardians.contains(&key) || observers.contains(&key) {
+                panic!("{}", Error::KeyAlreadyUsed);
+            }
+        }
+
+        // The keys are held to the license policy of the account, as keys
+        // added by a change are
+        let account = self.accounts.get(&account_id).unwrap();
+        if let Some(policy) = account.license_policy.filter(|p| p.signers) {
+            if keys.iter().any(|key| !is_licensed(&policy, key)) {
+                panic!("{}", Error::KeyNotLicensed);
+            }
+        }
+    }
+
+    /// Claims an account on the signature of the fallback key of its
+    /// inactivity policy, once it performed no operation for the period of
+    /// the policy, either sweeping its balance or replacing its keys.
+    ///
+    /// A sweep is held to the state of the account as a transfer is, and the
+    /// keys replacing those of the account are checked as a recovery's are.
+    fn claim_inactive(&mut self, ci: ClaimInactive) {
+        check_unpaused(&self.config);
+
+        let Some(account) = self.accounts.get(&ci.account_id) else {
+            panic!("{}", Error::AccountNotFound);
+        };
+        if account.merged_into.is_some() {
+            panic!("{}", Error::AccountMerged);
+        }
+        if account.frozen {
+            panic!("{}", Error::AccountFrozen);
+        }
+        if ci.nonce != account.nonce + 1 {
+            panic!("{}", Error::InvalidNonce);
+        }
+        let Some(policy) = account.inactivity_policy.clone() else {
+            panic!("{}", Error::NoInactivityPolicy);
+        };
+        let block_height = rusk_abi::block_height();
+        let last_active = account.last_active;
+        if block_height < last_active.saturating_add(policy.period) {
+            panic!("{}", Error::AccountActive);
+        }
+        if self
+            .compromised_keys
+            .contains(&WrappedPublicKey(policy.fallback))
+        {
+            panic!("{}", Error::KeyCompromised);
+        }
+
+        match &ci.claim {
+            InactiveClaim::Sweep { receiver } => {
+                if account.locked {
+                    panic!("{}", Error::AccountLocked);
+                }
+                if account.archived {
+                    panic!("{}", Error::AccountArchived);
+                }
+                check_hold(account, Some(receiver));
+                check_licensed(account, receiver);
+                if account.whitelist_enforced {
+                    let whitelist =
+                        self.whitelists.get(&ci.account_id).unwrap();
+                    check_whitelisted(whitelist, *receiver);
+                }
+                if account.balance == 0 {
+                    panic!("{}", Error::InsufficientBalance);
+                }
+                // A sweep can't be queued, so one the account would hold
+                // back is refused
+                check_unqueued(account, account.balance);
+            }
+            InactiveClaim::RotateKeys { keys, threshold } => {
+                self.check_replacement(ci.account_id, keys, *threshold);
+            }
+        }
+
+        let msg =
+            ClaimInactive::signature_msg(ci.account_id, &ci.claim, ci.nonce);
+        if !verify_multisig(
+            &mut self.keys_verified,
+            msg,
+            vec![policy.fallback],
+            ci.signature,
+        ) {
+            panic!("{}", Error::InvalidSignature);
+        }
+
+        let (added_keys, removed_keys) = match &ci.claim {
+            InactiveClaim::Sweep { receiver } => {
+                let account = self.accounts.get_mut(&ci.account_id).unwrap();
+                account.nonce += 1;
+                let amount = account.balance;
+                let payment = Payment {
+                    receiver: *receiver,
+                    amount,
+                    memo: String::new(),
+                    reference: None,
+                    category: None,
+                };
+                // The fallback key isn't a key of the account, so it signs
+                // for none of them
+                let signers = Signers {
+                    keys: vec![policy.fallback],
+                    principals: Vec::new(),
+                    cosigners: Vec::new(),
+                    weight: 0,
+                    group_signed: false,
+                    duress: false,
+                };
+                self.pay_out(ci.account_id, payment, ci.nonce, signers);
+                self.record_outflow(ci.account_id, amount);
+                (Vec::new(), Vec::new())
+            }
+            InactiveClaim::RotateKeys { keys, threshold } => {
+                let replaced =
+                    self.replace_keys(ci.account_id, keys, *threshold);
+                self.accounts.get_mut(&ci.account_id).unwrap().nonce += 1;
+                replaced
+            }
+        };
+
+        rusk_abi::emit(
+            "inactive_claimed",
+            EventEnvelope::new(InactiveClaimedEvent {
+                account_id: ci.account_id,
+                key: policy.fallback,
+                claim: ci.claim,
+                last_active,
+                removed_keys: removed_keys.clone(),
+                nonce: ci.nonce,
+                block_height,
+                sequence: next_sequence(&mut self.event_sequence),
+                event_id: next_event_id(
+                    &mut self.event_indices,
+                    "inactive_claimed",
+                    ci.account_id,
+                    ci.nonce,
+                ),
+            }),
+        );
+
+        self.log_operation(ci.account_id, added_keys, removed_keys);
+    }
+
+    /// Cancels a transfer queued by an account on the signature of any single
+    /// one of its keys, so long as it wasn't executed.
+    ///
+    /// As with cancelling a recovery, the nonce is left untouched.
+    fn cancel_queued(&mut self, cq: CancelQueued) {
+        let Some(account) = self.accounts.get(&cq.account_id) else {
+            panic!("{}", Error::AccountNotFound);
+        };
+        if account.merged_into.is_some() {
+            panic!("{}", Error::AccountMerged);
+        }
+
+        let key = WrappedPublicKey(cq.key);
+        if !self
+            .account_keys
+            .get(&cq.account_id)
+            .unwrap()
+            .contains(&key)
+        {
+            panic!("{}", Error::UnknownKey);
+        }
+        if self.compromised_keys.contains(&key) {
+            panic!("{}", Error::KeyCompromised);
+        }
+        check_usable(self.pending_keys.get(&cq.account_id), &key);
+        check_unexpired(self.key_expiries.get(&cq.account_id), &key);
+        if !self
+            .queued_transfers
+            .get(&cq.account_id)
+            .is_some_and(|queued| queued.contains_key(&cq.nonce))
+        {
+            panic!("{}", Error::NoQueuedTransfer);
+        }
+
+        let msg = CancelQueued::signature_msg(cq.account_id, cq.nonce);
+        if !verify_multisig(
+            &mut self.keys_verified,
+            msg,
+            vec![cq.key],
+            cq.signature,
+        ) {
+            panic!("{}", Error::InvalidSignature);
+        }
+
+        self.queued_transfers
+            .get_mut(&cq.account_id)
+            .unwrap()
+            .remove(&cq.nonce);
+
+        rusk_abi::emit(
+            "queued_cancelled",
+            EventEnvelope::new(QueuedCancelledEvent {
+                account_id: cq.account_id,
+                key: cq.key,
+                nonce: cq.nonce,
+                block_height: rusk_abi::block_height(),
+                sequence: next_sequence(&mut self.event_sequence),
+                event_id: next_event_id(
+                    &mut self.event_indices,
+                    "queued_cancelled",
+                    cq.account_id,
+                    cq.nonce,
+                ),
+            }),
+        );
+    }
+
+    /// Executes a transfer queued by an account once its delay passed,
+    /// paying it out as `transfer` would have.
+    ///
+    /// The transfer was signed for when it was queued, but is held to the
+    /// state of the account as of its execution - its signers, transfer
+    /// window, balance, whitelist and spending categories included. Its
+    /// events carry the nonce it used up when it was queued.
+    ///
+    /// A transfer the balance falls short of is skipped, staying queued, and
+    /// one left unexecuted for [`QUEUE_EXPIRY`] blocks past its delay is
+    /// dropped, each with an event and the call succeeding.
+    fn execute_queued(&mut self, eq: ExecuteQueued) {
+        check_unpaused(&self.config);
+
+        let Some(account) = self.accounts.get(&eq.account_id) else {
+            panic!("{}", Error::AccountNotFound);
+        };
+        if account.merged_into.is_some() {
+            panic!("{}", Error::AccountMerged);
+        }
+        if account.frozen {
+            panic!("{}", Error::AccountFrozen);
+        }
+        if account.locked {
+            panic!("{}", Error::AccountLocked);
+        }
+        if account.archived {
+            panic!("{}", Error::AccountArchived);
+        }
+        let Some(queued) = self
+            .queued_transfers
+            .get(&eq.account_id)
+            .and_then(|queued| queued.get(&eq.nonce))
+        else {
+            panic!("{}", Error::NoQueuedTransfer);
+        };
+        let block_height = rusk_abi::block_height();
+        if block_height < queued.executable_from {
+            panic!("{}", Error::QueuedTransferNotReady);
+        }
+        if block_height > queued.executable_from.saturating_add(QUEUE_EXPIRY) {
+            self.queued_transfers
+                .get_mut(&eq.account_id)
+                .unwrap()
+                .remove(&eq.nonce);
+            rusk_abi::emit(
+                "queued_expired",
+                EventEnvelope::new(QueuedExpiredEvent {
+                    account_id: eq.account_id,
+                    nonce: eq.nonce,
+                    block_height,
+                    sequence: next_sequence(&mut self.event_sequence),
+                    event_id: next_event_id(
+                        &mut self.event_indices,
+                        "queued_expired",
+                        eq.account_id,
+                        eq.nonce,
+                    ),
+                }),
+            );
+            return;
+        }
+
+        // The signers are held to the account as of the execution too, a key
+        // removed or compromised since no longer signing for the transfer
+        let account_keys = self.account_keys.get(&eq.account_id).unwrap();
+        let cosigners = self.cosigners.get(&eq.account_id).unwrap();
+        let delegations = self.delegations.get(&eq.account_id);
+        let mut principals = BTreeSet::new();
+        for key in &queued.keys {
+            let key = WrappedPublicKey(*key);
+            let Some(principal) = principal_of(account_keys, delegations, key)
+            else {
+                panic!("{}", Error::UnknownKey);
+            };
+            if self.compromised_keys.contains(&key)
+                || self.compromised_keys.contains(&principal)
+            {
+                panic!("{}", Error::KeyCompromised);
+            }
+            principals.insert(principal);
+        }
+        for cosigner in &queued.cosigners {
+            if !cosigners.contains(cosigner) {
+                panic!("{}", Error::UnknownKey);
+            }
+            if self.compromised_cosigners.contains(cosigner) {
+                panic!("{}", Error::KeyCompromised);
+            }
+        }
+        let weights = self.key_weights.get(&eq.account_id);
+        let weight = weight_of(weights, &principals, queued.cosigners.len());
+        let signers = Signers {
+            keys: queued.keys.clone(),
+            principals: principals.into_iter().collect(),
+            cosigners: queued.cosigners.clone(),
+            weight,
+            group_signed: queued.group_signed,
+            duress: false,
+        };
+        check_transfer_window(
+            account,
+            weight_of(weights, account_keys, cosigners.len()),
+            &signers,
+        );
+
+        check_hold(account, Some(&queued.receiver));
+        check_licensed(account, &queued.receiver);
+        if queued.amount > account.balance {
+            rusk_abi::emit(
+                "queued_skipped",
+                EventEnvelope::new(QueuedSkippedEvent {
+                    account_id: eq.account_id,
+                    nonce: eq.nonce,
+                    amount: queued.amount,
+                    balance: account.balance,
+                    block_height,
+                    sequence: next_sequence(&mut self.event_sequence),
+                    event_id: next_event_id(
+                        &mut self.event_indices,
+                        "queued_skipped",
+                        eq.account_id,
+                        eq.nonce,
+                    ),
+                }),
+            );
+            return;
+        }
+        if account.whitelist_enforced {
+            let whitelist = self.whitelists.get(&eq.account_id).unwrap();
+            check_whitelisted(whitelist, queued.receiver);
+        }
+        if let Some(category) = &queued.category {
+            let spending =
+                self.category_spending.entry(eq.account_id).or_default();
+            spend_category(account, spending, category, queued.amount);
+        }
+
+        let queued = self
+            .queued_transfers
+            .get_mut(&eq.account_id)
+            .unwrap()
+            .remove(&eq.nonce)
+            .unwrap();
+        let amount = queued.amount;
+        let payment = Payment {
+            receiver: queued.receiver,
+            amount,
+            memo: queued.memo,
+            reference: queued.reference,
+            category: queued.category,
+        };
+        self.pay_out(eq.account_id, payment, eq.nonce, signers);
+        self.record_outflow(eq.account_id, amount);
+
+        rusk_abi::emit(
+            "queued_executed",
+            EventEnvelope::new(QueuedExecutedEvent {
+                account_id: eq.account_id,
+                nonce: eq.nonce,
+                block_height,
+                sequence: next_sequence(&mut self.event_sequence),
+                event_id: next_event_id(
+                    &mut self.event_indices,
+                    "queued_executed",
+                    eq.account_id,
+                    eq.nonce,
+                ),
+            }),
+        );
+    }
+
+    /// Transfers an amount from an account on the signature of a single one
+    /// of its keys, out of the allowance the account gave the key, without
+    /// meeting the threshold.
+    ///
+    /// Other than its signers, the transfer is held to everything a transfer
+    /// is - the transfer window and queue policy of the account included. Its
+    /// gas refund is taken out of the allowance along with the amount, so that
+    /// the key can't spend past it through refunds.
+    fn transfer_with_allowance(&mut self, at: AllowanceTransfer) {
+        check_unpaused(&self.config);
+        check_memo(&self.config, &at.memo);
+
+        let Some(account) = self.accounts.get_mut(&at.account_id) else {
+            panic!("{}", Error::AccountNotFound);
+        };
+        if account.merged_into.is_some() {
+            panic!("{}", Error::AccountMerged);
+        }
+        if account.frozen {
+            panic!("{}", Error::AccountFrozen);
+        }
+        if account.locked {
+            panic!("{}", Error::AccountLocked);
+        }
+        if account.archived {
+            panic!("{}", Error::AccountArchived);
+        }
+        check_hold(account, Some(&at.receiver));
+        check_licensed(account, &at.receiver);
+        let refund = gas_refund_amount(
+            at.gas_refund.as_ref(),
+            self.config.max_gas_refund,
+        );
+        let Some(available) = account.balance.checked_sub(refund) else {
+            panic!("{}", Error::InsufficientBalance);
+        };
+        if at.amount > available {
+            panic!("{}", Error::InsufficientBalance);
+        }
+        check_unqueued(account, at.amount);
+        if at.nonce != account.nonce + 1 {
+            panic!("{}", Error::InvalidNonce);
+        }
+        check_chain(account, at.chain_id);
+
+        let key = WrappedPublicKey(at.key);
+        let account_keys = self.account_keys.get(&at.account_id).unwrap();
+        if !account_keys.contains(&key) {
+            panic!("{}", Error::UnknownKey);
+        }
+        if self.compromised_keys.contains(&key) {
+            panic!("{}", Error::KeyCompromised);
+        }
+        check_usable(self.pending_keys.get(&at.account_id), &key);
+        check_unexpired(self.key_expiries.get(&at.account_id), &key);
+        let Some(&allowance) = self
+            .allowances
+            .get(&at.account_id)
+            .and_then(|allowances| allowances.get(&key))
+        else {
+            panic!("{}", Error::NoAllowance);
+        };
+        let Some(remaining) = allowance
+            .checked_sub(at.amount)
+            .and_then(|remaining| remaining.checked_sub(refund))
+        else {
+            panic!("{}", Error::AllowanceExceeded);
+        };
+
+        if !verify_multisig(
+            &mut self.keys_verified,
+            at.signature_msg(),
+            vec![at.key],
+            at.signature,
+        ) {
+            panic!("{}", Error::InvalidSignature);
+        }
+
+        let weights = self.key_weights.get(&at.account_id);
+        let cosigners = self.cosigners.get(&at.account_id).unwrap();
+        let signers = Signers {
+            keys: vec![at.key],
+            principals: vec![key],
+            cosigners: Vec::new(),
+            weight: weight_of(weights, [&key], 0),
+            group_signed: false,
+            duress: false,
+        };
+        check_transfer_window(
+            account,
+            weight_of(weights, account_keys, cosigners.len()),
+            &signers,
+        );
+        if account.whitelist_enforced {
+            let whitelist = self.whitelists.get(&at.account_id).unwrap();
+            check_whitelisted(whitelist, at.receiver);
+        }
+        record_usage(&mut self.key_usage, at.account_id, &signers.principals);
+
+        let allowances = self.allowances.get_mut(&at.account_id).unwrap();
+        match remaining {
+            0 => allowances.remove(&key),
+            remaining => allowances.insert(key, remaining),
+        };
+
+        account.nonce += 1;
+        let payment = Payment {
+            receiver: at.receiver,
+            amount: at.amount,
+            memo: at.memo,
+            reference: None,
+            category: None,
+        };
+        self.pay_out(at.account_id, payment, at.nonce, signers);
+        self.record_outflow(at.account_id, at.amount);
+        self.refund_gas(at.account_id, at.nonce, at.gas_refund);
+
+        rusk_abi::emit(
+            "allowance_spent",
+            EventEnvelope::new(AllowanceSpentEvent {
+                account_id: at.account_id,
+                key: at.key,
+                amount: at.amount,
+                remaining,
+                nonce: at.nonce,
+                block_height: rusk_abi::block_height(),
+                sequence: next_sequence(&mut self.event_sequence),
+                event_id: next_event_id(
+                    &mut self.event_indices,
+                    "allowance_spent",
+                    at.account_id,
+                    at.nonce,
+                ),
+            }),
+        );
+        self.log_operation(at.account_id, Vec::new(), Vec::new());
+    }
+
+    /// Merges an account into another, on the signatures of both accounts'
+    /// thresholds.
+    ///
+    /// The remaining account takes the balance, keys and co-signers of the
+    /// merged one, keeping its own threshold and settings. The merged account
+    /// keeps its ID, so that it's never reused, but loses its keys, co-signers,
+    /// duress keys, guardians, observers, alias and tags, and refuses every
+    /// operation from then on - deposits included.
+    fn merge_accounts(&mut self, m: MergeAccounts) {
+        check_unpaused(&self.config);
+        if m.account_id == m.merged_id {
+            panic!("{}", Error::MergeIntoSelf);
+        }
+        if self.governance == Some(m.merged_id) {
+            panic!("{}", Error::GovernanceAccount);
+        }
+        for (account_id, nonce) in
+            [(m.account_id, m.nonce), (m.merged_id, m.merged_nonce)]
+        {
+            let Some(account) = self.accounts.get(&account_id) else {
+                panic!("{}", Error::AccountNotFound);
+            };
+            if account.merged_into.is_some() {
+                panic!("{}", Error::AccountMerged);
+            }
+            if account.frozen {
+                panic!("{}", Error::AccountFrozen);
+            }
+            if account.locked {
+                panic!("{}", Error::AccountLocked);
+            }
+            if account.archived {
+                panic!("{}", Error::AccountArchived);
+            }
+            check_hold(account, None);
+            if nonce != account.nonce + 1 {
+                panic!("{}", Error::InvalidNonce);
+            }
+        }
+
+        let msg = m.signature_msg();
+        let signers = self.authorize(
+            m.account_id,
+            OperationKind::MergeAccounts,
+            None,
+            &m.keys,
+            m.signature,
+            &m.cosignatures,
+            m.group_signature,
+            &msg,
+        );
+        let duress = signers.duress;
+        let merged_balance = self.accounts.get(&m.merged_id).unwrap().balance;
+        let merged_signers = self.authorize(
+            m.merged_id,
+            OperationKind::MergeAccounts,
+            Some(merged_balance),
+            &m.merged_keys,
+            m.merged_signature,
+            &m.merged_cosignatures,
+            m.merged_group_signature,
+            &msg,
+        );
+        let merged_duress = merged_signers.duress;
+
+        // As with a transfer, a duress key freezes its account instead, with
+        // the call succeeding
+        if duress || merged_duress {
+            self.accounts.get_mut(&m.account_id).unwrap().frozen |= duress;
+            self.accounts.get_mut(&m.merged_id).unwrap().frozen |=
+                merged_duress;
+            return;
+        }
+        record_usage(&mut self.key_usage, m.account_id, &signers.principals);
+        record_usage(
+            &mut self.key_usage,
+            m.merged_id,
+            &merged_signers.principals,
+        );
+
+        // The keys taken over are held to the bar of keys added by a change
+        let account = self.accounts.get(&m.account_id).unwrap();
+        let account_signers = self.account_signers(m.account_id);
+        if account.key_addition_policy.is_some_and(|p| p.supermajority)
+            && !supermajority_met(account, account_signers, &signers)
+        {
+            panic!("{}", Error::SupermajorityNotMet);
+        }
+
+        // The merge moves the funds out of the merged account, which its
+        // transfer window, queue policy, whitelist and license policy would
+        // otherwise keep to their blocks, delay and receivers
+        let merged_signers_count = self.account_signers(m.merged_id);
+        let merged = self.accounts.get(&m.merged_id).unwrap();
+        check_transfer_window(merged, merged_signers_count, &merged_signers);
+        check_unqueued(merged, merged.balance);
+        check_unrestricted(merged);
+        let merged = self.accounts.get_mut(&m.merged_id).unwrap();
+        let merged_keys = self.account_keys.get(&m.merged_id).unwrap();
+        if !merged_keys.is_empty() {
+            let keys = self.account_keys.get(&m.account_id).unwrap();
+            check_key_count(&self.config, keys.union(merged_keys).count());
+        }
+
+        let amount = merged.balance;
+        let merged_retention = merged.history_retention;
+        merged.balance = 0;
+        merged.nonce += 1;
+        merged.merged_into = Some(m.account_id);
+        if let Some(alias) = merged.alias.take() {
+            self.aliases.remove(&alias);
+        }
+        for tag in core::mem::take(&mut merged.tags) {
+            release_tag(&mut self.tags, &tag, m.merged_id);
+        }
+
+        let merged_keys = self
+            .account_keys
+            .insert(m.merged_id, BTreeSet::new())
+            .unwrap();
+        let removed_keys = merged_keys.iter().map(|key| key.0).collect();
+        let merged_cosigners =
+            self.cosigners.insert(m.merged_id, BTreeSet::new()).unwrap();
+        self.duress_keys.insert(m.merged_id, BTreeSet::new());
+        self.whitelists.insert(m.merged_id, BTreeMap::new());
+        self.guardians.insert(m.merged_id, BTreeSet::new());
+        self.observers.insert(m.merged_id, BTreeSet::new());
+        self.outflows.remove(&m.merged_id);
+        self.category_spending.remove(&m.merged_id);
+        self.budget_draws.remove(&m.merged_id);
+        self.liveness.remove(&m.merged_id);
+        self.delegations.remove(&m.merged_id);
+        let merged_pending_keys =
+            self.pending_keys.remove(&m.merged_id).unwrap_or_default();
+        let merged_key_expiries =
+            self.key_expiries.remove(&m.merged_id).unwrap_or_default();
+        self.key_usage.remove(&m.merged_id);
+        let merged_key_weights =
+            self.key_weights.remove(&m.merged_id).unwrap_or_default();
+        let merged_key_roles =
+            self.key_roles.remove(&m.merged_id).unwrap_or_default();
+        self.transfer_tiers.remove(&m.merged_id);
+        self.pending_recoveries.remove(&m.merged_id);
+        self.queued_transfers.remove(&m.merged_id);
+        self.allowances.remove(&m.merged_id);
+
+        let account_keys = self.account_keys.get_mut(&m.account_id).unwrap();
+        let duress_keys = self.duress_keys.get(&m.account_id).unwrap();
+        let guardians = self.guardians.get(&m.account_id).unwrap();
+        let observers = self.observers.get(&m.account_id).unwrap();
+
+        let mut added_keys = Vec::new();
+        for key in merged_keys {
+            if self.compromised_keys.contains(&key) {
+                panic!("{}", Error::KeyCompromised);
+            }
+            if duress_keys.contains(&key)
+                || guardians.contains(&key)
+                || observers.contains(&key)
+            {
+                panic!("{}", Error::KeyAlreadyUsed);
+            }
+
+            let key_accounts = self.key_accounts.get_mut(&key).unwrap();
+            key_accounts.remove(&m.merged_id);
+            key_accounts.insert(m.account_id);
+
+            if account_keys.insert(key) {
+                added_keys.push(key.0);
+            }
+        }
+
+        // The keys gained are held to the license policy of the account
+        // merged into, as keys added by a change are
+        let license_policy = self.accounts[&m.account_id].license_policy;
+        if let Some(policy) = license_policy.filter(|p| p.signers) {
+            if added_keys.iter().any(|key| !is_licensed(&policy, key)) {
+                panic!("{}", Error::KeyNotLicensed);
+            }
+        }
+
+        let cosigners = self.cosigners.get_mut(&m.account_id).unwrap();
+        let mut added_cosigners = Vec::new();
+        for cosigner in merged_cosigners {
+            if cosigners.insert(cosigner) {
+                added_cosigners.push(cosigner);
+            }
+        }
+
+        let account = self.accounts.get_mut(&m.account_id).unwrap();
+        account.balance += amount;
+        account.nonce += 1;
+
+        // As with keys added by a change, the keys taken over are live as of
+        // the merge
+        if account.liveness_policy.is_some() {
+            let block_height = rusk_abi::block_height();
+            let liveness = self.liveness.entry(m.account_id).or_default();
+            for key in &added_keys {
+                liveness.insert(WrappedPublicKey(*key), block_height);
+            }
+        }
+
+        // ...and wait out the delay of the policy, or whatever was left of
+        // theirs in the merged account, whichever ends later
+        let delay = account.key_addition_policy.map_or(0, |p| p.delay);
+        let usable_from = rusk_abi::block_height().saturating_add(delay);
+        for key in &added_keys {
+            let key = WrappedPublicKey(*key);
+            let usable_from = merged_pending_keys
+                .get(&key)
+                .map_or(usable_from, |&h| h.max(usable_from));
+            if usable_from > rusk_abi::block_height() {
+                self.pending_keys
+                    .entry(m.account_id)
+                    .or_default()
+                    .insert(key, usable_from);
+            }
+            // ...and lapse when they would have
+            if let Some(&valid_until) = merged_key_expiries.get(&key) {
+                self.key_expiries
+                    .entry(m.account_id)
+                    .or_default()
+                    .insert(key, valid_until);
+            }
+            // ...and weigh what they did
+            if let Some(&weight) = merged_key_weights.get(&key) {
+                self.key_weights
+                    .entry(m.account_id)
+                    .or_default()
+                    .insert(key, weight);
+            }
+            // ...and keep their role
+            if let Some(&role) = merged_key_roles.get(&key) {
+                self.key_roles
+                    .entry(m.account_id)
+                    .or_default()
+                    .insert(key, role);
+            }
+        }
+
+        // Both ledgers record the move, under the merge's event
+        let sequence = next_sequence(&mut self.event_sequence);
+        let block_height = rusk_abi::block_height();
+        record_movement(
+            &mut self.movements,
+            m.merged_id,
+            merged_retention,
+            Movement {
+                block_height,
+                sequence,
+                direction: Direction::Out,
+                counterparty: Counterparty::Account(m.account_id),
+                amount,
+                memo_hash: None,
+                balance_after: 0,
+            },
+        );
+        record_movement(
+            &mut self.movements,
+            m.account_id,
+            account.history_retention,
+            Movement {
+                block_height,
+                sequence,
+                direction: Direction::In,
+                counterparty: Counterparty::Account(m.merged_id),
+                amount,
+                memo_hash: None,
+                balance_after: account.balance,
+            },
+        );
+
+        rusk_abi::emit(
+            "merge_accounts",
+            EventEnvelope::new(MergeAccountsEvent {
+                account_id: m.account_id,
+                merged_id: m.merged_id,
+                amount,
+                added_keys: added_keys.clone(),
+                added_cosigners,
+                balance_after: account.balance,
+                nonce: m.nonce,
+                merged_nonce: m.merged_nonce,
+                block_height,
+                sequence,
+                event_id: next_event_id(
+                    &mut self.event_indices,
+                    "merge_accounts",
+                    m.account_id,
+                    m.nonce,
+                ),
+            }),
+        );
+
+        self.log_operation(m.merged_id, Vec::new(), removed_keys);
+        self.log_operation(m.account_id, added_keys, Vec::new());
+    }
+
+    /// Reimburses the submitter of an operation with the gas refund the
+    /// operation designates, if any, once the operation succeeded.
+    ///
+    /// A change can't pay a refund from an account it leaves locked or
+    /// archived.
+    fn refund_gas(
+        &mut self,
+        account_id: u64,
+        nonce: u64,
+        refund: Option<GasRefund>,
+    ) {
+        let Some(refund) = refund else {
+            return;
+        };
+
+        let account = self.accounts.get(&account_id).unwrap();
+        if account.locked {
+            panic!("{}", Error::AccountLocked);
+        }
+        if account.archived {
+            panic!("{}", Error::AccountArchived);
+        }
+        if refund.amount > account.balance {
+            panic!("{}", Error::InsufficientBalance);
+        }
+
+        self.pay_or_defer(account_id, refund.submitter, refund.amount);
+
+        let account = self.accounts.get_mut(&account_id).unwrap();
+        account.balance -= refund.amount;
+        self.total_balance -= refund.amount;
+
+        let sequence = next_sequence(&mut self.event_sequence);
+        let submitter = match account.private_events {
+            true => {
+                Disclosure::Hashed(hash_receiver(sequence, &refund.submitter))
+            }
+            false => Disclosure::Revealed(refund.submitter),
+        };
+        record_movement(
+            &mut self.movements,
+            account_id,
+            account.history_retention,
+            Movement {
+                block_height: rusk_abi::block_height(),
+                sequence,
+                direction: Direction::Out,
+                counterparty: Counterparty::Moonlight(submitter.clone()),
+                amount: refund.amount,
+                memo_hash: None,
+                balance_after: account.balance,
+            },
+        );
+
+        rusk_abi::emit(
+            "gas_refund",
+            EventEnvelope::new(GasRefundEvent {
+                account_id,
+                submitter,
+                amount: refund.amount,
+                nonce,
+                block_height: rusk_abi::block_height(),
+                balance_after: account.balance,
+                sequence,
+                event_id: next_event_id(
+                    &mut self.event_indices,
+                    "gas_refund",
+                    account_id,
+                    nonce,
+                ),
+            }),
+        );
+
+        self.record_outflow(account_id, refund.amount);
+    }
+
+    /// Splits a new account off an account, with some of its keys and part of
+    /// its balance.
+    ///
+    /// The new account is created as by `create_account`, its creation event
+    /// being emitted before the split's, and starts out with the amount moved
+    /// to it and no settings beyond its keys, threshold and description.
+    fn split_account(&mut self, s: SplitAccount) {
+        check_unpaused(&self.config);
+
+        let Some(account) = self.accounts.get(&s.account_id) else {
+            panic!("{}", Error::AccountNotFound);
+        };
+        if account.merged_into.is_some() {
+            panic!("{}", Error::AccountMerged);
+        }
+        if account.frozen {
+            panic!("{}", Error::AccountFrozen);
+        }
+        if account.locked {
+            panic!("{}", Error::AccountLocked);
+        }
+        if account.archived {
+            panic!("{}", Error::AccountArchived);
+        }
+        check_hold(account, None);
+        let refund = gas_refund_amount(
+            s.gas_refund.as_ref(),
+            self.config.max_gas_refund,
+        );
+        let Some(available) = account.balance.checked_sub(refund) else {
+            panic!("{}", Error::InsufficientBalance);
+        };
+        if s.amount > available {
+            panic!("{}", Error::InsufficientBalance);
+        }
+        check_unqueued(account, s.amount);
+        if s.nonce != account.nonce + 1 {
+            panic!("{}", Error::InvalidNonce);
+        }
+        check_chain(account, s.chain_id);
+
+        let signers = self.authorize(
+            s.account_id,
+            OperationKind::SplitAccount,
+            Some(s.amount),
+            &s.keys,
+            s.signature,
+            &s.cosignatures,
+            s.group_signature,
+            &s.signature_msg(),
+        );
+
+        // As with a transfer, a duress key freezes the account instead, with
+        // the call succeeding
+        let account_signers = self.account_signers(s.account_id);
+        let account = self.accounts.get_mut(&s.account_id).unwrap();
+        if signers.duress {
+            account.frozen = true;
+            return;
+        }
+        record_usage(&mut self.key_usage, s.account_id, &signers.principals);
+
+        check_transfer_window(account, account_signers, &signers);
+
+        // The new account has no whitelist or license policy, so the split
+        // would otherwise let the funds moved to it go to any receiver
+        check_unrestricted(account);
+
+        let account_keys = self.account_keys.get(&s.account_id).unwrap();
+        for key in &s.new_keys {
+            if !account_keys.contains(&WrappedPublicKey(*key)) {
+                panic!("{}", Error::KeyNotInAccount);
+            }
+        }
+
+        let new_account_id = self.create_account(CreateAccount {
+            keys: s.new_keys,
+            threshold: s.threshold,
+            description: s.description,
+            alias: None,
+            key_weights: Vec::new(),
+            key_roles: Vec::new(),
+        });
+        self.accounts.get_mut(&new_account_id).unwrap().balance = s.amount;
+        // As with genesis accounts, the new account is created holding the
+        // amount split off
+        if let Some(ops) = self.operations.get_mut(&new_account_id) {
+            ops[0].balance = s.amount;
+        }
+
+        let account = self.accounts.get_mut(&s.account_id).unwrap();
+        account.balance -= s.amount;
+        account.nonce += 1;
+
+        // Both ledgers record the move, under the split's event
+        let sequence = next_sequence(&mut self.event_sequence);
+        let block_height = rusk_abi::block_height();
+        record_movement(
+            &mut self.movements,
+            s.account_id,
+            account.history_retention,
+            Movement {
+                block_height,
+                sequence,
+                direction: Direction::Out,
+                counterparty: Counterparty::Account(new_account_id),
+                amount: s.amount,
+                memo_hash: None,
+                balance_after: account.balance,
+            },
+        );
+        record_movement(
+            &mut self.movements,
+            new_account_id,
+            None,
+            Movement {
+                block_height,
+                sequence,
+                direction: Direction::In,
+                counterparty: Counterparty::Account(s.account_id),
+                amount: s.amount,
+                memo_hash: None,
+                balance_after: s.amount,
+            },
+        );
+
+        rusk_abi::emit(
+            "split_account",
+            EventEnvelope::new(SplitAccountEvent {
+                account_id: s.account_id,
+                new_account_id,
+                amount: s.amount,
+                balance_after: account.balance,
+                nonce: s.nonce,
+                block_height,
+                sequence,
+                event_id: next_event_id(
+                    &mut self.event_indices,
+                    "split_account",
+                    s.account_id,
+                    s.nonce,
+                ),
+            }),
+        );
+
+        self.record_outflow(s.account_id, s.amount);
+        self.refund_gas(s.account_id, s.nonce, s.gas_refund);
+        self.log_operation(s.account_id, Vec::new(), Vec::new());
+    }
+
+    /// Draws on the budget a parent account allocated to a child account, on
+    /// the signatures of the child alone, moving the amount drawn from the
+    /// parent's balance to the child's.
+    fn draw_budget(&mut self, d: DrawBudget) {
+        check_unpaused(&self.config);
+
+        let Some(account) = self.accounts.get(&d.account_id) else {
+            panic!("{}", Error::AccountNotFound);
+        };
+        if account.merged_into.is_some() {
+            panic!("{}", Error::AccountMerged);
+        }
+        if account.frozen {
+            panic!("{}", Error::AccountFrozen);
+        }
+        if account.archived {
+            panic!("{}", Error::AccountArchived);
+        }
+        if d.nonce != account.nonce + 1 {
+            panic!("{}", Error::InvalidNonce);
+        }
+
+        // The funds leave the parent, which must be able to move them
+        let Some(parent) = self.accounts.get(&d.parent_id) else {
+            panic!("{}", Error::AccountNotFound);
+        };
+        if parent.merged_into.is_some() {
+            panic!("{}", Error::AccountMerged);
+        }
+        if parent.frozen {
+            panic!("{}", Error::AccountFrozen);
+        }
+        if parent.locked {
+            panic!("{}", Error::AccountLocked);
+        }
+        if parent.archived {
+            panic!("{}", Error::AccountArchived);
+        }
+        check_hold(parent, None);
+        check_unqueued(parent, d.amount);
+        // The child pays out to receivers of its own
+        check_unrestricted(parent);
+        let Some(budget) = parent
+            .child_budgets
+            .iter()
+            .find(|b| b.child_id == d.account_id)
+            .copied()
+        else {
+            panic!("{}", Error::UnknownChildBudget);
+        };
+
+        let signers = self.authorize(
+            d.account_id,
+            OperationKind::DrawBudget,
+            None,
+            &d.keys,
+            d.signature,
+            &d.cosignatures,
+            d.group_signature,
+            &d.signature_msg(),
+        );
+
+        // As with a transfer, a duress key freezes the child instead, with the
+        // call succeeding
+        if signers.duress {
+            self.accounts.get_mut(&d.account_id).unwrap().frozen = true;
+            return;
+        }
+        record_usage(&mut self.key_usage, d.account_id, &signers.principals);
+
+        let block_height = rusk_abi::block_height();
+        let window_start = block_height - block_height % budget.window;
+        let draws = self
+            .budget_draws
+            .entry(d.parent_id)
+            .or_default()
+            .entry(d.account_id)
+            .or_insert((window_start, 0, 0));
+        if draws.0 != window_start {
+            *draws = (window_start, 0, draws.2);
+        }
+        match draws.1.checked_add(d.amount) {
+            Some(total) if total <= budget.amount => draws.1 = total,
+            _ => panic!("{}", Error::ChildBudgetExceeded),
+        }
+        draws.2 = draws.2.saturating_add(d.amount);
+
+        let parent = self.accounts.get_mut(&d.parent_id).unwrap();
+        if d.amount > parent.balance {
+            panic!("{}", Error::InsufficientBalance);
+        }
+        parent.balance -= d.amount;
+        let parent_balance_after = parent.balance;
+        let parent_retention = parent.history_retention;
+
+        let account = self.accounts.get_mut(&d.account_id).unwrap();
+        if account
+            .balance_cap
+            .is_some_and(|cap| d.amount > cap.saturating_sub(account.balance))
+        {
+            panic!("{}", Error::BalanceCapExceeded);
+        }
+        account.balance += d.amount;
+        account.nonce += 1;
+
+        // Both ledgers record the move, under the draw's event
+        let sequence = next_sequence(&mut self.event_sequence);
+        record_movement(
+            &mut self.movements,
+            d.parent_id,
+            parent_retention,
+            Movement {
+                block_height,
+                sequence,
+                direction: Direction::Out,
+                counterparty: Counterparty::Account(d.account_id),
+                amount: d.amount,
+                memo_hash: None,
+                balance_after: parent_balance_after,
+            },
+        );
+        record_movement(
+            &mut self.movements,
+            d.account_id,
+            account.history_retention,
+            Movement {
+                block_height,
+                sequence,
+                direction: Direction::In,
+                counterparty: Counterparty::Account(d.parent_id),
+                amount: d.amount,
+                memo_hash: None,
+                balance_after: account.balance,
+            },
+        );
+
+        rusk_abi::emit(
+            "budget_drawn",
+            EventEnvelope::new(BudgetDrawnEvent {
+                account_id: d.account_id,
+                parent_id: d.parent_id,
+                amount: d.amount,
+                balance_after: account.balance,
+                parent_balance_after,
+                nonce: d.nonce,
+                block_height,
+                sequence,
+                event_id: next_event_id(
+                    &mut self.event_indices,
+                    "budget_drawn",
+                    d.account_id,
+                    d.nonce,
+                ),
+            }),
+        );
+
+        self.record_outflow(d.parent_id, d.amount);
+        self.log_operation(d.account_id, Vec::new(), Vec::new());
+    }
+
+    /// Records a checkpoint of an account, fixing its balance, nonce and keys
+    /// as of now, and emits the `checkpoint` event.
+    fn record_checkpoint(&mut self, rc: RecordCheckpoint) {
+        let Some(account) = self.accounts.get(&rc.account_id) else {
+            panic!("{}", Error::AccountNotFound);
+        };
+        if account.merged_into.is_some() {
+            panic!("{}", Error::AccountMerged);
+        }
+        if account.frozen {
+            panic!("{}", Error::AccountFrozen);
+        }
+        if rc.nonce != account.nonce + 1 {
+            panic!("{}", Error::InvalidNonce);
+        }
+
+        let signers = self.authorize(
+            rc.account_id,
+            OperationKind::RecordCheckpoint,
+            None,
+            &rc.keys,
+            rc.signature,
+            &rc.cosignatures,
+            rc.group_signature,
+            &rc.signature_msg(),
+        );
+
+        // As with a transfer, a duress key freezes the account instead, with
+        // the call succeeding
+        let account = self.accounts.get_mut(&rc.account_id).unwrap();
+        if signers.duress {
+            account.frozen = true;
+            return;
+        }
+        record_usage(&mut self.key_usage, rc.account_id, &signers.principals);
+
+        account.nonce += 1;
+        let keys: Vec<_> = self
+            .account_keys
+            .get(&rc.account_id)
+            .unwrap()
+            .iter()
+            .map(|key| key.0)
+            .collect();
+        let checkpoint = Checkpoint {
+            balance: account.balance,
+            nonce: account.nonce,
+            keys_hash: hash_keys(&keys),
+            block_height: rusk_abi::block_height(),
+            sequence: next_sequence(&mut self.event_sequence),
+        };
+        let checkpoints = self.checkpoints.entry(rc.account_id).or_default();
+        checkpoints.push(checkpoint.clone());
+        retain_history(checkpoints, account.history_retention);
+
+        rusk_abi::emit(
+            "checkpoint",
+            EventEnvelope::new(CheckpointEvent {
+                account_id: rc.account_id,
+                event_id: next_event_id(
+                    &mut self.event_indices,
+                    "checkpoint",
+                    rc.account_id,
+                    checkpoint.nonce,
+                ),
+                checkpoint,
+            }),
+        );
+
+        self.log_operation(rc.account_id, Vec::new(), Vec::new());
+    }
+
+    /// Sets the contract-wide parameters on the signatures of the governance
+    /// account, and emits the `configure` event.
+    fn configure(&mut self, c: Configure) {
+        let Some(account_id) = self.governance else {
+            panic!("{}", Error::NoGovernance);
+        };
+        let account = self.accounts.get(&account_id).unwrap();
+        if account.merged_into.is_some() {
+            panic!("{}", Error::AccountMerged);
+        }
+        if account.frozen {
+            panic!("{}", Error::AccountFrozen);
+        }
+        if c.nonce != account.nonce + 1 {
+            panic!("{}", Error::InvalidNonce);
+        }
+        if !c.config.is_valid() {
+            panic!("{}", Error::InvalidConfig);
+        }
+
+        let signers = self.authorize(
+            account_id,
+            OperationKind::Configure,
+            None,
+            &c.keys,
+            c.signature,
+            &c.cosignatures,
+            c.group_signature,
+            &c.signature_msg(),
+        );
+
+        // As with a transfer, a duress key freezes the account instead, with
+        // the call succeeding
+        let account = self.accounts.get_mut(&account_id).unwrap();
+        if signers.duress {
+            account.frozen = true;
+            return;
+        }
+        record_usage(&mut self.key_usage, account_id, &signers.principals);
+
+        account.nonce += 1;
+        self.config = c.config;
+
+        rusk_abi::emit(
+            "configure",
+            EventEnvelope::new(ConfigureEvent {
+                account_id,
+                keys: signers.keys,
+                cosigners: signers.cosigners,
+                group_signed: signers.group_signed,
+                config: c.config,
+                nonce: c.nonce,
+                block_height: rusk_abi::block_height(),
+                sequence: next_sequence(&mut self.event_sequence),
+                event_id: next_event_id(
+                    &mut self.event_indices,
+                    "configure",
+                    account_id,
+                    c.nonce,
+                ),
+            }),
+        );
+
+        self.log_operation(account_id, Vec::new(), Vec::new());
+    }
+
+    /// Pays several Moonlight accounts from an account at once, emitting a
+    /// `payroll` event for each row.
+    ///
+    /// The rows are paid in order, and any of them failing fails the whole
+    /// payroll.
+    fn payroll(&mut self, p: Payroll) {
+        check_unpaused(&self.config);
+        for row in &p.rows {
+            check_memo(&self.config, &row.memo);
+        }
+
+        let Some(account) = self.accounts.get(&p.account_id) else {
+            panic!("{}", Error::AccountNotFound);
+        };
+        if account.merged_into.is_some() {
+            panic!("{}", Error::AccountMerged);
+        }
+        if account.frozen {
+            panic!("{}", Error::AccountFrozen);
+        }
+        if account.locked {
+            panic!("{}", Error::AccountLocked);
+        }
+        if account.archived {
+            panic!("{}", Error::AccountArchived);
+        }
+        if p.rows.is_empty() {
+            panic!("{}", Error::EmptyPayroll);
+        }
+        for row in &p.rows {
+            check_hold(account, Some(&row.receiver));
+            check_licensed(account, &row.receiver);
+        }
+        let refund = gas_refund_amount(
+            p.gas_refund.as_ref(),
+            self.config.max_gas_refund,
+        );
+        let Some(total) = p
+            .rows
+            .iter()
+            .try_fold(refund, |total, row| total.checked_add(row.amount))
+            .filter(|&total| total <= account.balance)
+            .map(|total| total - refund)
+        else {
+            panic!("{}", Error::InsufficientBalance);
+        };
+        // Payrolls can't be queued, so none totalling what the account holds
+        // back can be made
+        check_unqueued(account, total);
+        if p.nonce != account.nonce + 1 {
+            panic!("{}", Error::InvalidNonce);
+        }
+        check_chain(account, p.chain_id);
+
+        let signers = self.authorize(
+            p.account_id,
+            OperationKind::Payroll,
+            Some(total),
+            &p.keys,
+            p.signature,
+            &p.cosignatures,
+            p.group_signature,
+            &p.signature_msg(),
+        );
+
+        // As with a transfer, a duress key freezes the account instead, with
+        // the call succeeding
+        let account_signers = self.account_signers(p.account_id);
+        let account = self.accounts.get_mut(&p.account_id).unwrap();
+        if signers.duress {
+            account.frozen = true;
+            return;
+        }
+        record_usage(&mut self.key_usage, p.account_id, &signers.principals);
+
+        check_transfer_window(account, account_signers, &signers);
+
+        if account.whitelist_enforced {
+            let whitelist = self.whitelists.get(&p.account_id).unwrap();
+            for row in &p.rows {
+                check_whitelisted(whitelist, row.receiver);
+            }
+        }
+
+        account.nonce += 1;
+
+        let rows = p.rows.len() as u32;
+        for (
+            row,
+            PayrollRow {
+                receiver,
+                amount,
+                memo,
+            },
+        ) in p.rows.into_iter().enumerate()
+        {
+            self.pay_or_defer(p.account_id, receiver, amount);
+
+            let account = self.accounts.get_mut(&p.account_id).unwrap();
+            account.balance -= amount;
+            self.total_balance -= amount;
+
+            let sequence = next_sequence(&mut self.event_sequence);
+            let memo_hash = hash_memo(sequence, &memo);
+            let (receiver, memo) = match account.private_events {
+                true => (
+                    Disclosure::Hashed(hash_receiver(sequence, &receiver)),
+                    Disclosure::Hashed(memo_hash),
+                ),
+                false => {
+                    (Disclosure::Revealed(receiver), Disclosure::Revealed(memo))
+                }
+            };
+            record_movement(
+                &mut self.movements,
+                p.account_id,
+                account.history_retention,
+                Movement {
+                    block_height: rusk_abi::block_height(),
+                    sequence,
+                    direction: Direction::Out,
+                    counterparty: Counterparty::Moonlight(receiver.clone()),
+                    amount,
+                    memo_hash: Some(memo_hash),
+                    balance_after: account.balance,
+                },
+            );
+
+            rusk_abi::emit(
+                "payroll",
+                EventEnvelope::new(PayrollEvent {
+                    account_id: p.account_id,
+                    keys: signers.keys.clone(),
+                    cosigners: signers.cosigners.clone(),
+                    group_signed: signers.group_signed,
+                    row: row as u32,
+                    rows,
+                    receiver,
+                    amount,
+                    memo,
+                    nonce: p.nonce,
+                    block_height: rusk_abi::block_height(),
+                    balance_after: account.balance,
+                    sequence,
+                    event_id: next_event_id(
+                        &mut self.event_indices,
+                        "payroll",
+                        p.account_id,
+                        p.nonce,
+                    ),
+                }),
+            );
+        }
+
+        self.record_outflow(p.account_id, total);
+        self.refund_gas(p.account_id, p.nonce, p.gas_refund);
+        self.log_operation(p.account_id, Vec::new(), Vec::new());
+    }
+
+    /// Performs an operation whose signing keys are given by a quorum
+    /// certificate, naming them by their place among the keys of the account.
+    ///
+    /// Once the keys are expanded, the operation is performed as if they'd
+    /// been named, emitting the same events.
+    fn certified(&mut self, c: CertifiedOperation) {
+        let account_id = c.operation.account_id();
+        let Some(account) = self.accounts.get(&account_id) else {
+            panic!("{}", Error::AccountNotFound);
+        };
+        if account.merged_into.is_some() {
+            panic!("{}", Error::AccountMerged);
+        }
+
+        let account_keys: Vec<_> = self
+            .account_keys
+            .get(&account_id)
+            .unwrap()
+            .iter()
+            .map(|key| key.0)
+            .collect();
+        let Some(keys) = c.keys(&account_keys) else {
+            panic!("{}", Error::InvalidCertificate);
+        };
+
+        let mut operation = c.operation;
+        operation.set_keys(keys);
+        match operation {
+            Operation::Transfer(t) => self.transfer(t),
+            Operation::ChangeAccount(c) => self.change_account(c),
+            Operation::SplitAccount(s) => self.split_account(s),
+            Operation::Payroll(p) => self.payroll(p),
+            Operation::TransferAndChange(tc) => self.transfer_and_change(tc),
+        }
+    }
+
+    /// Transfers an amount from an account and changes it, under a single
+    /// signature and nonce.
+    ///
+    /// The transfer is made as by `transfer`, under the account as it was
+    /// signed for, and the changes are then applied as by `change_account`.
+    /// Either failing fails both.
+    fn transfer_and_change(&mut self, tc: TransferAndChange) {
+        check_unpaused(&self.config);
+        check_memo(&self.config, &tc.memo);
+
+        let Some(account) = self.accounts.get(&tc.account_id) else {
+            panic!("{}", Error::AccountNotFound);
+        };
+        if account.merged_into.is_some() {
+            panic!("{}", Error::AccountMerged);
+        }
+        if account.frozen {
+            panic!("{}", Error::AccountFrozen);
+        }
+        if account.locked {
+            panic!("{}", Error::AccountLocked);
+        }
+        if account.archived {
+            panic!("{}", Error::AccountArchived);
+        }
+        check_hold(account, Some(&tc.receiver));
+        check_licensed(account, &tc.receiver);
+        let refund = gas_refund_amount(
+            tc.gas_refund.as_ref(),
+            self.config.max_gas_refund,
+        );
+        let Some(available) = account.balance.checked_sub(refund) else {
+            panic!("{}", Error::InsufficientBalance);
+        };
+        let amount = match tc.amount {
+            TRANSFER_ALL => available,
+            amount => amount,
+        };
+        if amount > available {
+            panic!("{}", Error::InsufficientBalance);
+        }
+        check_unqueued(account, amount);
+        if tc.nonce != account.nonce + 1 {
+            panic!("{}", Error::InvalidNonce);
+        }
+        check_chain(account, tc.chain_id);
+
+        let signers = self.authorize(
+            tc.account_id,
+            OperationKind::TransferAndChange,
+            Some(amount),
+            &tc.keys,
+            tc.signature,
+            &tc.cosignatures,
+            tc.group_signature,
+            &tc.signature_msg(),
+        );
+
+        // As with a transfer, a duress key freezes the account instead, with
+        // the call succeeding
+        if signers.duress {
+            self.accounts.get_mut(&tc.account_id).unwrap().frozen = true;
+            return;
+        }
+        record_usage(&mut self.key_usage, tc.account_id, &signers.principals);
+
+        let account = self.accounts.get(&tc.account_id).unwrap();
+        check_transfer_window(
+            account,
+            self.account_signers(tc.account_id),
+            &signers,
+        );
+
+        if account.whitelist_enforced {
+            let whitelist = self.whitelists.get(&tc.account_id).unwrap();
+            check_whitelisted(whitelist, tc.receiver);
+        }
+
+        // Both events list the same signers
+        let change_signers = signers.clone();
+        let payment = Payment {
+            receiver: tc.receiver,
+            amount,
+            memo: tc.memo,
+            reference: None,
+            category: None,
+        };
+        self.pay_out(tc.account_id, payment, tc.nonce, signers);
+        let (added_keys, removed_keys) = self.apply_changes(
+            tc.account_id,
+            tc.nonce,
+            tc.changes,
+            change_signers,
+            true,
+        );
+
+        self.record_outflow(tc.account_id, amount);
+        self.refund_gas(tc.account_id, tc.nonce, tc.gas_refund);
+        self.log_operation(tc.account_id, added_keys, removed_keys);
+    }
+
+    /// Records the state an operation left an account in, under the nonce it
+    /// used up, together with the keys it added and removed.
+    fn log_operation(
+        &mut self,
+        account_id: u64,
+        added_keys: Vec<bls::PublicKey>,
+        removed_keys: Vec<bls::PublicKey>,
+    ) {
+        let account = self.accounts.get_mut(&account_id).unwrap();
+        account.last_active = rusk_abi::block_height();
+        let ops = self.operations.entry(account_id).or_default();
+        ops.push(OperationRecord {
+            nonce: account.nonce,
+            block_height: rusk_abi::block_height(),
+            balance: account.balance,
+            threshold: account.threshold,
+            added_keys,
+            removed_keys,
+        });
+        retain_history(ops, account.history_retention);
+    }
+
+    /// Returns the number of keys moving the given amount out of an account
+    /// needs under its transfer tiers, if the amount falls in one.
+    fn tier_threshold(&self, account_id: u64, amount: u64) -> Option<u32> {
+        self.transfer_tiers
+            .get(&account_id)
+            .and_then(|tiers| TransferTier::threshold_for(tiers, amount))
+    }
+
+    /// Returns the number of signers the keys and co-signers of an account
+    /// count for, out of which its supermajority is reckoned.
+    fn account_signers(&self, account_id: u64) -> usize {
+        weight_of(
+            self.key_weights.get(&account_id),
+            self.account_keys.get(&account_id).unwrap(),
+            self.cosigners.get(&account_id).unwrap().len(),
+        )
+    }
+
+    /// Checks that the given keys and co-signers - or the group key - may
+    /// operate on the account, and that they signed the given message,
+    /// returning the signers and whether a duress key was among them.
+    ///
+    /// These are the same checks a transfer makes, against the threshold of
+    /// the account for operations of the given kind, or that of the tier the
+    /// amount they move out of the account falls in if higher.
+    #[allow(clippy::too_many_arguments)]
+    fn authorize(
+        &mut self,
+        account_id: u64,
+        op: OperationKind,
+        amount: Option<u64>,
+        keys: &[bls::PublicKey],
+        signature: bls::MultisigSignature,
+        cosignatures: &[Cosignature],
+        group_signature: Option<bls::Signature>,
+        msg: &[u8],
+    ) -> Signers {
+        let account = self.accounts.get(&account_id).unwrap();
+
+        let group_signed = match (account.group_key, group_signature) {
+            (Some(group_key), Some(signature)) => {
+                if !keys.is_empty() || !cosignatures.is_empty() {
+                    panic!("{}", Error::GroupSignatureRequired);
+                }
+                if !verify_group(
+                    &mut self.keys_verified,
+                    msg.to_vec(),
+                    group_key,
+                    signature,
+                ) {
+                    panic!("{}", Error::InvalidSignature);
+                }
+                true
+            }
+            (None, Some(_)) => panic!("{}", Error::NoGroupKey),
+            (Some(_), None) => panic!("{}", Error::GroupSignatureRequired),
+            (None, None) => false,
+        };
+
+        let mut key_set = BTreeSet::new();
+        let mut duress = false;
+        let account_keys = self.account_keys.get(&account_id).unwrap();
+        let duress_keys = self.duress_keys.get(&account_id).unwrap();
+        let cosigners = self.cosigners.get(&account_id).unwrap();
+
+        let delegations = self.delegations.get(&account_id);
+        let pending_keys = self.pending_keys.get(&account_id);
+        let key_expiries = self.key_expiries.get(&account_id);
+        let key_roles = self.key_roles.get(&account_id);
+        let mut principals = BTreeSet::new();
+        for key in keys {
+            let key = WrappedPublicKey(*key);
+
+            if !key_set.insert(key) {
+                panic!("{}", Error::DuplicateKey);
+            }
+
+            if duress_keys.contains(&key) {
+                duress = true;
+                continue;
+            }
+
+            let Some(principal) = principal_of(account_keys, delegations, key)
+            else {
+                panic!("{}", Error::UnknownKey);
+            };
+
+            if self.compromised_keys.contains(&key)
+                || self.compromised_keys.contains(&principal)
+            {
+                panic!("{}", Error::KeyCompromised);
+            }
+            check_usable(pending_keys, &principal);
+            check_unexpired(key_expiries, &principal);
+            check_role(key_roles, &principal, op);
+
+            // A key and its delegate sign for the same key of the account
+            if !principals.insert(principal) {
+                panic!("{}", Error::DuplicateKey);
+            }
+        }
+
+        let mut cosigner_set = BTreeSet::new();
+        for cosignature in cosignatures {
+            if !cosigner_set.insert(cosignature.key) {
+                panic!("{}", Error::DuplicateKey);
+            }
+            if !cosigners.contains(&cosignature.key) {
+                panic!("{}", Error::UnknownKey);
+            }
+            if self.compromised_cosigners.contains(&cosignature.key) {
+                panic!("{}", Error::KeyCompromised);
+            }
+        }
+
+        let liveness = self.liveness.get(&account_id);
+        let weights = self.key_weights.get(&account_id);
+        let weight = weight_of(
+            weights,
+            &principals,
+            keys.len() - principals.len() + cosignatures.len(),
+        );
+        let threshold = amount
+            .and_then(|amount| self.tier_threshold(account_id, amount))
+            .map_or(account.threshold_of(op), |tier| {
+                tier.max(account.threshold_of(op))
+            });
+        let required = required_signers(
+            account,
+            threshold,
+            liveness,
+            weights,
+            cosigners.len(),
+        );
+        if !group_signed && weight < required {
+            panic!("{}", Error::ThresholdNotMet);
+        }
+
+        for cosignature in cosignatures {
+            if !verify_cosignature(
+                &mut self.cosignatures_verified,
+                msg,
+                cosignature,
+            ) {
+                panic!("{}", Error::InvalidSignature);
+            }
+        }
+        if !keys.is_empty()
+            && !verify_multisig(
+                &mut self.keys_verified,
+                msg.to_vec(),
+                keys.to_vec(),
+                signature,
+            )
+        {
+            panic!("{}", Error::InvalidSignature);
+        }
+
+        Signers {
+            keys: key_set.into_iter().map(|k| k.0).collect(),
+            principals: principals.into_iter().collect(),
+            cosigners: cosigner_set.into_iter().collect(),
+            weight,
+            group_signed,
+            duress,
+        }
+    }
+
+    /// Pays the receiver of a withdrawal awaiting claim, on the signature of
+    /// the receiver.
+    ///
+    /// The amount left the account when the withdrawal was recorded, so the
+    /// claim goes through whatever became of the account since - merged,
+    /// frozen, locked or archived.
+    fn claim_withdrawal(&mut self, c: ClaimWithdrawal) {
+        check_unpaused(&self.config);
+
+        let Some(withdrawal) = self.withdrawals.get(&c.claim_id) else {
+            panic!("{}", Error::WithdrawalNotFound);
+        };
+        if WrappedPublicKey(withdrawal.receiver) != WrappedPublicKey(c.receiver)
+        {
+            panic!("{}", Error::NotWithdrawalReceiver);
+        }
+
+        let msg = ClaimWithdrawal::signature_msg(c.claim_id);
+        if !verify_multisig(
+            &mut self.keys_verified,
+            msg,
+            vec![c.receiver],
+            c.signature,
+        ) {
+            panic!("{}", Error::InvalidSignature);
+        }
+
+        let withdrawal = self.withdrawals.remove(&c.claim_id).unwrap();
+        if rusk_abi::call::<_, ()>(
+            TRANSFER_CONTRACT,
+            "contract_to_account",
+            &ContractToAccount {
+                account: withdrawal.receiver,
+                value: withdrawal.amount,
+            },
+        )
+        .is_err()
+        {
+            panic!("{}", Error::TransferFailed);
+        }
+
+        let account = self.accounts.get(&withdrawal.account_id).unwrap();
+        let sequence = next_sequence(&mut self.event_sequence);
+        let receiver = match account.private_events {
+            true => Disclosure::Hashed(hash_receiver(
+                sequence,
+                &withdrawal.receiver,
+            )),
+            false => Disclosure::Revealed(withdrawal.receiver),
+        };
+
+        rusk_abi::emit(
+            "withdrawal_claimed",
+            EventEnvelope::new(WithdrawalClaimedEvent {
+                claim_id: c.claim_id,
+                account_id: withdrawal.account_id,
+                receiver,
+                amount: withdrawal.amount,
+                block_height: rusk_abi::block_height(),
+                sequence,
+                event_id: next_event_id(
+                    &mut self.event_indices,
+                    "withdrawal_claimed",
+                    withdrawal.account_id,
+                    account.nonce,
+                ),
+            }),
+        );
+    }
+
+    /// Marks a key as compromised, on the signature of the key itself.
+    ///
+    /// The key is refused as a signer from then on, and can't be added to
+    /// any account. The accounts using it are left as they are, and are
+    /// expected to rotate it out.
+    fn mark_compromised(&mut self, mc: MarkCompromised) {
+        let msg = MarkCompromised::signature_msg(&mc.key);
+        if !verify_multisig(
+            &mut self.keys_verified,
+            msg,
+            vec![mc.key],
+            mc.signature,
+        ) {
+            panic!("{}", Error::InvalidSignature);
+        }
+
+        let key = WrappedPublicKey(mc.key);
+        if !self.compromised_keys.insert(key) {
+            panic!("{}", Error::KeyCompromised);
+        }
+
+        let account_ids = self
+            .key_accounts
+            .get(&key)
+            .map(|ids| ids.iter().copied().collect())
+            .unwrap_or_default();
+
+        rusk_abi::emit(
+            "key_compromised",
+            EventEnvelope::new(KeyCompromisedEvent {
+                key: mc.key,
+                account_ids,
+                block_height: rusk_abi::block_height(),
+                sequence: next_sequence(&mut self.event_sequence),
+                event_id: next_event_id(
+                    &mut self.event_indices,
+                    "key_compromised",
+                    0,
+                    0,
+                ),
+            }),
+        );
+    }
+
+    /// Marks a co-signer key as compromised, on the signature of the key
+    /// itself, with the same effect `mark_compromised` has on a key.
+    fn mark_cosigner_compromised(&mut self, mc: MarkCosignerCompromised) {
+        let msg = MarkCosignerCompromised::signature_msg(&mc.key);
+        let cosignature = Cosignature {
+            key: mc.key,
+            signature: mc.signature,
+        };
+        if !verify_cosignature(
+            &mut self.cosignatures_verified,
+            &msg,
+            &cosignature,
+        ) {
+            panic!("{}", Error::InvalidSignature);
+        }
+
+        if !self.compromised_cosigners.insert(mc.key) {
+            panic!("{}", Error::KeyCompromised);
+        }
+
+        let account_ids = self
+            .cosigners
+            .iter()
+            .filter(|(_, cosigners)| cosigners.contains(&mc.key))
+            .map(|(account_id, _)| *account_id)
+            .collect();
+
+        rusk_abi::emit(
+            "cosigner_compromised",
+            EventEnvelope::new(CosignerCompromisedEvent {
+                key: mc.key,
+                account_ids,
+                block_height: rusk_abi::block_height(),
+                sequence: next_sequence(&mut self.event_sequence),
+                event_id: next_event_id(
+                    &mut self.event_indices,
+                    "cosigner_compromised",
+                    0,
+                    0,
+                ),
+            }),
+        );
+    }
+
+    /// Replaces a key with another across every account using it, on the
+    /// signature of the key replaced and the change of each account.
+    ///
+    /// The changes are applied as by `change_account`, emitting the same
+    /// events, and any of them failing fails the whole replacement.
+    fn replace_key(&mut self, rk: ReplaceKey) {
+        let msg = ReplaceKey::signature_msg(&rk.old_key, &rk.new_key);
+        if !verify_multisig(
+            &mut self.keys_verified,
+            msg,
+            vec![rk.old_key],
+            rk.signature,
+        ) {
+            panic!("{}", Error::InvalidSignature);
+        }
+        if rk.old_key == rk.new_key {
+            panic!("{}", Error::InvalidKeyReplacement);
+        }
+
+        // The accounts changed must be exactly those using the key, so that
+        // none is left behind
+        let key = WrappedPublicKey(rk.old_key);
+        let account_ids = match self.key_accounts.get(&key) {
+            Some(ids) if !ids.is_empty() => ids.clone(),
+            _ => panic!("{}", Error::KeyNotUsed),
+        };
+        let expected = ReplaceKey::account_changes(rk.old_key, rk.new_key);
+        let mut changed_ids = BTreeSet::new();
+        for c in &rk.changes {
+            if c.changes != expected {
+                panic!("{}", Error::InvalidKeyReplacement);
+            }
+            if !changed_ids.insert(c.account_id) {
+                panic!("{}", Error::KeyReplacementIncomplete);
+            }
+        }
+        if changed_ids != account_ids {
+            panic!("{}", Error::KeyReplacementIncomplete);
+        }
+
+        for c in rk.changes {
+            self.change_account(c);
+        }
+    }
+
+    /// Restores the accounts of a previous version of the contract, when
+    /// migrating from it.
+    ///
+    /// Only the host may migrate, and only into a contract with no accounts,
+    /// so accounts can never be overwritten.
+    fn migrate(&mut self, migration: Migration) {
+        if rusk_abi::caller().is_some() || !self.accounts.is_empty() {
+            panic!("{}", Error::MigrationNotAllowed);
+        }
+
+        for account in migration.accounts {
+            let mut account_keys = BTreeSet::new();
+            for key in account.keys {
+                let key = WrappedPublicKey(key);
+                account_keys.insert(key);
+                self.key_accounts
+                    .entry(key)
+                    .or_default()
+                    .insert(account.account_id);
+            }
+
+            self.account_keys.insert(account.account_id, account_keys);
+            self.duress_keys.insert(
+                account.account_id,
+                account
+                    .duress_keys
+                    .into_iter()
+                    .map(WrappedPublicKey)
+                    .collect(),
+            );
+            self.cosigners.insert(
+                account.account_id,
+                account.cosigners.into_iter().collect(),
+            );
+            self.whitelists.insert(
+                account.account_id,
+                account
+                    .whitelist
+                    .into_iter()
+                    .map(|e| (WrappedPublicKey(e.receiver), e.usable_from))
+                    .collect(),
+            );
+            self.guardians.insert(
+                account.account_id,
+                account
+                    .guardians
+                    .into_iter()
+                    .map(WrappedPublicKey)
+                    .collect(),
+            );
+            self.observers.insert(
+                account.account_id,
+                account
+                    .observers
+                    .into_iter()
+                    .map(WrappedPublicKey)
+                    .collect(),
+            );
+            if !account.budget_draws.is_empty() {
+                self.budget_draws.insert(
+                    account.account_id,
+                    account
+                        .budget_draws
+                        .iter()
+                        .map(|d| (d.child_id, (0, 0, d.held)))
+                        .collect(),
+                );
+            }
+            if !account.operations.is_empty() {
+                self.operations
+                    .insert(account.account_id, account.operations);
+            }
+            if !account.movements.is_empty() {
+                self.movements.insert(account.account_id, account.movements);
+            }
+            if !account.checkpoints.is_empty() {
+                self.checkpoints
+                    .insert(account.account_id, account.checkpoints);
+            }
+            if let Some(alias) = &account.data.alias {
+                self.aliases.insert(alias.clone(), account.account_id);
+            }
+            if account.data.liveness_policy.is_some() {
+                self.liveness.insert(
+                    account.account_id,
+                    account
+                        .liveness
+                        .into_iter()
+                        .map(|l| (WrappedPublicKey(l.key), l.attested_at))
+                        .collect(),
+                );
+            }
+            if !account.pending_keys.is_empty() {
+                self.pending_keys.insert(
+                    account.account_id,
+                    account
+                        .pending_keys
+                        .into_iter()
+                        .map(|p| (WrappedPublicKey(p.key), p.usable_from))
+                        .collect(),
+                );
+            }
+            if !account.key_expiries.is_empty() {
+                self.key_expiries.insert(
+                    account.account_id,
+                    account
+                        .key_expiries
+                        .into_iter()
+                        .map(|e| (WrappedPublicKey(e.key), e.valid_until))
+                        .collect(),
+                );
+            }
+            let key_usage: BTreeMap<_, _> = account
+                .key_usage
+                .into_iter()
+                .filter_map(|u| {
+                    let last_signed = u.last_signed?;
+                    Some((WrappedPublicKey(u.key), (u.operations, last_signed)))
+                })
+                .collect();
+            if !key_usage.is_empty() {
+                self.key_usage.insert(account.account_id, key_usage);
+            }
+            if !account.key_weights.is_empty() {
+                self.key_weights.insert(
+                    account.account_id,
+                    account
+                        .key_weights
+                        .into_iter()
+                        .map(|w| (WrappedPublicKey(w.key), w.weight))
+                        .collect(),
+                );
+            }
+            if !account.key_roles.is_empty() {
+                self.key_roles.insert(
+                    account.account_id,
+                    account
+                        .key_roles
+                        .into_iter()
+                        .map(|r| (WrappedPublicKey(r.key), r.role))
+                        .collect(),
+                );
+            }
+            if !account.transfer_tiers.is_empty() {
+                self.transfer_tiers
+                    .insert(account.account_id, account.transfer_tiers);
+            }
+            if let Some(recovery) = account.pending_recovery {
+                self.pending_recoveries.insert(account.account_id, recovery);
+            }
+            if !account.allowances.is_empty() {
+                self.allowances.insert(
+                    account.account_id,
+                    account
+                        .allowances
+                        .iter()
+                        .map(|a| (WrappedPublicKey(a.key), a.amount))
+                        .collect(),
+                );
+            }
+            if !account.queued_transfers.is_empty() {
+                self.queued_transfers.insert(
+                    account.account_id,
+                    account
+                        .queued_transfers
+                        .into_iter()
+                        .map(|queued| (queued.nonce, queued))
+                        .collect(),
+                );
+            }
+            if !account.delegations.is_empty() {
+                self.delegations.insert(
+                    account.account_id,
+                    account
+                        .delegations
+                        .into_iter()
+                        .map(|kd| (WrappedPublicKey(kd.key), kd))
+                        .collect(),
+                );
+            }
+            for tag in &account.data.tags {
+                self.tags
+                    .entry(tag.clone())
+                    .or_default()
+                    .insert(account.account_id);
+            }
+            self.total_balance += account.data.balance;
+            self.accounts.insert(account.account_id, account.data);
+        }
+        self.custody_cap = migration.custody_cap;
+        self.min_deposit = migration.min_deposit;
+
+        for (template_id, template) in (1..).zip(migration.templates) {
+            self.templates.insert(template_id, template);
+        }
+        for key in migration.compromised_keys {
+            self.compromised_keys.insert(WrappedPublicKey(key));
+        }
+        self.compromised_cosigners
+            .extend(migration.compromised_cosigners);
+        for withdrawal in migration.withdrawals {
+            self.withdrawals.insert(withdrawal.claim_id, withdrawal);
+        }
+        self.withdrawal_count = migration.withdrawal_count;
+        self.storage_fees = migration.storage_fees;
+
+        // The governance account must be among those migrated, and in use
+        if let Some(id) = migration.governance {
+            let Some(account) = self.accounts.get(&id) else {
+                panic!("{}", Error::AccountNotFound);
+            };
+            if account.merged_into.is_some() {
+                panic!("{}", Error::AccountMerged);
+            }
+            if account.archived {
+                panic!("{}", Error::AccountArchived);
+            }
+        }
+        if !migration.config.is_valid() {
+            panic!("{}", Error::InvalidConfig);
+        }
+        self.governance = migration.governance;
+        self.config = migration.config;
+        self.event_sequence = migration.event_sequence;
+    }
+
+    /// Returns the balance and nonce of the account with the given ID.
+    fn account(&self, id: u64) -> AccountData {
+        self.accounts
+            .get(&id)
+            .unwrap_or(&AccountData {
+                balance: 0,
+                threshold: 0,
+                description: String::new(),
+                nonce: 0,
+                private_events: false,
+                frozen: false,
+                group_key: None,
+                whitelist_enforced: false,
+                velocity_limit: None,
+                locked: false,
+                alias: None,
+                merged_into: None,
+                chain_id: 0,
+                balance_cap: None,
+                tags: Vec::new(),
+                liveness_policy: None,
+                supermajority: None,
+                key_addition_policy: None,
+                transfer_window: None,
+                categories: Vec::new(),
+                child_budgets: Vec::new(),
+                archived: false,
+                claimable_withdrawals: false,
+                history_retention: None,
+                compliance: None,
+                on_hold: false,
+                license_policy: None,
+                operation_thresholds: Vec::new(),
+                recovery_policy: None,
+                recovery_nonce: 0,
+                inactivity_policy: None,
+                last_active: 0,
+                queue_policy: None,
+            })
+            .clone()
+    }
+
+    /// Returns the ID of the account claiming the given alias, if any.
+    fn resolve_alias(&self, alias: String) -> Option<u64> {
+        self.aliases.get(&alias).copied()
+    }
+
+    /// Returns the recovery of the account with the given ID its guardians
+    /// started, if any.
+    fn pending_recovery(&self, id: u64) -> Option<PendingRecovery> {
+        self.pending_recoveries.get(&id).cloned()
+    }
+
+    /// Returns the keys of the account with the given ID with an allowance
+    /// left, and what is left of it.
+    fn allowances(&self, id: u64) -> Vec<KeyAllowance> {
+        self.allowances
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .map(|(key, &amount)| KeyAllowance { key: key.0, amount })
+            .collect()
+    }
+
+    /// Returns the transfers queued by the account with the given ID, in the
+    /// order of their nonces.
+    fn queued_transfers(&self, id: u64) -> Vec<QueuedTransfer> {
+        self.queued_transfers
+            .get(&id)
+            .map(|queued| queued.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the account template with the given ID, if any.
+    fn template(&self, template_id: u64) -> Option<AccountTemplate> {
+        self.templates.get(&template_id).cloned()
+    }
+
+    /// Returns whether the given key is marked as compromised.
+    fn is_compromised(&self, key: bls::PublicKey) -> bool {
+        self.compromised_keys.contains(&WrappedPublicKey(key))
+    }
+
+    /// Returns the sequence number of the last event emitted.
+    fn event_sequence(&self) -> u64 {
+        self.event_sequence
+    }
+
+    /// Returns the total balance of all accounts.
+    fn total_balance(&self) -> u64 {
+        self.total_balance
+    }
+
+    /// Returns the custody cap of the contract, if any.
+    fn custody_cap(&self) -> Option<u64> {
+        self.custody_cap
+    }
+
+    /// Returns the minimum deposit of the contract, if any.
+    fn min_deposit(&self) -> Option<u64> {
+        self.min_deposit
+    }
+
+    /// Returns the governance account of the contract, if any.
+    fn governance(&self) -> Option<u64> {
+        self.governance
+    }
+
+    /// Returns the contract-wide parameters.
+    fn config(&self) -> ContractConfig {
+        self.config
+    }
+
+    /// Returns the total balance of all accounts, of the withdrawals awaiting
+    /// claim and of the storage fees kept, together with the balance the
+    /// contract holds in the transfer contract.
+    fn custody(&self) -> CustodyReport {
+        let held: u64 = rusk_abi::call(
+            TRANSFER_CONTRACT,
+            "contract_balance",
+            &rusk_abi::self_id(),
+        )
+        .expect("Querying the transfer contract should succeed");
+        let pending_withdrawals: u64 =
+            self.withdrawals.values().map(|w| w.amount).sum();
+
+        CustodyReport {
+            total_balance: self.total_balance,
+            pending_withdrawals,
+            storage_fees: self.storage_fees,
+            held,
+            delta: held as i128
+                - self.total_balance as i128
+                - pending_withdrawals as i128
+                - self.storage_fees as i128,
+        }
+    }
+
+    /// Returns the usage of the contract since it was deployed.
+    fn stats(&self) -> UsageStats {
+        UsageStats {
+            calls: self
+                .calls
+                .iter()
+                .map(|(entrypoint, calls)| EntrypointCalls {
+                    entrypoint: String::from(*entrypoint),
+                    calls: *calls,
+                })
+                .collect(),
+            keys_verified: self.keys_verified,
+            cosignatures_verified: self.cosignatures_verified,
+        }
+    }
+
+    /// Returns the withdrawal awaiting claim under the given claim ID, if any.
+    fn withdrawal(&self, claim_id: u64) -> Option<PendingWithdrawal> {
+        self.withdrawals.get(&claim_id).cloned()
+    }
+
+    /// Returns the claim ID of the last withdrawal recorded.
+    fn withdrawal_count(&self) -> u64 {
+        self.withdrawal_count
+    }
+
+    /// Feeds the public keys used by the account with the given ID.
+    fn account_keys(&self, id: u64) {
+        for key in self
+            .account_keys
+            .get(&id)
+            .cloned()
+            .unwrap_or(BTreeSet::new())
+        {
+            rusk_abi::feed(key);
+        }
+    }
+
+    /// Feeds the duress keys of the account with the given ID.
+    fn duress_keys(&self, id: u64) {
+        for key in self.duress_keys.get(&id).into_iter().flatten() {
+            rusk_abi::feed(key.0);
+        }
+    }
+
+    /// Feeds the co-signers of the account with the given ID.
+    fn cosigners(&self, id: u64) {
+        for key in self.cosigners.get(&id).into_iter().flatten() {
+            rusk_abi::feed(*key);
+        }
+    }
+
+    /// Feeds the receivers whitelisted by the account with the given ID.
+    fn whitelist(&self, id: u64) {
+        for (key, usable_from) in self.whitelists.get(&id).into_iter().flatten()
+        {
+            rusk_abi::feed(WhitelistEntry {
+                receiver: key.0,
+                usable_from: *usable_from,
+            });
+        }
+    }
+
+    /// Feeds the guardians of the account with the given ID.
+    fn guardians(&self, id: u64) {
+        for key in self.guardians.get(&id).into_iter().flatten() {
+            rusk_abi::feed(key.0);
+        }
+    }
+
+    /// Feeds the observers of the account with the given ID.
+    fn observers(&self, id: u64) {
+        for key in self.observers.get(&id).into_iter().flatten() {
+            rusk_abi::feed(key.0);
+        }
+    }
+
+    /// Feeds the amount transferred under each spending category of the
+    /// account with the given ID within the current window of its budget.
+    fn category_spend(&self, id: u64) {
+        let Some(account) = self.accounts.get(&id) else {
+            return;
+        };
+        let block_height = rusk_abi::block_height();
+        let spending = self.category_spending.get(&id);
+        for category in &account.categories {
+            let window_start = block_height - block_height % category.window;
+            let spent = spending
+                .and_then(|spending| spending.get(&category.name))
+                .filter(|(start, _)| *start == window_start)
+                .map_or(0, |(_, spent)| *spent);
+            rusk_abi::feed(CategorySpend {
+                name: category.name.clone(),
+                spent,
+            });
+        }
+    }
+
+    /// Feeds the draws of each child of the account with the given ID on the
+    /// budget it allocated them, including the children it no longer
+    /// allocates a budget to but that still hold what they drew.
+    fn budget_draws(&self, id: u64) {
+        let Some(account) = self.accounts.get(&id) else {
+            return;
+        };
+        let block_height = rusk_abi::block_height();
+        for (child_id, (start, drawn, held)) in
+            self.budget_draws.get(&id).into_iter().flatten()
+        {
+            let drawn = account
+                .child_budgets
+                .iter()
+                .find(|b| b.child_id == *child_id)
+                .filter(|b| *start == block_height - block_height % b.window)
+                .map_or(0, |_| *drawn);
+            rusk_abi::feed(BudgetDraw {
+                child_id: *child_id,
+                drawn,
+                held: *held,
+            });
+        }
+    }
+
+    /// Feeds the last attestation of each key of the account with the given
+    /// ID, if it has a liveness policy.
+    fn key_liveness(&self, id: u64) {
+        for (key, attested_at) in self.liveness.get(&id).into_iter().flatten() {
+            rusk_abi::feed(KeyLiveness {
+                key: key.0,
+                attested_at: *attested_at,
+            });
+        }
+    }
+
+    /// Feeds the delegation registered by each key of the account with the
+    /// given ID, together with its nonce.
+    fn delegations(&self, id: u64) {
+        for kd in self
+            .delegations
+            .get(&id)
+            .into_iter()
+            .flat_map(|d| d.values())
+        {
+            rusk_abi::feed(kd.clone());
+        }
+    }
+
+    /// Feeds the keys of the account with the given ID that were added under
+    /// a delay that hasn't yet passed, with the height they become usable
+    /// from.
+    fn pending_keys(&self, id: u64) {
+        let block_height = rusk_abi::block_height();
+        for (key, usable_from) in
+            self.pending_keys.get(&id).into_iter().flatten()
+        {
+            if *usable_from > block_height {
+                rusk_abi::feed(PendingKey {
+                    key: key.0,
+                    usable_from: *usable_from,
+                });
+            }
+        }
+    }
+
+    /// Feeds the keys of the account with the given ID that lapse, with the
+    /// height of the last block they can sign in. Keys that already lapsed
+    /// are fed too, for the account to remove them.
+    fn key_expiries(&self, id: u64) {
+        for (key, valid_until) in
+            self.key_expiries.get(&id).into_iter().flatten()
+        {
+            rusk_abi::feed(KeyExpiry {
+                key: key.0,
+                valid_until: *valid_until,
+            });
+        }
+    }
+
+    /// Feeds the keys of the account with the given ID weighing other than
+    /// one.
+    fn key_weights(&self, id: u64) {
+        for (key, weight) in self.key_weights.get(&id).into_iter().flatten() {
+            rusk_abi::feed(KeyWeight {
+                key: key.0,
+                weight: *weight,
+            });
+        }
+    }
+
+    /// Feeds the keys of the account with the given ID that aren't admins,
+    /// with their role.
+    fn key_roles(&self, id: u64) {
+        for (key, role) in self.key_roles.get(&id).into_iter().flatten() {
+            rusk_abi::feed(RoleAssignment {
+                key: key.0,
+                role: *role,
+            });
+        }
+    }
+
+    /// Feeds the tiers of the transfers of the account with the given ID, in
+    /// the order of their ceilings.
+    fn transfer_tiers(&self, id: u64) {
+        for tier in self.transfer_tiers.get(&id).into_iter().flatten() {
+            rusk_abi::feed(*tier);
+        }
+    }
+
+    /// Feeds how many operations each key of the account with the given ID
+    /// signed, and when it last did. Keys that never signed are fed too, so
+    /// that inactive keys stand out.
+    fn key_usage(&self, id: u64) {
+        let usage = self.key_usage.get(&id);
+        for key in self.account_keys.get(&id).into_iter().flatten() {
+            let used = usage.and_then(|usage| usage.get(key));
+            rusk_abi::feed(KeyUsage {
+                key: key.0,
+                operations: used.map_or(0, |(operations, _)| *operations),
+                last_signed: used.map(|(_, last_signed)| *last_signed),
+            });
+        }
+    }
+
+    /// Feeds the IDs of the accounts carrying the given tag, in ascending
+    /// order.
+    fn tagged_accounts(&self, tag: String) {
+        for account_id in self.tags.get(&tag).into_iter().flatten() {
+            rusk_abi::feed(*account_id);
+        }
+    }
+
+    /// Feeds the checkpoints recorded of the account with the given ID, in
+    /// the order they were.
+    fn checkpoints(&self, id: u64) {
+        for checkpoint in self.checkpoints.get(&id).into_iter().flatten() {
+            rusk_abi::feed(checkpoint.clone());
+        }
+    }
+
+    /// Returns the operations of an account still retained, in the order of
+    /// their nonces.
+    fn operations(&self, account_id: u64) -> Vec<OperationRecord> {
+        self.operations
+            .get(&account_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Returns what changed in an account between two of its nonces, or
+    /// `None` if either isn't retained, or the second precedes the first.
+    fn state_diff(&self, q: StateDiffQuery) -> Option<StateDiff> {
+        if q.to_nonce < q.from_nonce {
+            return None;
+        }
+        let ops = self.operations.get(&q.account_id)?;
+
+        let start = ops.partition_point(|op| op.nonce < q.from_nonce);
+        let end = ops.partition_point(|op| op.nonce <= q.to_nonce);
+        let from = ops.get(start).filter(|op| op.nonce == q.from_nonce)?;
+        let to = ops[..end].last().filter(|op| op.nonce == q.to_nonce)?;
+
+        // Keys added and removed again in between cancel out, removals being
+        // taken first as a change may remove a key and add it back
+        let mut added = BTreeSet::new();
+        let mut removed = BTreeSet::new();
+        for op in &ops[start + 1..end] {
+            for key in &op.removed_keys {
+                let key = WrappedPublicKey(*key);
+                if !added.remove(&key) {
+                    removed.insert(key);
+                }
+            }
+            for key in &op.added_keys {
+                let key = WrappedPublicKey(*key);
+                if !removed.remove(&key) {
+                    added.insert(key);
+                }
+            }
+        }
+
+        Some(StateDiff {
+            balance_before: from.balance,
+            balance_after: to.balance,
+            threshold_before: from.threshold,
+            threshold_after: to.threshold,
+            added_keys: added.into_iter().map(|key| key.0).collect(),
+            removed_keys: removed.into_iter().map(|key| key.0).collect(),
+        })
+    }
+
+    /// Previews a change to an account, returning the keys and threshold it
+    /// would leave the account with, and the errors it would be rejected
+    /// with.
+    ///
+    /// The keys and co-signers listed in the change are taken as having
+    /// signed it. The changes are checked as they are when applied, but
+    /// walked past any violation, so that all of them are reported at once.
+    fn simulate_change(&self, c: ChangeAccount) -> ChangeSimulation {
+        let mut violations = Vec::new();
+        let mut violate = |error: Error| {
+            if !violations.contains(&error) {
+                violations.push(error);
+            }
+        };
+
+        let Some(account) = self.accounts.get(&c.account_id) else {
+            violate(Error::AccountNotFound);
+            return ChangeSimulation {
+                keys: Vec::new(),
+                threshold: 0,
+                violations,
+            };
+        };
+        if account.merged_into.is_some() {
+            violate(Error::AccountMerged);
+        }
+        if c.nonce != account.nonce + 1 {
+            violate(Error::InvalidNonce);
+        }
+        if c.chain_id
+            .is_some_and(|chain_id| chain_id != account.chain_id)
+        {
+            violate(Error::WrongChain);
+        }
+
+        let account_keys = self.account_keys.get(&c.account_id).unwrap();
+        let cosigners = self.cosigners.get(&c.account_id).unwrap();
+        let weights = self.key_weights.get(&c.account_id);
+        let key_roles = self.key_roles.get(&c.account_id);
+
+        let group_signed = match (account.group_key, c.group_signature) {
+            (Some(_), Some(_)) => {
+                if !c.keys.is_empty() || !c.cosignatures.is_empty() {
+                    violate(Error::GroupSignatureRequired);
+                }
+                true
+            }
+            (None, Some(_)) => {
+                violate(Error::NoGroupKey);
+                false
+            }
+            (Some(_), None) if !account.frozen => {
+                violate(Error::GroupSignatureRequired);
+                false
+            }
+            _ => false,
+        };
+
+        let delegations = self.delegations.get(&c.account_id);
+        let mut key_set = BTreeSet::new();
+        let mut principals = BTreeSet::new();
+        for key in &c.keys {
+            let key = WrappedPublicKey(*key);
+
+            if !key_set.insert(key) {
+                violate(Error::DuplicateKey);
+            }
+            let Some(principal) = principal_of(account_keys, delegations, key)
+            else {
+                violate(Error::UnknownKey);
+                continue;
+            };
+            if self.compromised_keys.contains(&key)
+                || self.compromised_keys.contains(&principal)
+            {
+                violate(Error::KeyCompromised);
+            }
+            if key_roles
+                .and_then(|roles| roles.get(&principal))
+                .is_some_and(|role| {
+                    !role.can_sign(OperationKind::ChangeAccount)
+                })
+            {
+                violate(Error::KeyRoleNotAllowed);
+            }
+            if !principals.insert(principal) {
+                violate(Error::DuplicateKey);
+            }
+        }
+        let mut cosigner_set = BTreeSet::new();
+        for cosignature in &c.cosignatures {
+            if !cosigner_set.insert(cosignature.key) {
+                violate(Error::DuplicateKey);
+            }
+            if !cosigners.contains(&cosignature.key) {
+                violate(Error::UnknownKey);
+            }
+            if self.compromised_cosigners.contains(&cosignature.key) {
+                violate(Error::KeyCompromised);
+            }
+        }
+
+        let liveness = self.liveness.get(&c.account_id);
+        let weight = weight_of(
+            weights,
+            &principals,
+            key_set.len() - principals.len() + cosigner_set.len(),
+        );
+        let required = required_signers(
+            account,
+            account.threshold_of(OperationKind::ChangeAccount),
+            liveness,
+            weights,
+            cosigners.len(),
+        );
+        if !group_signed && weight < required {
+            violate(Error::ThresholdNotMet);
+        }
+        if account.frozen
+            && (group_signed
+                || key_set.len() + cosigner_set.len()
+                    != account_keys.len() + cosigners.len())
+        {
+            violate(Error::AccountFrozen);
+        }
+
+        let signers = Signers {
+            keys: key_set.into_iter().map(|k| k.0).collect(),
+            principals: principals.into_iter().collect(),
+            cosigners: cosigner_set.into_iter().collect(),
+            weight,
+            group_signed,
+            duress: false,
+        };
+        let draft = self.check_changes(
+            c.account_id,
+            &c.changes,
+            &signers,
+            &mut violate,
+        );
+
+        ChangeSimulation {
+            keys: draft.keys.into_iter().map(|key| key.0).collect(),
+            threshold: draft.account.threshold,
+            violations,
+        }
+    }
+
+    /// Feeds the movements of an account within a range of blocks, in the
+    /// order they happened.
+    fn movements(&self, q: MovementQuery) {
+        let movements = self.movements.get(&q.account_id);
+        let movements = movements.map_or(&[][..], Vec::as_slice);
+
+        // The ledger is in block order, so the range is found by searching
+        let start =
+            movements.partition_point(|m| m.block_height < q.from_height);
+        for movement in movements[start..]
+            .iter()
+            .take_while(|m| m.block_height <= q.to_height)
+        {
+            rusk_abi::feed(movement.clone());
+        }
+    }
 
-                    account.threshold = threshold;
-                    changed_threshold = true;
-                }
-                AccountChange::SetDescription { description } => {
-                    account.description = description;
-                    changed_description = true;
-                }
+    /// Feeds the movements of funds into or out of the contract of at least
+    /// an amount, within a range of blocks, across all accounts in the order
+    /// they happened.
+    fn large_transfers(&self, q: LargeTransferQuery) {
+        let mut transfers = Vec::new();
+
+        for (account_id, movements) in &self.movements {
+            let start =
+                movements.partition_point(|m| m.block_height < q.from_height);
+            let movements = movements[start..]
+                .iter()
+                .take_while(|m| m.block_height <= q.to_height)
+                .filter(|m| m.amount >= q.min_amount)
+                .filter(|m| {
+                    matches!(
+                        m.counterparty,
+                        Counterparty::Depositor | Counterparty::Moonlight(_)
+                    )
+                });
+            for movement in movements {
+                transfers.push(LargeTransfer {
+                    account_id: *account_id,
+                    movement: movement.clone(),
+                });
             }
         }
 
-        account.nonce += 1;
+        // Ledgers are each in order, and the sequence numbers of the events
+        // emitted with the movements order them across ledgers
+        transfers.sort_by_key(|t| t.movement.sequence);
+        for transfer in transfers {
+            rusk_abi::feed(transfer);
+        }
+    }
 
-        rusk_abi::emit(
-            "change_account",
-            ChangeAccountEvent {
-                account_id: c.account_id,
-                added_keys,
-                removed_keys,
-                threshold: changed_threshold.then_some(account.threshold),
-                description: changed_description
-                    .then_some(account.description.clone()),
-            },
-        );
+    /// Feeds the withdrawals awaiting claim by the given receiver, in the
+    /// order of their claim IDs.
+    fn withdrawals(&self, receiver: bls::PublicKey) {
+        let receiver = WrappedPublicKey(receiver);
+        for withdrawal in self.withdrawals.values() {
+            if WrappedPublicKey(withdrawal.receiver) == receiver {
+                rusk_abi::feed(withdrawal.clone());
+            }
+        }
     }
 
-    /// Returns the balance and nonce of the account with the given ID.
-    fn account(&self, id: u64) -> AccountData {
-        self.accounts
-            .get(&id)
-            .unwrap_or(&AccountData {
-                balance: 0,
-                threshold: 0,
-                description: String::new(),
-                nonce: 0,
-            })
-            .clone()
+    /// Feeds the keys marked as compromised.
+    fn compromised_keys(&self) {
+        for key in &self.compromised_keys {
+            rusk_abi::feed(key.0);
+        }
     }
 
-    /// Feeds the public keys used by the account with the given ID.
-    fn account_keys(&self, id: u64) {
-        for key in self
-            .account_keys
-            .get(&id)
-            .cloned()
-            .unwrap_or(BTreeSet::new())
-        {
-            rusk_abi::feed(key);
+    /// Feeds the co-signer keys marked as compromised.
+    fn compromised_cosigners(&self) {
+        for key in &self.compromised_cosigners {
+            rusk_abi::feed(*key);
         }
     }
 
@@ -355,24 +6631,265 @@ impl ContractState {
 
 // Mutations
 
+#[no_mangle]
+unsafe fn init(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.init(arg))
+}
+
 #[no_mangle]
 unsafe fn create_account(arg_len: u32) -> u32 {
-    rusk_abi::wrap_call(arg_len, |arg| STATE.create_account(arg))
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("create_account");
+        STATE.create_account(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn register_template(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("register_template");
+        STATE.register_template(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn create_account_from_template(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("create_account_from_template");
+        STATE.create_account_from_template(arg)
+    })
 }
 
 #[no_mangle]
 unsafe fn deposit(arg_len: u32) -> u32 {
-    rusk_abi::wrap_call(arg_len, |arg| STATE.deposit(arg))
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("deposit");
+        STATE.deposit(arg)
+    })
 }
 
 #[no_mangle]
 unsafe fn transfer(arg_len: u32) -> u32 {
-    rusk_abi::wrap_call(arg_len, |arg| STATE.transfer(arg))
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("transfer");
+        STATE.transfer(arg)
+    })
 }
 
 #[no_mangle]
 unsafe fn change_account(arg_len: u32) -> u32 {
-    rusk_abi::wrap_call(arg_len, |arg| STATE.change_account(arg))
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("change_account");
+        STATE.change_account(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn lock_account(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("lock_account");
+        STATE.lock_account(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn place_hold(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("place_hold");
+        STATE.place_hold(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn attest_liveness(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("attest_liveness");
+        STATE.attest_liveness(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn delegate_key(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("delegate_key");
+        STATE.delegate_key(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn guard_account(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("guard_account");
+        STATE.guard_account(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn start_recovery(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("start_recovery");
+        STATE.start_recovery(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn cancel_recovery(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("cancel_recovery");
+        STATE.cancel_recovery(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn finalize_recovery(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("finalize_recovery");
+        STATE.finalize_recovery(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn claim_inactive(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("claim_inactive");
+        STATE.claim_inactive(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn queue_transfer(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("queue_transfer");
+        STATE.queue_transfer(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn cancel_queued(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("cancel_queued");
+        STATE.cancel_queued(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn execute_queued(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("execute_queued");
+        STATE.execute_queued(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn transfer_with_allowance(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("transfer_with_allowance");
+        STATE.transfer_with_allowance(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn merge_accounts(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("merge_accounts");
+        STATE.merge_accounts(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn split_account(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("split_account");
+        STATE.split_account(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn draw_budget(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("draw_budget");
+        STATE.draw_budget(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn record_checkpoint(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("record_checkpoint");
+        STATE.record_checkpoint(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn configure(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("configure");
+        STATE.configure(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn payroll(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("payroll");
+        STATE.payroll(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn transfer_and_change(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("transfer_and_change");
+        STATE.transfer_and_change(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn certified(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("certified");
+        STATE.certified(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn mark_compromised(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("mark_compromised");
+        STATE.mark_compromised(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn mark_cosigner_compromised(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("mark_cosigner_compromised");
+        STATE.mark_cosigner_compromised(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn replace_key(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("replace_key");
+        STATE.replace_key(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn claim_withdrawal(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("claim_withdrawal");
+        STATE.claim_withdrawal(arg)
+    })
+}
+
+#[no_mangle]
+unsafe fn migrate(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| {
+        STATE.count_call("migrate");
+        STATE.migrate(arg)
+    })
 }
 
 // Queries
@@ -382,6 +6899,86 @@ unsafe fn account(arg_len: u32) -> u32 {
     rusk_abi::wrap_call(arg_len, |arg| STATE.account(arg))
 }
 
+#[no_mangle]
+unsafe fn resolve_alias(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.resolve_alias(arg))
+}
+
+#[no_mangle]
+unsafe fn pending_recovery(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.pending_recovery(arg))
+}
+
+#[no_mangle]
+unsafe fn allowances(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.allowances(arg))
+}
+
+#[no_mangle]
+unsafe fn queued_transfers(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.queued_transfers(arg))
+}
+
+#[no_mangle]
+unsafe fn template(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.template(arg))
+}
+
+#[no_mangle]
+unsafe fn is_compromised(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.is_compromised(arg))
+}
+
+#[no_mangle]
+unsafe fn event_sequence(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |_: ()| STATE.event_sequence())
+}
+
+#[no_mangle]
+unsafe fn total_balance(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |_: ()| STATE.total_balance())
+}
+
+#[no_mangle]
+unsafe fn custody_cap(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |_: ()| STATE.custody_cap())
+}
+
+#[no_mangle]
+unsafe fn min_deposit(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |_: ()| STATE.min_deposit())
+}
+
+#[no_mangle]
+unsafe fn governance(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |_: ()| STATE.governance())
+}
+
+#[no_mangle]
+unsafe fn config(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |_: ()| STATE.config())
+}
+
+#[no_mangle]
+unsafe fn custody(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |_: ()| STATE.custody())
+}
+
+#[no_mangle]
+unsafe fn stats(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |_: ()| STATE.stats())
+}
+
+#[no_mangle]
+unsafe fn withdrawal(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.withdrawal(arg))
+}
+
+#[no_mangle]
+unsafe fn withdrawal_count(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |_: ()| STATE.withdrawal_count())
+}
+
 // Feeder queries
 
 #[no_mangle]
@@ -389,6 +6986,131 @@ unsafe fn account_keys(arg_len: u32) -> u32 {
     rusk_abi::wrap_call(arg_len, |arg| STATE.account_keys(arg))
 }
 
+#[no_mangle]
+unsafe fn duress_keys(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.duress_keys(arg))
+}
+
+#[no_mangle]
+unsafe fn cosigners(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.cosigners(arg))
+}
+
+#[no_mangle]
+unsafe fn whitelist(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.whitelist(arg))
+}
+
+#[no_mangle]
+unsafe fn guardians(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.guardians(arg))
+}
+
+#[no_mangle]
+unsafe fn observers(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.observers(arg))
+}
+
+#[no_mangle]
+unsafe fn budget_draws(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.budget_draws(arg))
+}
+
+#[no_mangle]
+unsafe fn category_spend(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.category_spend(arg))
+}
+
+#[no_mangle]
+unsafe fn key_liveness(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.key_liveness(arg))
+}
+
+#[no_mangle]
+unsafe fn delegations(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.delegations(arg))
+}
+
+#[no_mangle]
+unsafe fn pending_keys(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.pending_keys(arg))
+}
+
+#[no_mangle]
+unsafe fn key_expiries(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.key_expiries(arg))
+}
+
+#[no_mangle]
+unsafe fn key_weights(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.key_weights(arg))
+}
+
+#[no_mangle]
+unsafe fn key_roles(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.key_roles(arg))
+}
+
+#[no_mangle]
+unsafe fn transfer_tiers(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.transfer_tiers(arg))
+}
+
+#[no_mangle]
+unsafe fn key_usage(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.key_usage(arg))
+}
+
+#[no_mangle]
+unsafe fn tagged_accounts(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.tagged_accounts(arg))
+}
+
+#[no_mangle]
+unsafe fn withdrawals(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.withdrawals(arg))
+}
+
+#[no_mangle]
+unsafe fn compromised_keys(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |_: ()| STATE.compromised_keys())
+}
+
+#[no_mangle]
+unsafe fn compromised_cosigners(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |_: ()| STATE.compromised_cosigners())
+}
+
+#[no_mangle]
+unsafe fn checkpoints(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.checkpoints(arg))
+}
+
+#[no_mangle]
+unsafe fn movements(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.movements(arg))
+}
+
+#[no_mangle]
+unsafe fn large_transfers(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.large_transfers(arg))
+}
+
+#[no_mangle]
+unsafe fn operations(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.operations(arg))
+}
+
+#[no_mangle]
+unsafe fn state_diff(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.state_diff(arg))
+}
+
+#[no_mangle]
+unsafe fn simulate_change(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.simulate_change(arg))
+}
+
 #[no_mangle]
 unsafe fn key_accounts(arg_len: u32) -> u32 {
     rusk_abi::wrap_call(arg_len, |arg| STATE.key_accounts(arg))
@@ -0,0 +1,254 @@
+//! The HTTP API of the coordinator.
+//!
+//! Operations, partial signatures and signed operations are all exchanged in
+//! their archived form, the same used by the contract and the CLI.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::Router;
+
+use execution_core::ContractId;
+
+use multisig_contract_types::*;
+use multisig_wallet::{decode, encode, Node};
+
+/// An operation whose signatures are being collected.
+struct PendingOperation {
+    operation: Operation,
+    partials: Vec<PartialSignature>,
+}
+
+/// Hosts pending operations and collects their partial signatures.
+pub struct Coordinator {
+    node: Node,
+    contract: ContractId,
+    pending: Mutex<Pending>,
+}
+
+/// Pending operations, indexed by the ID they were assigned.
+#[derive(Default)]
+struct Pending {
+    next_id: u64,
+    operations: BTreeMap<u64, PendingOperation>,
+}
+
+impl Coordinator {
+    /// Creates a coordinator for the multisig contract with the given ID,
+    /// checking signatures against the state of the given node.
+    pub fn new(node: Node, contract: ContractId) -> Self {
+        Self {
+            node,
+            contract,
+            pending: Mutex::new(Pending::default()),
+        }
+    }
+
+    /// Builds the router serving the API.
+    pub fn router(self) -> Router {
+        Router::new()
+            .route("/operations", post(submit_operation))
+            .route("/operations/:id", get(operation))
+            .route("/operations/:id/signatures", post(submit_signature))
+            .route("/operations/:id/finalize", post(finalize))
+            .with_state(Arc::new(self))
+    }
+}
+
+/// Errors returned by the API.
+pub enum ApiError {
+    /// There is no pending operation with the requested ID.
+    NotFound,
+    /// The request was rejected.
+    BadRequest(String),
+    /// Communicating with the node failed.
+    Node(String),
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        match self {
+            ApiError::NotFound => {
+                (StatusCode::NOT_FOUND, "No such operation").into_response()
+            }
+            ApiError::BadRequest(msg) => {
+                (StatusCode::BAD_REQUEST, msg).into_response()
+            }
+            ApiError::Node(msg) => {
+                (StatusCode::BAD_GATEWAY, msg).into_response()
+            }
+        }
+    }
+}
+
+type ApiResult<T> = Result<T, ApiError>;
+
+impl Coordinator {
+    fn with_pending<T>(
+        &self,
+        id: u64,
+        f: impl FnOnce(&mut PendingOperation) -> T,
+    ) -> ApiResult<T> {
+        let mut pending = self.pending.lock().unwrap();
+        pending
+            .operations
+            .get_mut(&id)
+            .map(f)
+            .ok_or(ApiError::NotFound)
+    }
+
+    async fn account(&self, account_id: u64) -> ApiResult<AccountData> {
+        self.node
+            .query(self.contract, "account", &account_id)
+            .await
+            .map_err(|err| ApiError::Node(err.to_string()))
+    }
+
+    async fn account_keys(
+        &self,
+        account_id: u64,
+    ) -> ApiResult<Vec<bls::PublicKey>> {
+        self.node
+            .feeder_query(self.contract, "account_keys", &account_id)
+            .await
+            .map_err(|err| ApiError::Node(err.to_string()))
+    }
+
+    async fn key_weights(&self, account_id: u64) -> ApiResult<Vec<KeyWeight>> {
+        self.node
+            .feeder_query(self.contract, "key_weights", &account_id)
+            .await
+            .map_err(|err| ApiError::Node(err.to_string()))
+    }
+
+    async fn key_roles(
+        &self,
+        account_id: u64,
+    ) -> ApiResult<Vec<RoleAssignment>> {
+        self.node
+            .feeder_query(self.contract, "key_roles", &account_id)
+            .await
+            .map_err(|err| ApiError::Node(err.to_string()))
+    }
+}
+
+/// Hosts a new operation, returning the ID assigned to it.
+async fn submit_operation(
+    State(coordinator): State<Arc<Coordinator>>,
+    body: Bytes,
+) -> ApiResult<String> {
+    let mut operation: Operation = decode(&body)
+        .map_err(|_| ApiError::BadRequest("Invalid operation".into()))?;
+
+    // Any signature the operation came with is replaced by the ones collected
+    operation.aggregate(&[]);
+
+    let mut pending = coordinator.pending.lock().unwrap();
+
+    pending.next_id += 1;
+    let id = pending.next_id;
+
+    pending.operations.insert(
+        id,
+        PendingOperation {
+            operation,
+            partials: Vec::new(),
+        },
+    );
+
+    Ok(id.to_string())
+}
+
+/// Returns the operation with the given ID, for signers to sign.
+async fn operation(
+    State(coordinator): State<Arc<Coordinator>>,
+    Path(id): Path<u64>,
+) -> ApiResult<Vec<u8>> {
+    let operation = coordinator.with_pending(id, |p| p.operation.clone())?;
+    encode(&operation).map_err(|err| ApiError::BadRequest(err.to_string()))
+}
+
+/// Adds a partial signature to the operation with the given ID, returning the
+/// number of signatures collected so far.
+///
+/// The signature must be valid, and made by a key currently used by the
+/// account whose role lets it sign the operation.
+async fn submit_signature(
+    State(coordinator): State<Arc<Coordinator>>,
+    Path(id): Path<u64>,
+    body: Bytes,
+) -> ApiResult<String> {
+    let partial: PartialSignature = decode(&body)
+        .map_err(|_| ApiError::BadRequest("Invalid signature".into()))?;
+
+    let operation = coordinator.with_pending(id, |p| p.operation.clone())?;
+
+    if !partial.verify(&operation) {
+        return Err(ApiError::BadRequest(Error::InvalidSignature.to_string()));
+    }
+
+    let keys = coordinator.account_keys(operation.account_id()).await?;
+    if !keys.contains(&partial.key) {
+        return Err(ApiError::BadRequest(Error::UnknownKey.to_string()));
+    }
+    let roles = coordinator.key_roles(operation.account_id()).await?;
+    if roles
+        .iter()
+        .any(|r| r.key == partial.key && !r.role.can_sign(operation.kind()))
+    {
+        return Err(ApiError::BadRequest(Error::KeyRoleNotAllowed.to_string()));
+    }
+
+    coordinator.with_pending(id, |p| {
+        if p.partials.iter().any(|p| p.key == partial.key) {
+            return Err(ApiError::BadRequest(Error::DuplicateKey.to_string()));
+        }
+        p.partials.push(partial);
+        Ok(p.partials.len().to_string())
+    })?
+}
+
+/// Aggregates the signatures collected for the operation with the given ID,
+/// returning the signed operation, ready for submission.
+///
+/// The operation stops being hosted once it is finalized.
+async fn finalize(
+    State(coordinator): State<Arc<Coordinator>>,
+    Path(id): Path<u64>,
+) -> ApiResult<Vec<u8>> {
+    let (mut operation, partials) = coordinator
+        .with_pending(id, |p| (p.operation.clone(), p.partials.clone()))?;
+
+    let account = coordinator.account(operation.account_id()).await?;
+
+    if operation.nonce() != account.nonce + 1 {
+        return Err(ApiError::BadRequest(Error::InvalidNonce.to_string()));
+    }
+    // Keys count for their weight, as they do in the contract
+    let weights = coordinator.key_weights(operation.account_id()).await?;
+    let weight: usize = partials
+        .iter()
+        .map(|p| {
+            weights
+                .iter()
+                .find(|w| w.key == p.key)
+                .map_or(1, |w| w.weight as usize)
+        })
+        .sum();
+    if weight < account.threshold_of(operation.kind()) as usize {
+        return Err(ApiError::BadRequest(Error::ThresholdNotMet.to_string()));
+    }
+
+    operation.aggregate(&partials);
+    let bytes = encode(&operation)
+        .map_err(|err| ApiError::BadRequest(err.to_string()))?;
+
+    coordinator.pending.lock().unwrap().operations.remove(&id);
+
+    Ok(bytes)
+}
@@ -0,0 +1,54 @@
+//! Signature coordination service for the `multisig-contract`.
+//!
+//! The coordinator hosts operations pending signature, allowing each signer
+//! to fetch them, sign them independently and submit their partial signature.
+//! Each partial signature is verified against the account's keys on-chain as
+//! it's submitted, so that once enough are collected the operation can be
+//! finalized into a signed operation, ready to be submitted to the network.
+
+mod api;
+
+use std::net::SocketAddr;
+
+use clap::Parser;
+use tokio::net::TcpListener;
+
+use execution_core::ContractId;
+
+use multisig_wallet::Node;
+
+use crate::api::Coordinator;
+
+type Result<T, E = Box<dyn std::error::Error + Send + Sync>> =
+    std::result::Result<T, E>;
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// Address to listen for requests on.
+    #[arg(long, default_value = "127.0.0.1:8090")]
+    listen: SocketAddr,
+    /// URL of the rusk node to check signatures against.
+    #[arg(long, env = "RUSK_NODE", default_value = "http://127.0.0.1:8080")]
+    node: String,
+    /// Hex encoded ID of the multisig contract.
+    #[arg(long, env = "MULTISIG_CONTRACT")]
+    contract: String,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let contract = hex::decode(&args.contract)?
+        .try_into()
+        .map_err(|_| "The contract ID must be 32 bytes long")?;
+    let contract = ContractId::from_bytes(contract);
+
+    let coordinator = Coordinator::new(Node::new(args.node), contract);
+
+    let listener = TcpListener::bind(args.listen).await?;
+    axum::serve(listener, coordinator.router()).await?;
+
+    Ok(())
+}
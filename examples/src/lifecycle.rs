@@ -0,0 +1,252 @@
+//! Walks through the lifecycle of a multisig account against a local session.
+//!
+//! A 2-of-3 account is created and funded, then its signers each sign a
+//! transfer out of it, the partial signatures are aggregated, and the transfer
+//! is executed. Finally, one of the keys of the account is rotated, again
+//! signed by two of the signers.
+//!
+//! The contract is deployed alongside the mock of the transfer contract, so
+//! the session starts instantly and operations are called directly, instead
+//! of through transactions. Build both with `make contract`, and run with:
+//!
+//! ```sh
+//! cargo run --release -p multisig-contract-examples --bin lifecycle
+//! ```
+
+use std::sync::mpsc;
+
+use execution_core::transfer::moonlight::AccountData as MoonlightAccountData;
+use execution_core::transfer::TRANSFER_CONTRACT;
+use execution_core::ContractId;
+use rusk_abi::{ContractData, Session, VM};
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use multisig_contract_types::bls::{MultisigSignature, PublicKey, SecretKey};
+use multisig_contract_types::*;
+
+const CONTRACT_BYTECODE: &[u8] =
+    include_bytes!("../../build/multisig_contract.wasm");
+const MOCK_TRANSFER_BYTECODE: &[u8] =
+    include_bytes!("../../build/mock_transfer.wasm");
+const CONTRACT_ID: ContractId = ContractId::from_bytes([1; 32]);
+const OWNER: [u8; 64] = [0u8; 64];
+
+const CHAIN_ID: u8 = 0xFE;
+const GAS_LIMIT: u64 = u64::MAX;
+
+/// Starts a session with the multisig contract and the mock of the transfer
+/// contract deployed.
+fn session() -> Session {
+    let vm = VM::ephemeral().expect("Creating a VM should succeed");
+    let mut session = rusk_abi::new_genesis_session(&vm, CHAIN_ID);
+
+    session
+        .deploy(
+            MOCK_TRANSFER_BYTECODE,
+            ContractData::builder()
+                .owner(OWNER)
+                .contract_id(TRANSFER_CONTRACT),
+            GAS_LIMIT,
+        )
+        .expect("Deploying the mock transfer contract should succeed");
+    session
+        .deploy(
+            CONTRACT_BYTECODE,
+            ContractData::builder()
+                .owner(OWNER)
+                .contract_id(CONTRACT_ID),
+            GAS_LIMIT,
+        )
+        .expect("Deploying the multisig contract should succeed");
+
+    session
+}
+
+fn account(session: &mut Session, account_id: u64) -> AccountData {
+    session
+        .call(CONTRACT_ID, "account", &account_id, GAS_LIMIT)
+        .expect("Querying an account should succeed")
+        .data
+}
+
+/// Has each of the given signers sign the operation, as they would on their
+/// own devices, and aggregates their partial signatures into it.
+fn sign(operation: &mut Operation, signers: &[&SecretKey]) {
+    let partials: Vec<PartialSignature> = signers
+        .iter()
+        .map(|sk| PartialSignature::sign(sk, operation))
+        .collect();
+
+    for partial in &partials {
+        assert!(
+            partial.verify(operation),
+            "Each partial signature should be valid"
+        );
+    }
+
+    operation.aggregate(&partials);
+}
+
+/// Executes a signed operation.
+fn execute(session: &mut Session, operation: &Operation) {
+    let receipt = match operation {
+        Operation::Transfer(t) => {
+            session.call::<_, ()>(CONTRACT_ID, "transfer", t, GAS_LIMIT)
+        }
+        Operation::ChangeAccount(c) => {
+            session.call::<_, ()>(CONTRACT_ID, "change_account", c, GAS_LIMIT)
+        }
+        Operation::SplitAccount(s) => {
+            session.call::<_, ()>(CONTRACT_ID, "split_account", s, GAS_LIMIT)
+        }
+        Operation::Payroll(p) => {
+            session.call::<_, ()>(CONTRACT_ID, "payroll", p, GAS_LIMIT)
+        }
+        Operation::TransferAndChange(tc) => session.call::<_, ()>(
+            CONTRACT_ID,
+            "transfer_and_change",
+            tc,
+            GAS_LIMIT,
+        ),
+    }
+    .expect("Executing the operation should succeed");
+
+    println!(
+        "Executed `{}`, spending {} gas",
+        operation.fn_name(),
+        receipt.gas_spent
+    );
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(0xBEEF);
+    let mut session = session();
+
+    // The three signers of the account, and a fourth key to rotate in
+    let sks: Vec<SecretKey> =
+        (0..4).map(|_| SecretKey::random(&mut rng)).collect();
+    let pks: Vec<PublicKey> = sks.iter().map(PublicKey::from).collect();
+
+    // Create a 2-of-3 account
+    let account_id: u64 = session
+        .call(
+            CONTRACT_ID,
+            "create_account",
+            &CreateAccount {
+                keys: pks[..3].to_vec(),
+                threshold: 2,
+                description: String::from("example account"),
+                alias: None,
+                key_weights: Vec::new(),
+                key_roles: Vec::new(),
+            },
+            GAS_LIMIT,
+        )
+        .expect("Creating an account should succeed")
+        .data;
+    println!("Created account {account_id}");
+
+    // Fund it. Anyone may deposit, and no signatures are required
+    session
+        .call::<_, ()>(
+            CONTRACT_ID,
+            "deposit",
+            &Deposit {
+                account_id,
+                amount: 1_000,
+                memo: String::from("funding"),
+                depositor_reference: String::from("invoice-1"),
+            },
+            GAS_LIMIT,
+        )
+        .expect("Depositing should succeed");
+    println!(
+        "Deposited, the balance is {}",
+        account(&mut session, account_id).balance
+    );
+
+    // Transfer out of it, with the first two signers signing for the next
+    // nonce of the account
+    let receiver = PublicKey::from(&SecretKey::random(&mut rng));
+    let mut transfer = Operation::Transfer(Transfer {
+        account_id,
+        keys: Vec::new(),
+        signature: MultisigSignature::default(),
+        cosignatures: Vec::new(),
+        group_signature: None,
+        receiver,
+        amount: 400,
+        nonce: account(&mut session, account_id).nonce + 1,
+        memo: String::from("payment"),
+        reference: None,
+        category: None,
+        gas_refund: None,
+        chain_id: None,
+    });
+    sign(&mut transfer, &[&sks[0], &sks[1]]);
+    execute(&mut session, &transfer);
+
+    let receiver_balance = session
+        .call::<_, MoonlightAccountData>(
+            TRANSFER_CONTRACT,
+            "account",
+            &receiver,
+            GAS_LIMIT,
+        )
+        .expect("Querying the receiver should succeed")
+        .data
+        .balance;
+    println!(
+        "Transferred, the balance is {} and the receiver holds {receiver_balance}",
+        account(&mut session, account_id).balance
+    );
+
+    // Rotate the third key out for the fourth, signed by the second and third
+    // signers
+    let mut rotation = Operation::ChangeAccount(ChangeAccount {
+        account_id,
+        keys: Vec::new(),
+        signature: MultisigSignature::default(),
+        cosignatures: Vec::new(),
+        group_signature: None,
+        changes: vec![
+            AccountChange::AddKey { key: pks[3] },
+            AccountChange::RemoveKey { key: pks[2] },
+        ],
+        nonce: account(&mut session, account_id).nonce + 1,
+        gas_refund: None,
+        chain_id: None,
+    });
+    sign(&mut rotation, &[&sks[1], &sks[2]]);
+    execute(&mut session, &rotation);
+
+    let (sender, feed) = mpsc::channel();
+    session
+        .feeder_call::<_, ()>(
+            CONTRACT_ID,
+            "account_keys",
+            &account_id,
+            GAS_LIMIT,
+            sender,
+        )
+        .expect("Querying the keys should succeed");
+    let keys: Vec<PublicKey> = feed
+        .into_iter()
+        .map(|bytes| rkyv::from_bytes(&bytes).expect("Keys should deserialize"))
+        .collect();
+
+    assert!(
+        keys.contains(&pks[3]),
+        "The new key should be in the account"
+    );
+    assert!(!keys.contains(&pks[2]), "The old key should be out of it");
+    println!("Rotated a key, the account has {} keys", keys.len());
+
+    let account = account(&mut session, account_id);
+    println!(
+        "Account {account_id}: balance {}, threshold {}, nonce {}",
+        account.balance, account.threshold, account.nonce
+    );
+}
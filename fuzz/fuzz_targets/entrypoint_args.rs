@@ -0,0 +1,56 @@
+//! Feeds arbitrary bytes to the argument decoding performed by each of the
+//! contract's entrypoints.
+//!
+//! The contract uses `rusk_abi::wrap_call`, which validates the archived
+//! argument before deserializing it. This target does the same, ensuring that
+//! no malformed payload can make either step panic.
+
+#![no_main]
+
+use bytecheck::CheckBytes;
+use libfuzzer_sys::fuzz_target;
+use rkyv::validation::validators::DefaultValidator;
+use rkyv::{AlignedVec, Archive, Deserialize, Infallible};
+
+use multisig_contract_types::*;
+
+fn decode<T>(bytes: &[u8])
+where
+    T: Archive,
+    T::Archived:
+        Deserialize<T, Infallible> + for<'b> CheckBytes<DefaultValidator<'b>>,
+{
+    // The contract receives its argument in an aligned buffer, so we do the
+    // same here to avoid spurious alignment failures.
+    let mut aligned = AlignedVec::with_capacity(bytes.len());
+    aligned.extend_from_slice(bytes);
+
+    if let Ok(archived) = rkyv::check_archived_root::<T>(&aligned) {
+        let _: T = archived
+            .deserialize(&mut Infallible)
+            .expect("Deserializing a checked archive should be infallible");
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let Some((selector, bytes)) = data.split_first() else {
+        return;
+    };
+
+    match selector % 14 {
+        0 => decode::<CreateAccount>(bytes),
+        1 => decode::<Deposit>(bytes),
+        2 => decode::<Transfer>(bytes),
+        3 => decode::<ChangeAccount>(bytes),
+        4 => decode::<SplitAccount>(bytes),
+        5 => decode::<Payroll>(bytes),
+        6 => decode::<TransferAndChange>(bytes),
+        7 => decode::<AccountTemplate>(bytes),
+        8 => decode::<CreateAccountFromTemplate>(bytes),
+        9 => decode::<Migration>(bytes),
+        10 => decode::<MovementQuery>(bytes),
+        11 => decode::<u64>(bytes),
+        12 => decode::<LargeTransferQuery>(bytes),
+        _ => decode::<bls::PublicKey>(bytes),
+    }
+});
@@ -0,0 +1,1083 @@
+//! Builds arbitrary operations and checks that their signature messages can be
+//! unambiguously decoded back into the operation that produced them.
+//!
+//! If two different operations were to produce the same message, a signature
+//! for one would also be valid for the other. Decoding every message with an
+//! independent reference decoder, and comparing the result with the original
+//! operation, ensures this can't happen.
+//!
+//! The hardware wallet payload of each operation is checked to produce the
+//! same message as well.
+
+#![no_main]
+
+use std::sync::OnceLock;
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use multisig_contract_types::*;
+
+const NUM_KEYS: usize = 16;
+const RAW_KEY_SIZE: usize = 193;
+
+/// Generating keys is expensive, so we use a fixed set of keys that the
+/// fuzzer selects from by index.
+fn keys() -> &'static [bls::PublicKey] {
+    static KEYS: OnceLock<Vec<bls::PublicKey>> = OnceLock::new();
+    KEYS.get_or_init(|| {
+        let mut rng = StdRng::seed_from_u64(0xBEEF);
+        (0..NUM_KEYS)
+            .map(|_| bls::PublicKey::from(&bls::SecretKey::random(&mut rng)))
+            .collect()
+    })
+}
+
+fn key(index: u8) -> bls::PublicKey {
+    keys()[index as usize % NUM_KEYS]
+}
+
+#[derive(Debug, Arbitrary)]
+enum Input {
+    Transfer {
+        account_id: u64,
+        receiver: u8,
+        amount: u64,
+        nonce: u64,
+        memo: String,
+        reference: Option<[u8; 32]>,
+        category: Option<String>,
+    },
+    ChangeAccount {
+        account_id: u64,
+        changes: Vec<Change>,
+        nonce: u64,
+    },
+    SplitAccount {
+        account_id: u64,
+        new_keys: Vec<u8>,
+        threshold: u32,
+        description: String,
+        amount: u64,
+        nonce: u64,
+    },
+    Payroll {
+        account_id: u64,
+        rows: Vec<(u8, u64, String)>,
+        nonce: u64,
+    },
+    TransferAndChange {
+        account_id: u64,
+        receiver: u8,
+        amount: u64,
+        memo: String,
+        changes: Vec<Change>,
+        nonce: u64,
+    },
+}
+
+#[derive(Debug, Arbitrary)]
+enum Change {
+    AddKey(u8),
+    RemoveKey(u8),
+    SetThreshold(u32),
+    SetDescription(String),
+    SetPrivateEvents(bool),
+    AddDuressKey(u8),
+    RemoveDuressKey(u8),
+    AddCosigner([u8; 32]),
+    RemoveCosigner([u8; 32]),
+    SetGroupKey(Option<u8>),
+    AddWhitelisted(u8),
+    RemoveWhitelisted(u8),
+    SetVelocityLimit(Option<(u64, u64)>),
+    Unlock,
+    AddGuardian(u8),
+    RemoveGuardian(u8),
+    SetAlias(Option<String>),
+    SetBalanceCap(Option<u64>),
+    AddTag(String),
+    RemoveTag(String),
+    SetLivenessPolicy(Option<(u64, u32)>),
+    SetSupermajority(Option<(u32, u32)>),
+    SetKeyAdditionPolicy(Option<(u64, bool)>),
+    SetTransferWindow(Option<(u64, u64, u64)>),
+    SetCategory(String, u64, u64),
+    RemoveCategory(String),
+    AddObserver(u8),
+    RemoveObserver(u8),
+    SetChildBudget(u64, u64, u64),
+    RemoveChildBudget(u64),
+    ClawBackBudget(u64),
+    SetKeyExpiry(u8, Option<u64>),
+    Archive,
+    Reactivate,
+    SetClaimableWithdrawals(bool),
+    SetHistoryRetention(Option<u32>),
+    SetCompliance(Option<(u8, u8)>),
+    LiftHold,
+    SetLicensePolicy(Option<([u8; 32], bool)>),
+    SetOperationThreshold(u8, Option<u32>),
+    SetTransferTiers(Vec<(u64, u32)>),
+    SetKeyWeight(u8, u32),
+    SetKeyRole(u8, u8),
+    SetRecoveryPolicy(Option<(u64, u32)>),
+    SetInactivityPolicy(Option<(u8, u64)>),
+    SetQueuePolicy(Option<(u64, u64)>),
+    SetAllowance(u8, u64),
+}
+
+impl From<Change> for AccountChange {
+    fn from(change: Change) -> Self {
+        match change {
+            Change::AddKey(i) => AccountChange::AddKey { key: key(i) },
+            Change::RemoveKey(i) => AccountChange::RemoveKey { key: key(i) },
+            Change::SetThreshold(threshold) => {
+                AccountChange::SetThreshold { threshold }
+            }
+            Change::SetDescription(description) => {
+                AccountChange::SetDescription { description }
+            }
+            Change::SetPrivateEvents(private_events) => {
+                AccountChange::SetPrivateEvents { private_events }
+            }
+            Change::AddDuressKey(i) => {
+                AccountChange::AddDuressKey { key: key(i) }
+            }
+            Change::RemoveDuressKey(i) => {
+                AccountChange::RemoveDuressKey { key: key(i) }
+            }
+            Change::AddCosigner(key) => AccountChange::AddCosigner {
+                key: CosignerKey::Ed25519(key),
+            },
+            Change::RemoveCosigner(key) => AccountChange::RemoveCosigner {
+                key: CosignerKey::Ed25519(key),
+            },
+            Change::SetGroupKey(i) => {
+                AccountChange::SetGroupKey { key: i.map(key) }
+            }
+            Change::AddWhitelisted(i) => {
+                AccountChange::AddWhitelisted { receiver: key(i) }
+            }
+            Change::RemoveWhitelisted(i) => {
+                AccountChange::RemoveWhitelisted { receiver: key(i) }
+            }
+            Change::SetVelocityLimit(limit) => {
+                AccountChange::SetVelocityLimit {
+                    limit: limit.map(|(amount, window)| VelocityLimit {
+                        amount,
+                        window,
+                    }),
+                }
+            }
+            Change::Unlock => AccountChange::Unlock,
+            Change::AddGuardian(i) => {
+                AccountChange::AddGuardian { key: key(i) }
+            }
+            Change::RemoveGuardian(i) => {
+                AccountChange::RemoveGuardian { key: key(i) }
+            }
+            Change::SetAlias(alias) => AccountChange::SetAlias { alias },
+            Change::SetBalanceCap(cap) => AccountChange::SetBalanceCap { cap },
+            Change::AddTag(tag) => AccountChange::AddTag { tag },
+            Change::RemoveTag(tag) => AccountChange::RemoveTag { tag },
+            Change::SetLivenessPolicy(policy) => {
+                AccountChange::SetLivenessPolicy {
+                    policy: policy.map(|(window, min_threshold)| {
+                        LivenessPolicy {
+                            window,
+                            min_threshold,
+                        }
+                    }),
+                }
+            }
+            Change::SetSupermajority(supermajority) => {
+                AccountChange::SetSupermajority {
+                    supermajority: supermajority.map(
+                        |(numerator, denominator)| Supermajority {
+                            numerator,
+                            denominator,
+                        },
+                    ),
+                }
+            }
+            Change::SetKeyAdditionPolicy(policy) => {
+                AccountChange::SetKeyAdditionPolicy {
+                    policy: policy.map(|(delay, supermajority)| {
+                        KeyAdditionPolicy {
+                            delay,
+                            supermajority,
+                        }
+                    }),
+                }
+            }
+            Change::SetTransferWindow(window) => {
+                AccountChange::SetTransferWindow {
+                    window: window.map(|(period, offset, length)| {
+                        TransferWindow {
+                            period,
+                            offset,
+                            length,
+                        }
+                    }),
+                }
+            }
+            Change::SetCategory(name, budget, window) => {
+                AccountChange::SetCategory {
+                    category: SpendingCategory {
+                        name,
+                        budget,
+                        window,
+                    },
+                }
+            }
+            Change::RemoveCategory(name) => {
+                AccountChange::RemoveCategory { name }
+            }
+            Change::AddObserver(i) => {
+                AccountChange::AddObserver { key: key(i) }
+            }
+            Change::RemoveObserver(i) => {
+                AccountChange::RemoveObserver { key: key(i) }
+            }
+            Change::SetChildBudget(child_id, amount, window) => {
+                AccountChange::SetChildBudget {
+                    budget: ChildBudget {
+                        child_id,
+                        amount,
+                        window,
+                    },
+                }
+            }
+            Change::RemoveChildBudget(child_id) => {
+                AccountChange::RemoveChildBudget { child_id }
+            }
+            Change::ClawBackBudget(child_id) => {
+                AccountChange::ClawBackBudget { child_id }
+            }
+            Change::SetKeyExpiry(i, valid_until) => {
+                AccountChange::SetKeyExpiry {
+                    key: key(i),
+                    valid_until,
+                }
+            }
+            Change::Archive => AccountChange::Archive,
+            Change::Reactivate => AccountChange::Reactivate,
+            Change::SetClaimableWithdrawals(claimable_withdrawals) => {
+                AccountChange::SetClaimableWithdrawals {
+                    claimable_withdrawals,
+                }
+            }
+            Change::SetHistoryRetention(records) => {
+                AccountChange::SetHistoryRetention { records }
+            }
+            Change::SetCompliance(compliance) => AccountChange::SetCompliance {
+                compliance: compliance.map(|(k, r)| ComplianceControl {
+                    key: key(k),
+                    remediation: key(r),
+                }),
+            },
+            // The signature of the compliance key is left out of the message
+            Change::LiftHold => AccountChange::LiftHold {
+                signature: bls::MultisigSignature::default(),
+            },
+            Change::SetLicensePolicy(policy) => {
+                AccountChange::SetLicensePolicy {
+                    policy: policy.map(|(contract, signers)| LicensePolicy {
+                        contract,
+                        signers,
+                    }),
+                }
+            }
+            Change::SetOperationThreshold(op, threshold) => {
+                AccountChange::SetOperationThreshold {
+                    op: OperationKind::ALL
+                        [op as usize % OperationKind::ALL.len()],
+                    threshold,
+                }
+            }
+            Change::SetTransferTiers(tiers) => {
+                AccountChange::SetTransferTiers {
+                    tiers: tiers
+                        .into_iter()
+                        .map(|(ceiling, threshold)| TransferTier {
+                            ceiling,
+                            threshold,
+                        })
+                        .collect(),
+                }
+            }
+            Change::SetKeyWeight(i, weight) => AccountChange::SetKeyWeight {
+                key: key(i),
+                weight,
+            },
+            Change::SetKeyRole(i, role) => AccountChange::SetKeyRole {
+                key: key(i),
+                role: KeyRole::ALL[role as usize % KeyRole::ALL.len()],
+            },
+            Change::SetRecoveryPolicy(policy) => {
+                AccountChange::SetRecoveryPolicy {
+                    policy: policy.map(|(delay, guardians)| RecoveryPolicy {
+                        delay,
+                        guardians,
+                    }),
+                }
+            }
+            Change::SetInactivityPolicy(policy) => {
+                AccountChange::SetInactivityPolicy {
+                    policy: policy.map(|(i, period)| InactivityPolicy {
+                        fallback: key(i),
+                        period,
+                    }),
+                }
+            }
+            Change::SetQueuePolicy(policy) => AccountChange::SetQueuePolicy {
+                policy: policy.map(|(min_amount, delay)| QueuePolicy {
+                    min_amount,
+                    delay,
+                }),
+            },
+            Change::SetAllowance(i, amount) => AccountChange::SetAllowance {
+                key: key(i),
+                amount,
+            },
+        }
+    }
+}
+
+/// Cursor over a signature message, panicking if it's read past its end.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, n: usize) -> &'a [u8] {
+        let (bytes, rest) = self.0.split_at(n);
+        self.0 = rest;
+        bytes
+    }
+
+    fn u8(&mut self) -> u8 {
+        self.bytes(1)[0]
+    }
+
+    fn u32(&mut self) -> u32 {
+        u32::from_le_bytes(self.bytes(4).try_into().unwrap())
+    }
+
+    fn u64(&mut self) -> u64 {
+        u64::from_le_bytes(self.bytes(8).try_into().unwrap())
+    }
+
+    fn key(&mut self) -> [u8; RAW_KEY_SIZE] {
+        self.bytes(RAW_KEY_SIZE).try_into().unwrap()
+    }
+
+    /// Reads a co-signer key, whose length is given by the tag of its scheme.
+    fn cosigner(&mut self) -> (u8, Vec<u8>) {
+        match self.u8() {
+            0 => (0, self.bytes(32).to_vec()),
+            scheme => panic!("Unknown co-signer scheme: {scheme}"),
+        }
+    }
+
+    fn string(&mut self, n: usize) -> String {
+        String::from_utf8(self.bytes(n).to_vec())
+            .expect("Strings should be encoded as UTF-8")
+    }
+}
+
+fn check_transfer(transfer: &Transfer) {
+    let msg = transfer.signature_msg();
+    let mut reader = Reader(&msg);
+
+    if let Some(category) = &transfer.category {
+        assert_eq!(reader.bytes(17), b"multisig-category");
+        let len = reader.u32() as usize;
+        assert_eq!(&reader.string(len), category);
+    }
+    if let Some(reference) = transfer.reference {
+        assert_eq!(reader.bytes(18), b"multisig-reference");
+        assert_eq!(reader.bytes(32), reference);
+    }
+
+    assert_eq!(reader.u64(), transfer.account_id);
+    assert_eq!(reader.key(), transfer.receiver.to_raw_bytes());
+    assert_eq!(reader.u64(), transfer.amount);
+    assert_eq!(reader.u64(), transfer.nonce);
+
+    // The memo takes up the rest of the message
+    let rest = reader.0.len();
+    assert_eq!(reader.string(rest), transfer.memo);
+}
+
+/// A change as decoded from a signature message.
+#[derive(Debug, PartialEq)]
+enum DecodedChange {
+    AddKey([u8; RAW_KEY_SIZE]),
+    RemoveKey([u8; RAW_KEY_SIZE]),
+    SetThreshold(u32),
+    SetDescription(String),
+    SetPrivateEvents(bool),
+    AddDuressKey([u8; RAW_KEY_SIZE]),
+    RemoveDuressKey([u8; RAW_KEY_SIZE]),
+    AddCosigner((u8, Vec<u8>)),
+    RemoveCosigner((u8, Vec<u8>)),
+    SetGroupKey(Option<[u8; RAW_KEY_SIZE]>),
+    AddWhitelisted([u8; RAW_KEY_SIZE]),
+    RemoveWhitelisted([u8; RAW_KEY_SIZE]),
+    SetVelocityLimit(Option<(u64, u64)>),
+    Unlock,
+    AddGuardian([u8; RAW_KEY_SIZE]),
+    RemoveGuardian([u8; RAW_KEY_SIZE]),
+    SetAlias(Option<String>),
+    SetBalanceCap(Option<u64>),
+    AddTag(String),
+    RemoveTag(String),
+    SetLivenessPolicy(Option<(u64, u32)>),
+    SetSupermajority(Option<(u32, u32)>),
+    SetKeyAdditionPolicy(Option<(u64, bool)>),
+    SetTransferWindow(Option<(u64, u64, u64)>),
+    SetCategory(String, u64, u64),
+    RemoveCategory(String),
+    AddObserver([u8; RAW_KEY_SIZE]),
+    RemoveObserver([u8; RAW_KEY_SIZE]),
+    SetChildBudget(u64, u64, u64),
+    RemoveChildBudget(u64),
+    ClawBackBudget(u64),
+    SetKeyExpiry([u8; RAW_KEY_SIZE], Option<u64>),
+    Archive,
+    Reactivate,
+    SetClaimableWithdrawals(bool),
+    SetHistoryRetention(Option<u32>),
+    SetCompliance(Option<([u8; RAW_KEY_SIZE], [u8; RAW_KEY_SIZE])>),
+    LiftHold,
+    SetLicensePolicy(Option<([u8; 32], bool)>),
+    SetOperationThreshold(u8, Option<u32>),
+    SetTransferTiers(Vec<(u64, u32)>),
+    SetKeyWeight([u8; RAW_KEY_SIZE], u32),
+    SetKeyRole([u8; RAW_KEY_SIZE], u8),
+    SetRecoveryPolicy(Option<(u64, u32)>),
+    SetInactivityPolicy(Option<([u8; RAW_KEY_SIZE], u64)>),
+    SetQueuePolicy(Option<(u64, u64)>),
+    SetAllowance([u8; RAW_KEY_SIZE], u64),
+}
+
+/// Encodes a co-signer key as the reference decoder reads it.
+fn cosigner(key: &CosignerKey) -> (u8, Vec<u8>) {
+    match key {
+        CosignerKey::Ed25519(key) => (0, key.to_vec()),
+    }
+}
+
+impl From<&AccountChange> for DecodedChange {
+    fn from(change: &AccountChange) -> Self {
+        match change {
+            AccountChange::AddKey { key } => Self::AddKey(key.to_raw_bytes()),
+            AccountChange::RemoveKey { key } => {
+                Self::RemoveKey(key.to_raw_bytes())
+            }
+            AccountChange::SetThreshold { threshold } => {
+                Self::SetThreshold(*threshold)
+            }
+            AccountChange::SetDescription { description } => {
+                Self::SetDescription(description.clone())
+            }
+            AccountChange::SetPrivateEvents { private_events } => {
+                Self::SetPrivateEvents(*private_events)
+            }
+            AccountChange::AddDuressKey { key } => {
+                Self::AddDuressKey(key.to_raw_bytes())
+            }
+            AccountChange::RemoveDuressKey { key } => {
+                Self::RemoveDuressKey(key.to_raw_bytes())
+            }
+            AccountChange::AddCosigner { key } => {
+                Self::AddCosigner(cosigner(key))
+            }
+            AccountChange::RemoveCosigner { key } => {
+                Self::RemoveCosigner(cosigner(key))
+            }
+            AccountChange::SetGroupKey { key } => {
+                Self::SetGroupKey(key.map(|key| key.to_raw_bytes()))
+            }
+            AccountChange::AddWhitelisted { receiver } => {
+                Self::AddWhitelisted(receiver.to_raw_bytes())
+            }
+            AccountChange::RemoveWhitelisted { receiver } => {
+                Self::RemoveWhitelisted(receiver.to_raw_bytes())
+            }
+            AccountChange::SetVelocityLimit { limit } => {
+                Self::SetVelocityLimit(
+                    limit.map(|limit| (limit.amount, limit.window)),
+                )
+            }
+            AccountChange::Unlock => Self::Unlock,
+            AccountChange::AddGuardian { key } => {
+                Self::AddGuardian(key.to_raw_bytes())
+            }
+            AccountChange::RemoveGuardian { key } => {
+                Self::RemoveGuardian(key.to_raw_bytes())
+            }
+            AccountChange::SetAlias { alias } => Self::SetAlias(alias.clone()),
+            AccountChange::SetBalanceCap { cap } => Self::SetBalanceCap(*cap),
+            AccountChange::AddTag { tag } => Self::AddTag(tag.clone()),
+            AccountChange::RemoveTag { tag } => Self::RemoveTag(tag.clone()),
+            AccountChange::SetLivenessPolicy { policy } => {
+                Self::SetLivenessPolicy(
+                    policy.map(|policy| (policy.window, policy.min_threshold)),
+                )
+            }
+            AccountChange::SetSupermajority { supermajority } => {
+                Self::SetSupermajority(supermajority.map(|supermajority| {
+                    (supermajority.numerator, supermajority.denominator)
+                }))
+            }
+            AccountChange::SetKeyAdditionPolicy { policy } => {
+                Self::SetKeyAdditionPolicy(
+                    policy.map(|policy| (policy.delay, policy.supermajority)),
+                )
+            }
+            AccountChange::SetTransferWindow { window } => {
+                Self::SetTransferWindow(window.map(|window| {
+                    (window.period, window.offset, window.length)
+                }))
+            }
+            AccountChange::SetCategory { category } => Self::SetCategory(
+                category.name.clone(),
+                category.budget,
+                category.window,
+            ),
+            AccountChange::RemoveCategory { name } => {
+                Self::RemoveCategory(name.clone())
+            }
+            AccountChange::AddObserver { key } => {
+                Self::AddObserver(key.to_raw_bytes())
+            }
+            AccountChange::RemoveObserver { key } => {
+                Self::RemoveObserver(key.to_raw_bytes())
+            }
+            AccountChange::SetChildBudget { budget } => Self::SetChildBudget(
+                budget.child_id,
+                budget.amount,
+                budget.window,
+            ),
+            AccountChange::RemoveChildBudget { child_id } => {
+                Self::RemoveChildBudget(*child_id)
+            }
+            AccountChange::ClawBackBudget { child_id } => {
+                Self::ClawBackBudget(*child_id)
+            }
+            AccountChange::SetKeyExpiry { key, valid_until } => {
+                Self::SetKeyExpiry(key.to_raw_bytes(), *valid_until)
+            }
+            AccountChange::Archive => Self::Archive,
+            AccountChange::Reactivate => Self::Reactivate,
+            AccountChange::SetClaimableWithdrawals {
+                claimable_withdrawals,
+            } => Self::SetClaimableWithdrawals(*claimable_withdrawals),
+            AccountChange::SetHistoryRetention { records } => {
+                Self::SetHistoryRetention(*records)
+            }
+            AccountChange::SetCompliance { compliance } => {
+                Self::SetCompliance(compliance.as_ref().map(|compliance| {
+                    (
+                        compliance.key.to_raw_bytes(),
+                        compliance.remediation.to_raw_bytes(),
+                    )
+                }))
+            }
+            AccountChange::LiftHold { .. } => Self::LiftHold,
+            AccountChange::SetLicensePolicy { policy } => {
+                Self::SetLicensePolicy(
+                    policy.map(|policy| (policy.contract, policy.signers)),
+                )
+            }
+            AccountChange::SetOperationThreshold { op, threshold } => {
+                Self::SetOperationThreshold(*op as u8, *threshold)
+            }
+            AccountChange::SetTransferTiers { tiers } => {
+                Self::SetTransferTiers(
+                    tiers.iter().map(|t| (t.ceiling, t.threshold)).collect(),
+                )
+            }
+            AccountChange::SetKeyWeight { key, weight } => {
+                Self::SetKeyWeight(key.to_raw_bytes(), *weight)
+            }
+            AccountChange::SetKeyRole { key, role } => {
+                Self::SetKeyRole(key.to_raw_bytes(), *role as u8)
+            }
+            AccountChange::SetRecoveryPolicy { policy } => {
+                Self::SetRecoveryPolicy(
+                    policy.map(|policy| (policy.delay, policy.guardians)),
+                )
+            }
+            AccountChange::SetInactivityPolicy { policy } => {
+                Self::SetInactivityPolicy(policy.as_ref().map(|policy| {
+                    (policy.fallback.to_raw_bytes(), policy.period)
+                }))
+            }
+            AccountChange::SetQueuePolicy { policy } => Self::SetQueuePolicy(
+                policy.map(|policy| (policy.min_amount, policy.delay)),
+            ),
+            AccountChange::SetAllowance { key, amount } => {
+                Self::SetAllowance(key.to_raw_bytes(), *amount)
+            }
+        }
+    }
+}
+
+/// Decodes changes solely based on the message's contents, until only the
+/// nonce is left.
+fn read_changes(reader: &mut Reader) -> Vec<DecodedChange> {
+    let mut changes = Vec::new();
+    while reader.0.len() > 8 {
+        let change = match reader.u8() {
+            0 => DecodedChange::AddKey(reader.key()),
+            1 => DecodedChange::RemoveKey(reader.key()),
+            2 => DecodedChange::SetThreshold(reader.u32()),
+            3 => {
+                let len = reader.u32() as usize;
+                DecodedChange::SetDescription(reader.string(len))
+            }
+            4 => match reader.u8() {
+                0 => DecodedChange::SetPrivateEvents(false),
+                1 => DecodedChange::SetPrivateEvents(true),
+                flag => panic!("Invalid private events flag: {flag}"),
+            },
+            5 => DecodedChange::AddDuressKey(reader.key()),
+            6 => DecodedChange::RemoveDuressKey(reader.key()),
+            7 => DecodedChange::AddCosigner(reader.cosigner()),
+            8 => DecodedChange::RemoveCosigner(reader.cosigner()),
+            9 => match reader.u8() {
+                0 => DecodedChange::SetGroupKey(None),
+                1 => DecodedChange::SetGroupKey(Some(reader.key())),
+                flag => panic!("Invalid group key flag: {flag}"),
+            },
+            10 => DecodedChange::AddWhitelisted(reader.key()),
+            11 => DecodedChange::RemoveWhitelisted(reader.key()),
+            12 => match reader.u8() {
+                0 => DecodedChange::SetVelocityLimit(None),
+                1 => DecodedChange::SetVelocityLimit(Some((
+                    reader.u64(),
+                    reader.u64(),
+                ))),
+                flag => panic!("Invalid velocity limit flag: {flag}"),
+            },
+            13 => DecodedChange::Unlock,
+            14 => DecodedChange::AddGuardian(reader.key()),
+            15 => DecodedChange::RemoveGuardian(reader.key()),
+            16 => match reader.u8() {
+                0 => DecodedChange::SetAlias(None),
+                1 => {
+                    let len = reader.u32() as usize;
+                    DecodedChange::SetAlias(Some(reader.string(len)))
+                }
+                flag => panic!("Invalid alias flag: {flag}"),
+            },
+            17 => match reader.u8() {
+                0 => DecodedChange::SetBalanceCap(None),
+                1 => DecodedChange::SetBalanceCap(Some(reader.u64())),
+                flag => panic!("Invalid balance cap flag: {flag}"),
+            },
+            18 => {
+                let len = reader.u32() as usize;
+                DecodedChange::AddTag(reader.string(len))
+            }
+            19 => {
+                let len = reader.u32() as usize;
+                DecodedChange::RemoveTag(reader.string(len))
+            }
+            20 => match reader.u8() {
+                0 => DecodedChange::SetLivenessPolicy(None),
+                1 => DecodedChange::SetLivenessPolicy(Some((
+                    reader.u64(),
+                    reader.u32(),
+                ))),
+                flag => panic!("Invalid liveness policy flag: {flag}"),
+            },
+            21 => match reader.u8() {
+                0 => DecodedChange::SetSupermajority(None),
+                1 => DecodedChange::SetSupermajority(Some((
+                    reader.u32(),
+                    reader.u32(),
+                ))),
+                flag => panic!("Invalid supermajority flag: {flag}"),
+            },
+            22 => match reader.u8() {
+                0 => DecodedChange::SetKeyAdditionPolicy(None),
+                1 => {
+                    let delay = reader.u64();
+                    let supermajority = match reader.u8() {
+                        0 => false,
+                        1 => true,
+                        flag => panic!("Invalid supermajority flag: {flag}"),
+                    };
+                    DecodedChange::SetKeyAdditionPolicy(Some((
+                        delay,
+                        supermajority,
+                    )))
+                }
+                flag => panic!("Invalid key addition policy flag: {flag}"),
+            },
+            23 => match reader.u8() {
+                0 => DecodedChange::SetTransferWindow(None),
+                1 => DecodedChange::SetTransferWindow(Some((
+                    reader.u64(),
+                    reader.u64(),
+                    reader.u64(),
+                ))),
+                flag => panic!("Invalid transfer window flag: {flag}"),
+            },
+            24 => {
+                let len = reader.u32() as usize;
+                DecodedChange::SetCategory(
+                    reader.string(len),
+                    reader.u64(),
+                    reader.u64(),
+                )
+            }
+            25 => {
+                let len = reader.u32() as usize;
+                DecodedChange::RemoveCategory(reader.string(len))
+            }
+            26 => DecodedChange::AddObserver(reader.key()),
+            27 => DecodedChange::RemoveObserver(reader.key()),
+            28 => DecodedChange::SetChildBudget(
+                reader.u64(),
+                reader.u64(),
+                reader.u64(),
+            ),
+            29 => DecodedChange::RemoveChildBudget(reader.u64()),
+            30 => DecodedChange::ClawBackBudget(reader.u64()),
+            31 => match reader.u8() {
+                0 => DecodedChange::SetKeyExpiry(reader.key(), None),
+                1 => DecodedChange::SetKeyExpiry(
+                    reader.key(),
+                    Some(reader.u64()),
+                ),
+                flag => panic!("Invalid key expiry flag: {flag}"),
+            },
+            32 => DecodedChange::Archive,
+            33 => DecodedChange::Reactivate,
+            34 => match reader.u8() {
+                0 => DecodedChange::SetClaimableWithdrawals(false),
+                1 => DecodedChange::SetClaimableWithdrawals(true),
+                flag => panic!("Invalid claimable withdrawals flag: {flag}"),
+            },
+            35 => match reader.u8() {
+                0 => DecodedChange::SetHistoryRetention(None),
+                1 => DecodedChange::SetHistoryRetention(Some(reader.u32())),
+                flag => panic!("Invalid history retention flag: {flag}"),
+            },
+            36 => match reader.u8() {
+                0 => DecodedChange::SetCompliance(None),
+                1 => DecodedChange::SetCompliance(Some((
+                    reader.key(),
+                    reader.key(),
+                ))),
+                flag => panic!("Invalid compliance flag: {flag}"),
+            },
+            37 => DecodedChange::LiftHold,
+            38 => match reader.u8() {
+                0 => DecodedChange::SetLicensePolicy(None),
+                1 => {
+                    let contract = reader.bytes(32).try_into().unwrap();
+                    let signers = match reader.u8() {
+                        0 => false,
+                        1 => true,
+                        flag => panic!("Invalid license signers flag: {flag}"),
+                    };
+                    DecodedChange::SetLicensePolicy(Some((contract, signers)))
+                }
+                flag => panic!("Invalid license policy flag: {flag}"),
+            },
+            39 => {
+                let op = reader.u8();
+                match reader.u8() {
+                    0 => DecodedChange::SetOperationThreshold(op, None),
+                    1 => DecodedChange::SetOperationThreshold(
+                        op,
+                        Some(reader.u32()),
+                    ),
+                    flag => panic!("Invalid operation threshold flag: {flag}"),
+                }
+            }
+            40 => {
+                let len = reader.u32() as usize;
+                DecodedChange::SetTransferTiers(
+                    (0..len).map(|_| (reader.u64(), reader.u32())).collect(),
+                )
+            }
+            41 => DecodedChange::SetKeyWeight(reader.key(), reader.u32()),
+            42 => DecodedChange::SetKeyRole(reader.key(), reader.u8()),
+            43 => match reader.u8() {
+                0 => DecodedChange::SetRecoveryPolicy(None),
+                1 => DecodedChange::SetRecoveryPolicy(Some((
+                    reader.u64(),
+                    reader.u32(),
+                ))),
+                flag => panic!("Invalid recovery policy flag: {flag}"),
+            },
+            44 => match reader.u8() {
+                0 => DecodedChange::SetInactivityPolicy(None),
+                1 => DecodedChange::SetInactivityPolicy(Some((
+                    reader.key(),
+                    reader.u64(),
+                ))),
+                flag => panic!("Invalid inactivity policy flag: {flag}"),
+            },
+            45 => match reader.u8() {
+                0 => DecodedChange::SetQueuePolicy(None),
+                1 => DecodedChange::SetQueuePolicy(Some((
+                    reader.u64(),
+                    reader.u64(),
+                ))),
+                flag => panic!("Invalid queue policy flag: {flag}"),
+            },
+            46 => DecodedChange::SetAllowance(reader.key(), reader.u64()),
+            tag => panic!("Unknown change tag: {tag}"),
+        };
+        changes.push(change);
+    }
+    changes
+}
+
+fn check_change_account(change_account: &ChangeAccount) {
+    let msg = change_account.signature_msg();
+    let mut reader = Reader(&msg);
+
+    assert_eq!(reader.u64(), change_account.account_id);
+
+    // The changes are decoded solely based on the message's contents, until
+    // only the nonce is left.
+    let changes = read_changes(&mut reader);
+
+    let expected: Vec<DecodedChange> =
+        change_account.changes.iter().map(Into::into).collect();
+    assert_eq!(changes, expected);
+
+    assert_eq!(reader.u64(), change_account.nonce);
+    assert!(reader.0.is_empty(), "The whole message should be consumed");
+}
+
+fn check_split_account(split: &SplitAccount) {
+    let msg = split.signature_msg();
+    let mut reader = Reader(&msg);
+
+    assert_eq!(reader.bytes(14), b"multisig-split");
+    assert_eq!(reader.u64(), split.account_id);
+    assert_eq!(reader.u64(), split.amount);
+
+    // The new keys are tagged as added keys, up to the threshold's tag
+    let mut new_keys = Vec::new();
+    let threshold = loop {
+        match reader.u8() {
+            0 => new_keys.push(reader.key()),
+            2 => break reader.u32(),
+            tag => panic!("Unknown split tag: {tag}"),
+        }
+    };
+    let expected: Vec<_> = split
+        .new_keys
+        .iter()
+        .map(|key| key.to_raw_bytes())
+        .collect();
+    assert_eq!(new_keys, expected);
+    assert_eq!(threshold, split.threshold);
+
+    assert_eq!(reader.u8(), 3);
+    let len = reader.u32() as usize;
+    assert_eq!(reader.string(len), split.description);
+
+    assert_eq!(reader.u64(), split.nonce);
+    assert!(reader.0.is_empty(), "The whole message should be consumed");
+}
+
+fn check_payroll(payroll: &Payroll) {
+    let msg = payroll.signature_msg();
+    let mut reader = Reader(&msg);
+
+    assert_eq!(reader.bytes(16), b"multisig-payroll");
+    assert_eq!(reader.u64(), payroll.account_id);
+
+    // The rows are decoded until only the nonce is left
+    let mut rows = Vec::new();
+    while reader.0.len() > 8 {
+        let receiver = reader.key();
+        let amount = reader.u64();
+        let len = reader.u32() as usize;
+        rows.push((receiver, amount, reader.string(len)));
+    }
+    let expected: Vec<_> = payroll
+        .rows
+        .iter()
+        .map(|row| (row.receiver.to_raw_bytes(), row.amount, row.memo.clone()))
+        .collect();
+    assert_eq!(rows, expected);
+
+    assert_eq!(reader.u64(), payroll.nonce);
+    assert!(reader.0.is_empty(), "The whole message should be consumed");
+}
+
+fn check_transfer_and_change(transfer_and_change: &TransferAndChange) {
+    let msg = transfer_and_change.signature_msg();
+    let mut reader = Reader(&msg);
+
+    assert_eq!(reader.bytes(24), b"multisig-transfer-change");
+    assert_eq!(reader.u64(), transfer_and_change.account_id);
+    assert_eq!(reader.key(), transfer_and_change.receiver.to_raw_bytes());
+    assert_eq!(reader.u64(), transfer_and_change.amount);
+    let len = reader.u32() as usize;
+    assert_eq!(reader.string(len), transfer_and_change.memo);
+
+    let changes = read_changes(&mut reader);
+    let expected: Vec<DecodedChange> =
+        transfer_and_change.changes.iter().map(Into::into).collect();
+    assert_eq!(changes, expected);
+
+    assert_eq!(reader.u64(), transfer_and_change.nonce);
+    assert!(reader.0.is_empty(), "The whole message should be consumed");
+}
+
+/// Checks that the hardware wallet payload of an operation survives being
+/// split into chunks, and that devices would sign the same message.
+fn check_payload(operation: &Operation) {
+    let Some(payload) = SigningPayload::new(operation) else {
+        return;
+    };
+
+    assert_eq!(payload.message(), operation.signature_msg());
+
+    let chunks = payload.chunks(MAX_CHUNK_SIZE);
+    assert_eq!(SigningPayload::from_chunks(&chunks), Some(payload));
+}
+
+fuzz_target!(|input: Input| {
+    let operation = match input {
+        Input::Transfer {
+            account_id,
+            receiver,
+            amount,
+            nonce,
+            memo,
+            reference,
+            category,
+        } => {
+            let transfer = Transfer {
+                account_id,
+                keys: Vec::new(),
+                signature: bls::MultisigSignature::default(),
+                cosignatures: Vec::new(),
+                group_signature: None,
+                receiver: key(receiver),
+                amount,
+                nonce,
+                memo,
+                reference,
+                category,
+                gas_refund: None,
+                chain_id: None,
+            };
+            check_transfer(&transfer);
+            Operation::Transfer(transfer)
+        }
+        Input::ChangeAccount {
+            account_id,
+            changes,
+            nonce,
+        } => {
+            let change_account = ChangeAccount {
+                account_id,
+                keys: Vec::new(),
+                signature: bls::MultisigSignature::default(),
+                cosignatures: Vec::new(),
+                group_signature: None,
+                changes: changes.into_iter().map(Into::into).collect(),
+                nonce,
+                gas_refund: None,
+                chain_id: None,
+            };
+            check_change_account(&change_account);
+            Operation::ChangeAccount(change_account)
+        }
+        Input::SplitAccount {
+            account_id,
+            new_keys,
+            threshold,
+            description,
+            amount,
+            nonce,
+        } => {
+            let split = SplitAccount {
+                account_id,
+                keys: Vec::new(),
+                signature: bls::MultisigSignature::default(),
+                cosignatures: Vec::new(),
+                group_signature: None,
+                new_keys: new_keys.into_iter().map(key).collect(),
+                threshold,
+                description,
+                amount,
+                nonce,
+                gas_refund: None,
+                chain_id: None,
+            };
+            check_split_account(&split);
+            Operation::SplitAccount(split)
+        }
+        Input::Payroll {
+            account_id,
+            rows,
+            nonce,
+        } => {
+            let payroll = Payroll {
+                account_id,
+                keys: Vec::new(),
+                signature: bls::MultisigSignature::default(),
+                cosignatures: Vec::new(),
+                group_signature: None,
+                rows: rows
+                    .into_iter()
+                    .map(|(receiver, amount, memo)| PayrollRow {
+                        receiver: key(receiver),
+                        amount,
+                        memo,
+                    })
+                    .collect(),
+                nonce,
+                gas_refund: None,
+                chain_id: None,
+            };
+            check_payroll(&payroll);
+            Operation::Payroll(payroll)
+        }
+        Input::TransferAndChange {
+            account_id,
+            receiver,
+            amount,
+            memo,
+            changes,
+            nonce,
+        } => {
+            let transfer_and_change = TransferAndChange {
+                account_id,
+                keys: Vec::new(),
+                signature: bls::MultisigSignature::default(),
+                cosignatures: Vec::new(),
+                group_signature: None,
+                receiver: key(receiver),
+                amount,
+                memo,
+                changes: changes.into_iter().map(Into::into).collect(),
+                nonce,
+                gas_refund: None,
+                chain_id: None,
+            };
+            check_transfer_and_change(&transfer_and_change);
+            Operation::TransferAndChange(transfer_and_change)
+        }
+    };
+
+    check_payload(&operation);
+});
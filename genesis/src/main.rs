@@ -0,0 +1,206 @@
+//! Genesis state generator for the `multisig-contract`.
+//!
+//! Takes a description of the accounts the contract should be deployed with,
+//! in TOML or JSON, and produces:
+//!
+//! - the argument to pass to the contract's `init` function on deployment
+//! - the `state.toml` snapshot entries funding the Moonlight accounts of the
+//!   accounts' keys, so that signers can pay for their transactions
+//!
+//! A description looks like this:
+//!
+//! ```toml
+//! # Balance of the Moonlight account of each key, for paying gas
+//! signer_balance = 10_000_000_000
+//! # Most the contract may hold across all accounts, left out for no cap
+//! custody_cap = 100_000_000
+//! # Smallest deposit accepted, left out for no minimum
+//! min_deposit = 1_000
+//! # Account governing the contract-wide parameters, left out for none
+//! governance = 1
+//!
+//! [[account]]
+//! keys = ["<base58 public key>", "<base58 public key>"]
+//! threshold = 2
+//! description = "treasury"
+//! balance = 1_000_000
+//! ```
+
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use dusk_bytes::Serializable;
+use serde::Deserialize;
+
+use multisig_contract_types::*;
+
+type Result<T, E = Box<dyn std::error::Error + Send + Sync>> =
+    std::result::Result<T, E>;
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// TOML or JSON file describing the accounts.
+    input: PathBuf,
+    /// File to write the argument of the contract's `init` function to.
+    #[arg(long)]
+    init: PathBuf,
+    /// File to write the snapshot entries to, instead of standard output.
+    #[arg(long)]
+    state: Option<PathBuf>,
+}
+
+/// The description of the genesis accounts.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Description {
+    /// Balance of the Moonlight account of each key.
+    #[serde(default)]
+    signer_balance: u64,
+    /// The most the contract may hold across all accounts.
+    custody_cap: Option<u64>,
+    /// The smallest amount a deposit may be.
+    min_deposit: Option<u64>,
+    /// The account governing the contract-wide parameters.
+    governance: Option<u64>,
+    #[serde(rename = "account", default)]
+    accounts: Vec<AccountDescription>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AccountDescription {
+    keys: Vec<String>,
+    threshold: u32,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    balance: u64,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let description = read_description(&args.input)?;
+    let genesis = genesis(&description)?;
+
+    let total = genesis
+        .accounts
+        .iter()
+        .try_fold(0u64, |total, account| total.checked_add(account.balance))
+        .ok_or("The total balance of the accounts overflows")?;
+    if genesis.custody_cap.is_some_and(|cap| total > cap) {
+        return Err(Error::CustodyCapExceeded.to_string().into());
+    }
+
+    let init = rkyv::to_bytes::<_, 1024>(&genesis)
+        .map_err(|_| "Serializing the genesis should succeed")?;
+    fs::write(&args.init, init)?;
+
+    let state = snapshot_entries(&genesis, description.signer_balance);
+    match args.state {
+        Some(path) => fs::write(path, state)?,
+        None => print!("{state}"),
+    }
+
+    eprintln!("The contract must hold a balance of {total} LUX");
+
+    Ok(())
+}
+
+fn read_description(path: &Path) -> Result<Description> {
+    let contents = fs::read_to_string(path)?;
+
+    let description = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => serde_json::from_str(&contents)?,
+        _ => toml::from_str(&contents)?,
+    };
+
+    Ok(description)
+}
+
+/// Builds the genesis, rejecting accounts the contract would fail to create.
+fn genesis(description: &Description) -> Result<Genesis> {
+    let mut accounts = Vec::with_capacity(description.accounts.len());
+
+    for (i, account) in description.accounts.iter().enumerate() {
+        let account_id = i + 1;
+        let invalid = |err: Error| format!("Account {account_id}: {err}");
+
+        let keys = account
+            .keys
+            .iter()
+            .map(|key| parse_public_key(key))
+            .collect::<Result<Vec<_>>>()?;
+
+        if keys.is_empty() {
+            return Err(invalid(Error::NoKeys).into());
+        }
+        if account.threshold < 1 {
+            return Err(invalid(Error::ZeroThreshold).into());
+        }
+        if account.threshold as usize > keys.len() {
+            return Err(invalid(Error::ThresholdTooLarge).into());
+        }
+        let unique: BTreeSet<_> = keys.iter().map(|k| k.to_bytes()).collect();
+        if unique.len() != keys.len() {
+            return Err(invalid(Error::DuplicateKey).into());
+        }
+
+        accounts.push(GenesisAccount {
+            keys,
+            threshold: account.threshold,
+            description: account.description.clone(),
+            balance: account.balance,
+        });
+    }
+
+    if description
+        .governance
+        .is_some_and(|id| id < 1 || id > accounts.len() as u64)
+    {
+        return Err(format!("Governance: {}", Error::AccountNotFound).into());
+    }
+
+    Ok(Genesis {
+        accounts,
+        custody_cap: description.custody_cap,
+        min_deposit: description.min_deposit,
+        governance: description.governance,
+    })
+}
+
+/// Returns the `state.toml` entries funding the Moonlight account of each key
+/// used by the genesis accounts.
+fn snapshot_entries(genesis: &Genesis, signer_balance: u64) -> String {
+    let mut entries = String::new();
+
+    if signer_balance == 0 {
+        return entries;
+    }
+
+    let keys: BTreeSet<_> = genesis
+        .accounts
+        .iter()
+        .flat_map(|account| &account.keys)
+        .map(encoding::encode_public_key)
+        .collect();
+
+    for key in keys {
+        let _ = writeln!(entries, "[[moonlight_account]]");
+        let _ = writeln!(entries, "address = \"{key}\"");
+        let _ = writeln!(entries, "balance = {signer_balance}");
+        let _ = writeln!(entries);
+    }
+
+    entries
+}
+
+/// Parses a base58 encoded public key.
+fn parse_public_key(s: &str) -> Result<bls::PublicKey> {
+    encoding::decode_public_key(s)
+        .map_err(|err| format!("{err} public key: {s}").into())
+}
@@ -0,0 +1,116 @@
+//! Event indexer for the `multisig-contract`.
+//!
+//! The indexer subscribes to the events emitted by the contract on a rusk
+//! node, decodes them into typed records, and maintains a SQLite mirror of the
+//! accounts, their keys and their history for explorers and back offices to
+//! query.
+//!
+//! On startup, the state of every existing account is fetched from the node,
+//! so that the mirror is complete even if the indexer wasn't running when the
+//! accounts were created. The history, however, only covers the events
+//! received while the indexer runs.
+
+// The `json!` of the account change events nests past the default limit
+#![recursion_limit = "256"]
+
+mod store;
+
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use execution_core::ContractId;
+
+use multisig_client::{MultisigClient, Record};
+use multisig_contract_types::*;
+
+use crate::store::Store;
+
+type Result<T, E = Box<dyn std::error::Error + Send + Sync>> =
+    std::result::Result<T, E>;
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// URL of the rusk node to index events from.
+    #[arg(long, env = "RUSK_NODE", default_value = "http://127.0.0.1:8080")]
+    node: String,
+    /// Hex encoded ID of the multisig contract.
+    #[arg(long, env = "MULTISIG_CONTRACT")]
+    contract: String,
+    /// Path to the SQLite database to maintain.
+    #[arg(long, default_value = "multisig.sqlite")]
+    database: PathBuf,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let contract = hex::decode(&args.contract)?
+        .try_into()
+        .map_err(|_| "The contract ID must be 32 bytes long")?;
+    let contract = ContractId::from_bytes(contract);
+
+    let client = MultisigClient::new(args.node, contract);
+    let mut store = Store::open(&args.database)?;
+
+    // Subscribing before fetching the existing accounts ensures no change
+    // made in between is missed.
+    let mut subscription = client.subscribe(&Record::TOPICS).await?;
+
+    let mut account_id = 1;
+    while sync_account(&client, &mut store, account_id).await? {
+        account_id += 1;
+    }
+    eprintln!("Synced {} existing accounts", account_id - 1);
+
+    let mut last_sequence = None;
+    while let Some((topic, data)) = subscription.next().await? {
+        let Some(record) = Record::decode(&topic, &data) else {
+            match event_version(&data) {
+                Some(EVENT_VERSION) => {
+                    eprintln!("Skipping undecodable `{topic}` event")
+                }
+                version => eprintln!(
+                    "Skipping `{topic}` event of unsupported version \
+                     {version:?}"
+                ),
+            }
+            continue;
+        };
+
+        // Events are numbered without gaps, so a jump means some were missed,
+        // and the history is incomplete
+        let sequence = record.sequence();
+        if let Some(last) = last_sequence {
+            if sequence != last + 1 {
+                eprintln!("Missed events {} to {}", last + 1, sequence - 1);
+            }
+        }
+        last_sequence = Some(sequence);
+
+        for account_id in record.account_ids() {
+            sync_account(&client, &mut store, account_id).await?;
+            store.push_history(account_id, &record)?;
+        }
+    }
+
+    Err("The node closed the session".into())
+}
+
+/// Fetches the current state of an account from the node and stores it,
+/// returning false if the account doesn't exist.
+async fn sync_account(
+    client: &MultisigClient,
+    store: &mut Store,
+    account_id: u64,
+) -> Result<bool> {
+    let Some(account) = client.account(account_id).await? else {
+        return Ok(false);
+    };
+    let keys = client.account_keys(account_id).await?;
+
+    store.put_account(account_id, &account, &keys)?;
+    Ok(true)
+}
@@ -0,0 +1,726 @@
+//! The SQLite mirror of the contract's state.
+//!
+//! Accounts and their keys are always stored as the node last reported them,
+//! while the events are appended to the history in the order they are
+//! received - each once, those delivered again being told apart by their
+//! ID.
+
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+use serde_json::json;
+
+use multisig_contract_types::*;
+
+use crate::Result;
+use multisig_client::Record;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS accounts (
+    id          INTEGER PRIMARY KEY,
+    balance     INTEGER NOT NULL,
+    threshold   INTEGER NOT NULL,
+    nonce       INTEGER NOT NULL,
+    description TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS account_keys (
+    account_id INTEGER NOT NULL REFERENCES accounts (id),
+    key        TEXT NOT NULL,
+    PRIMARY KEY (account_id, key)
+);
+
+CREATE INDEX IF NOT EXISTS account_keys_key ON account_keys (key);
+
+CREATE TABLE IF NOT EXISTS history (
+    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+    account_id INTEGER NOT NULL,
+    topic      TEXT NOT NULL,
+    amount     INTEGER,
+    memo       TEXT,
+    details    TEXT NOT NULL,
+    event_id   TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS history_account ON history (account_id);
+CREATE UNIQUE INDEX IF NOT EXISTS history_event ON history (account_id, event_id);
+";
+
+/// A database mirroring the contract's accounts and their history.
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    /// Opens the database at the given path, creating its tables if they
+    /// don't exist yet.
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// Stores the given state of an account, replacing any previous one.
+    pub fn put_account(
+        &mut self,
+        account_id: u64,
+        account: &AccountData,
+        keys: &[bls::PublicKey],
+    ) -> Result<()> {
+        let tx = self.conn.transaction()?;
+
+        tx.execute(
+            "INSERT OR REPLACE INTO accounts
+                (id, balance, threshold, nonce, description)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                account_id as i64,
+                account.balance as i64,
+                account.threshold,
+                account.nonce as i64,
+                account.description,
+            ],
+        )?;
+
+        tx.execute(
+            "DELETE FROM account_keys WHERE account_id = ?1",
+            params![account_id as i64],
+        )?;
+        for key in keys {
+            tx.execute(
+                "INSERT INTO account_keys (account_id, key) VALUES (?1, ?2)",
+                params![account_id as i64, encode_key(key)],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Appends an event to the history of one of its accounts, unless it's
+    /// already there.
+    pub fn push_history(
+        &mut self,
+        account_id: u64,
+        record: &Record,
+    ) -> Result<()> {
+        let (amount, memo, mut details) = match record {
+            Record::CreateAccount(e) => (
+                None,
+                None,
+                json!({
+                    "keys": encode_keys(&e.keys),
+                    "threshold": e.threshold,
+                    "description": e.description,
+                    "alias": e.alias,
+                    "key_weights": e.key_weights.iter().map(encode_key_weight).collect::<Vec<_>>(),
+                    "key_roles": e.key_roles.iter().map(encode_role_assignment).collect::<Vec<_>>(),
+                    "template_id": e.template_id,
+                }),
+            ),
+            Record::Deposit(e) => (
+                Some(e.amount),
+                e.memo.revealed(),
+                json!({
+                    "memo_hash": disclosed_hash(&e.memo),
+                    "depositor_reference": e.depositor_reference.revealed(),
+                    "depositor_reference_hash": disclosed_hash(&e.depositor_reference),
+                    "nonce": e.nonce,
+                    "block_height": e.block_height,
+                    "balance_after": e.balance_after,
+                }),
+            ),
+            Record::Transfer(e) => (
+                Some(e.amount),
+                e.memo.revealed(),
+                json!({
+                    "keys": encode_keys(&e.keys),
+                    "cosigners": encode_cosigners(&e.cosigners),
+                    "group_signed": e.group_signed,
+                    "receiver": e.receiver.revealed().map(encode_key),
+                    "receiver_hash": disclosed_hash(&e.receiver),
+                    "memo_hash": disclosed_hash(&e.memo),
+                    "reference": e.reference.as_ref().and_then(Disclosure::revealed).map(hex::encode),
+                    "reference_hash": e.reference.as_ref().and_then(disclosed_hash),
+                    "category": e.category,
+                    "claim_id": e.claim_id,
+                    "nonce": e.nonce,
+                    "block_height": e.block_height,
+                    "balance_after": e.balance_after,
+                }),
+            ),
+            Record::Payroll(e) => (
+                Some(e.amount),
+                e.memo.revealed(),
+                json!({
+                    "keys": encode_keys(&e.keys),
+                    "cosigners": encode_cosigners(&e.cosigners),
+                    "group_signed": e.group_signed,
+                    "row": e.row,
+                    "rows": e.rows,
+                    "receiver": e.receiver.revealed().map(encode_key),
+                    "receiver_hash": disclosed_hash(&e.receiver),
+                    "memo_hash": disclosed_hash(&e.memo),
+                    "nonce": e.nonce,
+                    "block_height": e.block_height,
+                    "balance_after": e.balance_after,
+                }),
+            ),
+            Record::GasRefund(e) => (
+                Some(e.amount),
+                None,
+                json!({
+                    "submitter": e.submitter.revealed().map(encode_key),
+                    "submitter_hash": disclosed_hash(&e.submitter),
+                    "nonce": e.nonce,
+                    "block_height": e.block_height,
+                    "balance_after": e.balance_after,
+                }),
+            ),
+            Record::ChangeAccount(e) => (
+                None,
+                None,
+                json!({
+                    "keys": encode_keys(&e.keys),
+                    "added_keys": encode_keys(&e.added_keys),
+                    "removed_keys": encode_keys(&e.removed_keys),
+                    "cosigners": encode_cosigners(&e.cosigners),
+                    "added_cosigners": encode_cosigners(&e.added_cosigners),
+                    "removed_cosigners": encode_cosigners(&e.removed_cosigners),
+                    "group_signed": e.group_signed,
+                    "group_key": e.group_key.map(|key| key.as_ref().map(encode_key)),
+                    "whitelisted": e.whitelisted.iter().map(|entry| json!({
+                        "receiver": encode_key(&entry.receiver),
+                        "usable_from": entry.usable_from,
+                    })).collect::<Vec<_>>(),
+                    "unwhitelisted": encode_keys(&e.unwhitelisted),
+                    "whitelist_lifted": e.whitelist_lifted,
+                    "velocity_limit": e.velocity_limit.map(|limit| limit.map(encode_velocity_limit)),
+                    "unlocked": e.unlocked,
+                    "archived": e.archived,
+                    "claimable_withdrawals": e.claimable_withdrawals,
+                    "history_retention": e.history_retention,
+                    "storage_fee": e.storage_fee,
+                    "compliance": e.compliance.as_ref().map(|compliance| compliance.as_ref().map(encode_compliance)),
+                    "hold_lifted": e.hold_lifted,
+                    "license_policy": e.license_policy.map(|policy| policy.map(encode_license_policy)),
+                    "set_operation_thresholds": e.set_operation_thresholds.iter().map(|t| json!({
+                        "op": t.op.fn_name(),
+                        "threshold": t.threshold,
+                    })).collect::<Vec<_>>(),
+                    "removed_operation_thresholds": e.removed_operation_thresholds.iter().map(OperationKind::fn_name).collect::<Vec<_>>(),
+                    "transfer_tiers": e.transfer_tiers.as_ref().map(|tiers| tiers.iter().map(|tier| json!({
+                        "ceiling": tier.ceiling,
+                        "threshold": tier.threshold,
+                    })).collect::<Vec<_>>()),
+                    "with_transfer": e.with_transfer,
+                    "added_guardians": encode_keys(&e.added_guardians),
+                    "removed_guardians": encode_keys(&e.removed_guardians),
+                    "added_observers": encode_keys(&e.added_observers),
+                    "removed_observers": encode_keys(&e.removed_observers),
+                    "alias": e.alias,
+                    "balance_cap": e.balance_cap,
+                    "added_tags": e.added_tags,
+                    "removed_tags": e.removed_tags,
+                    "set_categories": e.set_categories.iter().map(encode_category).collect::<Vec<_>>(),
+                    "removed_categories": e.removed_categories,
+                    "set_child_budgets": e.set_child_budgets.iter().map(encode_child_budget).collect::<Vec<_>>(),
+                    "removed_child_budgets": e.removed_child_budgets,
+                    "set_key_expiries": e.set_key_expiries.iter().map(|e| json!({
+                        "key": encode_key(&e.key),
+                        "valid_until": e.valid_until,
+                    })).collect::<Vec<_>>(),
+                    "removed_key_expiries": encode_keys(&e.removed_key_expiries),
+                    "set_key_weights": e.set_key_weights.iter().map(encode_key_weight).collect::<Vec<_>>(),
+                    "set_key_roles": e.set_key_roles.iter().map(encode_role_assignment).collect::<Vec<_>>(),
+                    "set_allowances": e.set_allowances.iter().map(encode_key_allowance).collect::<Vec<_>>(),
+                    "liveness_policy": e.liveness_policy.map(|policy| policy.map(encode_liveness_policy)),
+                    "supermajority": e.supermajority.map(|supermajority| supermajority.map(encode_supermajority)),
+                    "key_addition_policy": e.key_addition_policy.map(|policy| policy.map(encode_key_addition_policy)),
+                    "transfer_window": e.transfer_window.map(|window| window.map(encode_transfer_window)),
+                    "recovery_policy": e.recovery_policy.map(|policy| policy.map(encode_recovery_policy)),
+                    "inactivity_policy": e.inactivity_policy.as_ref().map(|policy| policy.as_ref().map(encode_inactivity_policy)),
+                    "queue_policy": e.queue_policy.map(|policy| policy.map(encode_queue_policy)),
+                    "previous_threshold": e.previous_threshold,
+                    "threshold": e.threshold,
+                    "description": e.description,
+                    "private_events": e.private_events,
+                    "nonce": e.nonce,
+                    "block_height": e.block_height,
+                }),
+            ),
+            Record::AccountLocked(e) => (
+                None,
+                None,
+                json!({
+                    "key": encode_key(&e.key),
+                    "nonce": e.nonce,
+                    "block_height": e.block_height,
+                }),
+            ),
+            Record::HoldPlaced(e) => (
+                None,
+                None,
+                json!({
+                    "key": encode_key(&e.key),
+                    "remediation": encode_key(&e.remediation),
+                    "nonce": e.nonce,
+                    "block_height": e.block_height,
+                }),
+            ),
+            Record::LivenessAttested(e) => (
+                None,
+                None,
+                json!({
+                    "key": encode_key(&e.key),
+                    "attested_at": e.attested_at,
+                    "block_height": e.block_height,
+                }),
+            ),
+            Record::KeyDelegated(e) => (
+                None,
+                None,
+                json!({
+                    "key": encode_key(&e.key),
+                    "delegation": e.delegation.as_ref().map(|d| json!({
+                        "delegate": encode_key(&d.delegate),
+                        "from_height": d.from_height,
+                        "until_height": d.until_height,
+                    })),
+                    "nonce": e.nonce,
+                    "block_height": e.block_height,
+                }),
+            ),
+            Record::GuardianAction(e) => (
+                None,
+                None,
+                json!({
+                    "key": encode_key(&e.key),
+                    "action": encode_guardian_action(&e.action),
+                    "nonce": e.nonce,
+                    "block_height": e.block_height,
+                }),
+            ),
+            Record::RecoveryStarted(e) => (
+                None,
+                None,
+                json!({
+                    "guardians": encode_keys(&e.recovery.guardians),
+                    "keys": encode_keys(&e.recovery.keys),
+                    "threshold": e.recovery.threshold,
+                    "executable_from": e.recovery.executable_from,
+                    "nonce": e.nonce,
+                    "block_height": e.block_height,
+                }),
+            ),
+            Record::RecoveryCancelled(e) => (
+                None,
+                None,
+                json!({
+                    "key": encode_key(&e.key),
+                    "nonce": e.nonce,
+                    "block_height": e.block_height,
+                }),
+            ),
+            Record::RecoveryFinalized(e) => (
+                None,
+                None,
+                json!({
+                    "keys": encode_keys(&e.keys),
+                    "removed_keys": encode_keys(&e.removed_keys),
+                    "threshold": e.threshold,
+                    "nonce": e.nonce,
+                    "block_height": e.block_height,
+                }),
+            ),
+            Record::InactiveClaimed(e) => (
+                None,
+                None,
+                json!({
+                    "key": encode_key(&e.key),
+                    "claim": encode_inactive_claim(&e.claim),
+                    "removed_keys": encode_keys(&e.removed_keys),
+                    "last_active": e.last_active,
+                    "nonce": e.nonce,
+                    "block_height": e.block_height,
+                }),
+            ),
+            // The amount only leaves the account with the transfer the queued
+            // one is executed with
+            Record::TransferQueued(e) => (
+                None,
+                None,
+                json!({
+                    "keys": encode_keys(&e.keys),
+                    "cosigners": encode_cosigners(&e.cosigners),
+                    "group_signed": e.group_signed,
+                    "amount": e.amount,
+                    "executable_from": e.executable_from,
+                    "nonce": e.nonce,
+                    "block_height": e.block_height,
+                }),
+            ),
+            Record::QueuedCancelled(e) => (
+                None,
+                None,
+                json!({
+                    "key": encode_key(&e.key),
+                    "nonce": e.nonce,
+                    "block_height": e.block_height,
+                }),
+            ),
+            Record::QueuedExecuted(e) => (
+                None,
+                None,
+                json!({
+                    "nonce": e.nonce,
+                    "block_height": e.block_height,
+                }),
+            ),
+            Record::QueuedSkipped(e) => (
+                None,
+                None,
+                json!({
+                    "nonce": e.nonce,
+                    "amount": e.amount,
+                    "balance": e.balance,
+                    "block_height": e.block_height,
+                }),
+            ),
+            Record::QueuedExpired(e) => (
+                None,
+                None,
+                json!({
+                    "nonce": e.nonce,
+                    "block_height": e.block_height,
+                }),
+            ),
+            // The amount already left the account with the transfer event
+            // before
+            Record::AllowanceSpent(e) => (
+                None,
+                None,
+                json!({
+                    "key": encode_key(&e.key),
+                    "amount": e.amount,
+                    "remaining": e.remaining,
+                    "nonce": e.nonce,
+                    "block_height": e.block_height,
+                }),
+            ),
+            Record::MergeAccounts(e) => (
+                Some(e.amount),
+                None,
+                json!({
+                    "account_id": e.account_id,
+                    "merged_id": e.merged_id,
+                    "added_keys": encode_keys(&e.added_keys),
+                    "added_cosigners": encode_cosigners(&e.added_cosigners),
+                    "nonce": e.nonce,
+                    "merged_nonce": e.merged_nonce,
+                    "block_height": e.block_height,
+                    "balance_after": e.balance_after,
+                }),
+            ),
+            Record::SplitAccount(e) => (
+                Some(e.amount),
+                None,
+                json!({
+                    "account_id": e.account_id,
+                    "new_account_id": e.new_account_id,
+                    "nonce": e.nonce,
+                    "block_height": e.block_height,
+                    "balance_after": e.balance_after,
+                }),
+            ),
+            Record::BudgetDrawn(e) => (
+                Some(e.amount),
+                None,
+                json!({
+                    "account_id": e.account_id,
+                    "parent_id": e.parent_id,
+                    "nonce": e.nonce,
+                    "block_height": e.block_height,
+                    "balance_after": e.balance_after,
+                    "parent_balance_after": e.parent_balance_after,
+                }),
+            ),
+            Record::BudgetClawedBack(e) => (
+                Some(e.amount),
+                None,
+                json!({
+                    "account_id": e.account_id,
+                    "child_id": e.child_id,
+                    "nonce": e.nonce,
+                    "block_height": e.block_height,
+                    "balance_after": e.balance_after,
+                    "child_balance_after": e.child_balance_after,
+                }),
+            ),
+            Record::Checkpoint(e) => (
+                None,
+                None,
+                json!({
+                    "account_id": e.account_id,
+                    "balance": e.checkpoint.balance,
+                    "nonce": e.checkpoint.nonce,
+                    "keys_hash": hex::encode(e.checkpoint.keys_hash),
+                    "block_height": e.checkpoint.block_height,
+                }),
+            ),
+            Record::Configure(e) => (
+                None,
+                None,
+                json!({
+                    "keys": encode_keys(&e.keys),
+                    "cosigners": encode_cosigners(&e.cosigners),
+                    "group_signed": e.group_signed,
+                    "max_keys": e.config.max_keys,
+                    "max_memo_len": e.config.max_memo_len,
+                    "paused": e.config.paused,
+                    "history_record_fee": e.config.history_record_fee,
+                    "max_gas_refund": e.config.max_gas_refund,
+                    "nonce": e.nonce,
+                    "block_height": e.block_height,
+                }),
+            ),
+            Record::WithdrawalClaimed(e) => (
+                Some(e.amount),
+                None,
+                json!({
+                    "claim_id": e.claim_id,
+                    "receiver": e.receiver.revealed().map(encode_key),
+                    "receiver_hash": disclosed_hash(&e.receiver),
+                    "block_height": e.block_height,
+                }),
+            ),
+            Record::PayoutDeferred(e) => (
+                Some(e.amount),
+                None,
+                json!({
+                    "claim_id": e.claim_id,
+                    "receiver": e.receiver.revealed().map(encode_key),
+                    "receiver_hash": disclosed_hash(&e.receiver),
+                    "block_height": e.block_height,
+                }),
+            ),
+            Record::VelocityWarning(e) => (
+                None,
+                None,
+                json!({
+                    "limit": encode_velocity_limit(e.limit),
+                    "outflow": e.outflow,
+                    "window_start": e.window_start,
+                    "block_height": e.block_height,
+                }),
+            ),
+            Record::KeyCompromised(e) => (
+                None,
+                None,
+                json!({
+                    "key": encode_key(&e.key),
+                    "block_height": e.block_height,
+                }),
+            ),
+            Record::CosignerCompromised(e) => (
+                None,
+                None,
+                json!({
+                    "key": e.key.to_string(),
+                    "block_height": e.block_height,
+                }),
+            ),
+            Record::RegisterTemplate(e) => (
+                None,
+                None,
+                json!({
+                    "template_id": e.template_id,
+                    "threshold": e.template.threshold,
+                    "description": e.template.description,
+                    "private_events": e.template.private_events,
+                    "velocity_limit": e.template.velocity_limit.map(encode_velocity_limit),
+                    "cosigners": encode_cosigners(&e.template.cosigners),
+                    "guardians": encode_keys(&e.template.guardians),
+                }),
+            ),
+        };
+
+        details["sequence"] = record.sequence().into();
+
+        self.conn.execute(
+            "INSERT OR IGNORE INTO history
+                (account_id, topic, amount, memo, details, event_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                account_id as i64,
+                record.topic(),
+                amount.map(|amount| amount as i64),
+                memo,
+                details.to_string(),
+                hex::encode(record.event_id()),
+            ],
+        )?;
+
+        Ok(())
+    }
+}
+
+fn encode_key(key: &bls::PublicKey) -> String {
+    encoding::encode_public_key(key)
+}
+
+fn encode_velocity_limit(limit: VelocityLimit) -> serde_json::Value {
+    json!({
+        "amount": limit.amount,
+        "window": limit.window,
+    })
+}
+
+fn encode_liveness_policy(policy: LivenessPolicy) -> serde_json::Value {
+    json!({
+        "window": policy.window,
+        "min_threshold": policy.min_threshold,
+    })
+}
+
+fn encode_supermajority(supermajority: Supermajority) -> serde_json::Value {
+    json!({
+        "numerator": supermajority.numerator,
+        "denominator": supermajority.denominator,
+    })
+}
+
+fn encode_compliance(compliance: &ComplianceControl) -> serde_json::Value {
+    json!({
+        "key": encode_key(&compliance.key),
+        "remediation": encode_key(&compliance.remediation),
+    })
+}
+
+fn encode_license_policy(policy: LicensePolicy) -> serde_json::Value {
+    json!({
+        "contract": hex::encode(policy.contract),
+        "signers": policy.signers,
+    })
+}
+
+fn encode_key_addition_policy(policy: KeyAdditionPolicy) -> serde_json::Value {
+    json!({
+        "delay": policy.delay,
+        "supermajority": policy.supermajority,
+    })
+}
+
+fn encode_category(category: &SpendingCategory) -> serde_json::Value {
+    json!({
+        "name": category.name,
+        "budget": category.budget,
+        "window": category.window,
+    })
+}
+
+fn encode_child_budget(budget: &ChildBudget) -> serde_json::Value {
+    json!({
+        "child_id": budget.child_id,
+        "amount": budget.amount,
+        "window": budget.window,
+    })
+}
+
+fn encode_key_weight(weight: &KeyWeight) -> serde_json::Value {
+    json!({
+        "key": encode_key(&weight.key),
+        "weight": weight.weight,
+    })
+}
+
+fn encode_role_assignment(assignment: &RoleAssignment) -> serde_json::Value {
+    json!({
+        "key": encode_key(&assignment.key),
+        "role": assignment.role.name(),
+    })
+}
+
+fn encode_key_allowance(allowance: &KeyAllowance) -> serde_json::Value {
+    json!({
+        "key": encode_key(&allowance.key),
+        "amount": allowance.amount,
+    })
+}
+
+fn encode_recovery_policy(policy: RecoveryPolicy) -> serde_json::Value {
+    json!({
+        "delay": policy.delay,
+        "guardians": policy.guardians,
+    })
+}
+
+fn encode_inactivity_policy(policy: &InactivityPolicy) -> serde_json::Value {
+    json!({
+        "fallback": encode_key(&policy.fallback),
+        "period": policy.period,
+    })
+}
+
+fn encode_queue_policy(policy: QueuePolicy) -> serde_json::Value {
+    json!({
+        "min_amount": policy.min_amount,
+        "delay": policy.delay,
+    })
+}
+
+fn encode_inactive_claim(claim: &InactiveClaim) -> serde_json::Value {
+    match claim {
+        InactiveClaim::Sweep { receiver } => json!({
+            "type": "sweep",
+            "receiver": encode_key(receiver),
+        }),
+        InactiveClaim::RotateKeys { keys, threshold } => json!({
+            "type": "rotate_keys",
+            "keys": encode_keys(keys),
+            "threshold": threshold,
+        }),
+    }
+}
+
+fn encode_transfer_window(window: TransferWindow) -> serde_json::Value {
+    json!({
+        "period": window.period,
+        "offset": window.offset,
+        "length": window.length,
+    })
+}
+
+fn encode_guardian_action(action: &GuardianAction) -> serde_json::Value {
+    match action {
+        GuardianAction::Lock => json!({ "type": "lock" }),
+        GuardianAction::ExtendWhitelistDelay {
+            receiver,
+            usable_from,
+        } => json!({
+            "type": "extend_whitelist_delay",
+            "receiver": encode_key(receiver),
+            "usable_from": usable_from,
+        }),
+        GuardianAction::VetoWhitelisted { receiver } => json!({
+            "type": "veto_whitelisted",
+            "receiver": encode_key(receiver),
+        }),
+    }
+}
+
+/// Hex encodes the hash of a value, if it was hashed rather than revealed.
+fn disclosed_hash<T>(disclosure: &Disclosure<T>) -> Option<String> {
+    match disclosure {
+        Disclosure::Revealed(_) => None,
+        Disclosure::Hashed(hash) => Some(hex::encode(hash)),
+    }
+}
+
+fn encode_keys(keys: &[bls::PublicKey]) -> Vec<String> {
+    keys.iter().map(encode_key).collect()
+}
+
+fn encode_cosigners(keys: &[CosignerKey]) -> Vec<String> {
+    keys.iter().map(CosignerKey::to_string).collect()
+}
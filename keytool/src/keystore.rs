@@ -0,0 +1,81 @@
+//! Encryption of secret keys at rest.
+//!
+//! An encrypted key file holds a magic prefix, followed by the salt used to
+//! derive the encryption key from the password with Argon2id, the nonce, and
+//! the secret key encrypted with ChaCha20-Poly1305:
+//!
+//! ```text
+//! MSKEY1 | salt (16 bytes) | nonce (12 bytes) | ciphertext (48 bytes)
+//! ```
+
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use dusk_bytes::Serializable;
+use rand::{CryptoRng, RngCore};
+
+use multisig_contract_types::bls::SecretKey;
+
+use crate::Result;
+
+const MAGIC: &[u8] = b"MSKEY1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Returns true if the given file contents are an encrypted key.
+pub fn is_encrypted(contents: &[u8]) -> bool {
+    contents.starts_with(MAGIC)
+}
+
+/// Encrypts a secret key with the given password.
+pub fn encrypt<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    sk: &SecretKey,
+    password: &str,
+) -> Result<Vec<u8>> {
+    let mut salt = [0; SALT_LEN];
+    let mut nonce = [0; NONCE_LEN];
+    rng.fill_bytes(&mut salt);
+    rng.fill_bytes(&mut nonce);
+
+    let cipher = cipher(password, &salt)?;
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), &sk.to_bytes()[..])
+        .map_err(|_| "Failed to encrypt the key")?;
+
+    let mut contents = MAGIC.to_vec();
+    contents.extend(salt);
+    contents.extend(nonce);
+    contents.extend(ciphertext);
+    Ok(contents)
+}
+
+/// Decrypts a secret key encrypted with the given password.
+pub fn decrypt(contents: &[u8], password: &str) -> Result<SecretKey> {
+    let contents = contents
+        .strip_prefix(MAGIC)
+        .ok_or("The key is not encrypted")?;
+    if contents.len() < SALT_LEN + NONCE_LEN {
+        return Err("The encrypted key is truncated".into());
+    }
+    let (salt, contents) = contents.split_at(SALT_LEN);
+    let (nonce, ciphertext) = contents.split_at(NONCE_LEN);
+
+    let bytes = cipher(password, salt)?
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Wrong password, or corrupted key")?;
+    let bytes = bytes
+        .try_into()
+        .map_err(|_| "Invalid length of the decrypted key")?;
+
+    SecretKey::from_bytes(&bytes).map_err(|_| "Invalid decrypted key".into())
+}
+
+/// Derives the cipher from the password and salt.
+fn cipher(password: &str, salt: &[u8]) -> Result<ChaCha20Poly1305> {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .map_err(|err| format!("Failed to derive the encryption key: {err}"))?;
+    Ok(ChaCha20Poly1305::new(&key))
+}
@@ -0,0 +1,219 @@
+//! Key management for the signers of `multisig-contract` accounts.
+//!
+//! The keytool generates BLS keypairs, and converts secret keys between the
+//! formats they may be kept in:
+//!
+//! - `base58` - the format the CLI reads key files in
+//! - `hex` - the hex encoding of the key's bytes
+//! - `raw` - the key's bytes themselves
+//!
+//! Secret keys can also be encrypted at rest with a password, which is read
+//! from `MULTISIG_KEY_PASSWORD` if set, and prompted for otherwise. Encrypted
+//! keys are accepted wherever the keytool reads a key, and can be exported as
+//! `base58` for use with the CLI.
+
+mod keystore;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use dusk_bytes::Serializable;
+use rand::rngs::OsRng;
+
+use multisig_contract_types::bls::{PublicKey, SecretKey};
+
+type Result<T, E = Box<dyn std::error::Error + Send + Sync>> =
+    std::result::Result<T, E>;
+
+const PASSWORD_ENV: &str = "MULTISIG_KEY_PASSWORD";
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate a new keypair, printing its base58 encoded public key.
+    Generate {
+        /// Encrypt the secret key with a password.
+        #[arg(long)]
+        encrypt: bool,
+        /// File to write the secret key to.
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Print the base58 encoded public key of a secret key.
+    Public {
+        /// File containing the secret key.
+        #[arg(long)]
+        key: PathBuf,
+    },
+    /// Encrypt a secret key with a password.
+    Encrypt {
+        /// File containing the secret key.
+        #[arg(long)]
+        key: PathBuf,
+        /// File to write the encrypted key to.
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Import a secret key from another format.
+    Import {
+        /// File containing the secret key.
+        #[arg(long)]
+        input: PathBuf,
+        /// Format of the secret key.
+        #[arg(long, value_enum)]
+        format: Format,
+        /// Encrypt the secret key with a password.
+        #[arg(long)]
+        encrypt: bool,
+        /// File to write the secret key to.
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Export a secret key, decrypting it if necessary.
+    Export {
+        /// File containing the secret key.
+        #[arg(long)]
+        key: PathBuf,
+        /// Format to export the secret key in.
+        #[arg(long, value_enum, default_value_t = Format::Base58)]
+        format: Format,
+        /// File to write the secret key to.
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+}
+
+/// Formats a secret key may be imported from and exported to.
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Base58,
+    Hex,
+    Raw,
+}
+
+impl Format {
+    /// Encodes a secret key in this format.
+    fn encode(self, sk: &SecretKey) -> Vec<u8> {
+        let bytes = sk.to_bytes();
+        match self {
+            Format::Base58 => bs58::encode(bytes).into_string().into_bytes(),
+            Format::Hex => hex::encode(bytes).into_bytes(),
+            Format::Raw => bytes.to_vec(),
+        }
+    }
+
+    /// Decodes a secret key from this format.
+    fn decode(self, contents: &[u8]) -> Result<SecretKey> {
+        let bytes = match self {
+            Format::Base58 => bs58::decode(trim(contents)?).into_vec()?,
+            Format::Hex => hex::decode(trim(contents)?)?,
+            Format::Raw => contents.to_vec(),
+        };
+        let bytes =
+            bytes.try_into().map_err(|_| "Invalid secret key length")?;
+
+        SecretKey::from_bytes(&bytes).map_err(|_| "Invalid secret key".into())
+    }
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Command::Generate { encrypt, output } => {
+            let sk = SecretKey::random(&mut OsRng);
+            write_key(&output, &sk, encrypt)?;
+            println!("{}", public_key(&sk));
+        }
+        Command::Public { key } => {
+            let sk = read_key(&key)?;
+            println!("{}", public_key(&sk));
+        }
+        Command::Encrypt { key, output } => {
+            let sk = read_key(&key)?;
+            write_key(&output, &sk, true)?;
+        }
+        Command::Import {
+            input,
+            format,
+            encrypt,
+            output,
+        } => {
+            let sk = format.decode(&fs::read(input)?)?;
+            write_key(&output, &sk, encrypt)?;
+            println!("{}", public_key(&sk));
+        }
+        Command::Export {
+            key,
+            format,
+            output,
+        } => {
+            let sk = read_key(&key)?;
+            fs::write(output, format.encode(&sk))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads a secret key, either base58 encoded or encrypted, from the file at
+/// the given path.
+fn read_key(path: &Path) -> Result<SecretKey> {
+    let contents = fs::read(path)?;
+    if keystore::is_encrypted(&contents) {
+        let password = password(&format!("Password for {path:?}: "))?;
+        return keystore::decrypt(&contents, &password);
+    }
+    Format::Base58
+        .decode(&contents)
+        .map_err(|err| format!("Reading {path:?}: {err}").into())
+}
+
+/// Writes a secret key to the file at the given path, either base58 encoded
+/// or encrypted.
+fn write_key(path: &Path, sk: &SecretKey, encrypt: bool) -> Result<()> {
+    let contents = match encrypt {
+        true => {
+            let password = new_password()?;
+            keystore::encrypt(&mut OsRng, sk, &password)?
+        }
+        false => Format::Base58.encode(sk),
+    };
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+/// Reads the password from the environment, or prompts for it.
+fn password(prompt: &str) -> Result<String> {
+    match std::env::var(PASSWORD_ENV) {
+        Ok(password) => Ok(password),
+        Err(_) => Ok(rpassword::prompt_password(prompt)?),
+    }
+}
+
+/// Reads a new password from the environment, or prompts for it twice.
+fn new_password() -> Result<String> {
+    if let Ok(password) = std::env::var(PASSWORD_ENV) {
+        return Ok(password);
+    }
+    let password = rpassword::prompt_password("New password: ")?;
+    if rpassword::prompt_password("Repeat password: ")? != password {
+        return Err("The passwords don't match".into());
+    }
+    Ok(password)
+}
+
+fn public_key(sk: &SecretKey) -> String {
+    bs58::encode(PublicKey::from(sk).to_bytes()).into_string()
+}
+
+fn trim(contents: &[u8]) -> Result<&str> {
+    Ok(std::str::from_utf8(contents)?.trim())
+}
@@ -0,0 +1,59 @@
+//! A mock of a license contract, for testing license policies.
+//!
+//! Implements the `licensed` query the multisig contract calls on the
+//! license contract of an account, attesting the keys it was told hold a
+//! license. Unlike a Citadel license, no proof is asked of them - anyone can
+//! license or unlicense any key.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::collections::BTreeSet;
+
+use execution_core::signatures::bls::PublicKey;
+
+/// The raw bytes of the keys holding a license.
+struct MockState {
+    licensed: BTreeSet<[u8; 193]>,
+}
+
+static mut STATE: MockState = MockState {
+    licensed: BTreeSet::new(),
+};
+
+impl MockState {
+    /// Attests that the given key holds a license.
+    fn issue_license(&mut self, key: PublicKey) {
+        self.licensed.insert(key.to_raw_bytes());
+    }
+
+    /// Stops attesting that the given key holds a license.
+    fn revoke_license(&mut self, key: PublicKey) {
+        self.licensed.remove(&key.to_raw_bytes());
+    }
+
+    /// Returns whether the given key holds a license.
+    fn licensed(&self, key: PublicKey) -> bool {
+        self.licensed.contains(&key.to_raw_bytes())
+    }
+}
+
+// Mutations
+
+#[no_mangle]
+unsafe fn issue_license(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.issue_license(arg))
+}
+
+#[no_mangle]
+unsafe fn revoke_license(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.revoke_license(arg))
+}
+
+// Queries
+
+#[no_mangle]
+unsafe fn licensed(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.licensed(arg))
+}
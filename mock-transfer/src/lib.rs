@@ -0,0 +1,100 @@
+//! A mock of the transfer contract, for fast tests.
+//!
+//! Implements just the functions of the transfer contract the multisig
+//! contract calls, keeping the balances of contracts and Moonlight accounts
+//! in memory. Deployed in place of the transfer contract, it allows the
+//! multisig contract to be tested without deploying the full genesis state.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+
+use execution_core::signatures::bls::PublicKey;
+use execution_core::transfer::moonlight::AccountData;
+use execution_core::transfer::ContractToAccount;
+use execution_core::ContractId;
+
+/// The balance of each contract, and of each Moonlight account, indexed by
+/// its raw bytes.
+struct MockState {
+    contract_balances: BTreeMap<ContractId, u64>,
+    account_balances: BTreeMap<[u8; 193], u64>,
+}
+
+static mut STATE: MockState = MockState {
+    contract_balances: BTreeMap::new(),
+    account_balances: BTreeMap::new(),
+};
+
+impl MockState {
+    /// Credits the calling contract with the given value.
+    ///
+    /// Unlike the real transfer contract, the value isn't taken from a
+    /// transaction's deposit, but simply created.
+    fn deposit(&mut self, value: u64) {
+        let contract =
+            rusk_abi::caller().expect("Must be called by a contract");
+        *self.contract_balances.entry(contract).or_default() += value;
+    }
+
+    /// Moves the given value from the calling contract to a Moonlight account.
+    fn contract_to_account(&mut self, transfer: ContractToAccount) {
+        let contract =
+            rusk_abi::caller().expect("Must be called by a contract");
+
+        let balance = self.contract_balances.entry(contract).or_default();
+        if *balance < transfer.value {
+            panic!("The contract doesn't have enough balance");
+        }
+        *balance -= transfer.value;
+
+        *self
+            .account_balances
+            .entry(transfer.account.to_raw_bytes())
+            .or_default() += transfer.value;
+    }
+
+    /// Returns the data of a Moonlight account, which only ever holds what was
+    /// transferred to it.
+    fn account(&self, key: PublicKey) -> AccountData {
+        AccountData {
+            nonce: 0,
+            balance: self
+                .account_balances
+                .get(&key.to_raw_bytes())
+                .copied()
+                .unwrap_or(0),
+        }
+    }
+
+    /// Returns the balance of a contract.
+    fn contract_balance(&self, contract: ContractId) -> u64 {
+        self.contract_balances.get(&contract).copied().unwrap_or(0)
+    }
+}
+
+// Mutations
+
+#[no_mangle]
+unsafe fn deposit(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.deposit(arg))
+}
+
+#[no_mangle]
+unsafe fn contract_to_account(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.contract_to_account(arg))
+}
+
+// Queries
+
+#[no_mangle]
+unsafe fn account(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.account(arg))
+}
+
+#[no_mangle]
+unsafe fn contract_balance(arg_len: u32) -> u32 {
+    rusk_abi::wrap_call(arg_len, |arg| STATE.contract_balance(arg))
+}
@@ -0,0 +1,350 @@
+//! Reconciliation of the state of the `multisig-contract` with its events.
+//!
+//! The full stream of events emitted by the contract is replayed to rebuild
+//! the state every account is expected to be in, which is then compared with
+//! the state the contract reports on-chain. Any difference is reported, and
+//! makes the tool exit with an error.
+//!
+//! Events are read from a file with one event per line, in the order they
+//! were emitted, each line holding the topic of the event and its payload -
+//! hex or base64 encoded, as served by the node's event API:
+//!
+//! ```text
+//! create_account 0x2a00...
+//! deposit AQAAAAAAAAA...
+//! ```
+//!
+//! Empty lines and lines starting with `#` are ignored. Accounts carried over
+//! by a migration are not created by any event, and show up as differences.
+
+mod replay;
+
+use std::fs;
+use std::path::PathBuf;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use clap::Parser;
+use dusk_bytes::Serializable;
+
+use execution_core::ContractId;
+
+use multisig_client::MultisigClient;
+use multisig_contract_types::*;
+
+use crate::replay::{Account, Key, Replay};
+
+type Result<T, E = Box<dyn std::error::Error + Send + Sync>> =
+    std::result::Result<T, E>;
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// URL of the rusk node to query the on-chain state from.
+    #[arg(long, env = "RUSK_NODE", default_value = "http://127.0.0.1:8080")]
+    node: String,
+    /// Hex encoded ID of the multisig contract.
+    #[arg(long, env = "MULTISIG_CONTRACT")]
+    contract: String,
+    /// File containing the events emitted by the contract.
+    events: PathBuf,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let contract = hex::decode(&args.contract)?
+        .try_into()
+        .map_err(|_| "The contract ID must be 32 bytes long")?;
+    let contract = ContractId::from_bytes(contract);
+
+    let mut replay = Replay::default();
+    for (i, line) in fs::read_to_string(&args.events)?.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (topic, payload) = line
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| format!("Line {}: missing payload", i + 1))?;
+        parse_payload(payload.trim())
+            .and_then(|data| replay.apply(topic, &data))
+            .map_err(|err| format!("Line {}: {err}", i + 1))?;
+    }
+    eprintln!(
+        "Replayed {} events over {} accounts",
+        replay.events(),
+        replay.accounts().len()
+    );
+
+    let client = MultisigClient::new(args.node, contract);
+    let mut differences = 0;
+
+    for (&account_id, expected) in replay.accounts() {
+        let actual = fetch_account(&client, account_id).await?;
+        for difference in diff(expected, &actual) {
+            println!("Account {account_id}: {difference}");
+            differences += 1;
+        }
+    }
+
+    // Accounts are numbered sequentially, so any account past the last one
+    // replayed was created without the events showing it
+    let mut account_id = replay.accounts().keys().last().copied().unwrap_or(0);
+    loop {
+        account_id += 1;
+        if client.account(account_id).await?.is_none() {
+            break;
+        }
+        println!("Account {account_id}: exists on-chain, but not in events");
+        differences += 1;
+    }
+
+    if differences > 0 {
+        return Err(format!("Found {differences} differences").into());
+    }
+    println!("The on-chain state matches the events");
+    Ok(())
+}
+
+/// Decodes a hex or base64 encoded event payload into its raw bytes.
+fn parse_payload(payload: &str) -> Result<Vec<u8>> {
+    let hex = payload.strip_prefix("0x").unwrap_or(payload);
+    if let Ok(bytes) = hex::decode(hex) {
+        return Ok(bytes);
+    }
+    BASE64
+        .decode(payload)
+        .map_err(|_| "The payload should be hex or base64 encoded".into())
+}
+
+/// Fetches the on-chain state of an account.
+async fn fetch_account(
+    client: &MultisigClient,
+    account_id: u64,
+) -> Result<Account> {
+    let data = client
+        .account(account_id)
+        .await?
+        .ok_or_else(|| format!("Account {account_id} doesn't exist"))?;
+    let keys = client.account_keys(account_id).await?;
+    let cosigners = client.cosigners(account_id).await?;
+    let guardians = client.guardians(account_id).await?;
+    let observers = client.observers(account_id).await?;
+
+    Ok(Account {
+        keys: keys.iter().map(|k| k.to_bytes()).collect(),
+        cosigners: cosigners.into_iter().collect(),
+        guardians: guardians.iter().map(|k| k.to_bytes()).collect(),
+        observers: observers.iter().map(|k| k.to_bytes()).collect(),
+        data,
+    })
+}
+
+/// Describes every difference between the expected and actual state of an
+/// account.
+fn diff(expected: &Account, actual: &Account) -> Vec<String> {
+    let mut differences = Vec::new();
+
+    let (e, a) = (&expected.data, &actual.data);
+    if a.threshold == 0 {
+        differences.push(String::from("exists in events, but not on-chain"));
+        return differences;
+    }
+    if e.balance != a.balance {
+        differences.push(format!("balance {} != {}", e.balance, a.balance));
+    }
+    if e.nonce != a.nonce {
+        differences.push(format!("nonce {} != {}", e.nonce, a.nonce));
+    }
+    if e.threshold != a.threshold {
+        differences
+            .push(format!("threshold {} != {}", e.threshold, a.threshold));
+    }
+    if e.description != a.description {
+        differences.push(format!(
+            "description {:?} != {:?}",
+            e.description, a.description
+        ));
+    }
+    if e.private_events != a.private_events {
+        differences.push(format!(
+            "private events {} != {}",
+            e.private_events, a.private_events
+        ));
+    }
+    if e.group_key != a.group_key {
+        let group_key = |key: &Option<bls::PublicKey>| match key {
+            Some(key) => key_str(&key.to_bytes()),
+            None => String::from("none"),
+        };
+        differences.push(format!(
+            "group key {} != {}",
+            group_key(&e.group_key),
+            group_key(&a.group_key)
+        ));
+    }
+    if e.whitelist_enforced != a.whitelist_enforced {
+        differences.push(format!(
+            "whitelist enforced {} != {}",
+            e.whitelist_enforced, a.whitelist_enforced
+        ));
+    }
+    if e.velocity_limit != a.velocity_limit {
+        differences.push(format!(
+            "velocity limit {:?} != {:?}",
+            e.velocity_limit, a.velocity_limit
+        ));
+    }
+    if e.locked != a.locked {
+        differences.push(format!("locked {} != {}", e.locked, a.locked));
+    }
+    if e.archived != a.archived {
+        differences.push(format!("archived {} != {}", e.archived, a.archived));
+    }
+    if e.claimable_withdrawals != a.claimable_withdrawals {
+        differences.push(format!(
+            "claimable withdrawals {} != {}",
+            e.claimable_withdrawals, a.claimable_withdrawals
+        ));
+    }
+    if e.history_retention != a.history_retention {
+        differences.push(format!(
+            "history retention {:?} != {:?}",
+            e.history_retention, a.history_retention
+        ));
+    }
+    if e.compliance != a.compliance {
+        differences.push(format!(
+            "compliance {:?} != {:?}",
+            e.compliance, a.compliance
+        ));
+    }
+    if e.on_hold != a.on_hold {
+        differences.push(format!("on hold {} != {}", e.on_hold, a.on_hold));
+    }
+    if e.license_policy != a.license_policy {
+        differences.push(format!(
+            "license policy {:?} != {:?}",
+            e.license_policy, a.license_policy
+        ));
+    }
+    if e.operation_thresholds != a.operation_thresholds {
+        differences.push(format!(
+            "operation thresholds {:?} != {:?}",
+            e.operation_thresholds, a.operation_thresholds
+        ));
+    }
+    if e.alias != a.alias {
+        differences.push(format!("alias {:?} != {:?}", e.alias, a.alias));
+    }
+    if e.merged_into != a.merged_into {
+        differences.push(format!(
+            "merged into {:?} != {:?}",
+            e.merged_into, a.merged_into
+        ));
+    }
+    if e.balance_cap != a.balance_cap {
+        differences.push(format!(
+            "balance cap {:?} != {:?}",
+            e.balance_cap, a.balance_cap
+        ));
+    }
+    if e.tags != a.tags {
+        differences.push(format!("tags {:?} != {:?}", e.tags, a.tags));
+    }
+    if e.liveness_policy != a.liveness_policy {
+        differences.push(format!(
+            "liveness policy {:?} != {:?}",
+            e.liveness_policy, a.liveness_policy
+        ));
+    }
+    if e.supermajority != a.supermajority {
+        differences.push(format!(
+            "supermajority {:?} != {:?}",
+            e.supermajority, a.supermajority
+        ));
+    }
+    if e.key_addition_policy != a.key_addition_policy {
+        differences.push(format!(
+            "key addition policy {:?} != {:?}",
+            e.key_addition_policy, a.key_addition_policy
+        ));
+    }
+    if e.categories != a.categories {
+        differences.push(format!(
+            "categories {:?} != {:?}",
+            e.categories, a.categories
+        ));
+    }
+    if e.child_budgets != a.child_budgets {
+        differences.push(format!(
+            "child budgets {:?} != {:?}",
+            e.child_budgets, a.child_budgets
+        ));
+    }
+    if e.transfer_window != a.transfer_window {
+        differences.push(format!(
+            "transfer window {:?} != {:?}",
+            e.transfer_window, a.transfer_window
+        ));
+    }
+    if e.recovery_policy != a.recovery_policy {
+        differences.push(format!(
+            "recovery policy {:?} != {:?}",
+            e.recovery_policy, a.recovery_policy
+        ));
+    }
+    if e.recovery_nonce != a.recovery_nonce {
+        differences.push(format!(
+            "recovery nonce {} != {}",
+            e.recovery_nonce, a.recovery_nonce
+        ));
+    }
+    if e.inactivity_policy != a.inactivity_policy {
+        differences.push(format!(
+            "inactivity policy {:?} != {:?}",
+            e.inactivity_policy, a.inactivity_policy
+        ));
+    }
+    if e.queue_policy != a.queue_policy {
+        differences.push(format!(
+            "queue policy {:?} != {:?}",
+            e.queue_policy, a.queue_policy
+        ));
+    }
+    for key in expected.keys.difference(&actual.keys) {
+        differences.push(format!("key {} missing on-chain", key_str(key)));
+    }
+    for key in actual.keys.difference(&expected.keys) {
+        differences.push(format!("key {} missing in events", key_str(key)));
+    }
+    for key in expected.cosigners.difference(&actual.cosigners) {
+        differences.push(format!("co-signer {key} missing on-chain"));
+    }
+    for key in actual.cosigners.difference(&expected.cosigners) {
+        differences.push(format!("co-signer {key} missing in events"));
+    }
+    for key in expected.guardians.difference(&actual.guardians) {
+        differences.push(format!("guardian {} missing on-chain", key_str(key)));
+    }
+    for key in actual.guardians.difference(&expected.guardians) {
+        differences
+            .push(format!("guardian {} missing in events", key_str(key)));
+    }
+    for key in expected.observers.difference(&actual.observers) {
+        differences.push(format!("observer {} missing on-chain", key_str(key)));
+    }
+    for key in actual.observers.difference(&expected.observers) {
+        differences
+            .push(format!("observer {} missing in events", key_str(key)));
+    }
+
+    differences
+}
+
+fn key_str(key: &Key) -> String {
+    bs58::encode(key).into_string()
+}
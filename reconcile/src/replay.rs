@@ -0,0 +1,875 @@
+//! Rebuilding the state of the accounts from the events of the contract.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use dusk_bytes::Serializable;
+
+use multisig_contract_types::*;
+use multisig_wallet::decode_event;
+
+use crate::Result;
+
+/// A key, in its compressed form, so that it can be ordered.
+pub type Key = [u8; bls::PublicKey::SIZE];
+
+/// The state of an account, as rebuilt from its events.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    /// The keys of the account.
+    pub keys: BTreeSet<Key>,
+    /// The co-signers of the account.
+    pub cosigners: BTreeSet<CosignerKey>,
+    /// The guardians of the account.
+    pub guardians: BTreeSet<Key>,
+    /// The observers of the account.
+    pub observers: BTreeSet<Key>,
+    /// The data the contract returns for the account.
+    pub data: AccountData,
+}
+
+/// The state of every account seen in the events.
+#[derive(Debug, Default)]
+pub struct Replay {
+    accounts: BTreeMap<u64, Account>,
+    templates: BTreeMap<u64, AccountTemplate>,
+    /// The account and amount of each withdrawal awaiting claim, by claim ID.
+    withdrawals: BTreeMap<u64, (u64, u64)>,
+    /// The claim ID of the payout the previous event deferred, which the
+    /// event of the payment carries again.
+    deferred: Option<u64>,
+    /// The account and nonce of each transfer awaiting execution.
+    queued: BTreeSet<(u64, u64)>,
+    events: usize,
+    last_sequence: Option<u64>,
+}
+
+impl Replay {
+    /// The accounts rebuilt so far, by ID.
+    pub fn accounts(&self) -> &BTreeMap<u64, Account> {
+        &self.accounts
+    }
+
+    /// The number of events applied so far.
+    pub fn events(&self) -> usize {
+        self.events
+    }
+
+    /// Applies an event emitted with the given topic, in the same way the
+    /// contract applied the operation emitting it.
+    pub fn apply(&mut self, topic: &str, data: &[u8]) -> Result<()> {
+        let deferred = self.deferred.take();
+        let sequence = match topic {
+            "create_account" => {
+                let event: CreateAccountEvent = decode_event(data)?;
+                let mut account = Account {
+                    keys: event.keys.iter().map(|k| k.to_bytes()).collect(),
+                    cosigners: BTreeSet::new(),
+                    guardians: BTreeSet::new(),
+                    observers: BTreeSet::new(),
+                    data: AccountData {
+                        balance: 0,
+                        threshold: event.threshold,
+                        description: event.description,
+                        nonce: 0,
+                        private_events: false,
+                        frozen: false,
+                        group_key: None,
+                        whitelist_enforced: false,
+                        velocity_limit: None,
+                        locked: false,
+                        alias: event.alias,
+                        merged_into: None,
+                        // The chain isn't carried by the event, and is left
+                        // out of the comparison
+                        chain_id: 0,
+                        balance_cap: None,
+                        tags: Vec::new(),
+                        liveness_policy: None,
+                        supermajority: None,
+                        key_addition_policy: None,
+                        transfer_window: None,
+                        categories: Vec::new(),
+                        child_budgets: Vec::new(),
+                        archived: false,
+                        claimable_withdrawals: false,
+                        history_retention: None,
+                        compliance: None,
+                        on_hold: false,
+                        license_policy: None,
+                        operation_thresholds: Vec::new(),
+                        recovery_policy: None,
+                        recovery_nonce: 0,
+                        inactivity_policy: None,
+                        // The height isn't carried by the event either, and
+                        // the activity of the account is left out of the
+                        // comparison
+                        last_active: 0,
+                        queue_policy: None,
+                    },
+                };
+                // The rest of the policy of an account created from a
+                // template is only in the template's event
+                if let Some(template_id) = event.template_id {
+                    let Some(template) = self.templates.get(&template_id)
+                    else {
+                        return Err(format!(
+                            "Account {} created from unknown template \
+                             {template_id}",
+                            event.account_id
+                        )
+                        .into());
+                    };
+                    account
+                        .cosigners
+                        .extend(template.cosigners.iter().cloned());
+                    account.guardians.extend(
+                        template.guardians.iter().map(|k| k.to_bytes()),
+                    );
+                    account.data.private_events = template.private_events;
+                    account.data.velocity_limit = template.velocity_limit;
+                }
+                if self.accounts.insert(event.account_id, account).is_some() {
+                    return Err(format!(
+                        "Account {} created twice",
+                        event.account_id
+                    )
+                    .into());
+                }
+                event.sequence
+            }
+            "deposit" => {
+                let event: DepositEvent = decode_event(data)?;
+                let account = self.account(event.account_id)?;
+                check_nonce(event.account_id, event.nonce, account.data.nonce)?;
+                account.data.balance += event.amount;
+                check_balance(
+                    event.account_id,
+                    event.balance_after,
+                    account.data.balance,
+                )?;
+                event.sequence
+            }
+            "transfer" => {
+                let event: TransferEvent = decode_event(data)?;
+                // A queued transfer used up its nonce when it was queued
+                let queued =
+                    self.queued.remove(&(event.account_id, event.nonce));
+                let account = self.account(event.account_id)?;
+                if !queued {
+                    check_nonce(
+                        event.account_id,
+                        event.nonce,
+                        account.data.nonce + 1,
+                    )?;
+                    account.data.nonce += 1;
+                }
+                let Some(balance) =
+                    account.data.balance.checked_sub(event.amount)
+                else {
+                    return Err(format!(
+                        "Account {} transferred more than its balance",
+                        event.account_id
+                    )
+                    .into());
+                };
+                account.data.balance = balance;
+                check_balance(
+                    event.account_id,
+                    event.balance_after,
+                    account.data.balance,
+                )?;
+                // A deferred payout was recorded by the event before
+                let claim_id =
+                    event.claim_id.filter(|&id| deferred != Some(id));
+                if let Some(claim_id) = claim_id {
+                    let withdrawal = (event.account_id, event.amount);
+                    if self.withdrawals.insert(claim_id, withdrawal).is_some() {
+                        return Err(format!(
+                            "Withdrawal {claim_id} recorded twice"
+                        )
+                        .into());
+                    }
+                }
+                event.sequence
+            }
+            // The amount of a deferred payout leaves its account with the
+            // event of the payment, which follows
+            "payout_deferred" => {
+                let event: PayoutDeferredEvent = decode_event(data)?;
+                let withdrawal = (event.account_id, event.amount);
+                if self
+                    .withdrawals
+                    .insert(event.claim_id, withdrawal)
+                    .is_some()
+                {
+                    return Err(format!(
+                        "Withdrawal {} recorded twice",
+                        event.claim_id
+                    )
+                    .into());
+                }
+                self.deferred = Some(event.claim_id);
+                event.sequence
+            }
+            // The amount of a claimed withdrawal already left its account
+            "withdrawal_claimed" => {
+                let event: WithdrawalClaimedEvent = decode_event(data)?;
+                let withdrawal = (event.account_id, event.amount);
+                if self.withdrawals.remove(&event.claim_id) != Some(withdrawal)
+                {
+                    return Err(format!(
+                        "Withdrawal {} claimed without being recorded",
+                        event.claim_id
+                    )
+                    .into());
+                }
+                event.sequence
+            }
+            // Every row of a payroll carries its nonce, which the first row
+            // uses up
+            "payroll" => {
+                let event: PayrollEvent = decode_event(data)?;
+                let account = self.account(event.account_id)?;
+                if event.row == 0 {
+                    account.data.nonce += 1;
+                }
+                check_nonce(event.account_id, event.nonce, account.data.nonce)?;
+                let Some(balance) =
+                    account.data.balance.checked_sub(event.amount)
+                else {
+                    return Err(format!(
+                        "Account {} paid more than its balance",
+                        event.account_id
+                    )
+                    .into());
+                };
+                account.data.balance = balance;
+                check_balance(
+                    event.account_id,
+                    event.balance_after,
+                    account.data.balance,
+                )?;
+                event.sequence
+            }
+            // A refund follows the operation designating it, which already
+            // used up the nonce
+            "gas_refund" => {
+                let event: GasRefundEvent = decode_event(data)?;
+                let account = self.account(event.account_id)?;
+                check_nonce(event.account_id, event.nonce, account.data.nonce)?;
+                let Some(balance) =
+                    account.data.balance.checked_sub(event.amount)
+                else {
+                    return Err(format!(
+                        "Account {} refunded more than its balance",
+                        event.account_id
+                    )
+                    .into());
+                };
+                account.data.balance = balance;
+                check_balance(
+                    event.account_id,
+                    event.balance_after,
+                    account.data.balance,
+                )?;
+                event.sequence
+            }
+            "change_account" => {
+                let event: ChangeAccountEvent = decode_event(data)?;
+                let account = self.account(event.account_id)?;
+                // A change made along with a transfer follows it, the
+                // transfer having already used up the nonce
+                if !event.with_transfer {
+                    account.data.nonce += 1;
+                }
+                check_nonce(event.account_id, event.nonce, account.data.nonce)?;
+                if event.previous_threshold != account.data.threshold {
+                    return Err(format!(
+                        "Account {} has previous threshold {} in the event, \
+                         but the events before it lead to {}",
+                        event.account_id,
+                        event.previous_threshold,
+                        account.data.threshold
+                    )
+                    .into());
+                }
+
+                // The event doesn't record the order of the changes, so a key
+                // both added and removed by a single change is assumed to have
+                // been added first - as the CLI builds changes.
+                for key in &event.added_keys {
+                    account.keys.insert(key.to_bytes());
+                }
+                for key in &event.removed_keys {
+                    account.keys.remove(&key.to_bytes());
+                }
+                account.cosigners.extend(&event.added_cosigners);
+                for key in &event.removed_cosigners {
+                    account.cosigners.remove(key);
+                }
+                for key in &event.added_guardians {
+                    account.guardians.insert(key.to_bytes());
+                }
+                for key in &event.removed_guardians {
+                    account.guardians.remove(&key.to_bytes());
+                }
+                for key in &event.added_observers {
+                    account.observers.insert(key.to_bytes());
+                }
+                for key in &event.removed_observers {
+                    account.observers.remove(&key.to_bytes());
+                }
+                if let Some(threshold) = event.threshold {
+                    account.data.threshold = threshold;
+                }
+                if let Some(description) = event.description {
+                    account.data.description = description;
+                }
+                if let Some(private_events) = event.private_events {
+                    account.data.private_events = private_events;
+                }
+                if let Some(group_key) = event.group_key {
+                    account.data.group_key = group_key;
+                }
+                if event.whitelist_lifted {
+                    account.data.whitelist_enforced = false;
+                }
+                if !event.whitelisted.is_empty() {
+                    account.data.whitelist_enforced = true;
+                }
+                if let Some(limit) = event.velocity_limit {
+                    account.data.velocity_limit = limit;
+                }
+                if event.unlocked {
+                    account.data.locked = false;
+                }
+                if let Some(archived) = event.archived {
+                    account.data.archived = archived;
+                }
+                if let Some(claimable) = event.claimable_withdrawals {
+                    account.data.claimable_withdrawals = claimable;
+                }
+                if let Some(retention) = event.history_retention {
+                    account.data.history_retention = retention;
+                }
+                if let Some(compliance) = event.compliance {
+                    account.data.compliance = compliance;
+                }
+                if event.hold_lifted {
+                    account.data.on_hold = false;
+                }
+                if let Some(policy) = event.license_policy {
+                    account.data.license_policy = policy;
+                }
+                for set in event.set_operation_thresholds {
+                    let thresholds = &mut account.data.operation_thresholds;
+                    match thresholds.binary_search_by_key(&set.op, |t| t.op) {
+                        Ok(i) => thresholds[i] = set,
+                        Err(i) => thresholds.insert(i, set),
+                    }
+                }
+                for op in &event.removed_operation_thresholds {
+                    account.data.operation_thresholds.retain(|t| t.op != *op);
+                }
+                let Some(balance) =
+                    account.data.balance.checked_sub(event.storage_fee)
+                else {
+                    return Err(format!(
+                        "Account {} paid a storage fee over its balance",
+                        event.account_id
+                    )
+                    .into());
+                };
+                account.data.balance = balance;
+                if let Some(alias) = event.alias {
+                    account.data.alias = alias;
+                }
+                if let Some(cap) = event.balance_cap {
+                    account.data.balance_cap = cap;
+                }
+                account.data.tags.extend(event.added_tags);
+                for tag in &event.removed_tags {
+                    account.data.tags.retain(|t| t != tag);
+                }
+                if let Some(policy) = event.liveness_policy {
+                    account.data.liveness_policy = policy;
+                }
+                if let Some(supermajority) = event.supermajority {
+                    account.data.supermajority = supermajority;
+                }
+                if let Some(policy) = event.key_addition_policy {
+                    account.data.key_addition_policy = policy;
+                }
+                if let Some(window) = event.transfer_window {
+                    account.data.transfer_window = window;
+                }
+                if let Some(policy) = event.recovery_policy {
+                    account.data.recovery_policy = policy;
+                }
+                if let Some(policy) = event.inactivity_policy {
+                    account.data.inactivity_policy = policy;
+                }
+                if let Some(policy) = event.queue_policy {
+                    account.data.queue_policy = policy;
+                }
+                for category in event.set_categories {
+                    let categories = &mut account.data.categories;
+                    match categories
+                        .iter_mut()
+                        .find(|c| c.name == category.name)
+                    {
+                        Some(existing) => *existing = category,
+                        None => categories.push(category),
+                    }
+                }
+                for name in &event.removed_categories {
+                    account.data.categories.retain(|c| c.name != *name);
+                }
+                for budget in event.set_child_budgets {
+                    let budgets = &mut account.data.child_budgets;
+                    match budgets
+                        .iter_mut()
+                        .find(|b| b.child_id == budget.child_id)
+                    {
+                        Some(existing) => *existing = budget,
+                        None => budgets.push(budget),
+                    }
+                }
+                for child_id in &event.removed_child_budgets {
+                    account
+                        .data
+                        .child_budgets
+                        .retain(|b| b.child_id != *child_id);
+                }
+                event.sequence
+            }
+            // Locking leaves the nonce as it is, the lock being signed with
+            // the current one
+            "account_locked" => {
+                let event: AccountLockedEvent = decode_event(data)?;
+                let account = self.account(event.account_id)?;
+                check_nonce(event.account_id, event.nonce, account.data.nonce)?;
+                account.data.locked = true;
+                event.sequence
+            }
+            // As with locking, placing a hold leaves the nonce as it is
+            "hold_placed" => {
+                let event: HoldPlacedEvent = decode_event(data)?;
+                let account = self.account(event.account_id)?;
+                check_nonce(event.account_id, event.nonce, account.data.nonce)?;
+                account.data.on_hold = true;
+                event.sequence
+            }
+            // Attesting a key's liveness leaves the data of the account, its
+            // nonce included, untouched
+            "liveness_attested" => {
+                let event: LivenessAttestedEvent = decode_event(data)?;
+                self.account(event.account_id)?;
+                event.sequence
+            }
+            // Delegations are kept apart from the data of the account, and
+            // carry their own nonces
+            "key_delegated" => {
+                let event: KeyDelegatedEvent = decode_event(data)?;
+                self.account(event.account_id)?;
+                event.sequence
+            }
+            // Guardians can lock the account, and otherwise only touch its
+            // whitelist, which isn't replayed
+            "guardian_action" => {
+                let event: GuardianActionEvent = decode_event(data)?;
+                let account = self.account(event.account_id)?;
+                check_nonce(event.account_id, event.nonce, account.data.nonce)?;
+                if event.action == GuardianAction::Lock {
+                    account.data.locked = true;
+                }
+                event.sequence
+            }
+            // Starting a recovery uses up a recovery nonce, so that
+            // cancellations signed before it don't apply to it, while leaving
+            // the nonce of the account as it is
+            "recovery_started" => {
+                let event: RecoveryStartedEvent = decode_event(data)?;
+                let account = self.account(event.account_id)?;
+                account.data.recovery_nonce += 1;
+                check_nonce(
+                    event.account_id,
+                    event.nonce,
+                    account.data.recovery_nonce,
+                )?;
+                event.sequence
+            }
+            // Cancelling a recovery leaves the recovery nonce as it is
+            "recovery_cancelled" => {
+                let event: RecoveryCancelledEvent = decode_event(data)?;
+                let account = self.account(event.account_id)?;
+                check_nonce(
+                    event.account_id,
+                    event.nonce,
+                    account.data.recovery_nonce,
+                )?;
+                event.sequence
+            }
+            // A finalized recovery replaces the keys and threshold of the
+            // account, dropping its group key and per-operation thresholds
+            "recovery_finalized" => {
+                let event: RecoveryFinalizedEvent = decode_event(data)?;
+                let account = self.account(event.account_id)?;
+                account.data.nonce += 1;
+                check_nonce(event.account_id, event.nonce, account.data.nonce)?;
+                account.keys =
+                    event.keys.iter().map(|k| k.to_bytes()).collect();
+                account.data.threshold = event.threshold;
+                account.data.group_key = None;
+                account.data.operation_thresholds.clear();
+                self.queued.retain(|&(id, _)| id != event.account_id);
+                event.sequence
+            }
+            // A sweep is carried by the transfer event before the claim, which
+            // already used up its nonce, while replacing the keys is as with a
+            // finalized recovery
+            "inactive_claimed" => {
+                let event: InactiveClaimedEvent = decode_event(data)?;
+                let account = self.account(event.account_id)?;
+                if let InactiveClaim::RotateKeys { keys, threshold } =
+                    &event.claim
+                {
+                    account.data.nonce += 1;
+                    account.keys = keys.iter().map(|k| k.to_bytes()).collect();
+                    account.data.threshold = *threshold;
+                    account.data.group_key = None;
+                    account.data.operation_thresholds.clear();
+                }
+                check_nonce(event.account_id, event.nonce, account.data.nonce)?;
+                if let InactiveClaim::RotateKeys { .. } = event.claim {
+                    self.queued.retain(|&(id, _)| id != event.account_id);
+                }
+                event.sequence
+            }
+            // A queued transfer uses up its nonce, but its amount only leaves
+            // the account with the transfer event it's executed with
+            "transfer_queued" => {
+                let event: TransferQueuedEvent = decode_event(data)?;
+                let account = self.account(event.account_id)?;
+                account.data.nonce += 1;
+                check_nonce(event.account_id, event.nonce, account.data.nonce)?;
+                self.queued.insert((event.account_id, event.nonce));
+                event.sequence
+            }
+            "queued_cancelled" => {
+                let event: QueuedCancelledEvent = decode_event(data)?;
+                self.account(event.account_id)?;
+                if !self.queued.remove(&(event.account_id, event.nonce)) {
+                    return Err(format!(
+                        "Account {} cancelled transfer {}, which wasn't queued",
+                        event.account_id, event.nonce
+                    )
+                    .into());
+                }
+                event.sequence
+            }
+            "queued_executed" => {
+                let event: QueuedExecutedEvent = decode_event(data)?;
+                event.sequence
+            }
+            // A skipped transfer stays queued, to be executed or to expire
+            "queued_skipped" => {
+                let event: QueuedSkippedEvent = decode_event(data)?;
+                event.sequence
+            }
+            "queued_expired" => {
+                let event: QueuedExpiredEvent = decode_event(data)?;
+                self.account(event.account_id)?;
+                if !self.queued.remove(&(event.account_id, event.nonce)) {
+                    return Err(format!(
+                        "Transfer {} of account {} expired, but wasn't queued",
+                        event.nonce, event.account_id
+                    )
+                    .into());
+                }
+                event.sequence
+            }
+            // The transfer event before already paid the amount out, and
+            // used up the nonce
+            "allowance_spent" => {
+                let event: AllowanceSpentEvent = decode_event(data)?;
+                event.sequence
+            }
+            // The merged account hands over its balance, keys and co-signers,
+            // and is left with nothing but its ID
+            "merge_accounts" => {
+                let event: MergeAccountsEvent = decode_event(data)?;
+                let merged = self.account(event.merged_id)?;
+                check_nonce(
+                    event.merged_id,
+                    event.merged_nonce,
+                    merged.data.nonce + 1,
+                )?;
+                if event.amount != merged.data.balance {
+                    return Err(format!(
+                        "Account {} merged a balance of {}, but the events \
+                         before it lead to {}",
+                        event.merged_id, event.amount, merged.data.balance
+                    )
+                    .into());
+                }
+                let keys = std::mem::take(&mut merged.keys);
+                let cosigners = std::mem::take(&mut merged.cosigners);
+                merged.guardians.clear();
+                merged.observers.clear();
+                merged.data.balance = 0;
+                merged.data.nonce += 1;
+                merged.data.alias = None;
+                merged.data.tags.clear();
+                merged.data.merged_into = Some(event.account_id);
+                self.queued.retain(|&(id, _)| id != event.merged_id);
+
+                let account = self.account(event.account_id)?;
+                check_nonce(
+                    event.account_id,
+                    event.nonce,
+                    account.data.nonce + 1,
+                )?;
+                account.keys.extend(keys);
+                account.cosigners.extend(cosigners);
+                account.data.balance += event.amount;
+                account.data.nonce += 1;
+                check_balance(
+                    event.account_id,
+                    event.balance_after,
+                    account.data.balance,
+                )?;
+                event.sequence
+            }
+            // The new account was created by the event right before, and is
+            // only given its balance here
+            "split_account" => {
+                let event: SplitAccountEvent = decode_event(data)?;
+                let account = self.account(event.account_id)?;
+                check_nonce(
+                    event.account_id,
+                    event.nonce,
+                    account.data.nonce + 1,
+                )?;
+                let Some(balance) =
+                    account.data.balance.checked_sub(event.amount)
+                else {
+                    return Err(format!(
+                        "Account {} split off more than its balance",
+                        event.account_id
+                    )
+                    .into());
+                };
+                account.data.balance = balance;
+                account.data.nonce += 1;
+                check_balance(
+                    event.account_id,
+                    event.balance_after,
+                    account.data.balance,
+                )?;
+
+                let new_account = self.account(event.new_account_id)?;
+                new_account.data.balance += event.amount;
+                event.sequence
+            }
+            // A draw is signed by the child, and leaves the parent's nonce as
+            // it is
+            "budget_drawn" => {
+                let event: BudgetDrawnEvent = decode_event(data)?;
+                let parent = self.account(event.parent_id)?;
+                let Some(balance) =
+                    parent.data.balance.checked_sub(event.amount)
+                else {
+                    return Err(format!(
+                        "Account {} had more drawn on it than its balance",
+                        event.parent_id
+                    )
+                    .into());
+                };
+                parent.data.balance = balance;
+                check_balance(
+                    event.parent_id,
+                    event.parent_balance_after,
+                    parent.data.balance,
+                )?;
+
+                let account = self.account(event.account_id)?;
+                check_nonce(
+                    event.account_id,
+                    event.nonce,
+                    account.data.nonce + 1,
+                )?;
+                account.data.balance += event.amount;
+                account.data.nonce += 1;
+                check_balance(
+                    event.account_id,
+                    event.balance_after,
+                    account.data.balance,
+                )?;
+                event.sequence
+            }
+            // Claw backs follow the change of the parent that made them, and
+            // carry its nonce
+            "budget_clawed_back" => {
+                let event: BudgetClawedBackEvent = decode_event(data)?;
+                let child = self.account(event.child_id)?;
+                let Some(balance) =
+                    child.data.balance.checked_sub(event.amount)
+                else {
+                    return Err(format!(
+                        "Account {} had more clawed back than its balance",
+                        event.child_id
+                    )
+                    .into());
+                };
+                child.data.balance = balance;
+                check_balance(
+                    event.child_id,
+                    event.child_balance_after,
+                    child.data.balance,
+                )?;
+
+                let account = self.account(event.account_id)?;
+                check_nonce(event.account_id, event.nonce, account.data.nonce)?;
+                account.data.balance += event.amount;
+                check_balance(
+                    event.account_id,
+                    event.balance_after,
+                    account.data.balance,
+                )?;
+                event.sequence
+            }
+            // A checkpoint uses up a nonce, and must fix the balance and keys
+            // the events before it lead to
+            "checkpoint" => {
+                let event: CheckpointEvent = decode_event(data)?;
+                let account = self.account(event.account_id)?;
+                let checkpoint = event.checkpoint;
+                check_nonce(
+                    event.account_id,
+                    checkpoint.nonce,
+                    account.data.nonce + 1,
+                )?;
+                account.data.nonce += 1;
+                check_balance(
+                    event.account_id,
+                    checkpoint.balance,
+                    account.data.balance,
+                )?;
+                let keys = account
+                    .keys
+                    .iter()
+                    .map(bls::PublicKey::from_bytes)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| "A replayed key should be valid")?;
+                if checkpoint.keys_hash != hash_keys(&keys) {
+                    return Err(format!(
+                        "Account {} has keys in the checkpoint other than \
+                         those the events before it lead to",
+                        event.account_id
+                    )
+                    .into());
+                }
+                checkpoint.sequence
+            }
+            // A configuration uses up a nonce of the governance account, and
+            // changes no balance
+            "configure" => {
+                let event: ConfigureEvent = decode_event(data)?;
+                let account = self.account(event.account_id)?;
+                check_nonce(
+                    event.account_id,
+                    event.nonce,
+                    account.data.nonce + 1,
+                )?;
+                account.data.nonce += 1;
+                event.sequence
+            }
+            // Warnings follow the transfer that crossed the limit, and change
+            // nothing themselves
+            "velocity_warning" => {
+                let event: VelocityWarningEvent = decode_event(data)?;
+                self.account(event.account_id)?;
+                event.sequence
+            }
+            // Marking a key leaves the accounts using it untouched
+            "key_compromised" => {
+                let event: KeyCompromisedEvent = decode_event(data)?;
+                for &account_id in &event.account_ids {
+                    self.account(account_id)?;
+                }
+                event.sequence
+            }
+            "cosigner_compromised" => {
+                let event: CosignerCompromisedEvent = decode_event(data)?;
+                for &account_id in &event.account_ids {
+                    self.account(account_id)?;
+                }
+                event.sequence
+            }
+            "register_template" => {
+                let event: RegisterTemplateEvent = decode_event(data)?;
+                if self
+                    .templates
+                    .insert(event.template_id, event.template)
+                    .is_some()
+                {
+                    return Err(format!(
+                        "Template {} registered twice",
+                        event.template_id
+                    )
+                    .into());
+                }
+                event.sequence
+            }
+            _ => return Err(format!("Unknown event topic: {topic}").into()),
+        };
+
+        // Events are numbered without gaps, starting from wherever the
+        // stream does - which may be after a migration
+        if let Some(last) = self.last_sequence {
+            if sequence != last + 1 {
+                return Err(format!(
+                    "Event {sequence} follows event {last}, events are missing"
+                )
+                .into());
+            }
+        }
+        self.last_sequence = Some(sequence);
+
+        self.events += 1;
+        Ok(())
+    }
+
+    fn account(&mut self, account_id: u64) -> Result<&mut Account> {
+        self.accounts.get_mut(&account_id).ok_or_else(|| {
+            format!("Event for account {account_id} before its creation").into()
+        })
+    }
+}
+
+/// Checks the nonce carried by an event is the one expected from the events
+/// before it, which it isn't if any were missed or duplicated.
+fn check_nonce(account_id: u64, nonce: u64, expected: u64) -> Result<()> {
+    if nonce != expected {
+        return Err(format!(
+            "Account {account_id} has nonce {nonce} in the event, but the \
+             events before it lead to {expected}"
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Checks the balance carried by an event is the one the events before it
+/// lead to.
+fn check_balance(account_id: u64, balance: u64, expected: u64) -> Result<()> {
+    if balance != expected {
+        return Err(format!(
+            "Account {account_id} has balance {balance} in the event, but the \
+             events before it lead to {expected}"
+        )
+        .into());
+    }
+    Ok(())
+}
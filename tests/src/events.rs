@@ -0,0 +1,211 @@
+//! Snapshots of the events emitted by each operation.
+//!
+//! Indexers decode the events of the contract from their exact bytes, so these
+//! are kept in golden files, making any change to them show up in review.
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use execution_core::ContractError;
+
+use multisig_contract_types::*;
+use multisig_wallet::decode_event;
+
+use crate::{golden, ContractSession, CONTRACT_ID, MEMO, RNG_SEED};
+
+/// Checks the events emitted by the last operation against the golden files
+/// of the given operation, asserting they have the expected topics.
+#[track_caller]
+fn check_events(session: &ContractSession, operation: &str, topics: &[&str]) {
+    let emitted: Vec<&str> = session
+        .events
+        .iter()
+        .map(|(topic, _)| topic.as_str())
+        .collect();
+    assert_eq!(
+        emitted, topics,
+        "{operation} should emit the expected events"
+    );
+
+    for (topic, data) in &session.events {
+        golden::check(&format!("events/{operation}/{topic}"), data);
+    }
+}
+
+/// Returns the sequence number of the single event emitted by the last
+/// operation.
+#[track_caller]
+fn last_sequence(session: &ContractSession) -> u64 {
+    let [(topic, data)] = session.events.as_slice() else {
+        panic!("A single event should be emitted");
+    };
+    match topic.as_str() {
+        "create_account" => {
+            decode_event::<CreateAccountEvent>(data).map(|e| e.sequence)
+        }
+        "deposit" => decode_event::<DepositEvent>(data).map(|e| e.sequence),
+        "transfer" => decode_event::<TransferEvent>(data).map(|e| e.sequence),
+        "change_account" => {
+            decode_event::<ChangeAccountEvent>(data).map(|e| e.sequence)
+        }
+        _ => panic!("Unexpected topic {topic}"),
+    }
+    .expect("The event should decode")
+}
+
+#[test]
+fn events() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const AMOUNT: u64 = 1_000;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    session.create_account();
+    check_events(&session, "create_account", &["create_account"]);
+
+    session.deposit(DEPOSITOR_INDEX, AMOUNT);
+    check_events(&session, "deposit", &["deposit"]);
+
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, AMOUNT);
+    check_events(&session, "transfer", &["transfer"]);
+
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![
+            AccountChange::RemoveKey {
+                key: session.pks[0],
+            },
+            AccountChange::SetThreshold { threshold: 2 },
+            AccountChange::SetDescription {
+                description: String::from("changed"),
+            },
+        ],
+    );
+    check_events(&session, "change_account", &["change_account"]);
+}
+
+/// Queries the sequence number of the last event emitted.
+fn event_sequence(session: &mut ContractSession) -> u64 {
+    session
+        .call(CONTRACT_ID, "event_sequence", &())
+        .expect("Querying the event sequence should succeed")
+        .data
+}
+
+/// Asserts the last operation emitted the event following the given one,
+/// returning its sequence number.
+#[track_caller]
+fn check_next(session: &mut ContractSession, sequence: u64) -> u64 {
+    assert_eq!(
+        last_sequence(session),
+        sequence + 1,
+        "The event should take the next sequence number"
+    );
+    assert_eq!(
+        event_sequence(session),
+        sequence + 1,
+        "The contract should keep the last sequence number"
+    );
+    sequence + 1
+}
+
+#[test]
+fn event_sequence_numbers() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const AMOUNT: u64 = 1_000;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let mut sequence = event_sequence(&mut session);
+
+    // Every event, of any account, takes the next number
+    session.create_account();
+    sequence = check_next(&mut session, sequence);
+    session.deposit(DEPOSITOR_INDEX, AMOUNT);
+    sequence = check_next(&mut session, sequence);
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, AMOUNT / 2);
+    sequence = check_next(&mut session, sequence);
+    session.create_account();
+    sequence = check_next(&mut session, sequence);
+
+    // Failed operations emit nothing, and leave the sequence untouched
+    let signers = session.all_signers();
+    let mut transfer = session.signed_transfer(&signers, RECEIVER_INDEX, 1);
+    transfer.nonce += 1;
+    assert!(matches!(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Err(ContractError::Panic(_))
+    ));
+    assert_eq!(event_sequence(&mut session), sequence);
+}
+
+/// Returns the payload of the single event emitted by the last operation.
+#[track_caller]
+fn last_payload(session: &ContractSession) -> &[u8] {
+    let [(_, data)] = session.events.as_slice() else {
+        panic!("A single event should be emitted");
+    };
+    data
+}
+
+#[test]
+fn private_events() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const AMOUNT: u64 = 1_000;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    session.create_account();
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetPrivateEvents {
+            private_events: true,
+        }],
+    );
+    let event: ChangeAccountEvent =
+        decode_event(last_payload(&session)).unwrap();
+    assert_eq!(event.private_events, Some(true));
+    assert!(session.account().private_events);
+
+    // Only hashes of the memo and receiver are shown, which can be checked
+    // against the values known by the parties to the operation
+    session.deposit(DEPOSITOR_INDEX, AMOUNT);
+    let event: DepositEvent = decode_event(last_payload(&session)).unwrap();
+    assert_eq!(
+        event.memo,
+        Disclosure::Hashed(hash_memo(event.sequence, MEMO))
+    );
+
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, AMOUNT / 2);
+    let event: TransferEvent = decode_event(last_payload(&session)).unwrap();
+    let receiver = session.pks[RECEIVER_INDEX];
+    assert_eq!(
+        event.receiver,
+        Disclosure::Hashed(hash_receiver(event.sequence, &receiver))
+    );
+    assert_eq!(
+        event.memo,
+        Disclosure::Hashed(hash_memo(event.sequence, MEMO))
+    );
+
+    // Turning it off reveals them again
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetPrivateEvents {
+            private_events: false,
+        }],
+    );
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, AMOUNT / 2);
+    let event: TransferEvent = decode_event(last_payload(&session)).unwrap();
+    assert_eq!(event.receiver, Disclosure::Revealed(receiver));
+    assert_eq!(event.memo, Disclosure::Revealed(String::from(MEMO)));
+}
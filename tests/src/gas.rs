@@ -0,0 +1,106 @@
+//! Gas regression checks.
+//!
+//! The gas spent by each operation is compared against the baseline recorded
+//! in `gas.toml`, failing if it exceeds it by more than a margin. The margin
+//! is a percentage set by `GAS_MARGIN`, and the baseline is recorded anew
+//! whenever `GAS_BASELINE_UPDATE` is set, or if it doesn't exist yet.
+
+use std::collections::BTreeMap;
+use std::fs;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use multisig_contract_types::*;
+
+use crate::{ContractSession, RNG_SEED};
+
+const BASELINE_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/gas.toml");
+const DEFAULT_MARGIN: u64 = 5;
+
+/// Performs each operation once, returning the gas each spent.
+fn measure() -> BTreeMap<String, u64> {
+    const DEPOSITOR_INDEX: usize = 1;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const AMOUNT: u64 = 1_000;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new(&mut rng);
+
+    let mut gas = BTreeMap::new();
+
+    session.create_account();
+    gas.insert(String::from("create_account"), session.gas_spent);
+
+    session.deposit(DEPOSITOR_INDEX, AMOUNT);
+    gas.insert(String::from("deposit"), session.gas_spent);
+
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, AMOUNT);
+    gas.insert(String::from("transfer"), session.gas_spent);
+
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetDescription {
+            description: String::from("changed"),
+        }],
+    );
+    gas.insert(String::from("change_account"), session.gas_spent);
+
+    gas
+}
+
+fn write_baseline(gas: &BTreeMap<String, u64>) {
+    let mut contents = String::from(
+        "# Gas spent by each operation, checked by the `gas` test.\n\
+         # Update with `GAS_BASELINE_UPDATE=1 cargo test gas`.\n\n",
+    );
+    contents.push_str(
+        &toml::to_string(gas).expect("Serializing the baseline should succeed"),
+    );
+
+    fs::write(BASELINE_PATH, contents)
+        .expect("Writing the baseline should succeed");
+}
+
+#[test]
+fn gas() {
+    let gas = measure();
+
+    let baseline = fs::read_to_string(BASELINE_PATH).ok();
+    let update = std::env::var_os("GAS_BASELINE_UPDATE").is_some();
+
+    let baseline: BTreeMap<String, u64> = match baseline {
+        Some(baseline) if !update => toml::from_str(&baseline)
+            .expect("Deserializing the baseline should succeed"),
+        _ => {
+            write_baseline(&gas);
+            return;
+        }
+    };
+
+    let margin = std::env::var("GAS_MARGIN")
+        .map(|margin| margin.parse().expect("GAS_MARGIN should be a number"))
+        .unwrap_or(DEFAULT_MARGIN);
+
+    let mut regressions = Vec::new();
+    for (operation, &spent) in &gas {
+        let Some(&expected) = baseline.get(operation) else {
+            regressions.push(format!("{operation}: no baseline recorded"));
+            continue;
+        };
+
+        let allowed = expected + expected * margin / 100;
+        if spent > allowed {
+            regressions.push(format!(
+                "{operation}: spent {spent}, baseline is {expected}"
+            ));
+        }
+    }
+
+    assert!(
+        regressions.is_empty(),
+        "Gas exceeds the baseline by more than {margin}%:\n{}",
+        regressions.join("\n")
+    );
+}
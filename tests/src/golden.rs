@@ -0,0 +1,48 @@
+//! Golden files, holding the exact bytes of encodings that clients depend on.
+//!
+//! Bytes are stored hex encoded under `golden/`, so that any change to them
+//! shows up as a reviewable diff. Files are recorded anew whenever
+//! `GOLDEN_UPDATE` is set, or if they don't exist yet.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const GOLDEN_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/golden");
+const LINE_LEN: usize = 64;
+
+/// Checks the given bytes against the golden file with the given name.
+#[track_caller]
+pub fn check(name: &str, bytes: &[u8]) {
+    let path = PathBuf::from(GOLDEN_DIR).join(format!("{name}.hex"));
+
+    let encoded = hex::encode(bytes);
+    let mut contents = String::new();
+    for line in encoded.as_bytes().chunks(LINE_LEN) {
+        contents.push_str(std::str::from_utf8(line).unwrap());
+        contents.push('\n');
+    }
+
+    check_file(&path, &contents);
+}
+
+/// Checks the given contents against the file at the given path, recording
+/// them in the same way as the golden files.
+#[track_caller]
+pub fn check_file(path: &Path, contents: &str) {
+    let update = std::env::var_os("GOLDEN_UPDATE").is_some();
+    match fs::read_to_string(path) {
+        Ok(golden) if !update => assert_eq!(
+            contents,
+            golden,
+            "The contents of {} changed. If this is intended, record them \
+             with `GOLDEN_UPDATE=1`",
+            path.display()
+        ),
+        _ => {
+            fs::create_dir_all(path.parent().unwrap())
+                .expect("Creating the golden directory should succeed");
+            fs::write(path, contents)
+                .expect("Writing the golden file should succeed");
+        }
+    }
+}
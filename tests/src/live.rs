@@ -0,0 +1,212 @@
+//! The core scenarios, run against a live rusk node over its HTTP API.
+//!
+//! Unlike the other tests, these go through the node's transaction pool and
+//! query endpoints, exercising the serialization of arguments and responses
+//! just like deployed clients do. They are opt-in, and need a local node with
+//! the contract deployed:
+//!
+//! - `RUSK_NODE` - URL of the node, defaulting to `http://127.0.0.1:8080`
+//! - `MULTISIG_CONTRACT` - hex encoded ID of the contract
+//! - `RUSK_CHAIN_ID` - ID of the node's chain
+//! - `LIVE_SENDER` - base58 encoded secret key of a funded Moonlight account,
+//!   paying for the transactions and deposits
+//!
+//! Transactions are only propagated, so every step waits for its effect to
+//! show in the contract's state, for at most `LIVE_TIMEOUT` seconds.
+
+use std::future::Future;
+use std::time::Duration;
+
+use dusk_bytes::Serializable;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use tokio::time::{sleep, Instant};
+
+use execution_core::ContractId;
+
+use bls::{PublicKey, SecretKey};
+use multisig_contract_types::*;
+use multisig_wallet::{Node, Wallet};
+
+const DEFAULT_NODE: &str = "http://127.0.0.1:8080";
+const DEFAULT_TIMEOUT: u64 = 60;
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+const LIVE_KEYS: usize = 3;
+const LIVE_THRESHOLD: u32 = 2;
+const DEPOSIT: u64 = 1_000_000;
+
+fn env(name: &str) -> String {
+    std::env::var(name)
+        .unwrap_or_else(|_| panic!("`{name}` must be set for the live tests"))
+}
+
+fn wallet() -> Wallet {
+    let node = std::env::var("RUSK_NODE")
+        .unwrap_or_else(|_| String::from(DEFAULT_NODE));
+
+    let contract = hex::decode(env("MULTISIG_CONTRACT"))
+        .expect("`MULTISIG_CONTRACT` should be hex encoded")
+        .try_into()
+        .expect("`MULTISIG_CONTRACT` should be 32 bytes long");
+    let contract = ContractId::from_bytes(contract);
+
+    let chain_id = env("RUSK_CHAIN_ID")
+        .parse()
+        .expect("`RUSK_CHAIN_ID` should be a number");
+
+    let sender = bs58::decode(env("LIVE_SENDER"))
+        .into_vec()
+        .expect("`LIVE_SENDER` should be base58 encoded")
+        .try_into()
+        .expect("`LIVE_SENDER` should be 32 bytes long");
+    let sender = SecretKey::from_bytes(&sender)
+        .expect("`LIVE_SENDER` should be a valid secret key");
+
+    Wallet::new(Node::new(node), contract, chain_id, sender)
+}
+
+/// Polls the given query until it returns a value, panicking with the given
+/// message once the timeout is reached.
+async fn wait_for<T, F, Fut>(what: &str, mut query: F) -> T
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Option<T>>,
+{
+    let timeout = std::env::var("LIVE_TIMEOUT")
+        .map(|t| t.parse().expect("`LIVE_TIMEOUT` should be a number"))
+        .unwrap_or(DEFAULT_TIMEOUT);
+    let deadline = Instant::now() + Duration::from_secs(timeout);
+
+    loop {
+        if let Some(value) = query().await {
+            return value;
+        }
+        if Instant::now() > deadline {
+            panic!("Timed out after {timeout}s waiting for {what}");
+        }
+        sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Signs the operation with the given keys, and submits it.
+async fn sign_and_submit(
+    wallet: &mut Wallet,
+    mut operation: Operation,
+    signers: &[SecretKey],
+) {
+    let partials: Vec<PartialSignature> = signers
+        .iter()
+        .map(|sk| PartialSignature::sign(sk, &operation))
+        .collect();
+    operation.aggregate(&partials);
+
+    wallet
+        .submit(&operation)
+        .await
+        .expect("Submitting the operation should succeed");
+}
+
+#[tokio::test]
+#[ignore = "needs a live node, run with `--ignored`"]
+async fn live() {
+    let mut wallet = wallet();
+
+    // Fresh keys, so that the account created is the only one using them
+    let mut rng = StdRng::from_entropy();
+    let sks: Vec<SecretKey> = (0..=LIVE_KEYS)
+        .map(|_| SecretKey::random(&mut rng))
+        .collect();
+    let pks: Vec<PublicKey> = sks.iter().map(PublicKey::from).collect();
+
+    wallet
+        .create_account(&CreateAccount {
+            keys: pks[..LIVE_KEYS].to_vec(),
+            threshold: LIVE_THRESHOLD,
+            description: String::from("live-test"),
+            alias: None,
+            key_weights: Vec::new(),
+            key_roles: Vec::new(),
+        })
+        .await
+        .expect("Submitting the account creation should succeed");
+
+    let account_id = wait_for("the account to be created", || async {
+        let ids = wallet.key_accounts(&pks[0]).await.ok()?;
+        ids.first().copied()
+    })
+    .await;
+
+    let account = wallet.account(account_id).await.unwrap();
+    assert_eq!(account.threshold, LIVE_THRESHOLD);
+    assert_eq!(account.description, "live-test");
+    assert_eq!(account.balance, 0);
+    assert_eq!(account.nonce, 0);
+
+    let keys = wallet.account_keys(account_id).await.unwrap();
+    assert_eq!(keys.len(), LIVE_KEYS, "Every key should be fed");
+    for key in &pks[..LIVE_KEYS] {
+        assert!(keys.contains(key), "The account should use its keys");
+    }
+
+    wallet
+        .deposit(account_id, DEPOSIT, "live-deposit", "")
+        .await
+        .expect("Submitting the deposit should succeed");
+    wait_for("the deposit to be made", || async {
+        let account = wallet.account(account_id).await.ok()?;
+        (account.balance == DEPOSIT).then_some(())
+    })
+    .await;
+
+    let receiver = pks[LIVE_KEYS];
+    let transfer = wallet
+        .transfer(account_id, receiver, DEPOSIT / 2, "live-transfer")
+        .await
+        .unwrap();
+    sign_and_submit(&mut wallet, transfer, &sks[..LIVE_THRESHOLD as usize])
+        .await;
+    wait_for("the transfer to be made", || async {
+        let account = wallet.account(account_id).await.ok()?;
+        (account.nonce == 1).then_some(())
+    })
+    .await;
+    let account = wallet.account(account_id).await.unwrap();
+    assert_eq!(account.balance, DEPOSIT - DEPOSIT / 2);
+
+    let change = wallet
+        .change_account(
+            account_id,
+            vec![
+                AccountChange::AddKey {
+                    key: pks[LIVE_KEYS],
+                },
+                AccountChange::RemoveKey { key: pks[0] },
+                AccountChange::SetThreshold { threshold: 3 },
+                AccountChange::SetDescription {
+                    description: String::from("live-changed"),
+                },
+            ],
+        )
+        .await
+        .unwrap();
+    sign_and_submit(&mut wallet, change, &sks[1..LIVE_KEYS]).await;
+    wait_for("the account to be changed", || async {
+        let account = wallet.account(account_id).await.ok()?;
+        (account.nonce == 2).then_some(())
+    })
+    .await;
+
+    let account = wallet.account(account_id).await.unwrap();
+    assert_eq!(account.threshold, 3);
+    assert_eq!(account.description, "live-changed");
+
+    let keys = wallet.account_keys(account_id).await.unwrap();
+    assert!(keys.contains(&pks[LIVE_KEYS]), "The key should be added");
+    assert!(!keys.contains(&pks[0]), "The key should be removed");
+
+    let ids = wallet.key_accounts(&pks[0]).await.unwrap();
+    assert!(!ids.contains(&account_id), "The key should be unlinked");
+    let ids = wallet.key_accounts(&pks[LIVE_KEYS]).await.unwrap();
+    assert_eq!(ids, vec![account_id], "The key should be linked");
+}
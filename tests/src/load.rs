@@ -0,0 +1,149 @@
+//! Load tests of the contract's queries at scale.
+//!
+//! Tens of thousands of accounts are created, all sharing one key, and the
+//! feeder queries are then run over them with a bounded gas limit, failing if
+//! they run out of gas or memory. The number of accounts can be set with
+//! `LOAD_ACCOUNTS`.
+//!
+//! Accounts are created by calling the contract directly, rather than through
+//! the session's helpers, since diffing the whole state against the model
+//! after each of them would take far too long.
+
+use std::sync::mpsc;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use bytecheck::CheckBytes;
+use execution_core::StandardBufSerializer;
+use rkyv::validation::validators::DefaultValidator;
+use rkyv::Serialize;
+
+use bls::{PublicKey, SecretKey};
+use multisig_contract_types::*;
+
+use crate::{ContractSession, CONTRACT_ID, RNG_SEED};
+
+/// Number of accounts created, unless overridden by `LOAD_ACCOUNTS`.
+const DEFAULT_ACCOUNTS: usize = 20_000;
+/// Number of keys of the single account with many keys.
+const WIDE_KEYS: usize = 256;
+
+/// The gas any single call may spend.
+const GAS_LIMIT: u64 = 1_000_000_000;
+
+/// Runs a feeder query with the bounded gas limit, returning the number of
+/// items fed and the gas spent.
+fn feed<A>(
+    session: &mut ContractSession,
+    fn_name: &str,
+    fn_arg: &A,
+) -> (usize, u64)
+where
+    A: for<'b> Serialize<StandardBufSerializer<'b>>,
+    A::Archived: for<'b> CheckBytes<DefaultValidator<'b>>,
+{
+    let (sender, receiver) = mpsc::channel();
+    let receipt = session
+        .session
+        .feeder_call::<_, ()>(CONTRACT_ID, fn_name, fn_arg, GAS_LIMIT, sender)
+        .unwrap_or_else(|err| panic!("Feeding {fn_name} failed: {err}"));
+
+    (receiver.into_iter().count(), receipt.gas_spent)
+}
+
+/// Creates an account, returning its ID and the gas spent.
+fn create_account(
+    session: &mut ContractSession,
+    keys: Vec<PublicKey>,
+) -> (u64, u64) {
+    let receipt = session
+        .session
+        .call::<_, u64>(
+            CONTRACT_ID,
+            "create_account",
+            &CreateAccount {
+                keys,
+                threshold: 1,
+                description: String::new(),
+                alias: None,
+                key_weights: Vec::new(),
+                key_roles: Vec::new(),
+            },
+            GAS_LIMIT,
+        )
+        .expect("Creating an account should succeed");
+
+    (receipt.data, receipt.gas_spent)
+}
+
+#[test]
+#[ignore = "long running, run with `--ignored`"]
+fn load() {
+    let num_accounts = std::env::var("LOAD_ACCOUNTS")
+        .map(|num| num.parse().expect("LOAD_ACCOUNTS should be a number"))
+        .unwrap_or(DEFAULT_ACCOUNTS);
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    // Every account uses the shared key, together with a key of its own
+    let shared = session.pks[0];
+    let mut own_keys = Vec::with_capacity(num_accounts);
+
+    let mut first_gas = 0;
+    let mut last_gas = 0;
+    for i in 0..num_accounts {
+        let key = PublicKey::from(&SecretKey::random(&mut rng));
+        own_keys.push(key);
+
+        let (id, gas) = create_account(&mut session, vec![shared, key]);
+        assert_eq!(id, i as u64 + 1, "Accounts should be created in order");
+
+        if i == 0 {
+            first_gas = gas;
+        }
+        last_gas = gas;
+    }
+
+    // Creating accounts should not get much more expensive as they grow
+    assert!(
+        last_gas <= 2 * first_gas,
+        "Creating an account cost {first_gas} gas at first, but {last_gas} \
+         with {num_accounts} accounts"
+    );
+
+    let wide_keys: Vec<PublicKey> = (0..WIDE_KEYS)
+        .map(|_| PublicKey::from(&SecretKey::random(&mut rng)))
+        .collect();
+    let (wide_id, _) = create_account(&mut session, wide_keys);
+
+    // Queries over a single account shouldn't be affected by the others
+    let last_key = own_keys[num_accounts - 1];
+    let (count, own_gas) = feed(&mut session, "key_accounts", &last_key);
+    assert_eq!(count, 1, "An account's own key should be fed its account");
+
+    let last_id = num_accounts as u64;
+    let (count, pair_gas) = feed(&mut session, "account_keys", &last_id);
+    assert_eq!(count, 2, "An account should be fed both its keys");
+
+    // Feeding many items should cost no more per item than feeding a few
+    let (count, gas) = feed(&mut session, "key_accounts", &shared);
+    assert_eq!(
+        count, num_accounts,
+        "The shared key should be fed every account using it"
+    );
+    assert!(
+        gas <= count as u64 * own_gas,
+        "Feeding {count} accounts cost {gas} gas, more per account than the \
+         {own_gas} of feeding one"
+    );
+
+    let (count, gas) = feed(&mut session, "account_keys", &wide_id);
+    assert_eq!(count, WIDE_KEYS, "Every key of the account should be fed");
+    assert!(
+        gas <= count as u64 * pair_gas / 2,
+        "Feeding {count} keys cost {gas} gas, more per key than the \
+         {pair_gas} of feeding two"
+    );
+}
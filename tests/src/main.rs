@@ -1,18 +1,49 @@
+// The crate only exists to run its tests, so items are only ever used when
+// compiling for them.
+#![cfg_attr(not(test), allow(dead_code, unused_imports))]
+
+#[cfg(test)]
+mod events;
+#[cfg(test)]
+mod gas;
+#[cfg(test)]
+mod golden;
+#[cfg(test)]
+mod live;
+#[cfg(test)]
+mod load;
+mod model;
+#[cfg(test)]
+mod roundtrip;
+#[cfg(test)]
+mod simulation;
+#[cfg(test)]
+mod vectors;
+
+use std::collections::{BTreeMap, BTreeSet};
 use std::sync::mpsc;
 
 use execution_core::{
+    license::LICENSE_CONTRACT,
     transfer::{
-        data::ContractCall, moonlight::AccountData as MoonlightAccountData,
+        data::ContractCall,
+        moonlight::AccountData as MoonlightAccountData,
+        phoenix::{
+            Note, NoteLeaf, NoteOpening, PublicKey as PhoenixPublicKey,
+            SecretKey as PhoenixSecretKey, ViewKey as PhoenixViewKey,
+        },
         Transaction, TRANSFER_CONTRACT,
     },
-    ContractError, ContractId, StandardBufSerializer,
+    BlsScalar, ContractError, ContractId, JubJubScalar, StandardBufSerializer,
 };
-use rusk_abi::{CallReceipt, ContractData, PiecrustError, Session};
+use rusk_abi::{CallReceipt, ContractData, PiecrustError, Session, VM};
+use rusk_prover::LocalProver;
 use rusk_recovery_tools::state;
 use tempfile::TempDir;
 
 use bytecheck::CheckBytes;
 use rkyv::de::deserializers::SharedDeserializeMap;
+use rkyv::ser::serializers::AllocSerializer;
 use rkyv::validation::validators::DefaultValidator;
 use rkyv::{Archive, Deserialize, Infallible, Serialize};
 
@@ -22,8 +53,14 @@ use rand::{CryptoRng, RngCore, SeedableRng};
 use bls::{MultisigSignature, PublicKey, SecretKey};
 use multisig_contract_types::*;
 
+use model::{Key, Model};
+
 const CONTRACT_BYTECODE: &[u8] =
     include_bytes!("../../build/multisig_contract.wasm");
+const MOCK_TRANSFER_BYTECODE: &[u8] =
+    include_bytes!("../../build/mock_transfer.wasm");
+const MOCK_LICENSE_BYTECODE: &[u8] =
+    include_bytes!("../../build/mock_license.wasm");
 const CONTRACT_ID: ContractId = ContractId::from_bytes([1; 32]);
 const CONTRACT_OWNER: [u8; 64] = [0u8; 64];
 
@@ -36,31 +73,142 @@ const THRESHOLD: u32 = NUM_KEYS as u32 / 2;
 const DESCRIPTION: &str = "test-description";
 const MEMO: &str = "test-memo";
 const RNG_SEED: u64 = 0xBEEF;
+const PHOENIX_RNG_SEED: u64 = 0xF00D;
 const INITIAL_BALANCE: u64 = 10_000_000_000;
 
-type Result<T, Error = PiecrustError> = std::result::Result<T, Error>;
+type Result<T, E = PiecrustError> = std::result::Result<T, E>;
 
 struct ContractSession {
     session: Session,
     sks: Vec<SecretKey>,
     pks: Vec<PublicKey>,
+    /// Phoenix keys, each owning a note of the initial balance when on the
+    /// full genesis state.
+    phoenix_sks: Vec<PhoenixSecretKey>,
+    /// Randomness for Phoenix transactions, kept apart from the one
+    /// generating the session's keys so that those stay the same.
+    phoenix_rng: StdRng,
     account_id: Option<u64>,
-    _state_dir: TempDir,
+    /// Gas spent by the last successful operation.
+    gas_spent: u64,
+    /// Topic and data of each event the contract emitted during the last
+    /// successful operation.
+    events: Vec<(String, Vec<u8>)>,
+    /// The reference model every operation is mirrored on and checked
+    /// against.
+    model: Model,
+    /// Whether the transfer contract is mocked, in which case operations are
+    /// called directly instead of through transactions.
+    mocked: bool,
+    _state_dir: Option<TempDir>,
 }
 
 #[allow(dead_code)]
 impl ContractSession {
+    /// Deploys the contract on top of the full genesis state.
     fn new<Rng: RngCore + CryptoRng>(rng: &mut Rng) -> Self {
-        let state_dir = TempDir::new()
-            .expect("Creating temporary directory should succeed");
-        let snapshot = toml::from_str(SNAPSHOT)
-            .expect("Deserializing snapshot should succeed");
+        Self::deploy(rng, false, |_| None)
+    }
+
+    /// Deploys the contract alongside a mock of the transfer contract, which
+    /// is much faster than deploying the full genesis state.
+    ///
+    /// Deposits are credited without being taken from anyone, and Moonlight
+    /// accounts only hold what is transferred to them.
+    fn new_mocked<Rng: RngCore + CryptoRng>(rng: &mut Rng) -> Self {
+        Self::deploy(rng, true, |_| None)
+    }
+
+    /// Deploys the contract, alongside a mock of the transfer contract, with
+    /// the genesis built from the session's keys.
+    fn with_genesis<Rng: RngCore + CryptoRng>(
+        rng: &mut Rng,
+        genesis: impl FnOnce(&[PublicKey]) -> Genesis,
+    ) -> Self {
+        Self::deploy(rng, true, |pks| Some(genesis(pks)))
+    }
 
-        let (vm, root) = state::deploy(&state_dir, &snapshot, |_| {})
+    fn deploy<Rng: RngCore + CryptoRng>(
+        rng: &mut Rng,
+        mocked: bool,
+        genesis: impl FnOnce(&[PublicKey]) -> Option<Genesis>,
+    ) -> Self {
+        let mut phoenix_rng = StdRng::seed_from_u64(PHOENIX_RNG_SEED);
+        let phoenix_sks: Vec<PhoenixSecretKey> = (0..NUM_KEYS)
+            .map(|_| PhoenixSecretKey::random(&mut phoenix_rng))
+            .collect();
+
+        let (mut session, state_dir) = if mocked {
+            let vm = VM::ephemeral().expect("Creating a VM should succeed");
+            let mut session = rusk_abi::new_genesis_session(&vm, CHAIN_ID);
+
+            session
+                .deploy(
+                    MOCK_TRANSFER_BYTECODE,
+                    ContractData::builder()
+                        .owner(CONTRACT_OWNER)
+                        .contract_id(TRANSFER_CONTRACT),
+                    u64::MAX,
+                )
+                .expect("Deploying the mock transfer contract should succeed");
+            session
+                .deploy(
+                    MOCK_LICENSE_BYTECODE,
+                    ContractData::builder()
+                        .owner(CONTRACT_OWNER)
+                        .contract_id(LICENSE_CONTRACT),
+                    u64::MAX,
+                )
+                .expect("Deploying the mock license contract should succeed");
+
+            (session, None)
+        } else {
+            let state_dir = TempDir::new()
+                .expect("Creating temporary directory should succeed");
+            let snapshot = toml::from_str(SNAPSHOT)
+                .expect("Deserializing snapshot should succeed");
+
+            // The Phoenix keys are given their notes on top of the snapshot
+            let (vm, root) = state::deploy(&state_dir, &snapshot, |session| {
+                for sk in &phoenix_sks {
+                    let pk = PhoenixPublicKey::from(sk);
+                    let blinder = [
+                        JubJubScalar::random(&mut phoenix_rng),
+                        JubJubScalar::random(&mut phoenix_rng),
+                    ];
+                    let note = Note::transparent(
+                        &mut phoenix_rng,
+                        &pk,
+                        &pk,
+                        INITIAL_BALANCE,
+                        blinder,
+                    );
+
+                    session
+                        .call::<_, Note>(
+                            TRANSFER_CONTRACT,
+                            "push_note",
+                            &(0u64, note),
+                            u64::MAX,
+                        )
+                        .expect("Pushing a note should succeed");
+                }
+                session
+                    .call::<_, ()>(
+                        TRANSFER_CONTRACT,
+                        "update_root",
+                        &(),
+                        u64::MAX,
+                    )
+                    .expect("Updating the root should succeed");
+            })
             .expect("Deploying snapshot should succeed");
-        let mut session =
-            rusk_abi::new_session(&vm, root, CHAIN_ID, BLOCK_HEIGHT)
-                .expect("Starting a new session should succeed");
+            let session =
+                rusk_abi::new_session(&vm, root, CHAIN_ID, BLOCK_HEIGHT)
+                    .expect("Starting a new session should succeed");
+
+            (session, Some(state_dir))
+        };
 
         let mut sks = Vec::with_capacity(NUM_KEYS);
         let mut pks = Vec::with_capacity(NUM_KEYS);
@@ -72,21 +220,39 @@ impl ContractSession {
             pks.push(pk);
         }
 
-        session
-            .deploy(
-                CONTRACT_BYTECODE,
-                ContractData::builder()
-                    .owner(CONTRACT_OWNER)
-                    .contract_id(CONTRACT_ID),
-                u64::MAX,
-            )
-            .expect("Deploying the multisig contract should succeed");
+        let mut model = Model {
+            block_height: if mocked { 0 } else { BLOCK_HEIGHT },
+            chain_id: CHAIN_ID,
+            ..Model::default()
+        };
+
+        let contract = ContractData::builder()
+            .owner(CONTRACT_OWNER)
+            .contract_id(CONTRACT_ID);
+        match genesis(&pks) {
+            Some(genesis) => {
+                model.init(&genesis);
+                session.deploy(
+                    CONTRACT_BYTECODE,
+                    contract.init_arg(&genesis),
+                    u64::MAX,
+                )
+            }
+            None => session.deploy(CONTRACT_BYTECODE, contract, u64::MAX),
+        }
+        .expect("Deploying the multisig contract should succeed");
 
         Self {
             session,
             sks,
             pks,
+            phoenix_sks,
+            phoenix_rng,
             account_id: None,
+            gas_spent: 0,
+            events: Vec::new(),
+            model,
+            mocked,
             _state_dir: state_dir,
         }
     }
@@ -109,6 +275,7 @@ impl ContractSession {
 
     fn feeder_query<A, R>(
         &mut self,
+        contract: ContractId,
         fn_name: &str,
         fn_arg: &A,
     ) -> Result<Vec<R>>
@@ -123,7 +290,7 @@ impl ContractSession {
         let mut results = Vec::new();
         let (sender, receiver) = mpsc::channel();
         self.session.feeder_call::<_, ()>(
-            CONTRACT_ID,
+            contract,
             fn_name,
             fn_arg,
             u64::MAX,
@@ -147,65 +314,208 @@ impl ContractSession {
             keys,
             threshold: THRESHOLD,
             description: String::from(DESCRIPTION),
+            alias: None,
+            key_weights: Vec::new(),
+            key_roles: Vec::new(),
         };
 
         let id = self
-            .call(CONTRACT_ID, "create_account", &create_account)
-            .expect("Creating an account should succeed")
-            .data;
+            .try_create_account(&create_account)
+            .expect("Creating an account should succeed");
         self.account_id = Some(id);
         id
     }
 
-    fn deposit(&mut self, index: usize, amount: u64) {
-        let account_id = self
-            .account_id
-            .expect("must call `create_account` before `account`");
-        let sk = self.sks[index].clone();
+    fn try_create_account(
+        &mut self,
+        create_account: &CreateAccount,
+    ) -> Result<u64, ContractError> {
+        let expected = self.model.create_account(create_account);
 
-        const GAS_LIMIT: u64 = 1_000_000;
-        const GAS_PRICE: u64 = 1;
-        const NONCE: u64 = 1;
+        let result = self
+            .call(CONTRACT_ID, "create_account", create_account)
+            .map_err(contract_error)
+            .map(|receipt| {
+                self.gas_spent = receipt.gas_spent;
+                self.events = contract_events(&receipt);
+                receipt.data
+            });
 
-        let deposit = Deposit {
-            account_id,
-            amount,
-            memo: String::from(MEMO),
-        };
+        self.check_model(&result, expected);
+        result
+    }
+
+    fn try_register_template(
+        &mut self,
+        template: &AccountTemplate,
+    ) -> Result<u64, ContractError> {
+        let expected = self.model.register_template(template);
+
+        let result = self
+            .call(CONTRACT_ID, "register_template", template)
+            .map_err(contract_error)
+            .map(|receipt| {
+                self.gas_spent = receipt.gas_spent;
+                self.events = contract_events(&receipt);
+                receipt.data
+            });
+
+        self.check_model(&result, expected);
+        result
+    }
+
+    fn try_create_account_from_template(
+        &mut self,
+        create: &CreateAccountFromTemplate,
+    ) -> Result<u64, ContractError> {
+        let expected = self.model.create_account_from_template(create);
+
+        let result = self
+            .call(CONTRACT_ID, "create_account_from_template", create)
+            .map_err(contract_error)
+            .map(|receipt| {
+                self.gas_spent = receipt.gas_spent;
+                self.events = contract_events(&receipt);
+                receipt.data
+            });
+
+        self.check_model(&result, expected);
+        result
+    }
+
+    /// Executes a call to the multisig contract by sending a Moonlight
+    /// transaction from the account of the secret key with the given index,
+    /// depositing the given amount to the contract.
+    fn try_execute<A>(
+        &mut self,
+        index: usize,
+        fn_name: &str,
+        fn_arg: &A,
+        deposit: u64,
+    ) -> Result<Vec<u8>, ContractError>
+    where
+        A: Serialize<AllocSerializer<128>>,
+    {
+        const GAS_LIMIT: u64 = 2_000_000;
+        const GAS_PRICE: u64 = 1;
 
-        let fn_args = rkyv::to_bytes::<_, 128>(&deposit)
+        let fn_args = rkyv::to_bytes::<_, 128>(fn_arg)
             .expect("Serializing argument should succeed")
             .to_vec();
 
+        // Without the transfer contract there are no transactions, so the
+        // contract is called directly
+        if self.mocked {
+            let receipt = self
+                .session
+                .call_raw(CONTRACT_ID, fn_name, fn_args, GAS_LIMIT)
+                .map_err(contract_error)?;
+            self.gas_spent = receipt.gas_spent;
+            self.events = contract_events(&receipt);
+            return Ok(receipt.data);
+        }
+
+        let sk = self.sks[index].clone();
+        let nonce = self.moonlight_account(self.pks[index]).nonce + 1;
+
         let tx = Transaction::moonlight(
             &sk,
             None,
             0,
-            amount,
+            deposit,
+            GAS_LIMIT,
+            GAS_PRICE,
+            nonce,
+            CHAIN_ID,
+            Some(ContractCall {
+                contract: CONTRACT_ID,
+                fn_name: String::from(fn_name),
+                fn_args,
+            }),
+        )
+        .unwrap();
+
+        self.try_transaction(&tx, GAS_LIMIT)
+    }
+
+    /// Executes a call to the multisig contract by sending a Phoenix
+    /// transaction, spending the notes of the Phoenix key with the given
+    /// index, and depositing the given amount to the contract.
+    ///
+    /// Phoenix transactions need the full genesis state, so they can't be
+    /// used with a mocked transfer contract.
+    fn try_execute_phoenix<A>(
+        &mut self,
+        index: usize,
+        fn_name: &str,
+        fn_arg: &A,
+        deposit: u64,
+    ) -> Result<Vec<u8>, ContractError>
+    where
+        A: Serialize<AllocSerializer<128>>,
+    {
+        const GAS_LIMIT: u64 = 2_000_000;
+        const GAS_PRICE: u64 = 1;
+
+        assert!(!self.mocked, "Phoenix transactions need the full state");
+
+        let fn_args = rkyv::to_bytes::<_, 128>(fn_arg)
+            .expect("Serializing argument should succeed")
+            .to_vec();
+
+        let sk = self.phoenix_sks[index].clone();
+        let pk = PhoenixPublicKey::from(&sk);
+
+        let inputs = self.phoenix_inputs(index);
+        let root: BlsScalar = self
+            .call(TRANSFER_CONTRACT, "root", &())
+            .expect("Querying the root should succeed")
+            .data;
+
+        // The change goes back to the sender, who is also the receiver of a
+        // zero value transfer
+        let tx = Transaction::phoenix(
+            &mut self.phoenix_rng,
+            &sk,
+            &pk,
+            &pk,
+            inputs,
+            root,
+            0,
+            true,
+            deposit,
             GAS_LIMIT,
             GAS_PRICE,
-            NONCE,
             CHAIN_ID,
             Some(ContractCall {
                 contract: CONTRACT_ID,
-                fn_name: String::from("deposit"),
+                fn_name: String::from(fn_name),
                 fn_args,
             }),
+            &LocalProver,
         )
         .unwrap();
 
+        self.try_transaction(&tx, GAS_LIMIT)
+    }
+
+    /// Executes a transaction through the transfer contract, refunding its
+    /// unspent gas.
+    fn try_transaction(
+        &mut self,
+        tx: &Transaction,
+        gas_limit: u64,
+    ) -> Result<Vec<u8>, ContractError> {
         let receipt = self
             .session
             .call::<_, Result<Vec<u8>, ContractError>>(
                 TRANSFER_CONTRACT,
                 "spend_and_execute",
-                &tx,
-                GAS_LIMIT,
+                tx,
+                gas_limit,
             )
             .expect("Executing transaction should succeed");
 
-        println!("{:?}", receipt.data);
-
         let _refund_receipt = self
             .session
             .call::<_, ()>(
@@ -215,451 +525,8906 @@ impl ContractSession {
                 u64::MAX,
             )
             .expect("Refunding must succeed");
+
+        if receipt.data.is_ok() {
+            self.gas_spent = receipt.gas_spent;
+            self.events = contract_events(&receipt);
+        }
+        receipt.data
     }
 
-    fn transfer(&mut self, index: usize, receiver_index: usize, amount: u64) {
+    /// Signs the given message with the keys at the given indices, returning
+    /// the keys used together with the aggregated signature.
+    fn sign(
+        &self,
+        indices: &[usize],
+        msg: &[u8],
+    ) -> (Vec<PublicKey>, MultisigSignature) {
+        let mut keys = Vec::with_capacity(indices.len());
+        let mut signature: Option<MultisigSignature> = None;
+
+        for &i in indices {
+            let public_key = self.pks[i];
+            keys.push(public_key);
+
+            let s = self.sks[i].sign_multisig(&public_key, msg);
+            signature = Some(match signature {
+                Some(signature) => signature.aggregate(&[s]),
+                None => s,
+            });
+        }
+
+        (keys, signature.unwrap_or_default())
+    }
+
+    /// Returns the indices of all the keys in the session.
+    fn all_signers(&self) -> Vec<usize> {
+        (0..NUM_KEYS).collect()
+    }
+
+    fn deposit(&mut self, index: usize, amount: u64) {
+        self.try_deposit(index, amount)
+            .expect("Depositing should succeed");
+    }
+
+    fn try_deposit(
+        &mut self,
+        index: usize,
+        amount: u64,
+    ) -> Result<(), ContractError> {
+        self.try_deposit_referenced(index, amount, "")
+    }
+
+    /// Deposits to the account, attributed with the given depositor
+    /// reference.
+    fn try_deposit_referenced(
+        &mut self,
+        index: usize,
+        amount: u64,
+        depositor_reference: &str,
+    ) -> Result<(), ContractError> {
         let account_id = self
             .account_id
-            .expect("must call `create_account` before `transfer`");
-        let sk = self.sks[index].clone();
+            .expect("must call `create_account` before `deposit`");
 
-        const GAS_LIMIT: u64 = 2_000_000;
-        const GAS_PRICE: u64 = 1;
-        const NONCE: u64 = 1;
+        let deposit = Deposit {
+            account_id,
+            amount,
+            memo: String::from(MEMO),
+            depositor_reference: String::from(depositor_reference),
+        };
+
+        let expected = self.model.deposit(&deposit);
+        let result = self
+            .try_execute(index, "deposit", &deposit, amount)
+            .map(|_| ());
+
+        self.check_model(&result, expected);
+        result
+    }
+
+    /// Deposits to the account through a Phoenix transaction spent by the
+    /// Phoenix key with the given index.
+    fn try_deposit_phoenix(
+        &mut self,
+        index: usize,
+        amount: u64,
+    ) -> Result<(), ContractError> {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `deposit`");
+
+        let deposit = Deposit {
+            account_id,
+            amount,
+            memo: String::from(MEMO),
+            depositor_reference: String::new(),
+        };
+
+        let expected = self.model.deposit(&deposit);
+        let result = self
+            .try_execute_phoenix(index, "deposit", &deposit, amount)
+            .map(|_| ());
+
+        self.check_model(&result, expected);
+        result
+    }
+
+    /// Builds a transfer for the next nonce of the account, signed by the keys
+    /// at the given indices.
+    fn signed_transfer(
+        &mut self,
+        signers: &[usize],
+        receiver_index: usize,
+        amount: u64,
+    ) -> Transfer {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `transfer`");
 
         let mut transfer = Transfer {
             account_id,
-            keys: Vec::with_capacity(NUM_KEYS),
+            keys: Vec::new(),
             signature: MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
             receiver: self.pks[receiver_index],
             amount,
-            nonce: 1, // if its the first interaction, 2 if second, etc...
+            nonce: self.account().nonce + 1,
             memo: String::from(MEMO),
+            reference: None,
+            category: None,
+            gas_refund: None,
+            chain_id: None,
         };
 
         let msg = transfer.signature_msg();
+        (transfer.keys, transfer.signature) = self.sign(signers, &msg);
+
+        transfer
+    }
 
+    fn transfer(&mut self, index: usize, receiver_index: usize, amount: u64) {
         // NOTE: Here we sign with all the keys of the account. This is
         //       technically unnecessary, since we could use only some of the
         //       keys, but as a test it is ok.
-        let public_key = self.pks[0];
-
-        transfer.keys.push(public_key);
-        transfer.signature = self.sks[0].sign_multisig(&public_key, &msg);
-
-        for i in 1..NUM_KEYS {
-            let public_key = self.pks[i];
-            transfer.keys.push(public_key);
+        let signers = self.all_signers();
+        let transfer = self.signed_transfer(&signers, receiver_index, amount);
 
-            let s = self.sks[i].sign_multisig(&public_key, &msg);
-            transfer.signature = transfer.signature.aggregate(&[s]);
-        }
-
-        let fn_args = rkyv::to_bytes::<_, 128>(&transfer)
-            .expect("Serializing argument should succeed")
-            .to_vec();
+        self.try_transfer(index, &transfer)
+            .expect("Transferring should succeed");
+    }
 
-        let tx = Transaction::moonlight(
-            &sk,
-            None,
-            0,
-            0,
-            GAS_LIMIT,
-            GAS_PRICE,
-            NONCE,
-            CHAIN_ID,
-            Some(ContractCall {
-                contract: CONTRACT_ID,
-                fn_name: String::from("transfer"),
-                fn_args,
-            }),
-        )
-        .unwrap();
+    fn try_transfer(
+        &mut self,
+        index: usize,
+        transfer: &Transfer,
+    ) -> Result<(), ContractError> {
+        let expected = self.model.transfer(transfer);
+        let result =
+            self.try_execute(index, "transfer", transfer, 0).map(|_| ());
 
-        let receipt = self
-            .session
-            .call::<_, Result<Vec<u8>, ContractError>>(
-                TRANSFER_CONTRACT,
-                "spend_and_execute",
-                &tx,
-                GAS_LIMIT,
-            )
-            .expect("Executing transaction should succeed");
+        self.check_model(&result, expected);
+        result
+    }
 
-        println!("{:?}", receipt.data);
+    /// Executes a transfer through a Phoenix transaction paid for by the
+    /// Phoenix key with the given index.
+    fn try_transfer_phoenix(
+        &mut self,
+        index: usize,
+        transfer: &Transfer,
+    ) -> Result<(), ContractError> {
+        let expected = self.model.transfer(transfer);
+        let result = self
+            .try_execute_phoenix(index, "transfer", transfer, 0)
+            .map(|_| ());
 
-        let _refund_receipt = self
-            .session
-            .call::<_, ()>(
-                TRANSFER_CONTRACT,
-                "refund",
-                &receipt.gas_spent,
-                u64::MAX,
-            )
-            .expect("Refunding must succeed");
+        self.check_model(&result, expected);
+        result
     }
 
-    fn change_account(&mut self, index: usize, changes: Vec<AccountChange>) {
+    /// Builds a change for the next nonce of the account, signed by the keys
+    /// at the given indices.
+    fn signed_change_account(
+        &mut self,
+        signers: &[usize],
+        changes: Vec<AccountChange>,
+    ) -> ChangeAccount {
         let account_id = self
             .account_id
             .expect("must call `create_account` before `change_account`");
-        let sk = self.sks[index].clone();
-
-        const GAS_LIMIT: u64 = 2_000_000;
-        const GAS_PRICE: u64 = 1;
-        const NONCE: u64 = 1;
 
         let mut change_account = ChangeAccount {
             account_id,
-            keys: Vec::with_capacity(NUM_KEYS),
+            keys: Vec::new(),
             signature: MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
             changes,
-            nonce: 1, // if its the first interaction, 2 if second, etc...
+            nonce: self.account().nonce + 1,
+            gas_refund: None,
+            chain_id: None,
         };
 
         let msg = change_account.signature_msg();
+        (change_account.keys, change_account.signature) =
+            self.sign(signers, &msg);
 
+        change_account
+    }
+
+    fn change_account(&mut self, index: usize, changes: Vec<AccountChange>) {
         // NOTE: Here we sign with all the keys of the account. This is
         //       technically unnecessary, since we could use only some of the
         //       keys, but as a test it is ok.
-        let public_key = self.pks[0];
+        let signers = self.all_signers();
+        let change_account = self.signed_change_account(&signers, changes);
 
-        change_account.keys.push(public_key);
-        change_account.signature = self.sks[0].sign_multisig(&public_key, &msg);
+        self.try_change_account(index, &change_account)
+            .expect("Changing the account should succeed");
+    }
 
-        for i in 1..NUM_KEYS {
-            let public_key = self.pks[i];
-            change_account.keys.push(public_key);
+    fn try_change_account(
+        &mut self,
+        index: usize,
+        change_account: &ChangeAccount,
+    ) -> Result<(), ContractError> {
+        let expected = self.model.change_account(change_account);
+        let result = self
+            .try_execute(index, "change_account", change_account, 0)
+            .map(|_| ());
 
-            let s = self.sks[i].sign_multisig(&public_key, &msg);
-            change_account.signature = change_account.signature.aggregate(&[s]);
-        }
+        self.check_model(&result, expected);
+        result
+    }
 
-        let fn_args = rkyv::to_bytes::<_, 128>(&change_account)
-            .expect("Serializing argument should succeed")
-            .to_vec();
+    /// Locks the account with a single key, paying with the Moonlight account
+    /// of the key with the given index.
+    fn try_lock_account(
+        &mut self,
+        index: usize,
+        lock: &LockAccount,
+    ) -> Result<(), ContractError> {
+        let expected = self.model.lock_account(lock);
+        let result =
+            self.try_execute(index, "lock_account", lock, 0).map(|_| ());
 
-        let tx = Transaction::moonlight(
-            &sk,
-            None,
-            0,
-            0,
-            GAS_LIMIT,
-            GAS_PRICE,
-            NONCE,
-            CHAIN_ID,
-            Some(ContractCall {
-                contract: CONTRACT_ID,
-                fn_name: String::from("change_account"),
-                fn_args,
-            }),
-        )
-        .unwrap();
+        self.check_model(&result, expected);
+        result
+    }
 
-        let receipt = self
-            .session
-            .call::<_, Result<Vec<u8>, ContractError>>(
-                TRANSFER_CONTRACT,
-                "spend_and_execute",
-                &tx,
-                GAS_LIMIT,
-            )
-            .expect("Executing transaction should succeed");
+    /// Places the account on hold with its compliance key, paying with the
+    /// Moonlight account of the key with the given index.
+    fn try_place_hold(
+        &mut self,
+        index: usize,
+        hold: &PlaceHold,
+    ) -> Result<(), ContractError> {
+        let expected = self.model.place_hold(hold);
+        let result = self.try_execute(index, "place_hold", hold, 0).map(|_| ());
 
-        println!("{:?}", receipt.data);
+        self.check_model(&result, expected);
+        result
+    }
 
-        let _refund_receipt = self
-            .session
-            .call::<_, ()>(
-                TRANSFER_CONTRACT,
-                "refund",
-                &receipt.gas_spent,
-                u64::MAX,
-            )
-            .expect("Refunding must succeed");
+    /// Attests the liveness of a key of an account, paying with the Moonlight
+    /// account of the key with the given index.
+    fn try_attest_liveness(
+        &mut self,
+        index: usize,
+        attest: &AttestLiveness,
+    ) -> Result<(), ContractError> {
+        let expected = self.model.attest_liveness(attest);
+        let result = self
+            .try_execute(index, "attest_liveness", attest, 0)
+            .map(|_| ());
+
+        self.check_model(&result, expected);
+        result
     }
 
-    fn account(&mut self) -> AccountData {
-        let account_id = self
-            .account_id
-            .expect("must call `create_account` before `account`");
-        self.call(CONTRACT_ID, "account", &account_id)
-            .expect("Querying an account should succeed")
-            .data
+    /// Registers the delegation of a key of an account, paying with the
+    /// Moonlight account of the key with the given index.
+    fn try_delegate_key(
+        &mut self,
+        index: usize,
+        delegate: &DelegateKey,
+    ) -> Result<(), ContractError> {
+        let expected = self.model.delegate_key(delegate);
+        let result = self
+            .try_execute(index, "delegate_key", delegate, 0)
+            .map(|_| ());
+
+        self.check_model(&result, expected);
+        result
     }
 
-    fn balance(&mut self, key: PublicKey) -> u64 {
-        self.call::<_, MoonlightAccountData>(TRANSFER_CONTRACT, "account", &key)
-            .expect("Querying an account should succeed")
-            .data
-            .balance
+    /// Acts on an account as one of its guardians, paying with the Moonlight
+    /// account of the key with the given index.
+    fn try_guard_account(
+        &mut self,
+        index: usize,
+        guard: &GuardAccount,
+    ) -> Result<(), ContractError> {
+        let expected = self.model.guard_account(guard);
+        let result = self
+            .try_execute(index, "guard_account", guard, 0)
+            .map(|_| ());
+
+        self.check_model(&result, expected);
+        result
     }
 
-    fn account_keys(&mut self) -> Vec<PublicKey> {
+    /// Builds a recovery of the session's account to the given keys and
+    /// threshold, for its next recovery nonce, signed by the given guardians.
+    fn signed_start_recovery(
+        &mut self,
+        guardian_sks: &[&SecretKey],
+        keys: Vec<PublicKey>,
+        threshold: u32,
+    ) -> StartRecovery {
         let account_id = self
             .account_id
-            .expect("must call `create_account` before `account_keys`");
+            .expect("must call `create_account` before `start_recovery`");
 
-        self.feeder_query("account_keys", &account_id)
-            .expect("Feeding account keys should succeed")
+        let mut start_recovery = StartRecovery {
+            account_id,
+            guardians: Vec::new(),
+            signature: MultisigSignature::default(),
+            keys,
+            threshold,
+            nonce: self.account().recovery_nonce + 1,
+        };
+        let partials: Vec<_> = guardian_sks
+            .iter()
+            .map(|sk| start_recovery.sign(sk))
+            .collect();
+        start_recovery.aggregate(&partials);
+
+        start_recovery
+    }
+
+    /// Starts recovering the account with its guardians, paying with the
+    /// Moonlight account of the key with the given index.
+    fn try_start_recovery(
+        &mut self,
+        index: usize,
+        start: &StartRecovery,
+    ) -> Result<(), ContractError> {
+        let expected = self.model.start_recovery(start);
+        let result = self
+            .try_execute(index, "start_recovery", start, 0)
+            .map(|_| ());
+
+        self.check_model(&result, expected);
+        result
+    }
+
+    /// Cancels the recovery of the account with a single key, paying with
+    /// the Moonlight account of the key with the given index.
+    fn try_cancel_recovery(
+        &mut self,
+        index: usize,
+        cancel: &CancelRecovery,
+    ) -> Result<(), ContractError> {
+        let expected = self.model.cancel_recovery(cancel);
+        let result = self
+            .try_execute(index, "cancel_recovery", cancel, 0)
+            .map(|_| ());
+
+        self.check_model(&result, expected);
+        result
+    }
+
+    /// Finalizes the recovery of the account, paying with the Moonlight
+    /// account of the key with the given index.
+    fn try_finalize_recovery(
+        &mut self,
+        index: usize,
+        finalize: &FinalizeRecovery,
+    ) -> Result<(), ContractError> {
+        let expected = self.model.finalize_recovery(finalize);
+        let result = self
+            .try_execute(index, "finalize_recovery", finalize, 0)
+            .map(|_| ());
+
+        self.check_model(&result, expected);
+        result
+    }
+
+    /// Claims the account with the fallback key of its inactivity policy,
+    /// paying with the Moonlight account of the key with the given index.
+    fn try_claim_inactive(
+        &mut self,
+        index: usize,
+        claim: &ClaimInactive,
+    ) -> Result<(), ContractError> {
+        let expected = self.model.claim_inactive(claim);
+        let result = self
+            .try_execute(index, "claim_inactive", claim, 0)
+            .map(|_| ());
+
+        self.check_model(&result, expected);
+        result
+    }
+
+    /// Queues a transfer, paying with the Moonlight account of the key with
+    /// the given index.
+    fn try_queue_transfer(
+        &mut self,
+        index: usize,
+        transfer: &Transfer,
+    ) -> Result<(), ContractError> {
+        let expected = self.model.queue_transfer(transfer);
+        let result = self
+            .try_execute(index, "queue_transfer", transfer, 0)
+            .map(|_| ());
+
+        self.check_model(&result, expected);
+        result
+    }
+
+    /// Cancels a queued transfer with a single key, paying with the
+    /// Moonlight account of the key with the given index.
+    fn try_cancel_queued(
+        &mut self,
+        index: usize,
+        cancel: &CancelQueued,
+    ) -> Result<(), ContractError> {
+        let expected = self.model.cancel_queued(cancel);
+        let result = self
+            .try_execute(index, "cancel_queued", cancel, 0)
+            .map(|_| ());
+
+        self.check_model(&result, expected);
+        result
+    }
+
+    /// Executes a queued transfer, paying with the Moonlight account of the
+    /// key with the given index.
+    fn try_execute_queued(
+        &mut self,
+        index: usize,
+        execute: &ExecuteQueued,
+    ) -> Result<(), ContractError> {
+        let expected = self.model.execute_queued(execute);
+        let result = self
+            .try_execute(index, "execute_queued", execute, 0)
+            .map(|_| ());
+
+        self.check_model(&result, expected);
+        result
+    }
+
+    /// Transfers out of the allowance of a key, paying with the Moonlight
+    /// account of the key with the given index.
+    fn try_transfer_with_allowance(
+        &mut self,
+        index: usize,
+        transfer: &AllowanceTransfer,
+    ) -> Result<(), ContractError> {
+        let expected = self.model.transfer_with_allowance(transfer);
+        let result = self
+            .try_execute(index, "transfer_with_allowance", transfer, 0)
+            .map(|_| ());
+
+        self.check_model(&result, expected);
+        result
+    }
+
+    /// Builds a merge of the account with the given ID into the session's
+    /// account, for the next nonces of both, signed by the keys at the given
+    /// indices on each side.
+    fn signed_merge_accounts(
+        &mut self,
+        signers: &[usize],
+        merged_id: u64,
+        merged_signers: &[usize],
+    ) -> MergeAccounts {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `merge_accounts`");
+        let merged: AccountData = self
+            .call(CONTRACT_ID, "account", &merged_id)
+            .expect("Querying an account should succeed")
+            .data;
+
+        let mut merge = MergeAccounts {
+            account_id,
+            keys: Vec::new(),
+            signature: MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
+            nonce: self.account().nonce + 1,
+            merged_id,
+            merged_keys: Vec::new(),
+            merged_signature: MultisigSignature::default(),
+            merged_cosignatures: Vec::new(),
+            merged_group_signature: None,
+            merged_nonce: merged.nonce + 1,
+        };
+
+        let msg = merge.signature_msg();
+        (merge.keys, merge.signature) = self.sign(signers, &msg);
+        (merge.merged_keys, merge.merged_signature) =
+            self.sign(merged_signers, &msg);
+
+        merge
+    }
+
+    fn try_merge_accounts(
+        &mut self,
+        index: usize,
+        merge: &MergeAccounts,
+    ) -> Result<(), ContractError> {
+        let expected = self.model.merge_accounts(merge);
+        let result = self
+            .try_execute(index, "merge_accounts", merge, 0)
+            .map(|_| ());
+
+        self.check_model(&result, expected);
+        result
+    }
+
+    /// Builds a draw of the session's account on the budget the account with
+    /// the given ID allocated to it, signed by the keys at the given indices.
+    fn signed_draw_budget(
+        &mut self,
+        signers: &[usize],
+        parent_id: u64,
+        amount: u64,
+    ) -> DrawBudget {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `draw_budget`");
+
+        let mut draw = DrawBudget {
+            account_id,
+            parent_id,
+            keys: Vec::new(),
+            signature: MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
+            amount,
+            nonce: self.account().nonce + 1,
+        };
+
+        let msg = draw.signature_msg();
+        (draw.keys, draw.signature) = self.sign(signers, &msg);
+
+        draw
+    }
+
+    /// Builds a checkpoint for the next nonce of the account, signed by the
+    /// keys at the given indices.
+    fn signed_checkpoint(&mut self, signers: &[usize]) -> RecordCheckpoint {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `record_checkpoint`");
+
+        let mut checkpoint = RecordCheckpoint {
+            account_id,
+            keys: Vec::new(),
+            signature: MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
+            nonce: self.account().nonce + 1,
+        };
+
+        let msg = checkpoint.signature_msg();
+        (checkpoint.keys, checkpoint.signature) = self.sign(signers, &msg);
+
+        checkpoint
+    }
+
+    fn try_record_checkpoint(
+        &mut self,
+        index: usize,
+        checkpoint: &RecordCheckpoint,
+    ) -> Result<(), ContractError> {
+        let expected = self.model.record_checkpoint(checkpoint);
+        let result = self
+            .try_execute(index, "record_checkpoint", checkpoint, 0)
+            .map(|_| ());
+
+        self.check_model(&result, expected);
+        result
+    }
+
+    /// Builds a configuration for the next nonce of the governance account,
+    /// signed by the keys at the given indices.
+    fn signed_configure(
+        &mut self,
+        signers: &[usize],
+        config: ContractConfig,
+    ) -> Configure {
+        let mut configure = Configure {
+            config,
+            keys: Vec::new(),
+            signature: MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
+            nonce: self.account().nonce + 1,
+        };
+
+        let msg = configure.signature_msg();
+        (configure.keys, configure.signature) = self.sign(signers, &msg);
+
+        configure
+    }
+
+    fn try_configure(
+        &mut self,
+        index: usize,
+        configure: &Configure,
+    ) -> Result<(), ContractError> {
+        let expected = self.model.configure(configure);
+        let result = self
+            .try_execute(index, "configure", configure, 0)
+            .map(|_| ());
+
+        self.check_model(&result, expected);
+        result
+    }
+
+    fn try_draw_budget(
+        &mut self,
+        index: usize,
+        draw: &DrawBudget,
+    ) -> Result<(), ContractError> {
+        let expected = self.model.draw_budget(draw);
+        let result =
+            self.try_execute(index, "draw_budget", draw, 0).map(|_| ());
+
+        self.check_model(&result, expected);
+        result
+    }
+
+    /// Signs a payroll from the account, paying each row's amount to the key
+    /// with its index.
+    fn signed_payroll(
+        &mut self,
+        signers: &[usize],
+        rows: &[(usize, u64)],
+    ) -> Payroll {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `payroll`");
+
+        let mut payroll = Payroll {
+            account_id,
+            keys: Vec::new(),
+            signature: MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
+            rows: rows
+                .iter()
+                .enumerate()
+                .map(|(row, &(index, amount))| PayrollRow {
+                    receiver: self.pks[index],
+                    amount,
+                    memo: format!("row {row}"),
+                })
+                .collect(),
+            nonce: self.account().nonce + 1,
+            gas_refund: None,
+            chain_id: None,
+        };
+
+        (payroll.keys, payroll.signature) =
+            self.sign(signers, &payroll.signature_msg());
+
+        payroll
+    }
+
+    fn try_payroll(
+        &mut self,
+        index: usize,
+        payroll: &Payroll,
+    ) -> Result<(), ContractError> {
+        let expected = self.model.payroll(payroll);
+        let result = self.try_execute(index, "payroll", payroll, 0).map(|_| ());
+
+        self.check_model(&result, expected);
+        result
+    }
+
+    /// Builds a transfer together with a change for the next nonce of the
+    /// account, signed by the keys at the given indices.
+    fn signed_transfer_and_change(
+        &mut self,
+        signers: &[usize],
+        receiver_index: usize,
+        amount: u64,
+        changes: Vec<AccountChange>,
+    ) -> TransferAndChange {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `transfer_and_change`");
+
+        let mut transfer_and_change = TransferAndChange {
+            account_id,
+            keys: Vec::new(),
+            signature: MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
+            receiver: self.pks[receiver_index],
+            amount,
+            memo: String::from(MEMO),
+            changes,
+            nonce: self.account().nonce + 1,
+            gas_refund: None,
+            chain_id: None,
+        };
+
+        let msg = transfer_and_change.signature_msg();
+        (transfer_and_change.keys, transfer_and_change.signature) =
+            self.sign(signers, &msg);
+
+        transfer_and_change
+    }
+
+    fn try_transfer_and_change(
+        &mut self,
+        index: usize,
+        transfer_and_change: &TransferAndChange,
+    ) -> Result<(), ContractError> {
+        let expected = self.model.transfer_and_change(transfer_and_change);
+        let result = self
+            .try_execute(index, "transfer_and_change", transfer_and_change, 0)
+            .map(|_| ());
+
+        self.check_model(&result, expected);
+        result
+    }
+
+    fn try_certified(
+        &mut self,
+        index: usize,
+        certified: &CertifiedOperation,
+    ) -> Result<(), ContractError> {
+        let expected = self.model.certified(certified);
+        let result = self
+            .try_execute(index, "certified", certified, 0)
+            .map(|_| ());
+
+        self.check_model(&result, expected);
+        result
+    }
+
+    /// Signs a split of the account, giving the new account the keys with the
+    /// given indices.
+    fn signed_split_account(
+        &mut self,
+        signers: &[usize],
+        new_keys: &[usize],
+        threshold: u32,
+        amount: u64,
+    ) -> SplitAccount {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `split_account`");
+
+        let mut split = SplitAccount {
+            account_id,
+            keys: Vec::new(),
+            signature: MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
+            new_keys: new_keys.iter().map(|&i| self.pks[i]).collect(),
+            threshold,
+            description: String::from("split"),
+            amount,
+            nonce: self.account().nonce + 1,
+            gas_refund: None,
+            chain_id: None,
+        };
+
+        (split.keys, split.signature) =
+            self.sign(signers, &split.signature_msg());
+
+        split
+    }
+
+    fn try_split_account(
+        &mut self,
+        index: usize,
+        split: &SplitAccount,
+    ) -> Result<(), ContractError> {
+        let expected = self.model.split_account(split);
+        let result = self
+            .try_execute(index, "split_account", split, 0)
+            .map(|_| ());
+
+        self.check_model(&result, expected);
+        result
+    }
+
+    /// Claims a withdrawal, paying with the Moonlight account of the key with
+    /// the given index.
+    fn try_claim_withdrawal(
+        &mut self,
+        index: usize,
+        claim: &ClaimWithdrawal,
+    ) -> Result<(), ContractError> {
+        let expected = self.model.claim_withdrawal(claim);
+        let result = self
+            .try_execute(index, "claim_withdrawal", claim, 0)
+            .map(|_| ());
+
+        self.check_model(&result, expected);
+        result
+    }
+
+    /// Marks the key with the given index as compromised, signing with the
+    /// key itself.
+    fn try_mark_compromised(
+        &mut self,
+        index: usize,
+        mark: &MarkCompromised,
+    ) -> Result<(), ContractError> {
+        let expected = self.model.mark_compromised(mark);
+        let result = self
+            .try_execute(index, "mark_compromised", mark, 0)
+            .map(|_| ());
+
+        self.check_model(&result, expected);
+        result
+    }
+
+    fn try_mark_cosigner_compromised(
+        &mut self,
+        index: usize,
+        mark: &MarkCosignerCompromised,
+    ) -> Result<(), ContractError> {
+        let expected = self.model.mark_cosigner_compromised(mark);
+        let result = self
+            .try_execute(index, "mark_cosigner_compromised", mark, 0)
+            .map(|_| ());
+
+        self.check_model(&result, expected);
+        result
+    }
+
+    fn try_replace_key(
+        &mut self,
+        index: usize,
+        replace: &ReplaceKey,
+    ) -> Result<(), ContractError> {
+        let expected = self.model.replace_key(replace);
+        let result = self
+            .try_execute(index, "replace_key", replace, 0)
+            .map(|_| ());
+
+        self.check_model(&result, expected);
+        result
+    }
+
+    fn account(&mut self) -> AccountData {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `account`");
+        self.call(CONTRACT_ID, "account", &account_id)
+            .expect("Querying an account should succeed")
+            .data
+    }
+
+    fn pending_recovery(&mut self) -> Option<PendingRecovery> {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `pending_recovery`");
+        self.call(CONTRACT_ID, "pending_recovery", &account_id)
+            .expect("Querying a pending recovery should succeed")
+            .data
+    }
+
+    fn allowances(&mut self) -> Vec<KeyAllowance> {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `allowances`");
+        self.call(CONTRACT_ID, "allowances", &account_id)
+            .expect("Querying allowances should succeed")
+            .data
+    }
+
+    fn queued_transfers(&mut self) -> Vec<QueuedTransfer> {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `queued_transfers`");
+        self.call(CONTRACT_ID, "queued_transfers", &account_id)
+            .expect("Querying queued transfers should succeed")
+            .data
+    }
+
+    fn resolve_alias(&mut self, alias: &str) -> Option<u64> {
+        self.call(CONTRACT_ID, "resolve_alias", &String::from(alias))
+            .expect("Resolving an alias should succeed")
+            .data
+    }
+
+    fn tagged_accounts(&mut self, tag: &str) -> Vec<u64> {
+        self.feeder_query(CONTRACT_ID, "tagged_accounts", &String::from(tag))
+            .expect("Feeding tagged accounts should succeed")
+    }
+
+    fn template(&mut self, template_id: u64) -> Option<AccountTemplate> {
+        self.call(CONTRACT_ID, "template", &template_id)
+            .expect("Querying a template should succeed")
+            .data
+    }
+
+    fn moonlight_account(&mut self, key: PublicKey) -> MoonlightAccountData {
+        self.call(TRANSFER_CONTRACT, "account", &key)
+            .expect("Querying an account should succeed")
+            .data
+    }
+
+    fn balance(&mut self, key: PublicKey) -> u64 {
+        self.moonlight_account(key).balance
+    }
+
+    /// Returns the unspent notes of the Phoenix key with the given index.
+    fn phoenix_notes(&mut self, index: usize) -> Vec<Note> {
+        let sk = self.phoenix_sks[index].clone();
+        let vk = PhoenixViewKey::from(&sk);
+
+        let leaves: Vec<NoteLeaf> = self
+            .feeder_query(TRANSFER_CONTRACT, "leaves_from_height", &0u64)
+            .expect("Feeding the note leaves should succeed");
+        let notes: Vec<Note> = leaves
+            .into_iter()
+            .map(|leaf| leaf.note)
+            .filter(|note| vk.owns(note.stealth_address()))
+            .collect();
+
+        let nullifiers: Vec<BlsScalar> =
+            notes.iter().map(|note| note.gen_nullifier(&sk)).collect();
+        let spent: Vec<BlsScalar> = self
+            .call(TRANSFER_CONTRACT, "existing_nullifiers", &nullifiers)
+            .expect("Querying the nullifiers should succeed")
+            .data;
+
+        notes
+            .into_iter()
+            .zip(nullifiers)
+            .filter(|(_, nullifier)| !spent.contains(nullifier))
+            .map(|(note, _)| note)
+            .collect()
+    }
+
+    /// Returns the unspent notes of the Phoenix key with the given index,
+    /// together with their openings, as inputs to a transaction.
+    fn phoenix_inputs(&mut self, index: usize) -> Vec<(Note, NoteOpening)> {
+        // A transaction spends at most four notes
+        const MAX_INPUTS: usize = 4;
+
+        self.phoenix_notes(index)
+            .into_iter()
+            .take(MAX_INPUTS)
+            .map(|note| {
+                let opening: Option<NoteOpening> = self
+                    .call(TRANSFER_CONTRACT, "opening", note.pos())
+                    .expect("Querying an opening should succeed")
+                    .data;
+                let opening = opening.expect("An unspent note has an opening");
+                (note, opening)
+            })
+            .collect()
+    }
+
+    /// Returns the balance held in the notes of the Phoenix key with the
+    /// given index.
+    fn phoenix_balance(&mut self, index: usize) -> u64 {
+        let vk = PhoenixViewKey::from(&self.phoenix_sks[index]);
+        self.phoenix_notes(index)
+            .iter()
+            .map(|note| note.value(Some(&vk)).expect("Note should be owned"))
+            .sum()
+    }
+
+    fn account_keys(&mut self) -> Vec<PublicKey> {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `account_keys`");
+
+        self.feeder_query(CONTRACT_ID, "account_keys", &account_id)
+            .expect("Feeding account keys should succeed")
+    }
+
+    fn duress_keys(&mut self) -> Vec<PublicKey> {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `duress_keys`");
+
+        self.feeder_query(CONTRACT_ID, "duress_keys", &account_id)
+            .expect("Feeding duress keys should succeed")
+    }
+
+    fn cosigners(&mut self) -> Vec<CosignerKey> {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `cosigners`");
+
+        self.feeder_query(CONTRACT_ID, "cosigners", &account_id)
+            .expect("Feeding co-signers should succeed")
+    }
+
+    fn whitelist(&mut self) -> Vec<WhitelistEntry> {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `whitelist`");
+
+        self.feeder_query(CONTRACT_ID, "whitelist", &account_id)
+            .expect("Feeding the whitelist should succeed")
+    }
+
+    fn guardians(&mut self) -> Vec<PublicKey> {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `guardians`");
+
+        self.feeder_query(CONTRACT_ID, "guardians", &account_id)
+            .expect("Feeding guardians should succeed")
+    }
+
+    fn observers(&mut self) -> Vec<PublicKey> {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `observers`");
+
+        self.feeder_query(CONTRACT_ID, "observers", &account_id)
+            .expect("Feeding observers should succeed")
+    }
+
+    fn key_liveness(&mut self) -> Vec<KeyLiveness> {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `key_liveness`");
+
+        self.feeder_query(CONTRACT_ID, "key_liveness", &account_id)
+            .expect("Feeding key liveness should succeed")
+    }
+
+    fn delegations(&mut self) -> Vec<KeyDelegation> {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `delegations`");
+
+        self.feeder_query(CONTRACT_ID, "delegations", &account_id)
+            .expect("Feeding delegations should succeed")
+    }
+
+    fn pending_keys(&mut self) -> Vec<PendingKey> {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `pending_keys`");
+
+        self.feeder_query(CONTRACT_ID, "pending_keys", &account_id)
+            .expect("Feeding pending keys should succeed")
+    }
+
+    fn key_expiries(&mut self) -> Vec<KeyExpiry> {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `key_expiries`");
+
+        self.feeder_query(CONTRACT_ID, "key_expiries", &account_id)
+            .expect("Feeding key expiries should succeed")
+    }
+
+    fn key_weights(&mut self) -> Vec<KeyWeight> {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `key_weights`");
+
+        self.feeder_query(CONTRACT_ID, "key_weights", &account_id)
+            .expect("Feeding key weights should succeed")
+    }
+
+    fn key_roles(&mut self) -> Vec<RoleAssignment> {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `key_roles`");
+
+        self.feeder_query(CONTRACT_ID, "key_roles", &account_id)
+            .expect("Feeding key roles should succeed")
+    }
+
+    fn transfer_tiers(&mut self) -> Vec<TransferTier> {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `transfer_tiers`");
+
+        self.feeder_query(CONTRACT_ID, "transfer_tiers", &account_id)
+            .expect("Feeding transfer tiers should succeed")
+    }
+
+    fn key_usage(&mut self) -> Vec<KeyUsage> {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `key_usage`");
+
+        self.feeder_query(CONTRACT_ID, "key_usage", &account_id)
+            .expect("Feeding key usage should succeed")
+    }
+
+    fn category_spend(&mut self) -> Vec<CategorySpend> {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `category_spend`");
+
+        self.feeder_query(CONTRACT_ID, "category_spend", &account_id)
+            .expect("Feeding category spending should succeed")
+    }
+
+    fn checkpoints(&mut self) -> Vec<Checkpoint> {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `checkpoints`");
+
+        self.feeder_query(CONTRACT_ID, "checkpoints", &account_id)
+            .expect("Feeding checkpoints should succeed")
+    }
+
+    fn budget_draws(&mut self) -> Vec<BudgetDraw> {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `budget_draws`");
+
+        self.feeder_query(CONTRACT_ID, "budget_draws", &account_id)
+            .expect("Feeding budget draws should succeed")
+    }
+
+    /// Moves the session, and its model, to the given block height.
+    fn set_block_height(&mut self, block_height: u64) {
+        self.session
+            .set_meta(rusk_abi::Metadata::BLOCK_HEIGHT, block_height);
+        self.model.block_height = block_height;
+    }
+
+    /// Has the mock license contract attest the given key holds a license, or
+    /// stop attesting it, along with the model.
+    fn set_licensed(&mut self, key: PublicKey, licensed: bool) {
+        let fn_name = if licensed {
+            "issue_license"
+        } else {
+            "revoke_license"
+        };
+        self.call::<_, ()>(LICENSE_CONTRACT, fn_name, &key)
+            .expect("Licensing a key should succeed");
+
+        let license = (LICENSE_CONTRACT.to_bytes(), Key(key));
+        if licensed {
+            self.model.licenses.insert(license);
+        } else {
+            self.model.licenses.remove(&license);
+        }
+    }
+
+    fn key_accounts(&mut self, key: PublicKey) -> Vec<u64> {
+        self.feeder_query(CONTRACT_ID, "key_accounts", &key)
+            .expect("Feeding key accounts should succeed")
+    }
+
+    fn movements(&mut self, from_height: u64, to_height: u64) -> Vec<Movement> {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `movements`");
+
+        let query = MovementQuery {
+            account_id,
+            from_height,
+            to_height,
+        };
+        self.feeder_query(CONTRACT_ID, "movements", &query)
+            .expect("Feeding movements should succeed")
+    }
+
+    fn large_transfers(
+        &mut self,
+        min_amount: u64,
+        from_height: u64,
+        to_height: u64,
+    ) -> Vec<LargeTransfer> {
+        let query = LargeTransferQuery {
+            min_amount,
+            from_height,
+            to_height,
+        };
+        self.feeder_query(CONTRACT_ID, "large_transfers", &query)
+            .expect("Feeding large transfers should succeed")
+    }
+
+    fn operations(&mut self) -> Vec<OperationRecord> {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `operations`");
+
+        self.call(CONTRACT_ID, "operations", &account_id)
+            .expect("Querying operations should succeed")
+            .data
+    }
+
+    fn state_diff(
+        &mut self,
+        from_nonce: u64,
+        to_nonce: u64,
+    ) -> Option<StateDiff> {
+        let account_id = self
+            .account_id
+            .expect("must call `create_account` before `state_diff`");
+
+        let query = StateDiffQuery {
+            account_id,
+            from_nonce,
+            to_nonce,
+        };
+        self.call(CONTRACT_ID, "state_diff", &query)
+            .expect("Querying the state diff should succeed")
+            .data
+    }
+
+    fn simulate_change(
+        &mut self,
+        change_account: &ChangeAccount,
+    ) -> ChangeSimulation {
+        self.call(CONTRACT_ID, "simulate_change", change_account)
+            .expect("Simulating the change should succeed")
+            .data
+    }
+
+    fn stats(&mut self) -> UsageStats {
+        self.call(CONTRACT_ID, "stats", &())
+            .expect("Querying the usage should succeed")
+            .data
+    }
+
+    fn compromised_keys(&mut self) -> Vec<PublicKey> {
+        self.feeder_query(CONTRACT_ID, "compromised_keys", &())
+            .expect("Feeding compromised keys should succeed")
+    }
+
+    fn compromised_cosigners(&mut self) -> Vec<CosignerKey> {
+        self.feeder_query(CONTRACT_ID, "compromised_cosigners", &())
+            .expect("Feeding compromised co-signers should succeed")
+    }
+
+    fn withdrawal(&mut self, claim_id: u64) -> Option<PendingWithdrawal> {
+        self.call(CONTRACT_ID, "withdrawal", &claim_id)
+            .expect("Querying a withdrawal should succeed")
+            .data
+    }
+
+    fn withdrawal_count(&mut self) -> u64 {
+        self.call(CONTRACT_ID, "withdrawal_count", &())
+            .expect("Querying the withdrawal count should succeed")
+            .data
+    }
+
+    fn withdrawals(&mut self, receiver: PublicKey) -> Vec<PendingWithdrawal> {
+        self.feeder_query(CONTRACT_ID, "withdrawals", &receiver)
+            .expect("Feeding withdrawals should succeed")
+    }
+
+    /// Migrates the contract to the given bytecode, exporting the state of
+    /// the current contract and restoring it into the new one through its
+    /// `migrate` function.
+    fn migrate(mut self, bytecode: &[u8]) -> Self {
+        let account_id = self.account_id;
+
+        // Accounts are numbered from one, and missing ones have no threshold
+        let mut accounts = Vec::new();
+        for id in 1.. {
+            self.account_id = Some(id);
+
+            let data = self.account();
+            if data.threshold == 0 {
+                break;
+            }
+            accounts.push(MigratedAccount {
+                account_id: id,
+                keys: self.account_keys(),
+                duress_keys: self.duress_keys(),
+                cosigners: self.cosigners(),
+                whitelist: self.whitelist(),
+                guardians: self.guardians(),
+                observers: self.observers(),
+                budget_draws: self.budget_draws(),
+                liveness: self.key_liveness(),
+                delegations: self.delegations(),
+                pending_keys: self.pending_keys(),
+                key_expiries: self.key_expiries(),
+                key_weights: self.key_weights(),
+                key_roles: self.key_roles(),
+                pending_recovery: self.pending_recovery(),
+                queued_transfers: self.queued_transfers(),
+                allowances: self.allowances(),
+                transfer_tiers: self.transfer_tiers(),
+                key_usage: self.key_usage(),
+                checkpoints: self.checkpoints(),
+                movements: self.movements(0, u64::MAX),
+                operations: self.operations(),
+                data,
+            });
+        }
+        self.account_id = account_id;
+
+        let event_sequence = self
+            .call(CONTRACT_ID, "event_sequence", &())
+            .expect("Querying the event sequence should succeed")
+            .data;
+
+        let custody_cap = self
+            .call(CONTRACT_ID, "custody_cap", &())
+            .expect("Querying the custody cap should succeed")
+            .data;
+        let min_deposit = self
+            .call(CONTRACT_ID, "min_deposit", &())
+            .expect("Querying the minimum deposit should succeed")
+            .data;
+        let custody: CustodyReport = self
+            .call(CONTRACT_ID, "custody", &())
+            .expect("Querying the custody should succeed")
+            .data;
+        let governance = self
+            .call(CONTRACT_ID, "governance", &())
+            .expect("Querying the governance account should succeed")
+            .data;
+        let config = self
+            .call(CONTRACT_ID, "config", &())
+            .expect("Querying the configuration should succeed")
+            .data;
+
+        // Templates are numbered from one too
+        let mut templates = Vec::new();
+        for id in 1.. {
+            let Some(template) = self.template(id) else {
+                break;
+            };
+            templates.push(template);
+        }
+
+        // Claimed withdrawals leave gaps in the claim IDs
+        let withdrawal_count = self.withdrawal_count();
+        let withdrawals = (1..=withdrawal_count)
+            .filter_map(|claim_id| self.withdrawal(claim_id))
+            .collect();
+
+        let migration = Migration {
+            accounts,
+            event_sequence,
+            compromised_keys: self.compromised_keys(),
+            compromised_cosigners: self.compromised_cosigners(),
+            custody_cap,
+            min_deposit,
+            templates,
+            withdrawals,
+            withdrawal_count,
+            storage_fees: custody.storage_fees,
+            governance,
+            config,
+        };
+        self.session = self
+            .session
+            .migrate(
+                CONTRACT_ID,
+                bytecode,
+                ContractData::builder().owner(CONTRACT_OWNER),
+                u64::MAX,
+                |contract, session| {
+                    session.call::<_, ()>(
+                        contract,
+                        "migrate",
+                        &migration,
+                        u64::MAX,
+                    )?;
+                    Ok(())
+                },
+            )
+            .expect("Migrating the contract should succeed");
+
+        self
+    }
+
+    /// Checks the outcome of an operation against the one the model
+    /// predicted, and then the whole state of the contract against the
+    /// model's.
+    #[track_caller]
+    fn check_model<T: PartialEq + std::fmt::Debug>(
+        &mut self,
+        result: &Result<T, ContractError>,
+        expected: Result<T, Error>,
+    ) {
+        let expected =
+            expected.map_err(|err| ContractError::Panic(err.to_string()));
+        assert_eq!(
+            result, &expected,
+            "The outcome of the operation should match the model's"
+        );
+
+        let account_id = self.account_id;
+
+        let accounts: Vec<_> = self
+            .model
+            .accounts
+            .iter()
+            .map(|(id, account)| (*id, account.clone()))
+            .collect();
+        for (id, expected) in accounts {
+            self.account_id = Some(id);
+
+            let account = self.account();
+            assert_eq!(
+                (
+                    account.balance,
+                    account.threshold,
+                    account.nonce,
+                    &account.description,
+                    account.private_events,
+                    account.frozen,
+                    account.group_key,
+                    account.whitelist_enforced,
+                    account.velocity_limit,
+                    account.locked,
+                    &account.alias,
+                    account.merged_into
+                ),
+                (
+                    expected.balance,
+                    expected.threshold,
+                    expected.nonce,
+                    &expected.description,
+                    expected.private_events,
+                    expected.frozen,
+                    expected.group_key,
+                    expected.whitelist_enforced,
+                    expected.velocity_limit,
+                    expected.locked,
+                    &expected.alias,
+                    expected.merged_into
+                ),
+                "Balance, threshold, nonce, description, privacy, freeze, \
+                 group key, whitelist enforcement, velocity limit, lock, \
+                 alias and merge of account {id} should match the model's"
+            );
+            assert_eq!(
+                (
+                    account.chain_id,
+                    account.balance_cap,
+                    &account.tags,
+                    account.liveness_policy,
+                    account.supermajority,
+                    account.key_addition_policy,
+                    account.transfer_window,
+                    &account.categories,
+                    &account.child_budgets,
+                    account.archived,
+                    account.claimable_withdrawals,
+                    account.history_retention
+                ),
+                (
+                    expected.chain_id,
+                    expected.balance_cap,
+                    &expected.tags,
+                    expected.liveness_policy,
+                    expected.supermajority,
+                    expected.key_addition_policy,
+                    expected.transfer_window,
+                    &expected.categories,
+                    &expected.child_budgets,
+                    expected.archived,
+                    expected.claimable_withdrawals,
+                    expected.history_retention
+                ),
+                "The chain, balance cap, tags, liveness policy, supermajority, \
+                 key addition policy, transfer window, spending categories, \
+                 child budgets, archival, claimable withdrawals and history \
+                 retention of account {id} should match the model's"
+            );
+            assert_eq!(
+                (&account.compliance, account.on_hold, account.license_policy),
+                (
+                    &expected.compliance,
+                    expected.on_hold,
+                    expected.license_policy
+                ),
+                "The compliance control, hold and license policy of account \
+                 {id} should match the model's"
+            );
+            let operation_thresholds: BTreeMap<OperationKind, u32> = account
+                .operation_thresholds
+                .iter()
+                .map(|t| (t.op, t.threshold))
+                .collect();
+            assert_eq!(
+                operation_thresholds, expected.operation_thresholds,
+                "Operation thresholds of account {id} should match the model's"
+            );
+            assert_eq!(
+                (
+                    account.recovery_policy,
+                    self.pending_recovery(),
+                    account.recovery_nonce
+                ),
+                (
+                    expected.recovery_policy,
+                    expected.pending_recovery.clone(),
+                    expected.recovery_nonce
+                ),
+                "The recovery policy, pending recovery and recovery nonce of \
+                 account {id} should match the model's"
+            );
+            assert_eq!(
+                (account.inactivity_policy.clone(), account.last_active),
+                (expected.inactivity_policy.clone(), expected.last_active),
+                "The inactivity policy and last activity of account {id} \
+                 should match the model's"
+            );
+            let queued: Vec<_> =
+                expected.queued_transfers.values().cloned().collect();
+            assert_eq!(
+                (account.queue_policy, self.queued_transfers()),
+                (expected.queue_policy, queued),
+                "The queue policy and queued transfers of account {id} should \
+                 match the model's"
+            );
+            let allowances: Vec<_> = expected
+                .allowances
+                .iter()
+                .map(|(key, &amount)| KeyAllowance { key: key.0, amount })
+                .collect();
+            assert_eq!(
+                self.allowances(),
+                allowances,
+                "The allowances of account {id} should match the model's"
+            );
+            for tag in &expected.tags {
+                assert_eq!(
+                    self.tagged_accounts(tag),
+                    self.model.tagged_accounts(tag),
+                    "The accounts tagged {tag} should match the model's"
+                );
+            }
+            if let Some(alias) = &expected.alias {
+                assert_eq!(
+                    self.resolve_alias(alias),
+                    Some(id),
+                    "The alias of account {id} should resolve to it"
+                );
+            }
+
+            let keys: BTreeSet<Key> =
+                self.account_keys().into_iter().map(Key).collect();
+            assert_eq!(
+                keys, expected.keys,
+                "Keys of account {id} should match the model's"
+            );
+
+            let keys: BTreeSet<Key> =
+                self.duress_keys().into_iter().map(Key).collect();
+            assert_eq!(
+                keys, expected.duress_keys,
+                "Duress keys of account {id} should match the model's"
+            );
+
+            let cosigners: BTreeSet<CosignerKey> =
+                self.cosigners().into_iter().collect();
+            assert_eq!(
+                cosigners, expected.cosigners,
+                "Co-signers of account {id} should match the model's"
+            );
+
+            let keys: BTreeSet<Key> =
+                self.guardians().into_iter().map(Key).collect();
+            assert_eq!(
+                keys, expected.guardians,
+                "Guardians of account {id} should match the model's"
+            );
+
+            let keys: BTreeSet<Key> =
+                self.observers().into_iter().map(Key).collect();
+            assert_eq!(
+                keys, expected.observers,
+                "Observers of account {id} should match the model's"
+            );
+
+            let whitelist: BTreeMap<Key, u64> = self
+                .whitelist()
+                .into_iter()
+                .map(|entry| (Key(entry.receiver), entry.usable_from))
+                .collect();
+            assert_eq!(
+                whitelist, expected.whitelist,
+                "Whitelist of account {id} should match the model's"
+            );
+
+            let liveness: BTreeMap<Key, u64> = self
+                .key_liveness()
+                .into_iter()
+                .map(|l| (Key(l.key), l.attested_at))
+                .collect();
+            assert_eq!(
+                liveness, expected.liveness,
+                "Key liveness of account {id} should match the model's"
+            );
+
+            let delegations: BTreeMap<Key, KeyDelegation> = self
+                .delegations()
+                .into_iter()
+                .map(|kd| (Key(kd.key), kd))
+                .collect();
+            assert_eq!(
+                delegations, expected.delegations,
+                "Delegations of account {id} should match the model's"
+            );
+
+            // Keys past their delay are no longer fed
+            let pending_keys: BTreeMap<Key, u64> = self
+                .pending_keys()
+                .into_iter()
+                .map(|p| (Key(p.key), p.usable_from))
+                .collect();
+            let expected_pending_keys: BTreeMap<Key, u64> = expected
+                .pending_keys
+                .iter()
+                .filter(|(_, &h)| h > self.model.block_height)
+                .map(|(key, &h)| (*key, h))
+                .collect();
+            assert_eq!(
+                pending_keys, expected_pending_keys,
+                "Pending keys of account {id} should match the model's"
+            );
+
+            // Lapsed keys are still fed, until they're removed
+            let key_expiries: BTreeMap<Key, u64> = self
+                .key_expiries()
+                .into_iter()
+                .map(|e| (Key(e.key), e.valid_until))
+                .collect();
+            assert_eq!(
+                key_expiries, expected.key_expiries,
+                "Key expiries of account {id} should match the model's"
+            );
+            let key_weights: BTreeMap<Key, u32> = self
+                .key_weights()
+                .into_iter()
+                .map(|w| (Key(w.key), w.weight))
+                .collect();
+            assert_eq!(
+                key_weights, expected.key_weights,
+                "Key weights of account {id} should match the model's"
+            );
+            let key_roles: BTreeMap<Key, KeyRole> = self
+                .key_roles()
+                .into_iter()
+                .map(|r| (Key(r.key), r.role))
+                .collect();
+            assert_eq!(
+                key_roles, expected.key_roles,
+                "Key roles of account {id} should match the model's"
+            );
+            assert_eq!(
+                self.transfer_tiers(),
+                expected.transfer_tiers,
+                "Transfer tiers of account {id} should match the model's"
+            );
+
+            // Keys that never signed are fed too, with no usage
+            let key_usage: BTreeMap<Key, (u64, u64)> = self
+                .key_usage()
+                .into_iter()
+                .filter_map(|u| {
+                    Some((Key(u.key), (u.operations, u.last_signed?)))
+                })
+                .collect();
+            assert_eq!(
+                key_usage, expected.key_usage,
+                "Key usage of account {id} should match the model's"
+            );
+
+            let checkpoints: Vec<_> = self
+                .checkpoints()
+                .into_iter()
+                .map(|c| (c.balance, c.nonce, c.keys_hash, c.block_height))
+                .collect();
+            assert_eq!(
+                checkpoints, expected.checkpoints,
+                "Checkpoints of account {id} should match the model's"
+            );
+
+            assert_eq!(
+                self.category_spend(),
+                expected.category_spend(self.model.block_height),
+                "Spending of the categories of account {id} should match the \
+                 model's"
+            );
+
+            assert_eq!(
+                self.budget_draws(),
+                expected.budget_draws(self.model.block_height),
+                "Draws on the child budgets of account {id} should match the \
+                 model's"
+            );
+        }
+
+        let key_accounts: Vec<_> = self
+            .model
+            .key_accounts
+            .iter()
+            .map(|(key, ids)| (*key, ids.clone()))
+            .collect();
+        for (key, expected) in key_accounts {
+            let ids: BTreeSet<u64> =
+                self.key_accounts(key.0).into_iter().collect();
+            assert_eq!(
+                ids, expected,
+                "Accounts of a key should match the model's"
+            );
+        }
+
+        let keys: BTreeSet<Key> =
+            self.compromised_keys().into_iter().map(Key).collect();
+        assert_eq!(
+            keys, self.model.compromised_keys,
+            "Compromised keys should match the model's"
+        );
+        let cosigners: BTreeSet<CosignerKey> =
+            self.compromised_cosigners().into_iter().collect();
+        assert_eq!(
+            cosigners, self.model.compromised_cosigners,
+            "Compromised co-signers should match the model's"
+        );
+
+        let templates: Vec<_> = self
+            .model
+            .templates
+            .iter()
+            .map(|(id, template)| (*id, template.clone()))
+            .collect();
+        for (id, expected) in templates {
+            assert_eq!(
+                self.template(id),
+                Some(expected),
+                "Template {id} should match the model's"
+            );
+        }
+
+        assert_eq!(
+            self.withdrawal_count(),
+            self.model.withdrawal_count,
+            "The withdrawal count should match the model's"
+        );
+        for claim_id in 1..=self.model.withdrawal_count {
+            assert_eq!(
+                self.withdrawal(claim_id),
+                self.model.withdrawals.get(&claim_id).cloned(),
+                "Withdrawal {claim_id} should match the model's"
+            );
+        }
+
+        let total_balance: u64 = self
+            .call(CONTRACT_ID, "total_balance", &())
+            .expect("Querying the total balance should succeed")
+            .data;
+        assert_eq!(
+            total_balance,
+            self.model.total_balance(),
+            "The total balance should match the model's"
+        );
+
+        self.account_id = account_id;
+    }
+}
+
+/// Returns the topic and data of each event emitted by the multisig contract
+/// in the given receipt.
+fn contract_events<T>(receipt: &CallReceipt<T>) -> Vec<(String, Vec<u8>)> {
+    receipt
+        .events
+        .iter()
+        .filter(|event| event.source == CONTRACT_ID)
+        .map(|event| (event.topic.clone(), event.data.clone()))
+        .collect()
+}
+
+/// Converts an error returned by the VM into the error a contract call would
+/// return, so that both direct calls and transactions can be checked the same
+/// way.
+fn contract_error(err: PiecrustError) -> ContractError {
+    match err {
+        PiecrustError::Panic(msg) => ContractError::Panic(msg),
+        PiecrustError::OutOfGas => ContractError::OutOfGas,
+        _ => ContractError::Unknown,
+    }
+}
+
+/// Asserts that the result of an operation is a failure with the given error.
+#[track_caller]
+fn expect_failure<T: std::fmt::Debug>(
+    result: Result<T, ContractError>,
+    error: Error,
+) {
+    match result {
+        Err(ContractError::Panic(msg)) => {
+            assert_eq!(msg, error.to_string(), "Should fail with {error:?}")
+        }
+        result => panic!("Expected failure with {error:?}, got {result:?}"),
+    }
+}
+
+#[test]
+fn create_account() {
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    session.create_account();
+
+    let account = session.account();
+    let account_keys = session.account_keys();
+
+    assert_eq!(
+        account_keys.len(),
+        session.pks.len(),
+        "Equal number of keys should be inserted"
+    );
+
+    for account_key in account_keys {
+        let mut contains = false;
+        for pk in &session.pks {
+            if account_key == *pk {
+                contains = true;
+                break;
+            }
+        }
+        assert!(
+            contains,
+            "Account keys should be the ones used in creating it"
+        );
+    }
+
+    let account_id = session.account_id.unwrap();
+
+    for key in session.pks.clone() {
+        let ids = session.key_accounts(key);
+        assert_eq!(
+            ids.len(),
+            1,
+            "The public key should only be used by one account"
+        );
+        assert_eq!(
+            ids[0], account_id,
+            "The ID should be of the created account"
+        );
+    }
+
+    assert_eq!(account.balance, 0, "Balance should be zero");
+    assert_eq!(account.threshold, THRESHOLD, "Threshold should be as set");
+    assert_eq!(
+        account.description, DESCRIPTION,
+        "Description should be as set"
+    );
+}
+
+/// Accounts created from a template share its policy, with only their keys
+/// and alias given on creation.
+#[test]
+fn account_templates() {
+    const GUARDIAN_INDEX: usize = NUM_KEYS - 1;
+    const LIMIT: VelocityLimit = VelocityLimit {
+        amount: 10,
+        window: 100,
+    };
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let guardian = session.pks[GUARDIAN_INDEX];
+    let template = AccountTemplate {
+        threshold: 2,
+        description: String::from(DESCRIPTION),
+        private_events: true,
+        velocity_limit: Some(LIMIT),
+        cosigners: Vec::new(),
+        guardians: vec![guardian],
+    };
+
+    expect_failure(
+        session.try_register_template(&AccountTemplate {
+            velocity_limit: Some(VelocityLimit { window: 0, ..LIMIT }),
+            ..template.clone()
+        }),
+        Error::ZeroVelocityWindow,
+    );
+    expect_failure(
+        session.try_register_template(&AccountTemplate {
+            guardians: vec![guardian, guardian],
+            ..template.clone()
+        }),
+        Error::DuplicateKey,
+    );
+
+    let template_id = session
+        .try_register_template(&template)
+        .expect("Registering a template should succeed");
+    assert_eq!(template_id, 1, "Templates should be numbered from one");
+    let [(topic, data)] = session.events.as_slice() else {
+        panic!("A single event should be emitted");
+    };
+    assert_eq!(topic, "register_template");
+    let event: RegisterTemplateEvent =
+        multisig_wallet::decode_event(data).unwrap();
+    assert_eq!(event.template_id, template_id);
+    assert_eq!(event.template, template);
+
+    expect_failure(
+        session.try_create_account_from_template(&CreateAccountFromTemplate {
+            template_id: template_id + 1,
+            keys: session.pks[..3].to_vec(),
+            alias: None,
+        }),
+        Error::TemplateNotFound,
+    );
+    expect_failure(
+        session.try_create_account_from_template(&CreateAccountFromTemplate {
+            template_id,
+            keys: session.pks[..1].to_vec(),
+            alias: None,
+        }),
+        Error::ThresholdTooLarge,
+    );
+    expect_failure(
+        session.try_create_account_from_template(&CreateAccountFromTemplate {
+            template_id,
+            keys: vec![session.pks[0], guardian],
+            alias: None,
+        }),
+        Error::KeyAlreadyUsed,
+    );
+
+    // Every client vault gets the template's policy, with its own keys
+    let pks = session.pks.clone();
+    for (index, keys) in pks[..6].chunks(3).enumerate() {
+        let account_id = session
+            .try_create_account_from_template(&CreateAccountFromTemplate {
+                template_id,
+                keys: keys.to_vec(),
+                alias: Some(format!("vault-{index}")),
+            })
+            .expect("Creating an account from a template should succeed");
+        let [(topic, data)] = session.events.as_slice() else {
+            panic!("A single event should be emitted");
+        };
+        assert_eq!(topic, "create_account");
+        let event: CreateAccountEvent =
+            multisig_wallet::decode_event(data).unwrap();
+        assert_eq!(event.template_id, Some(template_id));
+
+        session.account_id = Some(account_id);
+        let account = session.account();
+        assert_eq!(account.threshold, template.threshold);
+        assert_eq!(account.description, template.description);
+        assert!(account.private_events);
+        assert_eq!(account.velocity_limit, Some(LIMIT));
+        let account_keys: BTreeSet<Key> =
+            session.account_keys().into_iter().map(Key).collect();
+        let expected: BTreeSet<Key> = keys.iter().copied().map(Key).collect();
+        assert_eq!(account_keys, expected);
+        assert_eq!(session.guardians(), vec![guardian]);
+    }
+}
+
+#[test]
+fn deposit() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new(&mut rng);
+
+    session.create_account();
+    let account = session.account();
+
+    assert_eq!(
+        account.balance, 0,
+        "Account should have zero initial balance"
+    );
+
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+    let account = session.account();
+
+    assert_eq!(
+        account.balance, DEPOSIT_AMOUNT,
+        "Account should have the amount deposited"
+    );
+}
+
+/// A deposit can be attributed with a reference of its depositor, which is
+/// echoed in its event.
+#[test]
+fn depositor_reference() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    session.create_account();
+
+    let reference = "x".repeat(MAX_DEPOSITOR_REFERENCE_LEN + 1);
+    expect_failure(
+        session.try_deposit_referenced(
+            DEPOSITOR_INDEX,
+            DEPOSIT_AMOUNT,
+            &reference,
+        ),
+        Error::DepositorReferenceTooLong,
+    );
+
+    let reference = "x".repeat(MAX_DEPOSITOR_REFERENCE_LEN);
+    session
+        .try_deposit_referenced(DEPOSITOR_INDEX, DEPOSIT_AMOUNT, &reference)
+        .expect("Depositing should succeed");
+
+    let [(topic, data)] = session.events.as_slice() else {
+        panic!("A single deposit event should be emitted");
+    };
+    assert_eq!(topic, "deposit");
+    let event: DepositEvent = multisig_wallet::decode_event(data).unwrap();
+    assert_eq!(
+        event.depositor_reference,
+        Disclosure::Revealed(reference),
+        "The event should echo the reference"
+    );
+
+    // Accounts with private events only show its hash
+    session.change_account(
+        DEPOSITOR_INDEX,
+        vec![AccountChange::SetPrivateEvents {
+            private_events: true,
+        }],
+    );
+    session
+        .try_deposit_referenced(DEPOSITOR_INDEX, DEPOSIT_AMOUNT, "invoice-1")
+        .expect("Depositing should succeed");
+
+    let [(_, data)] = session.events.as_slice() else {
+        panic!("A single deposit event should be emitted");
+    };
+    let event: DepositEvent = multisig_wallet::decode_event(data).unwrap();
+    assert_eq!(
+        event.depositor_reference,
+        Disclosure::Hashed(hash_memo(event.sequence, "invoice-1")),
+    );
+}
+
+/// A transfer can carry a fixed-size reference, signed for along with the rest
+/// of the transfer and echoed in its event.
+#[test]
+fn transfer_reference() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const TRANSFERRER_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const TRANSFER_AMOUNT: u64 = 300;
+    const REFERENCE: [u8; 32] = [0x2A; 32];
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    let signers = session.all_signers();
+    let referenced = |session: &mut ContractSession| {
+        let mut transfer =
+            session.signed_transfer(&signers, RECEIVER_INDEX, TRANSFER_AMOUNT);
+        transfer.reference = Some(REFERENCE);
+        let msg = transfer.signature_msg();
+        (transfer.keys, transfer.signature) = session.sign(&signers, &msg);
+        transfer
+    };
+
+    // The reference is part of the message, so it can't be added to a signed
+    // transfer
+    let mut transfer =
+        session.signed_transfer(&signers, RECEIVER_INDEX, TRANSFER_AMOUNT);
+    transfer.reference = Some(REFERENCE);
+    expect_failure(
+        session.try_transfer(TRANSFERRER_INDEX, &transfer),
+        Error::InvalidSignature,
+    );
+
+    let transfer = referenced(&mut session);
+    let payload = SigningPayload::new(&Operation::Transfer(transfer.clone()))
+        .expect("Building the payload should succeed");
+    assert_eq!(
+        payload.message(),
+        transfer.signature_msg(),
+        "The payload should rebuild the message with the reference"
+    );
+    session
+        .try_transfer(TRANSFERRER_INDEX, &transfer)
+        .expect("Transferring should succeed");
+
+    let [(topic, data)] = session.events.as_slice() else {
+        panic!("A single transfer event should be emitted");
+    };
+    assert_eq!(topic, "transfer");
+    let event: TransferEvent = multisig_wallet::decode_event(data).unwrap();
+    assert_eq!(
+        event.reference,
+        Some(Disclosure::Revealed(REFERENCE)),
+        "The event should echo the reference"
+    );
+
+    // Accounts with private events only show its hash
+    session.change_account(
+        TRANSFERRER_INDEX,
+        vec![AccountChange::SetPrivateEvents {
+            private_events: true,
+        }],
+    );
+    let transfer = referenced(&mut session);
+    session
+        .try_transfer(TRANSFERRER_INDEX, &transfer)
+        .expect("Transferring should succeed");
+
+    let [(_, data)] = session.events.as_slice() else {
+        panic!("A single transfer event should be emitted");
+    };
+    let event: TransferEvent = multisig_wallet::decode_event(data).unwrap();
+    assert_eq!(
+        event.reference,
+        Some(Disclosure::Hashed(hash_reference(
+            event.sequence,
+            &REFERENCE
+        ))),
+    );
+}
+
+/// A transfer can declare a spending category of its account, counting it
+/// against the category's budget for the current window.
+#[test]
+fn spending_categories() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const TRANSFERRER_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const TRANSFER_AMOUNT: u64 = 200;
+    const BUDGET: u64 = 300;
+    const WINDOW: u64 = 100;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    let category = |name: &str, window| SpendingCategory {
+        name: String::from(name),
+        budget: BUDGET,
+        window,
+    };
+    for invalid in [category("Marketing", WINDOW), category("marketing", 0)] {
+        let signers = session.all_signers();
+        let change_account = session.signed_change_account(
+            &signers,
+            vec![AccountChange::SetCategory { category: invalid }],
+        );
+        expect_failure(
+            session.try_change_account(TRANSFERRER_INDEX, &change_account),
+            Error::InvalidCategory,
+        );
+    }
+    session.change_account(
+        TRANSFERRER_INDEX,
+        vec![AccountChange::SetCategory {
+            category: category("marketing", WINDOW),
+        }],
+    );
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.set_categories, vec![category("marketing", WINDOW)]);
+
+    let signers = session.all_signers();
+    let categorized = |session: &mut ContractSession, name: &str| {
+        let mut transfer =
+            session.signed_transfer(&signers, RECEIVER_INDEX, TRANSFER_AMOUNT);
+        transfer.category = Some(String::from(name));
+        let msg = transfer.signature_msg();
+        (transfer.keys, transfer.signature) = session.sign(&signers, &msg);
+        transfer
+    };
+
+    // The category is part of the message, so it can't be added to a signed
+    // transfer
+    let mut transfer =
+        session.signed_transfer(&signers, RECEIVER_INDEX, TRANSFER_AMOUNT);
+    transfer.category = Some(String::from("marketing"));
+    expect_failure(
+        session.try_transfer(TRANSFERRER_INDEX, &transfer),
+        Error::InvalidSignature,
+    );
+
+    let transfer = categorized(&mut session, "travel");
+    expect_failure(
+        session.try_transfer(TRANSFERRER_INDEX, &transfer),
+        Error::UnknownCategory,
+    );
+
+    let transfer = categorized(&mut session, "marketing");
+    let payload = SigningPayload::new(&Operation::Transfer(transfer.clone()))
+        .expect("Building the payload should succeed");
+    assert_eq!(
+        payload.message(),
+        transfer.signature_msg(),
+        "The payload should rebuild the message with the category"
+    );
+    session
+        .try_transfer(TRANSFERRER_INDEX, &transfer)
+        .expect("Transferring within the budget should succeed");
+    let event: TransferEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.category.as_deref(), Some("marketing"));
+    assert_eq!(
+        session.category_spend(),
+        vec![CategorySpend {
+            name: String::from("marketing"),
+            spent: TRANSFER_AMOUNT,
+        }]
+    );
+
+    // Past the budget only transfers under no category go through, until
+    // the next window
+    let transfer = categorized(&mut session, "marketing");
+    expect_failure(
+        session.try_transfer(TRANSFERRER_INDEX, &transfer),
+        Error::CategoryBudgetExceeded,
+    );
+    session.transfer(TRANSFERRER_INDEX, RECEIVER_INDEX, TRANSFER_AMOUNT);
+    session.set_block_height(WINDOW);
+    let transfer = categorized(&mut session, "marketing");
+    session
+        .try_transfer(TRANSFERRER_INDEX, &transfer)
+        .expect("Transferring in the next window should succeed");
+
+    // Accounts have a bounded number of categories, and removed ones can't
+    // be transferred under
+    let changes = (1..MAX_CATEGORIES)
+        .map(|i| AccountChange::SetCategory {
+            category: category(&format!("category-{i}"), WINDOW),
+        })
+        .collect();
+    session.change_account(TRANSFERRER_INDEX, changes);
+    let change_account = session.signed_change_account(
+        &signers,
+        vec![AccountChange::SetCategory {
+            category: category("travel", WINDOW),
+        }],
+    );
+    expect_failure(
+        session.try_change_account(TRANSFERRER_INDEX, &change_account),
+        Error::TooManyCategories,
+    );
+    session.change_account(
+        TRANSFERRER_INDEX,
+        vec![AccountChange::RemoveCategory {
+            name: String::from("marketing"),
+        }],
+    );
+    let transfer = categorized(&mut session, "marketing");
+    expect_failure(
+        session.try_transfer(TRANSFERRER_INDEX, &transfer),
+        Error::UnknownCategory,
+    );
+}
+
+#[test]
+fn transfer() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const TRANSFERRER_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const TRANSFER_AMOUNT: u64 = DEPOSIT_AMOUNT / 2;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new(&mut rng);
+
+    session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    let account = session.account();
+    let balance = session.balance(session.pks[RECEIVER_INDEX]);
+    assert_eq!(
+        account.balance, DEPOSIT_AMOUNT,
+        "Account should have the amount deposited",
+    );
+    assert_eq!(
+        balance, INITIAL_BALANCE,
+        "The receiver account should, at first, just have its initial balance"
+    );
+
+    session.transfer(TRANSFERRER_INDEX, RECEIVER_INDEX, TRANSFER_AMOUNT);
+
+    let account = session.account();
+    let balance = session.balance(session.pks[RECEIVER_INDEX]);
+    assert_eq!(
+        account.balance,
+        DEPOSIT_AMOUNT - TRANSFER_AMOUNT,
+        "Account should have the amount deposited minus the transferred amount"
+    );
+    assert_eq!(
+        balance,
+        INITIAL_BALANCE + TRANSFER_AMOUNT,
+        "The receiver account should, after the transfer, have its initial balance plus the transferred amount"
+    );
+}
+
+/// A transfer of everything sends the balance as of its execution, rather than
+/// of its signing.
+#[test]
+fn transfer_all() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const TRANSFERRER_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new(&mut rng);
+
+    session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    let signers = session.all_signers();
+    let transfer =
+        session.signed_transfer(&signers, RECEIVER_INDEX, TRANSFER_ALL);
+
+    // Deposited between signing and executing the transfer
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    session
+        .try_transfer(TRANSFERRER_INDEX, &transfer)
+        .expect("Transferring should succeed");
+
+    let [(topic, data)] = session.events.as_slice() else {
+        panic!("A single transfer event should be emitted");
+    };
+    assert_eq!(topic, "transfer");
+    let event: TransferEvent = multisig_wallet::decode_event(data).unwrap();
+    assert_eq!(
+        event.amount,
+        2 * DEPOSIT_AMOUNT,
+        "The event should carry the amount actually transferred"
+    );
+    assert_eq!(event.balance_after, 0);
+
+    assert_eq!(session.account().balance, 0, "Account should be emptied");
+    assert_eq!(
+        session.balance(session.pks[RECEIVER_INDEX]),
+        INITIAL_BALANCE + 2 * DEPOSIT_AMOUNT,
+        "The receiver should have both deposits"
+    );
+
+    // An empty account can still be swept, moving nothing
+    session.transfer(TRANSFERRER_INDEX, RECEIVER_INDEX, TRANSFER_ALL);
+    assert_eq!(session.account().balance, 0);
+}
+
+/// A payroll pays every row on a single signature, with an event per row, or
+/// pays none of them.
+#[test]
+fn payroll() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDICES: [usize; 2] = [2, 4];
+    const AMOUNTS: [u64; 2] = [300, 200];
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new(&mut rng);
+
+    session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    let signers = session.all_signers();
+    let payroll = session.signed_payroll(&signers, &[]);
+    expect_failure(
+        session.try_payroll(EXECUTOR_INDEX, &payroll),
+        Error::EmptyPayroll,
+    );
+
+    // The rows are checked against the balance together
+    let rows = [(2, DEPOSIT_AMOUNT / 2), (4, DEPOSIT_AMOUNT / 2 + 1)];
+    let payroll = session.signed_payroll(&signers, &rows);
+    expect_failure(
+        session.try_payroll(EXECUTOR_INDEX, &payroll),
+        Error::InsufficientBalance,
+    );
+    let rows = [(2, u64::MAX), (4, 2)];
+    let payroll = session.signed_payroll(&signers, &rows);
+    expect_failure(
+        session.try_payroll(EXECUTOR_INDEX, &payroll),
+        Error::InsufficientBalance,
+    );
+
+    let rows: Vec<_> = RECEIVER_INDICES.into_iter().zip(AMOUNTS).collect();
+    let payroll = session.signed_payroll(&signers, &rows);
+    session
+        .try_payroll(EXECUTOR_INDEX, &payroll)
+        .expect("Paying the payroll should succeed");
+
+    let events: Vec<PayrollEvent> = session
+        .events
+        .iter()
+        .map(|(topic, data)| {
+            assert_eq!(topic, "payroll");
+            multisig_wallet::decode_event(data).unwrap()
+        })
+        .collect();
+    assert_eq!(events.len(), 2, "An event should be emitted per row");
+    let mut balance = DEPOSIT_AMOUNT;
+    for (row, event) in events.iter().enumerate() {
+        balance -= AMOUNTS[row];
+        assert_eq!(event.row, row as u32);
+        assert_eq!(event.rows, 2);
+        assert_eq!(event.amount, AMOUNTS[row]);
+        assert_eq!(event.balance_after, balance);
+        assert_eq!(event.nonce, payroll.nonce);
+    }
+
+    assert_eq!(session.account().balance, balance);
+    for (index, amount) in RECEIVER_INDICES.into_iter().zip(AMOUNTS) {
+        assert_eq!(
+            session.balance(session.pks[index]),
+            INITIAL_BALANCE + amount,
+            "Each receiver should be paid its row"
+        );
+    }
+}
+
+/// An operation can reimburse its submitter for the gas, from the balance of
+/// the account, once it succeeded.
+#[test]
+fn gas_refund() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const TRANSFERRER_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const SUBMITTER_INDEX: usize = 4;
+    const TRANSFER_AMOUNT: u64 = DEPOSIT_AMOUNT / 2;
+    const REFUND_AMOUNT: u64 = 100;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new(&mut rng);
+
+    session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    let signers = session.all_signers();
+    let submitter = session.pks[SUBMITTER_INDEX];
+    let refunded_transfer =
+        |session: &mut ContractSession, amount: u64, refund: u64| {
+            let mut transfer =
+                session.signed_transfer(&signers, RECEIVER_INDEX, amount);
+            transfer.gas_refund = Some(GasRefund {
+                submitter,
+                amount: refund,
+            });
+            let msg = transfer.signature_msg();
+            (transfer.keys, transfer.signature) = session.sign(&signers, &msg);
+            transfer
+        };
+
+    let transfer =
+        refunded_transfer(&mut session, TRANSFER_AMOUNT, MAX_GAS_REFUND + 1);
+    expect_failure(
+        session.try_transfer(TRANSFERRER_INDEX, &transfer),
+        Error::GasRefundTooLarge,
+    );
+
+    // The refund is paid from the same balance as the transfer
+    let transfer = refunded_transfer(
+        &mut session,
+        DEPOSIT_AMOUNT - REFUND_AMOUNT + 1,
+        REFUND_AMOUNT,
+    );
+    expect_failure(
+        session.try_transfer(TRANSFERRER_INDEX, &transfer),
+        Error::InsufficientBalance,
+    );
+
+    let transfer =
+        refunded_transfer(&mut session, TRANSFER_AMOUNT, REFUND_AMOUNT);
+    let payload = SigningPayload::new(&Operation::Transfer(transfer.clone()))
+        .expect("Building the payload should succeed");
+    assert_eq!(
+        payload.message(),
+        transfer.signature_msg(),
+        "The payload should rebuild the message with the refund"
+    );
+    session
+        .try_transfer(TRANSFERRER_INDEX, &transfer)
+        .expect("Transferring should succeed");
+
+    let topics: Vec<&str> = session
+        .events
+        .iter()
+        .map(|(topic, _)| topic.as_str())
+        .collect();
+    assert_eq!(
+        topics,
+        ["transfer", "gas_refund"],
+        "The refund should follow the transfer"
+    );
+    let event: GasRefundEvent =
+        multisig_wallet::decode_event(&session.events[1].1).unwrap();
+    assert_eq!(event.submitter, Disclosure::Revealed(submitter));
+    assert_eq!(event.amount, REFUND_AMOUNT);
+    assert_eq!(event.nonce, transfer.nonce);
+    assert_eq!(
+        event.balance_after,
+        DEPOSIT_AMOUNT - TRANSFER_AMOUNT - REFUND_AMOUNT
+    );
+    assert_eq!(
+        session.balance(submitter),
+        INITIAL_BALANCE + REFUND_AMOUNT,
+        "The submitter should be reimbursed"
+    );
+
+    // A transfer of everything leaves the refund behind
+    let transfer = refunded_transfer(&mut session, TRANSFER_ALL, REFUND_AMOUNT);
+    session
+        .try_transfer(TRANSFERRER_INDEX, &transfer)
+        .expect("Transferring should succeed");
+    let event: TransferEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(
+        event.amount,
+        DEPOSIT_AMOUNT - TRANSFER_AMOUNT - 2 * REFUND_AMOUNT
+    );
+    assert_eq!(session.account().balance, 0, "Account should be emptied");
+    assert_eq!(
+        session.balance(submitter),
+        INITIAL_BALANCE + 2 * REFUND_AMOUNT,
+    );
+
+    let transfer = refunded_transfer(&mut session, 0, 1);
+    expect_failure(
+        session.try_transfer(TRANSFERRER_INDEX, &transfer),
+        Error::InsufficientBalance,
+    );
+}
+
+#[test]
+fn phoenix() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const TRANSFERRER_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const TRANSFER_AMOUNT: u64 = DEPOSIT_AMOUNT / 2;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new(&mut rng);
+
+    session.create_account();
+
+    assert_eq!(
+        session.phoenix_balance(DEPOSITOR_INDEX),
+        INITIAL_BALANCE,
+        "The depositor should start with its genesis note"
+    );
+    session
+        .try_deposit_phoenix(DEPOSITOR_INDEX, DEPOSIT_AMOUNT)
+        .expect("Depositing through Phoenix should succeed");
+
+    assert_eq!(
+        session.account().balance,
+        DEPOSIT_AMOUNT,
+        "Account should have the amount deposited"
+    );
+    assert!(
+        session.phoenix_balance(DEPOSITOR_INDEX)
+            <= INITIAL_BALANCE - DEPOSIT_AMOUNT,
+        "The depositor should have paid for the deposit and its gas"
+    );
+
+    let signers = session.all_signers();
+    let transfer =
+        session.signed_transfer(&signers, RECEIVER_INDEX, TRANSFER_AMOUNT);
+    session
+        .try_transfer_phoenix(TRANSFERRER_INDEX, &transfer)
+        .expect("Transferring through Phoenix should succeed");
+
+    assert_eq!(
+        session.account().balance,
+        DEPOSIT_AMOUNT - TRANSFER_AMOUNT,
+        "Account should have the amount deposited minus the transferred amount"
+    );
+    assert_eq!(
+        session.balance(session.pks[RECEIVER_INDEX]),
+        INITIAL_BALANCE + TRANSFER_AMOUNT,
+        "The receiver should get the transferred amount"
+    );
+}
+
+#[test]
+fn change_account() {
+    const CHANGER_INDEX: usize = 1;
+    const REMOVE_INDEX: usize = 4;
+    const NEW_THRESHOLD: u32 = THRESHOLD + 1;
+    const NEW_DESCRIPTION: &str = "new-description";
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let new_sk = SecretKey::random(&mut rng);
+    let new_pk = PublicKey::from(&new_sk);
+
+    session.create_account();
+
+    let account = session.account();
+    assert_eq!(account.balance, 0, "Balance should be zero");
+    assert_eq!(account.threshold, THRESHOLD, "Threshold should be as set");
+    assert_eq!(
+        account.description, DESCRIPTION,
+        "Description should be as set"
+    );
+
+    let mut pks = session.pks.clone();
+    let account_keys = session.account_keys();
+
+    assert_eq!(
+        account_keys.len(),
+        pks.len(),
+        "Equal number of keys should be inserted"
+    );
+
+    for account_key in account_keys {
+        let mut contains = false;
+        for pk in &pks {
+            if account_key == *pk {
+                contains = true;
+                break;
+            }
+        }
+        assert!(
+            contains,
+            "Account keys should be the ones used in creating it"
+        );
+    }
+
+    let account_id = session.account_id.unwrap();
+
+    for key in pks.clone() {
+        let ids = session.key_accounts(key);
+        assert_eq!(
+            ids.len(),
+            1,
+            "The public key should only be used by one account"
+        );
+        assert_eq!(
+            ids[0], account_id,
+            "The ID should be of the created account"
+        );
+    }
+
+    session.change_account(
+        CHANGER_INDEX,
+        vec![
+            AccountChange::SetThreshold {
+                threshold: NEW_THRESHOLD,
+            },
+            AccountChange::RemoveKey {
+                key: session.pks[REMOVE_INDEX],
+            },
+            AccountChange::AddKey { key: new_pk },
+            AccountChange::SetDescription {
+                description: String::from(NEW_DESCRIPTION),
+            },
+        ],
+    );
+
+    let account = session.account();
+    assert_eq!(account.balance, 0, "Balance should be zero");
+    assert_eq!(
+        account.threshold, NEW_THRESHOLD,
+        "Threshold should be as set"
+    );
+    assert_eq!(
+        account.description, NEW_DESCRIPTION,
+        "Description should be as set"
+    );
+
+    let removed_pk = pks.remove(REMOVE_INDEX);
+    pks.push(new_pk);
+    let account_keys = session.account_keys();
+
+    assert_eq!(
+        account_keys.len(),
+        pks.len(),
+        "There should be the same number of keys after change"
+    );
+
+    for account_key in account_keys {
+        let mut contains = false;
+        for pk in &pks {
+            if account_key == *pk {
+                contains = true;
+                break;
+            }
+        }
+        assert!(
+            contains,
+            "Account keys should be the ones used in creating it"
+        );
+    }
+
+    let account_id = session.account_id.unwrap();
+
+    for key in pks.clone() {
+        let ids = session.key_accounts(key);
+        assert_eq!(
+            ids.len(),
+            1,
+            "The public key should only be used by one account"
+        );
+        assert_eq!(
+            ids[0], account_id,
+            "The ID should be of the created account"
+        );
+    }
+
+    assert_eq!(
+        session.key_accounts(removed_pk).len(),
+        0,
+        "The removed key should have no accounts"
+    );
+}
+
+#[test]
+fn create_account_fails() {
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let create_account = |keys: &[PublicKey], threshold| CreateAccount {
+        keys: keys.to_vec(),
+        threshold,
+        description: String::from(DESCRIPTION),
+        alias: None,
+        key_weights: Vec::new(),
+        key_roles: Vec::new(),
+    };
+
+    let pks = session.pks.clone();
+
+    expect_failure(
+        session.try_create_account(&create_account(&[], 1)),
+        Error::NoKeys,
+    );
+    expect_failure(
+        session.try_create_account(&create_account(&pks, 0)),
+        Error::ZeroThreshold,
+    );
+    expect_failure(
+        session.try_create_account(&create_account(&pks, NUM_KEYS as u32 + 1)),
+        Error::ThresholdTooLarge,
+    );
+    expect_failure(
+        session.try_create_account(&create_account(&[pks[0], pks[0]], 1)),
+        Error::DuplicateKey,
+    );
+}
+
+#[test]
+fn transfer_fails() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const TRANSFERRER_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let other_sk = SecretKey::random(&mut rng);
+    let other_pk = PublicKey::from(&other_sk);
+
+    session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    let signers = session.all_signers();
+    let transfer =
+        session.signed_transfer(&signers, RECEIVER_INDEX, DEPOSIT_AMOUNT + 1);
+    expect_failure(
+        session.try_transfer(TRANSFERRER_INDEX, &transfer),
+        Error::InsufficientBalance,
+    );
+
+    let mut transfer =
+        session.signed_transfer(&signers, RECEIVER_INDEX, DEPOSIT_AMOUNT);
+    transfer.nonce += 1;
+    expect_failure(
+        session.try_transfer(TRANSFERRER_INDEX, &transfer),
+        Error::InvalidNonce,
+    );
+
+    let signers: Vec<usize> = (0..THRESHOLD as usize - 1).collect();
+    let transfer =
+        session.signed_transfer(&signers, RECEIVER_INDEX, DEPOSIT_AMOUNT);
+    expect_failure(
+        session.try_transfer(TRANSFERRER_INDEX, &transfer),
+        Error::ThresholdNotMet,
+    );
+
+    let signers: Vec<usize> = (0..THRESHOLD as usize).collect();
+    let mut transfer =
+        session.signed_transfer(&signers, RECEIVER_INDEX, DEPOSIT_AMOUNT);
+    transfer.keys.push(transfer.keys[0]);
+    expect_failure(
+        session.try_transfer(TRANSFERRER_INDEX, &transfer),
+        Error::DuplicateKey,
+    );
+
+    let mut transfer =
+        session.signed_transfer(&signers, RECEIVER_INDEX, DEPOSIT_AMOUNT);
+    transfer.keys.push(other_pk);
+    expect_failure(
+        session.try_transfer(TRANSFERRER_INDEX, &transfer),
+        Error::UnknownKey,
+    );
+
+    let mut transfer =
+        session.signed_transfer(&signers, RECEIVER_INDEX, DEPOSIT_AMOUNT);
+    transfer.amount -= 1;
+    expect_failure(
+        session.try_transfer(TRANSFERRER_INDEX, &transfer),
+        Error::InvalidSignature,
+    );
+
+    let account = session.account();
+    assert_eq!(
+        account.balance, DEPOSIT_AMOUNT,
+        "Failed transfers should leave the balance untouched"
+    );
+    assert_eq!(
+        account.nonce, 0,
+        "Failed transfers should not bump the nonce"
+    );
+}
+
+#[test]
+fn change_account_fails() {
+    const CHANGER_INDEX: usize = 1;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let other_sk = SecretKey::random(&mut rng);
+    let other_pk = PublicKey::from(&other_sk);
+
+    session.create_account();
+
+    let signers = session.all_signers();
+    let pks = session.pks.clone();
+
+    let failures = [
+        (
+            vec![AccountChange::AddKey { key: pks[0] }],
+            Error::KeyAlreadyUsed,
+        ),
+        (
+            vec![AccountChange::RemoveKey { key: other_pk }],
+            Error::KeyNotUsed,
+        ),
+        (
+            vec![AccountChange::SetThreshold { threshold: 0 }],
+            Error::ZeroThreshold,
+        ),
+        (
+            vec![AccountChange::SetThreshold {
+                threshold: NUM_KEYS as u32 + 1,
+            }],
+            Error::ThresholdTooLarge,
+        ),
+        (
+            vec![
+                AccountChange::SetThreshold {
+                    threshold: NUM_KEYS as u32,
+                },
+                AccountChange::RemoveKey { key: pks[0] },
+            ],
+            Error::KeysBelowThreshold,
+        ),
+    ];
+
+    for (changes, error) in failures {
+        let change_account = session.signed_change_account(&signers, changes);
+        expect_failure(
+            session.try_change_account(CHANGER_INDEX, &change_account),
+            error,
+        );
+    }
+
+    let account = session.account();
+    assert_eq!(
+        account.threshold, THRESHOLD,
+        "Threshold should be unchanged"
+    );
+    assert_eq!(account.nonce, 0, "Failed changes should not bump the nonce");
+}
+
+/// A key marked as compromised by its owner is refused as a signer, and can't
+/// be added to any account, until the accounts using it rotate it out.
+#[test]
+fn compromised_key() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const COMPROMISED_INDEX: usize = 0;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let account_id = session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    let compromised_sk = session.sks[COMPROMISED_INDEX].clone();
+    let compromised_pk = session.pks[COMPROMISED_INDEX];
+
+    // Only the key itself can mark it
+    let mut forged = MarkCompromised::new(&session.sks[EXECUTOR_INDEX]);
+    forged.key = compromised_pk;
+    expect_failure(
+        session.try_mark_compromised(EXECUTOR_INDEX, &forged),
+        Error::InvalidSignature,
+    );
+
+    let mark = MarkCompromised::new(&compromised_sk);
+    session
+        .try_mark_compromised(EXECUTOR_INDEX, &mark)
+        .expect("Marking the key should succeed");
+
+    let [(topic, data)] = session.events.as_slice() else {
+        panic!("A single event should be emitted");
+    };
+    assert_eq!(topic, "key_compromised");
+    let event: KeyCompromisedEvent =
+        multisig_wallet::decode_event(data).expect("The event should decode");
+    assert_eq!(event.key, compromised_pk);
+    assert_eq!(
+        event.account_ids,
+        vec![account_id],
+        "The event should list the accounts to rotate the key out of"
+    );
+
+    let compromised: bool = session
+        .call(CONTRACT_ID, "is_compromised", &compromised_pk)
+        .expect("Querying the key should succeed")
+        .data;
+    assert!(compromised, "The key should be marked as compromised");
+
+    expect_failure(
+        session.try_mark_compromised(EXECUTOR_INDEX, &mark),
+        Error::KeyCompromised,
+    );
+
+    // The key is refused as a signer, but the other keys can still operate
+    let signers = session.all_signers();
+    let transfer = session.signed_transfer(&signers, RECEIVER_INDEX, 1);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::KeyCompromised,
+    );
+
+    let signers: Vec<usize> = (1..NUM_KEYS).collect();
+    let change_account = session.signed_change_account(
+        &signers,
+        vec![AccountChange::RemoveKey {
+            key: compromised_pk,
+        }],
+    );
+    session
+        .try_change_account(EXECUTOR_INDEX, &change_account)
+        .expect("Rotating the key out should succeed");
+    for change in [
+        AccountChange::AddKey {
+            key: compromised_pk,
+        },
+        AccountChange::AddDuressKey {
+            key: compromised_pk,
+        },
+    ] {
+        let change_account =
+            session.signed_change_account(&signers, vec![change]);
+        expect_failure(
+            session.try_change_account(EXECUTOR_INDEX, &change_account),
+            Error::KeyCompromised,
+        );
+    }
+    let transfer = session.signed_transfer(&signers, RECEIVER_INDEX, 1);
+    session
+        .try_transfer(EXECUTOR_INDEX, &transfer)
+        .expect("Transferring without the key should succeed");
+
+    expect_failure(
+        session
+            .try_create_account(&CreateAccount {
+                keys: vec![compromised_pk],
+                threshold: 1,
+                description: String::from(DESCRIPTION),
+                alias: None,
+                key_weights: Vec::new(),
+                key_roles: Vec::new(),
+            })
+            .map(|_| ()),
+        Error::KeyCompromised,
+    );
+}
+
+/// A key is replaced across every account using it at once, on its own
+/// signature and a change of each account.
+#[test]
+fn replace_key() {
+    const EXECUTOR_INDEX: usize = 3;
+    const OLD_INDEX: usize = 0;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let old_sk = session.sks[OLD_INDEX].clone();
+    let old_pk = session.pks[OLD_INDEX];
+    let new_pk = PublicKey::from(&SecretKey::random(&mut rng));
+
+    let account_id = session.create_account();
+    let other_id = session
+        .try_create_account(&CreateAccount {
+            keys: session.pks[..3].to_vec(),
+            threshold: 2,
+            description: String::new(),
+            alias: None,
+            key_weights: Vec::new(),
+            key_roles: Vec::new(),
+        })
+        .expect("Creating an account should succeed");
+
+    // The key may already be compromised, and not sign the changes itself
+    let mark = MarkCompromised::new(&old_sk);
+    session
+        .try_mark_compromised(EXECUTOR_INDEX, &mark)
+        .expect("Marking the key should succeed");
+
+    let replacement = ReplaceKey::account_changes(old_pk, new_pk);
+    let signers: Vec<usize> = (1..NUM_KEYS).collect();
+    let change = session.signed_change_account(&signers, replacement.clone());
+    session.account_id = Some(other_id);
+    let other_change =
+        session.signed_change_account(&[1, 2], replacement.clone());
+    let mut extra = replacement;
+    extra.push(AccountChange::SetThreshold { threshold: 1 });
+    let extra_change = session.signed_change_account(&[1, 2], extra);
+
+    let mut forged = ReplaceKey::new(
+        &session.sks[EXECUTOR_INDEX],
+        new_pk,
+        vec![change.clone(), other_change.clone()],
+    );
+    forged.old_key = old_pk;
+    expect_failure(
+        session.try_replace_key(EXECUTOR_INDEX, &forged),
+        Error::InvalidSignature,
+    );
+    for (changes, error) in [
+        (vec![change.clone()], Error::KeyReplacementIncomplete),
+        (
+            vec![change.clone(), change.clone(), other_change.clone()],
+            Error::KeyReplacementIncomplete,
+        ),
+        (
+            vec![change.clone(), extra_change],
+            Error::InvalidKeyReplacement,
+        ),
+    ] {
+        let replace = ReplaceKey::new(&old_sk, new_pk, changes);
+        expect_failure(
+            session.try_replace_key(EXECUTOR_INDEX, &replace),
+            error,
+        );
+    }
+    let replace = ReplaceKey::new(&old_sk, old_pk, Vec::new());
+    expect_failure(
+        session.try_replace_key(EXECUTOR_INDEX, &replace),
+        Error::InvalidKeyReplacement,
+    );
+
+    let replace = ReplaceKey::new(&old_sk, new_pk, vec![change, other_change]);
+    session
+        .try_replace_key(EXECUTOR_INDEX, &replace)
+        .expect("Replacing the key should succeed");
+    let topics: Vec<&str> =
+        session.events.iter().map(|(t, _)| t.as_str()).collect();
+    assert_eq!(topics, ["change_account", "change_account"]);
+
+    assert!(session.key_accounts(old_pk).is_empty());
+    assert_eq!(session.key_accounts(new_pk), vec![account_id, other_id]);
+
+    expect_failure(
+        session.try_replace_key(EXECUTOR_INDEX, &replace),
+        Error::KeyNotUsed,
+    );
+}
+
+/// A coerced signer signing with their duress key freezes the account instead
+/// of effecting the operation, which otherwise appears to succeed.
+#[test]
+fn duress_key() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let duress_sk = SecretKey::random(&mut rng);
+    let duress_pk = PublicKey::from(&duress_sk);
+
+    session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::AddDuressKey { key: duress_pk }],
+    );
+
+    // One of the signers is coerced into signing, and uses the duress key
+    let signers: Vec<usize> = (1..THRESHOLD as usize).collect();
+    let transfer =
+        session.signed_transfer(&signers, RECEIVER_INDEX, DEPOSIT_AMOUNT);
+    let mut operation = Operation::Transfer(transfer);
+    let partials: Vec<PartialSignature> = signers
+        .iter()
+        .map(|&i| &session.sks[i])
+        .chain([&duress_sk])
+        .map(|sk| PartialSignature::sign(sk, &operation))
+        .collect();
+    operation.aggregate(&partials);
+    let Operation::Transfer(transfer) = operation else {
+        unreachable!()
+    };
+
+    session
+        .try_transfer(EXECUTOR_INDEX, &transfer)
+        .expect("A transfer signed under duress should appear to succeed");
+    assert!(session.events.is_empty(), "No event should be emitted");
+
+    let account = session.account();
+    assert!(account.frozen, "The account should be frozen");
+    assert_eq!(account.balance, DEPOSIT_AMOUNT, "No funds should move");
+    assert_eq!(account.nonce, 1, "The nonce should be left untouched");
+
+    // Frozen, the account can only be changed by all of its keys together
+    let transfer = session.signed_transfer(&signers, RECEIVER_INDEX, 1);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::AccountFrozen,
+    );
+    let quorum: Vec<usize> = (0..THRESHOLD as usize).collect();
+    let change_account = session.signed_change_account(
+        &quorum,
+        vec![AccountChange::RemoveDuressKey { key: duress_pk }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::AccountFrozen,
+    );
+
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::RemoveDuressKey { key: duress_pk }],
+    );
+    assert!(!session.account().frozen, "The account should be unfrozen");
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, DEPOSIT_AMOUNT);
+}
+
+/// An Ed25519 co-signer counts towards the threshold alongside the BLS keys,
+/// signing the same message on its own, until it's marked as compromised.
+#[test]
+fn cosigner() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let mut random_ed25519 = || {
+        let mut bytes = [0u8; 32];
+        rng.fill_bytes(&mut bytes);
+        ed25519::SigningKey::from_bytes(&bytes)
+    };
+    let cosigner_sk = random_ed25519();
+    let stranger_sk = random_ed25519();
+    let cosigner = CosignerKey::from(&cosigner_sk);
+
+    let account_id = session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::AddCosigner { key: cosigner }],
+    );
+    assert_eq!(session.cosigners(), vec![cosigner]);
+
+    // One BLS key short of the threshold, made up for by the co-signer
+    let signers: Vec<usize> = (1..THRESHOLD as usize).collect();
+    let transfer = session.signed_transfer(&signers, RECEIVER_INDEX, 1);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::ThresholdNotMet,
+    );
+
+    let mut unknown = transfer.clone();
+    let operation = Operation::Transfer(transfer.clone());
+    unknown
+        .cosignatures
+        .push(Cosignature::sign_ed25519(&stranger_sk, &operation));
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &unknown),
+        Error::UnknownKey,
+    );
+
+    // A co-signature of another transfer is refused
+    let mut other = transfer.clone();
+    other.amount = DEPOSIT_AMOUNT;
+    let mut forged = transfer.clone();
+    forged.cosignatures.push(Cosignature::sign_ed25519(
+        &cosigner_sk,
+        &Operation::Transfer(other),
+    ));
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &forged),
+        Error::InvalidSignature,
+    );
+
+    let mut operation = operation;
+    operation.cosign(Cosignature::sign_ed25519(&cosigner_sk, &operation));
+    let Operation::Transfer(transfer) = operation else {
+        unreachable!()
+    };
+    session
+        .try_transfer(EXECUTOR_INDEX, &transfer)
+        .expect("Transferring with a co-signer should succeed");
+    assert_eq!(session.account().balance, DEPOSIT_AMOUNT - 1);
+
+    let [(_, data)] = session.events.as_slice() else {
+        panic!("A single event should be emitted");
+    };
+    let event: TransferEvent = multisig_wallet::decode_event(data).unwrap();
+    assert_eq!(event.cosigners, vec![cosigner]);
+
+    // Once marked as compromised by its owner, the co-signer is refused, and
+    // can't be added back
+    let mut forged = MarkCosignerCompromised::new_ed25519(&stranger_sk);
+    forged.key = cosigner;
+    expect_failure(
+        session.try_mark_cosigner_compromised(EXECUTOR_INDEX, &forged),
+        Error::InvalidSignature,
+    );
+    let mark = MarkCosignerCompromised::new_ed25519(&cosigner_sk);
+    session
+        .try_mark_cosigner_compromised(EXECUTOR_INDEX, &mark)
+        .expect("Marking the co-signer should succeed");
+    let [(topic, data)] = session.events.as_slice() else {
+        panic!("A single event should be emitted");
+    };
+    assert_eq!(topic, "cosigner_compromised");
+    let event: CosignerCompromisedEvent =
+        multisig_wallet::decode_event(data).unwrap();
+    assert_eq!(event.account_ids, vec![account_id]);
+
+    let transfer = session.signed_transfer(&signers, RECEIVER_INDEX, 1);
+    let mut operation = Operation::Transfer(transfer);
+    operation.cosign(Cosignature::sign_ed25519(&cosigner_sk, &operation));
+    let Operation::Transfer(transfer) = operation else {
+        unreachable!()
+    };
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::KeyCompromised,
+    );
+
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::RemoveCosigner { key: cosigner }],
+    );
+    assert!(
+        session.cosigners().is_empty(),
+        "The co-signer should be gone"
+    );
+    let signers = session.all_signers();
+    let change_account = session.signed_change_account(
+        &signers,
+        vec![AccountChange::AddCosigner { key: cosigner }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::KeyCompromised,
+    );
+}
+
+/// An account with a group key is signed for by a single signature of it, as
+/// combined off-chain by the signers' threshold scheme, in place of its keys.
+#[test]
+fn group_key() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let group_sk = SecretKey::random(&mut rng);
+    let group_pk = PublicKey::from(&group_sk);
+    let other_sk = SecretKey::random(&mut rng);
+
+    session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetGroupKey {
+            key: Some(group_pk),
+        }],
+    );
+    assert_eq!(session.account().group_key, Some(group_pk));
+
+    // The keys themselves no longer sign for the account
+    let signers = session.all_signers();
+    let transfer = session.signed_transfer(&signers, RECEIVER_INDEX, 1);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::GroupSignatureRequired,
+    );
+
+    let mut transfer = session.signed_transfer(&[], RECEIVER_INDEX, 1);
+    let msg = transfer.signature_msg();
+    transfer.group_signature = Some(other_sk.sign(&msg));
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::InvalidSignature,
+    );
+
+    transfer.group_signature = Some(group_sk.sign(&msg));
+    session
+        .try_transfer(EXECUTOR_INDEX, &transfer)
+        .expect("Transferring with the group signature should succeed");
+
+    // The event doesn't show which signers took part
+    let [(_, data)] = session.events.as_slice() else {
+        panic!("A single event should be emitted");
+    };
+    let event: TransferEvent = multisig_wallet::decode_event(data).unwrap();
+    assert!(event.group_signed);
+    assert!(event.keys.is_empty());
+
+    let mut change_account = session.signed_change_account(
+        &[],
+        vec![AccountChange::SetGroupKey { key: None }],
+    );
+    change_account.group_signature =
+        Some(group_sk.sign(&change_account.signature_msg()));
+    session
+        .try_change_account(EXECUTOR_INDEX, &change_account)
+        .expect("Removing the group key should succeed");
+
+    let mut transfer = session.signed_transfer(&[], RECEIVER_INDEX, 1);
+    transfer.group_signature = Some(group_sk.sign(&transfer.signature_msg()));
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::NoGroupKey,
+    );
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, 1);
+}
+
+/// Once an account whitelists a receiver it may only transfer to whitelisted
+/// ones, and each only after the whitelisting delay has passed, until the
+/// supermajority removes the last of them.
+#[test]
+fn whitelist() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const OTHER_RECEIVER_INDEX: usize = 4;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    // Any receiver goes until the first one is whitelisted
+    session.transfer(EXECUTOR_INDEX, OTHER_RECEIVER_INDEX, 1);
+
+    let receiver = session.pks[RECEIVER_INDEX];
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::AddWhitelisted { receiver }],
+    );
+    assert!(session.account().whitelist_enforced);
+    assert_eq!(
+        session.whitelist(),
+        vec![WhitelistEntry {
+            receiver,
+            usable_from: WHITELIST_DELAY,
+        }]
+    );
+
+    let signers = session.all_signers();
+    let transfer = session.signed_transfer(&signers, OTHER_RECEIVER_INDEX, 1);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::ReceiverNotWhitelisted,
+    );
+
+    let transfer = session.signed_transfer(&signers, RECEIVER_INDEX, 1);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::ReceiverNotYetUsable,
+    );
+
+    session.set_block_height(WHITELIST_DELAY);
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, 1);
+
+    // Removals take effect at once, and don't lift the enforcement while
+    // receivers remain
+    let other_receiver = session.pks[OTHER_RECEIVER_INDEX];
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::AddWhitelisted {
+            receiver: other_receiver,
+        }],
+    );
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::RemoveWhitelisted { receiver }],
+    );
+    assert_eq!(session.whitelist().len(), 1, "The receiver should be gone");
+    assert!(session.account().whitelist_enforced);
+
+    let transfer = session.signed_transfer(&signers, RECEIVER_INDEX, 1);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::ReceiverNotWhitelisted,
+    );
+
+    // Removing the last receiver lifts the enforcement, so it takes the
+    // supermajority
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetSupermajority {
+            supermajority: Some(Supermajority {
+                numerator: 3,
+                denominator: 4,
+            }),
+        }],
+    );
+    let threshold_signers: Vec<_> = (0..THRESHOLD as usize).collect();
+    let change_account = session.signed_change_account(
+        &threshold_signers,
+        vec![AccountChange::RemoveWhitelisted {
+            receiver: other_receiver,
+        }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::SupermajorityNotMet,
+    );
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::RemoveWhitelisted {
+            receiver: other_receiver,
+        }],
+    );
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert!(event.whitelist_lifted);
+    assert!(session.whitelist().is_empty());
+    assert!(!session.account().whitelist_enforced);
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, 1);
+}
+
+/// Transfers crossing an account's velocity limit within a window go through,
+/// with a warning emitted after the one crossing it.
+#[test]
+fn velocity_limit() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const LIMIT: VelocityLimit = VelocityLimit {
+        amount: 10,
+        window: 100,
+    };
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    let signers = session.all_signers();
+    let change_account = session.signed_change_account(
+        &signers,
+        vec![AccountChange::SetVelocityLimit {
+            limit: Some(VelocityLimit { window: 0, ..LIMIT }),
+        }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::ZeroVelocityWindow,
+    );
+
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetVelocityLimit { limit: Some(LIMIT) }],
+    );
+    assert_eq!(session.account().velocity_limit, Some(LIMIT));
+
+    // Reaching the limit is fine, going past it isn't
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, LIMIT.amount - 1);
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, 1);
+    assert_eq!(session.events.len(), 1, "No warning should be emitted");
+
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, 1);
+    let [_, (topic, data)] = session.events.as_slice() else {
+        panic!("A transfer and a warning should be emitted");
+    };
+    assert_eq!(topic, "velocity_warning");
+    let event: VelocityWarningEvent =
+        multisig_wallet::decode_event(data).unwrap();
+    assert_eq!(event.limit, LIMIT);
+    assert_eq!(event.window_start, 0);
+    assert_eq!(event.outflow, LIMIT.amount + 1);
+
+    // A single warning is emitted per window
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, 1);
+    assert_eq!(session.events.len(), 1, "No warning should be emitted");
+
+    session.set_block_height(LIMIT.window + 1);
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, LIMIT.amount + 1);
+    let [_, (_, data)] = session.events.as_slice() else {
+        panic!("A transfer and a warning should be emitted");
+    };
+    let event: VelocityWarningEvent =
+        multisig_wallet::decode_event(data).unwrap();
+    assert_eq!(event.window_start, LIMIT.window);
+    assert_eq!(event.outflow, LIMIT.amount + 1);
+
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetVelocityLimit { limit: None }],
+    );
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, LIMIT.amount + 1);
+    assert_eq!(session.events.len(), 1, "No warning should be emitted");
+}
+
+#[test]
+fn balance_cap() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const CAP: u64 = 1_000;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    session.create_account();
+
+    let signers = session.all_signers();
+    let change_account = session.signed_change_account(
+        &signers,
+        vec![AccountChange::SetBalanceCap { cap: Some(CAP) }],
+    );
+    let payload =
+        SigningPayload::new(&Operation::ChangeAccount(change_account.clone()))
+            .expect("Building the payload should succeed");
+    assert_eq!(
+        payload.message(),
+        change_account.signature_msg(),
+        "The payload should rebuild the message with the cap"
+    );
+    session
+        .try_change_account(EXECUTOR_INDEX, &change_account)
+        .expect("Setting the cap should succeed");
+    assert_eq!(session.account().balance_cap, Some(CAP));
+
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.balance_cap, Some(Some(CAP)));
+
+    // Reaching the cap is fine, going past it isn't
+    session.deposit(DEPOSITOR_INDEX, CAP - 1);
+    expect_failure(
+        session.try_deposit(DEPOSITOR_INDEX, 2),
+        Error::BalanceCapExceeded,
+    );
+    session.deposit(DEPOSITOR_INDEX, 1);
+
+    // Sweeping funds out makes room for new deposits
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, CAP / 2);
+    session.deposit(DEPOSITOR_INDEX, CAP / 2);
+
+    // Lowering the cap below the balance leaves the balance as it is
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetBalanceCap { cap: Some(CAP / 2) }],
+    );
+    assert_eq!(session.account().balance, CAP);
+    expect_failure(
+        session.try_deposit(DEPOSITOR_INDEX, 1),
+        Error::BalanceCapExceeded,
+    );
+
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetBalanceCap { cap: None }],
+    );
+    session.deposit(DEPOSITOR_INDEX, CAP);
+    assert_eq!(session.account().balance, 2 * CAP);
+}
+
+/// Accounts carrying the same tag are grouped under it, and can be looked up
+/// by it.
+#[test]
+fn tags() {
+    const EXECUTOR_INDEX: usize = 3;
+    const TAG: &str = "grants";
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let first_id = session.create_account();
+    let second_id = session.create_account();
+    assert!(session.tagged_accounts(TAG).is_empty());
+
+    let add_tag = |tag: &str| AccountChange::AddTag {
+        tag: String::from(tag),
+    };
+
+    let signers = session.all_signers();
+    let change_account =
+        session.signed_change_account(&signers, vec![add_tag(TAG)]);
+    let payload =
+        SigningPayload::new(&Operation::ChangeAccount(change_account.clone()))
+            .expect("Building the payload should succeed");
+    assert_eq!(
+        payload.message(),
+        change_account.signature_msg(),
+        "The payload should rebuild the message with the tag"
+    );
+    session
+        .try_change_account(EXECUTOR_INDEX, &change_account)
+        .expect("Adding a tag should succeed");
+
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.added_tags, [TAG]);
+
+    session.account_id = Some(first_id);
+    session.change_account(EXECUTOR_INDEX, vec![add_tag(TAG)]);
+    assert_eq!(session.tagged_accounts(TAG), [first_id, second_id]);
+    assert_eq!(session.account().tags, [TAG]);
+
+    for (tag, error) in [
+        ("", Error::InvalidTag),
+        ("Grants", Error::InvalidTag),
+        ("a-tag-far-too-long", Error::InvalidTag),
+        (TAG, Error::TagAlreadyAdded),
+    ] {
+        let change_account =
+            session.signed_change_account(&signers, vec![add_tag(tag)]);
+        expect_failure(
+            session.try_change_account(EXECUTOR_INDEX, &change_account),
+            error,
+        );
+    }
+    let change_account = session.signed_change_account(
+        &signers,
+        vec![AccountChange::RemoveTag {
+            tag: String::from("treasury"),
+        }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::TagNotFound,
+    );
+
+    let changes = (0..=MAX_TAGS).map(|i| add_tag(&format!("tag-{i}")));
+    let change_account =
+        session.signed_change_account(&signers, changes.collect());
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::TooManyTags,
+    );
+
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::RemoveTag {
+            tag: String::from(TAG),
+        }],
+    );
+    assert_eq!(session.tagged_accounts(TAG), [second_id]);
+    assert!(session.account().tags.is_empty());
+}
+
+/// Under a liveness policy, keys that haven't attested within the window stop
+/// counting towards the threshold, down to the policy's minimum.
+#[test]
+fn key_liveness() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const TRANSFER_AMOUNT: u64 = 100;
+    const POLICY: LivenessPolicy = LivenessPolicy {
+        window: 100,
+        min_threshold: 4,
+    };
+    const LIVE_SIGNERS: [usize; 4] = [0, 1, 2, 3];
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let account_id = session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    let attest = |session: &ContractSession, index: usize, height: u64| {
+        AttestLiveness::new(&session.sks[index], account_id, height)
+    };
+    expect_failure(
+        session.try_attest_liveness(EXECUTOR_INDEX, &attest(&session, 0, 0)),
+        Error::NoLivenessPolicy,
+    );
+
+    let signers = session.all_signers();
+    for (policy, error) in [
+        (
+            LivenessPolicy {
+                window: 0,
+                ..POLICY
+            },
+            Error::ZeroLivenessWindow,
+        ),
+        (
+            LivenessPolicy {
+                min_threshold: 0,
+                ..POLICY
+            },
+            Error::ZeroThreshold,
+        ),
+    ] {
+        let change_account = session.signed_change_account(
+            &signers,
+            vec![AccountChange::SetLivenessPolicy {
+                policy: Some(policy),
+            }],
+        );
+        expect_failure(
+            session.try_change_account(EXECUTOR_INDEX, &change_account),
+            error,
+        );
+    }
+
+    // Setting the policy makes every key live as of the change
+    session.set_block_height(10);
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetLivenessPolicy {
+            policy: Some(POLICY),
+        }],
+    );
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.liveness_policy, Some(Some(POLICY)));
+    assert!(session.key_liveness().iter().all(|l| l.attested_at == 10));
+
+    session.set_block_height(50);
+    expect_failure(
+        session.try_attest_liveness(EXECUTOR_INDEX, &attest(&session, 0, 51)),
+        Error::AttestationInFuture,
+    );
+    let other_sk = SecretKey::random(&mut rng);
+    expect_failure(
+        session.try_attest_liveness(
+            EXECUTOR_INDEX,
+            &AttestLiveness::new(&other_sk, account_id, 50),
+        ),
+        Error::UnknownKey,
+    );
+    let mut forged = attest(&session, 0, 50);
+    forged.block_height = 40;
+    expect_failure(
+        session.try_attest_liveness(EXECUTOR_INDEX, &forged),
+        Error::InvalidSignature,
+    );
+
+    for index in LIVE_SIGNERS {
+        session
+            .try_attest_liveness(EXECUTOR_INDEX, &attest(&session, index, 50))
+            .expect("Attesting liveness should succeed");
+    }
+    let [(topic, data)] = session.events.as_slice() else {
+        panic!("A single event should be emitted");
+    };
+    assert_eq!(topic, "liveness_attested");
+    let event: LivenessAttestedEvent =
+        multisig_wallet::decode_event(data).unwrap();
+    assert_eq!(event.key, session.pks[LIVE_SIGNERS[3]]);
+    assert_eq!(event.attested_at, 50);
+
+    // An older attestation doesn't set the key back
+    session
+        .try_attest_liveness(EXECUTOR_INDEX, &attest(&session, 0, 10))
+        .expect("Replaying an older attestation should succeed");
+    let event: LivenessAttestedEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.attested_at, 50);
+
+    // Past the window of the keys that didn't attest, the live keys alone
+    // meet the threshold
+    session.set_block_height(100);
+    let transfer =
+        session.signed_transfer(&LIVE_SIGNERS, RECEIVER_INDEX, TRANSFER_AMOUNT);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::ThresholdNotMet,
+    );
+    session.set_block_height(120);
+    session
+        .try_transfer(EXECUTOR_INDEX, &transfer)
+        .expect("Transferring with the live keys should succeed");
+
+    // With every key stale, the threshold never drops below the minimum
+    session.set_block_height(200);
+    let transfer = session.signed_transfer(
+        &LIVE_SIGNERS[..3],
+        RECEIVER_INDEX,
+        TRANSFER_AMOUNT,
+    );
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::ThresholdNotMet,
+    );
+
+    let change_account = session.signed_change_account(
+        &LIVE_SIGNERS,
+        vec![AccountChange::SetLivenessPolicy { policy: None }],
+    );
+    session
+        .try_change_account(EXECUTOR_INDEX, &change_account)
+        .expect("Removing the policy should succeed");
+    assert!(session.key_liveness().is_empty());
+    assert_eq!(session.account().liveness_policy, None);
+}
+
+/// A key can hand its signing power to another key for a range of blocks,
+/// until it revokes the delegation.
+#[test]
+fn supermajority() {
+    const EXECUTOR_INDEX: usize = 3;
+    const SUPERMAJORITY: Supermajority = Supermajority {
+        numerator: 3,
+        denominator: 4,
+    };
+    // Three quarters of the sixteen keys
+    const SUPERMAJORITY_SIGNERS: usize = 12;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new(&mut rng);
+
+    session.create_account();
+
+    let signers = session.all_signers();
+    for supermajority in [
+        Supermajority {
+            numerator: 1,
+            denominator: 2,
+        },
+        Supermajority {
+            numerator: 5,
+            denominator: 4,
+        },
+        Supermajority {
+            numerator: 0,
+            denominator: 0,
+        },
+    ] {
+        let change_account = session.signed_change_account(
+            &signers,
+            vec![AccountChange::SetSupermajority {
+                supermajority: Some(supermajority),
+            }],
+        );
+        expect_failure(
+            session.try_change_account(EXECUTOR_INDEX, &change_account),
+            Error::InvalidSupermajority,
+        );
+    }
+
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetSupermajority {
+            supermajority: Some(SUPERMAJORITY),
+        }],
+    );
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.supermajority, Some(Some(SUPERMAJORITY)));
+    assert_eq!(session.account().supermajority, Some(SUPERMAJORITY));
+
+    // The threshold suffices for other changes, but not for the threshold
+    let threshold_signers: Vec<_> = (0..THRESHOLD as usize).collect();
+    let change_account = session.signed_change_account(
+        &threshold_signers,
+        vec![AccountChange::SetDescription {
+            description: String::from("governed"),
+        }],
+    );
+    session
+        .try_change_account(EXECUTOR_INDEX, &change_account)
+        .expect("Changing the description should succeed");
+    let change_account = session.signed_change_account(
+        &threshold_signers,
+        vec![AccountChange::SetThreshold {
+            threshold: THRESHOLD + 1,
+        }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::SupermajorityNotMet,
+    );
+
+    // Nor for the changes that would let it get around the supermajority, by
+    // signing for the account on its own or bringing the threshold down
+    let group_key = PublicKey::from(&SecretKey::random(&mut rng));
+    for change in [
+        AccountChange::SetGroupKey {
+            key: Some(group_key),
+        },
+        AccountChange::AddCosigner {
+            key: CosignerKey::Ed25519([0x11; 32]),
+        },
+        AccountChange::SetLivenessPolicy {
+            policy: Some(LivenessPolicy {
+                window: 10,
+                min_threshold: 1,
+            }),
+        },
+        AccountChange::SetKeyExpiry {
+            key: session.pks[THRESHOLD as usize],
+            valid_until: Some(1),
+        },
+        AccountChange::SetLicensePolicy {
+            policy: Some(LicensePolicy {
+                contract: [0x22; 32],
+                signers: false,
+            }),
+        },
+    ] {
+        let change_account =
+            session.signed_change_account(&threshold_signers, vec![change]);
+        expect_failure(
+            session.try_change_account(EXECUTOR_INDEX, &change_account),
+            Error::SupermajorityNotMet,
+        );
+    }
+
+    let supermajority_signers: Vec<_> = (0..SUPERMAJORITY_SIGNERS).collect();
+    let change_account = session.signed_change_account(
+        &supermajority_signers,
+        vec![AccountChange::SetThreshold {
+            threshold: THRESHOLD + 1,
+        }],
+    );
+    session
+        .try_change_account(EXECUTOR_INDEX, &change_account)
+        .expect("Changing the threshold by a supermajority should succeed");
+    assert_eq!(session.account().threshold, THRESHOLD + 1);
+
+    // The supermajority itself is only removed by a supermajority
+    let change_account = session.signed_change_account(
+        &supermajority_signers[1..],
+        vec![AccountChange::SetSupermajority {
+            supermajority: None,
+        }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::SupermajorityNotMet,
+    );
+    let change_account = session.signed_change_account(
+        &supermajority_signers,
+        vec![AccountChange::SetSupermajority {
+            supermajority: None,
+        }],
+    );
+    session
+        .try_change_account(EXECUTOR_INDEX, &change_account)
+        .expect("Removing the supermajority should succeed");
+    assert_eq!(session.account().supermajority, None);
+
+    let threshold_signers: Vec<_> = (0..=THRESHOLD as usize).collect();
+    let change_account = session.signed_change_account(
+        &threshold_signers,
+        vec![AccountChange::SetThreshold {
+            threshold: THRESHOLD,
+        }],
+    );
+    session
+        .try_change_account(EXECUTOR_INDEX, &change_account)
+        .expect("Changing the threshold should succeed once removed");
+}
+
+#[test]
+fn key_addition_policy() {
+    const EXECUTOR_INDEX: usize = 3;
+    const POLICY: KeyAdditionPolicy = KeyAdditionPolicy {
+        delay: 100,
+        supermajority: true,
+    };
+    const SUPERMAJORITY: Supermajority = Supermajority {
+        numerator: 3,
+        denominator: 4,
+    };
+    const SUPERMAJORITY_SIGNERS: usize = 12;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let account_id = session.create_account();
+
+    // Adding keys by the supermajority needs the account to have one
+    let signers = session.all_signers();
+    let change_account = session.signed_change_account(
+        &signers,
+        vec![AccountChange::SetKeyAdditionPolicy {
+            policy: Some(POLICY),
+        }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::NoSupermajority,
+    );
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![
+            AccountChange::SetSupermajority {
+                supermajority: Some(SUPERMAJORITY),
+            },
+            AccountChange::SetKeyAdditionPolicy {
+                policy: Some(POLICY),
+            },
+        ],
+    );
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.key_addition_policy, Some(Some(POLICY)));
+
+    let new_sk = SecretKey::random(&mut rng);
+    let new_pk = PublicKey::from(&new_sk);
+    let threshold_signers: Vec<_> = (0..THRESHOLD as usize).collect();
+    let change_account = session.signed_change_account(
+        &threshold_signers,
+        vec![AccountChange::AddKey { key: new_pk }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::SupermajorityNotMet,
+    );
+
+    session.set_block_height(10);
+    let supermajority_signers: Vec<_> = (0..SUPERMAJORITY_SIGNERS).collect();
+    let change_account = session.signed_change_account(
+        &supermajority_signers,
+        vec![AccountChange::AddKey { key: new_pk }],
+    );
+    session
+        .try_change_account(EXECUTOR_INDEX, &change_account)
+        .expect("Adding a key by the supermajority should succeed");
+    assert_eq!(
+        session.pending_keys(),
+        vec![PendingKey {
+            key: new_pk,
+            usable_from: 10 + POLICY.delay,
+        }]
+    );
+
+    // The new key can neither lock the account nor sign until the delay
+    // has passed
+    let nonce = session.account().nonce;
+    let lock = LockAccount::new(&new_sk, account_id, nonce);
+    expect_failure(
+        session.try_lock_account(EXECUTOR_INDEX, &lock),
+        Error::KeyNotYetUsable,
+    );
+    let signed_with_new_key = |session: &mut ContractSession| {
+        let signers: Vec<_> = (0..THRESHOLD as usize - 1).collect();
+        let mut change_account = session.signed_change_account(
+            &signers,
+            vec![AccountChange::SetDescription {
+                description: String::from("signed with the new key"),
+            }],
+        );
+        let msg = change_account.signature_msg();
+        change_account.keys.push(new_pk);
+        change_account.signature = change_account
+            .signature
+            .aggregate(&[new_sk.sign_multisig(&new_pk, &msg)]);
+        change_account
+    };
+    let change_account = signed_with_new_key(&mut session);
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::KeyNotYetUsable,
+    );
+
+    session.set_block_height(10 + POLICY.delay);
+    assert!(session.pending_keys().is_empty());
+    let change_account = signed_with_new_key(&mut session);
+    session
+        .try_change_account(EXECUTOR_INDEX, &change_account)
+        .expect("The new key should sign once the delay has passed");
+
+    // The policy is lifted only by the supermajority
+    let change_account = session.signed_change_account(
+        &threshold_signers,
+        vec![AccountChange::SetKeyAdditionPolicy { policy: None }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::SupermajorityNotMet,
+    );
+}
+
+/// A key added with an expiry signs up to the height it's valid until, and
+/// is refused after it, while remaining listed for the account to remove.
+#[test]
+fn key_expiry() {
+    const EXECUTOR_INDEX: usize = 3;
+    const VALID_UNTIL: u64 = 100;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let account_id = session.create_account();
+
+    let new_sk = SecretKey::random(&mut rng);
+    let new_pk = PublicKey::from(&new_sk);
+    let expiry = AccountChange::SetKeyExpiry {
+        key: new_pk,
+        valid_until: Some(VALID_UNTIL),
+    };
+
+    // Only keys of the account can lapse
+    let signers = session.all_signers();
+    let change_account =
+        session.signed_change_account(&signers, vec![expiry.clone()]);
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::KeyNotUsed,
+    );
+
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::AddKey { key: new_pk }, expiry],
+    );
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    let expected = KeyExpiry {
+        key: new_pk,
+        valid_until: VALID_UNTIL,
+    };
+    assert_eq!(event.set_key_expiries, vec![expected.clone()]);
+    assert_eq!(session.key_expiries(), vec![expected.clone()]);
+
+    let signed_with_new_key = |session: &mut ContractSession| {
+        let signers: Vec<_> = (0..THRESHOLD as usize - 1).collect();
+        let mut change_account = session.signed_change_account(
+            &signers,
+            vec![AccountChange::SetDescription {
+                description: String::from("signed with the new key"),
+            }],
+        );
+        let msg = change_account.signature_msg();
+        change_account.keys.push(new_pk);
+        change_account.signature = change_account
+            .signature
+            .aggregate(&[new_sk.sign_multisig(&new_pk, &msg)]);
+        change_account
+    };
+
+    session.set_block_height(VALID_UNTIL);
+    let change_account = signed_with_new_key(&mut session);
+    session
+        .try_change_account(EXECUTOR_INDEX, &change_account)
+        .expect("The key should sign up to the height it's valid until");
+
+    // Past it, the key neither signs nor locks the account
+    session.set_block_height(VALID_UNTIL + 1);
+    let change_account = signed_with_new_key(&mut session);
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::KeyExpired,
+    );
+    let nonce = session.account().nonce;
+    let lock = LockAccount::new(&new_sk, account_id, nonce);
+    expect_failure(
+        session.try_lock_account(EXECUTOR_INDEX, &lock),
+        Error::KeyExpired,
+    );
+    assert_eq!(session.key_expiries(), vec![expected]);
+
+    let threshold_signers: Vec<_> = (0..THRESHOLD as usize).collect();
+    let change_account = session.signed_change_account(
+        &threshold_signers,
+        vec![AccountChange::RemoveKey { key: new_pk }],
+    );
+    session
+        .try_change_account(EXECUTOR_INDEX, &change_account)
+        .expect("Removing the lapsed key should succeed");
+    assert!(session.key_expiries().is_empty());
+}
+
+/// Each key of the account is listed with the operations it signed and the
+/// height it last signed at, failed operations counting for nothing.
+#[test]
+fn key_usage() {
+    const EXECUTOR_INDEX: usize = 3;
+    const FIRST_HEIGHT: u64 = 10;
+    const SECOND_HEIGHT: u64 = 20;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    session.create_account();
+
+    let usage = session.key_usage();
+    assert_eq!(usage.len(), NUM_KEYS);
+    assert!(usage.iter().all(|u| u.operations == 0
+        && u.last_signed.is_none()
+        && u.is_inactive_since(0)));
+
+    let describe = |description: &str| {
+        vec![AccountChange::SetDescription {
+            description: String::from(description),
+        }]
+    };
+
+    let first: Vec<_> = (0..THRESHOLD as usize).collect();
+    let second: Vec<_> = (THRESHOLD as usize..NUM_KEYS).collect();
+    session.set_block_height(FIRST_HEIGHT);
+    let change_account =
+        session.signed_change_account(&first, describe("first"));
+    session
+        .try_change_account(EXECUTOR_INDEX, &change_account)
+        .expect("Changing the account should succeed");
+
+    session.set_block_height(SECOND_HEIGHT);
+    let change_account =
+        session.signed_change_account(&second, describe("second"));
+    session
+        .try_change_account(EXECUTOR_INDEX, &change_account)
+        .expect("Changing the account should succeed");
+
+    // Signing an operation that fails isn't counted
+    let change_account =
+        session.signed_change_account(&first[1..], describe("failed"));
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::ThresholdNotMet,
+    );
+
+    for u in session.key_usage() {
+        let index = session.pks.iter().position(|pk| *pk == u.key).unwrap();
+        let last_signed = if first.contains(&index) {
+            FIRST_HEIGHT
+        } else {
+            SECOND_HEIGHT
+        };
+        assert_eq!(u.operations, 1);
+        assert_eq!(u.last_signed, Some(last_signed));
+        assert_eq!(
+            u.is_inactive_since(FIRST_HEIGHT + 1),
+            first.contains(&index)
+        );
+    }
+
+    // A removed key's usage goes with it
+    let removed = session.pks[0];
+    let change_account = session.signed_change_account(
+        &second,
+        vec![AccountChange::RemoveKey { key: removed }],
+    );
+    session
+        .try_change_account(EXECUTOR_INDEX, &change_account)
+        .expect("Removing a key should succeed");
+    let usage = session.key_usage();
+    assert_eq!(usage.len(), NUM_KEYS - 1);
+    assert!(usage.iter().all(|u| u.key != removed));
+}
+
+/// A checkpoint fixes the balance, nonce and keys of the account at the height
+/// it's recorded at, and is kept in the order recorded, as long as the account
+/// retains its history.
+#[test]
+fn checkpoints() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const FIRST_HEIGHT: u64 = 10;
+    const SECOND_HEIGHT: u64 = 20;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+    assert!(session.checkpoints().is_empty());
+
+    let threshold_signers: Vec<_> = (0..THRESHOLD as usize).collect();
+    session.set_block_height(FIRST_HEIGHT);
+    let checkpoint = session.signed_checkpoint(&threshold_signers[1..]);
+    expect_failure(
+        session.try_record_checkpoint(EXECUTOR_INDEX, &checkpoint),
+        Error::ThresholdNotMet,
+    );
+
+    let checkpoint = session.signed_checkpoint(&threshold_signers);
+    session
+        .try_record_checkpoint(EXECUTOR_INDEX, &checkpoint)
+        .expect("Recording a checkpoint should succeed");
+    expect_failure(
+        session.try_record_checkpoint(EXECUTOR_INDEX, &checkpoint),
+        Error::InvalidNonce,
+    );
+
+    let event: CheckpointEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    let first = Checkpoint {
+        balance: DEPOSIT_AMOUNT,
+        nonce: checkpoint.nonce,
+        keys_hash: hash_keys(&session.pks),
+        block_height: FIRST_HEIGHT,
+        sequence: event.checkpoint.sequence,
+    };
+    assert_eq!(session.events[0].0, "checkpoint");
+    assert_eq!(event.checkpoint, first);
+    assert_eq!(session.checkpoints(), vec![first.clone()]);
+
+    // A later checkpoint fixes the keys as changed since
+    let new_pk = PublicKey::from(&SecretKey::random(&mut rng));
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::AddKey { key: new_pk }],
+    );
+    session.set_block_height(SECOND_HEIGHT);
+    let checkpoint = session.signed_checkpoint(&threshold_signers);
+    session
+        .try_record_checkpoint(EXECUTOR_INDEX, &checkpoint)
+        .expect("Recording a checkpoint should succeed");
+
+    let checkpoints = session.checkpoints();
+    assert_eq!(checkpoints.len(), 2);
+    assert_eq!(checkpoints[0], first);
+    let mut keys = session.pks.clone();
+    keys.push(new_pk);
+    assert_eq!(checkpoints[1].keys_hash, hash_keys(&keys));
+    assert_eq!(checkpoints[1].nonce, checkpoint.nonce);
+    assert!(checkpoints[1].sequence > first.sequence);
+
+    // Lowering the retention drops the oldest checkpoints at once, and later
+    // ones push the oldest out
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetHistoryRetention { records: Some(1) }],
+    );
+    assert_eq!(session.checkpoints(), vec![checkpoints[1].clone()]);
+
+    let checkpoint = session.signed_checkpoint(&threshold_signers);
+    session
+        .try_record_checkpoint(EXECUTOR_INDEX, &checkpoint)
+        .expect("Recording a checkpoint should succeed");
+    let checkpoints = session.checkpoints();
+    assert_eq!(
+        checkpoints.len(),
+        1,
+        "The oldest checkpoint should be dropped"
+    );
+    assert_eq!(checkpoints[0].nonce, checkpoint.nonce);
+}
+
+#[test]
+fn transfer_window() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const TRANSFER_AMOUNT: u64 = 100;
+    const WINDOW: TransferWindow = TransferWindow {
+        period: 100,
+        offset: 95,
+        length: 10,
+    };
+    const SUPERMAJORITY: Supermajority = Supermajority {
+        numerator: 3,
+        denominator: 4,
+    };
+    const SUPERMAJORITY_SIGNERS: usize = 12;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    // Overriding the window needs the account to have a supermajority
+    let signers = session.all_signers();
+    let change_account = session.signed_change_account(
+        &signers,
+        vec![AccountChange::SetTransferWindow {
+            window: Some(WINDOW),
+        }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::NoSupermajority,
+    );
+    let change_account = session.signed_change_account(
+        &signers,
+        vec![
+            AccountChange::SetSupermajority {
+                supermajority: Some(SUPERMAJORITY),
+            },
+            AccountChange::SetTransferWindow {
+                window: Some(TransferWindow {
+                    offset: 100,
+                    ..WINDOW
+                }),
+            },
+        ],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::InvalidTransferWindow,
+    );
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![
+            AccountChange::SetSupermajority {
+                supermajority: Some(SUPERMAJORITY),
+            },
+            AccountChange::SetTransferWindow {
+                window: Some(WINDOW),
+            },
+        ],
+    );
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.transfer_window, Some(Some(WINDOW)));
+
+    // Outside the window the threshold moves no funds, but the supermajority
+    // does
+    session.set_block_height(150);
+    let threshold_signers: Vec<_> = (0..THRESHOLD as usize).collect();
+    let transfer = session.signed_transfer(
+        &threshold_signers,
+        RECEIVER_INDEX,
+        TRANSFER_AMOUNT,
+    );
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::OutsideTransferWindow,
+    );
+    let payroll = session.signed_payroll(
+        &threshold_signers,
+        &[(RECEIVER_INDEX, TRANSFER_AMOUNT)],
+    );
+    expect_failure(
+        session.try_payroll(EXECUTOR_INDEX, &payroll),
+        Error::OutsideTransferWindow,
+    );
+    let supermajority_signers: Vec<_> = (0..SUPERMAJORITY_SIGNERS).collect();
+    let transfer = session.signed_transfer(
+        &supermajority_signers,
+        RECEIVER_INDEX,
+        TRANSFER_AMOUNT,
+    );
+    session
+        .try_transfer(EXECUTOR_INDEX, &transfer)
+        .expect("The supermajority should transfer outside the window");
+
+    // The window stays open into the next period
+    for block_height in [195, 204] {
+        session.set_block_height(block_height);
+        let transfer = session.signed_transfer(
+            &threshold_signers,
+            RECEIVER_INDEX,
+            TRANSFER_AMOUNT,
+        );
+        session
+            .try_transfer(EXECUTOR_INDEX, &transfer)
+            .expect("The threshold should transfer within the window");
+    }
+
+    // The window is lifted only by the supermajority
+    let change_account = session.signed_change_account(
+        &threshold_signers,
+        vec![AccountChange::SetTransferWindow { window: None }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::SupermajorityNotMet,
+    );
+}
+
+#[test]
+fn delegate_key() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const TRANSFER_AMOUNT: u64 = 100;
+    const DELEGATOR_INDEX: usize = THRESHOLD as usize - 1;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let account_id = session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    let delegate_sk = SecretKey::random(&mut rng);
+    let delegate = PublicKey::from(&delegate_sk);
+    let delegation = Delegation {
+        delegate,
+        from_height: 10,
+        until_height: 20,
+    };
+    let delegate_key = |session: &ContractSession, delegation, nonce| {
+        DelegateKey::new(
+            &session.sks[DELEGATOR_INDEX],
+            account_id,
+            delegation,
+            nonce,
+        )
+    };
+
+    // The delegate signs in place of the delegator, with the keys before it
+    let signers: Vec<usize> = (0..DELEGATOR_INDEX).collect();
+    let delegated_transfer = |session: &mut ContractSession, signers| {
+        let mut transfer =
+            session.signed_transfer(signers, RECEIVER_INDEX, TRANSFER_AMOUNT);
+        let msg = transfer.signature_msg();
+        let signature = delegate_sk.sign_multisig(&delegate, &msg);
+        transfer.keys.push(delegate);
+        transfer.signature = transfer.signature.aggregate(&[signature]);
+        transfer
+    };
+
+    let other_sk = SecretKey::random(&mut rng);
+    let mut forged = delegate_key(&session, Some(delegation.clone()), 1);
+    forged.signature =
+        DelegateKey::new(&other_sk, account_id, Some(delegation.clone()), 1)
+            .signature;
+    for (d, error) in [
+        (
+            delegate_key(&session, Some(delegation.clone()), 2),
+            Error::InvalidNonce,
+        ),
+        (
+            delegate_key(
+                &session,
+                Some(Delegation {
+                    from_height: 21,
+                    ..delegation.clone()
+                }),
+                1,
+            ),
+            Error::InvalidDelegationRange,
+        ),
+        (
+            delegate_key(
+                &session,
+                Some(Delegation {
+                    delegate: session.pks[0],
+                    ..delegation.clone()
+                }),
+                1,
+            ),
+            Error::KeyAlreadyUsed,
+        ),
+        (
+            DelegateKey::new(&other_sk, account_id, None, 1),
+            Error::UnknownKey,
+        ),
+        (forged, Error::InvalidSignature),
+    ] {
+        expect_failure(session.try_delegate_key(EXECUTOR_INDEX, &d), error);
+    }
+
+    session
+        .try_delegate_key(
+            EXECUTOR_INDEX,
+            &delegate_key(&session, Some(delegation.clone()), 1),
+        )
+        .expect("Delegating should succeed");
+    let [(topic, data)] = session.events.as_slice() else {
+        panic!("A single event should be emitted");
+    };
+    assert_eq!(topic, "key_delegated");
+    let event: KeyDelegatedEvent = multisig_wallet::decode_event(data).unwrap();
+    assert_eq!(event.key, session.pks[DELEGATOR_INDEX]);
+    assert_eq!(event.delegation, Some(delegation.clone()));
+
+    // A delegate signs for a single key at a time
+    let other = DelegateKey::new(
+        &session.sks[0],
+        account_id,
+        Some(delegation.clone()),
+        1,
+    );
+    expect_failure(
+        session.try_delegate_key(EXECUTOR_INDEX, &other),
+        Error::KeyAlreadyUsed,
+    );
+
+    // The delegate only signs within the range
+    session.set_block_height(9);
+    let transfer = delegated_transfer(&mut session, &signers);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::UnknownKey,
+    );
+    session.set_block_height(10);
+    session
+        .try_transfer(EXECUTOR_INDEX, &transfer)
+        .expect("Transferring with the delegate should succeed");
+
+    // The delegator and its delegate count once
+    let mut both = signers.clone();
+    both.push(DELEGATOR_INDEX);
+    let transfer = delegated_transfer(&mut session, &both);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::DuplicateKey,
+    );
+
+    let revoke = delegate_key(&session, None, 2);
+    session
+        .try_delegate_key(EXECUTOR_INDEX, &revoke)
+        .expect("Revoking the delegation should succeed");
+    expect_failure(
+        session.try_delegate_key(
+            EXECUTOR_INDEX,
+            &delegate_key(&session, Some(delegation), 1),
+        ),
+        Error::InvalidNonce,
+    );
+    let transfer = delegated_transfer(&mut session, &signers);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::UnknownKey,
+    );
+}
+
+/// Any single key can lock an account's transfers at once, while unlocking
+/// takes a change signed by the threshold.
+#[test]
+fn emergency_lock() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const LOCKER_INDEX: usize = 5;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let account_id = session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, 1);
+
+    // The lock is bound to the account's current nonce
+    let nonce = session.account().nonce;
+    let stale = LockAccount::new(&session.sks[LOCKER_INDEX], account_id, 0);
+    expect_failure(
+        session.try_lock_account(EXECUTOR_INDEX, &stale),
+        Error::InvalidSignature,
+    );
+
+    let other_sk = SecretKey::random(&mut rng);
+    let lock = LockAccount::new(&other_sk, account_id, nonce);
+    expect_failure(
+        session.try_lock_account(EXECUTOR_INDEX, &lock),
+        Error::UnknownKey,
+    );
+
+    let lock = LockAccount::new(&session.sks[LOCKER_INDEX], account_id, nonce);
+    session
+        .try_lock_account(EXECUTOR_INDEX, &lock)
+        .expect("Locking with a single key should succeed");
+    assert!(session.account().locked);
+
+    let [(topic, data)] = session.events.as_slice() else {
+        panic!("A single event should be emitted");
+    };
+    assert_eq!(topic, "account_locked");
+    let event: AccountLockedEvent =
+        multisig_wallet::decode_event(data).unwrap();
+    assert_eq!(event.key, session.pks[LOCKER_INDEX]);
+    assert_eq!(event.nonce, nonce);
+
+    expect_failure(
+        session.try_lock_account(EXECUTOR_INDEX, &lock),
+        Error::AccountLocked,
+    );
+
+    let signers = session.all_signers();
+    let transfer = session.signed_transfer(&signers, RECEIVER_INDEX, 1);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::AccountLocked,
+    );
+
+    // A single key can lock the account, but unlocking it takes the threshold
+    let change_account = session
+        .signed_change_account(&[LOCKER_INDEX], vec![AccountChange::Unlock]);
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::ThresholdNotMet,
+    );
+    session.change_account(EXECUTOR_INDEX, vec![AccountChange::Unlock]);
+    assert!(!session.account().locked);
+
+    let [(_, data)] = session.events.as_slice() else {
+        panic!("A single event should be emitted");
+    };
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(data).unwrap();
+    assert!(event.unlocked);
+
+    expect_failure(
+        session.try_lock_account(EXECUTOR_INDEX, &lock),
+        Error::InvalidSignature,
+    );
+    let signers = session.all_signers();
+    let change_account =
+        session.signed_change_account(&signers, vec![AccountChange::Unlock]);
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::NotLocked,
+    );
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, 1);
+}
+
+/// The compliance key of an account places it on hold, restricting its
+/// transfers to the remediation address until the supermajority of the
+/// account lifts the hold together with the compliance key.
+#[test]
+fn compliance_hold() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const REMEDIATION_INDEX: usize = 4;
+    const SUPERMAJORITY: Supermajority = Supermajority {
+        numerator: 3,
+        denominator: 4,
+    };
+    // Three quarters of the sixteen keys
+    const SUPERMAJORITY_SIGNERS: usize = 12;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let account_id = session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    let compliance_sk = SecretKey::random(&mut rng);
+    let compliance = ComplianceControl {
+        key: PublicKey::from(&compliance_sk),
+        remediation: session.pks[REMEDIATION_INDEX],
+    };
+
+    let hold = PlaceHold::new(&compliance_sk, account_id, 0);
+    expect_failure(
+        session.try_place_hold(EXECUTOR_INDEX, &hold),
+        Error::NoComplianceControl,
+    );
+
+    // Holds are lifted by the supermajority, so the account must have one
+    let signers = session.all_signers();
+    let change_account = session.signed_change_account(
+        &signers,
+        vec![AccountChange::SetCompliance {
+            compliance: Some(compliance.clone()),
+        }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::NoSupermajority,
+    );
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![
+            AccountChange::SetSupermajority {
+                supermajority: Some(SUPERMAJORITY),
+            },
+            AccountChange::SetCompliance {
+                compliance: Some(compliance.clone()),
+            },
+        ],
+    );
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.compliance, Some(Some(compliance.clone())));
+    assert_eq!(session.account().compliance, Some(compliance.clone()));
+
+    // The hold is bound to the account's current nonce, and to its
+    // compliance key
+    let nonce = session.account().nonce;
+    let stale = PlaceHold::new(&compliance_sk, account_id, 0);
+    expect_failure(
+        session.try_place_hold(EXECUTOR_INDEX, &stale),
+        Error::InvalidSignature,
+    );
+    let forged =
+        PlaceHold::new(&session.sks[EXECUTOR_INDEX], account_id, nonce);
+    expect_failure(
+        session.try_place_hold(EXECUTOR_INDEX, &forged),
+        Error::InvalidSignature,
+    );
+
+    let hold = PlaceHold::new(&compliance_sk, account_id, nonce);
+    session
+        .try_place_hold(EXECUTOR_INDEX, &hold)
+        .expect("Placing a hold with the compliance key should succeed");
+    assert!(session.account().on_hold);
+    assert_eq!(session.account().nonce, nonce);
+
+    let [(topic, data)] = session.events.as_slice() else {
+        panic!("A single event should be emitted");
+    };
+    assert_eq!(topic, "hold_placed");
+    let event: HoldPlacedEvent = multisig_wallet::decode_event(data).unwrap();
+    assert_eq!(event.key, compliance.key);
+    assert_eq!(event.remediation, compliance.remediation);
+    assert_eq!(event.nonce, nonce);
+
+    expect_failure(
+        session.try_place_hold(EXECUTOR_INDEX, &hold),
+        Error::AccountOnHold,
+    );
+
+    // Only the remediation address can be transferred to
+    let transfer = session.signed_transfer(&signers, RECEIVER_INDEX, 1);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::AccountOnHold,
+    );
+    session.transfer(EXECUTOR_INDEX, REMEDIATION_INDEX, 1);
+
+    // Nor can the compliance control be dropped while it holds the account
+    let change_account = session.signed_change_account(
+        &signers,
+        vec![AccountChange::SetCompliance { compliance: None }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::AccountOnHold,
+    );
+
+    // Lifting the hold takes the compliance key, along with the supermajority
+    let nonce = session.account().nonce + 1;
+    let forged =
+        PlaceHold::sign_lift(&session.sks[EXECUTOR_INDEX], account_id, nonce);
+    let change_account = session.signed_change_account(
+        &signers,
+        vec![AccountChange::LiftHold { signature: forged }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::InvalidSignature,
+    );
+
+    let signature = PlaceHold::sign_lift(&compliance_sk, account_id, nonce);
+    let threshold_signers: Vec<_> = (0..THRESHOLD as usize).collect();
+    let change_account = session.signed_change_account(
+        &threshold_signers,
+        vec![AccountChange::LiftHold { signature }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::SupermajorityNotMet,
+    );
+
+    let supermajority_signers: Vec<_> = (0..SUPERMAJORITY_SIGNERS).collect();
+    let change_account = session.signed_change_account(
+        &supermajority_signers,
+        vec![AccountChange::LiftHold { signature }],
+    );
+    session
+        .try_change_account(EXECUTOR_INDEX, &change_account)
+        .expect("Lifting the hold with the supermajority should succeed");
+    assert!(!session.account().on_hold);
+
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert!(event.hold_lifted);
+
+    let change_account = session.signed_change_account(
+        &signers,
+        vec![AccountChange::LiftHold { signature }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::NotOnHold,
+    );
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, 1);
+}
+
+/// An account with a license policy only transfers to receivers licensed by
+/// its license contract, and, if the policy says so, only holds licensed keys.
+#[test]
+fn license_policy() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    let policy = LicensePolicy {
+        contract: LICENSE_CONTRACT.to_bytes(),
+        signers: false,
+    };
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetLicensePolicy {
+            policy: Some(policy),
+        }],
+    );
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.license_policy, Some(Some(policy)));
+    assert_eq!(session.account().license_policy, Some(policy));
+
+    let signers = session.all_signers();
+    let transfer = session.signed_transfer(&signers, RECEIVER_INDEX, 1);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::ReceiverNotLicensed,
+    );
+
+    let receiver = session.pks[RECEIVER_INDEX];
+    session.set_licensed(receiver, true);
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, 1);
+
+    // Licenses are checked as of the transfer, so revoked ones are refused
+    session.set_licensed(receiver, false);
+    let transfer = session.signed_transfer(&signers, RECEIVER_INDEX, 1);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::ReceiverNotLicensed,
+    );
+
+    // Nor are the funds split off or merged into an account that would pay
+    // them out to the receiver unlicensed
+    let split = session.signed_split_account(&signers, &[0, 1], 1, 1);
+    expect_failure(
+        session.try_split_account(EXECUTOR_INDEX, &split),
+        Error::ReceiverNotLicensed,
+    );
+    let account_id = session.account_id.unwrap();
+    session.create_account();
+    let merge = session.signed_merge_accounts(&signers, account_id, &signers);
+    expect_failure(
+        session.try_merge_accounts(EXECUTOR_INDEX, &merge),
+        Error::ReceiverNotLicensed,
+    );
+    session.account_id = Some(account_id);
+
+    // Requiring licensed keys checks all the keys the account holds
+    let policy = LicensePolicy {
+        signers: true,
+        ..policy
+    };
+    let change_account = session.signed_change_account(
+        &signers,
+        vec![AccountChange::SetLicensePolicy {
+            policy: Some(policy),
+        }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::KeyNotLicensed,
+    );
+    for key in session.pks.clone() {
+        session.set_licensed(key, true);
+    }
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetLicensePolicy {
+            policy: Some(policy),
+        }],
+    );
+
+    // And the keys added under it
+    let new_pk = PublicKey::from(&SecretKey::random(&mut rng));
+    let change_account = session.signed_change_account(
+        &signers,
+        vec![AccountChange::AddKey { key: new_pk }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::KeyNotLicensed,
+    );
+    session.set_licensed(new_pk, true);
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::AddKey { key: new_pk }],
+    );
+
+    // A contract failing the query licenses no one
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetLicensePolicy {
+            policy: Some(LicensePolicy {
+                contract: [0xFF; 32],
+                signers: false,
+            }),
+        }],
+    );
+    let transfer = session.signed_transfer(&signers, RECEIVER_INDEX, 1);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::ReceiverNotLicensed,
+    );
+
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetLicensePolicy { policy: None }],
+    );
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, 1);
+}
+
+/// Operations of a kind with a threshold set need that many keys instead of
+/// the threshold of the account, which keys can't be removed below.
+#[test]
+fn operation_thresholds() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const TRANSFER_THRESHOLD: u32 = 2;
+    const CHANGE_THRESHOLD: u32 = THRESHOLD + 4;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new(&mut rng);
+
+    session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    let signers = session.all_signers();
+    for threshold in [0, NUM_KEYS as u32 + 1] {
+        let change_account = session.signed_change_account(
+            &signers,
+            vec![AccountChange::SetOperationThreshold {
+                op: OperationKind::Transfer,
+                threshold: Some(threshold),
+            }],
+        );
+        expect_failure(
+            session.try_change_account(EXECUTOR_INDEX, &change_account),
+            match threshold {
+                0 => Error::ZeroThreshold,
+                _ => Error::ThresholdTooLarge,
+            },
+        );
+    }
+
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![
+            AccountChange::SetOperationThreshold {
+                op: OperationKind::ChangeAccount,
+                threshold: Some(CHANGE_THRESHOLD),
+            },
+            AccountChange::SetOperationThreshold {
+                op: OperationKind::Transfer,
+                threshold: Some(TRANSFER_THRESHOLD),
+            },
+        ],
+    );
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.set_operation_thresholds.len(), 2);
+    assert_eq!(
+        session.account().operation_thresholds,
+        vec![
+            OperationThreshold {
+                op: OperationKind::Transfer,
+                threshold: TRANSFER_THRESHOLD,
+            },
+            OperationThreshold {
+                op: OperationKind::ChangeAccount,
+                threshold: CHANGE_THRESHOLD,
+            },
+        ],
+        "Operation thresholds should be kept in the order of their kinds"
+    );
+
+    // Transfers need fewer keys than the threshold of the account
+    let transfer = session.signed_transfer(&[0], RECEIVER_INDEX, 1);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::ThresholdNotMet,
+    );
+    let transfer = session.signed_transfer(&[0, 1], RECEIVER_INDEX, 1);
+    session
+        .try_transfer(EXECUTOR_INDEX, &transfer)
+        .expect("Transferring with the transfer threshold should succeed");
+
+    // While changes need more
+    let few: Vec<usize> = (0..THRESHOLD as usize).collect();
+    let many: Vec<usize> = (0..CHANGE_THRESHOLD as usize).collect();
+    let description = AccountChange::SetDescription {
+        description: String::from("operation thresholds"),
+    };
+    let change_account =
+        session.signed_change_account(&few, vec![description.clone()]);
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::ThresholdNotMet,
+    );
+    let change_account =
+        session.signed_change_account(&many, vec![description.clone()]);
+    session
+        .try_change_account(EXECUTOR_INDEX, &change_account)
+        .expect("Changing with the change threshold should succeed");
+
+    // Bundling the change with a transfer doesn't lower the bar
+    let transfer_and_change = session.signed_transfer_and_change(
+        &few,
+        RECEIVER_INDEX,
+        1,
+        vec![description.clone()],
+    );
+    expect_failure(
+        session.try_transfer_and_change(EXECUTOR_INDEX, &transfer_and_change),
+        Error::ThresholdNotMet,
+    );
+    let transfer_and_change = session.signed_transfer_and_change(
+        &many,
+        RECEIVER_INDEX,
+        1,
+        vec![description.clone()],
+    );
+    session
+        .try_transfer_and_change(EXECUTOR_INDEX, &transfer_and_change)
+        .expect("Transferring with the change threshold should succeed");
+
+    // Keys can't be removed below the highest threshold, even when above the
+    // threshold of the account
+    let removed = (CHANGE_THRESHOLD as usize - 1..NUM_KEYS)
+        .map(|index| AccountChange::RemoveKey {
+            key: session.pks[index],
+        })
+        .collect();
+    let change_account = session.signed_change_account(&signers, removed);
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::KeysBelowThreshold,
+    );
+
+    // Dropping a threshold falls back to the threshold of the account
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetOperationThreshold {
+            op: OperationKind::ChangeAccount,
+            threshold: None,
+        }],
+    );
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(
+        event.removed_operation_thresholds,
+        vec![OperationKind::ChangeAccount]
+    );
+    let change_account = session.signed_change_account(&few, vec![description]);
+    session
+        .try_change_account(EXECUTOR_INDEX, &change_account)
+        .expect("Changing with the account threshold should succeed");
+
+    // Other operations moving funds out need at least the transfer threshold
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetOperationThreshold {
+            op: OperationKind::Transfer,
+            threshold: Some(CHANGE_THRESHOLD),
+        }],
+    );
+    let payroll = session.signed_payroll(&few, &[(RECEIVER_INDEX, 1)]);
+    expect_failure(
+        session.try_payroll(EXECUTOR_INDEX, &payroll),
+        Error::ThresholdNotMet,
+    );
+    let split = session.signed_split_account(&few, &[0, 1], 1, 1);
+    expect_failure(
+        session.try_split_account(EXECUTOR_INDEX, &split),
+        Error::ThresholdNotMet,
+    );
+    let payroll = session.signed_payroll(&many, &[(RECEIVER_INDEX, 1)]);
+    session
+        .try_payroll(EXECUTOR_INDEX, &payroll)
+        .expect("Paying with the transfer threshold should succeed");
+}
+
+/// Transfers falling in a tier of the account need as many keys as the tier,
+/// fewer or more than the threshold, and larger ones the threshold. Funds
+/// moved out by other means need no fewer keys than the tier.
+#[test]
+fn transfer_tiers() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const SMALL: u64 = 10;
+    const MEDIUM: u64 = 100;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new(&mut rng);
+
+    session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    let tier = |ceiling, threshold| TransferTier { ceiling, threshold };
+    let signers = session.all_signers();
+    for (tiers, error) in [
+        (
+            vec![tier(MEDIUM, 3), tier(SMALL, 1)],
+            Error::InvalidTransferTiers,
+        ),
+        (
+            vec![tier(SMALL, 1), tier(SMALL, 3)],
+            Error::InvalidTransferTiers,
+        ),
+        (
+            (0..=MAX_TRANSFER_TIERS as u64)
+                .map(|c| tier(c, 1))
+                .collect(),
+            Error::InvalidTransferTiers,
+        ),
+        (vec![tier(SMALL, 0)], Error::ZeroThreshold),
+    ] {
+        let change_account = session.signed_change_account(
+            &signers,
+            vec![AccountChange::SetTransferTiers { tiers }],
+        );
+        expect_failure(
+            session.try_change_account(EXECUTOR_INDEX, &change_account),
+            error,
+        );
+    }
+
+    let tiers = vec![tier(SMALL, 1), tier(MEDIUM, 3)];
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetTransferTiers {
+            tiers: tiers.clone(),
+        }],
+    );
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.transfer_tiers, Some(tiers.clone()));
+    assert_eq!(session.transfer_tiers(), tiers);
+
+    // Each transfer needs the keys of the lowest tier it falls in, and
+    // transfers above every ceiling the threshold
+    for (amount, enough) in
+        [(SMALL, 1), (SMALL + 1, 3), (MEDIUM + 1, THRESHOLD)]
+    {
+        let short: Vec<usize> = (0..enough as usize - 1).collect();
+        let transfer = session.signed_transfer(&short, RECEIVER_INDEX, amount);
+        expect_failure(
+            session.try_transfer(EXECUTOR_INDEX, &transfer),
+            Error::ThresholdNotMet,
+        );
+        let enough: Vec<usize> = (0..enough as usize).collect();
+        let transfer = session.signed_transfer(&enough, RECEIVER_INDEX, amount);
+        session
+            .try_transfer(EXECUTOR_INDEX, &transfer)
+            .expect("Transferring with the keys of the tier should succeed");
+    }
+
+    // Tiers can raise the bar as well, up to the keys of the account
+    let change_account = session.signed_change_account(
+        &signers,
+        vec![AccountChange::SetTransferTiers {
+            tiers: vec![tier(SMALL, NUM_KEYS as u32 + 1)],
+        }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::InvalidTransferTiers,
+    );
+    let raised = THRESHOLD + 2;
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetTransferTiers {
+            tiers: vec![tier(SMALL, 1), tier(DEPOSIT_AMOUNT, raised)],
+        }],
+    );
+    let threshold: Vec<usize> = (0..THRESHOLD as usize).collect();
+    let transfer = session.signed_transfer(&threshold, RECEIVER_INDEX, MEDIUM);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::ThresholdNotMet,
+    );
+    // Transferring everything falls in the tier of the balance transferred
+    let transfer =
+        session.signed_transfer(&threshold, RECEIVER_INDEX, TRANSFER_ALL);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::ThresholdNotMet,
+    );
+    let raised: Vec<usize> = (0..raised as usize).collect();
+    let transfer = session.signed_transfer(&raised, RECEIVER_INDEX, MEDIUM);
+    session
+        .try_transfer(EXECUTOR_INDEX, &transfer)
+        .expect("Transferring with the keys of the tier should succeed");
+
+    // Funds moved out by other means are held to the tier too, a payroll by
+    // its total
+    let rows = [(RECEIVER_INDEX, SMALL), (RECEIVER_INDEX, SMALL)];
+    let payroll = session.signed_payroll(&threshold, &rows);
+    expect_failure(
+        session.try_payroll(EXECUTOR_INDEX, &payroll),
+        Error::ThresholdNotMet,
+    );
+    let payroll = session.signed_payroll(&raised, &rows);
+    session
+        .try_payroll(EXECUTOR_INDEX, &payroll)
+        .expect("Paying with the keys of the tier should succeed");
+    let transfer_and_change = session.signed_transfer_and_change(
+        &threshold,
+        RECEIVER_INDEX,
+        MEDIUM,
+        vec![AccountChange::SetDescription {
+            description: String::from("tiers"),
+        }],
+    );
+    expect_failure(
+        session.try_transfer_and_change(EXECUTOR_INDEX, &transfer_and_change),
+        Error::ThresholdNotMet,
+    );
+    let split = session.signed_split_account(&threshold, &[0, 1], 1, MEDIUM);
+    expect_failure(
+        session.try_split_account(EXECUTOR_INDEX, &split),
+        Error::ThresholdNotMet,
+    );
+
+    // A child draws on its budget without the keys, so the budget is held to
+    // the tier when it's allocated, and a merged account to the tier of its
+    // balance
+    let account_id = session.account_id.unwrap();
+    let child_id = session
+        .try_create_account(&CreateAccount {
+            keys: session.pks[..3].to_vec(),
+            threshold: 2,
+            description: String::new(),
+            alias: None,
+            key_weights: Vec::new(),
+            key_roles: Vec::new(),
+        })
+        .expect("Creating an account should succeed");
+    session.account_id = Some(account_id);
+    let change_account = session.signed_change_account(
+        &threshold,
+        vec![AccountChange::SetChildBudget {
+            budget: ChildBudget {
+                child_id,
+                amount: MEDIUM,
+                window: 100,
+            },
+        }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::ThresholdNotMet,
+    );
+    session.account_id = Some(child_id);
+    let merge = session.signed_merge_accounts(&[0, 1], account_id, &threshold);
+    expect_failure(
+        session.try_merge_accounts(EXECUTOR_INDEX, &merge),
+        Error::ThresholdNotMet,
+    );
+    session.account_id = Some(account_id);
+
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetTransferTiers { tiers: Vec::new() }],
+    );
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.transfer_tiers, Some(Vec::new()));
+    assert!(session.transfer_tiers().is_empty());
+    let transfer = session.signed_transfer(&[0], RECEIVER_INDEX, SMALL);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::ThresholdNotMet,
+    );
+}
+
+/// Keys given a weight count for as many signers towards the threshold, and
+/// keys given none for one.
+#[test]
+fn key_weights() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const AMOUNT: u64 = 10;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new(&mut rng);
+
+    let pks = session.pks.clone();
+    let weight = |key, weight| KeyWeight { key, weight };
+    let create_account = |key_weights| CreateAccount {
+        keys: pks[..2].to_vec(),
+        threshold: 3,
+        description: String::new(),
+        alias: None,
+        key_weights,
+        key_roles: Vec::new(),
+    };
+    for (key_weights, error) in [
+        (Vec::new(), Error::ThresholdTooLarge),
+        (vec![weight(pks[0], 1)], Error::ThresholdTooLarge),
+        (vec![weight(pks[2], 2)], Error::UnknownKey),
+        (vec![weight(pks[0], 0)], Error::ZeroWeight),
+        (
+            vec![weight(pks[0], 2), weight(pks[0], 2)],
+            Error::DuplicateKey,
+        ),
+    ] {
+        expect_failure(
+            session.try_create_account(&create_account(key_weights)),
+            error,
+        );
+    }
+    session
+        .try_create_account(&create_account(vec![
+            weight(pks[0], 2),
+            weight(pks[1], 1),
+        ]))
+        .expect("Creating an account weighing the threshold should succeed");
+    let event: CreateAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.key_weights, vec![weight(pks[0], 2)]);
+
+    session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    let signers = session.all_signers();
+    let change_account = session.signed_change_account(
+        &signers,
+        vec![AccountChange::SetKeyWeight {
+            key: pks[0],
+            weight: 0,
+        }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::ZeroWeight,
+    );
+
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetKeyWeight {
+            key: pks[0],
+            weight: THRESHOLD,
+        }],
+    );
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.set_key_weights, vec![weight(pks[0], THRESHOLD)]);
+    assert_eq!(session.key_weights(), vec![weight(pks[0], THRESHOLD)]);
+
+    // The weighted key alone meets the threshold, while one key short of it
+    // otherwise doesn't
+    let transfer = session.signed_transfer(&[0], RECEIVER_INDEX, AMOUNT);
+    session
+        .try_transfer(EXECUTOR_INDEX, &transfer)
+        .expect("Transferring with the weighted key should succeed");
+    let short: Vec<usize> = (1..THRESHOLD as usize).collect();
+    let transfer = session.signed_transfer(&short, RECEIVER_INDEX, AMOUNT);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::ThresholdNotMet,
+    );
+
+    // A weight of one is the weight of a key given none
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetKeyWeight {
+            key: pks[0],
+            weight: 1,
+        }],
+    );
+    assert!(session.key_weights().is_empty());
+    let transfer = session.signed_transfer(&[0], RECEIVER_INDEX, AMOUNT);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::ThresholdNotMet,
+    );
+}
+
+/// Spender keys sign transfers but not changes to the account, which the
+/// admin keys must remain able to make on their own.
+#[test]
+fn key_roles() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const AMOUNT: u64 = 10;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new(&mut rng);
+
+    let pks = session.pks.clone();
+    let assign = |key, role| RoleAssignment { key, role };
+    let create_account = |key_roles| CreateAccount {
+        keys: pks[..2].to_vec(),
+        threshold: 1,
+        description: String::new(),
+        alias: None,
+        key_weights: Vec::new(),
+        key_roles,
+    };
+    for (key_roles, error) in [
+        (
+            vec![
+                assign(pks[0], KeyRole::Spender),
+                assign(pks[1], KeyRole::Spender),
+            ],
+            Error::AdminsBelowThreshold,
+        ),
+        (vec![assign(pks[2], KeyRole::Spender)], Error::UnknownKey),
+        (
+            vec![
+                assign(pks[0], KeyRole::Spender),
+                assign(pks[0], KeyRole::Admin),
+            ],
+            Error::DuplicateKey,
+        ),
+    ] {
+        expect_failure(
+            session.try_create_account(&create_account(key_roles)),
+            error,
+        );
+    }
+    session
+        .try_create_account(&create_account(vec![
+            assign(pks[0], KeyRole::Spender),
+            assign(pks[1], KeyRole::Admin),
+        ]))
+        .expect("Creating an account with an admin key should succeed");
+    let event: CreateAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.key_roles, vec![assign(pks[0], KeyRole::Spender)]);
+
+    session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetKeyRole {
+            key: pks[0],
+            role: KeyRole::Spender,
+        }],
+    );
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.set_key_roles, vec![assign(pks[0], KeyRole::Spender)]);
+    assert_eq!(session.key_roles(), vec![assign(pks[0], KeyRole::Spender)]);
+
+    // The spender key signs transfers, but not changes to the account
+    let signers: Vec<usize> = (0..THRESHOLD as usize).collect();
+    let transfer = session.signed_transfer(&signers, RECEIVER_INDEX, AMOUNT);
+    session
+        .try_transfer(EXECUTOR_INDEX, &transfer)
+        .expect("Transferring with the spender key should succeed");
+    let change_account = session.signed_change_account(
+        &signers,
+        vec![AccountChange::SetDescription {
+            description: String::from(DESCRIPTION),
+        }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::KeyRoleNotAllowed,
+    );
+
+    // Too many spenders leave the admins short of the threshold
+    let admins: Vec<usize> = (1..NUM_KEYS).collect();
+    let changes = (1..=NUM_KEYS - THRESHOLD as usize)
+        .map(|index| AccountChange::SetKeyRole {
+            key: pks[index],
+            role: KeyRole::Spender,
+        })
+        .collect();
+    let change_account = session.signed_change_account(&admins, changes);
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::AdminsBelowThreshold,
+    );
+
+    // Turning the key back into an admin key clears its role
+    let change_account = session.signed_change_account(
+        &admins,
+        vec![AccountChange::SetKeyRole {
+            key: pks[0],
+            role: KeyRole::Admin,
+        }],
+    );
+    session
+        .try_change_account(EXECUTOR_INDEX, &change_account)
+        .expect("Changing the account with the admin keys should succeed");
+    assert!(session.key_roles().is_empty());
+}
+
+/// An archived account refuses deposits and transfers, keeping its balance
+/// and history, until the threshold reactivates it.
+#[test]
+fn archive_account() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    session.change_account(EXECUTOR_INDEX, vec![AccountChange::Archive]);
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.archived, Some(true));
+    assert!(session.account().archived);
+
+    expect_failure(
+        session.try_deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT),
+        Error::AccountArchived,
+    );
+    let signers = session.all_signers();
+    let transfer = session.signed_transfer(&signers, RECEIVER_INDEX, 1);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::AccountArchived,
+    );
+    let change_account =
+        session.signed_change_account(&signers, vec![AccountChange::Archive]);
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::AccountArchived,
+    );
+    assert_eq!(session.account().balance, DEPOSIT_AMOUNT);
+    assert_eq!(session.movements(0, u64::MAX).len(), 1);
+
+    // Reactivating takes the threshold, as any change does
+    let below_threshold: Vec<_> = (1..THRESHOLD as usize).collect();
+    let change_account = session.signed_change_account(
+        &below_threshold,
+        vec![AccountChange::Reactivate],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::ThresholdNotMet,
+    );
+    session.change_account(EXECUTOR_INDEX, vec![AccountChange::Reactivate]);
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.archived, Some(false));
+    assert!(!session.account().archived);
+
+    let change_account = session
+        .signed_change_account(&signers, vec![AccountChange::Reactivate]);
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::NotArchived,
+    );
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, 1);
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+}
+
+/// An account with claimable withdrawals only records its transfers, leaving
+/// the receiver to claim them and be paid.
+#[test]
+fn claimable_withdrawals() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const TRANSFER_AMOUNT: u64 = 300;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new(&mut rng);
+
+    session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetClaimableWithdrawals {
+            claimable_withdrawals: true,
+        }],
+    );
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.claimable_withdrawals, Some(true));
+
+    let receiver = session.pks[RECEIVER_INDEX];
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, TRANSFER_AMOUNT);
+    let event: TransferEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.claim_id, Some(1));
+    assert_eq!(
+        session.account().balance,
+        DEPOSIT_AMOUNT - TRANSFER_AMOUNT,
+        "The amount should leave the account at once"
+    );
+    assert_eq!(
+        session.balance(receiver),
+        INITIAL_BALANCE,
+        "The receiver should only be paid once it claims the withdrawal"
+    );
+    let withdrawals = session.withdrawals(receiver);
+    assert_eq!(withdrawals.len(), 1);
+    assert_eq!(withdrawals[0].amount, TRANSFER_AMOUNT);
+
+    // Only the receiver can claim, and only the withdrawal it signed for
+    let claim = ClaimWithdrawal::new(&session.sks[DEPOSITOR_INDEX], 1);
+    expect_failure(
+        session.try_claim_withdrawal(EXECUTOR_INDEX, &claim),
+        Error::NotWithdrawalReceiver,
+    );
+    let mut claim = ClaimWithdrawal::new(&session.sks[RECEIVER_INDEX], 2);
+    expect_failure(
+        session.try_claim_withdrawal(EXECUTOR_INDEX, &claim),
+        Error::WithdrawalNotFound,
+    );
+    claim.claim_id = 1;
+    expect_failure(
+        session.try_claim_withdrawal(EXECUTOR_INDEX, &claim),
+        Error::InvalidSignature,
+    );
+
+    // The claim is paid whatever became of the account since, and by
+    // whoever submits it
+    session.change_account(EXECUTOR_INDEX, vec![AccountChange::Archive]);
+    let claim = ClaimWithdrawal::new(&session.sks[RECEIVER_INDEX], 1);
+    session
+        .try_claim_withdrawal(EXECUTOR_INDEX, &claim)
+        .expect("Claiming the withdrawal should succeed");
+    let event: WithdrawalClaimedEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.amount, TRANSFER_AMOUNT);
+    assert_eq!(event.receiver.revealed(), Some(&receiver));
+    assert_eq!(
+        session.balance(receiver),
+        INITIAL_BALANCE + TRANSFER_AMOUNT,
+        "The receiver should be paid the withdrawal"
+    );
+    assert!(session.withdrawals(receiver).is_empty());
+
+    expect_failure(
+        session.try_claim_withdrawal(EXECUTOR_INDEX, &claim),
+        Error::WithdrawalNotFound,
+    );
+}
+
+/// A payment the transfer contract refuses is deferred as a withdrawal,
+/// instead of reverting the operation.
+#[test]
+fn deferred_payouts() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 500;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const TRANSFER_AMOUNT: u64 = 300;
+    const GENESIS_BALANCE: u64 = 1_000;
+
+    // The mock doesn't fund genesis balances, so the contract can't pay
+    // anything out of them until it's deposited to
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::with_genesis(&mut rng, |pks| Genesis {
+        accounts: vec![GenesisAccount {
+            keys: pks.to_vec(),
+            threshold: THRESHOLD,
+            description: String::from(DESCRIPTION),
+            balance: GENESIS_BALANCE,
+        }],
+        custody_cap: None,
+        min_deposit: None,
+        governance: None,
+    });
+    session.account_id = Some(1);
+
+    let receiver = session.pks[RECEIVER_INDEX];
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, TRANSFER_AMOUNT);
+    let event: PayoutDeferredEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.claim_id, 1);
+    assert_eq!(event.amount, TRANSFER_AMOUNT);
+    assert_eq!(event.receiver.revealed(), Some(&receiver));
+    let event: TransferEvent =
+        multisig_wallet::decode_event(&session.events[1].1).unwrap();
+    assert_eq!(
+        event.claim_id,
+        Some(1),
+        "The transfer should carry the claim ID of the deferred payout"
+    );
+    assert_eq!(
+        session.account().balance,
+        GENESIS_BALANCE - TRANSFER_AMOUNT,
+        "The amount should leave the account all the same"
+    );
+    assert_eq!(session.balance(receiver), 0);
+    assert_eq!(session.withdrawals(receiver).len(), 1);
+
+    // A claim still fails with the transfer, leaving the withdrawal in place
+    let claim = ClaimWithdrawal::new(&session.sks[RECEIVER_INDEX], 1);
+    expect_failure(
+        session.try_claim_withdrawal(EXECUTOR_INDEX, &claim),
+        Error::TransferFailed,
+    );
+
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+    session
+        .try_claim_withdrawal(EXECUTOR_INDEX, &claim)
+        .expect("Claiming the withdrawal should succeed");
+    assert_eq!(session.balance(receiver), TRANSFER_AMOUNT);
+    assert!(session.withdrawals(receiver).is_empty());
+
+    // Once the contract holds the amount, it's paid at once
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, TRANSFER_AMOUNT);
+    let event: TransferEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.claim_id, None);
+    assert_eq!(session.balance(receiver), 2 * TRANSFER_AMOUNT);
+}
+
+/// The governance account sets the contract-wide parameters, which every
+/// account is then held to.
+#[test]
+fn governance() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const EXECUTOR_INDEX: usize = 3;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::with_genesis(&mut rng, |pks| Genesis {
+        accounts: vec![GenesisAccount {
+            keys: pks.to_vec(),
+            threshold: THRESHOLD,
+            description: String::from(DESCRIPTION),
+            balance: 0,
+        }],
+        custody_cap: None,
+        min_deposit: None,
+        governance: Some(1),
+    });
+    session.account_id = Some(1);
+    let signers = session.all_signers();
+
+    let config: ContractConfig = session
+        .call(CONTRACT_ID, "config", &())
+        .expect("Querying the configuration should succeed")
+        .data;
+    assert_eq!(config, ContractConfig::DEFAULT);
+
+    // Bounds leave room for at least one key and memo byte
+    for config in [
+        ContractConfig {
+            max_keys: Some(0),
+            ..ContractConfig::DEFAULT
+        },
+        ContractConfig {
+            max_memo_len: Some(0),
+            ..ContractConfig::DEFAULT
+        },
+    ] {
+        let configure = session.signed_configure(&signers, config);
+        expect_failure(
+            session.try_configure(EXECUTOR_INDEX, &configure),
+            Error::InvalidConfig,
+        );
+    }
+
+    // The governance account is never retired
+    let change_account =
+        session.signed_change_account(&signers, vec![AccountChange::Archive]);
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::GovernanceAccount,
+    );
+    let other_id = session
+        .try_create_account(&CreateAccount {
+            keys: session.pks.clone(),
+            threshold: THRESHOLD,
+            description: String::from(DESCRIPTION),
+            alias: None,
+            key_weights: Vec::new(),
+            key_roles: Vec::new(),
+        })
+        .expect("Creating an account should succeed");
+    session.account_id = Some(other_id);
+    let merge = session.signed_merge_accounts(&signers, 1, &signers);
+    expect_failure(
+        session.try_merge_accounts(EXECUTOR_INDEX, &merge),
+        Error::GovernanceAccount,
+    );
+    session.account_id = Some(1);
+
+    // Too few signatures leave the parameters as they are
+    let paused = ContractConfig {
+        paused: true,
+        ..ContractConfig::DEFAULT
+    };
+    let configure = session.signed_configure(&signers[..1], paused);
+    expect_failure(
+        session.try_configure(EXECUTOR_INDEX, &configure),
+        Error::ThresholdNotMet,
+    );
+
+    let configure = session.signed_configure(&signers, paused);
+    session
+        .try_configure(EXECUTOR_INDEX, &configure)
+        .expect("Configuring the contract should succeed");
+    let event: ConfigureEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.account_id, 1);
+    assert_eq!(event.config, paused);
+    expect_failure(
+        session.try_deposit(DEPOSITOR_INDEX, 1_000),
+        Error::ContractPaused,
+    );
+
+    // Bounds apply to the memos and keys of every account
+    let bounded = ContractConfig {
+        max_keys: Some(2),
+        max_memo_len: Some(MEMO.len() as u32 - 1),
+        ..ContractConfig::DEFAULT
+    };
+    let configure = session.signed_configure(&signers, bounded);
+    session
+        .try_configure(EXECUTOR_INDEX, &configure)
+        .expect("Configuring the contract should succeed");
+    expect_failure(
+        session.try_deposit(DEPOSITOR_INDEX, 1_000),
+        Error::MemoTooLong,
+    );
+    let create_account = CreateAccount {
+        keys: session.pks.clone(),
+        threshold: THRESHOLD,
+        description: String::from(DESCRIPTION),
+        alias: None,
+        key_weights: Vec::new(),
+        key_roles: Vec::new(),
+    };
+    expect_failure(
+        session.try_create_account(&create_account),
+        Error::TooManyKeys,
+    );
+}
+
+/// Guardians can't sign for an account, but can lock it and hold back its
+/// pending whitelisted receivers.
+#[test]
+fn guardians() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const OTHER_RECEIVER_INDEX: usize = 4;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let account_id = session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    let guardian_sk = SecretKey::random(&mut rng);
+    let guardian = PublicKey::from(&guardian_sk);
+
+    // Guardians and keys are kept apart
+    let signers = session.all_signers();
+    let change_account = session.signed_change_account(
+        &signers,
+        vec![AccountChange::AddGuardian {
+            key: session.pks[0],
+        }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::KeyAlreadyUsed,
+    );
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::AddGuardian { key: guardian }],
+    );
+    assert_eq!(session.guardians(), vec![guardian]);
+
+    let change_account = session.signed_change_account(
+        &signers,
+        vec![AccountChange::AddKey { key: guardian }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::KeyAlreadyUsed,
+    );
+
+    let receiver = session.pks[RECEIVER_INDEX];
+    let other_receiver = session.pks[OTHER_RECEIVER_INDEX];
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![
+            AccountChange::AddWhitelisted { receiver },
+            AccountChange::AddWhitelisted {
+                receiver: other_receiver,
+            },
+        ],
+    );
+
+    let nonce = session.account().nonce;
+    let guard = |sk: &SecretKey, action| {
+        GuardAccount::new(sk, account_id, action, nonce)
+    };
+
+    let veto = GuardianAction::VetoWhitelisted {
+        receiver: other_receiver,
+    };
+    expect_failure(
+        session.try_guard_account(
+            EXECUTOR_INDEX,
+            &guard(&session.sks[0], veto.clone()),
+        ),
+        Error::NotGuardian,
+    );
+    session
+        .try_guard_account(EXECUTOR_INDEX, &guard(&guardian_sk, veto.clone()))
+        .expect("Vetoing a pending receiver should succeed");
+    assert_eq!(
+        session.whitelist(),
+        vec![WhitelistEntry {
+            receiver,
+            usable_from: WHITELIST_DELAY,
+        }]
+    );
+    expect_failure(
+        session.try_guard_account(EXECUTOR_INDEX, &guard(&guardian_sk, veto)),
+        Error::KeyNotUsed,
+    );
+
+    let [(topic, data)] = session.events.as_slice() else {
+        panic!("A single event should be emitted");
+    };
+    assert_eq!(topic, "guardian_action");
+    let event: GuardianActionEvent =
+        multisig_wallet::decode_event(data).unwrap();
+    assert_eq!(event.key, guardian);
+    assert_eq!(event.nonce, nonce);
+
+    let extend = |usable_from| GuardianAction::ExtendWhitelistDelay {
+        receiver,
+        usable_from,
+    };
+    expect_failure(
+        session.try_guard_account(
+            EXECUTOR_INDEX,
+            &guard(&guardian_sk, extend(WHITELIST_DELAY)),
+        ),
+        Error::DelayNotExtended,
+    );
+    session
+        .try_guard_account(
+            EXECUTOR_INDEX,
+            &guard(&guardian_sk, extend(2 * WHITELIST_DELAY)),
+        )
+        .expect("Extending the delay of a pending receiver should succeed");
+
+    session.set_block_height(WHITELIST_DELAY);
+    let transfer = session.signed_transfer(&signers, RECEIVER_INDEX, 1);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::ReceiverNotYetUsable,
+    );
+
+    // Receivers that can be transferred to are left to the signers
+    session.set_block_height(2 * WHITELIST_DELAY);
+    expect_failure(
+        session.try_guard_account(
+            EXECUTOR_INDEX,
+            &guard(&guardian_sk, extend(3 * WHITELIST_DELAY)),
+        ),
+        Error::ReceiverAlreadyUsable,
+    );
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, 1);
+
+    let nonce = session.account().nonce;
+    let lock = GuardAccount::new(
+        &guardian_sk,
+        account_id,
+        GuardianAction::Lock,
+        nonce,
+    );
+    session
+        .try_guard_account(EXECUTOR_INDEX, &lock)
+        .expect("Locking as a guardian should succeed");
+    assert!(session.account().locked);
+
+    let transfer = session.signed_transfer(&signers, RECEIVER_INDEX, 1);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::AccountLocked,
+    );
+
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![
+            AccountChange::Unlock,
+            AccountChange::RemoveGuardian { key: guardian },
+        ],
+    );
+    assert!(session.guardians().is_empty());
+
+    let nonce = session.account().nonce;
+    let lock = GuardAccount::new(
+        &guardian_sk,
+        account_id,
+        GuardianAction::Lock,
+        nonce,
+    );
+    expect_failure(
+        session.try_guard_account(EXECUTOR_INDEX, &lock),
+        Error::NotGuardian,
+    );
+}
+
+/// Enough guardians can recover an account, replacing its keys once the
+/// delay of its recovery policy passes, unless one of its keys cancels it.
+#[test]
+fn recovery() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const AMOUNT: u64 = 10;
+    const DELAY: u64 = 100;
+    const POLICY: RecoveryPolicy = RecoveryPolicy {
+        delay: DELAY,
+        guardians: 2,
+    };
+    const RECOVERED_KEYS: usize = 3;
+    const RECOVERED_THRESHOLD: u32 = 2;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let account_id = session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    let guardian_sks: Vec<_> =
+        (0..3).map(|_| SecretKey::random(&mut rng)).collect();
+    let guardians: Vec<_> = guardian_sks.iter().map(PublicKey::from).collect();
+    let keys = session.pks[..RECOVERED_KEYS].to_vec();
+
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![
+            AccountChange::AddGuardian { key: guardians[0] },
+            AccountChange::AddGuardian { key: guardians[1] },
+        ],
+    );
+    let start = session.signed_start_recovery(
+        &[&guardian_sks[0], &guardian_sks[1]],
+        keys.clone(),
+        RECOVERED_THRESHOLD,
+    );
+    expect_failure(
+        session.try_start_recovery(EXECUTOR_INDEX, &start),
+        Error::NoRecoveryPolicy,
+    );
+
+    let signers = session.all_signers();
+    let change_account = session.signed_change_account(
+        &signers,
+        vec![AccountChange::SetRecoveryPolicy {
+            policy: Some(RecoveryPolicy {
+                delay: DELAY,
+                guardians: 0,
+            }),
+        }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::InvalidRecoveryPolicy,
+    );
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetRecoveryPolicy {
+            policy: Some(POLICY),
+        }],
+    );
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.recovery_policy, Some(Some(POLICY)));
+
+    // Recoveries need enough guardians of the account, and keys that aren't
+    // its guardians
+    for (sks, keys, error) in [
+        (vec![&guardian_sks[0]], keys.clone(), Error::ThresholdNotMet),
+        (
+            vec![&guardian_sks[0], &guardian_sks[2]],
+            keys.clone(),
+            Error::NotGuardian,
+        ),
+        (
+            vec![&guardian_sks[0], &guardian_sks[1]],
+            vec![guardians[0]],
+            Error::KeyAlreadyUsed,
+        ),
+    ] {
+        let start =
+            session.signed_start_recovery(&sks, keys, RECOVERED_THRESHOLD);
+        expect_failure(
+            session.try_start_recovery(EXECUTOR_INDEX, &start),
+            error,
+        );
+    }
+
+    // Starting a recovery leaves the nonce of the account to its keys, so
+    // that what they signed before still goes through
+    let transfer = session.signed_transfer(&signers, RECEIVER_INDEX, AMOUNT);
+    let guardian_signers = [&guardian_sks[0], &guardian_sks[1]];
+    let start = session.signed_start_recovery(
+        &guardian_signers,
+        keys.clone(),
+        RECOVERED_THRESHOLD,
+    );
+    session
+        .try_start_recovery(EXECUTOR_INDEX, &start)
+        .expect("Starting a recovery should succeed");
+    assert_eq!(session.account().recovery_nonce, 1);
+    let (topic, data) = &session.events[0];
+    assert_eq!(topic, "recovery_started");
+    let event: RecoveryStartedEvent =
+        multisig_wallet::decode_event(data).unwrap();
+    let recovery = PendingRecovery {
+        guardians: guardians[..2].to_vec(),
+        keys: keys.clone(),
+        threshold: RECOVERED_THRESHOLD,
+        executable_from: BLOCK_HEIGHT + DELAY,
+    };
+    assert_eq!(event.recovery, recovery);
+    assert_eq!(event.nonce, 1);
+    assert_eq!(session.pending_recovery(), Some(recovery));
+    session
+        .try_transfer(EXECUTOR_INDEX, &transfer)
+        .expect("A transfer signed before the recovery should succeed");
+
+    let start = session.signed_start_recovery(
+        &guardian_signers,
+        keys.clone(),
+        RECOVERED_THRESHOLD,
+    );
+    expect_failure(
+        session.try_start_recovery(EXECUTOR_INDEX, &start),
+        Error::RecoveryPending,
+    );
+
+    // Any key of the account cancels the recovery
+    let nonce = session.account().recovery_nonce;
+    let cancel = CancelRecovery::new(&session.sks[5], account_id, nonce);
+    session
+        .try_cancel_recovery(EXECUTOR_INDEX, &cancel)
+        .expect("Cancelling the recovery should succeed");
+    assert_eq!(session.events[0].0, "recovery_cancelled");
+    assert_eq!(session.pending_recovery(), None);
+    expect_failure(
+        session.try_cancel_recovery(EXECUTOR_INDEX, &cancel),
+        Error::NoRecoveryPending,
+    );
+
+    // Changing the guardians or the recovery policy drops the recovery,
+    // which was started under them
+    for change in [
+        AccountChange::AddGuardian { key: guardians[2] },
+        AccountChange::RemoveGuardian { key: guardians[2] },
+        AccountChange::SetRecoveryPolicy { policy: None },
+    ] {
+        let start = session.signed_start_recovery(
+            &guardian_signers,
+            keys.clone(),
+            RECOVERED_THRESHOLD,
+        );
+        session
+            .try_start_recovery(EXECUTOR_INDEX, &start)
+            .expect("Starting a recovery should succeed");
+        session.change_account(EXECUTOR_INDEX, vec![change]);
+        assert_eq!(session.pending_recovery(), None);
+    }
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetRecoveryPolicy {
+            policy: Some(POLICY),
+        }],
+    );
+
+    // Otherwise it's finalized once the delay passes, by anyone
+    let start = session.signed_start_recovery(
+        &guardian_signers,
+        keys.clone(),
+        RECOVERED_THRESHOLD,
+    );
+    session
+        .try_start_recovery(EXECUTOR_INDEX, &start)
+        .expect("Starting a recovery again should succeed");
+    let finalize = FinalizeRecovery { account_id };
+    expect_failure(
+        session.try_finalize_recovery(EXECUTOR_INDEX, &finalize),
+        Error::RecoveryNotReady,
+    );
+    session.set_block_height(BLOCK_HEIGHT + DELAY);
+    session
+        .try_finalize_recovery(EXECUTOR_INDEX, &finalize)
+        .expect("Finalizing the recovery should succeed");
+    let (topic, data) = &session.events[0];
+    assert_eq!(topic, "recovery_finalized");
+    let event: RecoveryFinalizedEvent =
+        multisig_wallet::decode_event(data).unwrap();
+    assert_eq!(event.keys, keys);
+    assert_eq!(event.removed_keys.len(), NUM_KEYS - RECOVERED_KEYS);
+    assert_eq!(session.account().threshold, RECOVERED_THRESHOLD);
+    assert_eq!(session.pending_recovery(), None);
+
+    let signers: Vec<usize> = (0..RECOVERED_THRESHOLD as usize).collect();
+    let transfer = session.signed_transfer(&signers, RECEIVER_INDEX, AMOUNT);
+    session
+        .try_transfer(EXECUTOR_INDEX, &transfer)
+        .expect("Transferring with the recovered keys should succeed");
+    let transfer = session.signed_transfer(&[5, 6], RECEIVER_INDEX, AMOUNT);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::UnknownKey,
+    );
+
+    // The guardians are checked anew when the recovery is finalized
+    let start = session.signed_start_recovery(
+        &guardian_signers,
+        session.pks[RECOVERED_KEYS..2 * RECOVERED_KEYS].to_vec(),
+        RECOVERED_THRESHOLD,
+    );
+    session
+        .try_start_recovery(EXECUTOR_INDEX, &start)
+        .expect("Starting another recovery should succeed");
+    let mark = MarkCompromised::new(&guardian_sks[0]);
+    session
+        .try_mark_compromised(EXECUTOR_INDEX, &mark)
+        .expect("Marking a guardian compromised should succeed");
+    session.set_block_height(BLOCK_HEIGHT + 2 * DELAY);
+    expect_failure(
+        session.try_finalize_recovery(EXECUTOR_INDEX, &finalize),
+        Error::KeyCompromised,
+    );
+}
+
+/// The fallback key of an account's inactivity policy can claim it once it
+/// performed no operation for the policy's period, sweeping its balance or
+/// replacing its keys.
+#[test]
+fn inactivity() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const AMOUNT: u64 = 10;
+    const PERIOD: u64 = 100;
+    const ROTATED_KEYS: usize = 3;
+    const ROTATED_THRESHOLD: u32 = 2;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let account_id = session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    let fallback_sk = SecretKey::random(&mut rng);
+    let fallback = PublicKey::from(&fallback_sk);
+    let sweep = InactiveClaim::Sweep {
+        receiver: session.pks[RECEIVER_INDEX],
+    };
+
+    let nonce = session.account().nonce + 1;
+    let claim =
+        ClaimInactive::new(&fallback_sk, account_id, sweep.clone(), nonce);
+    expect_failure(
+        session.try_claim_inactive(EXECUTOR_INDEX, &claim),
+        Error::NoInactivityPolicy,
+    );
+
+    let signers = session.all_signers();
+    let change_account = session.signed_change_account(
+        &signers,
+        vec![AccountChange::SetInactivityPolicy {
+            policy: Some(InactivityPolicy {
+                fallback,
+                period: 0,
+            }),
+        }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::InvalidInactivityPolicy,
+    );
+    let policy = InactivityPolicy {
+        fallback,
+        period: PERIOD,
+    };
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetInactivityPolicy {
+            policy: Some(policy.clone()),
+        }],
+    );
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.inactivity_policy, Some(Some(policy)));
+    assert_eq!(session.account().last_active, BLOCK_HEIGHT);
+
+    // Claims wait for the period to pass since the last operation, and are
+    // signed by the fallback key
+    let nonce = session.account().nonce + 1;
+    let claim =
+        ClaimInactive::new(&fallback_sk, account_id, sweep.clone(), nonce);
+    expect_failure(
+        session.try_claim_inactive(EXECUTOR_INDEX, &claim),
+        Error::AccountActive,
+    );
+    session.set_block_height(BLOCK_HEIGHT + PERIOD);
+    let forged =
+        ClaimInactive::new(&session.sks[0], account_id, sweep.clone(), nonce);
+    expect_failure(
+        session.try_claim_inactive(EXECUTOR_INDEX, &forged),
+        Error::InvalidSignature,
+    );
+
+    session
+        .try_claim_inactive(EXECUTOR_INDEX, &claim)
+        .expect("Sweeping the inactive account should succeed");
+    let topics: Vec<_> = session
+        .events
+        .iter()
+        .map(|(topic, _)| topic.as_str())
+        .collect();
+    assert_eq!(topics, ["transfer", "inactive_claimed"]);
+    let event: InactiveClaimedEvent =
+        multisig_wallet::decode_event(&session.events[1].1).unwrap();
+    assert_eq!(event.key, fallback);
+    assert_eq!(event.last_active, BLOCK_HEIGHT);
+    assert_eq!(event.nonce, nonce);
+    assert_eq!(session.account().balance, 0);
+
+    // The claim counts as an operation, so replacing the keys waits for
+    // another period
+    let keys = session.pks[..ROTATED_KEYS].to_vec();
+    let rotate = InactiveClaim::RotateKeys {
+        keys: keys.clone(),
+        threshold: ROTATED_THRESHOLD,
+    };
+    let nonce = session.account().nonce + 1;
+    let claim = ClaimInactive::new(&fallback_sk, account_id, rotate, nonce);
+    expect_failure(
+        session.try_claim_inactive(EXECUTOR_INDEX, &claim),
+        Error::AccountActive,
+    );
+    session.set_block_height(BLOCK_HEIGHT + 2 * PERIOD);
+    session
+        .try_claim_inactive(EXECUTOR_INDEX, &claim)
+        .expect("Replacing the keys of the inactive account should succeed");
+    let (topic, data) = &session.events[0];
+    assert_eq!(topic, "inactive_claimed");
+    let event: InactiveClaimedEvent =
+        multisig_wallet::decode_event(data).unwrap();
+    assert_eq!(event.removed_keys.len(), NUM_KEYS - ROTATED_KEYS);
+    assert_eq!(session.account().threshold, ROTATED_THRESHOLD);
+
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+    let signers: Vec<usize> = (0..ROTATED_THRESHOLD as usize).collect();
+    let transfer = session.signed_transfer(&signers, RECEIVER_INDEX, AMOUNT);
+    session
+        .try_transfer(EXECUTOR_INDEX, &transfer)
+        .expect("Transferring with the replaced keys should succeed");
+}
+
+/// Transfers of at least the minimum of an account's queue policy must be
+/// queued, and can be cancelled by any single key until the delay passes and
+/// they're executed.
+#[test]
+fn queued_transfers() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const CANCELLER_INDEX: usize = 4;
+    const MIN_AMOUNT: u64 = 100;
+    const DELAY: u64 = 50;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let account_id = session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    let signers = session.all_signers();
+    let change_account = session.signed_change_account(
+        &signers,
+        vec![AccountChange::SetQueuePolicy {
+            policy: Some(QueuePolicy {
+                min_amount: MIN_AMOUNT,
+                delay: 0,
+            }),
+        }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::InvalidQueuePolicy,
+    );
+    let policy = QueuePolicy {
+        min_amount: MIN_AMOUNT,
+        delay: DELAY,
+    };
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetQueuePolicy {
+            policy: Some(policy),
+        }],
+    );
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.queue_policy, Some(Some(policy)));
+
+    // Transfers under the minimum go through directly, and only those
+    let small =
+        session.signed_transfer(&signers, RECEIVER_INDEX, MIN_AMOUNT - 1);
+    expect_failure(
+        session.try_queue_transfer(EXECUTOR_INDEX, &small),
+        Error::TransferNotQueueable,
+    );
+    session
+        .try_transfer(EXECUTOR_INDEX, &small)
+        .expect("Transferring under the minimum should succeed");
+    let large = session.signed_transfer(&signers, RECEIVER_INDEX, MIN_AMOUNT);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &large),
+        Error::TransferQueueRequired,
+    );
+
+    // Queuing uses up the nonce, but leaves the balance be
+    let balance = session.account().balance;
+    session
+        .try_queue_transfer(EXECUTOR_INDEX, &large)
+        .expect("Queuing the transfer should succeed");
+    let (topic, data) = &session.events[0];
+    assert_eq!(topic, "transfer_queued");
+    let event: TransferQueuedEvent =
+        multisig_wallet::decode_event(data).unwrap();
+    assert_eq!(event.executable_from, BLOCK_HEIGHT + DELAY);
+    assert_eq!(event.nonce, large.nonce);
+    assert_eq!(session.account().nonce, large.nonce);
+    assert_eq!(session.account().balance, balance);
+
+    // Any single key cancels it
+    let cancel = CancelQueued::new(
+        &session.sks[CANCELLER_INDEX],
+        account_id,
+        large.nonce,
+    );
+    session
+        .try_cancel_queued(EXECUTOR_INDEX, &cancel)
+        .expect("Cancelling the queued transfer should succeed");
+    expect_failure(
+        session.try_cancel_queued(EXECUTOR_INDEX, &cancel),
+        Error::NoQueuedTransfer,
+    );
+    let execute = ExecuteQueued {
+        account_id,
+        nonce: large.nonce,
+    };
+    expect_failure(
+        session.try_execute_queued(EXECUTOR_INDEX, &execute),
+        Error::NoQueuedTransfer,
+    );
+
+    // Executing waits for the delay, and pays out with the original signers
+    let large = session.signed_transfer(&signers, RECEIVER_INDEX, MIN_AMOUNT);
+    session
+        .try_queue_transfer(EXECUTOR_INDEX, &large)
+        .expect("Queuing the transfer should succeed");
+    let execute = ExecuteQueued {
+        account_id,
+        nonce: large.nonce,
+    };
+    expect_failure(
+        session.try_execute_queued(EXECUTOR_INDEX, &execute),
+        Error::QueuedTransferNotReady,
+    );
+    session.set_block_height(BLOCK_HEIGHT + DELAY);
+    session
+        .try_execute_queued(EXECUTOR_INDEX, &execute)
+        .expect("Executing the queued transfer should succeed");
+    let topics: Vec<_> = session
+        .events
+        .iter()
+        .map(|(topic, _)| topic.as_str())
+        .collect();
+    assert_eq!(topics, ["transfer", "queued_executed"]);
+    let event: TransferEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.keys.len(), signers.len());
+    assert_eq!(event.nonce, large.nonce);
+    assert_eq!(session.account().balance, balance - MIN_AMOUNT);
+    assert!(session.queued_transfers().is_empty());
+
+    // A transfer the balance falls short of is skipped, staying queued
+    let balance = session.account().balance;
+    let amount = balance / 2 + 1;
+    let first = session.signed_transfer(&signers, RECEIVER_INDEX, amount);
+    session
+        .try_queue_transfer(EXECUTOR_INDEX, &first)
+        .expect("Queuing the transfer should succeed");
+    let second = session.signed_transfer(&signers, RECEIVER_INDEX, amount);
+    session
+        .try_queue_transfer(EXECUTOR_INDEX, &second)
+        .expect("Queuing the transfer should succeed");
+    let executable_from = BLOCK_HEIGHT + 2 * DELAY;
+    session.set_block_height(executable_from);
+    session
+        .try_execute_queued(
+            EXECUTOR_INDEX,
+            &ExecuteQueued {
+                account_id,
+                nonce: first.nonce,
+            },
+        )
+        .expect("Executing the queued transfer should succeed");
+    let execute = ExecuteQueued {
+        account_id,
+        nonce: second.nonce,
+    };
+    session
+        .try_execute_queued(EXECUTOR_INDEX, &execute)
+        .expect("Skipping the queued transfer should succeed");
+    let [(topic, data)] = session.events.as_slice() else {
+        panic!("A single event should be emitted");
+    };
+    assert_eq!(topic, "queued_skipped");
+    let event: QueuedSkippedEvent =
+        multisig_wallet::decode_event(data).unwrap();
+    assert_eq!(event.nonce, second.nonce);
+    assert_eq!(event.amount, amount);
+    assert_eq!(event.balance, balance - amount);
+    assert_eq!(session.account().balance, balance - amount);
+    assert_eq!(session.queued_transfers().len(), 1);
+
+    // Left unexecuted past the expiry, it's dropped
+    session.set_block_height(executable_from + QUEUE_EXPIRY + 1);
+    session
+        .try_execute_queued(EXECUTOR_INDEX, &execute)
+        .expect("Expiring the queued transfer should succeed");
+    let [(topic, data)] = session.events.as_slice() else {
+        panic!("A single event should be emitted");
+    };
+    assert_eq!(topic, "queued_expired");
+    let event: QueuedExpiredEvent =
+        multisig_wallet::decode_event(data).unwrap();
+    assert_eq!(event.nonce, second.nonce);
+    assert_eq!(session.account().balance, balance - amount);
+    assert!(session.queued_transfers().is_empty());
+    expect_failure(
+        session.try_execute_queued(EXECUTOR_INDEX, &execute),
+        Error::NoQueuedTransfer,
+    );
+}
+
+/// A queued transfer is held to the keys and transfer window of the account
+/// as of its execution, a key removed or compromised since no longer signing
+/// for it.
+#[test]
+fn queued_transfer_signers() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const AMOUNT: u64 = 100;
+    const DELAY: u64 = 50;
+    const REMOVED_INDEX: usize = NUM_KEYS - 1;
+    const COMPROMISED_INDEX: usize = 0;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let account_id = session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![
+            AccountChange::SetQueuePolicy {
+                policy: Some(QueuePolicy {
+                    min_amount: AMOUNT,
+                    delay: DELAY,
+                }),
+            },
+            AccountChange::SetSupermajority {
+                supermajority: Some(Supermajority {
+                    numerator: 3,
+                    denominator: 4,
+                }),
+            },
+            AccountChange::SetTransferWindow {
+                window: Some(TransferWindow {
+                    period: 100,
+                    offset: 0,
+                    length: 60,
+                }),
+            },
+        ],
+    );
+
+    let threshold = THRESHOLD as usize;
+    let queue = |session: &mut ContractSession, signers: Vec<usize>| {
+        let transfer =
+            session.signed_transfer(&signers, RECEIVER_INDEX, AMOUNT);
+        session
+            .try_queue_transfer(EXECUTOR_INDEX, &transfer)
+            .expect("Queuing the transfer should succeed");
+        ExecuteQueued {
+            account_id,
+            nonce: transfer.nonce,
+        }
+    };
+    let removed =
+        queue(&mut session, (NUM_KEYS - threshold..NUM_KEYS).collect());
+    let compromised = queue(&mut session, (0..threshold).collect());
+    let windowed = queue(&mut session, (1..threshold + 1).collect());
+    let supermajority = queue(&mut session, (1..NUM_KEYS - 2).collect());
+
+    let removed_pk = session.pks[REMOVED_INDEX];
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::RemoveKey { key: removed_pk }],
+    );
+    let mark = MarkCompromised::new(&session.sks[COMPROMISED_INDEX]);
+    session
+        .try_mark_compromised(EXECUTOR_INDEX, &mark)
+        .expect("Marking the key as compromised should succeed");
+
+    session.set_block_height(BLOCK_HEIGHT + DELAY);
+    expect_failure(
+        session.try_execute_queued(EXECUTOR_INDEX, &removed),
+        Error::UnknownKey,
+    );
+    expect_failure(
+        session.try_execute_queued(EXECUTOR_INDEX, &compromised),
+        Error::KeyCompromised,
+    );
+
+    // Outside the window only the supermajority's transfer goes through,
+    // the threshold's waiting for it to open again
+    session.set_block_height(70);
+    expect_failure(
+        session.try_execute_queued(EXECUTOR_INDEX, &windowed),
+        Error::OutsideTransferWindow,
+    );
+    session
+        .try_execute_queued(EXECUTOR_INDEX, &supermajority)
+        .expect("Executing the supermajority's transfer should succeed");
+    session.set_block_height(101);
+    session
+        .try_execute_queued(EXECUTOR_INDEX, &windowed)
+        .expect("Executing the transfer within the window should succeed");
+    assert_eq!(session.account().balance, DEPOSIT_AMOUNT - 2 * AMOUNT);
+}
+
+/// Splits, merges, budget draws and sweeps of inactive accounts of at least
+/// the minimum of an account's queue policy are refused, as transfers of as
+/// much are.
+#[test]
+fn queue_policy_outflows() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const MIN_AMOUNT: u64 = 100;
+    const PERIOD: u64 = 100;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let account_id = session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+    let child_id = session
+        .try_create_account(&CreateAccount {
+            keys: session.pks[..3].to_vec(),
+            threshold: 2,
+            description: String::new(),
+            alias: None,
+            key_weights: Vec::new(),
+            key_roles: Vec::new(),
+        })
+        .expect("Creating an account should succeed");
+
+    session.account_id = Some(account_id);
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![
+            AccountChange::SetQueuePolicy {
+                policy: Some(QueuePolicy {
+                    min_amount: MIN_AMOUNT,
+                    delay: 50,
+                }),
+            },
+            AccountChange::SetChildBudget {
+                budget: ChildBudget {
+                    child_id,
+                    amount: DEPOSIT_AMOUNT,
+                    window: 100,
+                },
+            },
+        ],
+    );
+
+    let signers = session.all_signers();
+    let split = session.signed_split_account(&signers, &[0, 1], 1, MIN_AMOUNT);
+    expect_failure(
+        session.try_split_account(EXECUTOR_INDEX, &split),
+        Error::TransferQueueRequired,
+    );
+
+    session.account_id = Some(child_id);
+    let draw = session.signed_draw_budget(&[0, 1], account_id, MIN_AMOUNT);
+    expect_failure(
+        session.try_draw_budget(EXECUTOR_INDEX, &draw),
+        Error::TransferQueueRequired,
+    );
+    let merge = session.signed_merge_accounts(&[0, 1], account_id, &signers);
+    expect_failure(
+        session.try_merge_accounts(EXECUTOR_INDEX, &merge),
+        Error::TransferQueueRequired,
+    );
+
+    // Under the minimum, they go through
+    let draw = session.signed_draw_budget(&[0, 1], account_id, MIN_AMOUNT - 1);
+    session
+        .try_draw_budget(EXECUTOR_INDEX, &draw)
+        .expect("Drawing under the minimum should succeed");
+    session.account_id = Some(account_id);
+    let split =
+        session.signed_split_account(&signers, &[0, 1], 1, MIN_AMOUNT - 1);
+    session
+        .try_split_account(EXECUTOR_INDEX, &split)
+        .expect("Splitting under the minimum should succeed");
+
+    // A sweep can't be queued, so the fallback key of the account can't take
+    // its balance past the minimum either
+    let fallback_sk = SecretKey::random(&mut rng);
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetInactivityPolicy {
+            policy: Some(InactivityPolicy {
+                fallback: PublicKey::from(&fallback_sk),
+                period: PERIOD,
+            }),
+        }],
+    );
+    session.set_block_height(BLOCK_HEIGHT + PERIOD);
+    let sweep = InactiveClaim::Sweep {
+        receiver: session.pks[0],
+    };
+    let nonce = session.account().nonce + 1;
+    let claim = ClaimInactive::new(&fallback_sk, account_id, sweep, nonce);
+    expect_failure(
+        session.try_claim_inactive(EXECUTOR_INDEX, &claim),
+        Error::TransferQueueRequired,
+    );
+}
+
+/// A key given an allowance transfers out of it on its own signature, until
+/// it's used up.
+#[test]
+fn allowances() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const SPENDER_INDEX: usize = 0;
+    const SUBMITTER_INDEX: usize = 4;
+    const ALLOWANCE: u64 = 100;
+    const AMOUNT: u64 = 60;
+    const REFUND_AMOUNT: u64 = 10;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let account_id = session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    let receiver = session.pks[RECEIVER_INDEX];
+    let spender_sk = session.sks[SPENDER_INDEX].clone();
+    let spender = session.pks[SPENDER_INDEX];
+    let transfer = |session: &mut ContractSession, amount| {
+        AllowanceTransfer::new(
+            &spender_sk,
+            account_id,
+            receiver,
+            amount,
+            session.account().nonce + 1,
+            String::from(MEMO),
+            Some(CHAIN_ID),
+        )
+    };
+    let resign = |transfer: &mut AllowanceTransfer| {
+        transfer.signature =
+            spender_sk.sign_multisig(&spender, &transfer.signature_msg());
+    };
+
+    let spend = transfer(&mut session, AMOUNT);
+    expect_failure(
+        session.try_transfer_with_allowance(EXECUTOR_INDEX, &spend),
+        Error::NoAllowance,
+    );
+
+    let signers = session.all_signers();
+    let stranger = PublicKey::from(&SecretKey::random(&mut rng));
+    let change_account = session.signed_change_account(
+        &signers,
+        vec![AccountChange::SetAllowance {
+            key: stranger,
+            amount: ALLOWANCE,
+        }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::KeyNotUsed,
+    );
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetAllowance {
+            key: spender,
+            amount: ALLOWANCE,
+        }],
+    );
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(
+        event.set_allowances,
+        [KeyAllowance {
+            key: spender,
+            amount: ALLOWANCE
+        }]
+    );
+
+    // The key alone signs, and the transfer uses up the nonce of the account
+    let mut forged = transfer(&mut session, AMOUNT);
+    forged.amount -= 1;
+    expect_failure(
+        session.try_transfer_with_allowance(EXECUTOR_INDEX, &forged),
+        Error::InvalidSignature,
+    );
+    let mut replayed = transfer(&mut session, AMOUNT);
+    replayed.chain_id = Some(CHAIN_ID + 1);
+    resign(&mut replayed);
+    expect_failure(
+        session.try_transfer_with_allowance(EXECUTOR_INDEX, &replayed),
+        Error::WrongChain,
+    );
+    let spend = transfer(&mut session, AMOUNT);
+    session
+        .try_transfer_with_allowance(EXECUTOR_INDEX, &spend)
+        .expect("Transferring out of the allowance should succeed");
+    let topics: Vec<_> = session
+        .events
+        .iter()
+        .map(|(topic, _)| topic.as_str())
+        .collect();
+    assert_eq!(topics, ["transfer", "allowance_spent"]);
+    let event: AllowanceSpentEvent =
+        multisig_wallet::decode_event(&session.events[1].1).unwrap();
+    assert_eq!(event.remaining, ALLOWANCE - AMOUNT);
+    assert_eq!(event.nonce, spend.nonce);
+    assert_eq!(session.account().nonce, spend.nonce);
+    assert_eq!(session.account().balance, DEPOSIT_AMOUNT - AMOUNT);
+
+    // What is left of it can be spent, gas refund included, but no more
+    let spend = transfer(&mut session, AMOUNT);
+    expect_failure(
+        session.try_transfer_with_allowance(EXECUTOR_INDEX, &spend),
+        Error::AllowanceExceeded,
+    );
+    let refunded = |session: &mut ContractSession, amount| {
+        let mut spend = transfer(session, amount);
+        spend.gas_refund = Some(GasRefund {
+            submitter: session.pks[SUBMITTER_INDEX],
+            amount: REFUND_AMOUNT,
+        });
+        resign(&mut spend);
+        spend
+    };
+    let spend = refunded(&mut session, ALLOWANCE - AMOUNT);
+    expect_failure(
+        session.try_transfer_with_allowance(EXECUTOR_INDEX, &spend),
+        Error::AllowanceExceeded,
+    );
+    let spend = refunded(&mut session, ALLOWANCE - AMOUNT - REFUND_AMOUNT);
+    session
+        .try_transfer_with_allowance(EXECUTOR_INDEX, &spend)
+        .expect("Transferring the rest of the allowance should succeed");
+    let topics: Vec<_> = session
+        .events
+        .iter()
+        .map(|(topic, _)| topic.as_str())
+        .collect();
+    assert_eq!(topics, ["transfer", "gas_refund", "allowance_spent"]);
+    assert_eq!(session.account().balance, DEPOSIT_AMOUNT - ALLOWANCE);
+    assert!(session.allowances().is_empty());
+}
+
+/// Observers are listed with an account, but can't sign for it nor count
+/// toward its threshold.
+#[test]
+fn observer_keys() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    let observer = PublicKey::from(&SecretKey::random(&mut rng));
+
+    // Observers and keys are kept apart
+    let signers = session.all_signers();
+    let change_account = session.signed_change_account(
+        &signers,
+        vec![AccountChange::AddObserver {
+            key: session.pks[0],
+        }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::KeyAlreadyUsed,
+    );
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::AddObserver { key: observer }],
+    );
+    assert_eq!(session.observers(), vec![observer]);
+
+    let (_, data) = session.events.last().unwrap();
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(data).unwrap();
+    assert_eq!(event.added_observers, vec![observer]);
+
+    for changes in [
+        vec![AccountChange::AddKey { key: observer }],
+        vec![AccountChange::AddGuardian { key: observer }],
+        vec![AccountChange::AddObserver { key: observer }],
+    ] {
+        let change_account = session.signed_change_account(&signers, changes);
+        expect_failure(
+            session.try_change_account(EXECUTOR_INDEX, &change_account),
+            Error::KeyAlreadyUsed,
+        );
+    }
+
+    // An observer never stands in for a missing signer
+    let signers: Vec<usize> = (0..THRESHOLD as usize - 1).collect();
+    let mut transfer = session.signed_transfer(&signers, RECEIVER_INDEX, 1);
+    transfer.keys.push(observer);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::UnknownKey,
+    );
+
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::RemoveObserver { key: observer }],
+    );
+    assert!(session.observers().is_empty());
+
+    let signers = session.all_signers();
+    let change_account = session.signed_change_account(
+        &signers,
+        vec![AccountChange::RemoveObserver { key: observer }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::KeyNotUsed,
+    );
+}
+
+/// Aliases are unique across accounts, resolving to the account claiming them
+/// until it releases them.
+#[test]
+fn alias() {
+    const EXECUTOR_INDEX: usize = 3;
+    const ALIAS: &str = "dusk-grants";
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let account_id = session.create_account();
+
+    let pks = session.pks.clone();
+    let create_account = |alias: &str| CreateAccount {
+        keys: pks[..2].to_vec(),
+        threshold: 1,
+        description: String::new(),
+        alias: Some(String::from(alias)),
+        key_weights: Vec::new(),
+        key_roles: Vec::new(),
+    };
+
+    // Aliases can't be mistaken for account IDs
+    for alias in ["", "42", "Dusk", "dusk-", "dusk grants"] {
+        expect_failure(
+            session.try_create_account(&create_account(alias)),
+            Error::InvalidAlias,
+        );
+    }
+
+    let other_id = session
+        .try_create_account(&create_account(ALIAS))
+        .expect("Claiming an alias at creation should succeed");
+    assert_eq!(session.resolve_alias(ALIAS), Some(other_id));
+    assert_eq!(session.resolve_alias("unclaimed"), None);
+
+    let [(_, data)] = session.events.as_slice() else {
+        panic!("A single event should be emitted");
+    };
+    let event: CreateAccountEvent =
+        multisig_wallet::decode_event(data).unwrap();
+    assert_eq!(event.alias.as_deref(), Some(ALIAS));
+
+    expect_failure(
+        session.try_create_account(&create_account(ALIAS)),
+        Error::AliasTaken,
+    );
+    let signers = session.all_signers();
+    let set_alias = AccountChange::SetAlias {
+        alias: Some(String::from(ALIAS)),
+    };
+    let change_account =
+        session.signed_change_account(&signers, vec![set_alias.clone()]);
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::AliasTaken,
+    );
+
+    // Released aliases can be claimed by any account
+    session.account_id = Some(other_id);
+    let change_account = session.signed_change_account(
+        &[0],
+        vec![AccountChange::SetAlias { alias: None }],
+    );
+    session
+        .try_change_account(EXECUTOR_INDEX, &change_account)
+        .expect("Releasing an alias should succeed");
+    assert_eq!(session.resolve_alias(ALIAS), None);
+
+    let [(_, data)] = session.events.as_slice() else {
+        panic!("A single event should be emitted");
+    };
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(data).unwrap();
+    assert_eq!(event.alias, Some(None));
+
+    session.account_id = Some(account_id);
+    session.change_account(EXECUTOR_INDEX, vec![set_alias]);
+    assert_eq!(session.resolve_alias(ALIAS), Some(account_id));
+    assert_eq!(session.account().alias.as_deref(), Some(ALIAS));
+}
+
+/// A parent account allocates a budget renewed every window to a child
+/// account, which draws on it on its own signatures, and claws back what the
+/// child holds of it.
+#[test]
+fn child_budgets() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const BUDGET: u64 = 300;
+    const WINDOW: u64 = 100;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let parent_id = session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+    let child_id = session
+        .try_create_account(&CreateAccount {
+            keys: session.pks[..3].to_vec(),
+            threshold: 2,
+            description: String::new(),
+            alias: None,
+            key_weights: Vec::new(),
+            key_roles: Vec::new(),
+        })
+        .expect("Creating an account should succeed");
+
+    let budget = |child_id, window| ChildBudget {
+        child_id,
+        amount: BUDGET,
+        window,
+    };
+    session.account_id = Some(parent_id);
+    for (invalid, error) in [
+        (budget(parent_id, WINDOW), Error::InvalidChildBudget),
+        (budget(child_id, 0), Error::InvalidChildBudget),
+        (budget(child_id + 1, WINDOW), Error::AccountNotFound),
+    ] {
+        let signers = session.all_signers();
+        let change_account = session.signed_change_account(
+            &signers,
+            vec![AccountChange::SetChildBudget { budget: invalid }],
+        );
+        expect_failure(
+            session.try_change_account(EXECUTOR_INDEX, &change_account),
+            error,
+        );
+    }
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetChildBudget {
+            budget: budget(child_id, WINDOW),
+        }],
+    );
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.set_child_budgets, vec![budget(child_id, WINDOW)]);
+
+    // The child draws on its own threshold, up to the budget in a window
+    session.account_id = Some(child_id);
+    let draw = session.signed_draw_budget(&[0], parent_id, 200);
+    expect_failure(
+        session.try_draw_budget(EXECUTOR_INDEX, &draw),
+        Error::ThresholdNotMet,
+    );
+    let draw = session.signed_draw_budget(&[0, 1], parent_id, 200);
+    session
+        .try_draw_budget(EXECUTOR_INDEX, &draw)
+        .expect("Drawing within the budget should succeed");
+    let event: BudgetDrawnEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.amount, 200);
+    assert_eq!(event.balance_after, 200);
+    assert_eq!(event.parent_balance_after, DEPOSIT_AMOUNT - 200);
+
+    let draw = session.signed_draw_budget(&[0, 1], parent_id, 200);
+    expect_failure(
+        session.try_draw_budget(EXECUTOR_INDEX, &draw),
+        Error::ChildBudgetExceeded,
+    );
+    session.set_block_height(WINDOW);
+    let draw = session.signed_draw_budget(&[0, 1], parent_id, BUDGET);
+    session
+        .try_draw_budget(EXECUTOR_INDEX, &draw)
+        .expect("Drawing in the next window should succeed");
+    assert_eq!(session.account().balance, 200 + BUDGET);
+
+    session.account_id = Some(parent_id);
+    assert_eq!(
+        session.budget_draws(),
+        vec![BudgetDraw {
+            child_id,
+            drawn: BUDGET,
+            held: 200 + BUDGET,
+        }]
+    );
+
+    // Only what the child still holds is clawed back
+    session.account_id = Some(child_id);
+    let transfer = session.signed_transfer(&[0, 1], RECEIVER_INDEX, 100);
+    session
+        .try_transfer(EXECUTOR_INDEX, &transfer)
+        .expect("Transferring from the child should succeed");
+
+    session.account_id = Some(parent_id);
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![
+            AccountChange::ClawBackBudget { child_id },
+            AccountChange::RemoveChildBudget { child_id },
+        ],
+    );
+    let event: BudgetClawedBackEvent =
+        multisig_wallet::decode_event(&session.events[1].1).unwrap();
+    assert_eq!(event.amount, 100 + BUDGET);
+    assert_eq!(event.child_balance_after, 0);
+    assert_eq!(session.account().balance, DEPOSIT_AMOUNT - 100);
+
+    session.account_id = Some(child_id);
+    let draw = session.signed_draw_budget(&[0, 1], parent_id, 1);
+    expect_failure(
+        session.try_draw_budget(EXECUTOR_INDEX, &draw),
+        Error::UnknownChildBudget,
+    );
+
+    // An account restricting its receivers allocates no budgets, and the
+    // budgets it allocated before are no longer drawn on
+    session.account_id = Some(parent_id);
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetChildBudget {
+            budget: budget(child_id, WINDOW),
+        }],
+    );
+    let receiver = session.pks[RECEIVER_INDEX];
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::AddWhitelisted { receiver }],
+    );
+    session.account_id = Some(child_id);
+    let draw = session.signed_draw_budget(&[0, 1], parent_id, 1);
+    expect_failure(
+        session.try_draw_budget(EXECUTOR_INDEX, &draw),
+        Error::ReceiverNotWhitelisted,
+    );
+
+    let licensed_id = session.create_account();
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetLicensePolicy {
+            policy: Some(LicensePolicy {
+                contract: LICENSE_CONTRACT.to_bytes(),
+                signers: false,
+            }),
+        }],
+    );
+    for (parent_id, error) in [
+        (parent_id, Error::ReceiverNotWhitelisted),
+        (licensed_id, Error::ReceiverNotLicensed),
+    ] {
+        session.account_id = Some(parent_id);
+        let signers = session.all_signers();
+        let change_account = session.signed_change_account(
+            &signers,
+            vec![AccountChange::SetChildBudget {
+                budget: budget(child_id, WINDOW),
+            }],
+        );
+        expect_failure(
+            session.try_change_account(EXECUTOR_INDEX, &change_account),
+            error,
+        );
+    }
+}
+
+/// Two accounts merge on the signatures of both their thresholds, the merged
+/// one handing over its balance and keys, and being retired.
+#[test]
+fn merge_accounts() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const ALIAS: &str = "dusk-treasury";
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    // The two accounts share keys 8 and 9
+    let pks = session.pks.clone();
+    let account_id = session
+        .try_create_account(&CreateAccount {
+            keys: pks[..10].to_vec(),
+            threshold: 2,
+            description: String::new(),
+            alias: None,
+            key_weights: Vec::new(),
+            key_roles: Vec::new(),
+        })
+        .expect("Creating an account should succeed");
+    let merged_id = session
+        .try_create_account(&CreateAccount {
+            keys: pks[8..].to_vec(),
+            threshold: 3,
+            description: String::new(),
+            alias: Some(String::from(ALIAS)),
+            key_weights: Vec::new(),
+            key_roles: Vec::new(),
+        })
+        .expect("Creating an account should succeed");
+
+    session.account_id = Some(account_id);
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+    session.account_id = Some(merged_id);
+    session.deposit(DEPOSITOR_INDEX, 2 * DEPOSIT_AMOUNT);
+
+    session.account_id = Some(account_id);
+    let merge = session.signed_merge_accounts(&[0, 1], account_id, &[0, 1]);
+    expect_failure(
+        session.try_merge_accounts(EXECUTOR_INDEX, &merge),
+        Error::MergeIntoSelf,
+    );
+
+    // Each side must meet its own threshold
+    let merge = session.signed_merge_accounts(&[0, 1], merged_id, &[8, 9]);
+    expect_failure(
+        session.try_merge_accounts(EXECUTOR_INDEX, &merge),
+        Error::ThresholdNotMet,
+    );
+    let merge = session.signed_merge_accounts(&[0], merged_id, &[8, 9, 10]);
+    expect_failure(
+        session.try_merge_accounts(EXECUTOR_INDEX, &merge),
+        Error::ThresholdNotMet,
+    );
+    let merge = session.signed_merge_accounts(&[0, 1], merged_id, &[0, 1, 10]);
+    expect_failure(
+        session.try_merge_accounts(EXECUTOR_INDEX, &merge),
+        Error::UnknownKey,
+    );
+
+    let merge = session.signed_merge_accounts(&[0, 1], merged_id, &[8, 9, 10]);
+    session
+        .try_merge_accounts(EXECUTOR_INDEX, &merge)
+        .expect("Merging the accounts should succeed");
+
+    let [(topic, data)] = session.events.as_slice() else {
+        panic!("A single event should be emitted");
+    };
+    assert_eq!(topic, "merge_accounts");
+    let event: MergeAccountsEvent =
+        multisig_wallet::decode_event(data).unwrap();
+    assert_eq!(event.amount, 2 * DEPOSIT_AMOUNT);
+    assert_eq!(event.balance_after, 3 * DEPOSIT_AMOUNT);
+    assert_eq!(event.added_keys.len(), NUM_KEYS - 10);
+
+    let account = session.account();
+    assert_eq!(account.balance, 3 * DEPOSIT_AMOUNT);
+    assert_eq!(account.threshold, 2, "The threshold should be kept");
+    assert_eq!(session.account_keys().len(), pks.len());
+    assert_eq!(session.resolve_alias(ALIAS), None);
+
+    // The merged account is retired, refusing even deposits
+    session.account_id = Some(merged_id);
+    assert_eq!(session.account().merged_into, Some(account_id));
+    expect_failure(
+        session.try_deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT),
+        Error::AccountMerged,
+    );
+    let change_account = session.signed_change_account(
+        &[10, 11, 12],
+        vec![AccountChange::SetDescription {
+            description: String::from("revived"),
+        }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::AccountMerged,
+    );
+
+    // Its keys now sign for the remaining account
+    session.account_id = Some(account_id);
+    session.transfer(EXECUTOR_INDEX, 2, DEPOSIT_AMOUNT);
+    let transfer = session.signed_transfer(&[14, 15], 2, DEPOSIT_AMOUNT);
+    session
+        .try_transfer(EXECUTOR_INDEX, &transfer)
+        .expect("Keys of the merged account should sign");
+}
+
+/// An account splits a new one off with some of its keys and part of its
+/// balance, the new account being signed for by its own threshold alone.
+#[test]
+fn split_account() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const SPLIT_AMOUNT: u64 = 400;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let pks = session.pks.clone();
+    let account_id = session
+        .try_create_account(&CreateAccount {
+            keys: pks[..10].to_vec(),
+            threshold: 2,
+            description: String::new(),
+            alias: None,
+            key_weights: Vec::new(),
+            key_roles: Vec::new(),
+        })
+        .expect("Creating an account should succeed");
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    // The new account may only be given keys of the account
+    let split = session.signed_split_account(&[0, 1], &[8, 10], 1, 0);
+    expect_failure(
+        session.try_split_account(EXECUTOR_INDEX, &split),
+        Error::KeyNotInAccount,
+    );
+    let split = session.signed_split_account(&[0, 1], &[8, 9], 3, 0);
+    expect_failure(
+        session.try_split_account(EXECUTOR_INDEX, &split),
+        Error::ThresholdTooLarge,
+    );
+    let split =
+        session.signed_split_account(&[0, 1], &[8, 9], 2, DEPOSIT_AMOUNT + 1);
+    expect_failure(
+        session.try_split_account(EXECUTOR_INDEX, &split),
+        Error::InsufficientBalance,
+    );
+
+    let split = session.signed_split_account(&[0, 1], &[8, 9], 2, SPLIT_AMOUNT);
+    session
+        .try_split_account(EXECUTOR_INDEX, &split)
+        .expect("Splitting the account should succeed");
+
+    let [(create_topic, _), (topic, data)] = session.events.as_slice() else {
+        panic!("Two events should be emitted");
+    };
+    assert_eq!(create_topic, "create_account");
+    assert_eq!(topic, "split_account");
+    let event: SplitAccountEvent = multisig_wallet::decode_event(data).unwrap();
+    assert_eq!(event.amount, SPLIT_AMOUNT);
+    assert_eq!(event.balance_after, DEPOSIT_AMOUNT - SPLIT_AMOUNT);
+    assert_ne!(event.new_account_id, account_id);
+
+    assert_eq!(session.account().balance, DEPOSIT_AMOUNT - SPLIT_AMOUNT);
+
+    session.account_id = Some(event.new_account_id);
+    let account = session.account();
+    assert_eq!(account.balance, SPLIT_AMOUNT);
+    assert_eq!(account.threshold, 2);
+    assert_eq!(session.account_keys().len(), 2);
+    let operations = session.operations();
+    assert_eq!(
+        operations[0].balance, SPLIT_AMOUNT,
+        "The new account should be opened with the amount split off"
+    );
+
+    let transfer = session.signed_transfer(&[0, 1], RECEIVER_INDEX, 100);
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::UnknownKey,
+    );
+    let transfer = session.signed_transfer(&[8, 9], RECEIVER_INDEX, 100);
+    session
+        .try_transfer(EXECUTOR_INDEX, &transfer)
+        .expect("The new account's keys should sign for it");
+}
+
+/// A transfer and a change made at once either both happen, under a single
+/// nonce, or neither does.
+#[test]
+fn transfer_and_change() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const DEPARTING_INDEX: usize = 5;
+    const PAYOUT_AMOUNT: u64 = 300;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new(&mut rng);
+
+    session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    let signers = session.all_signers();
+    let departing = session.pks[DEPARTING_INDEX];
+    let nonce = session.account().nonce;
+
+    // A failing change fails the transfer with it
+    let transfer_and_change = session.signed_transfer_and_change(
+        &signers,
+        DEPARTING_INDEX,
+        PAYOUT_AMOUNT,
+        vec![
+            AccountChange::RemoveKey { key: departing },
+            AccountChange::RemoveKey { key: departing },
+        ],
+    );
+    expect_failure(
+        session.try_transfer_and_change(EXECUTOR_INDEX, &transfer_and_change),
+        Error::KeyNotUsed,
+    );
+    assert_eq!(session.account().balance, DEPOSIT_AMOUNT);
+    assert_eq!(session.balance(departing), INITIAL_BALANCE);
+
+    let transfer_and_change = session.signed_transfer_and_change(
+        &signers,
+        DEPARTING_INDEX,
+        PAYOUT_AMOUNT,
+        vec![AccountChange::RemoveKey { key: departing }],
+    );
+    session
+        .try_transfer_and_change(EXECUTOR_INDEX, &transfer_and_change)
+        .expect("Transferring and changing should succeed");
+
+    let [(transfer_topic, transfer_data), (change_topic, change_data)] =
+        session.events.as_slice()
+    else {
+        panic!("A transfer and a change event should be emitted");
+    };
+    assert_eq!(transfer_topic, "transfer");
+    assert_eq!(change_topic, "change_account");
+    let transfer: TransferEvent =
+        multisig_wallet::decode_event(transfer_data).unwrap();
+    let change: ChangeAccountEvent =
+        multisig_wallet::decode_event(change_data).unwrap();
+    assert_eq!(transfer.amount, PAYOUT_AMOUNT);
+    assert_eq!(transfer.nonce, nonce + 1);
+    assert_eq!(change.nonce, nonce + 1, "Both should share the nonce");
+    assert!(change.with_transfer);
+    assert_eq!(change.removed_keys, vec![departing]);
+
+    let account = session.account();
+    assert_eq!(account.nonce, nonce + 1, "A single nonce should be used");
+    assert_eq!(account.balance, DEPOSIT_AMOUNT - PAYOUT_AMOUNT);
+    assert_eq!(
+        session.balance(departing),
+        INITIAL_BALANCE + PAYOUT_AMOUNT,
+        "The departing member should be paid out"
+    );
+    assert!(
+        !session.account_keys().contains(&departing),
+        "The departing member's key should be removed"
+    );
+}
+
+#[test]
+fn quorum_certificate() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 5;
+    const TRANSFER_AMOUNT: u64 = 300;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new(&mut rng);
+
+    session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    let signers: Vec<_> = (0..THRESHOLD as usize).collect();
+    let transfer =
+        session.signed_transfer(&signers, RECEIVER_INDEX, TRANSFER_AMOUNT);
+    let account_keys = session.account_keys();
+    let certified = CertifiedOperation::new(
+        Operation::Transfer(transfer.clone()),
+        &account_keys,
+    )
+    .expect("The signers should all be keys of the account");
+    assert_eq!(certified.signers.len(), NUM_KEYS.div_ceil(8));
+    assert!(
+        rkyv::to_bytes::<_, 1024>(&certified).unwrap().len()
+            < rkyv::to_bytes::<_, 1024>(&transfer).unwrap().len(),
+        "A certificate should be smaller than the keys it names"
+    );
+    assert!(
+        CertifiedOperation::new(
+            Operation::Transfer(transfer.clone()),
+            &account_keys[..1]
+        )
+        .is_none(),
+        "Signers outside the account shouldn't be certified"
+    );
+
+    // The bitmap must cover exactly the keys of the account
+    let mut wrong_length = certified.clone();
+    wrong_length.signers.push(0);
+    expect_failure(
+        session.try_certified(EXECUTOR_INDEX, &wrong_length),
+        Error::InvalidCertificate,
+    );
+
+    // The operation itself must name no keys
+    let mut with_keys = certified.clone();
+    with_keys.operation.set_keys(transfer.keys.clone());
+    expect_failure(
+        session.try_certified(EXECUTOR_INDEX, &with_keys),
+        Error::InvalidCertificate,
+    );
+
+    // Naming keys that didn't sign fails the signature
+    let mut other_signers = certified.clone();
+    other_signers.signers.fill(u8::MAX);
+    expect_failure(
+        session.try_certified(EXECUTOR_INDEX, &other_signers),
+        Error::InvalidSignature,
+    );
+
+    session
+        .try_certified(EXECUTOR_INDEX, &certified)
+        .expect("Transferring under a certificate should succeed");
+
+    let [(topic, data)] = session.events.as_slice() else {
+        panic!("A single transfer event should be emitted");
+    };
+    assert_eq!(topic, "transfer");
+    let event: TransferEvent = multisig_wallet::decode_event(data).unwrap();
+    assert_eq!(event.amount, TRANSFER_AMOUNT);
+    assert_eq!(session.account().balance, DEPOSIT_AMOUNT - TRANSFER_AMOUNT);
+}
+
+/// The ledger of an account records every movement of its funds, running its
+/// balance, and can be fed by block range.
+#[test]
+fn movements() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const TRANSFER_AMOUNT: u64 = 300;
+    const SPLIT_AMOUNT: u64 = 200;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let account_id = session.create_account();
+    session.set_block_height(10);
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+    session.set_block_height(20);
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, TRANSFER_AMOUNT);
+    session.set_block_height(30);
+    let signers = session.all_signers();
+    let split =
+        session.signed_split_account(&signers, &[0, 1], 1, SPLIT_AMOUNT);
+    session
+        .try_split_account(EXECUTOR_INDEX, &split)
+        .expect("Splitting the account should succeed");
+    let new_account_id = account_id + 1;
+
+    let movements = session.movements(0, u64::MAX);
+    let [deposit, transfer, split] = movements.as_slice() else {
+        panic!("Every movement should be recorded, got {movements:?}");
+    };
+
+    assert_eq!(deposit.block_height, 10);
+    assert_eq!(deposit.direction, Direction::In);
+    assert_eq!(deposit.counterparty, Counterparty::Depositor);
+    assert_eq!(deposit.amount, DEPOSIT_AMOUNT);
+    assert_eq!(deposit.memo_hash, Some(hash_memo(deposit.sequence, MEMO)));
+    assert_eq!(deposit.balance_after, DEPOSIT_AMOUNT);
+
+    let receiver = session.pks[RECEIVER_INDEX];
+    assert_eq!(transfer.block_height, 20);
+    assert_eq!(transfer.direction, Direction::Out);
+    assert_eq!(
+        transfer.counterparty,
+        Counterparty::Moonlight(Disclosure::Revealed(receiver))
+    );
+    assert_eq!(transfer.amount, TRANSFER_AMOUNT);
+    assert_eq!(transfer.memo_hash, Some(hash_memo(transfer.sequence, MEMO)));
+    assert_eq!(transfer.balance_after, DEPOSIT_AMOUNT - TRANSFER_AMOUNT);
+
+    assert_eq!(split.block_height, 30);
+    assert_eq!(split.direction, Direction::Out);
+    assert_eq!(split.counterparty, Counterparty::Account(new_account_id));
+    assert_eq!(split.memo_hash, None);
+    assert_eq!(
+        split.balance_after,
+        DEPOSIT_AMOUNT - TRANSFER_AMOUNT - SPLIT_AMOUNT
+    );
+
+    assert_eq!(
+        session.movements(15, 25),
+        vec![transfer.clone()],
+        "Only the movements within the range should be fed"
+    );
+    assert_eq!(session.movements(31, u64::MAX), vec![]);
+
+    // The new account's ledger starts with the split
+    session.account_id = Some(new_account_id);
+    let [received] = session.movements(0, u64::MAX).try_into().unwrap();
+    assert_eq!(received.direction, Direction::In);
+    assert_eq!(received.counterparty, Counterparty::Account(account_id));
+    assert_eq!(received.sequence, split.sequence);
+    assert_eq!(received.balance_after, SPLIT_AMOUNT);
+
+    // Accounts with private events don't leak their payees in the ledger
+    session.account_id = Some(account_id);
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetPrivateEvents {
+            private_events: true,
+        }],
+    );
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, TRANSFER_AMOUNT);
+    let movements = session.movements(30, u64::MAX);
+    let [_, transfer] = movements.as_slice() else {
+        panic!("The private transfer should be recorded");
+    };
+    assert_eq!(
+        transfer.counterparty,
+        Counterparty::Moonlight(Disclosure::Hashed(hash_receiver(
+            transfer.sequence,
+            &receiver
+        )))
+    );
+}
+
+/// Movements of funds into or out of the contract of at least an amount are
+/// fed across all accounts, in the order they happened.
+#[test]
+fn large_transfers() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const TRANSFER_AMOUNT: u64 = 300;
+    const SPLIT_AMOUNT: u64 = 400;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let account_id = session.create_account();
+    session.set_block_height(10);
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+    session.set_block_height(20);
+    let signers = session.all_signers();
+    let split =
+        session.signed_split_account(&signers, &[0, 1], 1, SPLIT_AMOUNT);
+    session
+        .try_split_account(EXECUTOR_INDEX, &split)
+        .expect("Splitting the account should succeed");
+    let new_account_id = account_id + 1;
+
+    session.set_block_height(30);
+    session.account_id = Some(new_account_id);
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+    session.set_block_height(40);
+    session.account_id = Some(account_id);
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, TRANSFER_AMOUNT);
+
+    let transfers = session.large_transfers(TRANSFER_AMOUNT, 0, u64::MAX);
+    let accounts: Vec<_> = transfers.iter().map(|t| t.account_id).collect();
+    let heights: Vec<_> =
+        transfers.iter().map(|t| t.movement.block_height).collect();
+    assert_eq!(
+        accounts,
+        vec![account_id, new_account_id, account_id],
+        "Movements across accounts should be fed in the order they happened"
+    );
+    assert_eq!(
+        heights,
+        vec![10, 30, 40],
+        "Movements between accounts should be left out"
+    );
+
+    let [deposit, _, transfer] = transfers.as_slice() else {
+        unreachable!();
+    };
+    assert_eq!(deposit.movement.counterparty, Counterparty::Depositor);
+    assert_eq!(
+        transfer.movement.counterparty,
+        Counterparty::Moonlight(Disclosure::Revealed(
+            session.pks[RECEIVER_INDEX]
+        ))
+    );
+
+    assert_eq!(
+        session
+            .large_transfers(TRANSFER_AMOUNT + 1, 0, u64::MAX)
+            .len(),
+        2,
+        "Movements below the amount should be left out"
+    );
+    assert_eq!(
+        session.large_transfers(0, 25, 35),
+        vec![transfers[1].clone()],
+        "Only the movements within the range should be fed"
+    );
+}
+
+/// The state of an account can be diffed between any two of its retained
+/// nonces, netting out the keys added and removed in between.
+#[test]
+fn state_diff() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const TRANSFER_AMOUNT: u64 = 300;
+    const REMOVED_INDEX: usize = NUM_KEYS - 1;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, TRANSFER_AMOUNT);
+
+    let removed = session.pks[REMOVED_INDEX];
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![
+            AccountChange::RemoveKey { key: removed },
+            AccountChange::SetThreshold {
+                threshold: THRESHOLD - 1,
+            },
+        ],
+    );
+    let signers = session.all_signers();
+    let change = session.signed_change_account(
+        &signers[..REMOVED_INDEX],
+        vec![AccountChange::AddKey { key: removed }],
+    );
+    session
+        .try_change_account(EXECUTOR_INDEX, &change)
+        .expect("Adding the key back should succeed");
+
+    let nonces: Vec<_> =
+        session.operations().iter().map(|op| op.nonce).collect();
+    assert_eq!(nonces, [0, 1, 2, 3], "Every operation should be recorded");
+
+    // The deposit made before the transfer counts towards the difference
+    let diff = session.state_diff(0, 2).expect("Both nonces are retained");
+    assert_eq!(diff.balance_before, 0);
+    assert_eq!(diff.balance_after, DEPOSIT_AMOUNT - TRANSFER_AMOUNT);
+    assert_eq!(
+        diff.balance_delta(),
+        (DEPOSIT_AMOUNT - TRANSFER_AMOUNT) as i128
+    );
+    assert_eq!(diff.threshold_before, THRESHOLD);
+    assert_eq!(diff.threshold_after, THRESHOLD - 1);
+    assert!(diff.added_keys.is_empty());
+    assert_eq!(diff.removed_keys, [removed]);
+
+    let diff = session.state_diff(2, 3).expect("Both nonces are retained");
+    assert_eq!(diff.added_keys, [removed]);
+    assert!(diff.removed_keys.is_empty());
+
+    let diff = session.state_diff(0, 3).expect("Both nonces are retained");
+    assert!(
+        diff.added_keys.is_empty() && diff.removed_keys.is_empty(),
+        "A key removed and added back should cancel out"
+    );
+
+    assert_eq!(session.state_diff(3, 1), None);
+    assert_eq!(session.state_diff(0, 4), None);
+}
+
+/// A change can be previewed before it's signed, reporting every violation
+/// it would be rejected with, and leaving the account untouched.
+#[test]
+fn simulate_change() {
+    const REMOVED_INDEX: usize = NUM_KEYS - 1;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    let unused_pk = PublicKey::from(&SecretKey::random(&mut rng));
+
+    session.create_account();
+
+    let removed = session.pks[REMOVED_INDEX];
+    let signers = session.all_signers();
+    let mut change = session.signed_change_account(
+        &signers,
+        vec![
+            AccountChange::RemoveKey { key: removed },
+            AccountChange::SetThreshold {
+                threshold: THRESHOLD - 1,
+            },
+        ],
+    );
+    change.signature = MultisigSignature::default();
+
+    let simulation = session.simulate_change(&change);
+    assert!(simulation.is_valid(), "The change should be accepted");
+    assert_eq!(simulation.keys.len(), NUM_KEYS - 1);
+    assert!(!simulation.keys.contains(&removed));
+    assert_eq!(simulation.threshold, THRESHOLD - 1);
+
+    let account = session.account();
+    assert_eq!(account.nonce, 0, "The account should be left untouched");
+    assert_eq!(account.threshold, THRESHOLD);
+
+    let mut change = session.signed_change_account(
+        &signers[..THRESHOLD as usize - 1],
+        vec![
+            AccountChange::RemoveKey { key: unused_pk },
+            AccountChange::SetThreshold { threshold: 0 },
+            AccountChange::AddKey { key: unused_pk },
+            AccountChange::AddKey { key: unused_pk },
+        ],
+    );
+    change.nonce += 1;
+
+    let simulation = session.simulate_change(&change);
+    assert_eq!(
+        simulation.violations,
+        [
+            Error::InvalidNonce,
+            Error::ThresholdNotMet,
+            Error::KeyNotUsed,
+            Error::ZeroThreshold,
+            Error::KeyAlreadyUsed,
+        ],
+        "Every violation should be reported once"
+    );
+    assert_eq!(simulation.keys.len(), NUM_KEYS + 1);
+    assert_eq!(simulation.threshold, 0);
+
+    expect_failure(session.try_change_account(0, &change), Error::InvalidNonce);
+
+    // Every kind of change is checked as it would be when applied
+    let change = session.signed_change_account(
+        &signers,
+        vec![
+            AccountChange::SetAlias {
+                alias: Some(String::from("42")),
+            },
+            AccountChange::AddTag {
+                tag: String::from("Grants"),
+            },
+            AccountChange::SetHistoryRetention {
+                records: Some(MAX_HISTORY_RETENTION + 1),
+            },
+            AccountChange::RemoveTag {
+                tag: String::from("grants"),
+            },
+        ],
+    );
+
+    let simulation = session.simulate_change(&change);
+    assert_eq!(
+        simulation.violations,
+        [
+            Error::InvalidAlias,
+            Error::InvalidTag,
+            Error::HistoryRetentionTooLong,
+            Error::InsufficientBalance,
+            Error::TagNotFound,
+        ],
+        "Every change should be checked"
+    );
+    expect_failure(session.try_change_account(0, &change), Error::InvalidAlias);
+}
+
+/// Successful calls are counted by entrypoint, along with the keys they
+/// verified, while failed calls are left uncounted.
+#[test]
+fn usage_stats() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const TRANSFER_AMOUNT: u64 = 300;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    let before = session.stats();
+    assert_eq!(before.calls_of("deposit"), 2);
+    assert_eq!(before.calls_of("transfer"), 0);
+
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, TRANSFER_AMOUNT);
+
+    let signers = session.all_signers();
+    let mut transfer =
+        session.signed_transfer(&signers, RECEIVER_INDEX, TRANSFER_AMOUNT);
+    transfer.nonce += 1;
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::InvalidNonce,
+    );
+
+    let after = session.stats();
+    assert_eq!(after.calls_of("transfer"), 1, "Failures aren't counted");
+    assert_eq!(after.total_calls(), before.total_calls() + 1);
+    assert_eq!(after.keys_verified, before.keys_verified + NUM_KEYS as u64);
+    assert_eq!(after.cosignatures_verified, before.cosignatures_verified);
+}
+
+/// Events carry an ID derived from the account, its nonce and the topic,
+/// telling apart the events of a topic emitted at the same nonce.
+#[test]
+fn event_ids() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const TRANSFER_AMOUNT: u64 = 300;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    session.create_account();
+    let account_id = session.account_id.unwrap();
+    let contract_id = CONTRACT_ID.to_bytes();
+
+    let mut deposit_ids = Vec::new();
+    for _ in 0..2 {
+        session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+        let [(_, data)] = session.events.as_slice() else {
+            panic!("A single deposit event should be emitted");
+        };
+        let event: DepositEvent = multisig_wallet::decode_event(data).unwrap();
+        deposit_ids.push(event.event_id);
+    }
+    assert_eq!(
+        deposit_ids,
+        [
+            event_id(&contract_id, "deposit", account_id, 0, 0),
+            event_id(&contract_id, "deposit", account_id, 0, 1),
+        ],
+        "Deposits at the same nonce should be told apart"
+    );
+
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, TRANSFER_AMOUNT);
+    let [(_, data)] = session.events.as_slice() else {
+        panic!("A single transfer event should be emitted");
+    };
+    let event: TransferEvent = multisig_wallet::decode_event(data).unwrap();
+    assert_eq!(
+        event.event_id,
+        event_id(&contract_id, "transfer", account_id, 1, 0)
+    );
+
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+    let [(_, data)] = session.events.as_slice() else {
+        panic!("A single deposit event should be emitted");
+    };
+    let event: DepositEvent = multisig_wallet::decode_event(data).unwrap();
+    assert_eq!(
+        event.event_id,
+        event_id(&contract_id, "deposit", account_id, 1, 0),
+        "The count should start anew at the next nonce"
+    );
+}
+
+/// Accounts retain only their last movements, and pay a storage fee to
+/// retain more than the default.
+#[test]
+fn history_retention() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RETENTION: u32 = 2;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    session.create_account();
+    for _ in 0..3 {
+        session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
     }
 
-    fn key_accounts(&mut self, key: PublicKey) -> Vec<u64> {
-        self.feeder_query("key_accounts", &key)
-            .expect("Feeding key accounts should succeed")
-    }
+    // Lowering the retention is free, and cuts the ledger down at once
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetHistoryRetention {
+            records: Some(RETENTION),
+        }],
+    );
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.history_retention, Some(Some(RETENTION)));
+    assert_eq!(event.storage_fee, 0);
+    let movements = session.movements(0, u64::MAX);
+    assert_eq!(movements.len(), RETENTION as usize);
+    assert_eq!(movements[1].balance_after, 3 * DEPOSIT_AMOUNT);
+
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+    let movements = session.movements(0, u64::MAX);
+    assert_eq!(
+        movements.len(),
+        RETENTION as usize,
+        "The oldest movement should be dropped"
+    );
+    assert_eq!(movements[1].balance_after, 4 * DEPOSIT_AMOUNT);
+
+    // Retaining more than the default is charged for, within the cap
+    let signers = session.all_signers();
+    let change_account = session.signed_change_account(
+        &signers,
+        vec![AccountChange::SetHistoryRetention {
+            records: Some(MAX_HISTORY_RETENTION + 1),
+        }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::HistoryRetentionTooLong,
+    );
+    let records = Some(DEFAULT_HISTORY_RETENTION + 1);
+    let change_account = session.signed_change_account(
+        &signers,
+        vec![AccountChange::SetHistoryRetention { records }],
+    );
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change_account),
+        Error::InsufficientBalance,
+    );
+
+    session.deposit(DEPOSITOR_INDEX, HISTORY_RECORD_FEE);
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetHistoryRetention { records }],
+    );
+    let event: ChangeAccountEvent =
+        multisig_wallet::decode_event(&session.events[0].1).unwrap();
+    assert_eq!(event.storage_fee, HISTORY_RECORD_FEE);
+    assert_eq!(session.account().balance, 4 * DEPOSIT_AMOUNT);
+    let movements = session.movements(0, u64::MAX);
+    let fee = movements.last().unwrap();
+    assert_eq!(fee.counterparty, Counterparty::StorageFee);
+    assert_eq!(fee.amount, HISTORY_RECORD_FEE);
+    assert_eq!(fee.sequence, event.sequence);
+
+    let custody: CustodyReport = session
+        .call(CONTRACT_ID, "custody", &())
+        .expect("Querying the custody should succeed")
+        .data;
+    assert_eq!(custody.storage_fees, HISTORY_RECORD_FEE);
+    assert_eq!(custody.delta, 0, "The fee should stay with the contract");
+}
+
+#[test]
+fn chain_binding() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const TRANSFERRER_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const TRANSFER_AMOUNT: u64 = 300;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+    assert_eq!(
+        session.account().chain_id,
+        CHAIN_ID,
+        "The account should be bound to the chain it was created on"
+    );
+
+    let signers = session.all_signers();
+    let transfer_on = |session: &mut ContractSession, chain_id: Option<u8>| {
+        let mut transfer =
+            session.signed_transfer(&signers, RECEIVER_INDEX, TRANSFER_AMOUNT);
+        transfer.chain_id = chain_id;
+        let msg = transfer.signature_msg();
+        (transfer.keys, transfer.signature) = session.sign(&signers, &msg);
+        transfer
+    };
+
+    let transfer = transfer_on(&mut session, Some(CHAIN_ID + 1));
+    expect_failure(
+        session.try_transfer(TRANSFERRER_INDEX, &transfer),
+        Error::WrongChain,
+    );
+
+    // Declaring the chain changes the message, so a signature of the same
+    // transfer for another chain doesn't carry over
+    let mut replayed = transfer_on(&mut session, None);
+    replayed.chain_id = Some(CHAIN_ID);
+    expect_failure(
+        session.try_transfer(TRANSFERRER_INDEX, &replayed),
+        Error::InvalidSignature,
+    );
+
+    let transfer = transfer_on(&mut session, Some(CHAIN_ID));
+    let payload = SigningPayload::new(&Operation::Transfer(transfer.clone()))
+        .expect("Building the payload should succeed");
+    assert_eq!(
+        payload.message(),
+        transfer.signature_msg(),
+        "The payload should rebuild the message with the chain"
+    );
+    session
+        .try_transfer(TRANSFERRER_INDEX, &transfer)
+        .expect("Transferring on the account's chain should succeed");
+
+    // Operations declaring no chain are accepted on any
+    let transfer = transfer_on(&mut session, None);
+    session
+        .try_transfer(TRANSFERRER_INDEX, &transfer)
+        .expect("Transferring without declaring a chain should succeed");
+
+    let mut change = session.signed_change_account(
+        &signers,
+        vec![AccountChange::SetDescription {
+            description: String::from("bound"),
+        }],
+    );
+    change.chain_id = Some(CHAIN_ID + 1);
+    let msg = change.signature_msg();
+    (change.keys, change.signature) = session.sign(&signers, &msg);
+    expect_failure(
+        session.try_change_account(TRANSFERRER_INDEX, &change),
+        Error::WrongChain,
+    );
+}
+
+/// Signers racing with operations sharing a nonce: whichever is executed first
+/// wins, and every other one fails with an invalid nonce, having to be signed
+/// anew.
+#[test]
+fn concurrent_signers() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const OTHER_RECEIVER_INDEX: usize = 4;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    // Two disjoint subsets of the signers, each meeting the threshold
+    let first: Vec<usize> = (0..THRESHOLD as usize).collect();
+    let second: Vec<usize> = (THRESHOLD as usize..NUM_KEYS).collect();
+
+    // Two transfers, prepared at the same time by each subset
+    let winner = session.signed_transfer(&first, RECEIVER_INDEX, 100);
+    let loser = session.signed_transfer(&second, OTHER_RECEIVER_INDEX, 200);
+    assert_eq!(winner.nonce, loser.nonce, "Both should use the same nonce");
+
+    session
+        .try_transfer(EXECUTOR_INDEX, &winner)
+        .expect("The first transfer executed should succeed");
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &loser),
+        Error::InvalidNonce,
+    );
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &winner),
+        Error::InvalidNonce,
+    );
+
+    let account = session.account();
+    assert_eq!(
+        account.balance,
+        DEPOSIT_AMOUNT - winner.amount,
+        "Only the winning transfer should be paid out"
+    );
+    assert_eq!(account.nonce, 1, "Only the winner should bump the nonce");
+
+    // The losing subset signs the same transfer anew, with the next nonce
+    let retried = session.signed_transfer(&second, OTHER_RECEIVER_INDEX, 200);
+    session
+        .try_transfer(EXECUTOR_INDEX, &retried)
+        .expect("The re-signed transfer should succeed");
+
+    // A change to the account racing with a transfer, both sharing a nonce.
+    // The change removing a key of the transfer's signers wins, and the
+    // transfer can't be executed with the nonce it was signed for.
+    let transfer = session.signed_transfer(&second, RECEIVER_INDEX, 100);
+    let change = session.signed_change_account(
+        &first,
+        vec![AccountChange::RemoveKey {
+            key: session.pks[NUM_KEYS - 1],
+        }],
+    );
+    assert_eq!(
+        transfer.nonce, change.nonce,
+        "Both should use the same nonce"
+    );
+
+    session
+        .try_change_account(EXECUTOR_INDEX, &change)
+        .expect("The change executed first should succeed");
+    expect_failure(
+        session.try_transfer(EXECUTOR_INDEX, &transfer),
+        Error::InvalidNonce,
+    );
+
+    // The other way around, a transfer signed under the current threshold
+    // wins over a change raising it, which must then be signed anew.
+    let transfer = session.signed_transfer(&first, RECEIVER_INDEX, 100);
+    let change = session.signed_change_account(
+        &first,
+        vec![AccountChange::SetThreshold {
+            threshold: THRESHOLD + 1,
+        }],
+    );
+
+    session
+        .try_transfer(EXECUTOR_INDEX, &transfer)
+        .expect("The transfer executed first should succeed");
+    expect_failure(
+        session.try_change_account(EXECUTOR_INDEX, &change),
+        Error::InvalidNonce,
+    );
+
+    let account = session.account();
+    assert_eq!(account.threshold, THRESHOLD, "The threshold should be kept");
+    assert_eq!(account.nonce, 4, "Only the winners should bump the nonce");
 }
 
 #[test]
-fn create_account() {
+fn genesis() {
+    const GENESIS_BALANCE: u64 = 1_000;
+
     let mut rng = StdRng::seed_from_u64(RNG_SEED);
-    let mut session = ContractSession::new(&mut rng);
+    let mut session = ContractSession::with_genesis(&mut rng, |pks| Genesis {
+        accounts: vec![
+            GenesisAccount {
+                keys: pks.to_vec(),
+                threshold: THRESHOLD,
+                description: String::from(DESCRIPTION),
+                balance: GENESIS_BALANCE,
+            },
+            GenesisAccount {
+                keys: pks[..2].to_vec(),
+                threshold: 1,
+                description: String::new(),
+                balance: 0,
+            },
+        ],
+        custody_cap: None,
+        min_deposit: None,
+        governance: None,
+    });
 
-    session.create_account();
+    session.account_id = Some(1);
 
     let account = session.account();
-    let account_keys = session.account_keys();
-
     assert_eq!(
-        account_keys.len(),
-        session.pks.len(),
-        "Equal number of keys should be inserted"
+        account.balance, GENESIS_BALANCE,
+        "Account should start with its genesis balance"
     );
-
+    assert_eq!(account.threshold, THRESHOLD);
+    assert_eq!(account.description, DESCRIPTION);
+    let account_keys = session.account_keys();
+    assert_eq!(account_keys.len(), session.pks.len());
     for account_key in account_keys {
-        let mut contains = false;
-        for pk in &session.pks {
-            if account_key == *pk {
-                contains = true;
-                break;
-            }
-        }
         assert!(
-            contains,
-            "Account keys should be the ones used in creating it"
-        );
-    }
-
-    let account_id = session.account_id.unwrap();
-
-    for key in session.pks.clone() {
-        let ids = session.key_accounts(key);
-        assert_eq!(
-            ids.len(),
-            1,
-            "The public key should only be used by one account"
-        );
-        assert_eq!(
-            ids[0], account_id,
-            "The ID should be of the created account"
+            session.pks.contains(&account_key),
+            "Account should be owned by its genesis keys"
         );
     }
 
-    assert_eq!(account.balance, 0, "Balance should be zero");
-    assert_eq!(account.threshold, THRESHOLD, "Threshold should be as set");
     assert_eq!(
-        account.description, DESCRIPTION,
-        "Description should be as set"
+        session.key_accounts(session.pks[0]),
+        vec![1, 2],
+        "Keys should be indexed to all genesis accounts using them"
     );
+    assert_eq!(session.key_accounts(session.pks[2]), vec![1]);
+
+    let id = session.create_account();
+    assert_eq!(id, 3, "New accounts should follow the genesis accounts");
 }
 
 #[test]
-fn deposit() {
+fn custody() {
     const DEPOSITOR_INDEX: usize = 1;
     const DEPOSIT_AMOUNT: u64 = 1_000;
+    const TRANSFERRER_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const TRANSFER_AMOUNT: u64 = 300;
+    const GENESIS_BALANCE: u64 = 500;
+
+    let custody = |session: &mut ContractSession| -> CustodyReport {
+        session
+            .call(CONTRACT_ID, "custody", &())
+            .expect("Querying the custody should succeed")
+            .data
+    };
 
     let mut rng = StdRng::seed_from_u64(RNG_SEED);
-    let mut session = ContractSession::new(&mut rng);
+    let mut session = ContractSession::new_mocked(&mut rng);
 
     session.create_account();
-    let account = session.account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+    session.transfer(TRANSFERRER_INDEX, RECEIVER_INDEX, TRANSFER_AMOUNT);
 
     assert_eq!(
-        account.balance, 0,
-        "Account should have zero initial balance"
+        custody(&mut session),
+        CustodyReport {
+            total_balance: DEPOSIT_AMOUNT - TRANSFER_AMOUNT,
+            pending_withdrawals: 0,
+            storage_fees: 0,
+            held: DEPOSIT_AMOUNT - TRANSFER_AMOUNT,
+            delta: 0,
+        },
+        "The contract should hold exactly the balances of its accounts"
     );
 
-    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
-    let account = session.account();
+    // The mock doesn't fund genesis balances, which the contract then
+    // accounts for without holding
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::with_genesis(&mut rng, |pks| Genesis {
+        accounts: vec![GenesisAccount {
+            keys: pks.to_vec(),
+            threshold: THRESHOLD,
+            description: String::from(DESCRIPTION),
+            balance: GENESIS_BALANCE,
+        }],
+        custody_cap: None,
+        min_deposit: None,
+        governance: None,
+    });
 
     assert_eq!(
-        account.balance, DEPOSIT_AMOUNT,
-        "Account should have the amount deposited"
+        custody(&mut session),
+        CustodyReport {
+            total_balance: GENESIS_BALANCE,
+            pending_withdrawals: 0,
+            storage_fees: 0,
+            held: 0,
+            delta: -(GENESIS_BALANCE as i128),
+        },
+        "The drift should show as a negative delta"
     );
 }
 
 #[test]
-fn transfer() {
+fn migration() {
     const DEPOSITOR_INDEX: usize = 1;
-    const DEPOSIT_AMOUNT: u64 = 1_000;
-    const TRANSFERRER_INDEX: usize = 3;
+    const EXECUTOR_INDEX: usize = 3;
     const RECEIVER_INDEX: usize = 2;
-    const TRANSFER_AMOUNT: u64 = DEPOSIT_AMOUNT / 2;
+    const AMOUNT: u64 = 1_000;
 
     let mut rng = StdRng::seed_from_u64(RNG_SEED);
-    let mut session = ContractSession::new(&mut rng);
+    let mut session = ContractSession::new_mocked(&mut rng);
 
     session.create_account();
-    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+    session.deposit(DEPOSITOR_INDEX, AMOUNT);
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, AMOUNT / 4);
+    session.change_account(
+        EXECUTOR_INDEX,
+        vec![AccountChange::SetDescription {
+            description: String::from("before migration"),
+        }],
+    );
+    session
+        .try_create_account(&CreateAccount {
+            keys: session.pks[..2].to_vec(),
+            threshold: 1,
+            description: String::new(),
+            alias: None,
+            key_weights: Vec::new(),
+            key_roles: Vec::new(),
+        })
+        .expect("Creating another account should succeed");
+    session
+        .try_register_template(&AccountTemplate {
+            threshold: 1,
+            description: String::new(),
+            private_events: false,
+            velocity_limit: None,
+            cosigners: Vec::new(),
+            guardians: Vec::new(),
+        })
+        .expect("Registering a template should succeed");
 
-    let account = session.account();
-    let balance = session.balance(session.pks[RECEIVER_INDEX]);
+    let contract_balance = |session: &mut ContractSession| -> u64 {
+        session
+            .call(TRANSFER_CONTRACT, "contract_balance", &CONTRACT_ID)
+            .expect("Querying the contract's balance should succeed")
+            .data
+    };
+    let event_sequence = |session: &mut ContractSession| -> u64 {
+        session
+            .call(CONTRACT_ID, "event_sequence", &())
+            .expect("Querying the event sequence should succeed")
+            .data
+    };
+    let balance_before = contract_balance(&mut session);
+    let account_before = session.account();
+    let sequence_before = event_sequence(&mut session);
+
+    // Here the contract is migrated to the same build, but any version that
+    // implements `migrate` is carried over in the same way
+    let mut session = session.migrate(CONTRACT_BYTECODE);
+
+    // Balances, nonces, thresholds, descriptions and keys of all accounts,
+    // as well as the index of keys to accounts and the templates, must carry
+    // over exactly
+    session.check_model(&Ok(()), Ok(()));
     assert_eq!(
-        account.balance, DEPOSIT_AMOUNT,
-        "Account should have the amount deposited",
+        session.account(),
+        account_before,
+        "The account should be the same after migrating"
     );
     assert_eq!(
-        balance, INITIAL_BALANCE,
-        "The receiver account should, at first, just have its initial balance"
+        contract_balance(&mut session),
+        balance_before,
+        "The contract should keep its balance after migrating"
     );
-
-    session.transfer(TRANSFERRER_INDEX, RECEIVER_INDEX, TRANSFER_AMOUNT);
-
-    let account = session.account();
-    let balance = session.balance(session.pks[RECEIVER_INDEX]);
     assert_eq!(
-        account.balance,
-        DEPOSIT_AMOUNT - TRANSFER_AMOUNT,
-        "Account should have the amount deposited minus the transferred amount"
+        event_sequence(&mut session),
+        sequence_before,
+        "The event sequence should carry over"
     );
-    assert_eq!(
-        balance,
-        INITIAL_BALANCE + TRANSFER_AMOUNT,
-        "The receiver account should, after the transfer, have its initial balance plus the transferred amount"
+
+    // The migrated contract picks up where the previous one left off
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, AMOUNT / 4);
+    let id = session.create_account();
+    assert_eq!(id, 3, "New accounts should follow the migrated accounts");
+
+    expect_failure(
+        session
+            .call::<_, ()>(
+                CONTRACT_ID,
+                "migrate",
+                &Migration {
+                    accounts: vec![],
+                    event_sequence: 0,
+                    compromised_keys: vec![],
+                    compromised_cosigners: vec![],
+                    custody_cap: None,
+                    min_deposit: None,
+                    templates: vec![],
+                    withdrawals: vec![],
+                    withdrawal_count: 0,
+                    storage_fees: 0,
+                    governance: None,
+                    config: ContractConfig::DEFAULT,
+                },
+            )
+            .map_err(contract_error),
+        Error::MigrationNotAllowed,
     );
 }
 
+/// Deposits taking the total balance of the contract past its custody cap are
+/// refused, counting the balances of the genesis accounts.
 #[test]
-fn change_account() {
-    const CHANGER_INDEX: usize = 1;
-    const REMOVE_INDEX: usize = 4;
-    const NEW_THRESHOLD: u32 = THRESHOLD + 1;
-    const NEW_DESCRIPTION: &str = "new-description";
+fn custody_cap() {
+    const GENESIS_BALANCE: u64 = 1_000;
+    const CUSTODY_CAP: u64 = 1_500;
+    const DEPOSITOR_INDEX: usize = 1;
+    const EXECUTOR_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
 
     let mut rng = StdRng::seed_from_u64(RNG_SEED);
-    let mut session = ContractSession::new(&mut rng);
+    let mut session = ContractSession::with_genesis(&mut rng, |pks| Genesis {
+        accounts: vec![GenesisAccount {
+            keys: pks.to_vec(),
+            threshold: THRESHOLD,
+            description: String::from(DESCRIPTION),
+            balance: GENESIS_BALANCE,
+        }],
+        custody_cap: Some(CUSTODY_CAP),
+        min_deposit: None,
+        governance: None,
+    });
+    session.account_id = Some(1);
 
-    let new_sk = SecretKey::random(&mut rng);
-    let new_pk = PublicKey::from(&new_sk);
+    session.deposit(DEPOSITOR_INDEX, CUSTODY_CAP - GENESIS_BALANCE);
+    expect_failure(
+        session.try_deposit(DEPOSITOR_INDEX, 1),
+        Error::CustodyCapExceeded,
+    );
 
-    session.create_account();
+    // Funds leaving the contract make room for new ones
+    session.transfer(EXECUTOR_INDEX, RECEIVER_INDEX, 100);
+    session.deposit(DEPOSITOR_INDEX, 100);
 
-    let account = session.account();
-    assert_eq!(account.balance, 0, "Balance should be zero");
-    assert_eq!(account.threshold, THRESHOLD, "Threshold should be as set");
+    let mut session = session.migrate(CONTRACT_BYTECODE);
+    let custody_cap: Option<u64> = session
+        .call(CONTRACT_ID, "custody_cap", &())
+        .expect("Querying the custody cap should succeed")
+        .data;
+    assert_eq!(custody_cap, Some(CUSTODY_CAP), "The cap should be migrated");
+}
+
+/// Deposits below the minimum deposit of the contract are refused.
+#[test]
+fn min_deposit() {
+    const MIN_DEPOSIT: u64 = 1_000;
+    const DEPOSITOR_INDEX: usize = 1;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::with_genesis(&mut rng, |pks| Genesis {
+        accounts: vec![GenesisAccount {
+            keys: pks.to_vec(),
+            threshold: THRESHOLD,
+            description: String::from(DESCRIPTION),
+            balance: 0,
+        }],
+        custody_cap: None,
+        min_deposit: Some(MIN_DEPOSIT),
+        governance: None,
+    });
+    session.account_id = Some(1);
+
+    expect_failure(
+        session.try_deposit(DEPOSITOR_INDEX, MIN_DEPOSIT - 1),
+        Error::DepositTooSmall,
+    );
+    session.deposit(DEPOSITOR_INDEX, MIN_DEPOSIT);
+
+    let mut session = session.migrate(CONTRACT_BYTECODE);
+    let min_deposit: Option<u64> = session
+        .call(CONTRACT_ID, "min_deposit", &())
+        .expect("Querying the minimum deposit should succeed")
+        .data;
     assert_eq!(
-        account.description, DESCRIPTION,
-        "Description should be as set"
+        min_deposit,
+        Some(MIN_DEPOSIT),
+        "The minimum should be migrated"
     );
+    expect_failure(
+        session.try_deposit(DEPOSITOR_INDEX, MIN_DEPOSIT - 1),
+        Error::DepositTooSmall,
+    );
+}
 
-    let mut pks = session.pks.clone();
-    let account_keys = session.account_keys();
+#[test]
+fn key_derivation() {
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon \
+                            abandon abandon abandon abandon abandon about";
+
+    let seed = keys::seed(MNEMONIC, "").expect("Mnemonic should be valid");
+    let sks = keys::derive_secret_keys(&seed, NUM_KEYS as u8);
 
+    assert_eq!(sks.len(), NUM_KEYS);
     assert_eq!(
-        account_keys.len(),
-        pks.len(),
-        "Equal number of keys should be inserted"
+        sks,
+        keys::derive_secret_keys(&seed, NUM_KEYS as u8),
+        "Derivation should be deterministic"
     );
+    assert_eq!(sks[3], keys::derive_secret_key(&seed, 3));
 
-    for account_key in account_keys {
-        let mut contains = false;
-        for pk in &pks {
-            if account_key == *pk {
-                contains = true;
-                break;
-            }
-        }
-        assert!(
-            contains,
-            "Account keys should be the ones used in creating it"
-        );
-    }
-
-    let account_id = session.account_id.unwrap();
+    let pks: BTreeSet<_> = sks
+        .iter()
+        .map(|sk| PublicKey::from(sk).to_raw_bytes())
+        .collect();
+    assert_eq!(pks.len(), NUM_KEYS, "Derived keys should be distinct");
 
-    for key in pks.clone() {
-        let ids = session.key_accounts(key);
-        assert_eq!(
-            ids.len(),
-            1,
-            "The public key should only be used by one account"
-        );
-        assert_eq!(
-            ids[0], account_id,
-            "The ID should be of the created account"
-        );
-    }
+    let other_seed =
+        keys::seed(MNEMONIC, "passphrase").expect("Mnemonic should be valid");
+    assert_ne!(
+        keys::derive_secret_key(&other_seed, 0),
+        sks[0],
+        "The passphrase should change the derived keys"
+    );
 
-    session.change_account(
-        CHANGER_INDEX,
-        vec![
-            AccountChange::SetThreshold {
-                threshold: NEW_THRESHOLD,
-            },
-            AccountChange::RemoveKey {
-                key: session.pks[REMOVE_INDEX],
-            },
-            AccountChange::AddKey { key: new_pk },
-            AccountChange::SetDescription {
-                description: String::from(NEW_DESCRIPTION),
-            },
-        ],
+    assert!(
+        keys::seed("abandon abandon abandon", "").is_err(),
+        "Invalid mnemonics should be rejected"
     );
+}
 
-    let account = session.account();
-    assert_eq!(account.balance, 0, "Balance should be zero");
+/// Keys, signatures, account IDs and messages decode from the textual forms
+/// they encode to, which are strict about what they accept.
+#[test]
+fn canonical_encodings() {
+    use dusk_bytes::Serializable;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let sk = SecretKey::random(&mut rng);
+    let pk = PublicKey::from(&sk);
+
+    let encoded = encoding::encode_public_key(&pk);
+    assert_eq!(encoded, bs58::encode(pk.to_bytes()).into_string());
+    assert_eq!(encoding::decode_public_key(&encoded), Ok(pk));
     assert_eq!(
-        account.threshold, NEW_THRESHOLD,
-        "Threshold should be as set"
+        encoding::decode_public_key(&format!(" {encoded}\n")),
+        Ok(pk),
+        "Surrounding whitespace should be ignored"
     );
     assert_eq!(
-        account.description, NEW_DESCRIPTION,
-        "Description should be as set"
+        encoding::decode_public_key("0OIl"),
+        Err(encoding::EncodingError::InvalidBase58)
+    );
+    assert_eq!(
+        encoding::decode_public_key(&encoded[1..]),
+        Err(encoding::EncodingError::InvalidLength)
     );
 
-    let removed_pk = pks.remove(REMOVE_INDEX);
-    pks.push(new_pk);
-    let account_keys = session.account_keys();
+    let msg = b"multisig message".to_vec();
+    let signature = sk.sign_multisig(&pk, &msg);
+    let encoded = encoding::encode_multisig_signature(&signature);
+    assert_eq!(encoding::decode_multisig_signature(&encoded), Ok(signature));
+
+    let encoded = encoding::encode_message(&msg);
+    assert_eq!(encoding::decode_message(&encoded), Ok(msg.clone()));
+    assert_eq!(encoding::decode_message(&format!("0x{encoded}")), Ok(msg));
 
+    assert_eq!(encoding::encode_account_id(42), "42");
+    assert_eq!(encoding::decode_account_id("42"), Ok(42));
     assert_eq!(
-        account_keys.len(),
-        pks.len(),
-        "There should be the same number of keys after change"
+        encoding::decode_account_id("+42"),
+        Err(encoding::EncodingError::InvalidAccountId)
     );
 
-    for account_key in account_keys {
-        let mut contains = false;
-        for pk in &pks {
-            if account_key == *pk {
-                contains = true;
-                break;
-            }
-        }
+    let cosigner = CosignerKey::Ed25519([7; 32]);
+    let encoded = encoding::encode_cosigner_key(&cosigner);
+    assert_eq!(encoded, cosigner.to_string());
+    assert_eq!(encoding::decode_cosigner_key(&encoded), Ok(cosigner));
+    assert_eq!(
+        encoding::decode_cosigner_key("ecdsa:00"),
+        Err(encoding::EncodingError::UnknownScheme)
+    );
+}
+
+#[test]
+fn hardware_wallet_payload() {
+    const DEPOSITOR_INDEX: usize = 1;
+    const DEPOSIT_AMOUNT: u64 = 1_000;
+    const TRANSFERRER_INDEX: usize = 3;
+    const RECEIVER_INDEX: usize = 2;
+    const TRANSFER_AMOUNT: u64 = DEPOSIT_AMOUNT / 2;
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    session.create_account();
+    session.deposit(DEPOSITOR_INDEX, DEPOSIT_AMOUNT);
+
+    let mut operation = Operation::Transfer(session.signed_transfer(
+        &[],
+        RECEIVER_INDEX,
+        TRANSFER_AMOUNT,
+    ));
+
+    let payload = SigningPayload::new(&operation)
+        .expect("Building the payload should succeed");
+    let chunks = payload.chunks(MAX_CHUNK_SIZE);
+    assert!(
+        chunks.iter().all(|chunk| chunk.len() <= MAX_CHUNK_SIZE),
+        "Chunks should fit the maximum size"
+    );
+
+    // Each device decodes the payload from its chunks, and signs the message
+    // it rebuilds from the fields
+    let partials: Vec<PartialSignature> = session
+        .all_signers()
+        .into_iter()
+        .map(|i| {
+            let payload = SigningPayload::from_chunks(&chunks)
+                .expect("Decoding the payload should succeed");
+            let key = session.pks[i];
+            let signature =
+                session.sks[i].sign_multisig(&key, &payload.message());
+            PartialSignature { key, signature }
+        })
+        .collect();
+
+    for partial in &partials {
         assert!(
-            contains,
-            "Account keys should be the ones used in creating it"
+            payload.verify(&operation, partial),
+            "Signatures made by the device should verify"
         );
     }
 
-    let account_id = session.account_id.unwrap();
+    let other = Operation::Transfer(session.signed_transfer(
+        &[],
+        RECEIVER_INDEX,
+        TRANSFER_AMOUNT + 1,
+    ));
+    assert!(
+        !payload.verify(&other, &partials[0]),
+        "The payload should not verify for a different operation"
+    );
 
-    for key in pks.clone() {
-        let ids = session.key_accounts(key);
-        assert_eq!(
-            ids.len(),
-            1,
-            "The public key should only be used by one account"
-        );
-        assert_eq!(
-            ids[0], account_id,
-            "The ID should be of the created account"
-        );
-    }
+    operation.aggregate(&partials);
+    let Operation::Transfer(transfer) = operation else {
+        unreachable!("The operation is a transfer");
+    };
+    session
+        .try_transfer(TRANSFERRER_INDEX, &transfer)
+        .expect("Transferring should succeed");
 
     assert_eq!(
-        session.key_accounts(removed_pk).len(),
-        0,
-        "The removed key should have no accounts"
+        session.account().balance,
+        DEPOSIT_AMOUNT - TRANSFER_AMOUNT,
+        "Account should have the amount deposited minus the transferred amount"
     );
 }
 
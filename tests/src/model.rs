@@ -0,0 +1,3494 @@
+//! A reference model of the contract, implementing its rules in plain Rust.
+//!
+//! Every operation performed through a [`crate::ContractSession`] is also
+//! applied to its model, and both the outcome and the resulting state of the
+//! contract are diffed against it. This way each test scenario also checks
+//! that the wasm contract behaves as its rules intend.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+
+use bls::{MultisigPublicKey, MultisigSignature, PublicKey, Signature};
+use multisig_contract_types::*;
+
+/// A public key, ordered by its raw bytes so it can be kept in sets.
+#[derive(Debug, Clone, Copy)]
+pub struct Key(pub PublicKey);
+
+impl PartialEq for Key {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_raw_bytes().eq(&other.0.to_raw_bytes())
+    }
+}
+
+impl Eq for Key {}
+
+impl PartialOrd for Key {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Key {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.to_raw_bytes().cmp(&other.0.to_raw_bytes())
+    }
+}
+
+/// The model of an account.
+#[derive(Debug, Clone)]
+pub struct ModelAccount {
+    pub keys: BTreeSet<Key>,
+    pub threshold: u32,
+    pub nonce: u64,
+    pub balance: u64,
+    pub description: String,
+    pub private_events: bool,
+    pub duress_keys: BTreeSet<Key>,
+    pub cosigners: BTreeSet<CosignerKey>,
+    pub guardians: BTreeSet<Key>,
+    pub observers: BTreeSet<Key>,
+    pub frozen: bool,
+    pub group_key: Option<PublicKey>,
+    /// The whitelisted receivers, with the height they're usable from.
+    pub whitelist: BTreeMap<Key, u64>,
+    pub whitelist_enforced: bool,
+    pub velocity_limit: Option<VelocityLimit>,
+    pub locked: bool,
+    pub archived: bool,
+    pub claimable_withdrawals: bool,
+    pub history_retention: Option<u32>,
+    pub compliance: Option<ComplianceControl>,
+    pub on_hold: bool,
+    pub license_policy: Option<LicensePolicy>,
+    pub operation_thresholds: BTreeMap<OperationKind, u32>,
+    pub transfer_tiers: Vec<TransferTier>,
+    pub alias: Option<String>,
+    pub merged_into: Option<u64>,
+    pub chain_id: u8,
+    pub balance_cap: Option<u64>,
+    pub tags: Vec<String>,
+    pub liveness_policy: Option<LivenessPolicy>,
+    /// The height each key last attested it's alive at, under a liveness
+    /// policy.
+    pub liveness: BTreeMap<Key, u64>,
+    pub supermajority: Option<Supermajority>,
+    pub key_addition_policy: Option<KeyAdditionPolicy>,
+    pub transfer_window: Option<TransferWindow>,
+    pub categories: Vec<SpendingCategory>,
+    /// The start of the current window of each spending category, and the
+    /// amount transferred under it within it.
+    pub category_spending: BTreeMap<String, (u64, u64)>,
+    pub child_budgets: Vec<ChildBudget>,
+    /// The start of the current window of the budget of each child, the
+    /// amount it drew within it and the amount it holds.
+    pub budget_draws: BTreeMap<u64, (u64, u64, u64)>,
+    /// The keys added under a delay, with the height they're usable from.
+    pub pending_keys: BTreeMap<Key, u64>,
+    /// The keys that lapse, with the last height they can sign at.
+    pub key_expiries: BTreeMap<Key, u64>,
+    /// The keys weighing other than one, with their weight.
+    pub key_weights: BTreeMap<Key, u32>,
+    /// The keys that aren't admins, with their role.
+    pub key_roles: BTreeMap<Key, KeyRole>,
+    /// The keys with an allowance left, with what is left of it.
+    pub allowances: BTreeMap<Key, u64>,
+    /// The number of operations each key signed, and the height it last
+    /// signed at.
+    pub key_usage: BTreeMap<Key, (u64, u64)>,
+    /// The checkpoints recorded, less the sequence numbers of their events,
+    /// which the model doesn't keep.
+    pub checkpoints: Vec<(u64, u64, [u8; 32], u64)>,
+    /// The delegation registered by each key, with its nonce.
+    pub delegations: BTreeMap<Key, KeyDelegation>,
+    /// The recovery policy, if the guardians can recover the account.
+    pub recovery_policy: Option<RecoveryPolicy>,
+    /// The recovery started by the guardians, if any.
+    pub pending_recovery: Option<PendingRecovery>,
+    /// The number of recoveries started.
+    pub recovery_nonce: u64,
+    /// The inactivity policy, if a fallback key can claim the account.
+    pub inactivity_policy: Option<InactivityPolicy>,
+    /// The height the account last performed an operation at.
+    pub last_active: u64,
+    /// The queue policy, if transfers over an amount must be queued.
+    pub queue_policy: Option<QueuePolicy>,
+    /// The transfers queued, by the nonce each used up.
+    pub queued_transfers: BTreeMap<u64, QueuedTransfer>,
+}
+
+impl ModelAccount {
+    /// The number of signers the keys and co-signers that may sign for the
+    /// account count for.
+    fn signers(&self) -> usize {
+        self.keys
+            .iter()
+            .map(|key| self.weight_of(*key))
+            .sum::<usize>()
+            + self.cosigners.len()
+    }
+
+    /// Whether the admin keys and co-signers of the account can meet its
+    /// threshold for changes between them.
+    /// Whether the admin keys and co-signers of the account can meet the
+    /// threshold of changes to it on their own.
+    fn admins_meet_threshold(&self) -> bool {
+        let admins = self
+            .keys
+            .iter()
+            .filter(|key| !self.key_roles.contains_key(key))
+            .map(|key| self.weight_of(*key))
+            .sum::<usize>();
+        admins + self.cosigners.len()
+            >= self.threshold_of(OperationKind::ChangeAccount) as usize
+    }
+
+    /// The number of signers a key of the account counts for.
+    fn weight_of(&self, key: Key) -> usize {
+        self.key_weights
+            .get(&key)
+            .map_or(1, |weight| *weight as usize)
+    }
+
+    /// The number of signers the given keys and number of co-signers count
+    /// for at the given height, a key counting for the key of the account it
+    /// signs for, and a duress key for one.
+    fn signed_weight(
+        &self,
+        keys: &[PublicKey],
+        cosigners: usize,
+        block_height: u64,
+    ) -> usize {
+        keys.iter()
+            .map(|key| match self.duress_keys.contains(&Key(*key)) {
+                true => 1,
+                false => self
+                    .principal_of(Key(*key), block_height)
+                    .map_or(1, |principal| self.weight_of(principal)),
+            })
+            .sum::<usize>()
+            + cosigners
+    }
+
+    /// The number of keys operations of the given kind need, the threshold
+    /// set for the kind or else the threshold of the account - and no fewer
+    /// than transfers, for those moving funds out, and than changes, for
+    /// transfers made together with one.
+    fn threshold_of(&self, op: OperationKind) -> u32 {
+        let set = |op| {
+            self.operation_thresholds
+                .get(&op)
+                .copied()
+                .unwrap_or(self.threshold)
+        };
+        match op {
+            OperationKind::SplitAccount
+            | OperationKind::MergeAccounts
+            | OperationKind::Payroll => {
+                set(op).max(set(OperationKind::Transfer))
+            }
+            OperationKind::TransferAndChange => set(op)
+                .max(set(OperationKind::Transfer))
+                .max(set(OperationKind::ChangeAccount)),
+            _ => set(op),
+        }
+    }
+
+    /// The number of keys an operation of the given kind moving the amount
+    /// out needs, if the amount falls in a tier - the tier's for transfers,
+    /// and no fewer than the kind's own for others.
+    fn tier_of(&self, op: OperationKind, amount: u64) -> Option<u32> {
+        let tier = TransferTier::threshold_for(&self.transfer_tiers, amount)?;
+        Some(match op {
+            OperationKind::Transfer => tier,
+            _ => tier.max(self.threshold_of(op)),
+        })
+    }
+
+    /// The highest number of keys operations of any kind need, transfers
+    /// falling in a tier included.
+    fn max_threshold(&self) -> u32 {
+        self.operation_thresholds
+            .values()
+            .copied()
+            .chain(self.transfer_tiers.iter().map(|tier| tier.threshold))
+            .fold(self.threshold, u32::max)
+    }
+
+    /// The number of signers an operation held to the given threshold needs
+    /// at the given height, lowered past its stale keys under a liveness
+    /// policy.
+    fn required_signers(&self, threshold: u32, block_height: u64) -> usize {
+        let threshold = threshold as usize;
+        let Some(policy) = self.liveness_policy else {
+            return threshold;
+        };
+
+        let live = self
+            .liveness
+            .iter()
+            .filter(|(_, at)| at.saturating_add(policy.window) >= block_height)
+            .map(|(key, _)| self.weight_of(*key))
+            .sum::<usize>();
+
+        let min_threshold = threshold.min(policy.min_threshold as usize);
+        threshold
+            .min(live + self.cosigners.len())
+            .max(min_threshold)
+    }
+
+    /// Whether a change signed by the given number of signers may change the
+    /// threshold and the supermajority of the account.
+    fn supermajority_met(&self, signed: usize) -> bool {
+        self.supermajority.is_none_or(|supermajority| {
+            let required = supermajority
+                .of(self.signers())
+                .max(self.threshold as usize);
+            signed >= required
+        })
+    }
+
+    /// Drops the oldest checkpoints beyond the number of records the account
+    /// retains.
+    fn retain_checkpoints(&mut self) {
+        let retention =
+            self.history_retention.unwrap_or(DEFAULT_HISTORY_RETENTION)
+                as usize;
+        let excess = self.checkpoints.len().saturating_sub(retention);
+        self.checkpoints.drain(..excess);
+    }
+
+    /// Fails if the account restricts its receivers, by a whitelist or a
+    /// license policy, which an account its funds are handed to wouldn't be
+    /// held to.
+    fn check_unrestricted(&self) -> Result<(), Error> {
+        if self.whitelist_enforced {
+            return Err(Error::ReceiverNotWhitelisted);
+        }
+        if self.license_policy.is_some() {
+            return Err(Error::ReceiverNotLicensed);
+        }
+        Ok(())
+    }
+
+    /// Fails if the account holds back transfers of the amount, which must
+    /// then be queued.
+    fn check_unqueued(&self, amount: u64) -> Result<(), Error> {
+        if self.queue_policy.is_some_and(|p| amount >= p.min_amount) {
+            return Err(Error::TransferQueueRequired);
+        }
+        Ok(())
+    }
+
+    /// Fails if the account's transfer window is closed at the given height,
+    /// and the signers - or the group key - don't meet its supermajority.
+    fn check_transfer_window(
+        &self,
+        block_height: u64,
+        group_signed: bool,
+        signed: usize,
+    ) -> Result<(), Error> {
+        if self
+            .transfer_window
+            .is_some_and(|window| !window.contains(block_height))
+            && !group_signed
+            && !self.supermajority_met(signed)
+        {
+            return Err(Error::OutsideTransferWindow);
+        }
+        Ok(())
+    }
+
+    /// The start of the current window of a spending category at the given
+    /// height, and its spending once a transfer of the given amount is made
+    /// under it, failing if the account has no such category or the transfer
+    /// would take its spending past the budget.
+    fn spend_category(
+        &self,
+        name: &str,
+        amount: u64,
+        block_height: u64,
+    ) -> Result<(u64, u64), Error> {
+        let category = self
+            .categories
+            .iter()
+            .find(|c| c.name == name)
+            .ok_or(Error::UnknownCategory)?;
+        let window_start = block_height - block_height % category.window;
+        let spent = self
+            .category_spending
+            .get(name)
+            .filter(|(start, _)| *start == window_start)
+            .map_or(0, |(_, spent)| *spent);
+        spent
+            .checked_add(amount)
+            .filter(|&total| total <= category.budget)
+            .map(|total| (window_start, total))
+            .ok_or(Error::CategoryBudgetExceeded)
+    }
+
+    /// The amount transferred under each spending category of the account
+    /// within its current window at the given height.
+    pub fn category_spend(&self, block_height: u64) -> Vec<CategorySpend> {
+        self.categories
+            .iter()
+            .map(|category| {
+                let window_start =
+                    block_height - block_height % category.window;
+                let spent = self
+                    .category_spending
+                    .get(&category.name)
+                    .filter(|(start, _)| *start == window_start)
+                    .map_or(0, |(_, spent)| *spent);
+                CategorySpend {
+                    name: category.name.clone(),
+                    spent,
+                }
+            })
+            .collect()
+    }
+
+    /// The draws of each child of the account on the budget it allocated them
+    /// at the given height.
+    pub fn budget_draws(&self, block_height: u64) -> Vec<BudgetDraw> {
+        self.budget_draws
+            .iter()
+            .map(|(&child_id, &(start, drawn, held))| {
+                let drawn = self
+                    .child_budgets
+                    .iter()
+                    .find(|b| b.child_id == child_id)
+                    .filter(|b| start == block_height - block_height % b.window)
+                    .map_or(0, |_| drawn);
+                BudgetDraw {
+                    child_id,
+                    drawn,
+                    held,
+                }
+            })
+            .collect()
+    }
+
+    /// Whether a key of the account is past any delay it was added under at
+    /// the given height.
+    fn is_usable(&self, key: Key, block_height: u64) -> bool {
+        self.pending_keys
+            .get(&key)
+            .is_none_or(|&usable_from| block_height >= usable_from)
+    }
+
+    /// Whether a key of the account has yet to lapse at the given height.
+    fn is_unexpired(&self, key: Key, block_height: u64) -> bool {
+        self.key_expiries
+            .get(&key)
+            .is_none_or(|&valid_until| block_height <= valid_until)
+    }
+
+    /// The key of the account a signing key signs for at the given height,
+    /// either itself or the key delegating to it.
+    fn principal_of(&self, key: Key, block_height: u64) -> Option<Key> {
+        if self.keys.contains(&key) {
+            return Some(key);
+        }
+        self.delegations.iter().find_map(|(delegator, kd)| {
+            let delegation = kd.delegation.as_ref()?;
+            let active = Key(delegation.delegate) == key
+                && delegation.is_active(block_height)
+                && self.keys.contains(delegator);
+            active.then_some(*delegator)
+        })
+    }
+
+    /// Counts an operation signed by the given keys towards the usage of the
+    /// keys of the account they sign for.
+    fn record_usage(&mut self, keys: &[PublicKey], block_height: u64) {
+        for key in keys {
+            if let Some(principal) = self.principal_of(Key(*key), block_height)
+            {
+                let (operations, last_signed) =
+                    self.key_usage.entry(principal).or_default();
+                *operations += 1;
+                *last_signed = block_height;
+            }
+        }
+    }
+}
+
+/// The parts of the model, besides the account, that authorizing an operation
+/// depends on. They're borrowed apart from the accounts, so an account can be
+/// authorized while borrowed mutably.
+#[derive(Clone, Copy)]
+struct Authority<'a> {
+    op: OperationKind,
+    /// The threshold the operation is held to in place of that of its kind,
+    /// if the amount it moves out falls in a transfer tier.
+    tier: Option<u32>,
+    compromised_keys: &'a BTreeSet<Key>,
+    compromised_cosigners: &'a BTreeSet<CosignerKey>,
+    block_height: u64,
+}
+
+/// The model of the contract's state.
+///
+/// Failed operations leave it untouched, just like a panic in the contract
+/// reverts its state.
+#[derive(Debug, Default, Clone)]
+pub struct Model {
+    pub accounts: BTreeMap<u64, ModelAccount>,
+    /// The accounts using each key. Keys removed from all their accounts are
+    /// kept, with no accounts.
+    pub key_accounts: BTreeMap<Key, BTreeSet<u64>>,
+    /// The keys marked as compromised.
+    pub compromised_keys: BTreeSet<Key>,
+    /// The co-signer keys marked as compromised.
+    pub compromised_cosigners: BTreeSet<CosignerKey>,
+    /// The account templates registered, by ID.
+    pub templates: BTreeMap<u64, AccountTemplate>,
+    /// The withdrawals awaiting claim, by claim ID.
+    pub withdrawals: BTreeMap<u64, PendingWithdrawal>,
+    /// The claim ID of the last withdrawal recorded.
+    pub withdrawal_count: u64,
+    /// The balance the contract holds in the transfer contract. Balances set
+    /// at genesis aren't part of it, since they're funded outside the contract.
+    pub custody: u64,
+    /// The most all accounts may hold together, if bounded.
+    pub custody_cap: Option<u64>,
+    /// The smallest amount a deposit may be, if bounded.
+    pub min_deposit: Option<u64>,
+    /// The account governing the contract-wide parameters, if any.
+    pub governance: Option<u64>,
+    /// The contract-wide parameters.
+    pub config: ContractConfig,
+    /// The height of the block operations are performed in.
+    pub block_height: u64,
+    /// The ID of the chain operations are performed on.
+    pub chain_id: u8,
+    /// The keys licensed by each license contract, by the contract's ID.
+    pub licenses: BTreeSet<([u8; 32], Key)>,
+}
+
+impl Model {
+    pub fn init(&mut self, genesis: &Genesis) {
+        for account in &genesis.accounts {
+            let id = self
+                .create_account(&CreateAccount {
+                    keys: account.keys.clone(),
+                    threshold: account.threshold,
+                    description: account.description.clone(),
+                    alias: None,
+                    key_weights: Vec::new(),
+                    key_roles: Vec::new(),
+                })
+                .expect("Genesis accounts should be valid");
+            self.accounts.get_mut(&id).unwrap().balance = account.balance;
+        }
+        self.custody_cap = genesis.custody_cap;
+        self.min_deposit = genesis.min_deposit;
+        self.governance = genesis.governance;
+    }
+
+    /// The total balance of all accounts.
+    pub fn total_balance(&self) -> u64 {
+        self.accounts.values().map(|account| account.balance).sum()
+    }
+
+    /// The ID of the account claiming the given alias, if any.
+    pub fn resolve_alias(&self, alias: &str) -> Option<u64> {
+        self.accounts
+            .iter()
+            .find(|(_, account)| account.alias.as_deref() == Some(alias))
+            .map(|(id, _)| *id)
+    }
+
+    /// The IDs of the accounts carrying the given tag, in ascending order.
+    pub fn tagged_accounts(&self, tag: &str) -> Vec<u64> {
+        self.accounts
+            .iter()
+            .filter(|(_, account)| account.tags.iter().any(|t| t == tag))
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    pub fn create_account(&mut self, ca: &CreateAccount) -> Result<u64, Error> {
+        self.open_account(ca, None)
+    }
+
+    pub fn register_template(
+        &mut self,
+        template: &AccountTemplate,
+    ) -> Result<u64, Error> {
+        if template.threshold < 1 {
+            return Err(Error::ZeroThreshold);
+        }
+        if template
+            .velocity_limit
+            .is_some_and(|limit| limit.window == 0)
+        {
+            return Err(Error::ZeroVelocityWindow);
+        }
+        let cosigners: BTreeSet<_> = template.cosigners.iter().collect();
+        let guardians: BTreeSet<_> =
+            template.guardians.iter().map(|key| Key(*key)).collect();
+        if cosigners.len() != template.cosigners.len()
+            || guardians.len() != template.guardians.len()
+        {
+            return Err(Error::DuplicateKey);
+        }
+
+        let id = self.templates.last_key_value().map_or(0, |(id, _)| *id) + 1;
+        self.templates.insert(id, template.clone());
+        Ok(id)
+    }
+
+    pub fn create_account_from_template(
+        &mut self,
+        c: &CreateAccountFromTemplate,
+    ) -> Result<u64, Error> {
+        let template = self
+            .templates
+            .get(&c.template_id)
+            .cloned()
+            .ok_or(Error::TemplateNotFound)?;
+        let ca = CreateAccount {
+            keys: c.keys.clone(),
+            threshold: template.threshold,
+            description: template.description.clone(),
+            alias: c.alias.clone(),
+            key_weights: Vec::new(),
+            key_roles: Vec::new(),
+        };
+        self.open_account(&ca, Some(&template))
+    }
+
+    fn open_account(
+        &mut self,
+        ca: &CreateAccount,
+        template: Option<&AccountTemplate>,
+    ) -> Result<u64, Error> {
+        let cosigners = template.map_or(0, |t| t.cosigners.len());
+
+        if ca.keys.is_empty() {
+            return Err(Error::NoKeys);
+        }
+        if ca.threshold < 1 {
+            return Err(Error::ZeroThreshold);
+        }
+        check_key_count(&self.config, ca.keys.len())?;
+
+        let mut keys = BTreeSet::new();
+        for key in &ca.keys {
+            if !keys.insert(Key(*key)) {
+                return Err(Error::DuplicateKey);
+            }
+            if self.compromised_keys.contains(&Key(*key)) {
+                return Err(Error::KeyCompromised);
+            }
+        }
+        let mut key_weights = BTreeMap::new();
+        for kw in &ca.key_weights {
+            if !keys.contains(&Key(kw.key)) {
+                return Err(Error::UnknownKey);
+            }
+            if kw.weight < 1 {
+                return Err(Error::ZeroWeight);
+            }
+            if key_weights.insert(Key(kw.key), kw.weight).is_some() {
+                return Err(Error::DuplicateKey);
+            }
+        }
+        key_weights.retain(|_, weight| *weight != 1);
+        let weight = keys
+            .iter()
+            .map(|key| key_weights.get(key).map_or(1, |w| *w as usize))
+            .sum::<usize>();
+        if ca.threshold as usize > weight + cosigners {
+            return Err(Error::ThresholdTooLarge);
+        }
+        let mut key_roles = BTreeMap::new();
+        for assignment in &ca.key_roles {
+            if !keys.contains(&Key(assignment.key)) {
+                return Err(Error::UnknownKey);
+            }
+            if key_roles
+                .insert(Key(assignment.key), assignment.role)
+                .is_some()
+            {
+                return Err(Error::DuplicateKey);
+            }
+        }
+        key_roles.retain(|_, role| *role != KeyRole::Admin);
+        let admins = keys
+            .iter()
+            .filter(|key| !key_roles.contains_key(key))
+            .map(|key| key_weights.get(key).map_or(1, |w| *w as usize))
+            .sum::<usize>();
+        if ca.threshold as usize > admins + cosigners {
+            return Err(Error::AdminsBelowThreshold);
+        }
+        let mut guardians = BTreeSet::new();
+        for key in template.iter().flat_map(|t| &t.guardians) {
+            if self.compromised_keys.contains(&Key(*key)) {
+                return Err(Error::KeyCompromised);
+            }
+            if keys.contains(&Key(*key)) {
+                return Err(Error::KeyAlreadyUsed);
+            }
+            guardians.insert(Key(*key));
+        }
+        if let Some(alias) = &ca.alias {
+            if !is_valid_alias(alias) {
+                return Err(Error::InvalidAlias);
+            }
+            if self.resolve_alias(alias).is_some() {
+                return Err(Error::AliasTaken);
+            }
+        }
+
+        let id = self.accounts.last_key_value().map_or(0, |(id, _)| *id) + 1;
+        for key in &keys {
+            self.key_accounts.entry(*key).or_default().insert(id);
+        }
+        self.accounts.insert(
+            id,
+            ModelAccount {
+                keys,
+                threshold: ca.threshold,
+                nonce: 0,
+                balance: 0,
+                description: ca.description.clone(),
+                private_events: template.is_some_and(|t| t.private_events),
+                duress_keys: BTreeSet::new(),
+                cosigners: template
+                    .iter()
+                    .flat_map(|t| t.cosigners.iter().cloned())
+                    .collect(),
+                guardians,
+                observers: BTreeSet::new(),
+                frozen: false,
+                group_key: None,
+                whitelist: BTreeMap::new(),
+                whitelist_enforced: false,
+                velocity_limit: template.and_then(|t| t.velocity_limit),
+                locked: false,
+                archived: false,
+                claimable_withdrawals: false,
+                history_retention: None,
+                compliance: None,
+                on_hold: false,
+                license_policy: None,
+                operation_thresholds: BTreeMap::new(),
+                transfer_tiers: Vec::new(),
+                alias: ca.alias.clone(),
+                merged_into: None,
+                chain_id: self.chain_id,
+                balance_cap: None,
+                tags: Vec::new(),
+                liveness_policy: None,
+                liveness: BTreeMap::new(),
+                supermajority: None,
+                key_addition_policy: None,
+                transfer_window: None,
+                categories: Vec::new(),
+                category_spending: BTreeMap::new(),
+                child_budgets: Vec::new(),
+                budget_draws: BTreeMap::new(),
+                pending_keys: BTreeMap::new(),
+                key_expiries: BTreeMap::new(),
+                key_weights,
+                key_roles,
+                key_usage: BTreeMap::new(),
+                checkpoints: Vec::new(),
+                delegations: BTreeMap::new(),
+                recovery_policy: None,
+                pending_recovery: None,
+                recovery_nonce: 0,
+                inactivity_policy: None,
+                last_active: self.block_height,
+                queue_policy: None,
+                queued_transfers: BTreeMap::new(),
+                allowances: BTreeMap::new(),
+            },
+        );
+
+        Ok(id)
+    }
+
+    pub fn deposit(&mut self, d: &Deposit) -> Result<(), Error> {
+        check_unpaused(&self.config)?;
+        check_memo(&self.config, &d.memo)?;
+
+        let total_balance = self.total_balance();
+        let account = self
+            .accounts
+            .get_mut(&d.account_id)
+            .ok_or(Error::AccountNotFound)?;
+        if account.merged_into.is_some() {
+            return Err(Error::AccountMerged);
+        }
+        if account.archived {
+            return Err(Error::AccountArchived);
+        }
+        if d.depositor_reference.len() > MAX_DEPOSITOR_REFERENCE_LEN {
+            return Err(Error::DepositorReferenceTooLong);
+        }
+        if self.min_deposit.is_some_and(|min| d.amount < min) {
+            return Err(Error::DepositTooSmall);
+        }
+
+        if self
+            .custody_cap
+            .is_some_and(|cap| total_balance + d.amount > cap)
+        {
+            return Err(Error::CustodyCapExceeded);
+        }
+        if account
+            .balance_cap
+            .is_some_and(|cap| account.balance + d.amount > cap)
+        {
+            return Err(Error::BalanceCapExceeded);
+        }
+
+        account.balance += d.amount;
+        self.custody += d.amount;
+        Ok(())
+    }
+
+    /// Checks that the given keys and co-signers - or the group key - may
+    /// operate on the account, and that they signed the given message,
+    /// returning whether a duress key signed.
+    fn authorize(
+        account: &ModelAccount,
+        authority: Authority,
+        keys: &[PublicKey],
+        signature: &MultisigSignature,
+        cosignatures: &[Cosignature],
+        group_signature: Option<&Signature>,
+        msg: &[u8],
+    ) -> Result<bool, Error> {
+        let group_signed = match (account.group_key, group_signature) {
+            (Some(group_key), Some(signature)) => {
+                if !keys.is_empty() || !cosignatures.is_empty() {
+                    return Err(Error::GroupSignatureRequired);
+                }
+                group_key
+                    .verify(signature, msg)
+                    .map_err(|_| Error::InvalidSignature)?;
+                true
+            }
+            (None, Some(_)) => return Err(Error::NoGroupKey),
+            (Some(_), None) if !account.frozen => {
+                return Err(Error::GroupSignatureRequired)
+            }
+            _ => false,
+        };
+
+        let mut signers = BTreeSet::new();
+        let mut principals = BTreeSet::new();
+        let mut duress = false;
+        for key in keys {
+            if !signers.insert(Key(*key)) {
+                return Err(Error::DuplicateKey);
+            }
+            if account.duress_keys.contains(&Key(*key)) {
+                duress = true;
+                continue;
+            }
+            let principal = account
+                .principal_of(Key(*key), authority.block_height)
+                .ok_or(Error::UnknownKey)?;
+            if authority.compromised_keys.contains(&Key(*key))
+                || authority.compromised_keys.contains(&principal)
+            {
+                return Err(Error::KeyCompromised);
+            }
+            if !account.is_usable(principal, authority.block_height) {
+                return Err(Error::KeyNotYetUsable);
+            }
+            if !account.is_unexpired(principal, authority.block_height) {
+                return Err(Error::KeyExpired);
+            }
+            if account
+                .key_roles
+                .get(&principal)
+                .is_some_and(|role| !role.can_sign(authority.op))
+            {
+                return Err(Error::KeyRoleNotAllowed);
+            }
+            if !principals.insert(principal) {
+                return Err(Error::DuplicateKey);
+            }
+        }
+
+        let mut cosigners = BTreeSet::new();
+        for cosignature in cosignatures {
+            if !cosigners.insert(cosignature.key) {
+                return Err(Error::DuplicateKey);
+            }
+            if !account.cosigners.contains(&cosignature.key) {
+                return Err(Error::UnknownKey);
+            }
+            if authority.compromised_cosigners.contains(&cosignature.key) {
+                return Err(Error::KeyCompromised);
+            }
+        }
+
+        let threshold =
+            authority.tier.unwrap_or(account.threshold_of(authority.op));
+        let required =
+            account.required_signers(threshold, authority.block_height);
+        if !group_signed
+            && account.signed_weight(
+                keys,
+                cosignatures.len(),
+                authority.block_height,
+            ) < required
+        {
+            return Err(Error::ThresholdNotMet);
+        }
+
+        for cosignature in cosignatures {
+            if !cosignature.key.verify(msg, &cosignature.signature) {
+                return Err(Error::InvalidSignature);
+            }
+        }
+        if !keys.is_empty() {
+            MultisigPublicKey::aggregate(keys)
+                .and_then(|pk| pk.verify(signature, msg))
+                .map_err(|_| Error::InvalidSignature)?;
+        }
+        Ok(duress)
+    }
+
+    pub fn transfer(&mut self, t: &Transfer) -> Result<(), Error> {
+        self.submit_transfer(t, false)
+    }
+
+    pub fn queue_transfer(&mut self, t: &Transfer) -> Result<(), Error> {
+        self.submit_transfer(t, true)
+    }
+
+    fn submit_transfer(
+        &mut self,
+        t: &Transfer,
+        queue: bool,
+    ) -> Result<(), Error> {
+        check_unpaused(&self.config)?;
+        check_memo(&self.config, &t.memo)?;
+
+        let account = self
+            .accounts
+            .get_mut(&t.account_id)
+            .ok_or(Error::AccountNotFound)?;
+        if account.merged_into.is_some() {
+            return Err(Error::AccountMerged);
+        }
+
+        if account.frozen {
+            return Err(Error::AccountFrozen);
+        }
+        if account.locked {
+            return Err(Error::AccountLocked);
+        }
+        if account.archived {
+            return Err(Error::AccountArchived);
+        }
+        check_hold(account, Some(&t.receiver))?;
+        check_licensed(&self.licenses, account, &t.receiver)?;
+        let refund = gas_refund_amount(
+            t.gas_refund.as_ref(),
+            self.config.max_gas_refund,
+        )?;
+        let available = account
+            .balance
+            .checked_sub(refund)
+            .ok_or(Error::InsufficientBalance)?;
+        let amount = match t.amount {
+            TRANSFER_ALL => available,
+            amount => amount,
+        };
+        if amount > available {
+            return Err(Error::InsufficientBalance);
+        }
+        if !queue {
+            account.check_unqueued(amount)?;
+        } else if account.queue_policy.is_none_or(|p| amount < p.min_amount) {
+            return Err(Error::TransferNotQueueable);
+        }
+        if t.nonce != account.nonce + 1 {
+            return Err(Error::InvalidNonce);
+        }
+        check_chain(account, t.chain_id)?;
+        let duress = Self::authorize(
+            account,
+            Authority {
+                op: OperationKind::Transfer,
+                tier: account.tier_of(OperationKind::Transfer, amount),
+                compromised_keys: &self.compromised_keys,
+                compromised_cosigners: &self.compromised_cosigners,
+                block_height: self.block_height,
+            },
+            &t.keys,
+            &t.signature,
+            &t.cosignatures,
+            t.group_signature.as_ref(),
+            &t.signature_msg(),
+        )?;
+        if duress {
+            account.frozen = true;
+            return Ok(());
+        }
+
+        account.check_transfer_window(
+            self.block_height,
+            t.group_signature.is_some(),
+            account.signed_weight(
+                &t.keys,
+                t.cosignatures.len(),
+                self.block_height,
+            ),
+        )?;
+        if account.whitelist_enforced {
+            match account.whitelist.get(&Key(t.receiver)) {
+                None => return Err(Error::ReceiverNotWhitelisted),
+                Some(&usable_from) if self.block_height < usable_from => {
+                    return Err(Error::ReceiverNotYetUsable)
+                }
+                Some(_) => {}
+            }
+        }
+        // A queued transfer counts against its category once it's executed
+        let spent = match t.category.as_ref().filter(|_| !queue) {
+            Some(category) => Some((
+                category.clone(),
+                account.spend_category(category, amount, self.block_height)?,
+            )),
+            None => None,
+        };
+
+        if let Some((category, spent)) = spent {
+            account.category_spending.insert(category, spent);
+        }
+
+        account.record_usage(&t.keys, self.block_height);
+        if queue {
+            let keys: BTreeSet<_> = t.keys.iter().copied().map(Key).collect();
+            let cosigners: BTreeSet<_> =
+                t.cosignatures.iter().map(|c| c.key).collect();
+            let delay = account.queue_policy.unwrap().delay;
+            account.queued_transfers.insert(
+                t.nonce,
+                QueuedTransfer {
+                    nonce: t.nonce,
+                    keys: keys.into_iter().map(|key| key.0).collect(),
+                    cosigners: cosigners.into_iter().collect(),
+                    group_signed: t.group_signature.is_some(),
+                    receiver: t.receiver,
+                    amount,
+                    memo: t.memo.clone(),
+                    reference: t.reference,
+                    category: t.category.clone(),
+                    executable_from: self.block_height.saturating_add(delay),
+                },
+            );
+            account.balance -= refund;
+            account.nonce += 1;
+            account.last_active = self.block_height;
+            self.refund_gas(t.account_id, t.gas_refund.as_ref());
+            return Ok(());
+        }
+        account.balance -= amount + refund;
+        account.nonce += 1;
+        account.last_active = self.block_height;
+
+        // A claimable withdrawal is held by the contract until claimed
+        match account.claimable_withdrawals {
+            true => self.record_withdrawal(t.account_id, t.receiver, amount),
+            false => self.pay_or_defer(t.account_id, t.receiver, amount),
+        }
+        self.refund_gas(t.account_id, t.gas_refund.as_ref());
+        Ok(())
+    }
+
+    /// Pays an amount out of custody, or records it as a withdrawal for the
+    /// receiver to claim if custody doesn't cover it, as the transfer
+    /// contract then refuses the payment.
+    fn pay_or_defer(
+        &mut self,
+        account_id: u64,
+        receiver: PublicKey,
+        amount: u64,
+    ) {
+        match self.custody.checked_sub(amount) {
+            Some(custody) => self.custody = custody,
+            None => self.record_withdrawal(account_id, receiver, amount),
+        }
+    }
+
+    /// Pays a gas refund, if any, out of custody or defers it.
+    fn refund_gas(&mut self, account_id: u64, refund: Option<&GasRefund>) {
+        if let Some(refund) = refund {
+            self.pay_or_defer(account_id, refund.submitter, refund.amount);
+        }
+    }
+
+    /// Records a withdrawal for its receiver to claim, under the next claim
+    /// ID.
+    fn record_withdrawal(
+        &mut self,
+        account_id: u64,
+        receiver: PublicKey,
+        amount: u64,
+    ) {
+        self.withdrawal_count += 1;
+        self.withdrawals.insert(
+            self.withdrawal_count,
+            PendingWithdrawal {
+                claim_id: self.withdrawal_count,
+                account_id,
+                receiver,
+                amount,
+                block_height: self.block_height,
+            },
+        );
+    }
+
+    pub fn change_account(&mut self, c: &ChangeAccount) -> Result<(), Error> {
+        let account = self
+            .accounts
+            .get_mut(&c.account_id)
+            .ok_or(Error::AccountNotFound)?;
+        if account.merged_into.is_some() {
+            return Err(Error::AccountMerged);
+        }
+
+        if c.nonce != account.nonce + 1 {
+            return Err(Error::InvalidNonce);
+        }
+        check_chain(account, c.chain_id)?;
+        let refund = gas_refund_amount(
+            c.gas_refund.as_ref(),
+            self.config.max_gas_refund,
+        )?;
+        let duress = Self::authorize(
+            account,
+            Authority {
+                op: OperationKind::ChangeAccount,
+                tier: None,
+                compromised_keys: &self.compromised_keys,
+                compromised_cosigners: &self.compromised_cosigners,
+                block_height: self.block_height,
+            },
+            &c.keys,
+            &c.signature,
+            &c.cosignatures,
+            c.group_signature.as_ref(),
+            &c.signature_msg(),
+        )?;
+        if duress {
+            account.frozen = true;
+            return Ok(());
+        }
+        if account.frozen
+            && (c.group_signature.is_some()
+                || c.keys.len() + c.cosignatures.len()
+                    != account.keys.len() + account.cosigners.len())
+        {
+            return Err(Error::AccountFrozen);
+        }
+
+        // Changes are applied to a copy, since a failed change reverts all
+        let signed = c.group_signature.is_none().then(|| {
+            account.signed_weight(
+                &c.keys,
+                c.cosignatures.len(),
+                self.block_height,
+            )
+        });
+        let mut changed = account.clone();
+        changed.frozen = false;
+        changed.record_usage(&c.keys, self.block_height);
+        let clawed_back =
+            self.apply_changes(c.account_id, &mut changed, &c.changes, signed)?;
+
+        // The refund is paid by the account as changed, which can't be locked
+        if c.gas_refund.is_some() {
+            if changed.locked {
+                return Err(Error::AccountLocked);
+            }
+            if changed.archived {
+                return Err(Error::AccountArchived);
+            }
+            if refund > changed.balance {
+                return Err(Error::InsufficientBalance);
+            }
+            changed.balance -= refund;
+        }
+
+        self.commit_changes(c.account_id, changed, clawed_back);
+        self.refund_gas(c.account_id, c.gas_refund.as_ref());
+        Ok(())
+    }
+
+    /// Applies changes to a copy of an account, failing as the contract
+    /// would.
+    ///
+    /// The signers weigh as much as given, or are the group key if not, and
+    /// are held to the account as it was signed for, before any of the
+    /// changes. Returns the amount clawed back from each child, already added
+    /// to the copy.
+    fn apply_changes(
+        &self,
+        account_id: u64,
+        changed: &mut ModelAccount,
+        changes: &[AccountChange],
+        signed: Option<usize>,
+    ) -> Result<Vec<(u64, u64)>, Error> {
+        let account = &self.accounts[&account_id];
+        let supermajority_met =
+            signed.is_none_or(|signed| account.supermajority_met(signed));
+        for change in changes {
+            if let AccountChange::SetChildBudget { budget } = change {
+                if budget.child_id == account_id {
+                    return Err(Error::InvalidChildBudget);
+                }
+                let child = self
+                    .accounts
+                    .get(&budget.child_id)
+                    .ok_or(Error::AccountNotFound)?;
+                if child.merged_into.is_some() {
+                    return Err(Error::AccountMerged);
+                }
+                account.check_unrestricted()?;
+                if let Some(threshold) = TransferTier::threshold_for(
+                    &account.transfer_tiers,
+                    budget.amount,
+                ) {
+                    let required =
+                        account.required_signers(threshold, self.block_height);
+                    if signed.is_some_and(|signed| signed < required) {
+                        return Err(Error::ThresholdNotMet);
+                    }
+                }
+            }
+        }
+
+        let taken_aliases: BTreeSet<String> = self
+            .accounts
+            .iter()
+            .filter(|(id, _)| **id != account_id)
+            .filter_map(|(_, account)| account.alias.clone())
+            .collect();
+        let key_addition_policy = changed.key_addition_policy;
+        let mut clawbacks = Vec::new();
+        let mut added_keys = Vec::new();
+        let mut changed_license_policy = false;
+
+        for change in changes {
+            match change {
+                AccountChange::AddKey { key } => {
+                    if key_addition_policy.is_some_and(|p| p.supermajority)
+                        && !supermajority_met
+                    {
+                        return Err(Error::SupermajorityNotMet);
+                    }
+                    if self.compromised_keys.contains(&Key(*key)) {
+                        return Err(Error::KeyCompromised);
+                    }
+                    if changed.duress_keys.contains(&Key(*key))
+                        || changed.guardians.contains(&Key(*key))
+                        || changed.observers.contains(&Key(*key))
+                        || !changed.keys.insert(Key(*key))
+                    {
+                        return Err(Error::KeyAlreadyUsed);
+                    }
+                    added_keys.push(Key(*key));
+                    if let Some(policy) = key_addition_policy {
+                        if policy.delay > 0 {
+                            changed.pending_keys.insert(
+                                Key(*key),
+                                self.block_height.saturating_add(policy.delay),
+                            );
+                        }
+                    }
+                }
+                AccountChange::RemoveKey { key } => {
+                    if !changed.keys.remove(&Key(*key)) {
+                        return Err(Error::KeyNotUsed);
+                    }
+                    changed.key_weights.remove(&Key(*key));
+                    changed.key_roles.remove(&Key(*key));
+                    changed.allowances.remove(&Key(*key));
+                    if changed.signers() < changed.max_threshold() as usize {
+                        return Err(Error::KeysBelowThreshold);
+                    }
+                    changed.pending_keys.remove(&Key(*key));
+                    changed.key_expiries.remove(&Key(*key));
+                    changed.key_usage.remove(&Key(*key));
+                }
+                AccountChange::SetThreshold { threshold } => {
+                    if !supermajority_met {
+                        return Err(Error::SupermajorityNotMet);
+                    }
+                    if *threshold < 1 {
+                        return Err(Error::ZeroThreshold);
+                    }
+                    if *threshold as usize > changed.signers() {
+                        return Err(Error::ThresholdTooLarge);
+                    }
+                    changed.threshold = *threshold;
+                }
+                AccountChange::SetDescription { description } => {
+                    changed.description = description.clone();
+                }
+                AccountChange::SetPrivateEvents { private_events } => {
+                    changed.private_events = *private_events;
+                }
+                AccountChange::SetClaimableWithdrawals {
+                    claimable_withdrawals,
+                } => {
+                    changed.claimable_withdrawals = *claimable_withdrawals;
+                }
+                AccountChange::SetHistoryRetention { records } => {
+                    if records.is_some_and(|r| r > MAX_HISTORY_RETENTION) {
+                        return Err(Error::HistoryRetentionTooLong);
+                    }
+                    let fee = history_retention_fee(
+                        changed.history_retention,
+                        *records,
+                        self.config.history_record_fee,
+                    );
+                    if fee > changed.balance {
+                        return Err(Error::InsufficientBalance);
+                    }
+                    changed.balance -= fee;
+                    changed.history_retention = *records;
+                    changed.retain_checkpoints();
+                }
+                AccountChange::AddDuressKey { key } => {
+                    if self.compromised_keys.contains(&Key(*key)) {
+                        return Err(Error::KeyCompromised);
+                    }
+                    if changed.keys.contains(&Key(*key))
+                        || changed.guardians.contains(&Key(*key))
+                        || changed.observers.contains(&Key(*key))
+                        || !changed.duress_keys.insert(Key(*key))
+                    {
+                        return Err(Error::KeyAlreadyUsed);
+                    }
+                }
+                AccountChange::RemoveDuressKey { key } => {
+                    if !changed.duress_keys.remove(&Key(*key)) {
+                        return Err(Error::KeyNotUsed);
+                    }
+                }
+                AccountChange::AddCosigner { key } => {
+                    if !supermajority_met {
+                        return Err(Error::SupermajorityNotMet);
+                    }
+                    if self.compromised_cosigners.contains(key) {
+                        return Err(Error::KeyCompromised);
+                    }
+                    if !changed.cosigners.insert(*key) {
+                        return Err(Error::KeyAlreadyUsed);
+                    }
+                }
+                AccountChange::RemoveCosigner { key } => {
+                    if !changed.cosigners.remove(key) {
+                        return Err(Error::KeyNotUsed);
+                    }
+                    if changed.signers() < changed.max_threshold() as usize {
+                        return Err(Error::KeysBelowThreshold);
+                    }
+                }
+                AccountChange::SetGroupKey { key } => {
+                    if !supermajority_met {
+                        return Err(Error::SupermajorityNotMet);
+                    }
+                    if key.is_some_and(|key| {
+                        self.compromised_keys.contains(&Key(key))
+                    }) {
+                        return Err(Error::KeyCompromised);
+                    }
+                    changed.group_key = *key;
+                }
+                AccountChange::AddWhitelisted { receiver } => {
+                    if changed.whitelist.contains_key(&Key(*receiver)) {
+                        return Err(Error::KeyAlreadyUsed);
+                    }
+                    changed.whitelist.insert(
+                        Key(*receiver),
+                        self.block_height + WHITELIST_DELAY,
+                    );
+                    changed.whitelist_enforced = true;
+                }
+                AccountChange::RemoveWhitelisted { receiver } => {
+                    if changed.whitelist.remove(&Key(*receiver)).is_none() {
+                        return Err(Error::KeyNotUsed);
+                    }
+                    if changed.whitelist.is_empty() {
+                        if !supermajority_met {
+                            return Err(Error::SupermajorityNotMet);
+                        }
+                        changed.whitelist_enforced = false;
+                    }
+                }
+                AccountChange::SetVelocityLimit { limit } => {
+                    if limit.is_some_and(|limit| limit.window == 0) {
+                        return Err(Error::ZeroVelocityWindow);
+                    }
+                    changed.velocity_limit = *limit;
+                }
+                AccountChange::SetCompliance { compliance } => {
+                    if !supermajority_met {
+                        return Err(Error::SupermajorityNotMet);
+                    }
+                    if changed.on_hold {
+                        return Err(Error::AccountOnHold);
+                    }
+                    changed.compliance = compliance.clone();
+                }
+                AccountChange::LiftHold { signature } => {
+                    if !supermajority_met {
+                        return Err(Error::SupermajorityNotMet);
+                    }
+                    if !changed.on_hold {
+                        return Err(Error::NotOnHold);
+                    }
+                    let compliance = changed.compliance.as_ref().unwrap();
+                    let msg =
+                        PlaceHold::lift_msg(account_id, changed.nonce + 1);
+                    MultisigPublicKey::aggregate(&[compliance.key])
+                        .and_then(|pk| pk.verify(signature, &msg))
+                        .map_err(|_| Error::InvalidSignature)?;
+                    changed.on_hold = false;
+                }
+                AccountChange::SetLicensePolicy { policy } => {
+                    if !supermajority_met {
+                        return Err(Error::SupermajorityNotMet);
+                    }
+                    changed.license_policy = *policy;
+                    changed_license_policy = true;
+                }
+                AccountChange::SetOperationThreshold { op, threshold } => {
+                    if !supermajority_met {
+                        return Err(Error::SupermajorityNotMet);
+                    }
+                    match threshold {
+                        Some(threshold) => {
+                            if *threshold < 1 {
+                                return Err(Error::ZeroThreshold);
+                            }
+                            if *threshold as usize > changed.signers() {
+                                return Err(Error::ThresholdTooLarge);
+                            }
+                            changed
+                                .operation_thresholds
+                                .insert(*op, *threshold);
+                        }
+                        None => {
+                            changed.operation_thresholds.remove(op);
+                        }
+                    }
+                }
+                AccountChange::SetTransferTiers { tiers } => {
+                    if !supermajority_met {
+                        return Err(Error::SupermajorityNotMet);
+                    }
+                    if !are_valid_transfer_tiers(tiers, changed.signers()) {
+                        return Err(Error::InvalidTransferTiers);
+                    }
+                    if tiers.iter().any(|tier| tier.threshold < 1) {
+                        return Err(Error::ZeroThreshold);
+                    }
+                    changed.transfer_tiers = tiers.clone();
+                }
+                AccountChange::Unlock => {
+                    if !changed.locked {
+                        return Err(Error::NotLocked);
+                    }
+                    changed.locked = false;
+                }
+                AccountChange::Archive => {
+                    if changed.archived {
+                        return Err(Error::AccountArchived);
+                    }
+                    if self.governance == Some(account_id) {
+                        return Err(Error::GovernanceAccount);
+                    }
+                    changed.archived = true;
+                }
+                AccountChange::Reactivate => {
+                    if !changed.archived {
+                        return Err(Error::NotArchived);
+                    }
+                    changed.archived = false;
+                }
+                AccountChange::AddGuardian { key } => {
+                    if self.compromised_keys.contains(&Key(*key)) {
+                        return Err(Error::KeyCompromised);
+                    }
+                    if changed.keys.contains(&Key(*key))
+                        || changed.duress_keys.contains(&Key(*key))
+                        || changed.observers.contains(&Key(*key))
+                        || !changed.guardians.insert(Key(*key))
+                    {
+                        return Err(Error::KeyAlreadyUsed);
+                    }
+                    changed.pending_recovery = None;
+                }
+                AccountChange::RemoveGuardian { key } => {
+                    if !changed.guardians.remove(&Key(*key)) {
+                        return Err(Error::KeyNotUsed);
+                    }
+                    changed.pending_recovery = None;
+                }
+                AccountChange::AddObserver { key } => {
+                    if self.compromised_keys.contains(&Key(*key)) {
+                        return Err(Error::KeyCompromised);
+                    }
+                    if changed.keys.contains(&Key(*key))
+                        || changed.duress_keys.contains(&Key(*key))
+                        || changed.guardians.contains(&Key(*key))
+                        || !changed.observers.insert(Key(*key))
+                    {
+                        return Err(Error::KeyAlreadyUsed);
+                    }
+                }
+                AccountChange::RemoveObserver { key } => {
+                    if !changed.observers.remove(&Key(*key)) {
+                        return Err(Error::KeyNotUsed);
+                    }
+                }
+                AccountChange::SetAlias { alias } => {
+                    if let Some(alias) = alias {
+                        if !is_valid_alias(alias) {
+                            return Err(Error::InvalidAlias);
+                        }
+                        if taken_aliases.contains(alias) {
+                            return Err(Error::AliasTaken);
+                        }
+                    }
+                    changed.alias = alias.clone();
+                }
+                AccountChange::SetBalanceCap { cap } => {
+                    changed.balance_cap = *cap;
+                }
+                AccountChange::AddTag { tag } => {
+                    if !is_valid_tag(tag) {
+                        return Err(Error::InvalidTag);
+                    }
+                    if changed.tags.contains(tag) {
+                        return Err(Error::TagAlreadyAdded);
+                    }
+                    if changed.tags.len() == MAX_TAGS {
+                        return Err(Error::TooManyTags);
+                    }
+                    changed.tags.push(tag.clone());
+                }
+                AccountChange::RemoveTag { tag } => {
+                    let Some(i) = changed.tags.iter().position(|t| t == tag)
+                    else {
+                        return Err(Error::TagNotFound);
+                    };
+                    changed.tags.remove(i);
+                }
+                AccountChange::SetCategory { category } => {
+                    if !category.is_valid() {
+                        return Err(Error::InvalidCategory);
+                    }
+                    match changed
+                        .categories
+                        .iter_mut()
+                        .find(|c| c.name == category.name)
+                    {
+                        Some(existing) => *existing = category.clone(),
+                        None => {
+                            if changed.categories.len() == MAX_CATEGORIES {
+                                return Err(Error::TooManyCategories);
+                            }
+                            changed.categories.push(category.clone());
+                        }
+                    }
+                    changed.category_spending.remove(&category.name);
+                }
+                AccountChange::RemoveCategory { name } => {
+                    let Some(i) =
+                        changed.categories.iter().position(|c| c.name == *name)
+                    else {
+                        return Err(Error::UnknownCategory);
+                    };
+                    changed.categories.remove(i);
+                    changed.category_spending.remove(name);
+                }
+                AccountChange::SetChildBudget { budget } => {
+                    if !budget.is_valid() {
+                        return Err(Error::InvalidChildBudget);
+                    }
+                    match changed
+                        .child_budgets
+                        .iter_mut()
+                        .find(|b| b.child_id == budget.child_id)
+                    {
+                        Some(existing) => *existing = *budget,
+                        None => {
+                            if changed.child_budgets.len() == MAX_CHILD_BUDGETS
+                            {
+                                return Err(Error::TooManyChildBudgets);
+                            }
+                            changed.child_budgets.push(*budget);
+                        }
+                    }
+                    if let Some(draws) =
+                        changed.budget_draws.get_mut(&budget.child_id)
+                    {
+                        draws.1 = 0;
+                    }
+                }
+                AccountChange::RemoveChildBudget { child_id } => {
+                    let Some(i) = changed
+                        .child_budgets
+                        .iter()
+                        .position(|b| b.child_id == *child_id)
+                    else {
+                        return Err(Error::UnknownChildBudget);
+                    };
+                    changed.child_budgets.remove(i);
+                }
+                AccountChange::ClawBackBudget { child_id } => {
+                    let draws = changed
+                        .budget_draws
+                        .get_mut(child_id)
+                        .ok_or(Error::UnknownChildBudget)?;
+                    clawbacks.push((*child_id, std::mem::take(&mut draws.2)));
+                }
+                AccountChange::SetKeyExpiry { key, valid_until } => {
+                    if !supermajority_met {
+                        return Err(Error::SupermajorityNotMet);
+                    }
+                    if !changed.keys.contains(&Key(*key)) {
+                        return Err(Error::KeyNotUsed);
+                    }
+                    match valid_until {
+                        Some(valid_until) => {
+                            changed
+                                .key_expiries
+                                .insert(Key(*key), *valid_until);
+                        }
+                        None => {
+                            changed.key_expiries.remove(&Key(*key));
+                        }
+                    }
+                }
+                AccountChange::SetKeyWeight { key, weight } => {
+                    if !supermajority_met {
+                        return Err(Error::SupermajorityNotMet);
+                    }
+                    if !changed.keys.contains(&Key(*key)) {
+                        return Err(Error::KeyNotUsed);
+                    }
+                    match weight {
+                        0 => return Err(Error::ZeroWeight),
+                        1 => changed.key_weights.remove(&Key(*key)),
+                        weight => {
+                            changed.key_weights.insert(Key(*key), *weight)
+                        }
+                    };
+                    if changed.signers() < changed.max_threshold() as usize {
+                        return Err(Error::KeysBelowThreshold);
+                    }
+                }
+                AccountChange::SetKeyRole { key, role } => {
+                    if !supermajority_met {
+                        return Err(Error::SupermajorityNotMet);
+                    }
+                    if !changed.keys.contains(&Key(*key)) {
+                        return Err(Error::KeyNotUsed);
+                    }
+                    match role {
+                        KeyRole::Admin => changed.key_roles.remove(&Key(*key)),
+                        role => changed.key_roles.insert(Key(*key), *role),
+                    };
+                }
+                AccountChange::SetAllowance { key, amount } => {
+                    if !supermajority_met {
+                        return Err(Error::SupermajorityNotMet);
+                    }
+                    if !changed.keys.contains(&Key(*key)) {
+                        return Err(Error::KeyNotUsed);
+                    }
+                    match amount {
+                        0 => changed.allowances.remove(&Key(*key)),
+                        amount => changed.allowances.insert(Key(*key), *amount),
+                    };
+                }
+                AccountChange::SetLivenessPolicy { policy } => {
+                    if !supermajority_met {
+                        return Err(Error::SupermajorityNotMet);
+                    }
+                    if let Some(policy) = policy {
+                        if policy.window == 0 {
+                            return Err(Error::ZeroLivenessWindow);
+                        }
+                        if policy.min_threshold < 1 {
+                            return Err(Error::ZeroThreshold);
+                        }
+                    }
+                    changed.liveness_policy = *policy;
+                }
+                AccountChange::SetSupermajority { supermajority } => {
+                    if !supermajority_met {
+                        return Err(Error::SupermajorityNotMet);
+                    }
+                    if supermajority.is_some_and(|s| !s.is_valid()) {
+                        return Err(Error::InvalidSupermajority);
+                    }
+                    changed.supermajority = *supermajority;
+                }
+                AccountChange::SetKeyAdditionPolicy { policy } => {
+                    if !supermajority_met {
+                        return Err(Error::SupermajorityNotMet);
+                    }
+                    changed.key_addition_policy = *policy;
+                }
+                AccountChange::SetRecoveryPolicy { policy } => {
+                    if !supermajority_met {
+                        return Err(Error::SupermajorityNotMet);
+                    }
+                    if policy.is_some_and(|p| p.guardians < 1) {
+                        return Err(Error::InvalidRecoveryPolicy);
+                    }
+                    changed.recovery_policy = *policy;
+                    changed.pending_recovery = None;
+                }
+                AccountChange::SetInactivityPolicy { policy } => {
+                    if !supermajority_met {
+                        return Err(Error::SupermajorityNotMet);
+                    }
+                    if policy.as_ref().is_some_and(|p| p.period < 1) {
+                        return Err(Error::InvalidInactivityPolicy);
+                    }
+                    changed.inactivity_policy = policy.clone();
+                }
+                AccountChange::SetQueuePolicy { policy } => {
+                    if !supermajority_met {
+                        return Err(Error::SupermajorityNotMet);
+                    }
+                    if policy.is_some_and(|p| p.delay < 1) {
+                        return Err(Error::InvalidQueuePolicy);
+                    }
+                    changed.queue_policy = *policy;
+                }
+                AccountChange::SetTransferWindow { window } => {
+                    if !supermajority_met {
+                        return Err(Error::SupermajorityNotMet);
+                    }
+                    if window.is_some_and(|w| !w.is_valid()) {
+                        return Err(Error::InvalidTransferWindow);
+                    }
+                    changed.transfer_window = *window;
+                }
+            }
+        }
+
+        if (changed.key_addition_policy.is_some_and(|p| p.supermajority)
+            || changed.transfer_window.is_some()
+            || changed.compliance.is_some())
+            && changed.supermajority.is_none()
+        {
+            return Err(Error::NoSupermajority);
+        }
+        if !changed.admins_meet_threshold() {
+            return Err(Error::AdminsBelowThreshold);
+        }
+
+        // Accounts over the bound keep their keys, but can't gain any
+        if !added_keys.is_empty() {
+            check_key_count(&self.config, changed.keys.len())?;
+        }
+
+        // Setting a policy licensing keys checks every key, and one already
+        // set the keys added under it
+        if let Some(policy) = changed.license_policy.filter(|p| p.signers) {
+            let unlicensed =
+                |key: &Key| !self.licenses.contains(&(policy.contract, *key));
+            let unlicensed = if changed_license_policy {
+                changed.keys.iter().any(unlicensed)
+            } else {
+                added_keys.iter().any(unlicensed)
+            };
+            if unlicensed {
+                return Err(Error::KeyNotLicensed);
+            }
+        }
+
+        // Keys are live as of the change that set the policy, or added them
+        // under it
+        match changed.liveness_policy {
+            Some(_) => {
+                let keys = &changed.keys;
+                changed.liveness.retain(|key, _| keys.contains(key));
+                for key in keys {
+                    changed.liveness.entry(*key).or_insert(self.block_height);
+                }
+            }
+            None => changed.liveness.clear(),
+        }
+
+        // Keys removed from the account lose their delegation, but keep their
+        // nonce
+        for (key, kd) in &mut changed.delegations {
+            if !changed.keys.contains(key) {
+                kd.delegation = None;
+            }
+        }
+
+        // What the children hold is clawed back once the changes are
+        // applied, up to their balance
+        let mut clawed_back = Vec::with_capacity(clawbacks.len());
+        for (child_id, held) in clawbacks {
+            let amount = held.min(self.accounts[&child_id].balance);
+            if changed
+                .balance_cap
+                .is_some_and(|cap| amount > cap.saturating_sub(changed.balance))
+            {
+                return Err(Error::BalanceCapExceeded);
+            }
+            changed.balance += amount;
+            clawed_back.push((child_id, amount));
+        }
+
+        Ok(clawed_back)
+    }
+
+    /// Replaces an account with its changed copy, using up its nonce, and
+    /// takes what was clawed back from its children.
+    fn commit_changes(
+        &mut self,
+        account_id: u64,
+        mut changed: ModelAccount,
+        clawed_back: Vec<(u64, u64)>,
+    ) {
+        for (child_id, amount) in clawed_back {
+            self.accounts.get_mut(&child_id).unwrap().balance -= amount;
+        }
+
+        let account = self.accounts.get_mut(&account_id).unwrap();
+        for key in changed.keys.difference(&account.keys) {
+            self.key_accounts
+                .entry(*key)
+                .or_default()
+                .insert(account_id);
+        }
+        for key in account.keys.difference(&changed.keys) {
+            if let Some(ids) = self.key_accounts.get_mut(key) {
+                ids.remove(&account_id);
+            }
+        }
+
+        changed.nonce += 1;
+        changed.last_active = self.block_height;
+        *account = changed;
+    }
+
+    pub fn lock_account(&mut self, l: &LockAccount) -> Result<(), Error> {
+        let account = self
+            .accounts
+            .get_mut(&l.account_id)
+            .ok_or(Error::AccountNotFound)?;
+        if account.merged_into.is_some() {
+            return Err(Error::AccountMerged);
+        }
+
+        if !account.keys.contains(&Key(l.key)) {
+            return Err(Error::UnknownKey);
+        }
+        if self.compromised_keys.contains(&Key(l.key)) {
+            return Err(Error::KeyCompromised);
+        }
+        if !account.is_usable(Key(l.key), self.block_height) {
+            return Err(Error::KeyNotYetUsable);
+        }
+        if !account.is_unexpired(Key(l.key), self.block_height) {
+            return Err(Error::KeyExpired);
+        }
+        if account.locked {
+            return Err(Error::AccountLocked);
+        }
+        MultisigPublicKey::aggregate(&[l.key])
+            .and_then(|pk| {
+                pk.verify(
+                    &l.signature,
+                    &LockAccount::signature_msg(l.account_id, account.nonce),
+                )
+            })
+            .map_err(|_| Error::InvalidSignature)?;
+
+        account.locked = true;
+        Ok(())
+    }
+
+    pub fn place_hold(&mut self, h: &PlaceHold) -> Result<(), Error> {
+        let account = self
+            .accounts
+            .get_mut(&h.account_id)
+            .ok_or(Error::AccountNotFound)?;
+        if account.merged_into.is_some() {
+            return Err(Error::AccountMerged);
+        }
+
+        let compliance = account
+            .compliance
+            .as_ref()
+            .ok_or(Error::NoComplianceControl)?;
+        if self.compromised_keys.contains(&Key(compliance.key)) {
+            return Err(Error::KeyCompromised);
+        }
+        if account.on_hold {
+            return Err(Error::AccountOnHold);
+        }
+        MultisigPublicKey::aggregate(&[compliance.key])
+            .and_then(|pk| {
+                pk.verify(
+                    &h.signature,
+                    &PlaceHold::signature_msg(h.account_id, account.nonce),
+                )
+            })
+            .map_err(|_| Error::InvalidSignature)?;
+
+        account.on_hold = true;
+        Ok(())
+    }
+
+    pub fn attest_liveness(&mut self, a: &AttestLiveness) -> Result<(), Error> {
+        let account = self
+            .accounts
+            .get_mut(&a.account_id)
+            .ok_or(Error::AccountNotFound)?;
+        if account.merged_into.is_some() {
+            return Err(Error::AccountMerged);
+        }
+        if account.liveness_policy.is_none() {
+            return Err(Error::NoLivenessPolicy);
+        }
+
+        if !account.keys.contains(&Key(a.key)) {
+            return Err(Error::UnknownKey);
+        }
+        if self.compromised_keys.contains(&Key(a.key)) {
+            return Err(Error::KeyCompromised);
+        }
+        if a.block_height > self.block_height {
+            return Err(Error::AttestationInFuture);
+        }
+        MultisigPublicKey::aggregate(&[a.key])
+            .and_then(|pk| {
+                pk.verify(
+                    &a.signature,
+                    &AttestLiveness::signature_msg(
+                        a.account_id,
+                        a.block_height,
+                    ),
+                )
+            })
+            .map_err(|_| Error::InvalidSignature)?;
+
+        let attested_at = account.liveness.entry(Key(a.key)).or_default();
+        *attested_at = a.block_height.max(*attested_at);
+        Ok(())
+    }
+
+    pub fn delegate_key(&mut self, d: &DelegateKey) -> Result<(), Error> {
+        let account = self
+            .accounts
+            .get_mut(&d.account_id)
+            .ok_or(Error::AccountNotFound)?;
+        if account.merged_into.is_some() {
+            return Err(Error::AccountMerged);
+        }
+
+        let key = Key(d.key);
+        if !account.keys.contains(&key) {
+            return Err(Error::UnknownKey);
+        }
+        if self.compromised_keys.contains(&key) {
+            return Err(Error::KeyCompromised);
+        }
+        let nonce = account.delegations.get(&key).map_or(0, |kd| kd.nonce);
+        if d.nonce != nonce + 1 {
+            return Err(Error::InvalidNonce);
+        }
+
+        if let Some(delegation) = &d.delegation {
+            if delegation.from_height > delegation.until_height {
+                return Err(Error::InvalidDelegationRange);
+            }
+            let delegate = Key(delegation.delegate);
+            if self.compromised_keys.contains(&delegate) {
+                return Err(Error::KeyCompromised);
+            }
+            let delegated = account.delegations.iter().any(|(other, kd)| {
+                *other != key
+                    && kd.delegation.as_ref().is_some_and(|other| {
+                        Key(other.delegate) == delegate
+                            && other.until_height >= self.block_height
+                    })
+            });
+            if account.keys.contains(&delegate)
+                || account.duress_keys.contains(&delegate)
+                || account.guardians.contains(&delegate)
+                || account.observers.contains(&delegate)
+                || delegated
+            {
+                return Err(Error::KeyAlreadyUsed);
+            }
+        }
+        MultisigPublicKey::aggregate(&[d.key])
+            .and_then(|pk| {
+                pk.verify(
+                    &d.signature,
+                    &DelegateKey::signature_msg(
+                        d.account_id,
+                        d.delegation.as_ref(),
+                        d.nonce,
+                    ),
+                )
+            })
+            .map_err(|_| Error::InvalidSignature)?;
+
+        account.delegations.insert(
+            key,
+            KeyDelegation {
+                key: d.key,
+                delegation: d.delegation.clone(),
+                nonce: d.nonce,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn guard_account(&mut self, g: &GuardAccount) -> Result<(), Error> {
+        let account = self
+            .accounts
+            .get_mut(&g.account_id)
+            .ok_or(Error::AccountNotFound)?;
+        if account.merged_into.is_some() {
+            return Err(Error::AccountMerged);
+        }
+
+        if !account.guardians.contains(&Key(g.key)) {
+            return Err(Error::NotGuardian);
+        }
+        if self.compromised_keys.contains(&Key(g.key)) {
+            return Err(Error::KeyCompromised);
+        }
+        MultisigPublicKey::aggregate(&[g.key])
+            .and_then(|pk| {
+                pk.verify(
+                    &g.signature,
+                    &GuardAccount::signature_msg(
+                        g.account_id,
+                        &g.action,
+                        account.nonce,
+                    ),
+                )
+            })
+            .map_err(|_| Error::InvalidSignature)?;
+
+        match &g.action {
+            GuardianAction::Lock => {
+                if account.locked {
+                    return Err(Error::AccountLocked);
+                }
+                account.locked = true;
+            }
+            GuardianAction::ExtendWhitelistDelay {
+                receiver,
+                usable_from,
+            } => {
+                let current = account
+                    .whitelist
+                    .get_mut(&Key(*receiver))
+                    .ok_or(Error::KeyNotUsed)?;
+                if *current <= self.block_height {
+                    return Err(Error::ReceiverAlreadyUsable);
+                }
+                if *usable_from <= *current {
+                    return Err(Error::DelayNotExtended);
+                }
+                *current = *usable_from;
+            }
+            GuardianAction::VetoWhitelisted { receiver } => {
+                let usable_from = *account
+                    .whitelist
+                    .get(&Key(*receiver))
+                    .ok_or(Error::KeyNotUsed)?;
+                if usable_from <= self.block_height {
+                    return Err(Error::ReceiverAlreadyUsable);
+                }
+                account.whitelist.remove(&Key(*receiver));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn start_recovery(&mut self, sr: &StartRecovery) -> Result<(), Error> {
+        let account = self
+            .accounts
+            .get(&sr.account_id)
+            .ok_or(Error::AccountNotFound)?;
+        if account.merged_into.is_some() {
+            return Err(Error::AccountMerged);
+        }
+        if sr.nonce != account.recovery_nonce + 1 {
+            return Err(Error::InvalidNonce);
+        }
+        let policy = account.recovery_policy.ok_or(Error::NoRecoveryPolicy)?;
+        if account.pending_recovery.is_some() {
+            return Err(Error::RecoveryPending);
+        }
+
+        let mut signers = BTreeSet::new();
+        for key in &sr.guardians {
+            if !account.guardians.contains(&Key(*key)) {
+                return Err(Error::NotGuardian);
+            }
+            if self.compromised_keys.contains(&Key(*key)) {
+                return Err(Error::KeyCompromised);
+            }
+            if !signers.insert(Key(*key)) {
+                return Err(Error::DuplicateKey);
+            }
+        }
+        if signers.len() < policy.guardians as usize {
+            return Err(Error::ThresholdNotMet);
+        }
+        self.check_replacement(account, &sr.keys, sr.threshold)?;
+        MultisigPublicKey::aggregate(&sr.guardians)
+            .and_then(|pk| pk.verify(&sr.signature, &sr.signature_msg()))
+            .map_err(|_| Error::InvalidSignature)?;
+
+        let account = self.accounts.get_mut(&sr.account_id).unwrap();
+        account.pending_recovery = Some(PendingRecovery {
+            guardians: sr.guardians.clone(),
+            keys: sr.keys.clone(),
+            threshold: sr.threshold,
+            executable_from: self.block_height.saturating_add(policy.delay),
+        });
+        account.recovery_nonce += 1;
+        account.last_active = self.block_height;
+        Ok(())
+    }
+
+    pub fn cancel_recovery(
+        &mut self,
+        cr: &CancelRecovery,
+    ) -> Result<(), Error> {
+        let account = self
+            .accounts
+            .get_mut(&cr.account_id)
+            .ok_or(Error::AccountNotFound)?;
+        if account.merged_into.is_some() {
+            return Err(Error::AccountMerged);
+        }
+
+        if !account.keys.contains(&Key(cr.key)) {
+            return Err(Error::UnknownKey);
+        }
+        if self.compromised_keys.contains(&Key(cr.key)) {
+            return Err(Error::KeyCompromised);
+        }
+        if !account.is_usable(Key(cr.key), self.block_height) {
+            return Err(Error::KeyNotYetUsable);
+        }
+        if !account.is_unexpired(Key(cr.key), self.block_height) {
+            return Err(Error::KeyExpired);
+        }
+        if account.pending_recovery.is_none() {
+            return Err(Error::NoRecoveryPending);
+        }
+        MultisigPublicKey::aggregate(&[cr.key])
+            .and_then(|pk| {
+                pk.verify(
+                    &cr.signature,
+                    &CancelRecovery::signature_msg(
+                        cr.account_id,
+                        account.recovery_nonce,
+                    ),
+                )
+            })
+            .map_err(|_| Error::InvalidSignature)?;
+
+        account.pending_recovery = None;
+        Ok(())
+    }
+
+    pub fn finalize_recovery(
+        &mut self,
+        fr: &FinalizeRecovery,
+    ) -> Result<(), Error> {
+        let account = self
+            .accounts
+            .get(&fr.account_id)
+            .ok_or(Error::AccountNotFound)?;
+        if account.merged_into.is_some() {
+            return Err(Error::AccountMerged);
+        }
+        let recovery = account
+            .pending_recovery
+            .clone()
+            .ok_or(Error::NoRecoveryPending)?;
+        if self.block_height < recovery.executable_from {
+            return Err(Error::RecoveryNotReady);
+        }
+        let policy = account.recovery_policy.ok_or(Error::NoRecoveryPolicy)?;
+        for key in &recovery.guardians {
+            if !account.guardians.contains(&Key(*key)) {
+                return Err(Error::NotGuardian);
+            }
+            if self.compromised_keys.contains(&Key(*key)) {
+                return Err(Error::KeyCompromised);
+            }
+        }
+        if recovery.guardians.len() < policy.guardians as usize {
+            return Err(Error::ThresholdNotMet);
+        }
+        self.check_replacement(account, &recovery.keys, recovery.threshold)?;
+
+        self.replace_keys(fr.account_id, &recovery.keys, recovery.threshold);
+        let account = self.accounts.get_mut(&fr.account_id).unwrap();
+        account.pending_recovery = None;
+        account.nonce += 1;
+        account.last_active = self.block_height;
+        Ok(())
+    }
+
+    pub fn claim_inactive(&mut self, ci: &ClaimInactive) -> Result<(), Error> {
+        check_unpaused(&self.config)?;
+
+        let account = self
+            .accounts
+            .get(&ci.account_id)
+            .ok_or(Error::AccountNotFound)?;
+        if account.merged_into.is_some() {
+            return Err(Error::AccountMerged);
+        }
+        if account.frozen {
+            return Err(Error::AccountFrozen);
+        }
+        if ci.nonce != account.nonce + 1 {
+            return Err(Error::InvalidNonce);
+        }
+        let policy = account
+            .inactivity_policy
+            .clone()
+            .ok_or(Error::NoInactivityPolicy)?;
+        if self.block_height < account.last_active.saturating_add(policy.period)
+        {
+            return Err(Error::AccountActive);
+        }
+        if self.compromised_keys.contains(&Key(policy.fallback)) {
+            return Err(Error::KeyCompromised);
+        }
+
+        match &ci.claim {
+            InactiveClaim::Sweep { receiver } => {
+                if account.locked {
+                    return Err(Error::AccountLocked);
+                }
+                if account.archived {
+                    return Err(Error::AccountArchived);
+                }
+                check_hold(account, Some(receiver))?;
+                check_licensed(&self.licenses, account, receiver)?;
+                if account.whitelist_enforced {
+                    match account.whitelist.get(&Key(*receiver)) {
+                        None => return Err(Error::ReceiverNotWhitelisted),
+                        Some(&usable_from)
+                            if self.block_height < usable_from =>
+                        {
+                            return Err(Error::ReceiverNotYetUsable)
+                        }
+                        Some(_) => {}
+                    }
+                }
+                if account.balance == 0 {
+                    return Err(Error::InsufficientBalance);
+                }
+                account.check_unqueued(account.balance)?;
+            }
+            InactiveClaim::RotateKeys { keys, threshold } => {
+                self.check_replacement(account, keys, *threshold)?;
+            }
+        }
+        MultisigPublicKey::aggregate(&[policy.fallback])
+            .and_then(|pk| {
+                pk.verify(
+                    &ci.signature,
+                    &ClaimInactive::signature_msg(
+                        ci.account_id,
+                        &ci.claim,
+                        ci.nonce,
+                    ),
+                )
+            })
+            .map_err(|_| Error::InvalidSignature)?;
+
+        match &ci.claim {
+            InactiveClaim::Sweep { receiver } => {
+                let account = self.accounts.get_mut(&ci.account_id).unwrap();
+                let amount = account.balance;
+                account.balance = 0;
+                match account.claimable_withdrawals {
+                    true => {
+                        self.record_withdrawal(ci.account_id, *receiver, amount)
+                    }
+                    false => {
+                        self.pay_or_defer(ci.account_id, *receiver, amount)
+                    }
+                }
+            }
+            InactiveClaim::RotateKeys { keys, threshold } => {
+                self.replace_keys(ci.account_id, keys, *threshold);
+            }
+        }
+        let account = self.accounts.get_mut(&ci.account_id).unwrap();
+        account.nonce += 1;
+        account.last_active = self.block_height;
+        Ok(())
+    }
+
+    pub fn transfer_with_allowance(
+        &mut self,
+        at: &AllowanceTransfer,
+    ) -> Result<(), Error> {
+        check_unpaused(&self.config)?;
+        check_memo(&self.config, &at.memo)?;
+
+        let account = self
+            .accounts
+            .get_mut(&at.account_id)
+            .ok_or(Error::AccountNotFound)?;
+        if account.merged_into.is_some() {
+            return Err(Error::AccountMerged);
+        }
+        if account.frozen {
+            return Err(Error::AccountFrozen);
+        }
+        if account.locked {
+            return Err(Error::AccountLocked);
+        }
+        if account.archived {
+            return Err(Error::AccountArchived);
+        }
+        check_hold(account, Some(&at.receiver))?;
+        check_licensed(&self.licenses, account, &at.receiver)?;
+        let refund = gas_refund_amount(
+            at.gas_refund.as_ref(),
+            self.config.max_gas_refund,
+        )?;
+        let available = account
+            .balance
+            .checked_sub(refund)
+            .ok_or(Error::InsufficientBalance)?;
+        if at.amount > available {
+            return Err(Error::InsufficientBalance);
+        }
+        account.check_unqueued(at.amount)?;
+        if at.nonce != account.nonce + 1 {
+            return Err(Error::InvalidNonce);
+        }
+        check_chain(account, at.chain_id)?;
+
+        let key = Key(at.key);
+        if !account.keys.contains(&key) {
+            return Err(Error::UnknownKey);
+        }
+        if self.compromised_keys.contains(&key) {
+            return Err(Error::KeyCompromised);
+        }
+        if !account.is_usable(key, self.block_height) {
+            return Err(Error::KeyNotYetUsable);
+        }
+        if !account.is_unexpired(key, self.block_height) {
+            return Err(Error::KeyExpired);
+        }
+        let allowance =
+            *account.allowances.get(&key).ok_or(Error::NoAllowance)?;
+        let remaining = allowance
+            .checked_sub(at.amount)
+            .and_then(|remaining| remaining.checked_sub(refund))
+            .ok_or(Error::AllowanceExceeded)?;
+        MultisigPublicKey::aggregate(&[at.key])
+            .and_then(|pk| pk.verify(&at.signature, &at.signature_msg()))
+            .map_err(|_| Error::InvalidSignature)?;
+
+        account.check_transfer_window(
+            self.block_height,
+            false,
+            account.signed_weight(&[at.key], 0, self.block_height),
+        )?;
+        if account.whitelist_enforced {
+            match account.whitelist.get(&Key(at.receiver)) {
+                None => return Err(Error::ReceiverNotWhitelisted),
+                Some(&usable_from) if self.block_height < usable_from => {
+                    return Err(Error::ReceiverNotYetUsable)
+                }
+                Some(_) => {}
+            }
+        }
+
+        account.record_usage(&[at.key], self.block_height);
+        match remaining {
+            0 => account.allowances.remove(&key),
+            remaining => account.allowances.insert(key, remaining),
+        };
+        account.balance -= at.amount + refund;
+        account.nonce += 1;
+        account.last_active = self.block_height;
+        match account.claimable_withdrawals {
+            true => {
+                self.record_withdrawal(at.account_id, at.receiver, at.amount)
+            }
+            false => self.pay_or_defer(at.account_id, at.receiver, at.amount),
+        }
+        self.refund_gas(at.account_id, at.gas_refund.as_ref());
+        Ok(())
+    }
+
+    pub fn cancel_queued(&mut self, cq: &CancelQueued) -> Result<(), Error> {
+        let account = self
+            .accounts
+            .get_mut(&cq.account_id)
+            .ok_or(Error::AccountNotFound)?;
+        if account.merged_into.is_some() {
+            return Err(Error::AccountMerged);
+        }
+        if !account.keys.contains(&Key(cq.key)) {
+            return Err(Error::UnknownKey);
+        }
+        if self.compromised_keys.contains(&Key(cq.key)) {
+            return Err(Error::KeyCompromised);
+        }
+        if !account.is_usable(Key(cq.key), self.block_height) {
+            return Err(Error::KeyNotYetUsable);
+        }
+        if !account.is_unexpired(Key(cq.key), self.block_height) {
+            return Err(Error::KeyExpired);
+        }
+        if !account.queued_transfers.contains_key(&cq.nonce) {
+            return Err(Error::NoQueuedTransfer);
+        }
+        MultisigPublicKey::aggregate(&[cq.key])
+            .and_then(|pk| {
+                pk.verify(
+                    &cq.signature,
+                    &CancelQueued::signature_msg(cq.account_id, cq.nonce),
+                )
+            })
+            .map_err(|_| Error::InvalidSignature)?;
+
+        account.queued_transfers.remove(&cq.nonce);
+        Ok(())
+    }
+
+    pub fn execute_queued(&mut self, eq: &ExecuteQueued) -> Result<(), Error> {
+        check_unpaused(&self.config)?;
+
+        let account = self
+            .accounts
+            .get_mut(&eq.account_id)
+            .ok_or(Error::AccountNotFound)?;
+        if account.merged_into.is_some() {
+            return Err(Error::AccountMerged);
+        }
+        if account.frozen {
+            return Err(Error::AccountFrozen);
+        }
+        if account.locked {
+            return Err(Error::AccountLocked);
+        }
+        if account.archived {
+            return Err(Error::AccountArchived);
+        }
+        let queued = account
+            .queued_transfers
+            .get(&eq.nonce)
+            .cloned()
+            .ok_or(Error::NoQueuedTransfer)?;
+        if self.block_height < queued.executable_from {
+            return Err(Error::QueuedTransferNotReady);
+        }
+        if self.block_height
+            > queued.executable_from.saturating_add(QUEUE_EXPIRY)
+        {
+            account.queued_transfers.remove(&eq.nonce);
+            return Ok(());
+        }
+        for key in &queued.keys {
+            let Some(principal) =
+                account.principal_of(Key(*key), self.block_height)
+            else {
+                return Err(Error::UnknownKey);
+            };
+            if self.compromised_keys.contains(&Key(*key))
+                || self.compromised_keys.contains(&principal)
+            {
+                return Err(Error::KeyCompromised);
+            }
+        }
+        for cosigner in &queued.cosigners {
+            if !account.cosigners.contains(cosigner) {
+                return Err(Error::UnknownKey);
+            }
+            if self.compromised_cosigners.contains(cosigner) {
+                return Err(Error::KeyCompromised);
+            }
+        }
+        account.check_transfer_window(
+            self.block_height,
+            queued.group_signed,
+            account.signed_weight(
+                &queued.keys,
+                queued.cosigners.len(),
+                self.block_height,
+            ),
+        )?;
+        check_hold(account, Some(&queued.receiver))?;
+        check_licensed(&self.licenses, account, &queued.receiver)?;
+        // Skipped, staying queued
+        if queued.amount > account.balance {
+            return Ok(());
+        }
+        if account.whitelist_enforced {
+            match account.whitelist.get(&Key(queued.receiver)) {
+                None => return Err(Error::ReceiverNotWhitelisted),
+                Some(&usable_from) if self.block_height < usable_from => {
+                    return Err(Error::ReceiverNotYetUsable)
+                }
+                Some(_) => {}
+            }
+        }
+        if let Some(category) = &queued.category {
+            let spent = account.spend_category(
+                category,
+                queued.amount,
+                self.block_height,
+            )?;
+            account.category_spending.insert(category.clone(), spent);
+        }
+
+        account.queued_transfers.remove(&eq.nonce);
+        account.balance -= queued.amount;
+        match account.claimable_withdrawals {
+            true => self.record_withdrawal(
+                eq.account_id,
+                queued.receiver,
+                queued.amount,
+            ),
+            false => {
+                self.pay_or_defer(eq.account_id, queued.receiver, queued.amount)
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces the keys and threshold of the account, dropping whatever was
+    /// tied to the keys replaced, the transfers they queued included.
+    fn replace_keys(
+        &mut self,
+        account_id: u64,
+        keys: &[PublicKey],
+        threshold: u32,
+    ) {
+        let keys: BTreeSet<_> = keys.iter().copied().map(Key).collect();
+        let account = self.accounts.get_mut(&account_id).unwrap();
+        for key in account.keys.difference(&keys) {
+            if let Some(ids) = self.key_accounts.get_mut(key) {
+                ids.remove(&account_id);
+            }
+        }
+        for key in keys.difference(&account.keys) {
+            self.key_accounts
+                .entry(*key)
+                .or_default()
+                .insert(account_id);
+        }
+
+        account.keys = keys;
+        account.threshold = threshold;
+        account.group_key = None;
+        account.operation_thresholds.clear();
+        account.transfer_tiers.clear();
+        account.duress_keys.clear();
+        account.pending_keys.clear();
+        account.key_expiries.clear();
+        account.key_usage.clear();
+        account.key_weights.clear();
+        account.key_roles.clear();
+        account.liveness.clear();
+        account.queued_transfers.clear();
+        account.allowances.clear();
+        if account.liveness_policy.is_some() {
+            for key in &account.keys {
+                account.liveness.insert(*key, self.block_height);
+            }
+        }
+        for kd in account.delegations.values_mut() {
+            kd.delegation = None;
+        }
+    }
+
+    /// Checks the keys and threshold a recovery or a claim of the account
+    /// while inactive would give it.
+    fn check_replacement(
+        &self,
+        account: &ModelAccount,
+        keys: &[PublicKey],
+        threshold: u32,
+    ) -> Result<(), Error> {
+        if keys.is_empty() {
+            return Err(Error::NoKeys);
+        }
+        if threshold < 1 {
+            return Err(Error::ZeroThreshold);
+        }
+        check_key_count(&self.config, keys.len())?;
+        if threshold as usize > keys.len() + account.cosigners.len() {
+            return Err(Error::ThresholdTooLarge);
+        }
+
+        let mut unique = BTreeSet::new();
+        for key in keys {
+            if !unique.insert(Key(*key)) {
+                return Err(Error::DuplicateKey);
+            }
+            if self.compromised_keys.contains(&Key(*key)) {
+                return Err(Error::KeyCompromised);
+            }
+            if account.guardians.contains(&Key(*key))
+                || account.observers.contains(&Key(*key))
+            {
+                return Err(Error::KeyAlreadyUsed);
+            }
+        }
+
+        if let Some(policy) = account.license_policy.filter(|p| p.signers) {
+            if keys.iter().any(|key| {
+                !self.licenses.contains(&(policy.contract, Key(*key)))
+            }) {
+                return Err(Error::KeyNotLicensed);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn merge_accounts(&mut self, m: &MergeAccounts) -> Result<(), Error> {
+        check_unpaused(&self.config)?;
+        if m.account_id == m.merged_id {
+            return Err(Error::MergeIntoSelf);
+        }
+        if self.governance == Some(m.merged_id) {
+            return Err(Error::GovernanceAccount);
+        }
+        for (id, nonce) in
+            [(m.account_id, m.nonce), (m.merged_id, m.merged_nonce)]
+        {
+            let account =
+                self.accounts.get(&id).ok_or(Error::AccountNotFound)?;
+            if account.merged_into.is_some() {
+                return Err(Error::AccountMerged);
+            }
+            if account.frozen {
+                return Err(Error::AccountFrozen);
+            }
+            if account.locked {
+                return Err(Error::AccountLocked);
+            }
+            if account.archived {
+                return Err(Error::AccountArchived);
+            }
+            check_hold(account, None)?;
+            if nonce != account.nonce + 1 {
+                return Err(Error::InvalidNonce);
+            }
+        }
+
+        let msg = m.signature_msg();
+        let duress = Self::authorize(
+            &self.accounts[&m.account_id],
+            Authority {
+                op: OperationKind::MergeAccounts,
+                tier: None,
+                compromised_keys: &self.compromised_keys,
+                compromised_cosigners: &self.compromised_cosigners,
+                block_height: self.block_height,
+            },
+            &m.keys,
+            &m.signature,
+            &m.cosignatures,
+            m.group_signature.as_ref(),
+            &msg,
+        )?;
+        let merged_duress = Self::authorize(
+            &self.accounts[&m.merged_id],
+            Authority {
+                op: OperationKind::MergeAccounts,
+                tier: self.accounts[&m.merged_id].tier_of(
+                    OperationKind::MergeAccounts,
+                    self.accounts[&m.merged_id].balance,
+                ),
+                compromised_keys: &self.compromised_keys,
+                compromised_cosigners: &self.compromised_cosigners,
+                block_height: self.block_height,
+            },
+            &m.merged_keys,
+            &m.merged_signature,
+            &m.merged_cosignatures,
+            m.merged_group_signature.as_ref(),
+            &msg,
+        )?;
+        if duress || merged_duress {
+            self.accounts.get_mut(&m.account_id).unwrap().frozen |= duress;
+            self.accounts.get_mut(&m.merged_id).unwrap().frozen |=
+                merged_duress;
+            return Ok(());
+        }
+
+        let account = &self.accounts[&m.account_id];
+        if account.key_addition_policy.is_some_and(|p| p.supermajority)
+            && m.group_signature.is_none()
+            && !account.supermajority_met(account.signed_weight(
+                &m.keys,
+                m.cosignatures.len(),
+                self.block_height,
+            ))
+        {
+            return Err(Error::SupermajorityNotMet);
+        }
+
+        let merged = &self.accounts[&m.merged_id];
+        merged.check_transfer_window(
+            self.block_height,
+            m.merged_group_signature.is_some(),
+            merged.signed_weight(
+                &m.merged_keys,
+                m.merged_cosignatures.len(),
+                self.block_height,
+            ),
+        )?;
+        merged.check_unqueued(merged.balance)?;
+        merged.check_unrestricted()?;
+        let account = &self.accounts[&m.account_id];
+        if !merged.keys.is_empty() {
+            let keys = account.keys.union(&merged.keys).count();
+            check_key_count(&self.config, keys)?;
+        }
+        if let Some(policy) = account.license_policy.filter(|p| p.signers) {
+            if merged.keys.iter().any(|key| {
+                !account.keys.contains(key)
+                    && !self.licenses.contains(&(policy.contract, *key))
+            }) {
+                return Err(Error::KeyNotLicensed);
+            }
+        }
+        for key in &merged.keys {
+            if self.compromised_keys.contains(key) {
+                return Err(Error::KeyCompromised);
+            }
+            if account.duress_keys.contains(key)
+                || account.guardians.contains(key)
+                || account.observers.contains(key)
+            {
+                return Err(Error::KeyAlreadyUsed);
+            }
+        }
+
+        let merged = self.accounts.get_mut(&m.merged_id).unwrap();
+        // The usage of the merged account's keys goes with it, signing the
+        // merge included
+        merged.key_usage.clear();
+        let amount = merged.balance;
+        let keys = std::mem::take(&mut merged.keys);
+        let cosigners = std::mem::take(&mut merged.cosigners);
+        merged.balance = 0;
+        merged.nonce += 1;
+        merged.last_active = self.block_height;
+        merged.merged_into = Some(m.account_id);
+        merged.alias = None;
+        merged.tags.clear();
+        merged.category_spending.clear();
+        merged.budget_draws.clear();
+        merged.liveness.clear();
+        merged.delegations.clear();
+        merged.transfer_tiers.clear();
+        merged.pending_recovery = None;
+        merged.queued_transfers.clear();
+        merged.allowances.clear();
+        let merged_pending_keys = std::mem::take(&mut merged.pending_keys);
+        let merged_key_expiries = std::mem::take(&mut merged.key_expiries);
+        let merged_key_weights = std::mem::take(&mut merged.key_weights);
+        let merged_key_roles = std::mem::take(&mut merged.key_roles);
+        merged.duress_keys.clear();
+        merged.guardians.clear();
+        merged.observers.clear();
+        merged.whitelist.clear();
+
+        for key in &keys {
+            let ids = self.key_accounts.get_mut(key).unwrap();
+            ids.remove(&m.merged_id);
+            ids.insert(m.account_id);
+        }
+
+        let account = self.accounts.get_mut(&m.account_id).unwrap();
+        account.record_usage(&m.keys, self.block_height);
+        // As with keys added by a change, the keys taken over are live as of
+        // the merge
+        if account.liveness_policy.is_some() {
+            for key in keys.difference(&account.keys) {
+                account.liveness.insert(*key, self.block_height);
+            }
+        }
+        // ...and wait out the delay of the policy, or whatever was left of
+        // theirs in the merged account, whichever ends later
+        let delay = account.key_addition_policy.map_or(0, |p| p.delay);
+        let usable_from = self.block_height.saturating_add(delay);
+        for key in keys.difference(&account.keys) {
+            let usable_from = merged_pending_keys
+                .get(key)
+                .map_or(usable_from, |&h| h.max(usable_from));
+            if usable_from > self.block_height {
+                account.pending_keys.insert(*key, usable_from);
+            }
+            // ...and lapse when they would have
+            if let Some(&valid_until) = merged_key_expiries.get(key) {
+                account.key_expiries.insert(*key, valid_until);
+            }
+            // ...and weigh what they did
+            if let Some(&weight) = merged_key_weights.get(key) {
+                account.key_weights.insert(*key, weight);
+            }
+            // ...and keep their role
+            if let Some(&role) = merged_key_roles.get(key) {
+                account.key_roles.insert(*key, role);
+            }
+        }
+        account.keys.extend(keys);
+        account.cosigners.extend(cosigners);
+        account.balance += amount;
+        account.nonce += 1;
+        account.last_active = self.block_height;
+        Ok(())
+    }
+
+    pub fn split_account(&mut self, s: &SplitAccount) -> Result<(), Error> {
+        check_unpaused(&self.config)?;
+
+        let account = self
+            .accounts
+            .get_mut(&s.account_id)
+            .ok_or(Error::AccountNotFound)?;
+        if account.merged_into.is_some() {
+            return Err(Error::AccountMerged);
+        }
+        if account.frozen {
+            return Err(Error::AccountFrozen);
+        }
+        if account.locked {
+            return Err(Error::AccountLocked);
+        }
+        if account.archived {
+            return Err(Error::AccountArchived);
+        }
+        check_hold(account, None)?;
+        let refund = gas_refund_amount(
+            s.gas_refund.as_ref(),
+            self.config.max_gas_refund,
+        )?;
+        if account.balance.checked_sub(refund) < Some(s.amount) {
+            return Err(Error::InsufficientBalance);
+        }
+        account.check_unqueued(s.amount)?;
+        if s.nonce != account.nonce + 1 {
+            return Err(Error::InvalidNonce);
+        }
+        check_chain(account, s.chain_id)?;
+        let duress = Self::authorize(
+            account,
+            Authority {
+                op: OperationKind::SplitAccount,
+                tier: account.tier_of(OperationKind::SplitAccount, s.amount),
+                compromised_keys: &self.compromised_keys,
+                compromised_cosigners: &self.compromised_cosigners,
+                block_height: self.block_height,
+            },
+            &s.keys,
+            &s.signature,
+            &s.cosignatures,
+            s.group_signature.as_ref(),
+            &s.signature_msg(),
+        )?;
+        if duress {
+            account.frozen = true;
+            return Ok(());
+        }
+
+        account.check_transfer_window(
+            self.block_height,
+            s.group_signature.is_some(),
+            account.signed_weight(
+                &s.keys,
+                s.cosignatures.len(),
+                self.block_height,
+            ),
+        )?;
+        account.check_unrestricted()?;
+        if s.new_keys
+            .iter()
+            .any(|key| !account.keys.contains(&Key(*key)))
+        {
+            return Err(Error::KeyNotInAccount);
+        }
+
+        let new_account_id = self.create_account(&CreateAccount {
+            keys: s.new_keys.clone(),
+            threshold: s.threshold,
+            description: s.description.clone(),
+            alias: None,
+            key_weights: Vec::new(),
+            key_roles: Vec::new(),
+        })?;
+        self.accounts.get_mut(&new_account_id).unwrap().balance = s.amount;
+
+        let account = self.accounts.get_mut(&s.account_id).unwrap();
+        account.record_usage(&s.keys, self.block_height);
+        account.balance -= s.amount + refund;
+        account.nonce += 1;
+        account.last_active = self.block_height;
+        self.refund_gas(s.account_id, s.gas_refund.as_ref());
+        Ok(())
+    }
+
+    pub fn draw_budget(&mut self, d: &DrawBudget) -> Result<(), Error> {
+        check_unpaused(&self.config)?;
+
+        let account = self
+            .accounts
+            .get(&d.account_id)
+            .ok_or(Error::AccountNotFound)?;
+        if account.merged_into.is_some() {
+            return Err(Error::AccountMerged);
+        }
+        if account.frozen {
+            return Err(Error::AccountFrozen);
+        }
+        if account.archived {
+            return Err(Error::AccountArchived);
+        }
+        if d.nonce != account.nonce + 1 {
+            return Err(Error::InvalidNonce);
+        }
+        let parent = self
+            .accounts
+            .get(&d.parent_id)
+            .ok_or(Error::AccountNotFound)?;
+        if parent.merged_into.is_some() {
+            return Err(Error::AccountMerged);
+        }
+        if parent.frozen {
+            return Err(Error::AccountFrozen);
+        }
+        if parent.locked {
+            return Err(Error::AccountLocked);
+        }
+        if parent.archived {
+            return Err(Error::AccountArchived);
+        }
+        check_hold(parent, None)?;
+        parent.check_unqueued(d.amount)?;
+        parent.check_unrestricted()?;
+        let budget = parent
+            .child_budgets
+            .iter()
+            .find(|b| b.child_id == d.account_id)
+            .copied()
+            .ok_or(Error::UnknownChildBudget)?;
+
+        let account = self.accounts.get_mut(&d.account_id).unwrap();
+        let duress = Self::authorize(
+            account,
+            Authority {
+                op: OperationKind::DrawBudget,
+                tier: None,
+                compromised_keys: &self.compromised_keys,
+                compromised_cosigners: &self.compromised_cosigners,
+                block_height: self.block_height,
+            },
+            &d.keys,
+            &d.signature,
+            &d.cosignatures,
+            d.group_signature.as_ref(),
+            &d.signature_msg(),
+        )?;
+        if duress {
+            account.frozen = true;
+            return Ok(());
+        }
+
+        let window_start =
+            self.block_height - self.block_height % budget.window;
+        let parent = &self.accounts[&d.parent_id];
+        let (drawn, held) = parent.budget_draws.get(&d.account_id).map_or(
+            (0, 0),
+            |&(start, drawn, held)| {
+                (if start == window_start { drawn } else { 0 }, held)
+            },
+        );
+        let drawn = drawn
+            .checked_add(d.amount)
+            .filter(|&total| total <= budget.amount)
+            .ok_or(Error::ChildBudgetExceeded)?;
+        if d.amount > parent.balance {
+            return Err(Error::InsufficientBalance);
+        }
+        let account = &self.accounts[&d.account_id];
+        if account
+            .balance_cap
+            .is_some_and(|cap| d.amount > cap.saturating_sub(account.balance))
+        {
+            return Err(Error::BalanceCapExceeded);
+        }
+
+        let parent = self.accounts.get_mut(&d.parent_id).unwrap();
+        parent.balance -= d.amount;
+        parent.budget_draws.insert(
+            d.account_id,
+            (window_start, drawn, held.saturating_add(d.amount)),
+        );
+        let account = self.accounts.get_mut(&d.account_id).unwrap();
+        account.record_usage(&d.keys, self.block_height);
+        account.balance += d.amount;
+        account.nonce += 1;
+        account.last_active = self.block_height;
+        Ok(())
+    }
+
+    pub fn record_checkpoint(
+        &mut self,
+        rc: &RecordCheckpoint,
+    ) -> Result<(), Error> {
+        let account = self
+            .accounts
+            .get_mut(&rc.account_id)
+            .ok_or(Error::AccountNotFound)?;
+        if account.merged_into.is_some() {
+            return Err(Error::AccountMerged);
+        }
+        if account.frozen {
+            return Err(Error::AccountFrozen);
+        }
+        if rc.nonce != account.nonce + 1 {
+            return Err(Error::InvalidNonce);
+        }
+        let duress = Self::authorize(
+            account,
+            Authority {
+                op: OperationKind::RecordCheckpoint,
+                tier: None,
+                compromised_keys: &self.compromised_keys,
+                compromised_cosigners: &self.compromised_cosigners,
+                block_height: self.block_height,
+            },
+            &rc.keys,
+            &rc.signature,
+            &rc.cosignatures,
+            rc.group_signature.as_ref(),
+            &rc.signature_msg(),
+        )?;
+        if duress {
+            account.frozen = true;
+            return Ok(());
+        }
+
+        account.record_usage(&rc.keys, self.block_height);
+        account.nonce += 1;
+        account.last_active = self.block_height;
+        let keys: Vec<_> = account.keys.iter().map(|key| key.0).collect();
+        account.checkpoints.push((
+            account.balance,
+            account.nonce,
+            hash_keys(&keys),
+            self.block_height,
+        ));
+        account.retain_checkpoints();
+        Ok(())
+    }
+
+    pub fn configure(&mut self, c: &Configure) -> Result<(), Error> {
+        let account_id = self.governance.ok_or(Error::NoGovernance)?;
+        let account = self.accounts.get_mut(&account_id).unwrap();
+        if account.merged_into.is_some() {
+            return Err(Error::AccountMerged);
+        }
+        if account.frozen {
+            return Err(Error::AccountFrozen);
+        }
+        if c.nonce != account.nonce + 1 {
+            return Err(Error::InvalidNonce);
+        }
+        if !c.config.is_valid() {
+            return Err(Error::InvalidConfig);
+        }
+        let duress = Self::authorize(
+            account,
+            Authority {
+                op: OperationKind::Configure,
+                tier: None,
+                compromised_keys: &self.compromised_keys,
+                compromised_cosigners: &self.compromised_cosigners,
+                block_height: self.block_height,
+            },
+            &c.keys,
+            &c.signature,
+            &c.cosignatures,
+            c.group_signature.as_ref(),
+            &c.signature_msg(),
+        )?;
+        if duress {
+            account.frozen = true;
+            return Ok(());
+        }
+
+        account.record_usage(&c.keys, self.block_height);
+        account.nonce += 1;
+        account.last_active = self.block_height;
+        self.config = c.config;
+        Ok(())
+    }
+
+    pub fn payroll(&mut self, p: &Payroll) -> Result<(), Error> {
+        check_unpaused(&self.config)?;
+        for row in &p.rows {
+            check_memo(&self.config, &row.memo)?;
+        }
+
+        let account = self
+            .accounts
+            .get_mut(&p.account_id)
+            .ok_or(Error::AccountNotFound)?;
+        if account.merged_into.is_some() {
+            return Err(Error::AccountMerged);
+        }
+        if account.frozen {
+            return Err(Error::AccountFrozen);
+        }
+        if account.locked {
+            return Err(Error::AccountLocked);
+        }
+        if account.archived {
+            return Err(Error::AccountArchived);
+        }
+        if p.rows.is_empty() {
+            return Err(Error::EmptyPayroll);
+        }
+        for row in &p.rows {
+            check_hold(account, Some(&row.receiver))?;
+            check_licensed(&self.licenses, account, &row.receiver)?;
+        }
+        let refund = gas_refund_amount(
+            p.gas_refund.as_ref(),
+            self.config.max_gas_refund,
+        )?;
+        let total = p
+            .rows
+            .iter()
+            .try_fold(refund, |total, row| total.checked_add(row.amount))
+            .filter(|&total| total <= account.balance)
+            .ok_or(Error::InsufficientBalance)?;
+        account.check_unqueued(total - refund)?;
+        if p.nonce != account.nonce + 1 {
+            return Err(Error::InvalidNonce);
+        }
+        check_chain(account, p.chain_id)?;
+        let duress = Self::authorize(
+            account,
+            Authority {
+                op: OperationKind::Payroll,
+                tier: account.tier_of(OperationKind::Payroll, total - refund),
+                compromised_keys: &self.compromised_keys,
+                compromised_cosigners: &self.compromised_cosigners,
+                block_height: self.block_height,
+            },
+            &p.keys,
+            &p.signature,
+            &p.cosignatures,
+            p.group_signature.as_ref(),
+            &p.signature_msg(),
+        )?;
+        if duress {
+            account.frozen = true;
+            return Ok(());
+        }
+
+        account.check_transfer_window(
+            self.block_height,
+            p.group_signature.is_some(),
+            account.signed_weight(
+                &p.keys,
+                p.cosignatures.len(),
+                self.block_height,
+            ),
+        )?;
+        if account.whitelist_enforced {
+            for row in &p.rows {
+                match account.whitelist.get(&Key(row.receiver)) {
+                    None => return Err(Error::ReceiverNotWhitelisted),
+                    Some(&usable_from) if self.block_height < usable_from => {
+                        return Err(Error::ReceiverNotYetUsable)
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+
+        // The total already includes the refund
+        account.record_usage(&p.keys, self.block_height);
+        account.balance -= total;
+        account.nonce += 1;
+        account.last_active = self.block_height;
+        for row in &p.rows {
+            self.pay_or_defer(p.account_id, row.receiver, row.amount);
+        }
+        self.refund_gas(p.account_id, p.gas_refund.as_ref());
+        Ok(())
+    }
+
+    pub fn certified(&mut self, c: &CertifiedOperation) -> Result<(), Error> {
+        let account = self
+            .accounts
+            .get(&c.operation.account_id())
+            .ok_or(Error::AccountNotFound)?;
+        if account.merged_into.is_some() {
+            return Err(Error::AccountMerged);
+        }
+
+        let account_keys: Vec<_> =
+            account.keys.iter().map(|key| key.0).collect();
+        let keys = c.keys(&account_keys).ok_or(Error::InvalidCertificate)?;
+
+        let mut operation = c.operation.clone();
+        operation.set_keys(keys);
+        match &operation {
+            Operation::Transfer(t) => self.transfer(t),
+            Operation::ChangeAccount(c) => self.change_account(c),
+            Operation::SplitAccount(s) => self.split_account(s),
+            Operation::Payroll(p) => self.payroll(p),
+            Operation::TransferAndChange(tc) => self.transfer_and_change(tc),
+        }
+    }
+
+    pub fn transfer_and_change(
+        &mut self,
+        tc: &TransferAndChange,
+    ) -> Result<(), Error> {
+        check_unpaused(&self.config)?;
+        check_memo(&self.config, &tc.memo)?;
+
+        let account = self
+            .accounts
+            .get_mut(&tc.account_id)
+            .ok_or(Error::AccountNotFound)?;
+        if account.merged_into.is_some() {
+            return Err(Error::AccountMerged);
+        }
+        if account.frozen {
+            return Err(Error::AccountFrozen);
+        }
+        if account.locked {
+            return Err(Error::AccountLocked);
+        }
+        if account.archived {
+            return Err(Error::AccountArchived);
+        }
+        check_hold(account, Some(&tc.receiver))?;
+        check_licensed(&self.licenses, account, &tc.receiver)?;
+        let refund = gas_refund_amount(
+            tc.gas_refund.as_ref(),
+            self.config.max_gas_refund,
+        )?;
+        let available = account
+            .balance
+            .checked_sub(refund)
+            .ok_or(Error::InsufficientBalance)?;
+        let amount = match tc.amount {
+            TRANSFER_ALL => available,
+            amount => amount,
+        };
+        if amount > available {
+            return Err(Error::InsufficientBalance);
+        }
+        account.check_unqueued(amount)?;
+        if tc.nonce != account.nonce + 1 {
+            return Err(Error::InvalidNonce);
+        }
+        check_chain(account, tc.chain_id)?;
+        let duress = Self::authorize(
+            account,
+            Authority {
+                op: OperationKind::TransferAndChange,
+                tier: account.tier_of(OperationKind::TransferAndChange, amount),
+                compromised_keys: &self.compromised_keys,
+                compromised_cosigners: &self.compromised_cosigners,
+                block_height: self.block_height,
+            },
+            &tc.keys,
+            &tc.signature,
+            &tc.cosignatures,
+            tc.group_signature.as_ref(),
+            &tc.signature_msg(),
+        )?;
+        if duress {
+            account.frozen = true;
+            return Ok(());
+        }
+
+        account.check_transfer_window(
+            self.block_height,
+            tc.group_signature.is_some(),
+            account.signed_weight(
+                &tc.keys,
+                tc.cosignatures.len(),
+                self.block_height,
+            ),
+        )?;
+        if account.whitelist_enforced {
+            match account.whitelist.get(&Key(tc.receiver)) {
+                None => return Err(Error::ReceiverNotWhitelisted),
+                Some(&usable_from) if self.block_height < usable_from => {
+                    return Err(Error::ReceiverNotYetUsable)
+                }
+                Some(_) => {}
+            }
+        }
+        let claimable = account.claimable_withdrawals;
+
+        // The transfer is made before the changes, on the same copy
+        let signed = tc.group_signature.is_none().then(|| {
+            account.signed_weight(
+                &tc.keys,
+                tc.cosignatures.len(),
+                self.block_height,
+            )
+        });
+        let mut changed = account.clone();
+        changed.record_usage(&tc.keys, self.block_height);
+        changed.balance -= amount;
+        let clawed_back = self.apply_changes(
+            tc.account_id,
+            &mut changed,
+            &tc.changes,
+            signed,
+        )?;
+
+        if tc.gas_refund.is_some() {
+            if changed.locked {
+                return Err(Error::AccountLocked);
+            }
+            if changed.archived {
+                return Err(Error::AccountArchived);
+            }
+        }
+
+        changed.balance -= refund;
+        self.commit_changes(tc.account_id, changed, clawed_back);
+        match claimable {
+            true => self.record_withdrawal(tc.account_id, tc.receiver, amount),
+            false => self.pay_or_defer(tc.account_id, tc.receiver, amount),
+        }
+        self.refund_gas(tc.account_id, tc.gas_refund.as_ref());
+        Ok(())
+    }
+
+    pub fn claim_withdrawal(
+        &mut self,
+        c: &ClaimWithdrawal,
+    ) -> Result<(), Error> {
+        check_unpaused(&self.config)?;
+
+        let withdrawal = self
+            .withdrawals
+            .get(&c.claim_id)
+            .ok_or(Error::WithdrawalNotFound)?;
+        if Key(withdrawal.receiver) != Key(c.receiver) {
+            return Err(Error::NotWithdrawalReceiver);
+        }
+        MultisigPublicKey::aggregate(&[c.receiver])
+            .and_then(|pk| {
+                pk.verify(
+                    &c.signature,
+                    &ClaimWithdrawal::signature_msg(c.claim_id),
+                )
+            })
+            .map_err(|_| Error::InvalidSignature)?;
+        if withdrawal.amount > self.custody {
+            return Err(Error::TransferFailed);
+        }
+
+        self.custody -= withdrawal.amount;
+        self.withdrawals.remove(&c.claim_id);
+        Ok(())
+    }
+
+    pub fn mark_compromised(
+        &mut self,
+        mc: &MarkCompromised,
+    ) -> Result<(), Error> {
+        MultisigPublicKey::aggregate(&[mc.key])
+            .and_then(|pk| {
+                pk.verify(
+                    &mc.signature,
+                    &MarkCompromised::signature_msg(&mc.key),
+                )
+            })
+            .map_err(|_| Error::InvalidSignature)?;
+
+        if !self.compromised_keys.insert(Key(mc.key)) {
+            return Err(Error::KeyCompromised);
+        }
+        Ok(())
+    }
+
+    pub fn mark_cosigner_compromised(
+        &mut self,
+        mc: &MarkCosignerCompromised,
+    ) -> Result<(), Error> {
+        let msg = MarkCosignerCompromised::signature_msg(&mc.key);
+        if !mc.key.verify(&msg, &mc.signature) {
+            return Err(Error::InvalidSignature);
+        }
+
+        if !self.compromised_cosigners.insert(mc.key) {
+            return Err(Error::KeyCompromised);
+        }
+        Ok(())
+    }
+
+    pub fn replace_key(&mut self, rk: &ReplaceKey) -> Result<(), Error> {
+        MultisigPublicKey::aggregate(&[rk.old_key])
+            .and_then(|pk| {
+                pk.verify(
+                    &rk.signature,
+                    &ReplaceKey::signature_msg(&rk.old_key, &rk.new_key),
+                )
+            })
+            .map_err(|_| Error::InvalidSignature)?;
+        if rk.old_key == rk.new_key {
+            return Err(Error::InvalidKeyReplacement);
+        }
+
+        let account_ids = match self.key_accounts.get(&Key(rk.old_key)) {
+            Some(ids) if !ids.is_empty() => ids.clone(),
+            _ => return Err(Error::KeyNotUsed),
+        };
+        let expected = ReplaceKey::account_changes(rk.old_key, rk.new_key);
+        let mut changed_ids = BTreeSet::new();
+        for c in &rk.changes {
+            if c.changes != expected {
+                return Err(Error::InvalidKeyReplacement);
+            }
+            if !changed_ids.insert(c.account_id) {
+                return Err(Error::KeyReplacementIncomplete);
+            }
+        }
+        if changed_ids != account_ids {
+            return Err(Error::KeyReplacementIncomplete);
+        }
+
+        // The changes are applied to a copy, since any failing fails all
+        let mut model = self.clone();
+        for c in &rk.changes {
+            model.change_account(c)?;
+        }
+        *self = model;
+        Ok(())
+    }
+}
+
+/// Returns the amount of a gas refund, failing if it's over the bound.
+fn gas_refund_amount(
+    refund: Option<&GasRefund>,
+    max: u64,
+) -> Result<u64, Error> {
+    let amount = refund.map_or(0, |refund| refund.amount);
+    if amount > max {
+        return Err(Error::GasRefundTooLarge);
+    }
+    Ok(amount)
+}
+
+/// Fails if the contract is paused.
+fn check_unpaused(config: &ContractConfig) -> Result<(), Error> {
+    if config.paused {
+        return Err(Error::ContractPaused);
+    }
+    Ok(())
+}
+
+/// Fails if a memo is longer than the contract allows.
+fn check_memo(config: &ContractConfig, memo: &str) -> Result<(), Error> {
+    if config
+        .max_memo_len
+        .is_some_and(|max| memo.len() > max as usize)
+    {
+        return Err(Error::MemoTooLong);
+    }
+    Ok(())
+}
+
+/// Fails if an account would have more keys than the contract allows.
+fn check_key_count(config: &ContractConfig, count: usize) -> Result<(), Error> {
+    if config.max_keys.is_some_and(|max| count > max as usize) {
+        return Err(Error::TooManyKeys);
+    }
+    Ok(())
+}
+
+/// Fails if an account is on hold, unless the funds go to its remediation
+/// address.
+fn check_hold(
+    account: &ModelAccount,
+    receiver: Option<&PublicKey>,
+) -> Result<(), Error> {
+    let remediation = account.compliance.as_ref().map(|c| &c.remediation);
+    if account.on_hold && remediation != receiver {
+        return Err(Error::AccountOnHold);
+    }
+    Ok(())
+}
+
+/// Fails unless the receiver holds the license the account requires, if it
+/// requires any.
+fn check_licensed(
+    licenses: &BTreeSet<([u8; 32], Key)>,
+    account: &ModelAccount,
+    receiver: &PublicKey,
+) -> Result<(), Error> {
+    if let Some(policy) = account.license_policy {
+        if !licenses.contains(&(policy.contract, Key(*receiver))) {
+            return Err(Error::ReceiverNotLicensed);
+        }
+    }
+    Ok(())
+}
+
+/// Fails if an operation declares a chain other than its account's.
+fn check_chain(
+    account: &ModelAccount,
+    chain_id: Option<u8>,
+) -> Result<(), Error> {
+    match chain_id {
+        Some(chain_id) if chain_id != account.chain_id => {
+            Err(Error::WrongChain)
+        }
+        _ => Ok(()),
+    }
+}
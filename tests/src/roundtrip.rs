@@ -0,0 +1,1595 @@
+//! Round trips of every public type through its archived form.
+//!
+//! Each value is serialized, its archive checked and then deserialized back,
+//! and the archived bytes are compared against golden files. Deployed clients
+//! depend on this layout, so any change to it must be deliberate.
+
+use std::fmt::Debug;
+
+use bytecheck::CheckBytes;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rkyv::ser::serializers::AllocSerializer;
+use rkyv::validation::validators::DefaultValidator;
+use rkyv::{Archive, Deserialize, Infallible, Serialize};
+
+use bls::{MultisigSignature, PublicKey, SecretKey};
+use multisig_contract_types::*;
+
+use crate::{golden, DESCRIPTION, MEMO, RNG_SEED};
+
+const VELOCITY_LIMIT: VelocityLimit = VelocityLimit {
+    amount: 1_000,
+    window: 100,
+};
+
+const REFERENCE: [u8; 32] = [0x2A; 32];
+
+const LIVENESS_POLICY: LivenessPolicy = LivenessPolicy {
+    window: 17_280,
+    min_threshold: 2,
+};
+
+const SUPERMAJORITY: Supermajority = Supermajority {
+    numerator: 2,
+    denominator: 3,
+};
+
+const KEY_ADDITION_POLICY: KeyAdditionPolicy = KeyAdditionPolicy {
+    delay: 8_640,
+    supermajority: true,
+};
+
+const CATEGORY: &str = "marketing";
+
+const TRANSFER_WINDOW: TransferWindow = TransferWindow {
+    period: 8_640,
+    offset: 3_240,
+    length: 2_880,
+};
+
+const RECOVERY_POLICY: RecoveryPolicy = RecoveryPolicy {
+    delay: 17_280,
+    guardians: 2,
+};
+
+const QUEUE_POLICY: QueuePolicy = QueuePolicy {
+    min_amount: 10_000,
+    delay: 8_640,
+};
+
+const CHILD_BUDGET: ChildBudget = ChildBudget {
+    child_id: 2,
+    amount: 5_000,
+    window: 8_640,
+};
+
+fn spending_category() -> SpendingCategory {
+    SpendingCategory {
+        name: String::from(CATEGORY),
+        budget: 10_000,
+        window: 8_640,
+    }
+}
+
+fn inactivity_policy(fallback: PublicKey) -> InactivityPolicy {
+    InactivityPolicy {
+        fallback,
+        period: 25_920,
+    }
+}
+
+#[track_caller]
+fn roundtrip<T>(name: &str, value: &T)
+where
+    T: Archive + Serialize<AllocSerializer<1024>> + PartialEq + Debug,
+    T::Archived:
+        Deserialize<T, Infallible> + for<'b> CheckBytes<DefaultValidator<'b>>,
+{
+    let bytes =
+        rkyv::to_bytes::<_, 1024>(value).expect("Serializing should succeed");
+
+    let archived = rkyv::check_archived_root::<T>(&bytes)
+        .expect("The archived value should be valid");
+    let deserialized: T = archived
+        .deserialize(&mut Infallible)
+        .expect("Deserializing should succeed");
+
+    assert_eq!(&deserialized, value, "{name} should survive a round trip");
+    golden::check(&format!("types/{name}"), &bytes);
+}
+
+/// A fixed co-signer, with a signature of a message.
+fn cosignature() -> Cosignature {
+    use ed25519::Signer;
+
+    let sk = ed25519::SigningKey::from_bytes(&[7; 32]);
+    Cosignature {
+        key: CosignerKey::from(&sk),
+        signature: sk.sign(b"message").to_bytes().to_vec(),
+    }
+}
+
+/// A signature of a message by a fixed group key.
+fn group_signature() -> bls::Signature {
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    SecretKey::random(&mut rng).sign(b"message")
+}
+
+/// Returns a few fixed keys, together with a signature of a message.
+fn keys() -> ([PublicKey; 3], MultisigSignature) {
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+
+    let sks: Vec<SecretKey> =
+        (0..3).map(|_| SecretKey::random(&mut rng)).collect();
+    let pks = [0, 1, 2].map(|i| PublicKey::from(&sks[i]));
+
+    let signature = sks[0].sign_multisig(&pks[0], b"message");
+    (pks, signature)
+}
+
+#[test]
+fn roundtrip_types() {
+    let (pks, signature) = keys();
+    let cosignature = cosignature();
+
+    roundtrip(
+        "create_account",
+        &CreateAccount {
+            keys: pks.to_vec(),
+            threshold: 2,
+            description: String::from(DESCRIPTION),
+            alias: Some(String::from("dusk-grants")),
+            key_weights: Vec::new(),
+            key_roles: Vec::new(),
+        },
+    );
+
+    let template = AccountTemplate {
+        threshold: 2,
+        description: String::from(DESCRIPTION),
+        private_events: true,
+        velocity_limit: Some(VELOCITY_LIMIT),
+        cosigners: vec![cosignature.key],
+        guardians: vec![pks[2]],
+    };
+    roundtrip("account_template", &template);
+    roundtrip(
+        "create_account_from_template",
+        &CreateAccountFromTemplate {
+            template_id: 1,
+            keys: pks[..2].to_vec(),
+            alias: Some(String::from("dusk-grants")),
+        },
+    );
+
+    let genesis_account = GenesisAccount {
+        keys: pks.to_vec(),
+        threshold: 2,
+        description: String::from(DESCRIPTION),
+        balance: 1_000,
+    };
+    roundtrip("genesis_account", &genesis_account);
+    roundtrip(
+        "genesis",
+        &Genesis {
+            accounts: vec![genesis_account],
+            custody_cap: Some(1_000_000),
+            min_deposit: Some(1_000),
+            governance: Some(1),
+        },
+    );
+
+    roundtrip(
+        "deposit",
+        &Deposit {
+            account_id: 1,
+            amount: 1_000,
+            memo: String::from(MEMO),
+            depositor_reference: String::from("invoice-1"),
+        },
+    );
+
+    let transfer = Transfer {
+        account_id: 1,
+        keys: pks[..2].to_vec(),
+        signature,
+        cosignatures: vec![cosignature.clone()],
+        group_signature: None,
+        receiver: pks[2],
+        amount: 500,
+        nonce: 1,
+        memo: String::from(MEMO),
+        reference: Some(REFERENCE),
+        category: Some(String::from(CATEGORY)),
+        gas_refund: None,
+        chain_id: None,
+    };
+    roundtrip("transfer", &transfer);
+
+    let changes = vec![
+        AccountChange::AddKey { key: pks[2] },
+        AccountChange::RemoveKey { key: pks[0] },
+        AccountChange::SetThreshold { threshold: 1 },
+        AccountChange::SetDescription {
+            description: String::from(DESCRIPTION),
+        },
+        AccountChange::SetPrivateEvents {
+            private_events: true,
+        },
+        AccountChange::AddDuressKey { key: pks[0] },
+        AccountChange::RemoveDuressKey { key: pks[1] },
+        AccountChange::AddCosigner {
+            key: cosignature.key,
+        },
+        AccountChange::RemoveCosigner {
+            key: cosignature.key,
+        },
+        AccountChange::SetGroupKey { key: Some(pks[2]) },
+        AccountChange::SetGroupKey { key: None },
+        AccountChange::AddWhitelisted { receiver: pks[2] },
+        AccountChange::RemoveWhitelisted { receiver: pks[2] },
+        AccountChange::SetVelocityLimit {
+            limit: Some(VELOCITY_LIMIT),
+        },
+        AccountChange::SetVelocityLimit { limit: None },
+        AccountChange::Unlock,
+        AccountChange::AddGuardian { key: pks[2] },
+        AccountChange::RemoveGuardian { key: pks[2] },
+        AccountChange::SetAlias {
+            alias: Some(String::from("dusk-grants")),
+        },
+        AccountChange::SetAlias { alias: None },
+        AccountChange::SetBalanceCap { cap: Some(1_000) },
+        AccountChange::SetBalanceCap { cap: None },
+        AccountChange::AddTag {
+            tag: String::from("grants"),
+        },
+        AccountChange::RemoveTag {
+            tag: String::from("grants"),
+        },
+        AccountChange::SetLivenessPolicy {
+            policy: Some(LIVENESS_POLICY),
+        },
+        AccountChange::SetLivenessPolicy { policy: None },
+        AccountChange::SetSupermajority {
+            supermajority: Some(SUPERMAJORITY),
+        },
+        AccountChange::SetSupermajority {
+            supermajority: None,
+        },
+        AccountChange::SetKeyAdditionPolicy {
+            policy: Some(KEY_ADDITION_POLICY),
+        },
+        AccountChange::SetKeyAdditionPolicy { policy: None },
+        AccountChange::SetTransferWindow {
+            window: Some(TRANSFER_WINDOW),
+        },
+        AccountChange::SetTransferWindow { window: None },
+        AccountChange::SetCategory {
+            category: spending_category(),
+        },
+        AccountChange::RemoveCategory {
+            name: String::from(CATEGORY),
+        },
+        AccountChange::AddObserver { key: pks[2] },
+        AccountChange::RemoveObserver { key: pks[2] },
+        AccountChange::SetChildBudget {
+            budget: CHILD_BUDGET,
+        },
+        AccountChange::RemoveChildBudget { child_id: 2 },
+        AccountChange::ClawBackBudget { child_id: 2 },
+        AccountChange::SetKeyExpiry {
+            key: pks[1],
+            valid_until: Some(8_640),
+        },
+        AccountChange::SetKeyExpiry {
+            key: pks[1],
+            valid_until: None,
+        },
+        AccountChange::Archive,
+        AccountChange::Reactivate,
+        AccountChange::SetClaimableWithdrawals {
+            claimable_withdrawals: true,
+        },
+        AccountChange::SetCompliance {
+            compliance: Some(ComplianceControl {
+                key: pks[2],
+                remediation: pks[1],
+            }),
+        },
+        AccountChange::LiftHold { signature },
+        AccountChange::SetLicensePolicy {
+            policy: Some(LicensePolicy {
+                contract: [3; 32],
+                signers: true,
+            }),
+        },
+        AccountChange::SetOperationThreshold {
+            op: OperationKind::Transfer,
+            threshold: Some(2),
+        },
+        AccountChange::SetOperationThreshold {
+            op: OperationKind::Configure,
+            threshold: None,
+        },
+        AccountChange::SetTransferTiers {
+            tiers: vec![TransferTier {
+                ceiling: 500,
+                threshold: 1,
+            }],
+        },
+        AccountChange::SetKeyWeight {
+            key: pks[0],
+            weight: 2,
+        },
+        AccountChange::SetKeyRole {
+            key: pks[1],
+            role: KeyRole::Spender,
+        },
+        AccountChange::SetRecoveryPolicy {
+            policy: Some(RECOVERY_POLICY),
+        },
+        AccountChange::SetRecoveryPolicy { policy: None },
+        AccountChange::SetInactivityPolicy {
+            policy: Some(inactivity_policy(pks[2])),
+        },
+        AccountChange::SetInactivityPolicy { policy: None },
+        AccountChange::SetQueuePolicy {
+            policy: Some(QUEUE_POLICY),
+        },
+        AccountChange::SetQueuePolicy { policy: None },
+        AccountChange::SetAllowance {
+            key: pks[1],
+            amount: 500,
+        },
+    ];
+    for (i, change) in changes.iter().enumerate() {
+        roundtrip(&format!("account_change_{i}"), change);
+    }
+
+    let change_account = ChangeAccount {
+        account_id: 1,
+        keys: pks[..2].to_vec(),
+        signature,
+        cosignatures: Vec::new(),
+        group_signature: Some(group_signature()),
+        changes,
+        nonce: 2,
+        gas_refund: None,
+        chain_id: None,
+    };
+    roundtrip("change_account", &change_account);
+
+    let split_account = SplitAccount {
+        account_id: 1,
+        keys: pks[..2].to_vec(),
+        signature,
+        cosignatures: vec![cosignature.clone()],
+        group_signature: None,
+        new_keys: pks[1..].to_vec(),
+        threshold: 1,
+        description: String::from("petty cash"),
+        amount: 500,
+        nonce: 4,
+        gas_refund: None,
+        chain_id: None,
+    };
+    roundtrip("split_account", &split_account);
+
+    let payroll = Payroll {
+        account_id: 1,
+        keys: pks[..2].to_vec(),
+        signature,
+        cosignatures: Vec::new(),
+        group_signature: Some(group_signature()),
+        rows: vec![
+            PayrollRow {
+                receiver: pks[2],
+                amount: 300,
+                memo: String::from("october"),
+            },
+            PayrollRow {
+                receiver: pks[1],
+                amount: 200,
+                memo: String::new(),
+            },
+        ],
+        nonce: 5,
+        gas_refund: Some(GasRefund {
+            submitter: pks[0],
+            amount: 10,
+        }),
+        chain_id: Some(0xFE),
+    };
+    roundtrip("payroll", &payroll);
+
+    let transfer_and_change = TransferAndChange {
+        account_id: 1,
+        keys: pks[..2].to_vec(),
+        signature,
+        cosignatures: vec![cosignature.clone()],
+        group_signature: None,
+        receiver: pks[2],
+        amount: 500,
+        memo: String::from(MEMO),
+        changes: vec![
+            AccountChange::RemoveKey { key: pks[2] },
+            AccountChange::SetThreshold { threshold: 1 },
+        ],
+        nonce: 6,
+        gas_refund: None,
+        chain_id: None,
+    };
+    roundtrip("transfer_and_change", &transfer_and_change);
+
+    roundtrip("operation_transfer", &Operation::Transfer(transfer));
+    roundtrip(
+        "operation_change_account",
+        &Operation::ChangeAccount(change_account),
+    );
+    roundtrip(
+        "operation_split_account",
+        &Operation::SplitAccount(split_account),
+    );
+    roundtrip("operation_payroll", &Operation::Payroll(payroll));
+    roundtrip(
+        "operation_transfer_and_change",
+        &Operation::TransferAndChange(transfer_and_change.clone()),
+    );
+    roundtrip(
+        "certified_operation",
+        &CertifiedOperation {
+            operation: Operation::TransferAndChange(transfer_and_change),
+            signers: vec![0b1011, 0b1],
+        },
+    );
+
+    roundtrip(
+        "lock_account",
+        &LockAccount {
+            account_id: 1,
+            key: pks[0],
+            signature,
+        },
+    );
+    roundtrip(
+        "place_hold",
+        &PlaceHold {
+            account_id: 1,
+            signature,
+        },
+    );
+    roundtrip(
+        "attest_liveness",
+        &AttestLiveness {
+            account_id: 1,
+            key: pks[0],
+            block_height: 8_640,
+            signature,
+        },
+    );
+    let delegation = Delegation {
+        delegate: pks[2],
+        from_height: 8_640,
+        until_height: 17_280,
+    };
+    roundtrip(
+        "delegate_key",
+        &DelegateKey {
+            account_id: 1,
+            key: pks[0],
+            delegation: Some(delegation.clone()),
+            nonce: 2,
+            signature,
+        },
+    );
+    roundtrip(
+        "guard_account",
+        &GuardAccount {
+            account_id: 1,
+            key: pks[0],
+            action: GuardianAction::ExtendWhitelistDelay {
+                receiver: pks[1],
+                usable_from: 17_280,
+            },
+            signature,
+        },
+    );
+    roundtrip(
+        "start_recovery",
+        &StartRecovery {
+            account_id: 1,
+            guardians: pks[1..].to_vec(),
+            signature,
+            keys: pks[..2].to_vec(),
+            threshold: 2,
+            nonce: 4,
+        },
+    );
+    roundtrip(
+        "cancel_recovery",
+        &CancelRecovery {
+            account_id: 1,
+            key: pks[0],
+            signature,
+        },
+    );
+    roundtrip("finalize_recovery", &FinalizeRecovery { account_id: 1 });
+    roundtrip("inactivity_policy", &inactivity_policy(pks[2]));
+    roundtrip(
+        "claim_inactive_sweep",
+        &ClaimInactive {
+            account_id: 1,
+            claim: InactiveClaim::Sweep { receiver: pks[1] },
+            signature,
+            nonce: 4,
+        },
+    );
+    roundtrip(
+        "claim_inactive_rotate_keys",
+        &ClaimInactive {
+            account_id: 1,
+            claim: InactiveClaim::RotateKeys {
+                keys: pks[..2].to_vec(),
+                threshold: 2,
+            },
+            signature,
+            nonce: 4,
+        },
+    );
+    roundtrip("queue_policy", &QUEUE_POLICY);
+    roundtrip(
+        "queued_transfer",
+        &QueuedTransfer {
+            nonce: 4,
+            keys: pks[..2].to_vec(),
+            cosigners: vec![cosignature.key],
+            group_signed: false,
+            receiver: pks[2],
+            amount: 10_000,
+            memo: String::from(MEMO),
+            reference: Some([3; 32]),
+            category: Some(String::from(CATEGORY)),
+            executable_from: 17_280,
+        },
+    );
+    roundtrip(
+        "cancel_queued",
+        &CancelQueued {
+            account_id: 1,
+            nonce: 4,
+            key: pks[0],
+            signature,
+        },
+    );
+    roundtrip(
+        "execute_queued",
+        &ExecuteQueued {
+            account_id: 1,
+            nonce: 4,
+        },
+    );
+    roundtrip(
+        "key_allowance",
+        &KeyAllowance {
+            key: pks[1],
+            amount: 500,
+        },
+    );
+    roundtrip(
+        "allowance_transfer",
+        &AllowanceTransfer {
+            account_id: 1,
+            key: pks[1],
+            signature,
+            receiver: pks[2],
+            amount: 100,
+            nonce: 4,
+            memo: String::from(MEMO),
+            gas_refund: Some(GasRefund {
+                submitter: pks[0],
+                amount: 10,
+            }),
+            chain_id: Some(0xFE),
+        },
+    );
+    roundtrip(
+        "merge_accounts",
+        &MergeAccounts {
+            account_id: 1,
+            keys: pks[..1].to_vec(),
+            signature,
+            cosignatures: vec![cosignature.clone()],
+            group_signature: None,
+            nonce: 3,
+            merged_id: 2,
+            merged_keys: pks[1..].to_vec(),
+            merged_signature: signature,
+            merged_cosignatures: Vec::new(),
+            merged_group_signature: Some(group_signature()),
+            merged_nonce: 5,
+        },
+    );
+    roundtrip(
+        "draw_budget",
+        &DrawBudget {
+            account_id: 2,
+            parent_id: 1,
+            keys: pks[..2].to_vec(),
+            signature,
+            cosignatures: Vec::new(),
+            group_signature: None,
+            amount: 1_000,
+            nonce: 2,
+        },
+    );
+    roundtrip(
+        "record_checkpoint",
+        &RecordCheckpoint {
+            account_id: 1,
+            keys: pks[..2].to_vec(),
+            signature,
+            cosignatures: vec![cosignature.clone()],
+            group_signature: None,
+            nonce: 6,
+        },
+    );
+    roundtrip(
+        "claim_withdrawal",
+        &ClaimWithdrawal {
+            claim_id: 1,
+            receiver: pks[2],
+            signature,
+        },
+    );
+    roundtrip(
+        "mark_compromised",
+        &MarkCompromised {
+            key: pks[0],
+            signature,
+        },
+    );
+    roundtrip(
+        "mark_cosigner_compromised",
+        &MarkCosignerCompromised {
+            key: cosignature.key,
+            signature: cosignature.signature.clone(),
+        },
+    );
+    roundtrip(
+        "replace_key",
+        &ReplaceKey {
+            old_key: pks[0],
+            new_key: pks[2],
+            signature,
+            changes: vec![ChangeAccount {
+                account_id: 1,
+                keys: pks[1..].to_vec(),
+                signature,
+                cosignatures: Vec::new(),
+                group_signature: None,
+                changes: ReplaceKey::account_changes(pks[0], pks[2]),
+                nonce: 3,
+                gas_refund: None,
+                chain_id: None,
+            }],
+        },
+    );
+
+    roundtrip("cosigner_key", &cosignature.key);
+    roundtrip("cosignature", &cosignature);
+
+    roundtrip(
+        "partial_signature",
+        &PartialSignature {
+            key: pks[0],
+            signature,
+        },
+    );
+
+    let account_data = AccountData {
+        balance: 1_000,
+        threshold: 2,
+        description: String::from(DESCRIPTION),
+        nonce: 3,
+        private_events: true,
+        frozen: false,
+        group_key: Some(pks[2]),
+        whitelist_enforced: true,
+        velocity_limit: Some(VELOCITY_LIMIT),
+        locked: true,
+        alias: Some(String::from("dusk-grants")),
+        merged_into: Some(4),
+        chain_id: 0xFE,
+        balance_cap: Some(10_000),
+        tags: vec![String::from("grants"), String::from("eu")],
+        liveness_policy: Some(LIVENESS_POLICY),
+        supermajority: Some(SUPERMAJORITY),
+        key_addition_policy: Some(KEY_ADDITION_POLICY),
+        transfer_window: Some(TRANSFER_WINDOW),
+        categories: vec![spending_category()],
+        child_budgets: vec![CHILD_BUDGET],
+        archived: false,
+        claimable_withdrawals: true,
+        history_retention: Some(1_024),
+        compliance: Some(ComplianceControl {
+            key: pks[2],
+            remediation: pks[1],
+        }),
+        on_hold: true,
+        license_policy: Some(LicensePolicy {
+            contract: [3; 32],
+            signers: false,
+        }),
+        operation_thresholds: vec![OperationThreshold {
+            op: OperationKind::ChangeAccount,
+            threshold: 2,
+        }],
+        recovery_policy: Some(RECOVERY_POLICY),
+        recovery_nonce: 2,
+        inactivity_policy: Some(inactivity_policy(pks[2])),
+        last_active: 17_280,
+        queue_policy: Some(QUEUE_POLICY),
+    };
+    roundtrip("account_data", &account_data);
+    roundtrip(
+        "budget_draw",
+        &BudgetDraw {
+            child_id: 2,
+            drawn: 1_000,
+            held: 800,
+        },
+    );
+    roundtrip(
+        "category_spend",
+        &CategorySpend {
+            name: String::from(CATEGORY),
+            spent: 2_500,
+        },
+    );
+    roundtrip(
+        "custody_report",
+        &CustodyReport {
+            total_balance: 1_000,
+            pending_withdrawals: 200,
+            storage_fees: 100,
+            held: 900,
+            delta: -400,
+        },
+    );
+    let withdrawal = PendingWithdrawal {
+        claim_id: 1,
+        account_id: 1,
+        receiver: pks[2],
+        amount: 200,
+        block_height: 8,
+    };
+    roundtrip("pending_withdrawal", &withdrawal);
+
+    let migrated_account = MigratedAccount {
+        account_id: 1,
+        keys: pks[..2].to_vec(),
+        duress_keys: vec![pks[2]],
+        cosigners: vec![cosignature.key],
+        whitelist: vec![WhitelistEntry {
+            receiver: pks[2],
+            usable_from: 8_640,
+        }],
+        guardians: vec![pks[1]],
+        observers: vec![pks[2]],
+        budget_draws: vec![BudgetDraw {
+            child_id: 2,
+            drawn: 0,
+            held: 800,
+        }],
+        liveness: vec![KeyLiveness {
+            key: pks[0],
+            attested_at: 8_640,
+        }],
+        delegations: vec![KeyDelegation {
+            key: pks[0],
+            delegation: Some(delegation.clone()),
+            nonce: 2,
+        }],
+        pending_keys: vec![PendingKey {
+            key: pks[1],
+            usable_from: 8_640,
+        }],
+        key_expiries: vec![KeyExpiry {
+            key: pks[0],
+            valid_until: 17_280,
+        }],
+        key_weights: vec![KeyWeight {
+            key: pks[1],
+            weight: 3,
+        }],
+        key_roles: vec![RoleAssignment {
+            key: pks[0],
+            role: KeyRole::Spender,
+        }],
+        pending_recovery: Some(PendingRecovery {
+            guardians: vec![pks[1]],
+            keys: pks[..2].to_vec(),
+            threshold: 1,
+            executable_from: 17_280,
+        }),
+        queued_transfers: vec![QueuedTransfer {
+            nonce: 4,
+            keys: pks[..1].to_vec(),
+            cosigners: Vec::new(),
+            group_signed: false,
+            receiver: pks[2],
+            amount: 10_000,
+            memo: String::from(MEMO),
+            reference: None,
+            category: None,
+            executable_from: 17_280,
+        }],
+        allowances: vec![KeyAllowance {
+            key: pks[1],
+            amount: 500,
+        }],
+        transfer_tiers: vec![TransferTier {
+            ceiling: 1_000,
+            threshold: 1,
+        }],
+        key_usage: vec![
+            KeyUsage {
+                key: pks[0],
+                operations: 3,
+                last_signed: Some(10),
+            },
+            KeyUsage {
+                key: pks[1],
+                operations: 0,
+                last_signed: None,
+            },
+        ],
+        checkpoints: vec![Checkpoint {
+            balance: 1_000,
+            nonce: 6,
+            keys_hash: hash_keys(&pks),
+            block_height: 10,
+            sequence: 4,
+        }],
+        movements: vec![Movement {
+            block_height: 10,
+            sequence: 2,
+            direction: Direction::Out,
+            counterparty: Counterparty::Moonlight(Disclosure::Revealed(pks[2])),
+            amount: 1_000,
+            memo_hash: Some(hash_memo(2, MEMO)),
+            balance_after: 0,
+        }],
+        operations: vec![OperationRecord {
+            nonce: 6,
+            block_height: 10,
+            balance: 0,
+            threshold: 2,
+            added_keys: vec![pks[2]],
+            removed_keys: Vec::new(),
+        }],
+        data: account_data,
+    };
+    roundtrip(
+        "movement",
+        &Movement {
+            block_height: 10,
+            sequence: 3,
+            direction: Direction::In,
+            counterparty: Counterparty::Account(2),
+            amount: 1_000,
+            memo_hash: None,
+            balance_after: 1_000,
+        },
+    );
+    roundtrip(
+        "storage_fee_movement",
+        &Movement {
+            block_height: 12,
+            sequence: 5,
+            direction: Direction::Out,
+            counterparty: Counterparty::StorageFee,
+            amount: 100,
+            memo_hash: None,
+            balance_after: 900,
+        },
+    );
+    roundtrip(
+        "movement_query",
+        &MovementQuery {
+            account_id: 1,
+            from_height: 10,
+            to_height: 20,
+        },
+    );
+    roundtrip(
+        "large_transfer_query",
+        &LargeTransferQuery {
+            min_amount: 10_000,
+            from_height: 10,
+            to_height: 20,
+        },
+    );
+    roundtrip(
+        "large_transfer",
+        &LargeTransfer {
+            account_id: 1,
+            movement: Movement {
+                block_height: 14,
+                sequence: 4,
+                direction: Direction::Out,
+                counterparty: Counterparty::Moonlight(Disclosure::Revealed(
+                    pks[1],
+                )),
+                amount: 10_000,
+                memo_hash: Some([3; 32]),
+                balance_after: 500,
+            },
+        },
+    );
+    roundtrip(
+        "state_diff_query",
+        &StateDiffQuery {
+            account_id: 1,
+            from_nonce: 2,
+            to_nonce: 6,
+        },
+    );
+    roundtrip(
+        "state_diff",
+        &StateDiff {
+            balance_before: 1_000,
+            balance_after: 0,
+            threshold_before: 2,
+            threshold_after: 3,
+            added_keys: vec![pks[2]],
+            removed_keys: vec![pks[0]],
+        },
+    );
+    roundtrip(
+        "change_simulation",
+        &ChangeSimulation {
+            keys: vec![pks[0], pks[1]],
+            threshold: 2,
+            violations: vec![Error::ThresholdNotMet, Error::KeyAlreadyUsed],
+        },
+    );
+    roundtrip(
+        "usage_stats",
+        &UsageStats {
+            calls: vec![
+                EntrypointCalls {
+                    entrypoint: String::from("deposit"),
+                    calls: 12,
+                },
+                EntrypointCalls {
+                    entrypoint: String::from("transfer"),
+                    calls: 5,
+                },
+            ],
+            keys_verified: 40,
+            cosignatures_verified: 3,
+        },
+    );
+    roundtrip("migrated_account", &migrated_account);
+    roundtrip(
+        "migration",
+        &Migration {
+            accounts: vec![migrated_account],
+            event_sequence: 42,
+            compromised_keys: vec![pks[1]],
+            compromised_cosigners: vec![cosignature.key],
+            custody_cap: Some(1_000_000),
+            min_deposit: Some(1_000),
+            templates: vec![template],
+            withdrawals: vec![withdrawal],
+            withdrawal_count: 2,
+            storage_fees: 100,
+            governance: Some(1),
+            config: ContractConfig {
+                max_keys: Some(16),
+                max_memo_len: Some(256),
+                paused: false,
+                history_record_fee: HISTORY_RECORD_FEE,
+                max_gas_refund: MAX_GAS_REFUND,
+            },
+        },
+    );
+}
+
+#[test]
+fn roundtrip_events() {
+    let (pks, _) = keys();
+    let cosigner = cosignature().key;
+
+    roundtrip(
+        "create_account_event",
+        &CreateAccountEvent {
+            account_id: 1,
+            keys: pks.to_vec(),
+            threshold: 2,
+            description: String::from(DESCRIPTION),
+            alias: Some(String::from("dusk-grants")),
+            key_weights: vec![KeyWeight {
+                key: pks[0],
+                weight: 2,
+            }],
+            key_roles: vec![RoleAssignment {
+                key: pks[1],
+                role: KeyRole::Spender,
+            }],
+            template_id: Some(1),
+            sequence: 1,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "register_template_event",
+        &RegisterTemplateEvent {
+            template_id: 1,
+            template: AccountTemplate {
+                threshold: 2,
+                description: String::from(DESCRIPTION),
+                private_events: false,
+                velocity_limit: None,
+                cosigners: vec![cosigner],
+                guardians: vec![pks[1]],
+            },
+            sequence: 1,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "deposit_event",
+        &DepositEvent {
+            account_id: 1,
+            amount: 1_000,
+            memo: Disclosure::Revealed(String::from(MEMO)),
+            depositor_reference: Disclosure::Revealed(String::from(
+                "invoice-1",
+            )),
+            nonce: 0,
+            block_height: 1,
+            balance_after: 1_000,
+            sequence: 2,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "transfer_event",
+        &TransferEvent {
+            account_id: 1,
+            keys: pks[..2].to_vec(),
+            cosigners: vec![cosigner],
+            group_signed: false,
+            receiver: Disclosure::Revealed(pks[2]),
+            amount: 500,
+            memo: Disclosure::Revealed(String::from(MEMO)),
+            reference: Some(Disclosure::Revealed(REFERENCE)),
+            category: Some(String::from(CATEGORY)),
+            claim_id: None,
+            nonce: 1,
+            block_height: 2,
+            balance_after: 500,
+            sequence: 3,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "private_transfer_event",
+        &TransferEvent {
+            account_id: 1,
+            keys: pks[..2].to_vec(),
+            cosigners: vec![cosigner],
+            group_signed: false,
+            receiver: Disclosure::Hashed(hash_receiver(3, &pks[2])),
+            amount: 500,
+            memo: Disclosure::Hashed(hash_memo(3, MEMO)),
+            reference: Some(Disclosure::Hashed(hash_reference(3, &REFERENCE))),
+            category: Some(String::from(CATEGORY)),
+            claim_id: None,
+            nonce: 1,
+            block_height: 2,
+            balance_after: 500,
+            sequence: 3,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "payroll_event",
+        &PayrollEvent {
+            account_id: 1,
+            keys: pks[..2].to_vec(),
+            cosigners: Vec::new(),
+            group_signed: false,
+            row: 1,
+            rows: 2,
+            receiver: Disclosure::Revealed(pks[2]),
+            amount: 200,
+            memo: Disclosure::Hashed(hash_memo(4, MEMO)),
+            nonce: 5,
+            block_height: 2,
+            balance_after: 300,
+            sequence: 4,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "gas_refund_event",
+        &GasRefundEvent {
+            account_id: 1,
+            submitter: Disclosure::Revealed(pks[0]),
+            amount: 10,
+            nonce: 5,
+            block_height: 2,
+            balance_after: 290,
+            sequence: 5,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "change_account_event",
+        &ChangeAccountEvent {
+            account_id: 1,
+            keys: pks[..2].to_vec(),
+            cosigners: vec![cosigner],
+            group_signed: false,
+            added_keys: vec![pks[2]],
+            removed_keys: vec![pks[0]],
+            added_cosigners: vec![cosigner],
+            removed_cosigners: Vec::new(),
+            previous_threshold: 2,
+            threshold: Some(1),
+            description: None,
+            private_events: Some(true),
+            group_key: Some(Some(pks[2])),
+            whitelisted: vec![WhitelistEntry {
+                receiver: pks[1],
+                usable_from: 8_643,
+            }],
+            unwhitelisted: vec![pks[0]],
+            whitelist_lifted: true,
+            velocity_limit: Some(None),
+            unlocked: true,
+            archived: Some(true),
+            claimable_withdrawals: Some(true),
+            history_retention: Some(Some(1_024)),
+            storage_fee: 76_800_000,
+            compliance: Some(None),
+            hold_lifted: true,
+            license_policy: Some(None),
+            set_operation_thresholds: vec![OperationThreshold {
+                op: OperationKind::Transfer,
+                threshold: 1,
+            }],
+            removed_operation_thresholds: vec![OperationKind::Payroll],
+            transfer_tiers: Some(vec![
+                TransferTier {
+                    ceiling: 100,
+                    threshold: 1,
+                },
+                TransferTier {
+                    ceiling: 10_000,
+                    threshold: 2,
+                },
+            ]),
+            added_guardians: vec![pks[1]],
+            removed_guardians: Vec::new(),
+            added_observers: vec![pks[2]],
+            removed_observers: Vec::new(),
+            alias: Some(None),
+            balance_cap: Some(Some(1_000)),
+            added_tags: vec![String::from("grants")],
+            removed_tags: vec![String::from("eu")],
+            liveness_policy: Some(Some(LIVENESS_POLICY)),
+            supermajority: Some(Some(SUPERMAJORITY)),
+            key_addition_policy: Some(Some(KEY_ADDITION_POLICY)),
+            transfer_window: Some(Some(TRANSFER_WINDOW)),
+            recovery_policy: Some(Some(RECOVERY_POLICY)),
+            inactivity_policy: Some(Some(inactivity_policy(pks[2]))),
+            queue_policy: Some(Some(QUEUE_POLICY)),
+            set_allowances: vec![KeyAllowance {
+                key: pks[1],
+                amount: 500,
+            }],
+            set_categories: vec![spending_category()],
+            removed_categories: vec![String::from("travel")],
+            set_child_budgets: vec![CHILD_BUDGET],
+            removed_child_budgets: vec![3],
+            set_key_expiries: vec![KeyExpiry {
+                key: pks[0],
+                valid_until: 17_280,
+            }],
+            removed_key_expiries: vec![pks[1]],
+            set_key_weights: vec![KeyWeight {
+                key: pks[1],
+                weight: 2,
+            }],
+            set_key_roles: vec![RoleAssignment {
+                key: pks[0],
+                role: KeyRole::Spender,
+            }],
+            with_transfer: false,
+            nonce: 2,
+            block_height: 3,
+            sequence: 4,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "account_locked_event",
+        &AccountLockedEvent {
+            account_id: 1,
+            key: pks[0],
+            nonce: 2,
+            block_height: 4,
+            sequence: 5,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "hold_placed_event",
+        &HoldPlacedEvent {
+            account_id: 1,
+            key: pks[2],
+            remediation: pks[1],
+            nonce: 2,
+            block_height: 4,
+            sequence: 5,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "liveness_attested_event",
+        &LivenessAttestedEvent {
+            account_id: 1,
+            key: pks[0],
+            attested_at: 8_640,
+            block_height: 8_650,
+            sequence: 5,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "key_delegated_event",
+        &KeyDelegatedEvent {
+            account_id: 1,
+            key: pks[0],
+            delegation: Some(Delegation {
+                delegate: pks[2],
+                from_height: 8_640,
+                until_height: 17_280,
+            }),
+            nonce: 2,
+            block_height: 8_650,
+            sequence: 5,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "guardian_action_event",
+        &GuardianActionEvent {
+            account_id: 1,
+            key: pks[1],
+            action: GuardianAction::VetoWhitelisted { receiver: pks[2] },
+            nonce: 2,
+            block_height: 4,
+            sequence: 5,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "merge_accounts_event",
+        &MergeAccountsEvent {
+            account_id: 1,
+            merged_id: 2,
+            amount: 500,
+            added_keys: vec![pks[2]],
+            added_cosigners: vec![cosigner],
+            balance_after: 1_500,
+            nonce: 3,
+            merged_nonce: 5,
+            block_height: 4,
+            sequence: 6,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "budget_drawn_event",
+        &BudgetDrawnEvent {
+            account_id: 2,
+            parent_id: 1,
+            amount: 1_000,
+            balance_after: 1_000,
+            parent_balance_after: 4_000,
+            nonce: 2,
+            block_height: 4,
+            sequence: 8,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "budget_clawed_back_event",
+        &BudgetClawedBackEvent {
+            account_id: 1,
+            child_id: 2,
+            amount: 800,
+            balance_after: 4_800,
+            child_balance_after: 0,
+            nonce: 5,
+            block_height: 5,
+            sequence: 9,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "checkpoint_event",
+        &CheckpointEvent {
+            account_id: 1,
+            checkpoint: Checkpoint {
+                balance: 4_800,
+                nonce: 6,
+                keys_hash: hash_keys(&pks),
+                block_height: 6,
+                sequence: 10,
+            },
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "configure_event",
+        &ConfigureEvent {
+            account_id: 1,
+            keys: pks.to_vec(),
+            cosigners: vec![cosigner],
+            group_signed: false,
+            config: ContractConfig {
+                paused: true,
+                ..ContractConfig::DEFAULT
+            },
+            nonce: 7,
+            block_height: 6,
+            sequence: 11,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "withdrawal_claimed_event",
+        &WithdrawalClaimedEvent {
+            claim_id: 1,
+            account_id: 1,
+            receiver: Disclosure::Revealed(pks[2]),
+            amount: 200,
+            block_height: 9,
+            sequence: 11,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "payout_deferred_event",
+        &PayoutDeferredEvent {
+            claim_id: 2,
+            account_id: 1,
+            receiver: Disclosure::Hashed([7; 32]),
+            amount: 300,
+            block_height: 9,
+            sequence: 12,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "split_account_event",
+        &SplitAccountEvent {
+            account_id: 1,
+            new_account_id: 3,
+            amount: 500,
+            balance_after: 1_000,
+            nonce: 4,
+            block_height: 4,
+            sequence: 7,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "velocity_warning_event",
+        &VelocityWarningEvent {
+            account_id: 1,
+            limit: VELOCITY_LIMIT,
+            window_start: 300,
+            outflow: 1_500,
+            block_height: 342,
+            sequence: 5,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "key_compromised_event",
+        &KeyCompromisedEvent {
+            key: pks[0],
+            account_ids: vec![1, 2],
+            block_height: 4,
+            sequence: 5,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "cosigner_compromised_event",
+        &CosignerCompromisedEvent {
+            key: cosignature().key,
+            account_ids: vec![1, 2],
+            block_height: 4,
+            sequence: 5,
+            event_id: [7; 32],
+        },
+    );
+    let recovery = PendingRecovery {
+        guardians: pks[1..].to_vec(),
+        keys: pks[..2].to_vec(),
+        threshold: 2,
+        executable_from: 17_281,
+    };
+    roundtrip(
+        "recovery_started_event",
+        &RecoveryStartedEvent {
+            account_id: 1,
+            recovery,
+            nonce: 4,
+            block_height: 1,
+            sequence: 6,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "recovery_cancelled_event",
+        &RecoveryCancelledEvent {
+            account_id: 1,
+            key: pks[0],
+            nonce: 4,
+            block_height: 2,
+            sequence: 7,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "recovery_finalized_event",
+        &RecoveryFinalizedEvent {
+            account_id: 1,
+            removed_keys: vec![pks[2]],
+            keys: pks[..2].to_vec(),
+            threshold: 2,
+            nonce: 5,
+            block_height: 17_281,
+            sequence: 8,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "inactive_claimed_event",
+        &InactiveClaimedEvent {
+            account_id: 1,
+            key: pks[2],
+            claim: InactiveClaim::RotateKeys {
+                keys: pks[..2].to_vec(),
+                threshold: 2,
+            },
+            last_active: 17_280,
+            removed_keys: vec![pks[2]],
+            nonce: 6,
+            block_height: 43_200,
+            sequence: 9,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "transfer_queued_event",
+        &TransferQueuedEvent {
+            account_id: 1,
+            keys: pks[..2].to_vec(),
+            cosigners: vec![cosignature().key],
+            group_signed: false,
+            amount: 10_000,
+            executable_from: 25_920,
+            nonce: 7,
+            block_height: 17_280,
+            sequence: 10,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "queued_cancelled_event",
+        &QueuedCancelledEvent {
+            account_id: 1,
+            key: pks[1],
+            nonce: 7,
+            block_height: 17_281,
+            sequence: 11,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "queued_executed_event",
+        &QueuedExecutedEvent {
+            account_id: 1,
+            nonce: 7,
+            block_height: 25_920,
+            sequence: 12,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "queued_skipped_event",
+        &QueuedSkippedEvent {
+            account_id: 1,
+            nonce: 7,
+            amount: 1_000,
+            balance: 400,
+            block_height: 25_920,
+            sequence: 12,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "queued_expired_event",
+        &QueuedExpiredEvent {
+            account_id: 1,
+            nonce: 7,
+            block_height: 86_401,
+            sequence: 13,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "allowance_spent_event",
+        &AllowanceSpentEvent {
+            account_id: 1,
+            key: pks[1],
+            amount: 100,
+            remaining: 400,
+            nonce: 8,
+            block_height: 25_921,
+            sequence: 13,
+            event_id: [7; 32],
+        },
+    );
+    roundtrip(
+        "event_envelope",
+        &EventEnvelope::new(DepositEvent {
+            account_id: 1,
+            amount: 1_000,
+            memo: Disclosure::Revealed(String::from(MEMO)),
+            depositor_reference: Disclosure::Revealed(String::from(
+                "invoice-1",
+            )),
+            nonce: 0,
+            block_height: 1,
+            balance_after: 1_000,
+            sequence: 2,
+            event_id: [7; 32],
+        }),
+    );
+
+    // The version should be readable from any event's payload, without
+    // decoding it
+    let envelope = EventEnvelope::new(TransferEvent {
+        account_id: 1,
+        keys: pks.to_vec(),
+        cosigners: Vec::new(),
+        group_signed: true,
+        receiver: Disclosure::Revealed(pks[2]),
+        amount: 500,
+        memo: Disclosure::Revealed(String::from(MEMO)),
+        reference: None,
+        category: None,
+        claim_id: None,
+        nonce: 1,
+        block_height: 2,
+        balance_after: 500,
+        sequence: 3,
+        event_id: [7; 32],
+    });
+    let bytes = rkyv::to_bytes::<_, 1024>(&envelope).unwrap();
+    assert_eq!(event_version(&bytes), Some(EVENT_VERSION));
+    assert_eq!(event_version(&bytes[..4]), None);
+}
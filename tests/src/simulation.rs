@@ -0,0 +1,150 @@
+//! Randomized simulation of the contract against its reference model.
+//!
+//! Random operations - valid or not - are driven against a session, which
+//! checks the outcome of each, and the whole state of the contract after it,
+//! against its model.
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use multisig_contract_types::*;
+
+use crate::model::{Key, Model};
+use crate::{ContractSession, NUM_KEYS, RNG_SEED};
+
+/// Number of steps taken, unless overridden by `SIMULATION_STEPS`.
+const DEFAULT_STEPS: usize = 2_000;
+
+const MAX_AMOUNT: u64 = 1_000;
+const DESCRIPTIONS: [&str; 3] = ["", "treasury", "operations"];
+
+/// Picks an account ID, occasionally one that doesn't exist.
+fn pick_account(rng: &mut StdRng, model: &Model) -> u64 {
+    let num_accounts = model.accounts.len() as u64;
+    if num_accounts == 0 || rng.gen_ratio(1, 20) {
+        return num_accounts + 1;
+    }
+    rng.gen_range(1..=num_accounts)
+}
+
+/// Picks the keys signing an operation on the given account, mostly enough
+/// of its keys, but sometimes too few or some it doesn't use.
+fn pick_signers(
+    rng: &mut StdRng,
+    session: &ContractSession,
+    id: u64,
+) -> Vec<usize> {
+    let Some(account) = session.model.accounts.get(&id) else {
+        return Vec::new();
+    };
+    let uses = |i: &usize| account.keys.contains(&Key(session.pks[*i]));
+
+    let mut keys: Vec<usize> = (0..NUM_KEYS).filter(uses).collect();
+    keys.shuffle(rng);
+
+    let threshold = account.threshold as usize;
+    let mut signers = match rng.gen_range(0..10) {
+        0 => keys[..threshold - 1].to_vec(),
+        _ => keys[..rng.gen_range(threshold..=keys.len())].to_vec(),
+    };
+
+    if rng.gen_ratio(1, 10) {
+        let outsider = (0..NUM_KEYS).find(|i| !uses(i));
+        signers.extend(outsider);
+    }
+
+    signers
+}
+
+/// Picks a change to an account.
+fn pick_change(rng: &mut StdRng, session: &ContractSession) -> AccountChange {
+    let key = session.pks[rng.gen_range(0..NUM_KEYS)];
+
+    match rng.gen_range(0..5) {
+        0 => AccountChange::AddKey { key },
+        1 => AccountChange::RemoveKey { key },
+        2 => AccountChange::SetThreshold {
+            threshold: rng.gen_range(0..=NUM_KEYS as u32 / 2),
+        },
+        3 => AccountChange::SetPrivateEvents {
+            private_events: rng.gen(),
+        },
+        _ => AccountChange::SetDescription {
+            description: String::from(*DESCRIPTIONS.choose(rng).unwrap()),
+        },
+    }
+}
+
+#[test]
+#[ignore = "long running, run with `--ignored`"]
+fn simulation() {
+    let steps = std::env::var("SIMULATION_STEPS")
+        .map(|steps| {
+            steps.parse().expect("SIMULATION_STEPS should be a number")
+        })
+        .unwrap_or(DEFAULT_STEPS);
+
+    let mut rng = StdRng::seed_from_u64(RNG_SEED);
+    let mut session = ContractSession::new_mocked(&mut rng);
+
+    // Every operation is checked against the model by the session itself, so
+    // their results only matter to it
+    for _ in 0..steps {
+        let sender = rng.gen_range(0..NUM_KEYS);
+
+        match rng.gen_range(0..4) {
+            0 => {
+                let mut keys: Vec<usize> = (0..NUM_KEYS).collect();
+                keys.shuffle(&mut rng);
+                keys.truncate(rng.gen_range(0..=4));
+                // Occasionally try to create an account with a duplicate key
+                if !keys.is_empty() && rng.gen_ratio(1, 20) {
+                    keys.push(keys[0]);
+                }
+                let threshold = rng.gen_range(0..=keys.len() as u32 + 1);
+
+                let _ = session.try_create_account(&CreateAccount {
+                    keys: keys.iter().map(|&i| session.pks[i]).collect(),
+                    threshold,
+                    description: String::new(),
+                    alias: None,
+                    key_weights: Vec::new(),
+                    key_roles: Vec::new(),
+                });
+            }
+            1 => {
+                session.account_id =
+                    Some(pick_account(&mut rng, &session.model));
+                let amount = rng.gen_range(1..=MAX_AMOUNT);
+
+                let _ = session.try_deposit(sender, amount);
+            }
+            2 => {
+                let id = pick_account(&mut rng, &session.model);
+                let signers = pick_signers(&mut rng, &session, id);
+                let balance =
+                    session.model.accounts.get(&id).map_or(0, |a| a.balance);
+                let amount = rng.gen_range(0..=balance + MAX_AMOUNT / 10);
+                let receiver = rng.gen_range(0..NUM_KEYS);
+
+                session.account_id = Some(id);
+                let transfer =
+                    session.signed_transfer(&signers, receiver, amount);
+                let _ = session.try_transfer(sender, &transfer);
+            }
+            _ => {
+                let id = pick_account(&mut rng, &session.model);
+                let signers = pick_signers(&mut rng, &session, id);
+                let changes = (0..rng.gen_range(1..=3))
+                    .map(|_| pick_change(&mut rng, &session))
+                    .collect();
+
+                session.account_id = Some(id);
+                let change_account =
+                    session.signed_change_account(&signers, changes);
+                let _ = session.try_change_account(sender, &change_account);
+            }
+        }
+    }
+}
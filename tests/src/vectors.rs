@@ -0,0 +1,674 @@
+//! Test vectors for implementations of the signing flow in other languages.
+//!
+//! Each vector holds an operation, the keys of its signers - derived from a
+//! fixed mnemonic - the message they sign, their partial signatures and the
+//! signature aggregated from them, as well as the archived bytes of the signed
+//! operation. All bytes are hex encoded, and keys and signatures are in their
+//! compressed form.
+//!
+//! The vectors are written as JSON under `vectors/`, and checked in the same
+//! way as the golden files.
+
+use std::path::PathBuf;
+
+use dusk_bytes::Serializable;
+use serde_json::{json, Value};
+
+use bls::{MultisigSignature, PublicKey, SecretKey};
+use multisig_contract_types::*;
+
+use crate::golden;
+
+const VECTORS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/vectors");
+
+const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon \
+                        abandon abandon abandon abandon abandon about";
+const NUM_KEYS: u8 = 3;
+
+fn operation_json(operation: &Operation) -> Value {
+    let mut value = match operation {
+        Operation::Transfer(t) => json!({
+            "type": "transfer",
+            "account_id": t.account_id,
+            "receiver": hex::encode(t.receiver.to_bytes()),
+            "amount": t.amount,
+            "nonce": t.nonce,
+            "memo": t.memo,
+            "reference": t.reference.map(hex::encode),
+            "category": t.category,
+        }),
+        Operation::ChangeAccount(c) => json!({
+            "type": "change_account",
+            "account_id": c.account_id,
+            "changes": changes_json(&c.changes),
+            "nonce": c.nonce,
+        }),
+        Operation::SplitAccount(s) => json!({
+            "type": "split_account",
+            "account_id": s.account_id,
+            "new_keys": s
+                .new_keys
+                .iter()
+                .map(|key| hex::encode(key.to_bytes()))
+                .collect::<Vec<_>>(),
+            "threshold": s.threshold,
+            "description": s.description,
+            "amount": s.amount,
+            "nonce": s.nonce,
+        }),
+        Operation::Payroll(p) => json!({
+            "type": "payroll",
+            "account_id": p.account_id,
+            "rows": p
+                .rows
+                .iter()
+                .map(|row| json!({
+                    "receiver": hex::encode(row.receiver.to_bytes()),
+                    "amount": row.amount,
+                    "memo": row.memo,
+                }))
+                .collect::<Vec<_>>(),
+            "nonce": p.nonce,
+        }),
+        Operation::TransferAndChange(tc) => json!({
+            "type": "transfer_and_change",
+            "account_id": tc.account_id,
+            "receiver": hex::encode(tc.receiver.to_bytes()),
+            "amount": tc.amount,
+            "memo": tc.memo,
+            "changes": changes_json(&tc.changes),
+            "nonce": tc.nonce,
+        }),
+    };
+    value["gas_refund"] = json!(operation.gas_refund().map(|refund| json!({
+        "submitter": hex::encode(refund.submitter.to_bytes()),
+        "amount": refund.amount,
+    })));
+    value["chain_id"] = json!(operation.chain_id());
+    value
+}
+
+/// Returns the JSON representation of a list of changes.
+fn changes_json(changes: &[AccountChange]) -> Vec<Value> {
+    changes
+        .iter()
+        .map(|change| match change {
+            AccountChange::AddKey { key } => json!({
+                "type": "add_key",
+                "key": hex::encode(key.to_bytes()),
+            }),
+            AccountChange::RemoveKey { key } => json!({
+                "type": "remove_key",
+                "key": hex::encode(key.to_bytes()),
+            }),
+            AccountChange::SetThreshold { threshold } => json!({
+                "type": "set_threshold",
+                "threshold": threshold,
+            }),
+            AccountChange::SetDescription { description } => json!({
+                "type": "set_description",
+                "description": description,
+            }),
+            AccountChange::SetPrivateEvents { private_events } => {
+                json!({
+                    "type": "set_private_events",
+                    "private_events": private_events,
+                })
+            }
+            AccountChange::AddDuressKey { key } => json!({
+                "type": "add_duress_key",
+                "key": hex::encode(key.to_bytes()),
+            }),
+            AccountChange::RemoveDuressKey { key } => json!({
+                "type": "remove_duress_key",
+                "key": hex::encode(key.to_bytes()),
+            }),
+            AccountChange::AddCosigner { key } => json!({
+                "type": "add_cosigner",
+                "key": hex::encode(key.to_bytes()),
+            }),
+            AccountChange::RemoveCosigner { key } => json!({
+                "type": "remove_cosigner",
+                "key": hex::encode(key.to_bytes()),
+            }),
+            AccountChange::SetGroupKey { key } => json!({
+                "type": "set_group_key",
+                "key": key.map(|key| hex::encode(key.to_bytes())),
+            }),
+            AccountChange::AddWhitelisted { receiver } => json!({
+                "type": "add_whitelisted",
+                "receiver": hex::encode(receiver.to_bytes()),
+            }),
+            AccountChange::RemoveWhitelisted { receiver } => json!({
+                "type": "remove_whitelisted",
+                "receiver": hex::encode(receiver.to_bytes()),
+            }),
+            AccountChange::SetVelocityLimit { limit } => json!({
+                "type": "set_velocity_limit",
+                "limit": limit.map(|limit| json!({
+                    "amount": limit.amount,
+                    "window": limit.window,
+                })),
+            }),
+            AccountChange::Unlock => json!({ "type": "unlock" }),
+            AccountChange::Archive => json!({ "type": "archive" }),
+            AccountChange::Reactivate => json!({ "type": "reactivate" }),
+            AccountChange::SetClaimableWithdrawals {
+                claimable_withdrawals,
+            } => json!({
+                "type": "set_claimable_withdrawals",
+                "claimable_withdrawals": claimable_withdrawals,
+            }),
+            AccountChange::SetHistoryRetention { records } => json!({
+                "type": "set_history_retention",
+                "records": records,
+            }),
+            AccountChange::SetCompliance { compliance } => json!({
+                "type": "set_compliance",
+                "compliance": compliance.as_ref().map(|compliance| json!({
+                    "key": hex::encode(compliance.key.to_bytes()),
+                    "remediation": hex::encode(compliance.remediation.to_bytes()),
+                })),
+            }),
+            AccountChange::LiftHold { signature } => json!({
+                "type": "lift_hold",
+                "signature": hex::encode(signature.to_bytes()),
+            }),
+            AccountChange::SetLicensePolicy { policy } => json!({
+                "type": "set_license_policy",
+                "policy": policy.map(|policy| json!({
+                    "contract": hex::encode(policy.contract),
+                    "signers": policy.signers,
+                })),
+            }),
+            AccountChange::SetOperationThreshold { op, threshold } => json!({
+                "type": "set_operation_threshold",
+                "op": op.fn_name(),
+                "threshold": threshold,
+            }),
+            AccountChange::SetTransferTiers { tiers } => json!({
+                "type": "set_transfer_tiers",
+                "tiers": tiers.iter().map(|tier| json!({
+                    "ceiling": tier.ceiling,
+                    "threshold": tier.threshold,
+                })).collect::<Vec<_>>(),
+            }),
+            AccountChange::AddGuardian { key } => json!({
+                "type": "add_guardian",
+                "key": hex::encode(key.to_bytes()),
+            }),
+            AccountChange::RemoveGuardian { key } => json!({
+                "type": "remove_guardian",
+                "key": hex::encode(key.to_bytes()),
+            }),
+            AccountChange::SetAlias { alias } => json!({
+                "type": "set_alias",
+                "alias": alias,
+            }),
+            AccountChange::SetBalanceCap { cap } => json!({
+                "type": "set_balance_cap",
+                "cap": cap,
+            }),
+            AccountChange::AddTag { tag } => json!({
+                "type": "add_tag",
+                "tag": tag,
+            }),
+            AccountChange::RemoveTag { tag } => json!({
+                "type": "remove_tag",
+                "tag": tag,
+            }),
+            AccountChange::SetLivenessPolicy { policy } => json!({
+                "type": "set_liveness_policy",
+                "policy": policy.map(|policy| json!({
+                    "window": policy.window,
+                    "min_threshold": policy.min_threshold,
+                })),
+            }),
+            AccountChange::SetSupermajority { supermajority } => json!({
+                "type": "set_supermajority",
+                "supermajority": supermajority.map(|supermajority| json!({
+                    "numerator": supermajority.numerator,
+                    "denominator": supermajority.denominator,
+                })),
+            }),
+            AccountChange::SetKeyAdditionPolicy { policy } => json!({
+                "type": "set_key_addition_policy",
+                "policy": policy.map(|policy| json!({
+                    "delay": policy.delay,
+                    "supermajority": policy.supermajority,
+                })),
+            }),
+            AccountChange::SetCategory { category } => json!({
+                "type": "set_category",
+                "name": category.name,
+                "budget": category.budget,
+                "window": category.window,
+            }),
+            AccountChange::RemoveCategory { name } => json!({
+                "type": "remove_category",
+                "name": name,
+            }),
+            AccountChange::AddObserver { key } => json!({
+                "type": "add_observer",
+                "key": hex::encode(key.to_bytes()),
+            }),
+            AccountChange::RemoveObserver { key } => json!({
+                "type": "remove_observer",
+                "key": hex::encode(key.to_bytes()),
+            }),
+            AccountChange::SetChildBudget { budget } => json!({
+                "type": "set_child_budget",
+                "child_id": budget.child_id,
+                "amount": budget.amount,
+                "window": budget.window,
+            }),
+            AccountChange::RemoveChildBudget { child_id } => json!({
+                "type": "remove_child_budget",
+                "child_id": child_id,
+            }),
+            AccountChange::ClawBackBudget { child_id } => json!({
+                "type": "claw_back_budget",
+                "child_id": child_id,
+            }),
+            AccountChange::SetKeyExpiry { key, valid_until } => json!({
+                "type": "set_key_expiry",
+                "key": hex::encode(key.to_bytes()),
+                "valid_until": valid_until,
+            }),
+            AccountChange::SetKeyWeight { key, weight } => json!({
+                "type": "set_key_weight",
+                "key": hex::encode(key.to_bytes()),
+                "weight": weight,
+            }),
+            AccountChange::SetKeyRole { key, role } => json!({
+                "type": "set_key_role",
+                "key": hex::encode(key.to_bytes()),
+                "role": role.name(),
+            }),
+            AccountChange::SetRecoveryPolicy { policy } => json!({
+                "type": "set_recovery_policy",
+                "policy": policy.map(|policy| json!({
+                    "delay": policy.delay,
+                    "guardians": policy.guardians,
+                })),
+            }),
+            AccountChange::SetInactivityPolicy { policy } => json!({
+                "type": "set_inactivity_policy",
+                "policy": policy.as_ref().map(|policy| json!({
+                    "fallback": hex::encode(policy.fallback.to_bytes()),
+                    "period": policy.period,
+                })),
+            }),
+            AccountChange::SetAllowance { key, amount } => json!({
+                "type": "set_allowance",
+                "key": hex::encode(key.to_bytes()),
+                "amount": amount,
+            }),
+            AccountChange::SetQueuePolicy { policy } => json!({
+                "type": "set_queue_policy",
+                "policy": policy.map(|policy| json!({
+                    "min_amount": policy.min_amount,
+                    "delay": policy.delay,
+                })),
+            }),
+            AccountChange::SetTransferWindow { window } => json!({
+                "type": "set_transfer_window",
+                "window": window.map(|window| json!({
+                    "period": window.period,
+                    "offset": window.offset,
+                    "length": window.length,
+                })),
+            }),
+        })
+        .collect()
+}
+
+/// Signs the operation with the given signers, and checks the resulting
+/// vector against the file with the given name.
+#[track_caller]
+fn check_vector(
+    name: &str,
+    description: &str,
+    sks: &[SecretKey],
+    signers: &[usize],
+    mut operation: Operation,
+) {
+    let partials: Vec<PartialSignature> = signers
+        .iter()
+        .map(|&i| PartialSignature::sign(&sks[i], &operation))
+        .collect();
+    operation.aggregate(&partials);
+
+    let signature = match &operation {
+        Operation::Transfer(t) => t.signature,
+        Operation::ChangeAccount(c) => c.signature,
+        Operation::SplitAccount(s) => s.signature,
+        Operation::Payroll(p) => p.signature,
+        Operation::TransferAndChange(tc) => tc.signature,
+    };
+    assert!(
+        signature_valid(&operation, signature),
+        "The aggregated signature of {name} should be valid"
+    );
+
+    let archived = match &operation {
+        Operation::Transfer(t) => rkyv::to_bytes::<_, 1024>(t),
+        Operation::ChangeAccount(c) => rkyv::to_bytes::<_, 1024>(c),
+        Operation::SplitAccount(s) => rkyv::to_bytes::<_, 1024>(s),
+        Operation::Payroll(p) => rkyv::to_bytes::<_, 1024>(p),
+        Operation::TransferAndChange(tc) => rkyv::to_bytes::<_, 1024>(tc),
+    }
+    .expect("Serializing the operation should succeed");
+
+    let vector = json!({
+        "description": description,
+        "mnemonic": MNEMONIC,
+        "keys": sks
+            .iter()
+            .enumerate()
+            .map(|(i, sk)| json!({
+                "index": i,
+                "secret_key": hex::encode(sk.to_bytes()),
+                "public_key": hex::encode(PublicKey::from(sk).to_bytes()),
+            }))
+            .collect::<Vec<_>>(),
+        "operation": operation_json(&operation),
+        "signature_msg": hex::encode(operation.signature_msg()),
+        "partial_signatures": partials
+            .iter()
+            .map(|p| json!({
+                "key": hex::encode(p.key.to_bytes()),
+                "signature": hex::encode(p.signature.to_bytes()),
+            }))
+            .collect::<Vec<_>>(),
+        "aggregated_signature": hex::encode(signature.to_bytes()),
+        "archived": hex::encode(&archived),
+    });
+
+    let mut contents = serde_json::to_string_pretty(&vector)
+        .expect("Serializing the vector should succeed");
+    contents.push('\n');
+
+    let path = PathBuf::from(VECTORS_DIR).join(format!("{name}.json"));
+    golden::check_file(&path, &contents);
+}
+
+/// Returns true if the signature is valid for the operation's message and
+/// keys.
+fn signature_valid(
+    operation: &Operation,
+    signature: MultisigSignature,
+) -> bool {
+    let keys = match operation {
+        Operation::Transfer(t) => &t.keys,
+        Operation::ChangeAccount(c) => &c.keys,
+        Operation::SplitAccount(s) => &s.keys,
+        Operation::Payroll(p) => &p.keys,
+        Operation::TransferAndChange(tc) => &tc.keys,
+    };
+    bls::MultisigPublicKey::aggregate(keys)
+        .and_then(|pk| pk.verify(&signature, &operation.signature_msg()))
+        .is_ok()
+}
+
+#[test]
+fn vectors() {
+    let seed = keys::seed(MNEMONIC, "").expect("Mnemonic should be valid");
+    let sks = keys::derive_secret_keys(&seed, NUM_KEYS);
+    let pks: Vec<PublicKey> = sks.iter().map(PublicKey::from).collect();
+
+    let receiver = PublicKey::from(&keys::derive_secret_key(&seed, NUM_KEYS));
+
+    check_vector(
+        "transfer",
+        "A transfer out of a 2-of-3 account, signed by its first two keys",
+        &sks,
+        &[0, 1],
+        Operation::Transfer(Transfer {
+            account_id: 1,
+            keys: Vec::new(),
+            signature: MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
+            receiver,
+            amount: 1_000,
+            nonce: 1,
+            memo: String::from("payment"),
+            reference: None,
+            category: None,
+            gas_refund: None,
+            chain_id: None,
+        }),
+    );
+    check_vector(
+        "transfer_no_memo",
+        "A transfer of the whole balance with an empty memo, signed by all \
+         three keys",
+        &sks,
+        &[0, 1, 2],
+        Operation::Transfer(Transfer {
+            account_id: 7,
+            keys: Vec::new(),
+            signature: MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
+            receiver,
+            amount: TRANSFER_ALL,
+            nonce: 42,
+            memo: String::new(),
+            reference: None,
+            category: None,
+            gas_refund: None,
+            chain_id: None,
+        }),
+    );
+    check_vector(
+        "transfer_gas_refund",
+        "A transfer reimbursing its submitter for the gas, signed by the \
+         first two keys",
+        &sks,
+        &[0, 1],
+        Operation::Transfer(Transfer {
+            account_id: 1,
+            keys: Vec::new(),
+            signature: MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
+            receiver,
+            amount: 1_000,
+            nonce: 2,
+            memo: String::from("payment"),
+            reference: None,
+            category: None,
+            gas_refund: Some(GasRefund {
+                submitter: pks[2],
+                amount: 25_000,
+            }),
+            chain_id: None,
+        }),
+    );
+    check_vector(
+        "transfer_chain",
+        "A transfer declaring the chain it's meant for and reimbursing its \
+         submitter, signed by the first two keys",
+        &sks,
+        &[0, 1],
+        Operation::Transfer(Transfer {
+            account_id: 1,
+            keys: Vec::new(),
+            signature: MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
+            receiver,
+            amount: 1_000,
+            nonce: 3,
+            memo: String::from("payment"),
+            reference: None,
+            category: None,
+            gas_refund: Some(GasRefund {
+                submitter: pks[2],
+                amount: 25_000,
+            }),
+            chain_id: Some(0x01),
+        }),
+    );
+    check_vector(
+        "transfer_reference",
+        "A transfer carrying an invoice reference, signed by the first two \
+         keys",
+        &sks,
+        &[0, 1],
+        Operation::Transfer(Transfer {
+            account_id: 1,
+            keys: Vec::new(),
+            signature: MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
+            receiver,
+            amount: 1_000,
+            nonce: 4,
+            memo: String::from("payment"),
+            reference: Some([0x2A; 32]),
+            category: None,
+            gas_refund: None,
+            chain_id: None,
+        }),
+    );
+    check_vector(
+        "transfer_category",
+        "A transfer counted against the marketing budget of its account, \
+         signed by the first two keys",
+        &sks,
+        &[0, 1],
+        Operation::Transfer(Transfer {
+            account_id: 1,
+            keys: Vec::new(),
+            signature: MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
+            receiver,
+            amount: 1_000,
+            nonce: 5,
+            memo: String::from("campaign"),
+            reference: None,
+            category: Some(String::from("marketing")),
+            gas_refund: None,
+            chain_id: None,
+        }),
+    );
+    check_vector(
+        "change_account",
+        "A change with every kind of change, signed by the last two keys",
+        &sks,
+        &[1, 2],
+        Operation::ChangeAccount(ChangeAccount {
+            account_id: 1,
+            keys: Vec::new(),
+            signature: MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
+            changes: vec![
+                AccountChange::AddKey { key: receiver },
+                AccountChange::RemoveKey { key: pks[0] },
+                AccountChange::SetThreshold { threshold: 3 },
+                AccountChange::SetDescription {
+                    description: String::from("treasury"),
+                },
+            ],
+            nonce: 2,
+            gas_refund: None,
+            chain_id: None,
+        }),
+    );
+    check_vector(
+        "change_account_threshold",
+        "A change of the threshold alone, signed by a single key",
+        &sks,
+        &[2],
+        Operation::ChangeAccount(ChangeAccount {
+            account_id: 3,
+            keys: Vec::new(),
+            signature: MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
+            changes: vec![AccountChange::SetThreshold { threshold: 1 }],
+            nonce: 1,
+            gas_refund: None,
+            chain_id: None,
+        }),
+    );
+    check_vector(
+        "split_account",
+        "A split of a 1-of-2 account off with the first two keys, signed by \
+         the first two keys",
+        &sks,
+        &[0, 1],
+        Operation::SplitAccount(SplitAccount {
+            account_id: 1,
+            keys: Vec::new(),
+            signature: MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
+            new_keys: pks[..2].to_vec(),
+            threshold: 1,
+            description: String::from("petty cash"),
+            amount: 500,
+            nonce: 3,
+            gas_refund: None,
+            chain_id: None,
+        }),
+    );
+    check_vector(
+        "payroll",
+        "A payroll of two rows, one without a memo, signed by the first two \
+         keys",
+        &sks,
+        &[0, 1],
+        Operation::Payroll(Payroll {
+            account_id: 1,
+            keys: Vec::new(),
+            signature: MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
+            rows: vec![
+                PayrollRow {
+                    receiver,
+                    amount: 1_500,
+                    memo: String::from("october"),
+                },
+                PayrollRow {
+                    receiver: pks[2],
+                    amount: 750,
+                    memo: String::new(),
+                },
+            ],
+            nonce: 4,
+            gas_refund: None,
+            chain_id: None,
+        }),
+    );
+    check_vector(
+        "transfer_and_change",
+        "A payout to the third key together with its removal, signed by the \
+         first two keys",
+        &sks,
+        &[0, 1],
+        Operation::TransferAndChange(TransferAndChange {
+            account_id: 1,
+            keys: Vec::new(),
+            signature: MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
+            receiver: pks[2],
+            amount: 2_000,
+            memo: String::from("severance"),
+            changes: vec![
+                AccountChange::RemoveKey { key: pks[2] },
+                AccountChange::SetThreshold { threshold: 1 },
+            ],
+            nonce: 5,
+            gas_refund: None,
+            chain_id: None,
+        }),
+    );
+}
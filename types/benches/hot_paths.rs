@@ -0,0 +1,155 @@
+//! Benchmarks of the paths clients hit when signing and submitting
+//! operations, across memo sizes and key counts.
+
+use criterion::{
+    criterion_group, criterion_main, BenchmarkId, Criterion, Throughput,
+};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rkyv::{Deserialize, Infallible};
+
+use multisig_contract_types::bls::{MultisigSignature, PublicKey, SecretKey};
+use multisig_contract_types::*;
+
+const MEMO_SIZES: [usize; 4] = [0, 64, 512, 4096];
+const KEY_COUNTS: [usize; 4] = [1, 4, 16, 64];
+
+fn keys(num: usize) -> (Vec<SecretKey>, Vec<PublicKey>) {
+    let mut rng = StdRng::seed_from_u64(0xBEEF);
+    let sks: Vec<SecretKey> =
+        (0..num).map(|_| SecretKey::random(&mut rng)).collect();
+    let pks = sks.iter().map(PublicKey::from).collect();
+    (sks, pks)
+}
+
+fn transfer(pks: &[PublicKey], memo_size: usize) -> Transfer {
+    Transfer {
+        account_id: 1,
+        keys: pks.to_vec(),
+        signature: MultisigSignature::default(),
+        cosignatures: Vec::new(),
+        group_signature: None,
+        receiver: pks[0],
+        amount: 1_000,
+        nonce: 1,
+        memo: "m".repeat(memo_size),
+        reference: None,
+        category: None,
+        gas_refund: None,
+        chain_id: None,
+    }
+}
+
+fn signature_msg(c: &mut Criterion) {
+    let (_, pks) = keys(*KEY_COUNTS.last().unwrap());
+
+    let mut group = c.benchmark_group("signature_msg/transfer");
+    for memo_size in MEMO_SIZES {
+        let transfer = transfer(&pks[..1], memo_size);
+        group.throughput(Throughput::Bytes(memo_size as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(memo_size),
+            &transfer,
+            |b, transfer| b.iter(|| transfer.signature_msg()),
+        );
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("signature_msg/change_account");
+    for num_keys in KEY_COUNTS {
+        let change_account = ChangeAccount {
+            account_id: 1,
+            keys: Vec::new(),
+            signature: MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
+            changes: pks[..num_keys]
+                .iter()
+                .map(|&key| AccountChange::AddKey { key })
+                .collect(),
+            nonce: 1,
+            gas_refund: None,
+            chain_id: None,
+        };
+        group.bench_with_input(
+            BenchmarkId::from_parameter(num_keys),
+            &change_account,
+            |b, change_account| b.iter(|| change_account.signature_msg()),
+        );
+    }
+    group.finish();
+}
+
+fn aggregation(c: &mut Criterion) {
+    let (sks, pks) = keys(*KEY_COUNTS.last().unwrap());
+    let operation = Operation::Transfer(transfer(&pks[..1], 64));
+
+    let mut group = c.benchmark_group("aggregate");
+    for num_keys in KEY_COUNTS {
+        let partials: Vec<PartialSignature> = sks[..num_keys]
+            .iter()
+            .map(|sk| PartialSignature::sign(sk, &operation))
+            .collect();
+        group.bench_with_input(
+            BenchmarkId::from_parameter(num_keys),
+            &partials,
+            |b, partials| {
+                b.iter_batched(
+                    || operation.clone(),
+                    |mut operation| operation.aggregate(partials),
+                    criterion::BatchSize::SmallInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+fn serialization(c: &mut Criterion) {
+    let (_, pks) = keys(*KEY_COUNTS.last().unwrap());
+
+    let mut cases = Vec::new();
+    for num_keys in KEY_COUNTS {
+        cases.push((format!("keys-{num_keys}"), transfer(&pks[..num_keys], 0)));
+    }
+    for memo_size in MEMO_SIZES {
+        cases.push((
+            format!("memo-{memo_size}"),
+            transfer(&pks[..1], memo_size),
+        ));
+    }
+
+    let mut group = c.benchmark_group("serialize");
+    for (name, transfer) in &cases {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name),
+            transfer,
+            |b, transfer| {
+                b.iter(|| rkyv::to_bytes::<_, 1024>(transfer).unwrap())
+            },
+        );
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("deserialize");
+    for (name, transfer) in &cases {
+        let bytes = rkyv::to_bytes::<_, 1024>(transfer).unwrap();
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name),
+            &bytes,
+            |b, bytes| {
+                b.iter(|| {
+                    let archived =
+                        rkyv::check_archived_root::<Transfer>(bytes).unwrap();
+                    let transfer: Transfer =
+                        archived.deserialize(&mut Infallible).unwrap();
+                    transfer
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, signature_msg, aggregation, serialization);
+criterion_main!(benches);
@@ -0,0 +1,137 @@
+//! Co-signers using signature schemes other than BLS.
+//!
+//! Signers whose devices can't take part in a BLS multisignature - such as
+//! hardware wallets that only do Ed25519 - can still be registered with an
+//! account as co-signers. A co-signer signs the message of an operation on
+//! its own, with its own scheme, and its [`Cosignature`] is carried alongside
+//! the aggregated BLS signature of the operation. Co-signers count towards the
+//! threshold of the account just like its BLS keys do.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use bytecheck::CheckBytes;
+use rkyv::{Archive, Deserialize, Serialize};
+
+pub use ed25519_dalek as ed25519;
+
+use crate::Operation;
+
+/// The key of a co-signer, tagged with its signature scheme.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Archive,
+    Serialize,
+    Deserialize,
+)]
+#[archive_attr(derive(CheckBytes))]
+pub enum CosignerKey {
+    /// An Ed25519 public key, in its compressed form.
+    Ed25519([u8; 32]),
+}
+
+impl CosignerKey {
+    const ED25519_TAG: u8 = 0;
+
+    /// Encodes the key as the tag of its scheme followed by its bytes, as it
+    /// appears in signature messages.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        match self {
+            CosignerKey::Ed25519(key) => {
+                bytes.push(Self::ED25519_TAG);
+                bytes.extend(key);
+            }
+        }
+        bytes
+    }
+
+    /// Decodes a key encoded with [`to_bytes`].
+    ///
+    /// [`to_bytes`]: Self::to_bytes
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        match bytes.split_first()? {
+            (&Self::ED25519_TAG, key) => {
+                Some(Self::Ed25519(key.try_into().ok()?))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns true if the signature is valid for the given message, under
+    /// the key's scheme.
+    pub fn verify(&self, msg: &[u8], signature: &[u8]) -> bool {
+        match self {
+            CosignerKey::Ed25519(key) => {
+                let Ok(key) = ed25519::VerifyingKey::from_bytes(key) else {
+                    return false;
+                };
+                let Ok(signature) = ed25519::Signature::from_slice(signature)
+                else {
+                    return false;
+                };
+                key.verify_strict(msg, &signature).is_ok()
+            }
+        }
+    }
+}
+
+/// Shows the key as the name of its scheme followed by its hex encoded bytes,
+/// as in `ed25519:<hex>`.
+impl fmt::Display for CosignerKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CosignerKey::Ed25519(key) => {
+                write!(f, "ed25519:")?;
+                for byte in key {
+                    write!(f, "{byte:02x}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl From<&ed25519::SigningKey> for CosignerKey {
+    fn from(sk: &ed25519::SigningKey) -> Self {
+        Self::Ed25519(sk.verifying_key().to_bytes())
+    }
+}
+
+/// The signature of an operation by a co-signer.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct Cosignature {
+    /// The co-signer that signed.
+    pub key: CosignerKey,
+    /// The signature of the operation's message, encoded as its scheme
+    /// specifies.
+    pub signature: Vec<u8>,
+}
+
+impl Cosignature {
+    /// Signs the message of the given operation with an Ed25519 key.
+    pub fn sign_ed25519(
+        sk: &ed25519::SigningKey,
+        operation: &Operation,
+    ) -> Self {
+        use ed25519::Signer;
+
+        let signature = sk.sign(&operation.signature_msg());
+        Self {
+            key: CosignerKey::from(sk),
+            signature: signature.to_bytes().to_vec(),
+        }
+    }
+
+    /// Returns true if the signature is valid for the given operation.
+    pub fn verify(&self, operation: &Operation) -> bool {
+        self.key.verify(&operation.signature_msg(), &self.signature)
+    }
+}
@@ -0,0 +1,144 @@
+//! Canonical textual forms of keys, signatures, account IDs and signature
+//! messages.
+//!
+//! BLS keys and signatures are encoded in base58, as they are in the rest of
+//! the Dusk ecosystem, and signature messages in hex, without a prefix.
+//! Account IDs are written in decimal, and co-signer keys as they're
+//! displayed, in `ed25519:<hex>`. Tools exchanging any of these as text - the
+//! CLI, the coordinator, wallets and the docs - should use these forms, so
+//! that what one prints the others can read.
+//!
+//! Decoding ignores surrounding whitespace, and accepts hex with a `0x`
+//! prefix, but is otherwise strict.
+//!
+//! This is meant to be used off-chain only, and is therefore only available
+//! with the `encoding` feature.
+
+use core::fmt;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use dusk_bytes::Serializable;
+
+use crate::{bls, CosignerKey};
+
+/// Errors that may occur when decoding a textual form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodingError {
+    /// The text isn't valid base58.
+    InvalidBase58,
+    /// The text isn't valid hex.
+    InvalidHex,
+    /// The text isn't a decimal account ID.
+    InvalidAccountId,
+    /// The decoded bytes have the wrong length.
+    InvalidLength,
+    /// The decoded bytes aren't a valid key or signature.
+    InvalidValue,
+    /// The co-signer key has a scheme that isn't known.
+    UnknownScheme,
+}
+
+impl fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let msg = match self {
+            EncodingError::InvalidBase58 => "Invalid base58",
+            EncodingError::InvalidHex => "Invalid hex",
+            EncodingError::InvalidAccountId => "Invalid account ID",
+            EncodingError::InvalidLength => "Invalid length",
+            EncodingError::InvalidValue => "Invalid key or signature",
+            EncodingError::UnknownScheme => "Unknown co-signer scheme",
+        };
+        write!(f, "{msg}")
+    }
+}
+
+/// Encodes a public key in base58.
+pub fn encode_public_key(key: &bls::PublicKey) -> String {
+    bs58::encode(key.to_bytes()).into_string()
+}
+
+/// Decodes a base58 encoded public key.
+pub fn decode_public_key(s: &str) -> Result<bls::PublicKey, EncodingError> {
+    decode_base58(s)
+}
+
+/// Encodes a signature in base58.
+pub fn encode_signature(signature: &bls::Signature) -> String {
+    bs58::encode(signature.to_bytes()).into_string()
+}
+
+/// Decodes a base58 encoded signature.
+pub fn decode_signature(s: &str) -> Result<bls::Signature, EncodingError> {
+    decode_base58(s)
+}
+
+/// Encodes a multisignature in base58.
+pub fn encode_multisig_signature(signature: &bls::MultisigSignature) -> String {
+    bs58::encode(signature.to_bytes()).into_string()
+}
+
+/// Decodes a base58 encoded multisignature.
+pub fn decode_multisig_signature(
+    s: &str,
+) -> Result<bls::MultisigSignature, EncodingError> {
+    decode_base58(s)
+}
+
+/// Encodes an account ID in decimal.
+pub fn encode_account_id(account_id: u64) -> String {
+    alloc::format!("{account_id}")
+}
+
+/// Decodes an account ID written in decimal.
+pub fn decode_account_id(s: &str) -> Result<u64, EncodingError> {
+    let s = s.trim();
+
+    // Signs are accepted by `parse`, but aren't part of the canonical form
+    if !s.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(EncodingError::InvalidAccountId);
+    }
+    s.parse().map_err(|_| EncodingError::InvalidAccountId)
+}
+
+/// Encodes a co-signer key as it's displayed, prefixed by its scheme.
+pub fn encode_cosigner_key(key: &CosignerKey) -> String {
+    alloc::format!("{key}")
+}
+
+/// Decodes a co-signer key prefixed by its scheme, as it's displayed.
+pub fn decode_cosigner_key(s: &str) -> Result<CosignerKey, EncodingError> {
+    let Some(("ed25519", key)) = s.trim().split_once(':') else {
+        return Err(EncodingError::UnknownScheme);
+    };
+    let key = hex::decode(key)
+        .map_err(|_| EncodingError::InvalidHex)?
+        .try_into()
+        .map_err(|_| EncodingError::InvalidLength)?;
+    Ok(CosignerKey::Ed25519(key))
+}
+
+/// Encodes a signature message in hex.
+pub fn encode_message(msg: &[u8]) -> String {
+    hex::encode(msg)
+}
+
+/// Decodes a hex encoded signature message.
+pub fn decode_message(s: &str) -> Result<Vec<u8>, EncodingError> {
+    let s = s.trim();
+    let s = s.strip_prefix("0x").unwrap_or(s);
+    hex::decode(s).map_err(|_| EncodingError::InvalidHex)
+}
+
+fn decode_base58<T, const N: usize>(s: &str) -> Result<T, EncodingError>
+where
+    T: Serializable<N>,
+{
+    let bytes = bs58::decode(s.trim())
+        .into_vec()
+        .map_err(|_| EncodingError::InvalidBase58)?;
+    let bytes = bytes.try_into().map_err(|_| EncodingError::InvalidLength)?;
+
+    T::from_bytes(&bytes).map_err(|_| EncodingError::InvalidValue)
+}
@@ -0,0 +1,45 @@
+//! Deterministic derivation of keys from a mnemonic.
+//!
+//! Lets tests and tooling reproduce a set of signers from a BIP39 mnemonic,
+//! instead of relying on ad-hoc RNG seeds. Keys are derived from the seed of
+//! the mnemonic in the same way Dusk's wallets derive their profile keys, so
+//! the same mnemonic yields the same keys in both.
+//!
+//! This is meant to be used off-chain only, and is therefore only available
+//! with the `keys` feature.
+
+use alloc::vec::Vec;
+
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+use sha2::{Digest, Sha256};
+
+pub use bip39::{Error as MnemonicError, Mnemonic};
+
+use crate::bls;
+
+/// Returns the seed of the given mnemonic, protected by the given passphrase.
+pub fn seed(
+    mnemonic: &str,
+    passphrase: &str,
+) -> Result<[u8; 64], MnemonicError> {
+    Ok(Mnemonic::parse(mnemonic)?.to_seed(passphrase))
+}
+
+/// Derives the secret key with the given index from a seed.
+pub fn derive_secret_key(seed: &[u8; 64], index: u8) -> bls::SecretKey {
+    let mut hash = Sha256::new();
+
+    hash.update(seed);
+    hash.update(index.to_le_bytes());
+    hash.update(b"SK");
+
+    let mut rng = ChaCha12Rng::from_seed(hash.finalize().into());
+    bls::SecretKey::random(&mut rng)
+}
+
+/// Derives the secret keys with indices from zero up to the given number
+/// from a seed.
+pub fn derive_secret_keys(seed: &[u8; 64], num: u8) -> Vec<bls::SecretKey> {
+    (0..num).map(|i| derive_secret_key(seed, i)).collect()
+}
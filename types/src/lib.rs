@@ -5,15 +5,29 @@
 
 extern crate alloc;
 
+use core::fmt;
+
+use alloc::boxed::Box;
 use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
 
 use bytecheck::CheckBytes;
 use rkyv::{Archive, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 pub use execution_core::signatures::bls;
 
+mod cosigner;
+mod payload;
+pub use cosigner::*;
+pub use payload::*;
+
+#[cfg(feature = "encoding")]
+pub mod encoding;
+#[cfg(feature = "keys")]
+pub mod keys;
+
 /// Used to create multisig accounts.
 #[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
 #[archive_attr(derive(CheckBytes))]
@@ -24,6 +38,264 @@ pub struct CreateAccount {
     pub threshold: u32,
     /// Description of the account.
     pub description: String,
+    /// Alias to claim for the account, if any.
+    pub alias: Option<String>,
+    /// The weights of the keys counting for more - or less - than one
+    /// signer, each key given no weight counting for one.
+    pub key_weights: Vec<KeyWeight>,
+    /// The roles of the keys limited to fewer operations than the others,
+    /// each key given no role being an admin.
+    pub key_roles: Vec<RoleAssignment>,
+}
+
+/// The longest alias an account can claim, in bytes.
+pub const MAX_ALIAS_LEN: usize = 32;
+
+/// Returns true if the given alias can be claimed by an account.
+///
+/// Aliases are made of lowercase ASCII letters, digits and dashes, start with
+/// a letter - so that they can't be mistaken for account IDs - and don't end
+/// with a dash.
+pub fn is_valid_alias(alias: &str) -> bool {
+    let bytes = alias.as_bytes();
+    bytes.len() <= MAX_ALIAS_LEN
+        && bytes.first().is_some_and(u8::is_ascii_lowercase)
+        && bytes.last() != Some(&b'-')
+        && bytes
+            .iter()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || *b == b'-')
+}
+
+/// The longest tag an account can carry, in bytes.
+pub const MAX_TAG_LEN: usize = 16;
+
+/// The most tags an account can carry.
+pub const MAX_TAGS: usize = 8;
+
+/// Returns true if the given tag can be carried by an account.
+///
+/// Tags are made of lowercase ASCII letters, digits and dashes, and aren't
+/// empty. Unlike aliases they aren't unique, many accounts carrying the same
+/// tag.
+pub fn is_valid_tag(tag: &str) -> bool {
+    let bytes = tag.as_bytes();
+    !bytes.is_empty()
+        && bytes.len() <= MAX_TAG_LEN
+        && bytes
+            .iter()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || *b == b'-')
+}
+
+/// The most spending categories an account can have.
+pub const MAX_CATEGORIES: usize = 8;
+
+/// The most child accounts an account can allocate budgets to.
+pub const MAX_CHILD_BUDGETS: usize = 8;
+
+/// The most tiers an account's transfers can have.
+pub const MAX_TRANSFER_TIERS: usize = 8;
+
+/// Returns true if the given tiers can be set for the transfers of an account
+/// whose keys and co-signers count for the given number of signers.
+///
+/// There can be at most [`MAX_TRANSFER_TIERS`] of them, ordered by strictly
+/// increasing ceilings, so that each amount falls in a single tier, and none
+/// can need more signers than the account has.
+pub fn are_valid_transfer_tiers(
+    tiers: &[TransferTier],
+    signers: usize,
+) -> bool {
+    tiers.len() <= MAX_TRANSFER_TIERS
+        && tiers.windows(2).all(|w| w[0].ceiling < w[1].ceiling)
+        && tiers.iter().all(|tier| tier.threshold as usize <= signers)
+}
+
+/// The number of movements an account retains in its ledger unless it sets
+/// its own retention, free of charge.
+pub const DEFAULT_HISTORY_RETENTION: u32 = 256;
+
+/// The most movements an account can retain in its ledger.
+pub const MAX_HISTORY_RETENTION: u32 = 4_096;
+
+/// The storage fee an account pays by default, in LUX, for every movement its
+/// retention is raised by beyond [`DEFAULT_HISTORY_RETENTION`].
+///
+/// The fee is paid out of the balance of the account when the retention is
+/// set, and kept by the contract. Lowering the retention refunds nothing. The
+/// governance account can set another with [`ContractConfig`].
+pub const HISTORY_RECORD_FEE: u64 = 100_000;
+
+/// Returns the storage fee of raising the retention of an account from one
+/// number of movements to another, at the given fee per movement.
+pub fn history_retention_fee(
+    from: Option<u32>,
+    to: Option<u32>,
+    record_fee: u64,
+) -> u64 {
+    let from = from.unwrap_or(DEFAULT_HISTORY_RETENTION);
+    let to = to.unwrap_or(DEFAULT_HISTORY_RETENTION);
+    let raised = to.saturating_sub(from.max(DEFAULT_HISTORY_RETENTION));
+    (raised as u64).saturating_mul(record_fee)
+}
+
+/// A policy registered once, under which any number of identical accounts can
+/// then be created with [`CreateAccountFromTemplate`], without repeating it.
+///
+/// Templates can be registered by anyone, and never change once registered.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct AccountTemplate {
+    /// Number of keys and co-signers that need to sign to effect an operation
+    /// on the accounts created.
+    pub threshold: u32,
+    /// Description of the accounts created.
+    pub description: String,
+    /// Whether the events of the accounts created carry hashes of memos and
+    /// receivers, instead of the values themselves.
+    pub private_events: bool,
+    /// The soft limit on the outflow of the accounts created, if any.
+    pub velocity_limit: Option<VelocityLimit>,
+    /// Co-signers registered with each account created.
+    pub cosigners: Vec<CosignerKey>,
+    /// Guardians registered with each account created.
+    pub guardians: Vec<bls::PublicKey>,
+}
+
+/// Used to create a multisig account under a registered [`AccountTemplate`].
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct CreateAccountFromTemplate {
+    /// The template to create the account under.
+    pub template_id: u64,
+    /// Keys to be owned by the account.
+    pub keys: Vec<bls::PublicKey>,
+    /// Alias to claim for the account, if any.
+    pub alias: Option<String>,
+}
+
+/// Accounts the contract is deployed with, passed as the argument of its
+/// `init` function.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct Genesis {
+    /// The accounts to create, in the order of their IDs.
+    pub accounts: Vec<GenesisAccount>,
+    /// The most the contract may hold across all its accounts, if bounded.
+    /// Deposits taking the total balance past it are refused.
+    pub custody_cap: Option<u64>,
+    /// The smallest amount a deposit may be, if bounded. Smaller deposits are
+    /// refused.
+    pub min_deposit: Option<u64>,
+    /// The genesis account governing the contract-wide parameters, if any.
+    pub governance: Option<u64>,
+}
+
+/// An account created when the contract is deployed.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct GenesisAccount {
+    /// Keys to be owned by the account.
+    pub keys: Vec<bls::PublicKey>,
+    /// Number of keys that need to sign to effect an operation.
+    pub threshold: u32,
+    /// Description of the account.
+    pub description: String,
+    /// The balance the account starts with.
+    ///
+    /// The contract's balance in the transfer contract must match the sum of
+    /// the balances of all genesis accounts.
+    pub balance: u64,
+}
+
+/// The state of a previous version of the contract, restored when migrating
+/// to a new version by calling its `migrate` function.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct Migration {
+    /// The accounts to restore.
+    pub accounts: Vec<MigratedAccount>,
+    /// The sequence number of the last event emitted, from which the new
+    /// version carries on.
+    pub event_sequence: u64,
+    /// Keys marked as compromised, which remain refused.
+    pub compromised_keys: Vec<bls::PublicKey>,
+    /// Co-signer keys marked as compromised, which remain refused.
+    pub compromised_cosigners: Vec<CosignerKey>,
+    /// The custody cap of the contract, which can only be changed by
+    /// migrating.
+    pub custody_cap: Option<u64>,
+    /// The minimum deposit of the contract, which can only be changed by
+    /// migrating.
+    pub min_deposit: Option<u64>,
+    /// The templates registered, in the order of their IDs.
+    pub templates: Vec<AccountTemplate>,
+    /// The withdrawals awaiting claim, in the order of their claim IDs.
+    pub withdrawals: Vec<PendingWithdrawal>,
+    /// The claim ID of the last withdrawal recorded, from which the new
+    /// version carries on so that claim IDs are never reused.
+    pub withdrawal_count: u64,
+    /// The storage fees the contract kept.
+    pub storage_fees: u64,
+    /// The account governing the contract-wide parameters, if any.
+    pub governance: Option<u64>,
+    /// The contract-wide parameters.
+    pub config: ContractConfig,
+}
+
+/// An account carried over from a previous version of the contract.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct MigratedAccount {
+    /// The ID of the account, kept across versions.
+    pub account_id: u64,
+    /// Keys used by the account.
+    pub keys: Vec<bls::PublicKey>,
+    /// Duress keys registered by the account.
+    pub duress_keys: Vec<bls::PublicKey>,
+    /// Co-signers registered by the account.
+    pub cosigners: Vec<CosignerKey>,
+    /// The receivers whitelisted by the account.
+    pub whitelist: Vec<WhitelistEntry>,
+    /// Guardians registered by the account.
+    pub guardians: Vec<bls::PublicKey>,
+    /// Observers registered by the account.
+    pub observers: Vec<bls::PublicKey>,
+    /// The draws of the account's children on the budgets it allocated them.
+    /// The amounts drawn within the current window aren't carried over, as
+    /// outflows aren't, but the amounts held are.
+    pub budget_draws: Vec<BudgetDraw>,
+    /// The last attestation of each key of the account, if it has a liveness
+    /// policy.
+    pub liveness: Vec<KeyLiveness>,
+    /// The delegations registered by the keys of the account.
+    pub delegations: Vec<KeyDelegation>,
+    /// The keys of the account added under a delay that hasn't yet passed.
+    pub pending_keys: Vec<PendingKey>,
+    /// The keys of the account that lapse, lapsed or not.
+    pub key_expiries: Vec<KeyExpiry>,
+    /// The keys of the account weighing other than one.
+    pub key_weights: Vec<KeyWeight>,
+    /// The keys of the account that aren't admins.
+    pub key_roles: Vec<RoleAssignment>,
+    /// The recovery of the account started by its guardians, if any.
+    pub pending_recovery: Option<PendingRecovery>,
+    /// The transfers queued by the account, in the order of their nonces.
+    pub queued_transfers: Vec<QueuedTransfer>,
+    /// The keys of the account with an allowance left.
+    pub allowances: Vec<KeyAllowance>,
+    /// The tiers of the account's transfers, in the order of their ceilings.
+    pub transfer_tiers: Vec<TransferTier>,
+    /// How many operations each key of the account signed, and when it last
+    /// did.
+    pub key_usage: Vec<KeyUsage>,
+    /// The checkpoints recorded for the account, in the order they were.
+    pub checkpoints: Vec<Checkpoint>,
+    /// The ledger of the account, in the order of its movements.
+    pub movements: Vec<Movement>,
+    /// The operations of the account, in the order of their nonces.
+    pub operations: Vec<OperationRecord>,
+    /// The balance, threshold, description and nonce of the account.
+    pub data: AccountData,
 }
 
 /// Used to deposit to a multisig account.
@@ -36,8 +308,15 @@ pub struct Deposit {
     pub amount: u64,
     /// Memo to include the in the deposit.
     pub memo: String,
+    /// Reference of the depositor, such as an invoice or customer ID, for
+    /// payment processors to reconcile their deposits by. At most
+    /// [`MAX_DEPOSITOR_REFERENCE_LEN`] bytes, and empty if there is none.
+    pub depositor_reference: String,
 }
 
+/// The longest depositor reference a deposit can carry, in bytes.
+pub const MAX_DEPOSITOR_REFERENCE_LEN: usize = 64;
+
 /// Used to transfer funds from an account to a Moonlight account.
 #[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
 #[archive_attr(derive(CheckBytes))]
@@ -48,17 +327,105 @@ pub struct Transfer {
     pub keys: Vec<bls::PublicKey>,
     /// The signature of the transfer.
     pub signature: bls::MultisigSignature,
+    /// Signatures of the transfer by co-signers of the account.
+    pub cosignatures: Vec<Cosignature>,
+    /// The signature of the transfer by the group key of the account, for
+    /// accounts signing with a threshold scheme.
+    pub group_signature: Option<bls::Signature>,
     /// The Moonlight account to transfer the amount to.
     pub receiver: bls::PublicKey,
-    /// The amount to transfer.
+    /// The amount to transfer, or [`TRANSFER_ALL`] to transfer the whole
+    /// balance of the account.
     pub amount: u64,
     /// The nonce used for the transfer.
     pub nonce: u64,
     /// Memo to include with the transfer.
     pub memo: String,
+    /// Fixed-size reference of the transfer, such as an invoice number, for
+    /// payments to be reconciled by without parsing the memo.
+    pub reference: Option<[u8; 32]>,
+    /// The spending category of the account the transfer is made under, if
+    /// declared, counting it against the category's budget.
+    pub category: Option<String>,
+    /// The reimbursement of whoever submits the transfer, if any.
+    pub gas_refund: Option<GasRefund>,
+    /// The chain the transfer is meant for, if declared.
+    pub chain_id: Option<u8>,
+}
+
+/// Amount transferring the whole balance of an account, whatever it is when
+/// the transfer is executed.
+///
+/// Signing for an exact amount fails the transfer if the balance drops below
+/// it before execution, and leaves behind anything deposited in the meantime.
+pub const TRANSFER_ALL: u64 = u64::MAX;
+
+/// The largest gas refund an operation can designate by default, in LUX.
+///
+/// Refunds are paid whatever the whitelist of the account, so the bound keeps
+/// them to the cost of broadcasting rather than a way around it. The
+/// governance account can set another with [`ContractConfig`].
+pub const MAX_GAS_REFUND: u64 = 1_000_000_000;
+
+/// A reimbursement of the submitter of an operation, for the gas spent
+/// broadcasting it, paid from the account's balance once the operation
+/// succeeds.
+///
+/// This separates who signs an operation from who pays to broadcast it: the
+/// signers agree on the allowance, and the submitter is made whole with it.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Archive, Serialize, Deserialize,
+)]
+#[archive_attr(derive(CheckBytes))]
+pub struct GasRefund {
+    /// The Moonlight account of the submitter, which is reimbursed.
+    pub submitter: bls::PublicKey,
+    /// The amount reimbursed, at most the largest gas refund of the
+    /// [`ContractConfig`].
+    pub amount: u64,
+}
+
+impl GasRefund {
+    const DOMAIN: &'static [u8] = b"multisig-refund";
+}
+
+/// The domain of the chain an operation declares, prefixing its signature
+/// message.
+const CHAIN_DOMAIN: &[u8] = b"multisig-chain";
+
+/// Prefixes the signature message of an operation with the chain it declares,
+/// if any, ahead of anything else - gas refund included.
+// NOTE: As with gas refunds, the message of an operation declaring no chain is
+//       left as it was.
+fn prefix_chain_id(chain_id: Option<u8>, msg: Vec<u8>) -> Vec<u8> {
+    let Some(chain_id) = chain_id else {
+        return msg;
+    };
+    let mut prefixed = Vec::from(CHAIN_DOMAIN);
+    prefixed.push(chain_id);
+    prefixed.extend(msg);
+    prefixed
+}
+
+/// Prefixes the signature message of an operation with the gas refund it
+/// designates, if any.
+// NOTE: The message of an operation without a refund is left as it was, so
+//       that signers unaware of refunds keep signing it as before.
+fn prefix_gas_refund(refund: Option<&GasRefund>, msg: Vec<u8>) -> Vec<u8> {
+    let Some(refund) = refund else {
+        return msg;
+    };
+    let mut prefixed = Vec::from(GasRefund::DOMAIN);
+    prefixed.extend(refund.submitter.to_raw_bytes());
+    prefixed.extend(refund.amount.to_le_bytes());
+    prefixed.extend(msg);
+    prefixed
 }
 
 impl Transfer {
+    const REFERENCE_DOMAIN: &'static [u8] = b"multisig-reference";
+    const CATEGORY_DOMAIN: &'static [u8] = b"multisig-category";
+
     /// Returns the message that should be signed to have a valid transfer.
     // NOTE: We purposefully don't include the keys used in the message to
     //       allow for the owner of each key to sign the message independently,
@@ -71,8 +438,91 @@ impl Transfer {
         msg[8..201].copy_from_slice(&self.receiver.to_raw_bytes());
         msg[201..209].copy_from_slice(&self.amount.to_le_bytes());
         msg[209..217].copy_from_slice(&self.nonce.to_le_bytes());
-        msg[217..].copy_from_slice(&self.memo.as_bytes());
-        msg
+        msg[217..].copy_from_slice(self.memo.as_bytes());
+
+        // As with the gas refund, the reference prefixes the message, so that
+        // it can't be mistaken for the end of the memo
+        if let Some(reference) = self.reference {
+            let mut prefixed = Vec::from(Self::REFERENCE_DOMAIN);
+            prefixed.extend(reference);
+            prefixed.extend(msg);
+            msg = prefixed;
+        }
+        if let Some(category) = &self.category {
+            let mut prefixed = Vec::from(Self::CATEGORY_DOMAIN);
+            prefixed.extend((category.len() as u32).to_le_bytes());
+            prefixed.extend(category.as_bytes());
+            prefixed.extend(msg);
+            msg = prefixed;
+        }
+
+        prefix_chain_id(
+            self.chain_id,
+            prefix_gas_refund(self.gas_refund.as_ref(), msg),
+        )
+    }
+}
+
+/// A single payment of a [`Payroll`].
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct PayrollRow {
+    /// The Moonlight account to pay.
+    pub receiver: bls::PublicKey,
+    /// The amount to pay.
+    pub amount: u64,
+    /// Memo to include with the payment.
+    pub memo: String,
+}
+
+/// Used to pay several Moonlight accounts from an account at once, on a single
+/// signature.
+///
+/// Either every row is paid or none is, and each row emits its own event.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct Payroll {
+    /// The ID of the account to pay from.
+    pub account_id: u64,
+    /// The keys used to sign the payroll.
+    pub keys: Vec<bls::PublicKey>,
+    /// The signature of the payroll.
+    pub signature: bls::MultisigSignature,
+    /// Signatures of the payroll by co-signers of the account.
+    pub cosignatures: Vec<Cosignature>,
+    /// The signature of the payroll by the group key of the account, for
+    /// accounts signing with a threshold scheme.
+    pub group_signature: Option<bls::Signature>,
+    /// The payments to make, in order.
+    pub rows: Vec<PayrollRow>,
+    /// The nonce used for the payroll.
+    pub nonce: u64,
+    /// The reimbursement of whoever submits the payroll, if any.
+    pub gas_refund: Option<GasRefund>,
+    /// The chain the payroll is meant for, if declared.
+    pub chain_id: Option<u8>,
+}
+
+impl Payroll {
+    const DOMAIN: &'static [u8] = b"multisig-payroll";
+
+    /// Returns the message that should be signed to have a valid payroll.
+    pub fn signature_msg(&self) -> Vec<u8> {
+        let mut msg = Vec::from(Self::DOMAIN);
+        msg.extend(self.account_id.to_le_bytes());
+        for row in &self.rows {
+            msg.extend(row.receiver.to_raw_bytes());
+            msg.extend(row.amount.to_le_bytes());
+            // Unlike a transfer's, the memo is followed by the next row, so
+            // it's prefixed with its length
+            msg.extend((row.memo.len() as u32).to_le_bytes());
+            msg.extend(row.memo.as_bytes());
+        }
+        msg.extend(self.nonce.to_le_bytes());
+        prefix_chain_id(
+            self.chain_id,
+            prefix_gas_refund(self.gas_refund.as_ref(), msg),
+        )
     }
 }
 
@@ -89,6 +539,161 @@ pub enum AccountChange {
     SetThreshold { threshold: u32 },
     /// Set the account's description.
     SetDescription { description: String },
+    /// Set whether the account's events carry hashes of memos and
+    /// receivers, instead of the values themselves.
+    SetPrivateEvents { private_events: bool },
+    /// Register a duress key, whose signature freezes the account instead of
+    /// effecting the operation.
+    AddDuressKey { key: bls::PublicKey },
+    /// Remove a duress key from an account.
+    RemoveDuressKey { key: bls::PublicKey },
+    /// Add a co-signer, using a signature scheme other than BLS.
+    AddCosigner { key: CosignerKey },
+    /// Remove a co-signer from an account.
+    RemoveCosigner { key: CosignerKey },
+    /// Set the group key of a threshold scheme run by the signers, which then
+    /// signs for the account alone - or remove it, with `None`.
+    SetGroupKey { key: Option<bls::PublicKey> },
+    /// Whitelist a receiver of transfers, which can only be transferred to
+    /// once [`WHITELIST_DELAY`] blocks have passed.
+    AddWhitelisted { receiver: bls::PublicKey },
+    /// Remove a receiver from the whitelist, with immediate effect.
+    RemoveWhitelisted { receiver: bls::PublicKey },
+    /// Set the soft limit on the account's outflow, past which a warning is
+    /// emitted - or remove it, with `None`.
+    SetVelocityLimit { limit: Option<VelocityLimit> },
+    /// Unlock the outgoing transfers of an account locked with a
+    /// [`LockAccount`].
+    Unlock,
+    /// Register a guardian, who can't sign for the account but can act on it
+    /// with a [`GuardAccount`].
+    AddGuardian { key: bls::PublicKey },
+    /// Remove a guardian from an account.
+    RemoveGuardian { key: bls::PublicKey },
+    /// Claim an alias for the account, releasing the one it had - or only
+    /// release it, with `None`.
+    SetAlias { alias: Option<String> },
+    /// Set the most the account may hold, past which deposits are refused -
+    /// or remove the cap, with `None`.
+    SetBalanceCap { cap: Option<u64> },
+    /// Tag the account, grouping it with the other accounts carrying the tag.
+    AddTag { tag: String },
+    /// Remove a tag from the account.
+    RemoveTag { tag: String },
+    /// Require the keys of the account to attest they're alive, with an
+    /// [`AttestLiveness`], every window of blocks - or drop the requirement,
+    /// with `None`.
+    SetLivenessPolicy { policy: Option<LivenessPolicy> },
+    /// Require a supermajority of the signers of the account to change its
+    /// threshold, its supermajority or its key addition policy - or drop the
+    /// requirement, with `None`.
+    SetSupermajority {
+        supermajority: Option<Supermajority>,
+    },
+    /// Raise the bar for adding keys to the account - or lower it back to the
+    /// threshold, with `None`.
+    SetKeyAdditionPolicy { policy: Option<KeyAdditionPolicy> },
+    /// Restrict the account's transfers to a recurring window of blocks - or
+    /// lift the restriction, with `None`.
+    SetTransferWindow { window: Option<TransferWindow> },
+    /// Add a spending category to the account, or replace the budget of the
+    /// category of the same name, counting its spending anew.
+    SetCategory { category: SpendingCategory },
+    /// Remove a spending category from the account.
+    RemoveCategory { name: String },
+    /// Allocate a budget to a child account, or replace the budget allocated
+    /// to it, counting its draws anew.
+    SetChildBudget { budget: ChildBudget },
+    /// Stop a child account from drawing on the account. What it drew and
+    /// didn't spend can still be clawed back.
+    RemoveChildBudget { child_id: u64 },
+    /// Move back to the account what a child account drew from it and still
+    /// holds.
+    ClawBackBudget { child_id: u64 },
+    /// Register an observer, who can't sign or act on the account but is
+    /// listed with it, for tooling to authenticate readers and attribute
+    /// events to.
+    AddObserver { key: bls::PublicKey },
+    /// Remove an observer from an account.
+    RemoveObserver { key: bls::PublicKey },
+    /// Set the height of the last block a key of the account can sign in,
+    /// or have it never lapse with `None`. Lapsed keys no longer count
+    /// towards the threshold, and are left for a change to remove.
+    SetKeyExpiry {
+        key: bls::PublicKey,
+        valid_until: Option<u64>,
+    },
+    /// Archive the account, so that it refuses deposits and moves no funds
+    /// until reactivated, while keeping its history.
+    Archive,
+    /// Reactivate an archived account.
+    Reactivate,
+    /// Set whether the account's transfers only record a withdrawal for their
+    /// receiver to claim with a [`ClaimWithdrawal`], instead of paying it at
+    /// once.
+    SetClaimableWithdrawals { claimable_withdrawals: bool },
+    /// Set the number of movements the account retains in its ledger, up to
+    /// [`MAX_HISTORY_RETENTION`], or fall back to
+    /// [`DEFAULT_HISTORY_RETENTION`] with `None`. Raising it charges the
+    /// storage fee of [`history_retention_fee`].
+    SetHistoryRetention { records: Option<u32> },
+    /// Designate a compliance key that can place the account on hold with a
+    /// [`PlaceHold`], restricting its transfers to a remediation address - or
+    /// drop it, with `None`. This needs the supermajority of the account, and
+    /// is refused while the account is on hold.
+    SetCompliance {
+        compliance: Option<ComplianceControl>,
+    },
+    /// Lift the hold placed on the account, signed for by its supermajority
+    /// along with its compliance key, whose signature of
+    /// [`PlaceHold::lift_msg`] is carried by the change.
+    LiftHold { signature: bls::MultisigSignature },
+    /// Require the receivers of the account's transfers - and, if the policy
+    /// says so, its keys - to hold a license attested by a license contract,
+    /// or drop the requirement, with `None`.
+    SetLicensePolicy { policy: Option<LicensePolicy> },
+    /// Set the number of keys needed to effect operations of a kind, in place
+    /// of the threshold of the account, or fall back to it with `None`. As
+    /// with the threshold, this needs the supermajority of the account, if it
+    /// has one.
+    SetOperationThreshold {
+        op: OperationKind,
+        threshold: Option<u32>,
+    },
+    /// Replace the tiers of the account's transfers, letting transfers of up
+    /// to an amount be signed by fewer keys, or drop them all with an empty
+    /// list. As with the threshold, this needs the supermajority of the
+    /// account, if it has one.
+    SetTransferTiers { tiers: Vec<TransferTier> },
+    /// Set the weight of a key of the account, the number of signers it
+    /// counts for towards the thresholds. Keys weigh one unless given
+    /// another weight. As with the threshold, this needs the supermajority
+    /// of the account, if it has one.
+    SetKeyWeight { key: bls::PublicKey, weight: u32 },
+    /// Set the role of a key of the account, limiting the operations it can
+    /// sign. Keys are admins unless given another role, and the admins must
+    /// be able to meet the threshold for changes between them.
+    SetKeyRole { key: bls::PublicKey, role: KeyRole },
+    /// Let the guardians of the account recover it, replacing its keys - or
+    /// take the ability away, with `None`. As with the threshold, this needs
+    /// the supermajority of the account, if it has one.
+    SetRecoveryPolicy { policy: Option<RecoveryPolicy> },
+    /// Designate a fallback key that can claim the account once it performs
+    /// no operation for a number of blocks, sweeping its funds or replacing
+    /// its keys - or drop it, with `None`. As with the threshold, this needs
+    /// the supermajority of the account, if it has one.
+    SetInactivityPolicy { policy: Option<InactivityPolicy> },
+    /// Hold back the large transfers of the account for a number of blocks,
+    /// during which any of its keys can cancel them - or stop holding them
+    /// back, with `None`. As with the threshold, this needs the supermajority
+    /// of the account, if it has one.
+    SetQueuePolicy { policy: Option<QueuePolicy> },
+    /// Allow a key of the account to transfer up to an amount from it on its
+    /// own signature, with `transfer_with_allowance` - or take the allowance
+    /// away, with zero. The amount replaces whatever was left of the key's
+    /// previous allowance. As with the threshold, this needs the
+    /// supermajority of the account, if it has one.
+    SetAllowance { key: bls::PublicKey, amount: u64 },
 }
 
 /// Used to perform changes to an account.
@@ -101,10 +706,19 @@ pub struct ChangeAccount {
     pub keys: Vec<bls::PublicKey>,
     /// The signature of the change.
     pub signature: bls::MultisigSignature,
+    /// Signatures of the change by co-signers of the account.
+    pub cosignatures: Vec<Cosignature>,
+    /// The signature of the change by the group key of the account, for
+    /// accounts signing with a threshold scheme.
+    pub group_signature: Option<bls::Signature>,
     /// List of changes to apply to the account.
     pub changes: Vec<AccountChange>,
     /// The nonce used for the change.
     pub nonce: u64,
+    /// The reimbursement of whoever submits the change, if any.
+    pub gas_refund: Option<GasRefund>,
+    /// The chain the change is meant for, if declared.
+    pub chain_id: Option<u8>,
 }
 
 impl ChangeAccount {
@@ -112,6 +726,49 @@ impl ChangeAccount {
     const REMOVE_KEY_TAG: u8 = 1;
     const SET_THRESHOLD_TAG: u8 = 2;
     const SET_DESCRIPTION_TAG: u8 = 3;
+    const SET_PRIVATE_EVENTS_TAG: u8 = 4;
+    const ADD_DURESS_KEY_TAG: u8 = 5;
+    const REMOVE_DURESS_KEY_TAG: u8 = 6;
+    const ADD_COSIGNER_TAG: u8 = 7;
+    const REMOVE_COSIGNER_TAG: u8 = 8;
+    const SET_GROUP_KEY_TAG: u8 = 9;
+    const ADD_WHITELISTED_TAG: u8 = 10;
+    const REMOVE_WHITELISTED_TAG: u8 = 11;
+    const SET_VELOCITY_LIMIT_TAG: u8 = 12;
+    const UNLOCK_TAG: u8 = 13;
+    const ADD_GUARDIAN_TAG: u8 = 14;
+    const REMOVE_GUARDIAN_TAG: u8 = 15;
+    const SET_ALIAS_TAG: u8 = 16;
+    const SET_BALANCE_CAP_TAG: u8 = 17;
+    const ADD_TAG_TAG: u8 = 18;
+    const REMOVE_TAG_TAG: u8 = 19;
+    const SET_LIVENESS_POLICY_TAG: u8 = 20;
+    const SET_SUPERMAJORITY_TAG: u8 = 21;
+    const SET_KEY_ADDITION_POLICY_TAG: u8 = 22;
+    const SET_TRANSFER_WINDOW_TAG: u8 = 23;
+    const SET_CATEGORY_TAG: u8 = 24;
+    const REMOVE_CATEGORY_TAG: u8 = 25;
+    const ADD_OBSERVER_TAG: u8 = 26;
+    const REMOVE_OBSERVER_TAG: u8 = 27;
+    const SET_CHILD_BUDGET_TAG: u8 = 28;
+    const REMOVE_CHILD_BUDGET_TAG: u8 = 29;
+    const CLAW_BACK_BUDGET_TAG: u8 = 30;
+    const SET_KEY_EXPIRY_TAG: u8 = 31;
+    const ARCHIVE_TAG: u8 = 32;
+    const REACTIVATE_TAG: u8 = 33;
+    const SET_CLAIMABLE_WITHDRAWALS_TAG: u8 = 34;
+    const SET_HISTORY_RETENTION_TAG: u8 = 35;
+    const SET_COMPLIANCE_TAG: u8 = 36;
+    const LIFT_HOLD_TAG: u8 = 37;
+    const SET_LICENSE_POLICY_TAG: u8 = 38;
+    const SET_OPERATION_THRESHOLD_TAG: u8 = 39;
+    const SET_TRANSFER_TIERS_TAG: u8 = 40;
+    const SET_KEY_WEIGHT_TAG: u8 = 41;
+    const SET_KEY_ROLE_TAG: u8 = 42;
+    const SET_RECOVERY_POLICY_TAG: u8 = 43;
+    const SET_INACTIVITY_POLICY_TAG: u8 = 44;
+    const SET_QUEUE_POLICY_TAG: u8 = 45;
+    const SET_ALLOWANCE_TAG: u8 = 46;
 
     /// Returns the message that should be signed to have a valid change.
     // NOTE: We purposefully don't include the keys used in the message to
@@ -120,140 +777,4689 @@ impl ChangeAccount {
     //       If we did include the keys, the signers would have to agree on the
     //       set of keys to be used prior to signing.
     pub fn signature_msg(&self) -> Vec<u8> {
-        let mut msg = vec![
-            0;
-            8 + self
-                .changes
-                .iter()
-                .map(|change| {
-                    1 + match change {
-                        AccountChange::AddKey { .. } => 193,
-                        AccountChange::RemoveKey { .. } => 193,
-                        AccountChange::SetThreshold { .. } => 4,
-                        AccountChange::SetDescription { description } => {
-                            description.len()
-                        }
-                    }
-                })
-                .sum::<usize>()
-                + 8
-        ];
+        let mut msg = Vec::new();
+
+        msg.extend(self.account_id.to_le_bytes());
 
-        let mut offset = 0;
-        msg[offset..offset + 8].copy_from_slice(&self.account_id.to_le_bytes());
-        offset += 8;
+        Self::extend_changes_msg(&mut msg, &self.changes);
 
-        for change in &self.changes {
+        msg.extend(self.nonce.to_le_bytes());
+
+        prefix_chain_id(
+            self.chain_id,
+            prefix_gas_refund(self.gas_refund.as_ref(), msg),
+        )
+    }
+
+    /// Appends the part of the signature message describing the given changes
+    /// to the given message.
+    fn extend_changes_msg(msg: &mut Vec<u8>, changes: &[AccountChange]) {
+        for change in changes {
             match change {
                 AccountChange::AddKey { key } => {
-                    msg[offset] = Self::ADD_KEY_TAG;
-                    offset += 1;
-
-                    msg[offset..offset + 193]
-                        .copy_from_slice(&key.to_raw_bytes());
-                    offset += 193;
+                    msg.push(Self::ADD_KEY_TAG);
+                    msg.extend(key.to_raw_bytes());
                 }
                 AccountChange::RemoveKey { key } => {
-                    msg[offset] = Self::REMOVE_KEY_TAG;
-                    offset += 1;
-
-                    msg[offset..offset + 193]
-                        .copy_from_slice(&key.to_raw_bytes());
-                    offset += 193;
+                    msg.push(Self::REMOVE_KEY_TAG);
+                    msg.extend(key.to_raw_bytes());
                 }
                 AccountChange::SetThreshold { threshold } => {
-                    msg[offset] = Self::SET_THRESHOLD_TAG;
-                    offset += 1;
-
-                    msg[offset..offset + 4]
-                        .copy_from_slice(&threshold.to_le_bytes());
-                    offset += 4;
+                    msg.push(Self::SET_THRESHOLD_TAG);
+                    msg.extend(threshold.to_le_bytes());
                 }
                 AccountChange::SetDescription { description } => {
-                    msg[offset] = Self::SET_DESCRIPTION_TAG;
-                    offset += 1;
-
-                    msg[offset..offset + description.len()]
-                        .copy_from_slice(&description.as_bytes());
-                    offset += description.len();
+                    msg.push(Self::SET_DESCRIPTION_TAG);
+                    // The description is prefixed with its length, otherwise
+                    // its bytes could be mistaken for subsequent changes.
+                    msg.extend((description.len() as u32).to_le_bytes());
+                    msg.extend(description.as_bytes());
+                }
+                AccountChange::SetPrivateEvents { private_events } => {
+                    msg.push(Self::SET_PRIVATE_EVENTS_TAG);
+                    msg.push(*private_events as u8);
+                }
+                AccountChange::AddDuressKey { key } => {
+                    msg.push(Self::ADD_DURESS_KEY_TAG);
+                    msg.extend(key.to_raw_bytes());
+                }
+                AccountChange::RemoveDuressKey { key } => {
+                    msg.push(Self::REMOVE_DURESS_KEY_TAG);
+                    msg.extend(key.to_raw_bytes());
+                }
+                // The key is tagged with its scheme, which determines its
+                // length
+                AccountChange::AddCosigner { key } => {
+                    msg.push(Self::ADD_COSIGNER_TAG);
+                    msg.extend(key.to_bytes());
+                }
+                AccountChange::RemoveCosigner { key } => {
+                    msg.push(Self::REMOVE_COSIGNER_TAG);
+                    msg.extend(key.to_bytes());
+                }
+                AccountChange::SetGroupKey { key } => {
+                    msg.push(Self::SET_GROUP_KEY_TAG);
+                    match key {
+                        Some(key) => {
+                            msg.push(1);
+                            msg.extend(key.to_raw_bytes());
+                        }
+                        None => msg.push(0),
+                    }
+                }
+                AccountChange::AddWhitelisted { receiver } => {
+                    msg.push(Self::ADD_WHITELISTED_TAG);
+                    msg.extend(receiver.to_raw_bytes());
+                }
+                AccountChange::RemoveWhitelisted { receiver } => {
+                    msg.push(Self::REMOVE_WHITELISTED_TAG);
+                    msg.extend(receiver.to_raw_bytes());
+                }
+                AccountChange::SetVelocityLimit { limit } => {
+                    msg.push(Self::SET_VELOCITY_LIMIT_TAG);
+                    match limit {
+                        Some(limit) => {
+                            msg.push(1);
+                            msg.extend(limit.to_bytes());
+                        }
+                        None => msg.push(0),
+                    }
+                }
+                AccountChange::Unlock => msg.push(Self::UNLOCK_TAG),
+                AccountChange::AddGuardian { key } => {
+                    msg.push(Self::ADD_GUARDIAN_TAG);
+                    msg.extend(key.to_raw_bytes());
+                }
+                AccountChange::RemoveGuardian { key } => {
+                    msg.push(Self::REMOVE_GUARDIAN_TAG);
+                    msg.extend(key.to_raw_bytes());
+                }
+                // As with the description, the alias is prefixed with its
+                // length
+                AccountChange::SetAlias { alias } => {
+                    msg.push(Self::SET_ALIAS_TAG);
+                    match alias {
+                        Some(alias) => {
+                            msg.push(1);
+                            msg.extend((alias.len() as u32).to_le_bytes());
+                            msg.extend(alias.as_bytes());
+                        }
+                        None => msg.push(0),
+                    }
+                }
+                AccountChange::SetBalanceCap { cap } => {
+                    msg.push(Self::SET_BALANCE_CAP_TAG);
+                    match cap {
+                        Some(cap) => {
+                            msg.push(1);
+                            msg.extend(cap.to_le_bytes());
+                        }
+                        None => msg.push(0),
+                    }
+                }
+                AccountChange::AddTag { tag } => {
+                    msg.push(Self::ADD_TAG_TAG);
+                    msg.extend((tag.len() as u32).to_le_bytes());
+                    msg.extend(tag.as_bytes());
+                }
+                AccountChange::RemoveTag { tag } => {
+                    msg.push(Self::REMOVE_TAG_TAG);
+                    msg.extend((tag.len() as u32).to_le_bytes());
+                    msg.extend(tag.as_bytes());
+                }
+                AccountChange::SetLivenessPolicy { policy } => {
+                    msg.push(Self::SET_LIVENESS_POLICY_TAG);
+                    match policy {
+                        Some(policy) => {
+                            msg.push(1);
+                            msg.extend(policy.to_bytes());
+                        }
+                        None => msg.push(0),
+                    }
+                }
+                AccountChange::SetSupermajority { supermajority } => {
+                    msg.push(Self::SET_SUPERMAJORITY_TAG);
+                    match supermajority {
+                        Some(supermajority) => {
+                            msg.push(1);
+                            msg.extend(supermajority.to_bytes());
+                        }
+                        None => msg.push(0),
+                    }
+                }
+                AccountChange::SetKeyAdditionPolicy { policy } => {
+                    msg.push(Self::SET_KEY_ADDITION_POLICY_TAG);
+                    match policy {
+                        Some(policy) => {
+                            msg.push(1);
+                            msg.extend(policy.to_bytes());
+                        }
+                        None => msg.push(0),
+                    }
+                }
+                AccountChange::SetTransferWindow { window } => {
+                    msg.push(Self::SET_TRANSFER_WINDOW_TAG);
+                    match window {
+                        Some(window) => {
+                            msg.push(1);
+                            msg.extend(window.to_bytes());
+                        }
+                        None => msg.push(0),
+                    }
+                }
+                // The name is prefixed with its length, followed by the budget
+                AccountChange::SetCategory { category } => {
+                    msg.push(Self::SET_CATEGORY_TAG);
+                    msg.extend((category.name.len() as u32).to_le_bytes());
+                    msg.extend(category.name.as_bytes());
+                    msg.extend(category.budget_bytes());
+                }
+                AccountChange::RemoveCategory { name } => {
+                    msg.push(Self::REMOVE_CATEGORY_TAG);
+                    msg.extend((name.len() as u32).to_le_bytes());
+                    msg.extend(name.as_bytes());
+                }
+                AccountChange::AddObserver { key } => {
+                    msg.push(Self::ADD_OBSERVER_TAG);
+                    msg.extend(key.to_raw_bytes());
+                }
+                AccountChange::RemoveObserver { key } => {
+                    msg.push(Self::REMOVE_OBSERVER_TAG);
+                    msg.extend(key.to_raw_bytes());
+                }
+                AccountChange::SetChildBudget { budget } => {
+                    msg.push(Self::SET_CHILD_BUDGET_TAG);
+                    msg.extend(budget.to_bytes());
+                }
+                AccountChange::RemoveChildBudget { child_id } => {
+                    msg.push(Self::REMOVE_CHILD_BUDGET_TAG);
+                    msg.extend(child_id.to_le_bytes());
+                }
+                AccountChange::ClawBackBudget { child_id } => {
+                    msg.push(Self::CLAW_BACK_BUDGET_TAG);
+                    msg.extend(child_id.to_le_bytes());
+                }
+                // The flag comes ahead of the key, so that the payload can
+                // carry the key and height as a single value
+                AccountChange::SetKeyExpiry { key, valid_until } => {
+                    msg.push(Self::SET_KEY_EXPIRY_TAG);
+                    msg.push(valid_until.is_some() as u8);
+                    msg.extend(key.to_raw_bytes());
+                    if let Some(valid_until) = valid_until {
+                        msg.extend(valid_until.to_le_bytes());
+                    }
+                }
+                AccountChange::Archive => msg.push(Self::ARCHIVE_TAG),
+                AccountChange::Reactivate => msg.push(Self::REACTIVATE_TAG),
+                AccountChange::SetClaimableWithdrawals {
+                    claimable_withdrawals,
+                } => {
+                    msg.push(Self::SET_CLAIMABLE_WITHDRAWALS_TAG);
+                    msg.push(*claimable_withdrawals as u8);
+                }
+                AccountChange::SetHistoryRetention { records } => {
+                    msg.push(Self::SET_HISTORY_RETENTION_TAG);
+                    match records {
+                        Some(records) => {
+                            msg.push(1);
+                            msg.extend(records.to_le_bytes());
+                        }
+                        None => msg.push(0),
+                    }
+                }
+                AccountChange::SetCompliance { compliance } => {
+                    msg.push(Self::SET_COMPLIANCE_TAG);
+                    match compliance {
+                        Some(compliance) => {
+                            msg.push(1);
+                            msg.extend(compliance.to_bytes());
+                        }
+                        None => msg.push(0),
+                    }
+                }
+                // The signature of the compliance key commits to the account
+                // and nonce on its own, so it's left out
+                AccountChange::LiftHold { .. } => msg.push(Self::LIFT_HOLD_TAG),
+                AccountChange::SetLicensePolicy { policy } => {
+                    msg.push(Self::SET_LICENSE_POLICY_TAG);
+                    match policy {
+                        Some(policy) => {
+                            msg.push(1);
+                            msg.extend(policy.to_bytes());
+                        }
+                        None => msg.push(0),
+                    }
+                }
+                AccountChange::SetOperationThreshold { op, threshold } => {
+                    msg.push(Self::SET_OPERATION_THRESHOLD_TAG);
+                    msg.push(*op as u8);
+                    match threshold {
+                        Some(threshold) => {
+                            msg.push(1);
+                            msg.extend(threshold.to_le_bytes());
+                        }
+                        None => msg.push(0),
+                    }
+                }
+                AccountChange::SetTransferTiers { tiers } => {
+                    msg.push(Self::SET_TRANSFER_TIERS_TAG);
+                    msg.extend((tiers.len() as u32).to_le_bytes());
+                    for tier in tiers {
+                        msg.extend(tier.ceiling.to_le_bytes());
+                        msg.extend(tier.threshold.to_le_bytes());
+                    }
+                }
+                AccountChange::SetKeyWeight { key, weight } => {
+                    msg.push(Self::SET_KEY_WEIGHT_TAG);
+                    msg.extend(key.to_raw_bytes());
+                    msg.extend(weight.to_le_bytes());
+                }
+                AccountChange::SetKeyRole { key, role } => {
+                    msg.push(Self::SET_KEY_ROLE_TAG);
+                    msg.extend(key.to_raw_bytes());
+                    msg.push(*role as u8);
+                }
+                AccountChange::SetRecoveryPolicy { policy } => {
+                    msg.push(Self::SET_RECOVERY_POLICY_TAG);
+                    match policy {
+                        Some(policy) => {
+                            msg.push(1);
+                            msg.extend(policy.to_bytes());
+                        }
+                        None => msg.push(0),
+                    }
+                }
+                AccountChange::SetInactivityPolicy { policy } => {
+                    msg.push(Self::SET_INACTIVITY_POLICY_TAG);
+                    match policy {
+                        Some(policy) => {
+                            msg.push(1);
+                            msg.extend(policy.to_bytes());
+                        }
+                        None => msg.push(0),
+                    }
+                }
+                AccountChange::SetQueuePolicy { policy } => {
+                    msg.push(Self::SET_QUEUE_POLICY_TAG);
+                    match policy {
+                        Some(policy) => {
+                            msg.push(1);
+                            msg.extend(policy.to_bytes());
+                        }
+                        None => msg.push(0),
+                    }
+                }
+                AccountChange::SetAllowance { key, amount } => {
+                    msg.push(Self::SET_ALLOWANCE_TAG);
+                    msg.extend(key.to_raw_bytes());
+                    msg.extend(amount.to_le_bytes());
                 }
             }
         }
-
-        msg[offset..offset + 8].copy_from_slice(&self.nonce.to_le_bytes());
-        // offset += 8;
-
-        msg
     }
 }
 
-/// The data about a given account.
+/// Used to transfer funds from an account and change it at once, under a
+/// single signature and nonce.
+///
+/// This is meant for transfers and changes that are unsafe apart, such as
+/// paying out a departing member and removing their key: either both happen or
+/// neither does.
 #[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
 #[archive_attr(derive(CheckBytes))]
-pub struct AccountData {
-    /// The balance the account holds.
-    pub balance: u64,
-    /// Number of keys that need to sign to effect an operation.
-    pub threshold: u32,
-    /// Description of the account.
-    pub description: String,
-    /// The current nonce of the account.
+pub struct TransferAndChange {
+    /// The ID of the account to transfer from and change.
+    pub account_id: u64,
+    /// The keys used to sign the operation.
+    pub keys: Vec<bls::PublicKey>,
+    /// The signature of the operation.
+    pub signature: bls::MultisigSignature,
+    /// Signatures of the operation by co-signers of the account.
+    pub cosignatures: Vec<Cosignature>,
+    /// The signature of the operation by the group key of the account, for
+    /// accounts signing with a threshold scheme.
+    pub group_signature: Option<bls::Signature>,
+    /// The Moonlight account to transfer the amount to.
+    pub receiver: bls::PublicKey,
+    /// The amount to transfer, or [`TRANSFER_ALL`] to transfer the whole
+    /// balance of the account.
+    pub amount: u64,
+    /// Memo to include with the transfer.
+    pub memo: String,
+    /// The changes to make to the account, once the transfer is made.
+    pub changes: Vec<AccountChange>,
+    /// The nonce used for the operation.
     pub nonce: u64,
+    /// The reimbursement of whoever submits the operation, if any.
+    pub gas_refund: Option<GasRefund>,
+    /// The chain the operation is meant for, if declared.
+    pub chain_id: Option<u8>,
 }
 
-/// Event emitted upon a successful account creation.
-#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
-pub struct CreateAccountEvent {
-    /// The ID of the account created.
-    pub account_id: u64,
-    /// Keys used by the account.
-    pub keys: Vec<bls::PublicKey>,
-    /// Number of keys that need to sign to effect an operation.
-    pub threshold: u32,
-    /// The description of the account.
-    pub description: String,
+impl TransferAndChange {
+    const DOMAIN: &'static [u8] = b"multisig-transfer-change";
+
+    /// Returns the message that should be signed to have a valid transfer and
+    /// change.
+    ///
+    /// The changes are described as in the message of a [`ChangeAccount`].
+    pub fn signature_msg(&self) -> Vec<u8> {
+        let mut msg = Vec::from(Self::DOMAIN);
+        msg.extend(self.account_id.to_le_bytes());
+        msg.extend(self.receiver.to_raw_bytes());
+        msg.extend(self.amount.to_le_bytes());
+        // The memo is followed by the changes, so it's prefixed with its
+        // length
+        msg.extend((self.memo.len() as u32).to_le_bytes());
+        msg.extend(self.memo.as_bytes());
+        ChangeAccount::extend_changes_msg(&mut msg, &self.changes);
+        msg.extend(self.nonce.to_le_bytes());
+        prefix_chain_id(
+            self.chain_id,
+            prefix_gas_refund(self.gas_refund.as_ref(), msg),
+        )
+    }
 }
 
-/// Event emitted upon a successful deposit.
+/// Used by the compliance key of an account to place it on hold, restricting
+/// its transfers to its remediation address. Lifting the hold takes an
+/// [`AccountChange::LiftHold`] signed by the supermajority of the account
+/// along with the compliance key.
 #[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
 #[archive_attr(derive(CheckBytes))]
-pub struct DepositEvent {
-    /// The account deposited to.
+pub struct PlaceHold {
+    /// The account to place on hold.
     pub account_id: u64,
-    /// Amount deposited.
-    pub amount: u64,
-    /// Memo included with the deposit.
-    pub memo: String,
+    /// The signature of the compliance key of the account.
+    pub signature: bls::MultisigSignature,
 }
 
-/// Event emitted upon a successful transfer.
-#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
-#[archive_attr(derive(CheckBytes))]
-pub struct TransferEvent {
-    /// The account that transferred.
-    pub account_id: u64,
-    /// Keys used to sign the transfer.
-    pub keys: Vec<bls::PublicKey>,
-    /// The receiver of the funds.
-    pub receiver: bls::PublicKey,
-    /// Amount transferred.
-    pub amount: u64,
-    /// Memo included with the transfer.
-    pub memo: String,
+impl PlaceHold {
+    const DOMAIN: &'static [u8] = b"multisig-hold";
+
+    const PLACE_TAG: u8 = 0;
+    const LIFT_TAG: u8 = 1;
+
+    /// Places the account with the given ID and current nonce on hold, with
+    /// the given compliance secret key.
+    pub fn new(sk: &bls::SecretKey, account_id: u64, nonce: u64) -> Self {
+        let key = bls::PublicKey::from(sk);
+        let msg = Self::signature_msg(account_id, nonce);
+        let signature = sk.sign_multisig(&key, &msg);
+        Self {
+            account_id,
+            signature,
+        }
+    }
+
+    /// Returns the message the compliance key must sign to place the account
+    /// with the given ID and current nonce on hold.
+    // NOTE: As with `LockAccount`, the nonce keeps a hold from being replayed
+    //       once lifted, since lifting bumps it.
+    pub fn signature_msg(account_id: u64, nonce: u64) -> Vec<u8> {
+        let mut msg = Vec::from(Self::DOMAIN);
+        msg.push(Self::PLACE_TAG);
+        msg.extend(account_id.to_le_bytes());
+        msg.extend(nonce.to_le_bytes());
+        msg
+    }
+
+    /// Returns the message the compliance key must sign to lift the hold on
+    /// the account with the given ID, by the change with the given nonce.
+    pub fn lift_msg(account_id: u64, nonce: u64) -> Vec<u8> {
+        let mut msg = Vec::from(Self::DOMAIN);
+        msg.push(Self::LIFT_TAG);
+        msg.extend(account_id.to_le_bytes());
+        msg.extend(nonce.to_le_bytes());
+        msg
+    }
+
+    /// Signs the lifting of the hold on the account with the given ID, by the
+    /// change with the given nonce, with the given compliance secret key.
+    pub fn sign_lift(
+        sk: &bls::SecretKey,
+        account_id: u64,
+        nonce: u64,
+    ) -> bls::MultisigSignature {
+        let key = bls::PublicKey::from(sk);
+        sk.sign_multisig(&key, &Self::lift_msg(account_id, nonce))
+    }
 }
 
-/// Event emitted upon a successful account change.
+/// Used by any single key of an account to lock its outgoing transfers at
+/// once, in an emergency. Unlocking takes an [`AccountChange::Unlock`] signed
+/// as any other change.
 #[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
 #[archive_attr(derive(CheckBytes))]
-pub struct ChangeAccountEvent {
-    /// The account that changed.
+pub struct LockAccount {
+    /// The account to lock.
     pub account_id: u64,
-    /// Keys added during the change.
-    pub added_keys: Vec<bls::PublicKey>,
-    /// Keys removed during the change.
-    pub removed_keys: Vec<bls::PublicKey>,
-    /// New threshold if changed.
+    /// The key locking the account.
+    pub key: bls::PublicKey,
+    /// The signature of the key.
+    pub signature: bls::MultisigSignature,
+}
+
+impl LockAccount {
+    const DOMAIN: &'static [u8] = b"multisig-lock";
+
+    /// Locks the account with the given ID and current nonce, with the given
+    /// secret key.
+    pub fn new(sk: &bls::SecretKey, account_id: u64, nonce: u64) -> Self {
+        let key = bls::PublicKey::from(sk);
+        let msg = Self::signature_msg(account_id, nonce);
+        let signature = sk.sign_multisig(&key, &msg);
+        Self {
+            account_id,
+            key,
+            signature,
+        }
+    }
+
+    /// Returns the message a key must sign to lock the account with the given
+    /// ID and current nonce.
+    // NOTE: The nonce is included so that a lock can't be replayed once the
+    //       account is unlocked, since unlocking bumps it. As with
+    //       `MarkCompromised`, the domain keeps operation signatures from
+    //       being replayed as locks.
+    pub fn signature_msg(account_id: u64, nonce: u64) -> Vec<u8> {
+        let mut msg = Vec::from(Self::DOMAIN);
+        msg.extend(account_id.to_le_bytes());
+        msg.extend(nonce.to_le_bytes());
+        msg
+    }
+}
+
+/// Used by a key of an account with a liveness policy to attest it's still
+/// held, keeping it counted among the signers of the account.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct AttestLiveness {
+    /// The account the key belongs to.
+    pub account_id: u64,
+    /// The key attesting.
+    pub key: bls::PublicKey,
+    /// Height of the block the key attests as of, which can't be ahead of the
+    /// block the attestation is made in.
+    pub block_height: u64,
+    /// The signature of the key.
+    pub signature: bls::MultisigSignature,
+}
+
+impl AttestLiveness {
+    const DOMAIN: &'static [u8] = b"multisig-liveness";
+
+    /// Attests the key of the given secret key is alive, as of the given block
+    /// height.
+    pub fn new(
+        sk: &bls::SecretKey,
+        account_id: u64,
+        block_height: u64,
+    ) -> Self {
+        let key = bls::PublicKey::from(sk);
+        let msg = Self::signature_msg(account_id, block_height);
+        let signature = sk.sign_multisig(&key, &msg);
+        Self {
+            account_id,
+            key,
+            block_height,
+            signature,
+        }
+    }
+
+    /// Returns the message a key must sign to attest it's alive as of the
+    /// given block height.
+    // NOTE: The height is included so that an attestation can only ever be
+    //       replayed to vouch for the key as of that height, never later.
+    pub fn signature_msg(account_id: u64, block_height: u64) -> Vec<u8> {
+        let mut msg = Vec::from(Self::DOMAIN);
+        msg.extend(account_id.to_le_bytes());
+        msg.extend(block_height.to_le_bytes());
+        msg
+    }
+}
+
+/// Used by a key of an account to delegate its signing power to another key
+/// for a range of blocks, or to revoke its delegation.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct DelegateKey {
+    /// The account the key belongs to.
+    pub account_id: u64,
+    /// The key delegating.
+    pub key: bls::PublicKey,
+    /// The delegation to register, replacing the key's current one, or `None`
+    /// to revoke it.
+    pub delegation: Option<Delegation>,
+    /// The next delegation nonce of the key in the account.
+    pub nonce: u64,
+    /// The signature of the key.
+    pub signature: bls::MultisigSignature,
+}
+
+impl DelegateKey {
+    const DOMAIN: &'static [u8] = b"multisig-delegation";
+
+    /// Registers, or revokes, the delegation of the key of the given secret
+    /// key.
+    pub fn new(
+        sk: &bls::SecretKey,
+        account_id: u64,
+        delegation: Option<Delegation>,
+        nonce: u64,
+    ) -> Self {
+        let key = bls::PublicKey::from(sk);
+        let msg = Self::signature_msg(account_id, delegation.as_ref(), nonce);
+        let signature = sk.sign_multisig(&key, &msg);
+        Self {
+            account_id,
+            key,
+            delegation,
+            nonce,
+            signature,
+        }
+    }
+
+    /// Returns the message a key must sign to register the given delegation,
+    /// or to revoke its delegation if `None`.
+    // NOTE: Each key has its own nonce, rather than using the account's, so
+    //       that a revoked delegation can't be replayed before the account is
+    //       next operated on.
+    pub fn signature_msg(
+        account_id: u64,
+        delegation: Option<&Delegation>,
+        nonce: u64,
+    ) -> Vec<u8> {
+        let mut msg = Vec::from(Self::DOMAIN);
+        msg.extend(account_id.to_le_bytes());
+        match delegation {
+            Some(delegation) => {
+                msg.push(1);
+                msg.extend(delegation.delegate.to_raw_bytes());
+                msg.extend(delegation.from_height.to_le_bytes());
+                msg.extend(delegation.until_height.to_le_bytes());
+            }
+            None => msg.push(0),
+        }
+        msg.extend(nonce.to_le_bytes());
+        msg
+    }
+}
+
+/// An action a guardian can take on an account, none of which can move its
+/// funds.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+#[allow(missing_docs)]
+pub enum GuardianAction {
+    /// Lock the outgoing transfers of the account, as any of its keys can
+    /// with a [`LockAccount`].
+    Lock,
+    /// Push back the height a pending whitelisted receiver can be transferred
+    /// to from.
+    ExtendWhitelistDelay {
+        receiver: bls::PublicKey,
+        usable_from: u64,
+    },
+    /// Remove a pending whitelisted receiver, before it can be transferred
+    /// to.
+    VetoWhitelisted { receiver: bls::PublicKey },
+}
+
+/// Used by a single guardian of an account to act on it.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct GuardAccount {
+    /// The account to act on.
+    pub account_id: u64,
+    /// The guardian acting.
+    pub key: bls::PublicKey,
+    /// The action taken.
+    pub action: GuardianAction,
+    /// The signature of the guardian.
+    pub signature: bls::MultisigSignature,
+}
+
+impl GuardAccount {
+    const DOMAIN: &'static [u8] = b"multisig-guardian";
+
+    const LOCK_TAG: u8 = 0;
+    const EXTEND_WHITELIST_DELAY_TAG: u8 = 1;
+    const VETO_WHITELISTED_TAG: u8 = 2;
+
+    /// Takes the given action on the account with the given ID and current
+    /// nonce, with the given secret key.
+    pub fn new(
+        sk: &bls::SecretKey,
+        account_id: u64,
+        action: GuardianAction,
+        nonce: u64,
+    ) -> Self {
+        let key = bls::PublicKey::from(sk);
+        let msg = Self::signature_msg(account_id, &action, nonce);
+        let signature = sk.sign_multisig(&key, &msg);
+        Self {
+            account_id,
+            key,
+            action,
+            signature,
+        }
+    }
+
+    /// Returns the message a guardian must sign to take the given action on
+    /// the account with the given ID and current nonce.
+    // NOTE: As with `LockAccount`, the nonce keeps an action from being
+    //       replayed once the account has moved on.
+    pub fn signature_msg(
+        account_id: u64,
+        action: &GuardianAction,
+        nonce: u64,
+    ) -> Vec<u8> {
+        let mut msg = Vec::from(Self::DOMAIN);
+        msg.extend(account_id.to_le_bytes());
+        match action {
+            GuardianAction::Lock => msg.push(Self::LOCK_TAG),
+            GuardianAction::ExtendWhitelistDelay {
+                receiver,
+                usable_from,
+            } => {
+                msg.push(Self::EXTEND_WHITELIST_DELAY_TAG);
+                msg.extend(receiver.to_raw_bytes());
+                msg.extend(usable_from.to_le_bytes());
+            }
+            GuardianAction::VetoWhitelisted { receiver } => {
+                msg.push(Self::VETO_WHITELISTED_TAG);
+                msg.extend(receiver.to_raw_bytes());
+            }
+        }
+        msg.extend(nonce.to_le_bytes());
+        msg
+    }
+}
+
+/// Used by the guardians of an account with a [`RecoveryPolicy`] to start
+/// recovering it, replacing its keys and threshold with new ones once the
+/// delay of the policy passes.
+///
+/// The recovery uses up the nonce of the account, so that operations signed
+/// by the keys it would replace must be signed anew.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct StartRecovery {
+    /// The account to recover.
+    pub account_id: u64,
+    /// Guardians of the account used to sign the recovery.
+    pub guardians: Vec<bls::PublicKey>,
+    /// The signature of the recovery.
+    pub signature: bls::MultisigSignature,
+    /// The keys to replace those of the account with.
+    pub keys: Vec<bls::PublicKey>,
+    /// The threshold to replace that of the account with.
+    pub threshold: u32,
+    /// The recovery nonce of the account used for the recovery.
+    pub nonce: u64,
+}
+
+impl StartRecovery {
+    const DOMAIN: &'static [u8] = b"multisig-recovery";
+
+    /// Returns the message the guardians should sign to have a valid
+    /// recovery.
+    pub fn signature_msg(&self) -> Vec<u8> {
+        let mut msg = Vec::from(Self::DOMAIN);
+        msg.extend(self.account_id.to_le_bytes());
+        msg.extend((self.keys.len() as u32).to_le_bytes());
+        for key in &self.keys {
+            msg.extend(key.to_raw_bytes());
+        }
+        msg.extend(self.threshold.to_le_bytes());
+        msg.extend(self.nonce.to_le_bytes());
+        msg
+    }
+
+    /// Signs the message of the recovery with the given secret key, of a
+    /// guardian of the account.
+    pub fn sign(&self, sk: &bls::SecretKey) -> PartialSignature {
+        let key = bls::PublicKey::from(sk);
+        let signature = sk.sign_multisig(&key, &self.signature_msg());
+        PartialSignature { key, signature }
+    }
+
+    /// Returns true if the partial signature is valid for the recovery.
+    pub fn verify(&self, partial: &PartialSignature) -> bool {
+        bls::MultisigPublicKey::aggregate(&[partial.key])
+            .and_then(|pk| pk.verify(&partial.signature, &self.signature_msg()))
+            .is_ok()
+    }
+
+    /// Sets the guardians and signature of the recovery to the aggregation of
+    /// the partial signatures of the guardians.
+    pub fn aggregate(&mut self, partials: &[PartialSignature]) {
+        (self.guardians, self.signature) = aggregate_partials(partials);
+    }
+}
+
+/// Used by a key of an account to cancel the recovery its guardians started,
+/// proving its holders haven't lost access to it.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct CancelRecovery {
+    /// The account whose recovery to cancel.
+    pub account_id: u64,
+    /// The key cancelling the recovery.
+    pub key: bls::PublicKey,
+    /// The signature of the key.
+    pub signature: bls::MultisigSignature,
+}
+
+impl CancelRecovery {
+    const DOMAIN: &'static [u8] = b"multisig-recovery-cancel";
+
+    /// Cancels the recovery of the account with the given ID and current
+    /// recovery nonce, with the given secret key.
+    pub fn new(sk: &bls::SecretKey, account_id: u64, nonce: u64) -> Self {
+        let key = bls::PublicKey::from(sk);
+        let msg = Self::signature_msg(account_id, nonce);
+        let signature = sk.sign_multisig(&key, &msg);
+        Self {
+            account_id,
+            key,
+            signature,
+        }
+    }
+
+    /// Returns the message a key must sign to cancel the recovery of the
+    /// account with the given ID and current recovery nonce.
+    // NOTE: Since starting a recovery uses up the recovery nonce, a
+    //       cancellation can't be replayed against a later recovery.
+    pub fn signature_msg(account_id: u64, nonce: u64) -> Vec<u8> {
+        let mut msg = Vec::from(Self::DOMAIN);
+        msg.extend(account_id.to_le_bytes());
+        msg.extend(nonce.to_le_bytes());
+        msg
+    }
+}
+
+/// Used to finalize the recovery of an account once its delay passed.
+///
+/// The recovery was authorized by the guardians starting it, so anyone can
+/// finalize it.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct FinalizeRecovery {
+    /// The account whose recovery to finalize.
+    pub account_id: u64,
+}
+
+/// What the fallback key of an inactive account claims it for.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+#[allow(missing_docs)]
+pub enum InactiveClaim {
+    /// Sweep the whole balance of the account to a Moonlight account.
+    Sweep { receiver: bls::PublicKey },
+    /// Replace the keys and threshold of the account, as a finalized recovery
+    /// does.
+    RotateKeys {
+        keys: Vec<bls::PublicKey>,
+        threshold: u32,
+    },
+}
+
+/// Used by the fallback key of an account with an [`InactivityPolicy`] to
+/// claim it, once it performed no operation for the period of the policy.
+///
+/// The claim uses up the nonce of the account, and counts as an operation of
+/// it.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct ClaimInactive {
+    /// The account to claim.
+    pub account_id: u64,
+    /// What the account is claimed for.
+    pub claim: InactiveClaim,
+    /// The signature of the fallback key.
+    pub signature: bls::MultisigSignature,
+    /// The nonce of the account used for the claim.
+    pub nonce: u64,
+}
+
+impl ClaimInactive {
+    const DOMAIN: &'static [u8] = b"multisig-inactive";
+
+    const SWEEP_TAG: u8 = 0;
+    const ROTATE_KEYS_TAG: u8 = 1;
+
+    /// Claims the account with the given ID for the given purpose, using the
+    /// given nonce, with the given secret key.
+    pub fn new(
+        sk: &bls::SecretKey,
+        account_id: u64,
+        claim: InactiveClaim,
+        nonce: u64,
+    ) -> Self {
+        let key = bls::PublicKey::from(sk);
+        let msg = Self::signature_msg(account_id, &claim, nonce);
+        let signature = sk.sign_multisig(&key, &msg);
+        Self {
+            account_id,
+            claim,
+            signature,
+            nonce,
+        }
+    }
+
+    /// Returns the message the fallback key must sign to claim the account
+    /// with the given ID for the given purpose, using the given nonce.
+    pub fn signature_msg(
+        account_id: u64,
+        claim: &InactiveClaim,
+        nonce: u64,
+    ) -> Vec<u8> {
+        let mut msg = Vec::from(Self::DOMAIN);
+        msg.extend(account_id.to_le_bytes());
+        match claim {
+            InactiveClaim::Sweep { receiver } => {
+                msg.push(Self::SWEEP_TAG);
+                msg.extend(receiver.to_raw_bytes());
+            }
+            InactiveClaim::RotateKeys { keys, threshold } => {
+                msg.push(Self::ROTATE_KEYS_TAG);
+                msg.extend((keys.len() as u32).to_le_bytes());
+                for key in keys {
+                    msg.extend(key.to_raw_bytes());
+                }
+                msg.extend(threshold.to_le_bytes());
+            }
+        }
+        msg.extend(nonce.to_le_bytes());
+        msg
+    }
+}
+
+/// Used by a key of an account to cancel a transfer the account queued,
+/// before its delay passes.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct CancelQueued {
+    /// The account that queued the transfer.
+    pub account_id: u64,
+    /// The nonce of the queued transfer.
+    pub nonce: u64,
+    /// The key cancelling the transfer.
+    pub key: bls::PublicKey,
+    /// The signature of the key.
+    pub signature: bls::MultisigSignature,
+}
+
+impl CancelQueued {
+    const DOMAIN: &'static [u8] = b"multisig-queued-cancel";
+
+    /// Cancels the transfer queued by the account with the given ID under
+    /// the given nonce, with the given secret key.
+    pub fn new(sk: &bls::SecretKey, account_id: u64, nonce: u64) -> Self {
+        let key = bls::PublicKey::from(sk);
+        let msg = Self::signature_msg(account_id, nonce);
+        let signature = sk.sign_multisig(&key, &msg);
+        Self {
+            account_id,
+            nonce,
+            key,
+            signature,
+        }
+    }
+
+    /// Returns the message a key must sign to cancel the transfer queued by
+    /// the account with the given ID under the given nonce.
+    // NOTE: Every transfer is queued under a nonce of its own, which is never
+    //       used again, so a cancellation can't be replayed against another.
+    pub fn signature_msg(account_id: u64, nonce: u64) -> Vec<u8> {
+        let mut msg = Vec::from(Self::DOMAIN);
+        msg.extend(account_id.to_le_bytes());
+        msg.extend(nonce.to_le_bytes());
+        msg
+    }
+}
+
+/// Used to execute a transfer queued by an account once its delay passed.
+///
+/// The transfer was signed for when it was queued, so anyone can execute it.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct ExecuteQueued {
+    /// The account that queued the transfer.
+    pub account_id: u64,
+    /// The nonce of the queued transfer.
+    pub nonce: u64,
+}
+
+/// Used by a key of an account to transfer from it on its own signature, up
+/// to the allowance the account gave the key.
+///
+/// The transfer uses up the nonce of the account, and counts as an operation
+/// of it. Its gas refund, if any, is paid out of the allowance too.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct AllowanceTransfer {
+    /// The account to transfer from.
+    pub account_id: u64,
+    /// The key transferring, out of its allowance.
+    pub key: bls::PublicKey,
+    /// The signature of the key.
+    pub signature: bls::MultisigSignature,
+    /// The Moonlight account to transfer the amount to.
+    pub receiver: bls::PublicKey,
+    /// The amount to transfer.
+    pub amount: u64,
+    /// The nonce of the account used for the transfer.
+    pub nonce: u64,
+    /// Memo to include with the transfer.
+    pub memo: String,
+    /// The reimbursement of whoever submits the transfer, if any.
+    pub gas_refund: Option<GasRefund>,
+    /// The chain the transfer is meant for, if declared.
+    pub chain_id: Option<u8>,
+}
+
+impl AllowanceTransfer {
+    const DOMAIN: &'static [u8] = b"multisig-allowance";
+
+    /// Transfers the amount from the account with the given ID to the
+    /// receiver, using the given nonce, on the given chain, with the given
+    /// secret key.
+    ///
+    /// The transfer designates no gas refund. One can be set before signing
+    /// the [`signature_msg`](Self::signature_msg) instead.
+    pub fn new(
+        sk: &bls::SecretKey,
+        account_id: u64,
+        receiver: bls::PublicKey,
+        amount: u64,
+        nonce: u64,
+        memo: String,
+        chain_id: Option<u8>,
+    ) -> Self {
+        let key = bls::PublicKey::from(sk);
+        let mut transfer = Self {
+            account_id,
+            key,
+            signature: bls::MultisigSignature::default(),
+            receiver,
+            amount,
+            nonce,
+            memo,
+            gas_refund: None,
+            chain_id,
+        };
+        transfer.signature = sk.sign_multisig(&key, &transfer.signature_msg());
+        transfer
+    }
+
+    /// Returns the message the key must sign for the transfer.
+    // NOTE: The domain keeps the message from being mistaken for that of a
+    //       transfer signed by the threshold, which the key signs on its own.
+    pub fn signature_msg(&self) -> Vec<u8> {
+        let mut msg = Vec::from(Self::DOMAIN);
+        msg.extend(self.account_id.to_le_bytes());
+        msg.extend(self.receiver.to_raw_bytes());
+        msg.extend(self.amount.to_le_bytes());
+        msg.extend(self.nonce.to_le_bytes());
+        msg.extend(self.memo.as_bytes());
+
+        prefix_chain_id(
+            self.chain_id,
+            prefix_gas_refund(self.gas_refund.as_ref(), msg),
+        )
+    }
+}
+
+/// Used to merge an account into another, each signed for by its own
+/// threshold.
+///
+/// The balance, keys and co-signers of the merged account are moved to the
+/// remaining one, and the merged account is retired, refusing any operation
+/// from then on.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct MergeAccounts {
+    /// The ID of the account that remains.
+    pub account_id: u64,
+    /// The keys of the remaining account used to sign the merge.
+    pub keys: Vec<bls::PublicKey>,
+    /// The signature of the merge by the keys of the remaining account.
+    pub signature: bls::MultisigSignature,
+    /// Signatures of the merge by co-signers of the remaining account.
+    pub cosignatures: Vec<Cosignature>,
+    /// The signature of the merge by the group key of the remaining account,
+    /// for accounts signing with a threshold scheme.
+    pub group_signature: Option<bls::Signature>,
+    /// The nonce used for the remaining account.
+    pub nonce: u64,
+    /// The ID of the account that is merged, and retired.
+    pub merged_id: u64,
+    /// The keys of the merged account used to sign the merge.
+    pub merged_keys: Vec<bls::PublicKey>,
+    /// The signature of the merge by the keys of the merged account.
+    pub merged_signature: bls::MultisigSignature,
+    /// Signatures of the merge by co-signers of the merged account.
+    pub merged_cosignatures: Vec<Cosignature>,
+    /// The signature of the merge by the group key of the merged account, for
+    /// accounts signing with a threshold scheme.
+    pub merged_group_signature: Option<bls::Signature>,
+    /// The nonce used for the merged account.
+    pub merged_nonce: u64,
+}
+
+impl MergeAccounts {
+    const DOMAIN: &'static [u8] = b"multisig-merge";
+
+    /// Returns the message that both accounts' signers should sign to have a
+    /// valid merge.
+    // NOTE: Both accounts sign the same message, which commits to the nonces
+    //       of both, so that neither side's signatures can be reused with a
+    //       different counterpart.
+    pub fn signature_msg(&self) -> Vec<u8> {
+        let mut msg = Vec::from(Self::DOMAIN);
+        msg.extend(self.account_id.to_le_bytes());
+        msg.extend(self.nonce.to_le_bytes());
+        msg.extend(self.merged_id.to_le_bytes());
+        msg.extend(self.merged_nonce.to_le_bytes());
+        msg
+    }
+
+    /// Signs the message of the merge with the given secret key, of a signer
+    /// of either account.
+    pub fn sign(&self, sk: &bls::SecretKey) -> PartialSignature {
+        let key = bls::PublicKey::from(sk);
+        let signature = sk.sign_multisig(&key, &self.signature_msg());
+        PartialSignature { key, signature }
+    }
+
+    /// Returns true if the partial signature is valid for the merge.
+    pub fn verify(&self, partial: &PartialSignature) -> bool {
+        bls::MultisigPublicKey::aggregate(&[partial.key])
+            .and_then(|pk| pk.verify(&partial.signature, &self.signature_msg()))
+            .is_ok()
+    }
+
+    /// Sets the keys and signature of each account to the aggregation of the
+    /// partial signatures of its signers.
+    pub fn aggregate(
+        &mut self,
+        partials: &[PartialSignature],
+        merged_partials: &[PartialSignature],
+    ) {
+        (self.keys, self.signature) = aggregate_partials(partials);
+        (self.merged_keys, self.merged_signature) =
+            aggregate_partials(merged_partials);
+    }
+}
+
+/// Used by a child account to draw on the budget its parent allocated to it,
+/// moving the amount drawn from the parent's balance to its own.
+///
+/// The draw is signed by the child alone, and is bounded by its budget within
+/// the current window.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct DrawBudget {
+    /// The child account drawing.
+    pub account_id: u64,
+    /// The parent account drawn on.
+    pub parent_id: u64,
+    /// Keys of the child account used to sign the draw.
+    pub keys: Vec<bls::PublicKey>,
+    /// The signature of the draw.
+    pub signature: bls::MultisigSignature,
+    /// Signatures of the draw by co-signers of the child account.
+    pub cosignatures: Vec<Cosignature>,
+    /// The signature of the draw by the group key of the child account, for
+    /// accounts signing with a threshold scheme.
+    pub group_signature: Option<bls::Signature>,
+    /// The amount drawn.
+    pub amount: u64,
+    /// The nonce of the child account used for the draw.
+    pub nonce: u64,
+}
+
+impl DrawBudget {
+    const DOMAIN: &'static [u8] = b"multisig-draw";
+
+    /// Returns the message the child account's signers should sign to have a
+    /// valid draw.
+    pub fn signature_msg(&self) -> Vec<u8> {
+        let mut msg = Vec::from(Self::DOMAIN);
+        msg.extend(self.account_id.to_le_bytes());
+        msg.extend(self.parent_id.to_le_bytes());
+        msg.extend(self.amount.to_le_bytes());
+        msg.extend(self.nonce.to_le_bytes());
+        msg
+    }
+
+    /// Signs the message of the draw with the given secret key, of a signer
+    /// of the child account.
+    pub fn sign(&self, sk: &bls::SecretKey) -> PartialSignature {
+        let key = bls::PublicKey::from(sk);
+        let signature = sk.sign_multisig(&key, &self.signature_msg());
+        PartialSignature { key, signature }
+    }
+
+    /// Returns true if the partial signature is valid for the draw.
+    pub fn verify(&self, partial: &PartialSignature) -> bool {
+        bls::MultisigPublicKey::aggregate(&[partial.key])
+            .and_then(|pk| pk.verify(&partial.signature, &self.signature_msg()))
+            .is_ok()
+    }
+
+    /// Sets the keys and signature of the draw to the aggregation of the
+    /// partial signatures of its signers.
+    pub fn aggregate(&mut self, partials: &[PartialSignature]) {
+        (self.keys, self.signature) = aggregate_partials(partials);
+    }
+}
+
+/// Used to record a checkpoint of an account, fixing its balance, nonce and
+/// keys at the current height for auditors to verify the event history
+/// against.
+///
+/// The checkpoint is signed as any operation of the account, and uses up its
+/// nonce.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct RecordCheckpoint {
+    /// The account to record a checkpoint of.
+    pub account_id: u64,
+    /// Keys used to sign the checkpoint.
+    pub keys: Vec<bls::PublicKey>,
+    /// The signature of the checkpoint.
+    pub signature: bls::MultisigSignature,
+    /// Signatures of the checkpoint by co-signers of the account.
+    pub cosignatures: Vec<Cosignature>,
+    /// The signature of the checkpoint by the group key of the account, for
+    /// accounts signing with a threshold scheme.
+    pub group_signature: Option<bls::Signature>,
+    /// The nonce of the account used for the checkpoint.
+    pub nonce: u64,
+}
+
+impl RecordCheckpoint {
+    const DOMAIN: &'static [u8] = b"multisig-checkpoint";
+
+    /// Returns the message the account's signers should sign to have a valid
+    /// checkpoint.
+    pub fn signature_msg(&self) -> Vec<u8> {
+        let mut msg = Vec::from(Self::DOMAIN);
+        msg.extend(self.account_id.to_le_bytes());
+        msg.extend(self.nonce.to_le_bytes());
+        msg
+    }
+
+    /// Signs the message of the checkpoint with the given secret key, of a
+    /// signer of the account.
+    pub fn sign(&self, sk: &bls::SecretKey) -> PartialSignature {
+        let key = bls::PublicKey::from(sk);
+        let signature = sk.sign_multisig(&key, &self.signature_msg());
+        PartialSignature { key, signature }
+    }
+
+    /// Returns true if the partial signature is valid for the checkpoint.
+    pub fn verify(&self, partial: &PartialSignature) -> bool {
+        bls::MultisigPublicKey::aggregate(&[partial.key])
+            .and_then(|pk| pk.verify(&partial.signature, &self.signature_msg()))
+            .is_ok()
+    }
+
+    /// Sets the keys and signature of the checkpoint to the aggregation of
+    /// the partial signatures of its signers.
+    pub fn aggregate(&mut self, partials: &[PartialSignature]) {
+        (self.keys, self.signature) = aggregate_partials(partials);
+    }
+}
+
+/// The contract-wide parameters, adjusted by the governance account of the
+/// contract with [`Configure`].
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Archive, Serialize, Deserialize,
+)]
+#[archive_attr(derive(CheckBytes))]
+pub struct ContractConfig {
+    /// The most keys an account may have, if bounded. Accounts already
+    /// holding more keep them, but can't be created with more or gain any.
+    pub max_keys: Option<u32>,
+    /// The longest memo a deposit, transfer or payroll row may carry, in
+    /// bytes, if bounded.
+    pub max_memo_len: Option<u32>,
+    /// Whether the contract is paused, refusing every operation moving funds
+    /// while leaving accounts free to change. Deposits, transfers, payrolls,
+    /// merges, splits, budget draws and withdrawal claims are all refused.
+    pub paused: bool,
+    /// The storage fee for every movement an account retains past
+    /// [`DEFAULT_HISTORY_RETENTION`], in LUX.
+    pub history_record_fee: u64,
+    /// The largest gas refund an operation can designate, in LUX.
+    pub max_gas_refund: u64,
+}
+
+impl ContractConfig {
+    /// The parameters the contract is deployed with.
+    pub const DEFAULT: Self = Self {
+        max_keys: None,
+        max_memo_len: None,
+        paused: false,
+        history_record_fee: HISTORY_RECORD_FEE,
+        max_gas_refund: MAX_GAS_REFUND,
+    };
+
+    /// Returns the bytes of the parameters, as signed for by [`Configure`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for bound in [self.max_keys, self.max_memo_len] {
+            match bound {
+                Some(bound) => {
+                    bytes.push(1);
+                    bytes.extend(bound.to_le_bytes());
+                }
+                None => bytes.push(0),
+            }
+        }
+        bytes.push(self.paused as u8);
+        bytes.extend(self.history_record_fee.to_le_bytes());
+        bytes.extend(self.max_gas_refund.to_le_bytes());
+        bytes
+    }
+
+    /// Returns whether the bounds on keys and memos, if any, leave room for
+    /// at least one.
+    pub fn is_valid(&self) -> bool {
+        self.max_keys != Some(0) && self.max_memo_len != Some(0)
+    }
+}
+
+impl Default for ContractConfig {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Used to set the contract-wide parameters, on the signatures of the
+/// governance account of the contract.
+///
+/// The configuration is signed as any operation of the governance account,
+/// and uses up its nonce.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct Configure {
+    /// The parameters to set, replacing the current ones.
+    pub config: ContractConfig,
+    /// Keys used to sign the configuration.
+    pub keys: Vec<bls::PublicKey>,
+    /// The signature of the configuration.
+    pub signature: bls::MultisigSignature,
+    /// Signatures of the configuration by co-signers of the governance
+    /// account.
+    pub cosignatures: Vec<Cosignature>,
+    /// The signature of the configuration by the group key of the governance
+    /// account, for accounts signing with a threshold scheme.
+    pub group_signature: Option<bls::Signature>,
+    /// The nonce of the governance account used for the configuration.
+    pub nonce: u64,
+}
+
+impl Configure {
+    const DOMAIN: &'static [u8] = b"multisig-configure";
+
+    /// Returns the message the signers of the governance account should sign
+    /// to have a valid configuration.
+    pub fn signature_msg(&self) -> Vec<u8> {
+        let mut msg = Vec::from(Self::DOMAIN);
+        msg.extend(self.config.to_bytes());
+        msg.extend(self.nonce.to_le_bytes());
+        msg
+    }
+
+    /// Signs the message of the configuration with the given secret key, of
+    /// a signer of the governance account.
+    pub fn sign(&self, sk: &bls::SecretKey) -> PartialSignature {
+        let key = bls::PublicKey::from(sk);
+        let signature = sk.sign_multisig(&key, &self.signature_msg());
+        PartialSignature { key, signature }
+    }
+
+    /// Returns true if the partial signature is valid for the configuration.
+    pub fn verify(&self, partial: &PartialSignature) -> bool {
+        bls::MultisigPublicKey::aggregate(&[partial.key])
+            .and_then(|pk| pk.verify(&partial.signature, &self.signature_msg()))
+            .is_ok()
+    }
+
+    /// Sets the keys and signature of the configuration to the aggregation of
+    /// the partial signatures of its signers.
+    pub fn aggregate(&mut self, partials: &[PartialSignature]) {
+        (self.keys, self.signature) = aggregate_partials(partials);
+    }
+}
+
+/// Used to split a new account off an existing one, with some of its keys and
+/// part of its balance.
+///
+/// The new account is created as by a [`CreateAccount`], and is signed for by
+/// its own keys and threshold from then on.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct SplitAccount {
+    /// The account to split.
+    pub account_id: u64,
+    /// Keys used to sign the split.
+    pub keys: Vec<bls::PublicKey>,
+    /// The signature of the split.
+    pub signature: bls::MultisigSignature,
+    /// Signatures of the split by co-signers of the account.
+    pub cosignatures: Vec<Cosignature>,
+    /// The signature of the split by the group key of the account, for
+    /// accounts signing with a threshold scheme.
+    pub group_signature: Option<bls::Signature>,
+    /// Keys to be owned by the new account, each of which must be a key of
+    /// the account split.
+    pub new_keys: Vec<bls::PublicKey>,
+    /// Number of keys that need to sign to effect an operation on the new
+    /// account.
+    pub threshold: u32,
+    /// Description of the new account.
+    pub description: String,
+    /// The amount moved to the new account.
+    pub amount: u64,
+    /// The nonce used for the split.
+    pub nonce: u64,
+    /// The reimbursement of whoever submits the split, if any.
+    pub gas_refund: Option<GasRefund>,
+    /// The chain the split is meant for, if declared.
+    pub chain_id: Option<u8>,
+}
+
+impl SplitAccount {
+    const DOMAIN: &'static [u8] = b"multisig-split";
+
+    /// Returns the message that should be signed to have a valid split.
+    // NOTE: The keys, threshold and description of the new account are tagged
+    //       as the account changes setting them would be, so that a signing
+    //       payload can describe - and rebuild - the message with the same
+    //       fields it uses for changes.
+    pub fn signature_msg(&self) -> Vec<u8> {
+        let mut msg = Vec::from(Self::DOMAIN);
+        msg.extend(self.account_id.to_le_bytes());
+        msg.extend(self.amount.to_le_bytes());
+        for key in &self.new_keys {
+            msg.push(ChangeAccount::ADD_KEY_TAG);
+            msg.extend(key.to_raw_bytes());
+        }
+        msg.push(ChangeAccount::SET_THRESHOLD_TAG);
+        msg.extend(self.threshold.to_le_bytes());
+        msg.push(ChangeAccount::SET_DESCRIPTION_TAG);
+        msg.extend((self.description.len() as u32).to_le_bytes());
+        msg.extend(self.description.as_bytes());
+        msg.extend(self.nonce.to_le_bytes());
+        prefix_chain_id(
+            self.chain_id,
+            prefix_gas_refund(self.gas_refund.as_ref(), msg),
+        )
+    }
+}
+
+/// Used by the owner of a key to mark it as compromised, after which the
+/// contract refuses it as a signer in any account.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct MarkCompromised {
+    /// The compromised key.
+    pub key: bls::PublicKey,
+    /// The signature of the key itself.
+    pub signature: bls::MultisigSignature,
+}
+
+impl MarkCompromised {
+    const DOMAIN: &'static [u8] = b"multisig-compromised";
+
+    /// Marks the key of the given secret key as compromised.
+    pub fn new(sk: &bls::SecretKey) -> Self {
+        let key = bls::PublicKey::from(sk);
+        let signature = sk.sign_multisig(&key, &Self::signature_msg(&key));
+        Self { key, signature }
+    }
+
+    /// Returns the message the given key must sign to be marked as
+    /// compromised.
+    // NOTE: The message is prefixed with a domain that no account ID could
+    //       start an operation's message with, so that the signature of an
+    //       operation can never be replayed to mark its keys.
+    pub fn signature_msg(key: &bls::PublicKey) -> Vec<u8> {
+        let mut msg = Vec::from(Self::DOMAIN);
+        msg.extend(key.to_raw_bytes());
+        msg
+    }
+}
+
+/// Used by the owner of a co-signer key to mark it as compromised, as
+/// [`MarkCompromised`] does for BLS keys.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct MarkCosignerCompromised {
+    /// The compromised co-signer key.
+    pub key: CosignerKey,
+    /// The signature of the key itself, encoded as its scheme specifies.
+    pub signature: Vec<u8>,
+}
+
+impl MarkCosignerCompromised {
+    const DOMAIN: &'static [u8] = b"multisig-cosigner-compromised";
+
+    /// Marks the co-signer key of the given Ed25519 key as compromised.
+    pub fn new_ed25519(sk: &ed25519::SigningKey) -> Self {
+        use ed25519::Signer;
+
+        let key = CosignerKey::from(sk);
+        let signature = sk.sign(&Self::signature_msg(&key));
+        Self {
+            key,
+            signature: signature.to_bytes().to_vec(),
+        }
+    }
+
+    /// Returns the message the given co-signer key must sign to be marked as
+    /// compromised.
+    // NOTE: The domain keeps operation signatures from being replayed, as
+    //       with `MarkCompromised`.
+    pub fn signature_msg(key: &CosignerKey) -> Vec<u8> {
+        let mut msg = Vec::from(Self::DOMAIN);
+        msg.extend(key.to_bytes());
+        msg
+    }
+}
+
+/// Used by the receiver of a withdrawal recorded by an account with claimable
+/// withdrawals to be paid it.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct ClaimWithdrawal {
+    /// The claim ID of the withdrawal.
+    pub claim_id: u64,
+    /// The receiver of the withdrawal.
+    pub receiver: bls::PublicKey,
+    /// The signature of the receiver.
+    pub signature: bls::MultisigSignature,
+}
+
+impl ClaimWithdrawal {
+    const DOMAIN: &'static [u8] = b"multisig-claim";
+
+    /// Claims the withdrawal with the given claim ID, with the secret key of
+    /// its receiver.
+    pub fn new(sk: &bls::SecretKey, claim_id: u64) -> Self {
+        let receiver = bls::PublicKey::from(sk);
+        let msg = Self::signature_msg(claim_id);
+        let signature = sk.sign_multisig(&receiver, &msg);
+        Self {
+            claim_id,
+            receiver,
+            signature,
+        }
+    }
+
+    /// Returns the message the receiver must sign to claim the withdrawal
+    /// with the given claim ID.
+    // NOTE: Claim IDs are never reused, and a withdrawal is gone once claimed,
+    //       so the ID alone keeps a claim from being replayed.
+    pub fn signature_msg(claim_id: u64) -> Vec<u8> {
+        let mut msg = Vec::from(Self::DOMAIN);
+        msg.extend(claim_id.to_le_bytes());
+        msg
+    }
+}
+
+/// Used to replace a key with another across every account using it, in a
+/// single transaction.
+///
+/// The replacement is signed by the key replaced, and carries a change of
+/// each account using it, signed as any other change of the account would
+/// be. Each change must be [`ReplaceKey::account_changes`], and the changes
+/// must cover every account using the key.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct ReplaceKey {
+    /// The key replaced.
+    pub old_key: bls::PublicKey,
+    /// The key replacing it.
+    pub new_key: bls::PublicKey,
+    /// The signature of the replaced key itself.
+    pub signature: bls::MultisigSignature,
+    /// The change of each account using the key.
+    pub changes: Vec<ChangeAccount>,
+}
+
+impl ReplaceKey {
+    const DOMAIN: &'static [u8] = b"multisig-replace-key";
+
+    /// Replaces the key of the given secret key with the new key, in the
+    /// accounts the given changes are of.
+    pub fn new(
+        sk: &bls::SecretKey,
+        new_key: bls::PublicKey,
+        changes: Vec<ChangeAccount>,
+    ) -> Self {
+        let old_key = bls::PublicKey::from(sk);
+        let msg = Self::signature_msg(&old_key, &new_key);
+        let signature = sk.sign_multisig(&old_key, &msg);
+        Self {
+            old_key,
+            new_key,
+            signature,
+            changes,
+        }
+    }
+
+    /// Returns the message the replaced key must sign to be replaced with
+    /// the new key.
+    // NOTE: The message is prefixed with a domain, as the message of
+    //       `MarkCompromised` is, so that no other signature can be
+    //       replayed as the replaced key's.
+    pub fn signature_msg(
+        old_key: &bls::PublicKey,
+        new_key: &bls::PublicKey,
+    ) -> Vec<u8> {
+        let mut msg = Vec::from(Self::DOMAIN);
+        msg.extend(old_key.to_raw_bytes());
+        msg.extend(new_key.to_raw_bytes());
+        msg
+    }
+
+    /// Returns the changes each account using the old key must sign for it
+    /// to be replaced. The new key is added before the old one is removed,
+    /// so that the account never has fewer keys than its threshold.
+    pub fn account_changes(
+        old_key: bls::PublicKey,
+        new_key: bls::PublicKey,
+    ) -> Vec<AccountChange> {
+        vec![
+            AccountChange::AddKey { key: new_key },
+            AccountChange::RemoveKey { key: old_key },
+        ]
+    }
+}
+
+/// An operation on an account that must be signed by its keys.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub enum Operation {
+    /// A transfer from an account.
+    Transfer(Transfer),
+    /// A change to an account.
+    ChangeAccount(ChangeAccount),
+    /// A split of a new account off an account.
+    SplitAccount(SplitAccount),
+    /// A payroll from an account.
+    Payroll(Payroll),
+    /// A transfer from an account and a change to it, made at once.
+    TransferAndChange(TransferAndChange),
+}
+
+impl Operation {
+    /// The ID of the account the operation is performed on.
+    pub fn account_id(&self) -> u64 {
+        match self {
+            Operation::Transfer(t) => t.account_id,
+            Operation::ChangeAccount(c) => c.account_id,
+            Operation::SplitAccount(s) => s.account_id,
+            Operation::Payroll(p) => p.account_id,
+            Operation::TransferAndChange(tc) => tc.account_id,
+        }
+    }
+
+    /// The nonce used for the operation.
+    pub fn nonce(&self) -> u64 {
+        match self {
+            Operation::Transfer(t) => t.nonce,
+            Operation::ChangeAccount(c) => c.nonce,
+            Operation::SplitAccount(s) => s.nonce,
+            Operation::Payroll(p) => p.nonce,
+            Operation::TransferAndChange(tc) => tc.nonce,
+        }
+    }
+
+    /// The reimbursement of the submitter of the operation, if any.
+    pub fn gas_refund(&self) -> Option<GasRefund> {
+        match self {
+            Operation::Transfer(t) => t.gas_refund,
+            Operation::ChangeAccount(c) => c.gas_refund,
+            Operation::SplitAccount(s) => s.gas_refund,
+            Operation::Payroll(p) => p.gas_refund,
+            Operation::TransferAndChange(tc) => tc.gas_refund,
+        }
+    }
+
+    /// The chain the operation declares it's meant for, if any.
+    pub fn chain_id(&self) -> Option<u8> {
+        match self {
+            Operation::Transfer(t) => t.chain_id,
+            Operation::ChangeAccount(c) => c.chain_id,
+            Operation::SplitAccount(s) => s.chain_id,
+            Operation::Payroll(p) => p.chain_id,
+            Operation::TransferAndChange(tc) => tc.chain_id,
+        }
+    }
+
+    /// The kind of the operation, whose threshold it must meet.
+    pub fn kind(&self) -> OperationKind {
+        match self {
+            Operation::Transfer(_) => OperationKind::Transfer,
+            Operation::ChangeAccount(_) => OperationKind::ChangeAccount,
+            Operation::SplitAccount(_) => OperationKind::SplitAccount,
+            Operation::Payroll(_) => OperationKind::Payroll,
+            Operation::TransferAndChange(_) => OperationKind::TransferAndChange,
+        }
+    }
+
+    /// The name of the contract function that performs the operation.
+    pub fn fn_name(&self) -> &'static str {
+        match self {
+            Operation::Transfer(_) => "transfer",
+            Operation::ChangeAccount(_) => "change_account",
+            Operation::SplitAccount(_) => "split_account",
+            Operation::Payroll(_) => "payroll",
+            Operation::TransferAndChange(_) => "transfer_and_change",
+        }
+    }
+
+    /// Returns the message that should be signed to have a valid operation.
+    pub fn signature_msg(&self) -> Vec<u8> {
+        match self {
+            Operation::Transfer(t) => t.signature_msg(),
+            Operation::ChangeAccount(c) => c.signature_msg(),
+            Operation::SplitAccount(s) => s.signature_msg(),
+            Operation::Payroll(p) => p.signature_msg(),
+            Operation::TransferAndChange(tc) => tc.signature_msg(),
+        }
+    }
+
+    /// Sets the keys and signature of the operation to the aggregation of the
+    /// given partial signatures.
+    pub fn aggregate(&mut self, partials: &[PartialSignature]) {
+        let (keys, signature) = aggregate_partials(partials);
+
+        match self {
+            Operation::Transfer(t) => {
+                t.keys = keys;
+                t.signature = signature;
+            }
+            Operation::ChangeAccount(c) => {
+                c.keys = keys;
+                c.signature = signature;
+            }
+            Operation::SplitAccount(s) => {
+                s.keys = keys;
+                s.signature = signature;
+            }
+            Operation::Payroll(p) => {
+                p.keys = keys;
+                p.signature = signature;
+            }
+            Operation::TransferAndChange(tc) => {
+                tc.keys = keys;
+                tc.signature = signature;
+            }
+        }
+    }
+
+    /// Designates a reimbursement of the submitter of the operation, which
+    /// changes the message to be signed.
+    pub fn set_gas_refund(&mut self, refund: Option<GasRefund>) {
+        match self {
+            Operation::Transfer(t) => t.gas_refund = refund,
+            Operation::ChangeAccount(c) => c.gas_refund = refund,
+            Operation::SplitAccount(s) => s.gas_refund = refund,
+            Operation::Payroll(p) => p.gas_refund = refund,
+            Operation::TransferAndChange(tc) => tc.gas_refund = refund,
+        }
+    }
+
+    /// Declares the chain the operation is meant for, which changes the
+    /// message to be signed.
+    pub fn set_chain_id(&mut self, chain_id: Option<u8>) {
+        match self {
+            Operation::Transfer(t) => t.chain_id = chain_id,
+            Operation::ChangeAccount(c) => c.chain_id = chain_id,
+            Operation::SplitAccount(s) => s.chain_id = chain_id,
+            Operation::Payroll(p) => p.chain_id = chain_id,
+            Operation::TransferAndChange(tc) => tc.chain_id = chain_id,
+        }
+    }
+
+    /// Sets the signature of the operation by the group key of its account,
+    /// as combined by the signers' threshold scheme.
+    pub fn set_group_signature(&mut self, signature: bls::Signature) {
+        match self {
+            Operation::Transfer(t) => t.group_signature = Some(signature),
+            Operation::ChangeAccount(c) => c.group_signature = Some(signature),
+            Operation::SplitAccount(s) => s.group_signature = Some(signature),
+            Operation::Payroll(p) => p.group_signature = Some(signature),
+            Operation::TransferAndChange(tc) => {
+                tc.group_signature = Some(signature)
+            }
+        }
+    }
+
+    /// Adds the signature of a co-signer to the operation.
+    pub fn cosign(&mut self, cosignature: Cosignature) {
+        match self {
+            Operation::Transfer(t) => t.cosignatures.push(cosignature),
+            Operation::ChangeAccount(c) => c.cosignatures.push(cosignature),
+            Operation::SplitAccount(s) => s.cosignatures.push(cosignature),
+            Operation::Payroll(p) => p.cosignatures.push(cosignature),
+            Operation::TransferAndChange(tc) => {
+                tc.cosignatures.push(cosignature)
+            }
+        }
+    }
+
+    /// The keys signing the operation.
+    pub fn keys(&self) -> &[bls::PublicKey] {
+        match self {
+            Operation::Transfer(t) => &t.keys,
+            Operation::ChangeAccount(c) => &c.keys,
+            Operation::SplitAccount(s) => &s.keys,
+            Operation::Payroll(p) => &p.keys,
+            Operation::TransferAndChange(tc) => &tc.keys,
+        }
+    }
+
+    /// Sets the keys signing the operation, leaving its signature as it is.
+    pub fn set_keys(&mut self, keys: Vec<bls::PublicKey>) {
+        match self {
+            Operation::Transfer(t) => t.keys = keys,
+            Operation::ChangeAccount(c) => c.keys = keys,
+            Operation::SplitAccount(s) => s.keys = keys,
+            Operation::Payroll(p) => p.keys = keys,
+            Operation::TransferAndChange(tc) => tc.keys = keys,
+        }
+    }
+}
+
+/// An operation whose signing keys are given as a quorum certificate, a bitmap
+/// over the keys of its account, rather than the keys themselves.
+///
+/// Bit `i` - the `i % 8` least significant bit of byte `i / 8` - stands for the
+/// `i`th key of the account, in the order the `account_keys` query feeds them.
+/// The bitmap covers exactly the keys of the account, and the operation itself
+/// names no keys.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct CertifiedOperation {
+    /// The operation, signed, but with no keys.
+    pub operation: Operation,
+    /// The bitmap of the keys signing the operation.
+    pub signers: Vec<u8>,
+}
+
+impl CertifiedOperation {
+    /// Certifies a signed operation, given the keys of its account in the
+    /// order the `account_keys` query feeds them.
+    ///
+    /// Returns `None` if a key signing the operation isn't one of the keys of
+    /// the account, such as a delegate, which can only sign by name.
+    pub fn new(
+        mut operation: Operation,
+        account_keys: &[bls::PublicKey],
+    ) -> Option<Self> {
+        let mut signers = vec![0u8; account_keys.len().div_ceil(8)];
+        for key in operation.keys() {
+            let i = account_keys.iter().position(|k| k == key)?;
+            signers[i / 8] |= 1 << (i % 8);
+        }
+        operation.set_keys(Vec::new());
+        Some(Self { operation, signers })
+    }
+
+    /// Returns the keys the certificate names, given the keys of the account
+    /// in the order the `account_keys` query feeds them.
+    ///
+    /// Returns `None` if the bitmap doesn't cover exactly the keys of the
+    /// account, or if the operation names keys of its own.
+    pub fn keys(
+        &self,
+        account_keys: &[bls::PublicKey],
+    ) -> Option<Vec<bls::PublicKey>> {
+        if !self.operation.keys().is_empty()
+            || self.signers.len() != account_keys.len().div_ceil(8)
+        {
+            return None;
+        }
+
+        let bit = |i: usize| self.signers[i / 8] >> (i % 8) & 1 == 1;
+        let padding = account_keys.len()..self.signers.len() * 8;
+        if padding.into_iter().any(bit) {
+            return None;
+        }
+        let keys = account_keys.iter().enumerate().filter(|(i, _)| bit(*i));
+        Some(keys.map(|(_, key)| *key).collect())
+    }
+}
+
+/// Returns the keys of the given partial signatures, together with their
+/// aggregated signature.
+fn aggregate_partials(
+    partials: &[PartialSignature],
+) -> (Vec<bls::PublicKey>, bls::MultisigSignature) {
+    let keys = partials.iter().map(|p| p.key).collect();
+    let signature = match partials.split_first() {
+        Some((first, rest)) => {
+            let rest: Vec<_> = rest.iter().map(|p| p.signature).collect();
+            first.signature.aggregate(&rest)
+        }
+        None => bls::MultisigSignature::default(),
+    };
+    (keys, signature)
+}
+
+/// A signature of an operation's message by a single key, meant to be
+/// aggregated with the signatures of the other signers.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct PartialSignature {
+    /// The key that signed.
+    pub key: bls::PublicKey,
+    /// The signature of the operation's message.
+    pub signature: bls::MultisigSignature,
+}
+
+impl PartialSignature {
+    /// Signs the message of the given operation with the given secret key.
+    pub fn sign(sk: &bls::SecretKey, operation: &Operation) -> Self {
+        let key = bls::PublicKey::from(sk);
+        let signature = sk.sign_multisig(&key, &operation.signature_msg());
+        Self { key, signature }
+    }
+
+    /// Returns true if the signature is valid for the given operation.
+    pub fn verify(&self, operation: &Operation) -> bool {
+        bls::MultisigPublicKey::aggregate(&[self.key])
+            .and_then(|pk| {
+                pk.verify(&self.signature, &operation.signature_msg())
+            })
+            .is_ok()
+    }
+}
+
+/// The data about a given account.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct AccountData {
+    /// The balance the account holds.
+    pub balance: u64,
+    /// Number of keys that need to sign to effect an operation.
+    pub threshold: u32,
+    /// Description of the account.
+    pub description: String,
+    /// The current nonce of the account.
+    pub nonce: u64,
+    /// Whether the account's events carry hashes of memos and receivers,
+    /// instead of the values themselves.
+    pub private_events: bool,
+    /// Whether the account was frozen by a duress key.
+    pub frozen: bool,
+    /// The group key of the threshold scheme the account's signers run, if
+    /// any, in which case only its signature is accepted.
+    pub group_key: Option<bls::PublicKey>,
+    /// Whether the account only transfers to whitelisted receivers. This is
+    /// set by whitelisting the first receiver, and unset only by removing the
+    /// last one, which takes the supermajority of the account.
+    pub whitelist_enforced: bool,
+    /// The soft limit on the account's outflow, if any.
+    pub velocity_limit: Option<VelocityLimit>,
+    /// Whether the account's outgoing transfers were locked by one of its
+    /// keys.
+    pub locked: bool,
+    /// The alias claimed by the account, if any.
+    pub alias: Option<String>,
+    /// The account this account was merged into, if any, in which case it
+    /// refuses any operation.
+    pub merged_into: Option<u64>,
+    /// The ID of the chain the account was created on. Operations declaring
+    /// another chain are refused.
+    pub chain_id: u8,
+    /// The most the account may hold, if capped. Deposits taking its balance
+    /// past it are refused.
+    pub balance_cap: Option<u64>,
+    /// The tags carried by the account, in the order they were added.
+    pub tags: Vec<String>,
+    /// The liveness policy of the account, if it has one.
+    pub liveness_policy: Option<LivenessPolicy>,
+    /// The supermajority of signers needed to change the threshold, if any.
+    pub supermajority: Option<Supermajority>,
+    /// The bar keys are added to the account under, if raised above the
+    /// threshold.
+    pub key_addition_policy: Option<KeyAdditionPolicy>,
+    /// The window of blocks the account's transfers are restricted to, if
+    /// any.
+    pub transfer_window: Option<TransferWindow>,
+    /// The spending categories of the account, in the order they were added.
+    pub categories: Vec<SpendingCategory>,
+    /// The budgets the account allocated to child accounts, in the order they
+    /// were allocated.
+    pub child_budgets: Vec<ChildBudget>,
+    /// Whether the account was archived, refusing deposits and transfers
+    /// until reactivated.
+    pub archived: bool,
+    /// Whether the account's transfers only record a withdrawal for their
+    /// receiver to claim, the contract holding the amount until then.
+    pub claimable_withdrawals: bool,
+    /// The number of movements the account retains in its ledger, if it set
+    /// its own rather than [`DEFAULT_HISTORY_RETENTION`].
+    pub history_retention: Option<u32>,
+    /// The compliance control of the account, if any.
+    pub compliance: Option<ComplianceControl>,
+    /// Whether the account was placed on hold by its compliance key, only
+    /// transferring to its remediation address until the hold is lifted.
+    pub on_hold: bool,
+    /// The license policy of the account, if any.
+    pub license_policy: Option<LicensePolicy>,
+    /// The thresholds the account set for operations of a kind, in place of
+    /// its own, in the order of their kinds.
+    pub operation_thresholds: Vec<OperationThreshold>,
+    /// The recovery policy of the account, if its guardians can recover it.
+    pub recovery_policy: Option<RecoveryPolicy>,
+    /// The number of recoveries started on the account, counted apart from
+    /// its nonce so that its guardians never use up one its keys signed for.
+    pub recovery_nonce: u64,
+    /// The inactivity policy of the account, if a fallback key can claim it.
+    pub inactivity_policy: Option<InactivityPolicy>,
+    /// Height of the block the account last performed an operation in, or
+    /// was created in if it performed none since.
+    pub last_active: u64,
+    /// The queue policy of the account, if it holds back large transfers.
+    pub queue_policy: Option<QueuePolicy>,
+}
+
+impl AccountData {
+    /// Returns the number of keys that need to sign to effect an operation of
+    /// the given kind - the threshold set for the kind, if any, or else the
+    /// threshold of the account.
+    ///
+    /// Operations moving funds out of the account need at least as many keys
+    /// as transfers do, and transfers made together with a change as many as
+    /// changes do too.
+    pub fn threshold_of(&self, op: OperationKind) -> u32 {
+        let set = |op| {
+            self.operation_thresholds
+                .iter()
+                .find(|t| t.op == op)
+                .map_or(self.threshold, |t| t.threshold)
+        };
+        match op {
+            OperationKind::SplitAccount
+            | OperationKind::MergeAccounts
+            | OperationKind::Payroll => {
+                set(op).max(set(OperationKind::Transfer))
+            }
+            OperationKind::TransferAndChange => set(op)
+                .max(set(OperationKind::Transfer))
+                .max(set(OperationKind::ChangeAccount)),
+            _ => set(op),
+        }
+    }
+
+    /// Returns the highest number of keys any operation on the account needs,
+    /// below which its keys can't be brought.
+    pub fn max_threshold(&self) -> u32 {
+        self.operation_thresholds
+            .iter()
+            .map(|t| t.threshold)
+            .fold(self.threshold, u32::max)
+    }
+}
+
+/// The kinds of operations signed for by the keys of an account, each of
+/// which the account can set a threshold of its own for.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Archive,
+    Serialize,
+    Deserialize,
+)]
+#[archive_attr(derive(CheckBytes))]
+pub enum OperationKind {
+    /// A [`Transfer`].
+    Transfer = 0,
+    /// A [`ChangeAccount`].
+    ChangeAccount = 1,
+    /// A [`SplitAccount`].
+    SplitAccount = 2,
+    /// A [`MergeAccounts`], by either account.
+    MergeAccounts = 3,
+    /// A [`DrawBudget`], by the child account.
+    DrawBudget = 4,
+    /// A [`Payroll`].
+    Payroll = 5,
+    /// A [`TransferAndChange`].
+    TransferAndChange = 6,
+    /// A [`RecordCheckpoint`].
+    RecordCheckpoint = 7,
+    /// A [`Configure`], by the governance account.
+    Configure = 8,
+}
+
+impl OperationKind {
+    /// Every kind of operation, in order.
+    pub const ALL: [Self; 9] = [
+        Self::Transfer,
+        Self::ChangeAccount,
+        Self::SplitAccount,
+        Self::MergeAccounts,
+        Self::DrawBudget,
+        Self::Payroll,
+        Self::TransferAndChange,
+        Self::RecordCheckpoint,
+        Self::Configure,
+    ];
+
+    /// The name of the contract function that performs operations of the
+    /// kind.
+    pub fn fn_name(&self) -> &'static str {
+        match self {
+            Self::Transfer => "transfer",
+            Self::ChangeAccount => "change_account",
+            Self::SplitAccount => "split_account",
+            Self::MergeAccounts => "merge_accounts",
+            Self::DrawBudget => "draw_budget",
+            Self::Payroll => "payroll",
+            Self::TransferAndChange => "transfer_and_change",
+            Self::RecordCheckpoint => "record_checkpoint",
+            Self::Configure => "configure",
+        }
+    }
+}
+
+/// The threshold an account set for operations of a kind.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Archive, Serialize, Deserialize,
+)]
+#[archive_attr(derive(CheckBytes))]
+pub struct OperationThreshold {
+    /// The kind of operations the threshold is for.
+    pub op: OperationKind,
+    /// Number of keys that need to sign to effect an operation of the kind.
+    pub threshold: u32,
+}
+
+/// A tier of an account's transfers, letting transfers of up to an amount be
+/// signed by another number of keys than the threshold for transfers.
+///
+/// A transfer falls in the tier with the lowest ceiling at or above its
+/// amount, and is held to the tier's threshold instead of the threshold for
+/// transfers, whether it's lower or higher. Transfers above every ceiling need
+/// the threshold as usual. Other operations moving funds out, and child
+/// budgets, are held to the tier of their amount on top of their own
+/// threshold.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Archive, Serialize, Deserialize,
+)]
+#[archive_attr(derive(CheckBytes))]
+pub struct TransferTier {
+    /// The largest amount a transfer in the tier can move, in LUX.
+    pub ceiling: u64,
+    /// Number of keys that need to sign a transfer in the tier.
+    pub threshold: u32,
+}
+
+impl TransferTier {
+    /// Returns the number of keys a transfer of the given amount needs under
+    /// the given tiers, ordered by their ceilings, if it falls in one.
+    pub fn threshold_for(tiers: &[TransferTier], amount: u64) -> Option<u32> {
+        tiers
+            .iter()
+            .find(|tier| amount <= tier.ceiling)
+            .map(|tier| tier.threshold)
+    }
+}
+
+/// The balance the contract holds in the transfer contract, compared against
+/// the sum of the balances of its accounts, of the withdrawals awaiting claim
+/// and of the storage fees it kept, exposing any drift between its accounting
+/// and the funds it actually holds.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Archive, Serialize, Deserialize,
+)]
+#[archive_attr(derive(CheckBytes))]
+pub struct CustodyReport {
+    /// The sum of the balances of all accounts.
+    pub total_balance: u64,
+    /// The sum of the withdrawals awaiting claim.
+    pub pending_withdrawals: u64,
+    /// The storage fees the contract kept.
+    pub storage_fees: u64,
+    /// The balance of the contract in the transfer contract.
+    pub held: u64,
+    /// The balance held less the total balance of the accounts, the
+    /// withdrawals awaiting claim and the storage fees - positive if the
+    /// contract holds more than it accounts for, and negative if less.
+    pub delta: i128,
+}
+
+/// The usage of a deployment of the contract, as returned by the `stats`
+/// query.
+///
+/// Only calls that succeed are counted, along with the signatures they
+/// verified. The counts start anew with each deployment, being left out of
+/// migrations.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct UsageStats {
+    /// The number of calls of each entrypoint changing the state, ordered by
+    /// name. Entrypoints never called are left out.
+    pub calls: Vec<EntrypointCalls>,
+    /// The number of BLS keys signatures were verified against, a group key
+    /// counting as one.
+    pub keys_verified: u64,
+    /// The number of co-signatures verified.
+    pub cosignatures_verified: u64,
+}
+
+impl UsageStats {
+    /// Returns the number of calls of an entrypoint.
+    pub fn calls_of(&self, entrypoint: &str) -> u64 {
+        self.calls
+            .iter()
+            .find(|c| c.entrypoint == entrypoint)
+            .map_or(0, |c| c.calls)
+    }
+
+    /// Returns the number of calls of all entrypoints together.
+    pub fn total_calls(&self) -> u64 {
+        self.calls.iter().map(|c| c.calls).sum()
+    }
+}
+
+/// The number of calls of an entrypoint of the contract, within
+/// [`UsageStats`].
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct EntrypointCalls {
+    /// The name of the entrypoint.
+    pub entrypoint: String,
+    /// The number of times it was called.
+    pub calls: u64,
+}
+
+/// A soft limit on the amount an account transfers within a window of blocks.
+///
+/// Transfers crossing the limit aren't refused, but have a
+/// [`VelocityWarningEvent`] emitted after them, giving monitoring an early
+/// signal of unusual outflows.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Archive, Serialize, Deserialize,
+)]
+#[archive_attr(derive(CheckBytes))]
+pub struct VelocityLimit {
+    /// The amount that may be transferred within a window without a warning.
+    pub amount: u64,
+    /// The length of a window, in blocks. Windows start at the multiples of
+    /// their length.
+    pub window: u64,
+}
+
+impl VelocityLimit {
+    /// Encodes the limit as its amount followed by its window, both as little
+    /// endian `u64`s.
+    pub fn to_bytes(&self) -> [u8; 16] {
+        let mut bytes = [0; 16];
+        bytes[..8].copy_from_slice(&self.amount.to_le_bytes());
+        bytes[8..].copy_from_slice(&self.window.to_le_bytes());
+        bytes
+    }
+}
+
+/// A category transfers out of an account can declare, with the most they may
+/// move under it within a window of blocks - a marketing budget per epoch,
+/// say.
+///
+/// Unlike the [`VelocityLimit`], the budget is a hard limit: a transfer taking
+/// the spending of its category past it is refused. Categories are named as
+/// tags are, see [`is_valid_tag`].
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct SpendingCategory {
+    /// The name of the category.
+    pub name: String,
+    /// The most that may be transferred under the category within a window.
+    pub budget: u64,
+    /// The length of a window, in blocks. Windows start at the multiples of
+    /// their length.
+    pub window: u64,
+}
+
+impl SpendingCategory {
+    /// Whether the category is named as a tag, with a window of at least a
+    /// block.
+    pub fn is_valid(&self) -> bool {
+        is_valid_tag(&self.name) && self.window > 0
+    }
+
+    /// Encodes the budget of the category followed by its window, both as
+    /// little endian `u64`s.
+    pub fn budget_bytes(&self) -> [u8; 16] {
+        let mut bytes = [0; 16];
+        bytes[..8].copy_from_slice(&self.budget.to_le_bytes());
+        bytes[8..].copy_from_slice(&self.window.to_le_bytes());
+        bytes
+    }
+}
+
+/// A budget a parent account allocates to a child account, which the child
+/// draws on with a [`DrawBudget`] without the parent's signatures.
+///
+/// The budget renews every window of blocks: the child may draw up to its
+/// amount within each. What the child drew and still holds can be clawed back
+/// by the parent with a [`AccountChange::ClawBackBudget`].
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Archive, Serialize, Deserialize,
+)]
+#[archive_attr(derive(CheckBytes))]
+pub struct ChildBudget {
+    /// The child account the budget is allocated to.
+    pub child_id: u64,
+    /// The most the child may draw within a window.
+    pub amount: u64,
+    /// The length of a window, in blocks. Windows start at the multiples of
+    /// their length.
+    pub window: u64,
+}
+
+impl ChildBudget {
+    /// Whether the budget has an amount and a window of at least a block.
+    pub fn is_valid(&self) -> bool {
+        self.amount > 0 && self.window > 0
+    }
+
+    /// Encodes the budget as its child's ID, amount and window, all as little
+    /// endian `u64`s.
+    pub fn to_bytes(&self) -> [u8; 24] {
+        let mut bytes = [0; 24];
+        bytes[..8].copy_from_slice(&self.child_id.to_le_bytes());
+        bytes[8..16].copy_from_slice(&self.amount.to_le_bytes());
+        bytes[16..].copy_from_slice(&self.window.to_le_bytes());
+        bytes
+    }
+}
+
+/// A requirement for the keys of an account to attest they're alive every
+/// window of blocks, so that lost keys don't leave the account unable to meet
+/// its threshold.
+///
+/// A key that hasn't attested within the last window is stale, and no longer
+/// counts among the signers of the account: the threshold is lowered to the
+/// number of signers left, but never below the minimum threshold.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Archive, Serialize, Deserialize,
+)]
+#[archive_attr(derive(CheckBytes))]
+pub struct LivenessPolicy {
+    /// The number of blocks a key stays live after attesting.
+    pub window: u64,
+    /// The lowest the threshold can be lowered to, however many keys are
+    /// stale.
+    pub min_threshold: u32,
+}
+
+impl LivenessPolicy {
+    /// Encodes the policy as its window as a little endian `u64`, followed by
+    /// its minimum threshold as a little endian `u32`.
+    pub fn to_bytes(&self) -> [u8; 12] {
+        let mut bytes = [0; 12];
+        bytes[..8].copy_from_slice(&self.window.to_le_bytes());
+        bytes[8..].copy_from_slice(&self.min_threshold.to_le_bytes());
+        bytes
+    }
+}
+
+/// The share of the signers of an account needed to change its threshold, its
+/// key addition policy, its transfer window or the supermajority itself,
+/// making such changes harder than spending. It also overrides the transfer
+/// window.
+///
+/// The share is taken of the keys and co-signers of the account, rounding up,
+/// but never needs fewer signers than the threshold does - however many keys
+/// are stale under a liveness policy. A change signed by the group key of the
+/// account is left to the threshold of its scheme.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Archive, Serialize, Deserialize,
+)]
+#[archive_attr(derive(CheckBytes))]
+pub struct Supermajority {
+    /// The numerator of the share.
+    pub numerator: u32,
+    /// The denominator of the share.
+    pub denominator: u32,
+}
+
+impl Supermajority {
+    /// Whether the share is more than half, and at most all, of the signers.
+    pub fn is_valid(&self) -> bool {
+        self.numerator <= self.denominator
+            && 2 * self.numerator as u64 > self.denominator as u64
+    }
+
+    /// Returns the number of signers the share is of the given number of
+    /// signers, rounding up.
+    pub fn of(&self, signers: usize) -> usize {
+        (signers as u64 * self.numerator as u64)
+            .div_ceil(self.denominator as u64) as usize
+    }
+
+    /// Encodes the share as its numerator followed by its denominator, both
+    /// as little endian `u32`s.
+    pub fn to_bytes(&self) -> [u8; 8] {
+        let mut bytes = [0; 8];
+        bytes[..4].copy_from_slice(&self.numerator.to_le_bytes());
+        bytes[4..].copy_from_slice(&self.denominator.to_le_bytes());
+        bytes
+    }
+}
+
+/// A bar for adding keys to an account above its threshold, so that a
+/// coalition meeting only the threshold can't instantly pack the account with
+/// keys it controls.
+///
+/// The bar applies to the keys taken over by merging another account into it,
+/// as it does to those added by a change.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Archive, Serialize, Deserialize,
+)]
+#[archive_attr(derive(CheckBytes))]
+pub struct KeyAdditionPolicy {
+    /// The number of blocks a key added to the account waits before it can
+    /// sign, giving the other signers time to notice and remove it.
+    pub delay: u64,
+    /// Whether adding keys needs the [`Supermajority`] of the account, rather
+    /// than its threshold.
+    pub supermajority: bool,
+}
+
+impl KeyAdditionPolicy {
+    /// Encodes the policy as its delay as a little endian `u64`, followed by
+    /// whether it needs the supermajority as a byte.
+    pub fn to_bytes(&self) -> [u8; 9] {
+        let mut bytes = [0; 9];
+        bytes[..8].copy_from_slice(&self.delay.to_le_bytes());
+        bytes[8] = self.supermajority as u8;
+        bytes
+    }
+}
+
+/// The terms under which the guardians of an account can recover it,
+/// replacing its keys and threshold should their holders lose access to them.
+///
+/// A recovery signed by enough guardians can be finalized once the delay
+/// passes, giving the holders time to cancel it with any key they still hold.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Archive, Serialize, Deserialize,
+)]
+#[archive_attr(derive(CheckBytes))]
+pub struct RecoveryPolicy {
+    /// The number of blocks a recovery waits before it can be finalized.
+    pub delay: u64,
+    /// The number of guardians that must sign to start a recovery.
+    pub guardians: u32,
+}
+
+impl RecoveryPolicy {
+    /// Encodes the policy as its delay as a little endian `u64`, followed by
+    /// its number of guardians as a little endian `u32`.
+    pub fn to_bytes(&self) -> [u8; 12] {
+        let mut bytes = [0; 12];
+        bytes[..8].copy_from_slice(&self.delay.to_le_bytes());
+        bytes[8..].copy_from_slice(&self.guardians.to_le_bytes());
+        bytes
+    }
+}
+
+/// A recovery of an account started by its guardians, awaiting its delay.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct PendingRecovery {
+    /// The guardians that started the recovery.
+    pub guardians: Vec<bls::PublicKey>,
+    /// The keys to replace those of the account with.
+    pub keys: Vec<bls::PublicKey>,
+    /// The threshold to replace that of the account with.
+    pub threshold: u32,
+    /// The height of the first block the recovery can be finalized in.
+    pub executable_from: u64,
+}
+
+/// A fallback key designated by an account, that can claim the account once
+/// it performs no operation for a number of blocks - a dead man's switch for
+/// keys lost all at once.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct InactivityPolicy {
+    /// The key that can claim the account.
+    pub fallback: bls::PublicKey,
+    /// The number of blocks without an operation after which the account can
+    /// be claimed.
+    pub period: u64,
+}
+
+impl InactivityPolicy {
+    /// Encodes the policy as the raw bytes of its fallback key, followed by
+    /// its period as a little endian `u64`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::from(self.fallback.to_raw_bytes());
+        bytes.extend(self.period.to_le_bytes());
+        bytes
+    }
+}
+
+/// A policy holding back the large transfers of an account, so that its keys
+/// can call off one made against their will.
+///
+/// Transfers of at least `min_amount` must be queued, and can be executed
+/// once `delay` blocks pass, unless any key of the account cancels them in
+/// the meantime, for [`QUEUE_EXPIRY`] blocks more. Payrolls totalling as much,
+/// and transfers, splits, merges and budget draws of as much made by other
+/// means, are refused.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Archive, Serialize, Deserialize,
+)]
+#[archive_attr(derive(CheckBytes))]
+pub struct QueuePolicy {
+    /// The smallest amount of a transfer that must be queued.
+    pub min_amount: u64,
+    /// The number of blocks a queued transfer waits before it can be
+    /// executed.
+    pub delay: u64,
+}
+
+impl QueuePolicy {
+    /// Encodes the policy as its minimum amount followed by its delay, both
+    /// as little endian `u64`s.
+    pub fn to_bytes(&self) -> [u8; 16] {
+        let mut bytes = [0; 16];
+        bytes[..8].copy_from_slice(&self.min_amount.to_le_bytes());
+        bytes[8..].copy_from_slice(&self.delay.to_le_bytes());
+        bytes
+    }
+}
+
+/// Number of blocks a queued transfer can be executed for once its delay
+/// passed, after which it expires.
+///
+/// The expiry keeps a transfer the signers moved on from from being paid out
+/// long after, once the account is funded again.
+pub const QUEUE_EXPIRY: u64 = 60_480;
+
+/// A transfer queued by an account, awaiting the delay of its
+/// [`QueuePolicy`].
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct QueuedTransfer {
+    /// The nonce the transfer used up when it was queued, by which it's
+    /// known.
+    pub nonce: u64,
+    /// The keys that signed the transfer.
+    pub keys: Vec<bls::PublicKey>,
+    /// The co-signers that signed the transfer.
+    pub cosigners: Vec<CosignerKey>,
+    /// Whether the transfer was signed by the group key of the account.
+    pub group_signed: bool,
+    /// The Moonlight account to transfer to.
+    pub receiver: bls::PublicKey,
+    /// The amount to transfer.
+    pub amount: u64,
+    /// The memo of the transfer.
+    pub memo: String,
+    /// The reference of the transfer, if any.
+    pub reference: Option<[u8; 32]>,
+    /// The spending category the transfer counts against once executed, if
+    /// any.
+    pub category: Option<String>,
+    /// The height of the first block the transfer can be executed in.
+    pub executable_from: u64,
+}
+
+/// A recurring window of blocks the transfers of an account are restricted
+/// to, as an operational control - office hours, say, reckoned in blocks.
+///
+/// The window opens `offset` blocks into every period of `period` blocks, and
+/// stays open for `length` blocks, possibly into the next period. Transfers,
+/// payrolls and splits outside it need the [`Supermajority`] of the account.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Archive, Serialize, Deserialize,
+)]
+#[archive_attr(derive(CheckBytes))]
+pub struct TransferWindow {
+    /// The number of blocks the window recurs every.
+    pub period: u64,
+    /// The number of blocks into each period the window opens at.
+    pub offset: u64,
+    /// The number of blocks the window stays open for.
+    pub length: u64,
+}
+
+impl TransferWindow {
+    /// Whether the window opens within every period, and stays open for at
+    /// least a block but no longer than the period.
+    pub fn is_valid(&self) -> bool {
+        self.offset < self.period
+            && 0 < self.length
+            && self.length <= self.period
+    }
+
+    /// Whether the window is open at the given block height.
+    pub fn contains(&self, block_height: u64) -> bool {
+        let position = block_height % self.period;
+        let into_window = match position.checked_sub(self.offset) {
+            Some(into_window) => into_window,
+            None => position + (self.period - self.offset),
+        };
+        into_window < self.length
+    }
+
+    /// Encodes the window as its period, offset and length, all as little
+    /// endian `u64`s.
+    pub fn to_bytes(&self) -> [u8; 24] {
+        let mut bytes = [0; 24];
+        bytes[..8].copy_from_slice(&self.period.to_le_bytes());
+        bytes[8..16].copy_from_slice(&self.offset.to_le_bytes());
+        bytes[16..].copy_from_slice(&self.length.to_le_bytes());
+        bytes
+    }
+}
+
+/// A compliance key designated by an account, along with the remediation
+/// address the account can still transfer to while the key holds it.
+///
+/// Regulated custodians use this to freeze an account's funds in place,
+/// short of sending them to the remediation address, pending review.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct ComplianceControl {
+    /// The key that can place the account on hold.
+    pub key: bls::PublicKey,
+    /// The Moonlight account the account can transfer to while on hold.
+    pub remediation: bls::PublicKey,
+}
+
+impl ComplianceControl {
+    /// Encodes the control as the raw bytes of its key, followed by those of
+    /// its remediation address.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::from(self.key.to_raw_bytes());
+        bytes.extend(self.remediation.to_raw_bytes());
+        bytes
+    }
+}
+
+/// The name of the query of a license contract attesting a key holds a valid
+/// license, taking the key and returning a `bool`.
+pub const LICENSED_QUERY: &str = "licensed";
+
+/// A license contract the receivers of an account's transfers must be
+/// licensed by, as by its [`LICENSED_QUERY`], for KYC-gated treasuries.
+///
+/// Citadel licenses are proven in zero knowledge, so the contract is
+/// expected to be one attesting the keys that proved a license to it, rather
+/// than the license contract itself. A contract failing the query is taken
+/// as not licensing the key.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Archive, Serialize, Deserialize,
+)]
+#[archive_attr(derive(CheckBytes))]
+pub struct LicensePolicy {
+    /// The ID of the license contract.
+    pub contract: [u8; 32],
+    /// Whether the keys of the account must be licensed too, both those it
+    /// holds when the policy is set and those added after.
+    pub signers: bool,
+}
+
+impl LicensePolicy {
+    /// Encodes the policy as the ID of its contract followed by a byte
+    /// flagging whether keys must be licensed.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::from(self.contract);
+        bytes.push(self.signers as u8);
+        bytes
+    }
+}
+
+/// A key added to an account under a delay, with the height it becomes usable
+/// from.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct PendingKey {
+    /// The key of the account.
+    pub key: bls::PublicKey,
+    /// Height of the first block the key can sign in.
+    pub usable_from: u64,
+}
+
+/// A key of an account that lapses, with the height of the last block it can
+/// sign in.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct KeyExpiry {
+    /// The key of the account.
+    pub key: bls::PublicKey,
+    /// Height of the last block the key can sign in.
+    pub valid_until: u64,
+}
+
+impl KeyExpiry {
+    /// Returns true if the key can no longer sign at the given height.
+    pub fn is_lapsed(&self, block_height: u64) -> bool {
+        block_height > self.valid_until
+    }
+}
+
+/// The weight of a key of an account, the number of signers it counts for
+/// towards the thresholds of the account.
+///
+/// Keys weigh one unless given another weight, as do co-signers and duress
+/// keys, and a delegate signs with the weight of the key it signs for.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct KeyWeight {
+    /// The key of the account.
+    pub key: bls::PublicKey,
+    /// The number of signers the key counts for.
+    pub weight: u32,
+}
+
+/// The role of a key of an account, bounding the kinds of operations it can
+/// sign.
+///
+/// A delegate signs with the role of the key it signs for, while co-signers
+/// and the group key can sign any operation.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Archive,
+    Serialize,
+    Deserialize,
+)]
+#[archive_attr(derive(CheckBytes))]
+pub enum KeyRole {
+    /// Signs any operation, the role of keys given no other.
+    Admin = 0,
+    /// Signs only operations moving funds out of the account - transfers,
+    /// payrolls and draws on budgets - and never changes to it.
+    Spender = 1,
+}
+
+impl KeyRole {
+    /// All the roles, in the order of their tags.
+    pub const ALL: [Self; 2] = [Self::Admin, Self::Spender];
+
+    /// Returns true if keys of the role can sign operations of the given
+    /// kind.
+    pub fn can_sign(&self, op: OperationKind) -> bool {
+        match self {
+            Self::Admin => true,
+            Self::Spender => matches!(
+                op,
+                OperationKind::Transfer
+                    | OperationKind::Payroll
+                    | OperationKind::DrawBudget
+            ),
+        }
+    }
+
+    /// The name of the role, as the CLI takes it.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Admin => "admin",
+            Self::Spender => "spender",
+        }
+    }
+}
+
+/// The role given to a key of an account.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct RoleAssignment {
+    /// The key of the account.
+    pub key: bls::PublicKey,
+    /// The role of the key.
+    pub role: KeyRole,
+}
+
+/// What is left of the allowance of a key of an account, which it can
+/// transfer on its own signature.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct KeyAllowance {
+    /// The key of the account.
+    pub key: bls::PublicKey,
+    /// The amount left for the key to transfer.
+    pub amount: u64,
+}
+
+/// How many operations a key of an account signed, and the height of the
+/// block it last signed in.
+///
+/// A delegate signing in place of the key counts for the key.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct KeyUsage {
+    /// The key of the account.
+    pub key: bls::PublicKey,
+    /// The number of operations the key signed.
+    pub operations: u64,
+    /// Height of the last block the key signed in, if it ever did.
+    pub last_signed: Option<u64>,
+}
+
+impl KeyUsage {
+    /// Returns true if the key signed nothing from the given height on.
+    pub fn is_inactive_since(&self, block_height: u64) -> bool {
+        self.last_signed.is_none_or(|h| h < block_height)
+    }
+}
+
+/// The balance, nonce and keys of an account as of a checkpoint, recorded for
+/// auditors to verify the event history of the account against.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct Checkpoint {
+    /// The balance of the account.
+    pub balance: u64,
+    /// The nonce of the account, the checkpoint's own included.
+    pub nonce: u64,
+    /// The hash of the keys of the account, as computed by [`hash_keys`].
+    pub keys_hash: [u8; 32],
+    /// Height of the block the checkpoint was recorded in.
+    pub block_height: u64,
+    /// Sequence number of the event emitted with the checkpoint, placing it
+    /// among the events of the contract.
+    pub sequence: u64,
+}
+
+/// The state of an account as an operation using up one of its nonces left
+/// it, kept for its state between two nonces to be diffed by the
+/// `state_diff` query.
+///
+/// Operations are retained as movements are, so the oldest are dropped past
+/// the history retention of the account. The creation of an account is
+/// recorded under nonce zero, adding its keys.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct OperationRecord {
+    /// The nonce the operation used up.
+    pub nonce: u64,
+    /// Height of the block the operation was made in.
+    pub block_height: u64,
+    /// The balance of the account after the operation.
+    pub balance: u64,
+    /// The threshold of the account after the operation.
+    pub threshold: u32,
+    /// The keys the operation added to the account.
+    pub added_keys: Vec<bls::PublicKey>,
+    /// The keys the operation removed from the account.
+    pub removed_keys: Vec<bls::PublicKey>,
+}
+
+/// The argument of the `state_diff` query, selecting the operations of an
+/// account after one nonce and up to another.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct StateDiffQuery {
+    /// The account to diff.
+    pub account_id: u64,
+    /// The nonce to diff from.
+    pub from_nonce: u64,
+    /// The nonce to diff to.
+    pub to_nonce: u64,
+}
+
+/// What changed in an account between two of its nonces, as returned by the
+/// `state_diff` query.
+///
+/// The balance is taken as each operation left it, so the difference also
+/// counts the deposits and other funds moved in between.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct StateDiff {
+    /// The balance of the account as of the first nonce.
+    pub balance_before: u64,
+    /// The balance of the account as of the second nonce.
+    pub balance_after: u64,
+    /// The threshold of the account as of the first nonce.
+    pub threshold_before: u32,
+    /// The threshold of the account as of the second nonce.
+    pub threshold_after: u32,
+    /// The keys of the account as of the second nonce that it didn't have as
+    /// of the first.
+    pub added_keys: Vec<bls::PublicKey>,
+    /// The keys of the account as of the first nonce that it no longer had
+    /// as of the second.
+    pub removed_keys: Vec<bls::PublicKey>,
+}
+
+impl StateDiff {
+    /// Returns the change in the balance of the account between the nonces.
+    pub fn balance_delta(&self) -> i128 {
+        self.balance_after as i128 - self.balance_before as i128
+    }
+}
+
+/// The outcome of a change to an account, as previewed by the
+/// `simulate_change` query without it being signed.
+///
+/// The keys listed in the change are taken as its signers, their signature
+/// left unchecked.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct ChangeSimulation {
+    /// The keys the account would have after the change.
+    pub keys: Vec<bls::PublicKey>,
+    /// The threshold the account would have after the change.
+    pub threshold: u32,
+    /// The errors the change would be rejected with, in the order they're
+    /// met, with each listed once.
+    pub violations: Vec<Error>,
+}
+
+impl ChangeSimulation {
+    /// Returns if the change would be accepted, once signed.
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// A withdrawal recorded by a transfer of an account with claimable
+/// withdrawals, held by the contract until its receiver claims it.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct PendingWithdrawal {
+    /// The ID the withdrawal is claimed by, numbered from one.
+    pub claim_id: u64,
+    /// The account the withdrawal was made from.
+    pub account_id: u64,
+    /// The Moonlight account entitled to the withdrawal.
+    pub receiver: bls::PublicKey,
+    /// The amount withdrawn.
+    pub amount: u64,
+    /// Height of the block the withdrawal was recorded in.
+    pub block_height: u64,
+}
+
+/// The amount transferred under a spending category of an account within the
+/// current window of its budget.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct CategorySpend {
+    /// The name of the category.
+    pub name: String,
+    /// The amount transferred under the category within the current window.
+    pub spent: u64,
+}
+
+/// The draws of a child account on the budget its parent allocated to it.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct BudgetDraw {
+    /// The child account.
+    pub child_id: u64,
+    /// The amount the child drew within the current window of its budget.
+    pub drawn: u64,
+    /// The amount the child drew and the parent hasn't clawed back, which is
+    /// the most that can be clawed back.
+    pub held: u64,
+}
+
+/// The height of the last attestation of a key, as of which it's live.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct KeyLiveness {
+    /// The key of the account.
+    pub key: bls::PublicKey,
+    /// Height of the block the key last attested as of, or that the policy
+    /// was set or the key added in, if later.
+    pub attested_at: u64,
+}
+
+/// The signing power of a key of an account, handed to another key for a range
+/// of blocks.
+///
+/// Within the range, the delegate signs for the account in place of the key,
+/// counting towards the threshold as the key would. The key itself can still
+/// sign, but never together with its delegate.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct Delegation {
+    /// The key signing in place of the delegating key.
+    pub delegate: bls::PublicKey,
+    /// Height of the first block the delegate can sign in.
+    pub from_height: u64,
+    /// Height of the last block the delegate can sign in.
+    pub until_height: u64,
+}
+
+impl Delegation {
+    /// Returns whether the delegate can sign in the block with the given
+    /// height.
+    pub fn is_active(&self, block_height: u64) -> bool {
+        (self.from_height..=self.until_height).contains(&block_height)
+    }
+}
+
+/// The delegation registered by a key of an account, together with the nonce
+/// of the last one.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct KeyDelegation {
+    /// The key of the account.
+    pub key: bls::PublicKey,
+    /// The delegation in place, if it hasn't been revoked.
+    pub delegation: Option<Delegation>,
+    /// The nonce of the last delegation or revocation, with the next one
+    /// using the nonce after it.
+    pub nonce: u64,
+}
+
+/// Number of blocks after which a whitelisted receiver can be transferred to.
+///
+/// The delay gives the owners of an account time to notice, and react to, a
+/// receiver being whitelisted by a compromised quorum, before funds can be
+/// sent to it.
+pub const WHITELIST_DELAY: u64 = 8_640;
+
+/// A receiver whitelisted by an account.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct WhitelistEntry {
+    /// The Moonlight account that can be transferred to.
+    pub receiver: bls::PublicKey,
+    /// Height of the first block in which the receiver can be transferred to.
+    pub usable_from: u64,
+}
+
+/// A movement of funds in or out of an account, as kept in its ledger for
+/// bookkeeping.
+///
+/// Movements are fed by the `movements` query in the order they happened,
+/// with every field of a fixed size so that they can be dumped as flat
+/// records. Genesis balances aren't movements.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct Movement {
+    /// Height of the block the movement happened in.
+    pub block_height: u64,
+    /// Sequence number of the event emitted with the movement, which salts
+    /// the hashes of the movement.
+    pub sequence: u64,
+    /// Whether the funds came in or went out.
+    pub direction: Direction,
+    /// The other side of the movement.
+    pub counterparty: Counterparty,
+    /// The amount moved.
+    pub amount: u64,
+    /// The hash of the memo of the movement, as by [`hash_memo`], if it had
+    /// one.
+    pub memo_hash: Option<[u8; 32]>,
+    /// The balance of the account after the movement.
+    pub balance_after: u64,
+}
+
+/// The direction of a [`Movement`].
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Archive, Serialize, Deserialize,
+)]
+#[archive_attr(derive(CheckBytes))]
+pub enum Direction {
+    /// Funds came into the account.
+    In,
+    /// Funds went out of the account.
+    Out,
+}
+
+/// The other side of a [`Movement`].
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub enum Counterparty {
+    /// Whoever deposited to the account, who the contract doesn't know.
+    Depositor,
+    /// A Moonlight account paid by the account, hashed as by
+    /// [`hash_receiver`] for accounts with private events.
+    Moonlight(Disclosure<bls::PublicKey>),
+    /// Another multisig account, merged with or split off the account.
+    Account(u64),
+    /// The contract, keeping the storage fee of a raised history retention.
+    StorageFee,
+}
+
+/// The argument of the `movements` query, selecting the movements of an
+/// account within a range of blocks.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct MovementQuery {
+    /// The account whose movements to feed.
+    pub account_id: u64,
+    /// Height of the first block to feed the movements of.
+    pub from_height: u64,
+    /// Height of the last block to feed the movements of.
+    pub to_height: u64,
+}
+
+/// The argument of the `large_transfers` query, selecting the movements of
+/// funds into or out of the contract of at least an amount, within a range of
+/// blocks.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct LargeTransferQuery {
+    /// The smallest amount of the movements to feed.
+    pub min_amount: u64,
+    /// Height of the first block to feed the movements of.
+    pub from_height: u64,
+    /// Height of the last block to feed the movements of.
+    pub to_height: u64,
+}
+
+/// A movement of funds into or out of the contract, as fed by the
+/// `large_transfers` query.
+///
+/// Only movements still retained in the ledger of their account are fed, and
+/// the movements between accounts, or to the contract itself, are left out.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct LargeTransfer {
+    /// The account whose ledger the movement is in.
+    pub account_id: u64,
+    /// The movement itself.
+    pub movement: Movement,
+}
+
+/// Version of the schema of the events emitted by the contract.
+///
+/// - `1` - the first versioned events
+/// - `2` - deposit and transfer events carry the balance after them
+/// - `3` - account change events carry the keys that signed them
+/// - `4` - account change events carry the threshold before them
+/// - `5` - every event carries a contract-wide sequence number
+/// - `6` - memos and receivers may be hashed, for accounts with private events,
+///   and account change events carry whether they were made private
+/// - `7` - transfer and account change events carry the co-signers that signed
+///   them, and account change events the co-signers added and removed
+/// - `8` - transfer and account change events carry whether they were signed
+///   by a group key, and account change events the group key set
+/// - `9` - account change events carry the receivers whitelisted and removed
+///   from the whitelist
+/// - `10` - account change events carry the velocity limit set, and velocity
+///   warning events are emitted
+/// - `11` - account change events carry whether they unlocked the account, and
+///   account locked events are emitted
+/// - `12` - account change events carry the guardians added and removed, and
+///   guardian action events are emitted
+/// - `13` - account creation and change events carry the alias claimed
+/// - `14` - account merge events are emitted
+/// - `15` - account split events are emitted
+/// - `16` - payroll events are emitted
+/// - `17` - gas refund events are emitted
+/// - `18` - deposit events carry the depositor reference
+/// - `19` - change events tell whether they were made with a transfer
+/// - `20` - account creation events carry the template used, and template
+///   registration events are emitted
+/// - `21` - account change events carry the balance cap set
+/// - `22` - account change events carry the tags added and removed
+/// - `23` - transfer events carry the reference of the transfer
+/// - `24` - account change events carry the liveness policy set, and liveness
+///   attestation events are emitted
+/// - `25` - key delegation events are emitted
+/// - `26` - account change events carry the supermajority set
+/// - `27` - account change events carry the key addition policy set
+/// - `28` - account change events carry the transfer window set
+/// - `29` - transfer events carry the spending category of the transfer, and
+///   account change events the categories set and removed
+/// - `30` - account change events carry the observers added and removed
+/// - `31` - account change events carry the child budgets set and removed, and
+///   budget draw and claw back events are emitted
+/// - `32` - account change events carry the key expiries set and removed
+/// - `33` - checkpoint events are emitted
+/// - `34` - account change events carry whether they archived or reactivated
+///   the account
+/// - `35` - transfer events carry the claim ID of the withdrawal they record,
+///   account change events whether withdrawals are claimable, and withdrawal
+///   claim events are emitted
+/// - `36` - payout deferral events are emitted
+/// - `37` - account change events carry the history retention set and the
+///   storage fee paid for it
+/// - `38` - configuration events are emitted
+/// - `39` - account change events carry the compliance control set and
+///   whether they lifted a hold, and hold placed events are emitted
+/// - `40` - every event carries its deterministic ID
+/// - `41` - account change events carry the license policy set
+/// - `42` - account change events carry the operation thresholds set and
+///   removed
+/// - `43` - account change events carry the transfer tiers set
+/// - `44` - account creation and change events carry the key weights set
+/// - `45` - account creation and change events carry the key roles set
+/// - `46` - account change events carry the recovery policy set, and recovery
+///   events are emitted
+/// - `47` - account change events carry the inactivity policy set, and claims
+///   of inactive accounts are emitted
+/// - `48` - account change events carry the queue policy set, and queued
+///   transfers are emitted
+/// - `49` - account change events carry the allowances set, and transfers out
+///   of them are emitted
+/// - `50` - queued transfers the balance falls short of, and those that
+///   expired, are emitted
+pub const EVENT_VERSION: u32 = 50;
+
+/// Envelope every event is emitted in, tagging it with the version of its
+/// schema.
+///
+/// The event is boxed so that the archived envelope has the same size for
+/// every event, and every version. The version can then always be read from
+/// the end of a payload with [`event_version`], before attempting to decode
+/// it - allowing decoders to reject versions they don't know, rather than
+/// misreading them.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct EventEnvelope<T> {
+    /// The version of the event's schema.
+    pub version: u32,
+    /// The event.
+    pub event: Box<T>,
+}
+
+impl<T> EventEnvelope<T> {
+    /// Wraps an event in an envelope of the current version.
+    pub fn new(event: T) -> Self {
+        Self {
+            version: EVENT_VERSION,
+            event: Box::new(event),
+        }
+    }
+}
+
+/// Size of an archived [`EventEnvelope`], which always comes last in a
+/// payload.
+const ARCHIVED_ENVELOPE_SIZE: usize = 8;
+const _: () = assert!(
+    core::mem::size_of::<ArchivedEventEnvelope<CreateAccountEvent>>()
+        == ARCHIVED_ENVELOPE_SIZE
+);
+
+/// Returns the version of the event in the given payload, or `None` if it's
+/// too short to hold an envelope.
+pub fn event_version(payload: &[u8]) -> Option<u32> {
+    let start = payload.len().checked_sub(ARCHIVED_ENVELOPE_SIZE)?;
+    let version = payload[start..start + 4].try_into().ok()?;
+    Some(u32::from_le_bytes(version))
+}
+
+/// A value carried by an event, either as is or as a hash of it.
+///
+/// Accounts with private events have the memos, references and receivers of
+/// their events hashed, so that payee details don't leak on the public event
+/// stream. The full values remain in the signed operations, and anyone knowing
+/// them can check they match the event with [`hash_memo`], [`hash_reference`]
+/// and [`hash_receiver`].
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub enum Disclosure<T> {
+    /// The value itself.
+    Revealed(T),
+    /// The hash of the value.
+    Hashed([u8; 32]),
+}
+
+impl<T> Disclosure<T> {
+    /// Returns the value, if it was revealed.
+    pub fn revealed(&self) -> Option<&T> {
+        match self {
+            Self::Revealed(value) => Some(value),
+            Self::Hashed(_) => None,
+        }
+    }
+}
+
+/// Hashes the memo of the event with the given sequence number.
+///
+/// The sequence number salts the hash, so that the same memo can't be
+/// recognized across events. Short or predictable memos can still be guessed
+/// by hashing candidates, however.
+pub fn hash_memo(sequence: u64, memo: &str) -> [u8; 32] {
+    disclosure_hash(b"multisig-memo", sequence, memo.as_bytes())
+}
+
+/// Hashes the receiver of the event with the given sequence number.
+///
+/// As with [`hash_memo`], the hash is salted with the sequence number, but
+/// anyone holding a list of candidate receivers can still hash each of them to
+/// find a match.
+pub fn hash_receiver(sequence: u64, receiver: &bls::PublicKey) -> [u8; 32] {
+    disclosure_hash(b"multisig-receiver", sequence, &receiver.to_raw_bytes())
+}
+
+/// Hashes the reference of the transfer event with the given sequence number.
+///
+/// As with [`hash_memo`], the hash is salted with the sequence number.
+pub fn hash_reference(sequence: u64, reference: &[u8; 32]) -> [u8; 32] {
+    disclosure_hash(b"multisig-reference", sequence, reference)
+}
+
+/// Hashes the keys of an account, as fixed by a [`Checkpoint`].
+///
+/// The keys are hashed in the order of their raw bytes, so that the hash
+/// doesn't depend on the order they're given in.
+pub fn hash_keys(keys: &[bls::PublicKey]) -> [u8; 32] {
+    let mut keys: Vec<_> = keys.iter().map(|key| key.to_raw_bytes()).collect();
+    keys.sort();
+
+    let mut hash = Sha256::new();
+    hash.update(b"multisig-keys");
+    for key in keys {
+        hash.update(key);
+    }
+    hash.finalize().into()
+}
+
+/// Derives the ID of an event emitted by the contract with the given ID.
+///
+/// The ID hashes the topic of the event, the account it's about and the nonce
+/// of the account as of the event, together with the number of events of the
+/// same topic emitted about the account at that nonce before it. Unlike the
+/// sequence number, it doesn't depend on the events emitted about other
+/// accounts, so an event delivered again - after a reorg, or a retry - carries
+/// the same ID, letting indexers process each event exactly once.
+///
+/// Events about no account, such as the registration of a template or the
+/// marking of a compromised key, are derived with the account ID `0`, which no
+/// account has, and the nonce `0`.
+pub fn event_id(
+    contract_id: &[u8; 32],
+    topic: &str,
+    account_id: u64,
+    nonce: u64,
+    index: u32,
+) -> [u8; 32] {
+    let mut hash = Sha256::new();
+
+    hash.update(b"multisig-event");
+    hash.update(contract_id);
+    hash.update((topic.len() as u32).to_le_bytes());
+    hash.update(topic.as_bytes());
+    hash.update(account_id.to_le_bytes());
+    hash.update(nonce.to_le_bytes());
+    hash.update(index.to_le_bytes());
+
+    hash.finalize().into()
+}
+
+fn disclosure_hash(domain: &[u8], sequence: u64, value: &[u8]) -> [u8; 32] {
+    let mut hash = Sha256::new();
+
+    hash.update(domain);
+    hash.update(sequence.to_le_bytes());
+    hash.update(value);
+
+    hash.finalize().into()
+}
+
+/// Event emitted upon a successful account creation.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct CreateAccountEvent {
+    /// The ID of the account created.
+    pub account_id: u64,
+    /// Keys used by the account.
+    pub keys: Vec<bls::PublicKey>,
+    /// Number of keys that need to sign to effect an operation.
+    pub threshold: u32,
+    /// The description of the account.
+    pub description: String,
+    /// The alias claimed by the account, if any.
+    pub alias: Option<String>,
+    /// The keys of the account weighing other than one.
+    pub key_weights: Vec<KeyWeight>,
+    /// The keys of the account that aren't admins.
+    pub key_roles: Vec<RoleAssignment>,
+    /// The template the account was created under, if any, whose
+    /// [`RegisterTemplateEvent`] has the rest of its policy.
+    pub template_id: Option<u64>,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted upon the registration of an account template.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct RegisterTemplateEvent {
+    /// The ID of the template registered.
+    pub template_id: u64,
+    /// The template registered.
+    pub template: AccountTemplate,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted upon a successful deposit.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct DepositEvent {
+    /// The account deposited to.
+    pub account_id: u64,
+    /// Amount deposited.
+    pub amount: u64,
+    /// Memo included with the deposit, hashed if the account has private
+    /// events.
+    pub memo: Disclosure<String>,
+    /// Reference of the depositor included with the deposit, hashed as the
+    /// memo is if the account has private events.
+    pub depositor_reference: Disclosure<String>,
+    /// The nonce of the account at the time of the deposit, which deposits
+    /// leave untouched.
+    pub nonce: u64,
+    /// Height of the block the deposit was made in.
+    pub block_height: u64,
+    /// The balance of the account after the deposit.
+    pub balance_after: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted upon a successful transfer.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct TransferEvent {
+    /// The account that transferred.
+    pub account_id: u64,
+    /// Keys used to sign the transfer.
+    pub keys: Vec<bls::PublicKey>,
+    /// Co-signers that signed the transfer.
+    pub cosigners: Vec<CosignerKey>,
+    /// Whether the transfer was signed by the group key of the account, in
+    /// which case no keys or co-signers are listed.
+    pub group_signed: bool,
+    /// The receiver of the funds, hashed if the account has private events.
+    pub receiver: Disclosure<bls::PublicKey>,
+    /// Amount transferred.
+    pub amount: u64,
+    /// Memo included with the transfer, hashed if the account has private
+    /// events.
+    pub memo: Disclosure<String>,
+    /// Reference of the transfer, if it carried one, hashed if the account
+    /// has private events.
+    pub reference: Option<Disclosure<[u8; 32]>>,
+    /// The spending category the transfer was declared under, if any.
+    pub category: Option<String>,
+    /// The claim ID of the withdrawal the transfer recorded, if the account
+    /// has claimable withdrawals or the transfer contract refused the
+    /// payment, in which case the receiver is only paid once it claims it.
+    pub claim_id: Option<u64>,
+    /// The nonce of the transfer.
+    pub nonce: u64,
+    /// Height of the block the transfer was made in.
+    pub block_height: u64,
+    /// The balance of the account after the transfer.
+    pub balance_after: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted for each row of a successful payroll.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct PayrollEvent {
+    /// The account that paid.
+    pub account_id: u64,
+    /// Keys used to sign the payroll.
+    pub keys: Vec<bls::PublicKey>,
+    /// Co-signers that signed the payroll.
+    pub cosigners: Vec<CosignerKey>,
+    /// Whether the payroll was signed by the group key of the account, in
+    /// which case no keys or co-signers are listed.
+    pub group_signed: bool,
+    /// The index of the row in the payroll.
+    pub row: u32,
+    /// The number of rows in the payroll.
+    pub rows: u32,
+    /// The receiver of the row, hashed if the account has private events.
+    pub receiver: Disclosure<bls::PublicKey>,
+    /// Amount paid by the row.
+    pub amount: u64,
+    /// Memo of the row, hashed if the account has private events.
+    pub memo: Disclosure<String>,
+    /// The nonce of the payroll, shared by all of its rows.
+    pub nonce: u64,
+    /// Height of the block the payroll was made in.
+    pub block_height: u64,
+    /// The balance of the account after the row was paid.
+    pub balance_after: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted when the submitter of an operation is reimbursed its gas,
+/// right after the operation's own event.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct GasRefundEvent {
+    /// The account that reimbursed the submitter.
+    pub account_id: u64,
+    /// The submitter reimbursed, hashed if the account has private events.
+    pub submitter: Disclosure<bls::PublicKey>,
+    /// The amount reimbursed.
+    pub amount: u64,
+    /// The nonce of the operation the refund was designated by.
+    pub nonce: u64,
+    /// Height of the block the operation was made in.
+    pub block_height: u64,
+    /// The balance of the account after the refund.
+    pub balance_after: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted upon a successful account change.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct ChangeAccountEvent {
+    /// The account that changed.
+    pub account_id: u64,
+    /// Keys used to sign the change.
+    pub keys: Vec<bls::PublicKey>,
+    /// Co-signers that signed the change.
+    pub cosigners: Vec<CosignerKey>,
+    /// Whether the change was signed by the group key of the account, in
+    /// which case no keys or co-signers are listed.
+    pub group_signed: bool,
+    /// Keys added during the change.
+    pub added_keys: Vec<bls::PublicKey>,
+    /// Keys removed during the change.
+    pub removed_keys: Vec<bls::PublicKey>,
+    /// Co-signers added during the change.
+    pub added_cosigners: Vec<CosignerKey>,
+    /// Co-signers removed during the change.
+    pub removed_cosigners: Vec<CosignerKey>,
+    /// The threshold before the change.
+    pub previous_threshold: u32,
+    /// New threshold if changed.
     pub threshold: Option<u32>,
     /// New account description if changed.
     pub description: Option<String>,
+    /// Whether the account has private events, if changed.
+    pub private_events: Option<bool>,
+    /// The group key of the account if changed, being `Some(None)` when it
+    /// was removed.
+    pub group_key: Option<Option<bls::PublicKey>>,
+    /// Receivers whitelisted during the change.
+    pub whitelisted: Vec<WhitelistEntry>,
+    /// Receivers removed from the whitelist during the change.
+    pub unwhitelisted: Vec<bls::PublicKey>,
+    /// Whether the change lifted the whitelist enforcement, by removing the
+    /// last whitelisted receiver.
+    pub whitelist_lifted: bool,
+    /// The velocity limit set, if it was changed, with `None` inside if it
+    /// was removed.
+    pub velocity_limit: Option<Option<VelocityLimit>>,
+    /// Whether the change unlocked the account.
+    pub unlocked: bool,
+    /// Whether the change archived the account, or reactivated it with
+    /// `false`, if it did either.
+    pub archived: Option<bool>,
+    /// Whether the account has claimable withdrawals, if changed.
+    pub claimable_withdrawals: Option<bool>,
+    /// The history retention set, if changed, `None` within if it fell back
+    /// to the default.
+    pub history_retention: Option<Option<u32>>,
+    /// The storage fee paid out of the balance of the account for raising
+    /// its history retention, if any.
+    pub storage_fee: u64,
+    /// The compliance control set, if changed, `None` within if it was
+    /// dropped.
+    pub compliance: Option<Option<ComplianceControl>>,
+    /// Whether the change lifted the hold on the account.
+    pub hold_lifted: bool,
+    /// The license policy set, if changed, `None` within if it was dropped.
+    pub license_policy: Option<Option<LicensePolicy>>,
+    /// Thresholds set for operations of a kind during the change.
+    pub set_operation_thresholds: Vec<OperationThreshold>,
+    /// Kinds of operations whose thresholds were dropped during the change,
+    /// falling back to the threshold of the account.
+    pub removed_operation_thresholds: Vec<OperationKind>,
+    /// The transfer tiers set, if changed, empty if they were dropped.
+    pub transfer_tiers: Option<Vec<TransferTier>>,
+    /// Guardians added during the change.
+    pub added_guardians: Vec<bls::PublicKey>,
+    /// Guardians removed during the change.
+    pub removed_guardians: Vec<bls::PublicKey>,
+    /// Observers added during the change.
+    pub added_observers: Vec<bls::PublicKey>,
+    /// Observers removed during the change.
+    pub removed_observers: Vec<bls::PublicKey>,
+    /// The alias of the account if changed, being `Some(None)` when it was
+    /// released.
+    pub alias: Option<Option<String>>,
+    /// The balance cap set, if it was changed, with `None` inside if it was
+    /// removed.
+    pub balance_cap: Option<Option<u64>>,
+    /// Tags added during the change.
+    pub added_tags: Vec<String>,
+    /// Tags removed during the change.
+    pub removed_tags: Vec<String>,
+    /// Spending categories added or replaced during the change.
+    pub set_categories: Vec<SpendingCategory>,
+    /// Names of the spending categories removed during the change.
+    pub removed_categories: Vec<String>,
+    /// Budgets allocated to child accounts, or replaced, during the change.
+    pub set_child_budgets: Vec<ChildBudget>,
+    /// IDs of the child accounts whose budgets were removed during the change.
+    pub removed_child_budgets: Vec<u64>,
+    /// Keys whose expiry was set during the change.
+    pub set_key_expiries: Vec<KeyExpiry>,
+    /// Keys whose expiry was removed during the change.
+    pub removed_key_expiries: Vec<bls::PublicKey>,
+    /// Keys whose weight was set during the change.
+    pub set_key_weights: Vec<KeyWeight>,
+    /// Keys whose role was set during the change.
+    pub set_key_roles: Vec<RoleAssignment>,
+    /// Keys whose allowance was set during the change, with zero for those
+    /// taken away.
+    pub set_allowances: Vec<KeyAllowance>,
+    /// The liveness policy set, if it was changed, with `None` inside if it
+    /// was removed.
+    pub liveness_policy: Option<Option<LivenessPolicy>>,
+    /// The supermajority set, if it was changed, with `None` inside if it was
+    /// removed.
+    pub supermajority: Option<Option<Supermajority>>,
+    /// The key addition policy set, if it was changed, with `None` inside if
+    /// it was removed.
+    pub key_addition_policy: Option<Option<KeyAdditionPolicy>>,
+    /// The transfer window set, if it was changed, with `None` inside if it
+    /// was removed.
+    pub transfer_window: Option<Option<TransferWindow>>,
+    /// The recovery policy set, if it was changed, with `None` inside if it
+    /// was removed.
+    pub recovery_policy: Option<Option<RecoveryPolicy>>,
+    /// The inactivity policy set, if it was changed, with `None` inside if
+    /// it was removed.
+    pub inactivity_policy: Option<Option<InactivityPolicy>>,
+    /// The queue policy set, if it was changed, with `None` inside if it was
+    /// removed.
+    pub queue_policy: Option<Option<QueuePolicy>>,
+    /// Whether the change was made together with a transfer, by a
+    /// [`TransferAndChange`], in which case the `transfer` event right before
+    /// it carries the same nonce.
+    pub with_transfer: bool,
+    /// The nonce of the change.
+    pub nonce: u64,
+    /// Height of the block the change was made in.
+    pub block_height: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted after a transfer taking the outflow of an account within a
+/// window past its velocity limit.
+///
+/// It's emitted once per window, by the transfer crossing the limit.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct VelocityWarningEvent {
+    /// The account that crossed its limit.
+    pub account_id: u64,
+    /// The limit crossed.
+    pub limit: VelocityLimit,
+    /// Height of the first block of the window.
+    pub window_start: u64,
+    /// The amount transferred within the window, including the transfer that
+    /// crossed the limit.
+    pub outflow: u64,
+    /// Height of the block the limit was crossed in.
+    pub block_height: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted when a key locks the outgoing transfers of its account.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct AccountLockedEvent {
+    /// The account locked.
+    pub account_id: u64,
+    /// The key that locked it.
+    pub key: bls::PublicKey,
+    /// The nonce of the account, which locking leaves untouched.
+    pub nonce: u64,
+    /// Height of the block the account was locked in.
+    pub block_height: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted when the compliance key of an account places it on hold.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct HoldPlacedEvent {
+    /// The account placed on hold.
+    pub account_id: u64,
+    /// The compliance key that placed the hold.
+    pub key: bls::PublicKey,
+    /// The address the account can still transfer to.
+    pub remediation: bls::PublicKey,
+    /// The nonce of the account, which placing a hold leaves untouched.
+    pub nonce: u64,
+    /// Height of the block the account was placed on hold in.
+    pub block_height: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted when a key attests it's alive.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct LivenessAttestedEvent {
+    /// The account the key belongs to.
+    pub account_id: u64,
+    /// The key that attested.
+    pub key: bls::PublicKey,
+    /// Height of the block the key attested as of.
+    pub attested_at: u64,
+    /// Height of the block the attestation was made in.
+    pub block_height: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted when a key registers or revokes its delegation.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct KeyDelegatedEvent {
+    /// The account the key belongs to.
+    pub account_id: u64,
+    /// The key delegating.
+    pub key: bls::PublicKey,
+    /// The delegation registered, or `None` if it was revoked.
+    pub delegation: Option<Delegation>,
+    /// The delegation nonce of the key.
+    pub nonce: u64,
+    /// Height of the block the delegation was registered in.
+    pub block_height: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted when a guardian acts on an account.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct GuardianActionEvent {
+    /// The account acted on.
+    pub account_id: u64,
+    /// The guardian that acted.
+    pub key: bls::PublicKey,
+    /// The action taken.
+    pub action: GuardianAction,
+    /// The nonce of the account, which guardians leave untouched.
+    pub nonce: u64,
+    /// Height of the block the action was taken in.
+    pub block_height: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted when the guardians of an account start recovering it.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct RecoveryStartedEvent {
+    /// The account being recovered.
+    pub account_id: u64,
+    /// The recovery started.
+    pub recovery: PendingRecovery,
+    /// The recovery nonce the recovery used up.
+    pub nonce: u64,
+    /// Height of the block the recovery was started in.
+    pub block_height: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted when a key of an account cancels its recovery.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct RecoveryCancelledEvent {
+    /// The account whose recovery was cancelled.
+    pub account_id: u64,
+    /// The key that cancelled it.
+    pub key: bls::PublicKey,
+    /// The recovery nonce of the account, which cancelling leaves untouched.
+    pub nonce: u64,
+    /// Height of the block the recovery was cancelled in.
+    pub block_height: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted when the recovery of an account is finalized, replacing its
+/// keys and threshold.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct RecoveryFinalizedEvent {
+    /// The account recovered.
+    pub account_id: u64,
+    /// The keys the account had, now removed.
+    pub removed_keys: Vec<bls::PublicKey>,
+    /// The keys the account has now.
+    pub keys: Vec<bls::PublicKey>,
+    /// The threshold the account has now.
+    pub threshold: u32,
+    /// The nonce the recovery used up.
+    pub nonce: u64,
+    /// Height of the block the recovery was finalized in.
+    pub block_height: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted when the fallback key of an inactive account claims it.
+///
+/// A sweep is emitted right after the `transfer` event paying it out, which
+/// carries the same nonce.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct InactiveClaimedEvent {
+    /// The account claimed.
+    pub account_id: u64,
+    /// The fallback key that claimed it.
+    pub key: bls::PublicKey,
+    /// What the account was claimed for.
+    pub claim: InactiveClaim,
+    /// Height of the block the account last performed an operation in
+    /// before the claim.
+    pub last_active: u64,
+    /// The keys the claim removed from the account, if it replaced them.
+    pub removed_keys: Vec<bls::PublicKey>,
+    /// The nonce the claim used up.
+    pub nonce: u64,
+    /// Height of the block the account was claimed in.
+    pub block_height: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted when an account queues a transfer.
+///
+/// The receiver, memo and reference are left to the `transfer` event
+/// emitted once the transfer is executed, which carries the same nonce.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct TransferQueuedEvent {
+    /// The account that queued the transfer.
+    pub account_id: u64,
+    /// The keys that signed the transfer.
+    pub keys: Vec<bls::PublicKey>,
+    /// The co-signers that signed the transfer.
+    pub cosigners: Vec<CosignerKey>,
+    /// Whether the transfer was signed by the group key of the account.
+    pub group_signed: bool,
+    /// The amount to transfer.
+    pub amount: u64,
+    /// The height of the first block the transfer can be executed in.
+    pub executable_from: u64,
+    /// The nonce the transfer used up.
+    pub nonce: u64,
+    /// Height of the block the transfer was queued in.
+    pub block_height: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted when a key of an account cancels a transfer it queued.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct QueuedCancelledEvent {
+    /// The account that queued the transfer.
+    pub account_id: u64,
+    /// The key that cancelled it.
+    pub key: bls::PublicKey,
+    /// The nonce of the transfer cancelled.
+    pub nonce: u64,
+    /// Height of the block the transfer was cancelled in.
+    pub block_height: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted when a queued transfer is executed, right after the
+/// `transfer` event paying it out, which carries the same nonce.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct QueuedExecutedEvent {
+    /// The account that queued the transfer.
+    pub account_id: u64,
+    /// The nonce of the transfer executed.
+    pub nonce: u64,
+    /// Height of the block the transfer was executed in.
+    pub block_height: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted when a queued transfer is due, but the balance of its account
+/// falls short of it. The transfer stays queued until it expires.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct QueuedSkippedEvent {
+    /// The account that queued the transfer.
+    pub account_id: u64,
+    /// The nonce of the transfer skipped.
+    pub nonce: u64,
+    /// The amount of the transfer.
+    pub amount: u64,
+    /// The balance of the account, short of the amount.
+    pub balance: u64,
+    /// Height of the block the transfer was skipped in.
+    pub block_height: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted when a queued transfer is dropped, having gone unexecuted
+/// for [`QUEUE_EXPIRY`] blocks past its delay.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct QueuedExpiredEvent {
+    /// The account that queued the transfer.
+    pub account_id: u64,
+    /// The nonce of the transfer expired.
+    pub nonce: u64,
+    /// Height of the block the transfer was dropped in.
+    pub block_height: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted when a key of an account transfers out of its allowance,
+/// right after the `transfer` event paying it out, which carries the same
+/// nonce.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct AllowanceSpentEvent {
+    /// The account transferred from.
+    pub account_id: u64,
+    /// The key that transferred.
+    pub key: bls::PublicKey,
+    /// The amount transferred.
+    pub amount: u64,
+    /// What is left of the allowance of the key.
+    pub remaining: u64,
+    /// The nonce of the transfer.
+    pub nonce: u64,
+    /// Height of the block the transfer was made in.
+    pub block_height: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted when an account is merged into another.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct MergeAccountsEvent {
+    /// The account that remains.
+    pub account_id: u64,
+    /// The account merged, and retired.
+    pub merged_id: u64,
+    /// The balance moved from the merged account.
+    pub amount: u64,
+    /// The keys of the merged account that the remaining one didn't have.
+    pub added_keys: Vec<bls::PublicKey>,
+    /// The co-signers of the merged account that the remaining one didn't
+    /// have.
+    pub added_cosigners: Vec<CosignerKey>,
+    /// The balance of the remaining account after the merge.
+    pub balance_after: u64,
+    /// The nonce used for the remaining account.
+    pub nonce: u64,
+    /// The nonce used for the merged account.
+    pub merged_nonce: u64,
+    /// Height of the block the merge was made in.
+    pub block_height: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted when a new account is split off an account.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct SplitAccountEvent {
+    /// The account split.
+    pub account_id: u64,
+    /// The account split off it.
+    pub new_account_id: u64,
+    /// The balance moved to the new account.
+    pub amount: u64,
+    /// The balance of the account split after the split.
+    pub balance_after: u64,
+    /// The nonce used for the split.
+    pub nonce: u64,
+    /// Height of the block the split was made in.
+    pub block_height: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted when a child account draws on the budget its parent
+/// allocated to it.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct BudgetDrawnEvent {
+    /// The child account drawing.
+    pub account_id: u64,
+    /// The parent account drawn on.
+    pub parent_id: u64,
+    /// The amount drawn.
+    pub amount: u64,
+    /// The balance of the child account after the draw.
+    pub balance_after: u64,
+    /// The balance of the parent account after the draw.
+    pub parent_balance_after: u64,
+    /// The nonce of the child account used for the draw.
+    pub nonce: u64,
+    /// Height of the block the draw was made in.
+    pub block_height: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted when a parent account claws back what a child account drew
+/// from it and still held.
+///
+/// It follows the `change_account` event of the change clawing back, which
+/// carries the same nonce.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct BudgetClawedBackEvent {
+    /// The parent account clawing back.
+    pub account_id: u64,
+    /// The child account clawed back from.
+    pub child_id: u64,
+    /// The amount clawed back.
+    pub amount: u64,
+    /// The balance of the parent account after the claw back.
+    pub balance_after: u64,
+    /// The balance of the child account after the claw back.
+    pub child_balance_after: u64,
+    /// The nonce of the change clawing back.
+    pub nonce: u64,
+    /// Height of the block the claw back was made in.
+    pub block_height: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted when the governance account sets the contract-wide
+/// parameters.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct ConfigureEvent {
+    /// The governance account.
+    pub account_id: u64,
+    /// The keys that signed the configuration.
+    pub keys: Vec<bls::PublicKey>,
+    /// The co-signers that signed the configuration.
+    pub cosigners: Vec<CosignerKey>,
+    /// Whether the configuration was signed by the group key of the account.
+    pub group_signed: bool,
+    /// The parameters set.
+    pub config: ContractConfig,
+    /// The nonce of the configuration.
+    pub nonce: u64,
+    /// Height of the block the parameters were set in.
+    pub block_height: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted when a checkpoint of an account is recorded.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct CheckpointEvent {
+    /// The account the checkpoint is of.
+    pub account_id: u64,
+    /// The checkpoint, carrying the height and sequence number of the event.
+    pub checkpoint: Checkpoint,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted when the receiver of a withdrawal claims it, and is paid.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct WithdrawalClaimedEvent {
+    /// The claim ID of the withdrawal, as carried by the `transfer` or
+    /// `payout_deferred` event that recorded it.
+    pub claim_id: u64,
+    /// The account the withdrawal was made from.
+    pub account_id: u64,
+    /// The receiver paid, hashed if the account has private events.
+    pub receiver: Disclosure<bls::PublicKey>,
+    /// The amount paid.
+    pub amount: u64,
+    /// Height of the block the withdrawal was claimed in.
+    pub block_height: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted when the transfer contract refuses a payment out of an
+/// account, which is recorded as a withdrawal for the receiver to claim
+/// instead.
+///
+/// It's emitted right before the event of the payment, be it a transfer, a
+/// payroll row or a gas refund.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct PayoutDeferredEvent {
+    /// The claim ID of the withdrawal recorded.
+    pub claim_id: u64,
+    /// The account the payment was made from.
+    pub account_id: u64,
+    /// The receiver of the payment, hashed if the account has private
+    /// events.
+    pub receiver: Disclosure<bls::PublicKey>,
+    /// The amount deferred.
+    pub amount: u64,
+    /// Height of the block the payment was made in.
+    pub block_height: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted when a key is marked as compromised.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct KeyCompromisedEvent {
+    /// The compromised key.
+    pub key: bls::PublicKey,
+    /// The accounts using the key, which should rotate it out.
+    pub account_ids: Vec<u64>,
+    /// Height of the block the key was marked in.
+    pub block_height: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Event emitted when a co-signer key is marked as compromised.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct CosignerCompromisedEvent {
+    /// The compromised co-signer key.
+    pub key: CosignerKey,
+    /// The accounts using the key, which should remove it.
+    pub account_ids: Vec<u64>,
+    /// Height of the block the key was marked in.
+    pub block_height: u64,
+    /// Sequence number of the event, incremented with every event the
+    /// contract emits.
+    pub sequence: u64,
+    /// Deterministic ID of the event, as derived by [`event_id`].
+    pub event_id: [u8; 32],
+}
+
+/// Errors the contract may fail with.
+///
+/// The contract panics with the [`Display`] representation of the error, which
+/// allows callers to know exactly why an operation was rejected.
+///
+/// New errors are only ever added last, so that those reported by queries
+/// keep decoding the same.
+///
+/// [`Display`]: fmt::Display
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Archive, Serialize, Deserialize,
+)]
+#[archive_attr(derive(CheckBytes))]
+pub enum Error {
+    /// The account doesn't exist.
+    AccountNotFound,
+    /// An account must be created with at least one key.
+    NoKeys,
+    /// The threshold must be at least one.
+    ZeroThreshold,
+    /// The threshold can't be larger than the number of keys in an account.
+    ThresholdTooLarge,
+    /// The same key was used more than once.
+    DuplicateKey,
+    /// A signing key is not used by the account.
+    UnknownKey,
+    /// Not enough keys signed the operation.
+    ThresholdNotMet,
+    /// The signature is invalid for the given keys and message.
+    InvalidSignature,
+    /// The nonce must be the current value of the account incremented.
+    InvalidNonce,
+    /// The account doesn't have enough balance.
+    InsufficientBalance,
+    /// The key to add is already used by the account.
+    KeyAlreadyUsed,
+    /// The key to remove is not used by the account.
+    KeyNotUsed,
+    /// Removing the key would leave the account with fewer keys than its
+    /// threshold.
+    KeysBelowThreshold,
+    /// Retrieving the deposit from the transfer contract failed.
+    DepositFailed,
+    /// Transferring to the receiver via the transfer contract failed.
+    TransferFailed,
+    /// State can only be migrated by the host, into a contract with no
+    /// accounts.
+    MigrationNotAllowed,
+    /// The key is marked as compromised.
+    KeyCompromised,
+    /// The account was frozen by a duress key, and can only be changed with
+    /// every one of its keys.
+    AccountFrozen,
+    /// The account has a group key, and only accepts its signature.
+    GroupSignatureRequired,
+    /// A group signature was given for an account without a group key.
+    NoGroupKey,
+    /// The account enforces a whitelist, and the receiver isn't on it.
+    ReceiverNotWhitelisted,
+    /// The receiver was whitelisted less than [`WHITELIST_DELAY`] blocks ago.
+    ReceiverNotYetUsable,
+    /// The window of a velocity limit must be at least one block.
+    ZeroVelocityWindow,
+    /// The deposit would take the total balance of the contract past its
+    /// custody cap.
+    CustodyCapExceeded,
+    /// The account's outgoing transfers are locked, until a change unlocks
+    /// them.
+    AccountLocked,
+    /// The account to unlock isn't locked.
+    NotLocked,
+    /// The key acting on the account isn't one of its guardians.
+    NotGuardian,
+    /// The whitelisted receiver can already be transferred to, so guardians
+    /// can no longer act on it.
+    ReceiverAlreadyUsable,
+    /// A whitelist delay can only be extended.
+    DelayNotExtended,
+    /// The alias isn't one [`is_valid_alias`] accepts.
+    InvalidAlias,
+    /// The alias is claimed by another account.
+    AliasTaken,
+    /// The account was merged into another.
+    AccountMerged,
+    /// An account can't be merged into itself.
+    MergeIntoSelf,
+    /// A key given to a split off account isn't a key of the account split.
+    KeyNotInAccount,
+    /// A payroll must have at least one row.
+    EmptyPayroll,
+    /// The gas refund is larger than the contract allows.
+    GasRefundTooLarge,
+    /// The depositor reference is longer than
+    /// [`MAX_DEPOSITOR_REFERENCE_LEN`].
+    DepositorReferenceTooLong,
+    /// The account template doesn't exist.
+    TemplateNotFound,
+    /// The operation declares a chain other than the one of its account.
+    WrongChain,
+    /// The deposit is smaller than the minimum deposit of the contract.
+    DepositTooSmall,
+    /// The deposit would take the balance of the account past its cap.
+    BalanceCapExceeded,
+    /// The tag isn't one [`is_valid_tag`] accepts.
+    InvalidTag,
+    /// The account already carries the tag.
+    TagAlreadyAdded,
+    /// The account doesn't carry the tag.
+    TagNotFound,
+    /// The account would carry more than [`MAX_TAGS`] tags.
+    TooManyTags,
+    /// The liveness policy has a window of zero blocks.
+    ZeroLivenessWindow,
+    /// The account has no liveness policy to attest for.
+    NoLivenessPolicy,
+    /// The attestation is as of a block that hasn't been reached yet.
+    AttestationInFuture,
+    /// The delegation ends before it starts.
+    InvalidDelegationRange,
+    /// The quorum certificate doesn't cover exactly the keys of the account.
+    InvalidCertificate,
+    /// The supermajority isn't more than half, and at most all, of the
+    /// signers.
+    InvalidSupermajority,
+    /// The change needs a supermajority of the signers, and was signed by
+    /// fewer.
+    SupermajorityNotMet,
+    /// The key was added under a delay that hasn't yet passed.
+    KeyNotYetUsable,
+    /// The key lapsed, and can no longer sign for the account.
+    KeyExpired,
+    /// The key addition policy or transfer window needs a supermajority the
+    /// account doesn't have.
+    NoSupermajority,
+    /// The transfer window doesn't open within every period, is empty or
+    /// longer than its period.
+    InvalidTransferWindow,
+    /// The transfer is made outside the transfer window of the account, and
+    /// wasn't signed by its supermajority.
+    OutsideTransferWindow,
+    /// The spending category isn't named as a tag, or has no window.
+    InvalidCategory,
+    /// The account would have more than [`MAX_CATEGORIES`] spending
+    /// categories.
+    TooManyCategories,
+    /// The account has no spending category of the given name.
+    UnknownCategory,
+    /// The transfer would take the spending of its category past its budget.
+    CategoryBudgetExceeded,
+    /// The child budget has no amount or no window, or is allocated by an
+    /// account to itself.
+    InvalidChildBudget,
+    /// The account would allocate budgets to more than
+    /// [`MAX_CHILD_BUDGETS`] child accounts.
+    TooManyChildBudgets,
+    /// The parent account allocated no budget to the child account.
+    UnknownChildBudget,
+    /// The draw would take the child account's draws within the window past
+    /// its budget.
+    ChildBudgetExceeded,
+    /// The key replacement replaces a key with itself, or one of its changes
+    /// isn't the replacement of the key.
+    InvalidKeyReplacement,
+    /// The key replacement doesn't change exactly the accounts using the key.
+    KeyReplacementIncomplete,
+    /// The account is archived, refusing deposits and moving no funds.
+    AccountArchived,
+    /// The account isn't archived.
+    NotArchived,
+    /// No withdrawal awaits claim under the claim ID.
+    WithdrawalNotFound,
+    /// The key claiming the withdrawal isn't its receiver.
+    NotWithdrawalReceiver,
+    /// The history retention exceeds [`MAX_HISTORY_RETENTION`].
+    HistoryRetentionTooLong,
+    /// The contract has no governance account.
+    NoGovernance,
+    /// The contract is paused by its governance account.
+    ContractPaused,
+    /// The account would have more keys than the contract allows.
+    TooManyKeys,
+    /// The memo is longer than the contract allows.
+    MemoTooLong,
+    /// The account is on hold, and only transfers to its remediation address.
+    AccountOnHold,
+    /// The account isn't on hold.
+    NotOnHold,
+    /// The account has no compliance control.
+    NoComplianceControl,
+    /// The receiver doesn't hold a license the account requires.
+    ReceiverNotLicensed,
+    /// A key doesn't hold a license the account requires.
+    KeyNotLicensed,
+    /// The ceilings of transfer tiers aren't strictly increasing, or there are
+    /// more than [`MAX_TRANSFER_TIERS`] of them.
+    InvalidTransferTiers,
+    /// A key must weigh at least one.
+    ZeroWeight,
+    /// A key signed an operation its role doesn't let it sign.
+    KeyRoleNotAllowed,
+    /// The admin keys and co-signers of an account can't meet its threshold
+    /// for changes between them.
+    AdminsBelowThreshold,
+    /// A recovery policy must need at least one guardian.
+    InvalidRecoveryPolicy,
+    /// The account has no recovery policy.
+    NoRecoveryPolicy,
+    /// A recovery of the account is already pending.
+    RecoveryPending,
+    /// No recovery of the account is pending.
+    NoRecoveryPending,
+    /// The delay of the recovery hasn't passed yet.
+    RecoveryNotReady,
+    /// An inactivity policy must have a period of at least one block.
+    InvalidInactivityPolicy,
+    /// The account has no inactivity policy.
+    NoInactivityPolicy,
+    /// The account performed an operation within the period of its
+    /// inactivity policy.
+    AccountActive,
+    /// A queue policy must have a delay of at least one block.
+    InvalidQueuePolicy,
+    /// The amount of the transfer must be queued first.
+    TransferQueueRequired,
+    /// The account doesn't queue transfers of the amount.
+    TransferNotQueueable,
+    /// No transfer of the account is queued under the nonce.
+    NoQueuedTransfer,
+    /// The delay of the queued transfer hasn't passed yet.
+    QueuedTransferNotReady,
+    /// The key has no allowance on the account.
+    NoAllowance,
+    /// The amount exceeds what is left of the allowance of the key.
+    AllowanceExceeded,
+    /// The contract parameters bound keys or memos to zero.
+    InvalidConfig,
+    /// The governance account of the contract can't be merged or archived.
+    GovernanceAccount,
+}
+
+impl Error {
+    /// Returns the message the contract panics with.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Error::AccountNotFound => "The account doesn't exist",
+            Error::NoKeys => "There must be at least one key in an account",
+            Error::ZeroThreshold => "Threshold must be at least 1",
+            Error::ThresholdTooLarge => {
+                "Threshold too large for number of keys in account"
+            }
+            Error::DuplicateKey => "Cannot use duplicate keys",
+            Error::UnknownKey => "Signing key must be used by account",
+            Error::ThresholdNotMet => "Threshold number of keys not met",
+            Error::InvalidSignature => "The signature should be valid",
+            Error::InvalidNonce => {
+                "The nonce must be the current value incremented"
+            }
+            Error::InsufficientBalance => {
+                "The account doesn't have enough balance"
+            }
+            Error::KeyAlreadyUsed => "Key to add already used by account",
+            Error::KeyNotUsed => "Key to remove not used by account",
+            Error::KeysBelowThreshold => {
+                "Removing key from account leaves key number below threshold"
+            }
+            Error::DepositFailed => "Retrieving deposit should succeed",
+            Error::TransferFailed => {
+                "Transferring to the given account should succeed"
+            }
+            Error::MigrationNotAllowed => {
+                "Migration is only allowed by the host, into an empty contract"
+            }
+            Error::KeyCompromised => "The key is marked as compromised",
+            Error::AccountFrozen => {
+                "The account is frozen, and can only be changed by all its keys"
+            }
+            Error::GroupSignatureRequired => {
+                "The account must be signed for by its group key alone"
+            }
+            Error::NoGroupKey => "The account has no group key",
+            Error::ReceiverNotWhitelisted => {
+                "The receiver must be whitelisted by the account"
+            }
+            Error::ReceiverNotYetUsable => {
+                "The receiver was whitelisted too recently to be used"
+            }
+            Error::ZeroVelocityWindow => {
+                "The velocity window must be at least one block"
+            }
+            Error::CustodyCapExceeded => {
+                "The contract can't hold more than its custody cap"
+            }
+            Error::AccountLocked => "The account's transfers are locked",
+            Error::NotLocked => "The account isn't locked",
+            Error::NotGuardian => "The key must be a guardian of the account",
+            Error::ReceiverAlreadyUsable => {
+                "The receiver can already be transferred to"
+            }
+            Error::DelayNotExtended => "The delay can only be extended",
+            Error::InvalidAlias => {
+                "The alias must be a lowercase name starting with a letter"
+            }
+            Error::AliasTaken => "The alias is claimed by another account",
+            Error::AccountMerged => "The account was merged into another",
+            Error::MergeIntoSelf => "An account can't be merged into itself",
+            Error::KeyNotInAccount => "The key is not a key of the account",
+            Error::EmptyPayroll => "A payroll must have at least one row",
+            Error::GasRefundTooLarge => "The gas refund is too large",
+            Error::DepositorReferenceTooLong => {
+                "The depositor reference is too long"
+            }
+            Error::TemplateNotFound => "The account template doesn't exist",
+            Error::WrongChain => "The operation is meant for a different chain",
+            Error::DepositTooSmall => "The deposit is below the minimum",
+            Error::BalanceCapExceeded => {
+                "The deposit would exceed the balance cap of the account"
+            }
+            Error::InvalidTag => "The tag must be a short lowercase name",
+            Error::TagAlreadyAdded => "The account already carries the tag",
+            Error::TagNotFound => "The account doesn't carry the tag",
+            Error::TooManyTags => "The account carries too many tags",
+            Error::ZeroLivenessWindow => {
+                "The liveness window must be at least one block"
+            }
+            Error::NoLivenessPolicy => "The account has no liveness policy",
+            Error::AttestationInFuture => {
+                "The attestation is for a block not yet reached"
+            }
+            Error::InvalidDelegationRange => {
+                "The delegation must not end before it starts"
+            }
+            Error::InvalidCertificate => {
+                "The quorum certificate doesn't match the keys of the account"
+            }
+            Error::InvalidSupermajority => {
+                "The supermajority must be over half, and at most all, signers"
+            }
+            Error::SupermajorityNotMet => {
+                "The change needs a supermajority of the signers"
+            }
+            Error::KeyNotYetUsable => "The key isn't usable yet",
+            Error::KeyExpired => "The key has lapsed",
+            Error::NoSupermajority => "The account has no supermajority",
+            Error::InvalidTransferWindow => {
+                "The transfer window must open within its period, for a block"
+            }
+            Error::OutsideTransferWindow => {
+                "Transfers outside the window need the supermajority"
+            }
+            Error::InvalidCategory => {
+                "The category must be named as a tag, with a window of blocks"
+            }
+            Error::TooManyCategories => "The account has too many categories",
+            Error::UnknownCategory => "The account has no such category",
+            Error::CategoryBudgetExceeded => {
+                "The transfer exceeds the budget of its category"
+            }
+            Error::InvalidChildBudget => {
+                "A child budget needs an amount, a window and another account"
+            }
+            Error::TooManyChildBudgets => {
+                "The account has too many child budgets"
+            }
+            Error::UnknownChildBudget => {
+                "The account has no budget for the child account"
+            }
+            Error::ChildBudgetExceeded => {
+                "The draw exceeds the budget of the child account"
+            }
+            Error::InvalidKeyReplacement => {
+                "The changes don't replace the key with the new key"
+            }
+            Error::KeyReplacementIncomplete => {
+                "The changes don't cover exactly the accounts using the key"
+            }
+            Error::AccountArchived => "The account is archived",
+            Error::NotArchived => "The account isn't archived",
+            Error::WithdrawalNotFound => "No withdrawal awaits this claim ID",
+            Error::NotWithdrawalReceiver => {
+                "The key isn't the receiver of the withdrawal"
+            }
+            Error::HistoryRetentionTooLong => {
+                "The history retention exceeds the contract's cap"
+            }
+            Error::NoGovernance => "The contract has no governance account",
+            Error::ContractPaused => "The contract is paused",
+            Error::TooManyKeys => "The account would have too many keys",
+            Error::MemoTooLong => "The memo is too long",
+            Error::AccountOnHold => {
+                "The account is on hold, and only transfers to its remediation address"
+            }
+            Error::NotOnHold => "The account isn't on hold",
+            Error::NoComplianceControl => {
+                "The account has no compliance control"
+            }
+            Error::ReceiverNotLicensed => {
+                "The receiver doesn't hold the license the account requires"
+            }
+            Error::KeyNotLicensed => {
+                "A key doesn't hold the license the account requires"
+            }
+            Error::InvalidTransferTiers => {
+                "Transfer tiers must have increasing ceilings and be few enough"
+            }
+            Error::ZeroWeight => "Key weight must be at least 1",
+            Error::KeyRoleNotAllowed => "Key role doesn't allow the operation",
+            Error::AdminsBelowThreshold => {
+                "Admin keys must be able to meet the change threshold"
+            }
+            Error::InvalidRecoveryPolicy => {
+                "Recovery policy must need at least one guardian"
+            }
+            Error::NoRecoveryPolicy => "The account has no recovery policy",
+            Error::RecoveryPending => "A recovery of the account is pending",
+            Error::NoRecoveryPending => "No recovery of the account is pending",
+            Error::RecoveryNotReady => "The recovery delay hasn't passed yet",
+            Error::InvalidInactivityPolicy => {
+                "Inactivity period must be at least one block"
+            }
+            Error::NoInactivityPolicy => "The account has no inactivity policy",
+            Error::AccountActive => {
+                "The account was active within its inactivity period"
+            }
+            Error::InvalidQueuePolicy => {
+                "Queue delay must be at least one block"
+            }
+            Error::TransferQueueRequired => {
+                "Transfers of this amount must be queued"
+            }
+            Error::TransferNotQueueable => {
+                "The account doesn't queue transfers of this amount"
+            }
+            Error::NoQueuedTransfer => "No transfer is queued under this nonce",
+            Error::QueuedTransferNotReady => {
+                "The queue delay hasn't passed yet"
+            }
+            Error::NoAllowance => "The key has no allowance on the account",
+            Error::AllowanceExceeded => {
+                "The transfer exceeds the allowance of the key"
+            }
+            Error::InvalidConfig => {
+                "The contract parameters must bound keys and memos above zero"
+            }
+            Error::GovernanceAccount => {
+                "The governance account can't be merged or archived"
+            }
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
@@ -0,0 +1,1094 @@
+//! Signing payloads for hardware wallets.
+//!
+//! Hardware wallets receive data in small chunks, and must show the user what
+//! they are about to sign before doing so. A [`SigningPayload`] describes an
+//! operation as a list of fields, each with a [`DisplayHint`] telling the
+//! device how to show it, in a compact encoding that can be split into chunks
+//! of any size.
+//!
+//! The device rebuilds the operation's signature message from the fields,
+//! which ensures that what it signs is exactly what it showed.
+
+use alloc::vec::Vec;
+
+use crate::{
+    AccountChange, ChangeAccount, ComplianceControl, CosignerKey, GasRefund,
+    InactivityPolicy, KeyRole, LicensePolicy, Operation, PartialSignature,
+    Payroll, SplitAccount, Transfer, TransferAndChange, CHAIN_DOMAIN,
+};
+
+/// The largest chunk of data a hardware wallet is sent at once.
+pub const MAX_CHUNK_SIZE: usize = 255;
+
+const RAW_KEY_SIZE: usize = 193;
+
+/// The kind of operation a payload is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum PayloadKind {
+    /// A transfer from an account.
+    Transfer = 0,
+    /// A change to an account.
+    ChangeAccount = 1,
+    /// A split of a new account off an account.
+    SplitAccount = 2,
+    /// A payroll from an account.
+    Payroll = 3,
+    /// A transfer from an account and a change to it, made at once.
+    TransferAndChange = 4,
+}
+
+impl PayloadKind {
+    fn from_u8(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Self::Transfer),
+            1 => Some(Self::ChangeAccount),
+            2 => Some(Self::SplitAccount),
+            3 => Some(Self::Payroll),
+            4 => Some(Self::TransferAndChange),
+            _ => None,
+        }
+    }
+}
+
+/// How a field of a payload should be shown, and how its value is encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum DisplayHint {
+    /// The ID of an account, as a little endian `u64`.
+    AccountId = 0,
+    /// The Moonlight account receiving a transfer or a payroll row, as a raw
+    /// public key.
+    Receiver = 1,
+    /// An amount of LUX, as a little endian `u64`, with [`TRANSFER_ALL`]
+    /// standing for the whole balance of the account.
+    ///
+    /// [`TRANSFER_ALL`]: crate::TRANSFER_ALL
+    Amount = 2,
+    /// The nonce of an operation, as a little endian `u64`.
+    Nonce = 3,
+    /// The memo of a transfer or a payroll row, as UTF-8 text.
+    Memo = 4,
+    /// A key to add to an account, or to give an account split off, as a raw
+    /// public key.
+    AddKey = 5,
+    /// A key to remove from an account, as a raw public key.
+    RemoveKey = 6,
+    /// The new threshold of an account, or the threshold of an account split
+    /// off, as a little endian `u32`.
+    Threshold = 7,
+    /// The new description of an account, or the description of an account
+    /// split off, as UTF-8 text.
+    Description = 8,
+    /// Whether an account should have private events, as a single byte that
+    /// is either `0` or `1`.
+    PrivateEvents = 9,
+    /// A duress key to add to an account, as a raw public key.
+    AddDuressKey = 10,
+    /// A duress key to remove from an account, as a raw public key.
+    RemoveDuressKey = 11,
+    /// A co-signer to add to an account, as the tag of its scheme followed by
+    /// its key.
+    AddCosigner = 12,
+    /// A co-signer to remove from an account, encoded as for
+    /// [`AddCosigner`](Self::AddCosigner).
+    RemoveCosigner = 13,
+    /// The group key of an account, as a raw public key, or empty if it's
+    /// removed.
+    GroupKey = 14,
+    /// A receiver to whitelist, as a raw public key.
+    AddWhitelisted = 15,
+    /// A receiver to remove from the whitelist, as a raw public key.
+    RemoveWhitelisted = 16,
+    /// The velocity limit of an account, as its amount and window in blocks,
+    /// both little endian `u64`s, or empty if it's removed.
+    VelocityLimit = 17,
+    /// The unlocking of an account, with an empty value.
+    Unlock = 18,
+    /// A guardian to add to an account, as a raw public key.
+    AddGuardian = 19,
+    /// A guardian to remove from an account, as a raw public key.
+    RemoveGuardian = 20,
+    /// The alias of an account, as UTF-8 text, or empty if it's released.
+    Alias = 21,
+    /// The gas refund of the submitter of an operation, as its raw public key
+    /// followed by the amount as a little endian `u64`. Always the first field
+    /// of the payloads that have it.
+    GasRefund = 22,
+    /// The ID of the chain an operation is meant for, as a single byte.
+    /// Always the first field of the payloads that have it, ahead of the gas
+    /// refund.
+    ChainId = 23,
+    /// The balance cap of an account, as a little endian `u64`, or empty if
+    /// it's removed.
+    BalanceCap = 24,
+    /// A tag to add to an account, as UTF-8 text.
+    AddTag = 25,
+    /// A tag to remove from an account, as UTF-8 text.
+    RemoveTag = 26,
+    /// The reference of a transfer, as 32 raw bytes. Follows the gas refund
+    /// of the payloads that have it, ahead of every other field.
+    Reference = 27,
+    /// The liveness policy of an account, as its window as a little endian
+    /// `u64` followed by its minimum threshold as a little endian `u32`, or
+    /// empty if it's removed.
+    LivenessPolicy = 28,
+    /// The supermajority of an account, as its numerator followed by its
+    /// denominator, both as little endian `u32`s, or empty if it's removed.
+    Supermajority = 29,
+    /// The key addition policy of an account, as its delay as a little endian
+    /// `u64` followed by whether it needs the supermajority as a byte, or
+    /// empty if it's removed.
+    KeyAdditionPolicy = 30,
+    /// The transfer window of an account, as its period, offset and length,
+    /// all as little endian `u64`s, or empty if it's removed.
+    TransferWindow = 31,
+    /// A spending category to set on an account, as its name as UTF-8 text
+    /// followed by its budget and window, both as little endian `u64`s.
+    SetCategory = 32,
+    /// The name of a spending category to remove from an account, as UTF-8
+    /// text.
+    RemoveCategory = 33,
+    /// The spending category of a transfer, as UTF-8 text. Follows the gas
+    /// refund of the payloads that have it, ahead of the reference and every
+    /// other field.
+    Category = 34,
+    /// An observer to add to an account, as a raw public key.
+    AddObserver = 35,
+    /// An observer to remove from an account, as a raw public key.
+    RemoveObserver = 36,
+    /// A budget to allocate to a child account, as the child's ID, the
+    /// amount and the window, all as little endian `u64`s.
+    SetChildBudget = 37,
+    /// The ID of a child account whose budget to remove, as a little endian
+    /// `u64`.
+    RemoveChildBudget = 38,
+    /// The ID of a child account to claw back from, as a little endian `u64`.
+    ClawBackBudget = 39,
+    /// The expiry of a key of an account, as the raw public key followed by
+    /// the height of the last block it can sign in as a little endian `u64`,
+    /// or by nothing if the key never lapses.
+    KeyExpiry = 40,
+    /// The archiving of an account, with an empty value.
+    Archive = 41,
+    /// The reactivation of an archived account, with an empty value.
+    Reactivate = 42,
+    /// Whether an account's withdrawals should be claimable, as a single byte
+    /// that is either `0` or `1`.
+    ClaimableWithdrawals = 43,
+    /// The number of movements an account retains in its ledger, as a 4 byte
+    /// little endian integer, or by nothing if it falls back to the default.
+    HistoryRetention = 44,
+    /// The compliance key of an account followed by its remediation address,
+    /// both as raw keys, or nothing if the account drops its compliance
+    /// control.
+    Compliance = 45,
+    /// The lifting of the hold on an account, with an empty value.
+    LiftHold = 46,
+    /// The ID of the license contract of an account followed by a byte
+    /// flagging whether its keys must be licensed, or nothing if the account
+    /// drops its license policy.
+    LicensePolicy = 47,
+    /// The kind of operations followed by the threshold set for them, as a 4
+    /// byte little endian integer, or by nothing if the threshold is dropped.
+    OperationThreshold = 48,
+    /// The tiers of an account's transfers, each as its ceiling as an 8 byte
+    /// little endian integer followed by its threshold as a 4 byte one, with
+    /// no tiers dropping them.
+    TransferTiers = 49,
+    /// A key of the account followed by the weight set for it, as a 4 byte
+    /// little endian integer.
+    KeyWeight = 50,
+    /// A key of the account followed by the role set for it, as a byte.
+    KeyRole = 51,
+    /// The recovery policy of an account, as its delay as a little endian
+    /// `u64` followed by its number of guardians as a little endian `u32`, or
+    /// empty if it's removed.
+    RecoveryPolicy = 52,
+    /// The inactivity policy of an account, as the raw bytes of its fallback
+    /// key followed by its period as a little endian `u64`, or empty if it's
+    /// removed.
+    InactivityPolicy = 53,
+    /// The queue policy of an account, as its minimum amount followed by its
+    /// delay, both as little endian `u64`s, or empty if it's removed.
+    QueuePolicy = 54,
+    /// A key of the account followed by the allowance set for it, as an 8
+    /// byte little endian integer.
+    Allowance = 55,
+}
+
+impl DisplayHint {
+    fn from_u8(byte: u8) -> Option<Self> {
+        Some(match byte {
+            0 => Self::AccountId,
+            1 => Self::Receiver,
+            2 => Self::Amount,
+            3 => Self::Nonce,
+            4 => Self::Memo,
+            5 => Self::AddKey,
+            6 => Self::RemoveKey,
+            7 => Self::Threshold,
+            8 => Self::Description,
+            9 => Self::PrivateEvents,
+            10 => Self::AddDuressKey,
+            11 => Self::RemoveDuressKey,
+            12 => Self::AddCosigner,
+            13 => Self::RemoveCosigner,
+            14 => Self::GroupKey,
+            15 => Self::AddWhitelisted,
+            16 => Self::RemoveWhitelisted,
+            17 => Self::VelocityLimit,
+            18 => Self::Unlock,
+            19 => Self::AddGuardian,
+            20 => Self::RemoveGuardian,
+            21 => Self::Alias,
+            22 => Self::GasRefund,
+            23 => Self::ChainId,
+            24 => Self::BalanceCap,
+            25 => Self::AddTag,
+            26 => Self::RemoveTag,
+            27 => Self::Reference,
+            28 => Self::LivenessPolicy,
+            29 => Self::Supermajority,
+            30 => Self::KeyAdditionPolicy,
+            31 => Self::TransferWindow,
+            32 => Self::SetCategory,
+            33 => Self::RemoveCategory,
+            34 => Self::Category,
+            35 => Self::AddObserver,
+            36 => Self::RemoveObserver,
+            37 => Self::SetChildBudget,
+            38 => Self::RemoveChildBudget,
+            39 => Self::ClawBackBudget,
+            40 => Self::KeyExpiry,
+            41 => Self::Archive,
+            42 => Self::Reactivate,
+            43 => Self::ClaimableWithdrawals,
+            44 => Self::HistoryRetention,
+            45 => Self::Compliance,
+            46 => Self::LiftHold,
+            47 => Self::LicensePolicy,
+            48 => Self::OperationThreshold,
+            49 => Self::TransferTiers,
+            50 => Self::KeyWeight,
+            51 => Self::KeyRole,
+            52 => Self::RecoveryPolicy,
+            53 => Self::InactivityPolicy,
+            54 => Self::QueuePolicy,
+            55 => Self::Allowance,
+            _ => return None,
+        })
+    }
+
+    /// Returns true if the given value is well formed for the hint.
+    fn is_valid(&self, value: &[u8]) -> bool {
+        match self {
+            Self::AccountId
+            | Self::Amount
+            | Self::Nonce
+            | Self::RemoveChildBudget
+            | Self::ClawBackBudget => value.len() == 8,
+            Self::Receiver
+            | Self::AddKey
+            | Self::RemoveKey
+            | Self::AddDuressKey
+            | Self::RemoveDuressKey
+            | Self::AddWhitelisted
+            | Self::RemoveWhitelisted
+            | Self::AddGuardian
+            | Self::RemoveGuardian
+            | Self::AddObserver
+            | Self::RemoveObserver => value.len() == RAW_KEY_SIZE,
+            Self::Threshold => value.len() == 4,
+            Self::Memo
+            | Self::Description
+            | Self::Alias
+            | Self::AddTag
+            | Self::RemoveTag
+            | Self::RemoveCategory
+            | Self::Category => core::str::from_utf8(value).is_ok(),
+            Self::SetCategory => value
+                .len()
+                .checked_sub(16)
+                .is_some_and(|n| core::str::from_utf8(&value[..n]).is_ok()),
+            Self::PrivateEvents | Self::ClaimableWithdrawals => {
+                matches!(value, [0] | [1])
+            }
+            Self::AddCosigner | Self::RemoveCosigner => {
+                CosignerKey::from_bytes(value).is_some()
+            }
+            Self::GroupKey => value.is_empty() || value.len() == RAW_KEY_SIZE,
+            Self::VelocityLimit => value.is_empty() || value.len() == 16,
+            Self::Unlock
+            | Self::Archive
+            | Self::Reactivate
+            | Self::LiftHold => value.is_empty(),
+            Self::Compliance => {
+                value.is_empty() || value.len() == 2 * RAW_KEY_SIZE
+            }
+            Self::LicensePolicy => value.is_empty() || value.len() == 33,
+            Self::OperationThreshold => value.len() == 1 || value.len() == 5,
+            Self::TransferTiers => value.len().is_multiple_of(12),
+            Self::GasRefund => value.len() == RAW_KEY_SIZE + 8,
+            Self::ChainId => value.len() == 1,
+            Self::BalanceCap => value.is_empty() || value.len() == 8,
+            Self::HistoryRetention => value.is_empty() || value.len() == 4,
+            Self::Reference => value.len() == 32,
+            Self::LivenessPolicy => value.is_empty() || value.len() == 12,
+            Self::Supermajority => value.is_empty() || value.len() == 8,
+            Self::KeyAdditionPolicy => value.is_empty() || value.len() == 9,
+            Self::TransferWindow => value.is_empty() || value.len() == 24,
+            Self::KeyExpiry => {
+                value.len() == RAW_KEY_SIZE || value.len() == RAW_KEY_SIZE + 8
+            }
+            Self::SetChildBudget => value.len() == 24,
+            Self::KeyWeight => value.len() == RAW_KEY_SIZE + 4,
+            Self::KeyRole => {
+                value.len() == RAW_KEY_SIZE + 1
+                    && value[RAW_KEY_SIZE] <= KeyRole::Spender as u8
+            }
+            Self::RecoveryPolicy => value.is_empty() || value.len() == 12,
+            Self::InactivityPolicy => {
+                value.is_empty() || value.len() == RAW_KEY_SIZE + 8
+            }
+            Self::QueuePolicy => value.is_empty() || value.len() == 16,
+            Self::Allowance => value.len() == RAW_KEY_SIZE + 8,
+        }
+    }
+}
+
+/// A field of a payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayloadField {
+    /// How the field should be shown.
+    pub hint: DisplayHint,
+    /// The value of the field, encoded as specified by the hint.
+    pub value: Vec<u8>,
+}
+
+impl PayloadField {
+    fn new(hint: DisplayHint, value: impl Into<Vec<u8>>) -> Self {
+        Self {
+            hint,
+            value: value.into(),
+        }
+    }
+
+    /// Appends the field's part of the signature message to the given one.
+    fn extend_msg(&self, msg: &mut Vec<u8>) {
+        match self.hint {
+            DisplayHint::AddKey => msg.push(ChangeAccount::ADD_KEY_TAG),
+            DisplayHint::RemoveKey => msg.push(ChangeAccount::REMOVE_KEY_TAG),
+            DisplayHint::Threshold => {
+                msg.push(ChangeAccount::SET_THRESHOLD_TAG)
+            }
+            DisplayHint::Description => {
+                msg.push(ChangeAccount::SET_DESCRIPTION_TAG);
+                msg.extend((self.value.len() as u32).to_le_bytes());
+            }
+            DisplayHint::PrivateEvents => {
+                msg.push(ChangeAccount::SET_PRIVATE_EVENTS_TAG)
+            }
+            DisplayHint::AddDuressKey => {
+                msg.push(ChangeAccount::ADD_DURESS_KEY_TAG)
+            }
+            DisplayHint::RemoveDuressKey => {
+                msg.push(ChangeAccount::REMOVE_DURESS_KEY_TAG)
+            }
+            DisplayHint::AddCosigner => {
+                msg.push(ChangeAccount::ADD_COSIGNER_TAG)
+            }
+            DisplayHint::RemoveCosigner => {
+                msg.push(ChangeAccount::REMOVE_COSIGNER_TAG)
+            }
+            DisplayHint::GroupKey => {
+                msg.push(ChangeAccount::SET_GROUP_KEY_TAG);
+                msg.push(!self.value.is_empty() as u8);
+            }
+            DisplayHint::AddWhitelisted => {
+                msg.push(ChangeAccount::ADD_WHITELISTED_TAG)
+            }
+            DisplayHint::RemoveWhitelisted => {
+                msg.push(ChangeAccount::REMOVE_WHITELISTED_TAG)
+            }
+            DisplayHint::VelocityLimit => {
+                msg.push(ChangeAccount::SET_VELOCITY_LIMIT_TAG);
+                msg.push(!self.value.is_empty() as u8);
+            }
+            DisplayHint::Unlock => msg.push(ChangeAccount::UNLOCK_TAG),
+            DisplayHint::Archive => msg.push(ChangeAccount::ARCHIVE_TAG),
+            DisplayHint::Reactivate => msg.push(ChangeAccount::REACTIVATE_TAG),
+            DisplayHint::ClaimableWithdrawals => {
+                msg.push(ChangeAccount::SET_CLAIMABLE_WITHDRAWALS_TAG)
+            }
+            DisplayHint::BalanceCap => {
+                msg.push(ChangeAccount::SET_BALANCE_CAP_TAG);
+                msg.push(!self.value.is_empty() as u8);
+            }
+            DisplayHint::HistoryRetention => {
+                msg.push(ChangeAccount::SET_HISTORY_RETENTION_TAG);
+                msg.push(!self.value.is_empty() as u8);
+            }
+            DisplayHint::Compliance => {
+                msg.push(ChangeAccount::SET_COMPLIANCE_TAG);
+                msg.push(!self.value.is_empty() as u8);
+            }
+            DisplayHint::LiftHold => msg.push(ChangeAccount::LIFT_HOLD_TAG),
+            DisplayHint::LicensePolicy => {
+                msg.push(ChangeAccount::SET_LICENSE_POLICY_TAG);
+                msg.push(!self.value.is_empty() as u8);
+            }
+            // The kind is carried by the value, ahead of the flag
+            DisplayHint::OperationThreshold => {
+                msg.push(ChangeAccount::SET_OPERATION_THRESHOLD_TAG);
+                msg.push(self.value[0]);
+                msg.push((self.value.len() > 1) as u8);
+                msg.extend(&self.value[1..]);
+                return;
+            }
+            DisplayHint::TransferTiers => {
+                msg.push(ChangeAccount::SET_TRANSFER_TIERS_TAG);
+                msg.extend(((self.value.len() / 12) as u32).to_le_bytes());
+            }
+            DisplayHint::LivenessPolicy => {
+                msg.push(ChangeAccount::SET_LIVENESS_POLICY_TAG);
+                msg.push(!self.value.is_empty() as u8);
+            }
+            DisplayHint::Supermajority => {
+                msg.push(ChangeAccount::SET_SUPERMAJORITY_TAG);
+                msg.push(!self.value.is_empty() as u8);
+            }
+            DisplayHint::KeyAdditionPolicy => {
+                msg.push(ChangeAccount::SET_KEY_ADDITION_POLICY_TAG);
+                msg.push(!self.value.is_empty() as u8);
+            }
+            DisplayHint::TransferWindow => {
+                msg.push(ChangeAccount::SET_TRANSFER_WINDOW_TAG);
+                msg.push(!self.value.is_empty() as u8);
+            }
+            DisplayHint::SetCategory => {
+                let name_len = self.value.len().saturating_sub(16);
+                msg.push(ChangeAccount::SET_CATEGORY_TAG);
+                msg.extend((name_len as u32).to_le_bytes());
+            }
+            DisplayHint::RemoveCategory => {
+                msg.push(ChangeAccount::REMOVE_CATEGORY_TAG);
+                msg.extend((self.value.len() as u32).to_le_bytes());
+            }
+            DisplayHint::Category => {
+                msg.extend(Transfer::CATEGORY_DOMAIN);
+                msg.extend((self.value.len() as u32).to_le_bytes());
+            }
+            DisplayHint::GasRefund => msg.extend(GasRefund::DOMAIN),
+            DisplayHint::ChainId => msg.extend(CHAIN_DOMAIN),
+            DisplayHint::Reference => msg.extend(Transfer::REFERENCE_DOMAIN),
+            DisplayHint::AddGuardian => {
+                msg.push(ChangeAccount::ADD_GUARDIAN_TAG)
+            }
+            DisplayHint::RemoveGuardian => {
+                msg.push(ChangeAccount::REMOVE_GUARDIAN_TAG)
+            }
+            DisplayHint::AddObserver => {
+                msg.push(ChangeAccount::ADD_OBSERVER_TAG)
+            }
+            DisplayHint::RemoveObserver => {
+                msg.push(ChangeAccount::REMOVE_OBSERVER_TAG)
+            }
+            DisplayHint::SetChildBudget => {
+                msg.push(ChangeAccount::SET_CHILD_BUDGET_TAG)
+            }
+            DisplayHint::RemoveChildBudget => {
+                msg.push(ChangeAccount::REMOVE_CHILD_BUDGET_TAG)
+            }
+            DisplayHint::ClawBackBudget => {
+                msg.push(ChangeAccount::CLAW_BACK_BUDGET_TAG)
+            }
+            DisplayHint::KeyWeight => {
+                msg.push(ChangeAccount::SET_KEY_WEIGHT_TAG)
+            }
+            DisplayHint::KeyRole => msg.push(ChangeAccount::SET_KEY_ROLE_TAG),
+            DisplayHint::Allowance => {
+                msg.push(ChangeAccount::SET_ALLOWANCE_TAG)
+            }
+            DisplayHint::RecoveryPolicy => {
+                msg.push(ChangeAccount::SET_RECOVERY_POLICY_TAG);
+                msg.push(!self.value.is_empty() as u8);
+            }
+            DisplayHint::InactivityPolicy => {
+                msg.push(ChangeAccount::SET_INACTIVITY_POLICY_TAG);
+                msg.push(!self.value.is_empty() as u8);
+            }
+            DisplayHint::QueuePolicy => {
+                msg.push(ChangeAccount::SET_QUEUE_POLICY_TAG);
+                msg.push(!self.value.is_empty() as u8);
+            }
+            DisplayHint::KeyExpiry => {
+                msg.push(ChangeAccount::SET_KEY_EXPIRY_TAG);
+                msg.push((self.value.len() > RAW_KEY_SIZE) as u8);
+            }
+            // Aliases are never empty, so an empty value releases the alias
+            DisplayHint::Alias => {
+                msg.push(ChangeAccount::SET_ALIAS_TAG);
+                msg.push(!self.value.is_empty() as u8);
+                if !self.value.is_empty() {
+                    msg.extend((self.value.len() as u32).to_le_bytes());
+                }
+            }
+            DisplayHint::AddTag => {
+                msg.push(ChangeAccount::ADD_TAG_TAG);
+                msg.extend((self.value.len() as u32).to_le_bytes());
+            }
+            DisplayHint::RemoveTag => {
+                msg.push(ChangeAccount::REMOVE_TAG_TAG);
+                msg.extend((self.value.len() as u32).to_le_bytes());
+            }
+            _ => {}
+        }
+        msg.extend(&self.value);
+    }
+}
+
+/// An operation in the form sent to hardware wallets for signing.
+///
+/// The payload is encoded as the kind of operation, followed by the number of
+/// fields as a little endian `u16` and then each field. A field is encoded as
+/// its hint, followed by the length of its value as a little endian `u16` and
+/// then the value itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SigningPayload {
+    kind: PayloadKind,
+    fields: Vec<PayloadField>,
+}
+
+impl SigningPayload {
+    /// Builds the payload for the given operation.
+    ///
+    /// Returns `None` if a field is too large to be encoded.
+    pub fn new(operation: &Operation) -> Option<Self> {
+        let (kind, mut fields) = match operation {
+            Operation::Transfer(t) => (
+                PayloadKind::Transfer,
+                Vec::from([
+                    PayloadField::new(
+                        DisplayHint::AccountId,
+                        t.account_id.to_le_bytes(),
+                    ),
+                    PayloadField::new(
+                        DisplayHint::Receiver,
+                        t.receiver.to_raw_bytes(),
+                    ),
+                    PayloadField::new(
+                        DisplayHint::Amount,
+                        t.amount.to_le_bytes(),
+                    ),
+                    PayloadField::new(
+                        DisplayHint::Nonce,
+                        t.nonce.to_le_bytes(),
+                    ),
+                    PayloadField::new(DisplayHint::Memo, t.memo.as_bytes()),
+                ]),
+            ),
+            Operation::ChangeAccount(c) => {
+                let mut fields = Vec::with_capacity(c.changes.len() + 2);
+                fields.push(PayloadField::new(
+                    DisplayHint::AccountId,
+                    c.account_id.to_le_bytes(),
+                ));
+                for change in &c.changes {
+                    fields.push(change_field(change));
+                }
+                fields.push(PayloadField::new(
+                    DisplayHint::Nonce,
+                    c.nonce.to_le_bytes(),
+                ));
+                (PayloadKind::ChangeAccount, fields)
+            }
+            Operation::SplitAccount(s) => {
+                let mut fields = Vec::with_capacity(s.new_keys.len() + 5);
+                fields.push(PayloadField::new(
+                    DisplayHint::AccountId,
+                    s.account_id.to_le_bytes(),
+                ));
+                fields.push(PayloadField::new(
+                    DisplayHint::Amount,
+                    s.amount.to_le_bytes(),
+                ));
+                for key in &s.new_keys {
+                    fields.push(PayloadField::new(
+                        DisplayHint::AddKey,
+                        key.to_raw_bytes(),
+                    ));
+                }
+                fields.push(PayloadField::new(
+                    DisplayHint::Threshold,
+                    s.threshold.to_le_bytes(),
+                ));
+                fields.push(PayloadField::new(
+                    DisplayHint::Description,
+                    s.description.as_bytes(),
+                ));
+                fields.push(PayloadField::new(
+                    DisplayHint::Nonce,
+                    s.nonce.to_le_bytes(),
+                ));
+                (PayloadKind::SplitAccount, fields)
+            }
+            Operation::Payroll(p) => {
+                let mut fields = Vec::with_capacity(3 * p.rows.len() + 2);
+                fields.push(PayloadField::new(
+                    DisplayHint::AccountId,
+                    p.account_id.to_le_bytes(),
+                ));
+                for row in &p.rows {
+                    fields.push(PayloadField::new(
+                        DisplayHint::Receiver,
+                        row.receiver.to_raw_bytes(),
+                    ));
+                    fields.push(PayloadField::new(
+                        DisplayHint::Amount,
+                        row.amount.to_le_bytes(),
+                    ));
+                    fields.push(PayloadField::new(
+                        DisplayHint::Memo,
+                        row.memo.as_bytes(),
+                    ));
+                }
+                fields.push(PayloadField::new(
+                    DisplayHint::Nonce,
+                    p.nonce.to_le_bytes(),
+                ));
+                (PayloadKind::Payroll, fields)
+            }
+            Operation::TransferAndChange(tc) => {
+                let mut fields = Vec::with_capacity(tc.changes.len() + 5);
+                fields.push(PayloadField::new(
+                    DisplayHint::AccountId,
+                    tc.account_id.to_le_bytes(),
+                ));
+                fields.push(PayloadField::new(
+                    DisplayHint::Receiver,
+                    tc.receiver.to_raw_bytes(),
+                ));
+                fields.push(PayloadField::new(
+                    DisplayHint::Amount,
+                    tc.amount.to_le_bytes(),
+                ));
+                fields.push(PayloadField::new(
+                    DisplayHint::Memo,
+                    tc.memo.as_bytes(),
+                ));
+                for change in &tc.changes {
+                    fields.push(change_field(change));
+                }
+                fields.push(PayloadField::new(
+                    DisplayHint::Nonce,
+                    tc.nonce.to_le_bytes(),
+                ));
+                (PayloadKind::TransferAndChange, fields)
+            }
+        };
+
+        if let Operation::Transfer(Transfer {
+            reference: Some(reference),
+            ..
+        }) = operation
+        {
+            fields.insert(
+                0,
+                PayloadField::new(DisplayHint::Reference, *reference),
+            );
+        }
+        if let Operation::Transfer(Transfer {
+            category: Some(category),
+            ..
+        }) = operation
+        {
+            fields.insert(
+                0,
+                PayloadField::new(DisplayHint::Category, category.as_bytes()),
+            );
+        }
+        if let Some(refund) = operation.gas_refund() {
+            let mut value = Vec::from(refund.submitter.to_raw_bytes());
+            value.extend(refund.amount.to_le_bytes());
+            fields.insert(0, PayloadField::new(DisplayHint::GasRefund, value));
+        }
+        if let Some(chain_id) = operation.chain_id() {
+            fields
+                .insert(0, PayloadField::new(DisplayHint::ChainId, [chain_id]));
+        }
+
+        let too_large = fields.len() > u16::MAX as usize
+            || fields.iter().any(|f| f.value.len() > u16::MAX as usize);
+        if too_large {
+            return None;
+        }
+
+        Some(Self { kind, fields })
+    }
+
+    /// The kind of operation the payload is for.
+    pub fn kind(&self) -> PayloadKind {
+        self.kind
+    }
+
+    /// The fields of the payload, in the order they should be shown.
+    pub fn fields(&self) -> &[PayloadField] {
+        &self.fields
+    }
+
+    /// Returns the message to be signed, equal to the signature message of the
+    /// operation the payload was built from.
+    pub fn message(&self) -> Vec<u8> {
+        let mut msg = Vec::new();
+
+        // The chain, the gas refund, the category and the reference prefix
+        // the whole message, domain included
+        let mut fields = self.fields.iter().peekable();
+        for hint in [
+            DisplayHint::ChainId,
+            DisplayHint::GasRefund,
+            DisplayHint::Category,
+            DisplayHint::Reference,
+        ] {
+            if let Some(field) = fields.next_if(|field| field.hint == hint) {
+                field.extend_msg(&mut msg);
+            }
+        }
+
+        match self.kind {
+            PayloadKind::SplitAccount => msg.extend(SplitAccount::DOMAIN),
+            PayloadKind::Payroll => msg.extend(Payroll::DOMAIN),
+            PayloadKind::TransferAndChange => {
+                msg.extend(TransferAndChange::DOMAIN)
+            }
+            _ => {}
+        }
+        let prefixed_memos = matches!(
+            self.kind,
+            PayloadKind::Payroll | PayloadKind::TransferAndChange
+        );
+        for field in fields {
+            // The memos of a payroll are followed by the next row, and that of
+            // a transfer and change by the changes, so they're prefixed with
+            // their length
+            if prefixed_memos && field.hint == DisplayHint::Memo {
+                msg.extend((field.value.len() as u32).to_le_bytes());
+            }
+            field.extend_msg(&mut msg);
+        }
+        msg
+    }
+
+    /// Encodes the payload.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        bytes.push(self.kind as u8);
+        bytes.extend((self.fields.len() as u16).to_le_bytes());
+
+        for field in &self.fields {
+            bytes.push(field.hint as u8);
+            bytes.extend((field.value.len() as u16).to_le_bytes());
+            bytes.extend(&field.value);
+        }
+
+        bytes
+    }
+
+    /// Decodes a payload, checking that the value of each field is well
+    /// formed for its hint.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let (&kind, mut bytes) = bytes.split_first()?;
+        let kind = PayloadKind::from_u8(kind)?;
+
+        let num_fields = read_u16(&mut bytes)?;
+        let mut fields = Vec::with_capacity(num_fields as usize);
+
+        for _ in 0..num_fields {
+            let (&hint, rest) = bytes.split_first()?;
+            bytes = rest;
+
+            let hint = DisplayHint::from_u8(hint)?;
+            let len = read_u16(&mut bytes)? as usize;
+            if bytes.len() < len {
+                return None;
+            }
+            let (value, rest) = bytes.split_at(len);
+            bytes = rest;
+
+            if !hint.is_valid(value) {
+                return None;
+            }
+            fields.push(PayloadField::new(hint, value));
+        }
+
+        if !bytes.is_empty() {
+            return None;
+        }
+
+        Some(Self { kind, fields })
+    }
+
+    /// Splits the encoded payload into chunks of at most the given size, to be
+    /// sent to the device in order.
+    ///
+    /// # Panics
+    /// If the chunk size is zero.
+    pub fn chunks(&self, chunk_size: usize) -> Vec<Vec<u8>> {
+        self.to_bytes()
+            .chunks(chunk_size)
+            .map(<[u8]>::to_vec)
+            .collect()
+    }
+
+    /// Decodes a payload from the chunks it was split into.
+    pub fn from_chunks<C: AsRef<[u8]>>(chunks: &[C]) -> Option<Self> {
+        let bytes: Vec<u8> = chunks
+            .iter()
+            .flat_map(|chunk| chunk.as_ref().iter().copied())
+            .collect();
+        Self::from_bytes(&bytes)
+    }
+
+    /// Returns true if the payload describes the given operation, and the
+    /// signature produced by a device is valid for it.
+    pub fn verify(
+        &self,
+        operation: &Operation,
+        signature: &PartialSignature,
+    ) -> bool {
+        self.message() == operation.signature_msg()
+            && signature.verify(operation)
+    }
+}
+
+fn change_field(change: &AccountChange) -> PayloadField {
+    match change {
+        AccountChange::AddKey { key } => {
+            PayloadField::new(DisplayHint::AddKey, key.to_raw_bytes())
+        }
+        AccountChange::RemoveKey { key } => {
+            PayloadField::new(DisplayHint::RemoveKey, key.to_raw_bytes())
+        }
+        AccountChange::SetThreshold { threshold } => {
+            PayloadField::new(DisplayHint::Threshold, threshold.to_le_bytes())
+        }
+        AccountChange::SetDescription { description } => {
+            PayloadField::new(DisplayHint::Description, description.as_bytes())
+        }
+        AccountChange::SetPrivateEvents { private_events } => {
+            PayloadField::new(
+                DisplayHint::PrivateEvents,
+                [*private_events as u8],
+            )
+        }
+        AccountChange::AddDuressKey { key } => {
+            PayloadField::new(DisplayHint::AddDuressKey, key.to_raw_bytes())
+        }
+        AccountChange::RemoveDuressKey { key } => {
+            PayloadField::new(DisplayHint::RemoveDuressKey, key.to_raw_bytes())
+        }
+        AccountChange::AddCosigner { key } => {
+            PayloadField::new(DisplayHint::AddCosigner, key.to_bytes())
+        }
+        AccountChange::RemoveCosigner { key } => {
+            PayloadField::new(DisplayHint::RemoveCosigner, key.to_bytes())
+        }
+        AccountChange::SetGroupKey { key } => PayloadField::new(
+            DisplayHint::GroupKey,
+            key.map(|key| key.to_raw_bytes().to_vec())
+                .unwrap_or_default(),
+        ),
+        AccountChange::AddWhitelisted { receiver } => PayloadField::new(
+            DisplayHint::AddWhitelisted,
+            receiver.to_raw_bytes(),
+        ),
+        AccountChange::RemoveWhitelisted { receiver } => PayloadField::new(
+            DisplayHint::RemoveWhitelisted,
+            receiver.to_raw_bytes(),
+        ),
+        AccountChange::SetVelocityLimit { limit } => PayloadField::new(
+            DisplayHint::VelocityLimit,
+            limit
+                .map(|limit| limit.to_bytes().to_vec())
+                .unwrap_or_default(),
+        ),
+        AccountChange::Unlock => {
+            PayloadField::new(DisplayHint::Unlock, Vec::new())
+        }
+        AccountChange::Archive => {
+            PayloadField::new(DisplayHint::Archive, Vec::new())
+        }
+        AccountChange::Reactivate => {
+            PayloadField::new(DisplayHint::Reactivate, Vec::new())
+        }
+        AccountChange::SetClaimableWithdrawals {
+            claimable_withdrawals,
+        } => PayloadField::new(
+            DisplayHint::ClaimableWithdrawals,
+            [*claimable_withdrawals as u8],
+        ),
+        AccountChange::SetHistoryRetention { records } => PayloadField::new(
+            DisplayHint::HistoryRetention,
+            records
+                .map(|records| records.to_le_bytes().to_vec())
+                .unwrap_or_default(),
+        ),
+        AccountChange::SetCompliance { compliance } => PayloadField::new(
+            DisplayHint::Compliance,
+            compliance
+                .as_ref()
+                .map(ComplianceControl::to_bytes)
+                .unwrap_or_default(),
+        ),
+        AccountChange::LiftHold { .. } => {
+            PayloadField::new(DisplayHint::LiftHold, [])
+        }
+        AccountChange::SetLicensePolicy { policy } => PayloadField::new(
+            DisplayHint::LicensePolicy,
+            policy
+                .as_ref()
+                .map(LicensePolicy::to_bytes)
+                .unwrap_or_default(),
+        ),
+        AccountChange::SetOperationThreshold { op, threshold } => {
+            let mut value = Vec::from([*op as u8]);
+            if let Some(threshold) = threshold {
+                value.extend(threshold.to_le_bytes());
+            }
+            PayloadField::new(DisplayHint::OperationThreshold, value)
+        }
+        AccountChange::SetTransferTiers { tiers } => {
+            let value: Vec<u8> = tiers
+                .iter()
+                .flat_map(|tier| {
+                    let mut bytes = tier.ceiling.to_le_bytes().to_vec();
+                    bytes.extend(tier.threshold.to_le_bytes());
+                    bytes
+                })
+                .collect();
+            PayloadField::new(DisplayHint::TransferTiers, value)
+        }
+        AccountChange::SetKeyWeight { key, weight } => {
+            let mut value = key.to_raw_bytes().to_vec();
+            value.extend(weight.to_le_bytes());
+            PayloadField::new(DisplayHint::KeyWeight, value)
+        }
+        AccountChange::SetKeyRole { key, role } => {
+            let mut value = key.to_raw_bytes().to_vec();
+            value.push(*role as u8);
+            PayloadField::new(DisplayHint::KeyRole, value)
+        }
+        AccountChange::SetRecoveryPolicy { policy } => PayloadField::new(
+            DisplayHint::RecoveryPolicy,
+            policy
+                .map(|policy| policy.to_bytes().to_vec())
+                .unwrap_or_default(),
+        ),
+        AccountChange::SetInactivityPolicy { policy } => PayloadField::new(
+            DisplayHint::InactivityPolicy,
+            policy
+                .as_ref()
+                .map(InactivityPolicy::to_bytes)
+                .unwrap_or_default(),
+        ),
+        AccountChange::SetQueuePolicy { policy } => PayloadField::new(
+            DisplayHint::QueuePolicy,
+            policy
+                .map(|policy| policy.to_bytes().to_vec())
+                .unwrap_or_default(),
+        ),
+        AccountChange::SetAllowance { key, amount } => {
+            let mut value = key.to_raw_bytes().to_vec();
+            value.extend(amount.to_le_bytes());
+            PayloadField::new(DisplayHint::Allowance, value)
+        }
+        AccountChange::AddGuardian { key } => {
+            PayloadField::new(DisplayHint::AddGuardian, key.to_raw_bytes())
+        }
+        AccountChange::RemoveGuardian { key } => {
+            PayloadField::new(DisplayHint::RemoveGuardian, key.to_raw_bytes())
+        }
+        AccountChange::SetAlias { alias } => PayloadField::new(
+            DisplayHint::Alias,
+            alias.as_deref().unwrap_or_default().as_bytes(),
+        ),
+        AccountChange::SetBalanceCap { cap } => PayloadField::new(
+            DisplayHint::BalanceCap,
+            cap.map(|cap| cap.to_le_bytes().to_vec())
+                .unwrap_or_default(),
+        ),
+        AccountChange::AddTag { tag } => {
+            PayloadField::new(DisplayHint::AddTag, tag.as_bytes())
+        }
+        AccountChange::RemoveTag { tag } => {
+            PayloadField::new(DisplayHint::RemoveTag, tag.as_bytes())
+        }
+        AccountChange::SetLivenessPolicy { policy } => PayloadField::new(
+            DisplayHint::LivenessPolicy,
+            policy
+                .map(|policy| policy.to_bytes().to_vec())
+                .unwrap_or_default(),
+        ),
+        AccountChange::SetSupermajority { supermajority } => PayloadField::new(
+            DisplayHint::Supermajority,
+            supermajority
+                .map(|supermajority| supermajority.to_bytes().to_vec())
+                .unwrap_or_default(),
+        ),
+        AccountChange::SetKeyAdditionPolicy { policy } => PayloadField::new(
+            DisplayHint::KeyAdditionPolicy,
+            policy
+                .map(|policy| policy.to_bytes().to_vec())
+                .unwrap_or_default(),
+        ),
+        AccountChange::SetTransferWindow { window } => PayloadField::new(
+            DisplayHint::TransferWindow,
+            window
+                .map(|window| window.to_bytes().to_vec())
+                .unwrap_or_default(),
+        ),
+        AccountChange::SetCategory { category } => {
+            let mut value = Vec::from(category.name.as_bytes());
+            value.extend(category.budget_bytes());
+            PayloadField::new(DisplayHint::SetCategory, value)
+        }
+        AccountChange::RemoveCategory { name } => {
+            PayloadField::new(DisplayHint::RemoveCategory, name.as_bytes())
+        }
+        AccountChange::AddObserver { key } => {
+            PayloadField::new(DisplayHint::AddObserver, key.to_raw_bytes())
+        }
+        AccountChange::RemoveObserver { key } => {
+            PayloadField::new(DisplayHint::RemoveObserver, key.to_raw_bytes())
+        }
+        AccountChange::SetChildBudget { budget } => {
+            PayloadField::new(DisplayHint::SetChildBudget, budget.to_bytes())
+        }
+        AccountChange::RemoveChildBudget { child_id } => PayloadField::new(
+            DisplayHint::RemoveChildBudget,
+            child_id.to_le_bytes(),
+        ),
+        AccountChange::ClawBackBudget { child_id } => PayloadField::new(
+            DisplayHint::ClawBackBudget,
+            child_id.to_le_bytes(),
+        ),
+        AccountChange::SetKeyExpiry { key, valid_until } => {
+            let mut value = key.to_raw_bytes().to_vec();
+            if let Some(valid_until) = valid_until {
+                value.extend(valid_until.to_le_bytes());
+            }
+            PayloadField::new(DisplayHint::KeyExpiry, value)
+        }
+    }
+}
+
+fn read_u16(bytes: &mut &[u8]) -> Option<u16> {
+    if bytes.len() < 2 {
+        return None;
+    }
+    let (n, rest) = bytes.split_at(2);
+    *bytes = rest;
+    Some(u16::from_le_bytes([n[0], n[1]]))
+}
@@ -0,0 +1,973 @@
+//! Wallet integration for the `multisig-contract`.
+//!
+//! The [`Wallet`] wraps a connection to a rusk node, building the Moonlight
+//! transactions that call the contract - just like the contract's tests do -
+//! and keeping track of the nonces of both the Moonlight account paying for
+//! them and the multisig accounts operated on.
+//!
+//! A wallet integrates with the contract in a few calls:
+//!
+//! ```no_run
+//! # async fn example(
+//! #     contract: execution_core::ContractId,
+//! #     sender: multisig_contract_types::bls::SecretKey,
+//! #     receiver: multisig_contract_types::bls::PublicKey,
+//! #     partials: Vec<multisig_contract_types::PartialSignature>,
+//! # ) -> Result<(), multisig_wallet::Error> {
+//! use multisig_wallet::{Node, Wallet};
+//!
+//! let node = Node::new("http://127.0.0.1:8080");
+//! let mut wallet = Wallet::new(node, contract, 1, sender);
+//!
+//! let mut transfer = wallet.transfer(1, receiver, 1_000, "").await?;
+//! // ... collect the partial signatures of the signers ...
+//! transfer.aggregate(&partials);
+//! wallet.submit(&transfer).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+#![deny(missing_docs)]
+
+mod node;
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use execution_core::transfer::data::ContractCall;
+use execution_core::transfer::Transaction;
+use execution_core::ContractId;
+
+use rkyv::ser::serializers::AllocSerializer;
+use rkyv::Serialize;
+
+use multisig_contract_types::*;
+
+pub use node::{decode, decode_event, encode, Node};
+
+/// Errors that may occur when interacting with the contract.
+#[derive(Debug)]
+pub enum Error {
+    /// Communicating with the node failed.
+    Http(reqwest::Error),
+    /// Serializing an argument failed.
+    Serialization,
+    /// Deserializing a response failed.
+    Deserialization,
+    /// Building a transaction failed.
+    Transaction(execution_core::Error),
+    /// An event has a version of its schema that isn't supported.
+    UnsupportedEventVersion(Option<u32>),
+    /// The signers of an operation aren't all keys of its account, so they
+    /// can't be named by a quorum certificate.
+    Uncertifiable,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Http(err) => write!(f, "Communicating with node: {err}"),
+            Error::Serialization => write!(f, "Failed to serialize data"),
+            Error::Deserialization => write!(f, "Failed to deserialize data"),
+            Error::Transaction(err) => {
+                write!(f, "Failed to build transaction: {err:?}")
+            }
+            Error::UnsupportedEventVersion(Some(version)) => {
+                write!(f, "Unsupported event version: {version}")
+            }
+            Error::UnsupportedEventVersion(None) => {
+                write!(f, "Event too short to have a version")
+            }
+            Error::Uncertifiable => {
+                write!(f, "Signers aren't all keys of the account")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        Error::Http(err)
+    }
+}
+
+impl From<execution_core::Error> for Error {
+    fn from(err: execution_core::Error) -> Self {
+        Error::Transaction(err)
+    }
+}
+
+/// Result of interacting with the contract.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+const DEFAULT_GAS_LIMIT: u64 = 100_000_000;
+const DEFAULT_GAS_PRICE: u64 = 1;
+
+/// Interacts with the multisig contract through Moonlight transactions paid
+/// for by a single account.
+///
+/// The operations it builds declare the wallet's chain, so that signatures
+/// gathered for one network can't be replayed on another.
+pub struct Wallet {
+    node: Node,
+    contract: ContractId,
+    chain_id: u8,
+    sender: bls::SecretKey,
+    gas_limit: u64,
+    gas_price: u64,
+    sender_nonce: Option<u64>,
+    account_nonces: BTreeMap<u64, u64>,
+}
+
+impl Wallet {
+    /// Creates a wallet interacting with the multisig contract with the given
+    /// ID, on the given chain, paying for transactions with the given key.
+    pub fn new(
+        node: Node,
+        contract: ContractId,
+        chain_id: u8,
+        sender: bls::SecretKey,
+    ) -> Self {
+        Self {
+            node,
+            contract,
+            chain_id,
+            sender,
+            gas_limit: DEFAULT_GAS_LIMIT,
+            gas_price: DEFAULT_GAS_PRICE,
+            sender_nonce: None,
+            account_nonces: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the gas limit and price used for transactions.
+    pub fn with_gas(mut self, gas_limit: u64, gas_price: u64) -> Self {
+        self.gas_limit = gas_limit;
+        self.gas_price = gas_price;
+        self
+    }
+
+    /// The connection to the node.
+    pub fn node(&self) -> &Node {
+        &self.node
+    }
+
+    /// Submits a transaction creating an account.
+    pub async fn create_account(&mut self, ca: &CreateAccount) -> Result<()> {
+        self.execute("create_account", ca, 0).await
+    }
+
+    /// Submits a transaction registering an account template.
+    pub async fn register_template(
+        &mut self,
+        template: &AccountTemplate,
+    ) -> Result<()> {
+        self.execute("register_template", template, 0).await
+    }
+
+    /// Submits a transaction creating an account from a template.
+    pub async fn create_account_from_template(
+        &mut self,
+        create: &CreateAccountFromTemplate,
+    ) -> Result<()> {
+        self.execute("create_account_from_template", create, 0)
+            .await
+    }
+
+    /// Submits a transaction depositing to an account, attributed with the
+    /// given depositor reference.
+    pub async fn deposit(
+        &mut self,
+        account_id: u64,
+        amount: u64,
+        memo: impl Into<String>,
+        depositor_reference: impl Into<String>,
+    ) -> Result<()> {
+        let deposit = Deposit {
+            account_id,
+            amount,
+            memo: memo.into(),
+            depositor_reference: depositor_reference.into(),
+        };
+        self.execute("deposit", &deposit, amount).await
+    }
+
+    /// Builds an unsigned transfer from an account, using its next nonce.
+    pub async fn transfer(
+        &mut self,
+        account_id: u64,
+        receiver: bls::PublicKey,
+        amount: u64,
+        memo: impl Into<String>,
+    ) -> Result<Operation> {
+        Ok(Operation::Transfer(Transfer {
+            account_id,
+            keys: Vec::new(),
+            signature: bls::MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
+            receiver,
+            amount,
+            nonce: self.next_nonce(account_id).await?,
+            memo: memo.into(),
+            reference: None,
+            category: None,
+            gas_refund: None,
+            chain_id: Some(self.chain_id),
+        }))
+    }
+
+    /// Builds an unsigned payroll from an account, using its next nonce.
+    pub async fn payroll(
+        &mut self,
+        account_id: u64,
+        rows: Vec<PayrollRow>,
+    ) -> Result<Operation> {
+        Ok(Operation::Payroll(Payroll {
+            account_id,
+            keys: Vec::new(),
+            signature: bls::MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
+            rows,
+            nonce: self.next_nonce(account_id).await?,
+            gas_refund: None,
+            chain_id: Some(self.chain_id),
+        }))
+    }
+
+    /// Builds an unsigned change to an account, using its next nonce.
+    pub async fn change_account(
+        &mut self,
+        account_id: u64,
+        changes: Vec<AccountChange>,
+    ) -> Result<Operation> {
+        Ok(Operation::ChangeAccount(ChangeAccount {
+            account_id,
+            keys: Vec::new(),
+            signature: bls::MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
+            changes,
+            nonce: self.next_nonce(account_id).await?,
+            gas_refund: None,
+            chain_id: Some(self.chain_id),
+        }))
+    }
+
+    /// Builds an unsigned transfer from an account together with a change to
+    /// it, using its next nonce.
+    pub async fn transfer_and_change(
+        &mut self,
+        account_id: u64,
+        receiver: bls::PublicKey,
+        amount: u64,
+        memo: impl Into<String>,
+        changes: Vec<AccountChange>,
+    ) -> Result<Operation> {
+        Ok(Operation::TransferAndChange(TransferAndChange {
+            account_id,
+            keys: Vec::new(),
+            signature: bls::MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
+            receiver,
+            amount,
+            memo: memo.into(),
+            changes,
+            nonce: self.next_nonce(account_id).await?,
+            gas_refund: None,
+            chain_id: Some(self.chain_id),
+        }))
+    }
+
+    /// Builds an unsigned split of a new account off an account, using its
+    /// next nonce.
+    pub async fn split_account(
+        &mut self,
+        account_id: u64,
+        new_keys: Vec<bls::PublicKey>,
+        threshold: u32,
+        description: impl Into<String>,
+        amount: u64,
+    ) -> Result<Operation> {
+        Ok(Operation::SplitAccount(SplitAccount {
+            account_id,
+            keys: Vec::new(),
+            signature: bls::MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
+            new_keys,
+            threshold,
+            description: description.into(),
+            amount,
+            nonce: self.next_nonce(account_id).await?,
+            gas_refund: None,
+            chain_id: Some(self.chain_id),
+        }))
+    }
+
+    /// Builds an unsigned merge of an account into another, using the next
+    /// nonces of both.
+    pub async fn merge_accounts(
+        &mut self,
+        account_id: u64,
+        merged_id: u64,
+    ) -> Result<MergeAccounts> {
+        Ok(MergeAccounts {
+            account_id,
+            keys: Vec::new(),
+            signature: bls::MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
+            nonce: self.next_nonce(account_id).await?,
+            merged_id,
+            merged_keys: Vec::new(),
+            merged_signature: bls::MultisigSignature::default(),
+            merged_cosignatures: Vec::new(),
+            merged_group_signature: None,
+            merged_nonce: self.next_nonce(merged_id).await?,
+        })
+    }
+
+    /// Submits a transaction merging two accounts, signed by both.
+    pub async fn submit_merge(&mut self, merge: &MergeAccounts) -> Result<()> {
+        self.execute("merge_accounts", merge, 0).await
+    }
+
+    /// Builds an unsigned draw of a child account on the budget its parent
+    /// allocated to it, using the next nonce of the child.
+    pub async fn draw_budget(
+        &mut self,
+        account_id: u64,
+        parent_id: u64,
+        amount: u64,
+    ) -> Result<DrawBudget> {
+        Ok(DrawBudget {
+            account_id,
+            parent_id,
+            keys: Vec::new(),
+            signature: bls::MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
+            amount,
+            nonce: self.next_nonce(account_id).await?,
+        })
+    }
+
+    /// Submits a transaction drawing on a child budget, signed by the child.
+    pub async fn submit_draw(&mut self, draw: &DrawBudget) -> Result<()> {
+        self.execute("draw_budget", draw, 0).await
+    }
+
+    /// Builds an unsigned checkpoint of an account, using its next nonce.
+    pub async fn record_checkpoint(
+        &mut self,
+        account_id: u64,
+    ) -> Result<RecordCheckpoint> {
+        Ok(RecordCheckpoint {
+            account_id,
+            keys: Vec::new(),
+            signature: bls::MultisigSignature::default(),
+            cosignatures: Vec::new(),
+            group_signature: None,
+            nonce: self.next_nonce(account_id).await?,
+        })
+    }
+
+    /// Submits a transaction recording a signed checkpoint.
+    pub async fn submit_checkpoint(
+        &mut self,
+        checkpoint: &RecordCheckpoint,
+    ) -> Result<()> {
+        self.execute("record_checkpoint", checkpoint, 0).await
+    }
+
+    /// Builds an unsigned recovery of an account, replacing its keys and
+    /// threshold, to be signed by its guardians. Uses its next recovery
+    /// nonce.
+    pub async fn start_recovery(
+        &mut self,
+        account_id: u64,
+        keys: Vec<bls::PublicKey>,
+        threshold: u32,
+    ) -> Result<StartRecovery> {
+        Ok(StartRecovery {
+            account_id,
+            guardians: Vec::new(),
+            signature: bls::MultisigSignature::default(),
+            keys,
+            threshold,
+            nonce: self.account(account_id).await?.recovery_nonce + 1,
+        })
+    }
+
+    /// Submits a transaction starting a recovery signed by the guardians of
+    /// the account.
+    pub async fn submit_recovery(
+        &mut self,
+        recovery: &StartRecovery,
+    ) -> Result<()> {
+        self.execute("start_recovery", recovery, 0).await
+    }
+
+    /// Submits a transaction setting the contract-wide parameters, signed by
+    /// the governance account.
+    pub async fn submit_configure(
+        &mut self,
+        configure: &Configure,
+    ) -> Result<()> {
+        self.execute("configure", configure, 0).await
+    }
+
+    /// Submits a transaction performing a signed operation.
+    pub async fn submit(&mut self, operation: &Operation) -> Result<()> {
+        match operation {
+            Operation::Transfer(t) => {
+                self.execute(operation.fn_name(), t, 0).await
+            }
+            Operation::ChangeAccount(c) => {
+                self.execute(operation.fn_name(), c, 0).await
+            }
+            Operation::SplitAccount(s) => {
+                self.execute(operation.fn_name(), s, 0).await
+            }
+            Operation::Payroll(p) => {
+                self.execute(operation.fn_name(), p, 0).await
+            }
+            Operation::TransferAndChange(tc) => {
+                self.execute(operation.fn_name(), tc, 0).await
+            }
+        }
+    }
+
+    /// Submits a transaction performing a signed operation, naming its
+    /// signers by a quorum certificate over the keys of the account rather
+    /// than by their keys.
+    pub async fn submit_certified(
+        &mut self,
+        operation: &Operation,
+    ) -> Result<()> {
+        let account_keys = self.account_keys(operation.account_id()).await?;
+        let certified =
+            CertifiedOperation::new(operation.clone(), &account_keys)
+                .ok_or(Error::Uncertifiable)?;
+        self.execute("certified", &certified, 0).await
+    }
+
+    /// Submits a transaction claiming the withdrawal with the given claim ID,
+    /// signed with the secret key of its receiver.
+    pub async fn claim_withdrawal(
+        &mut self,
+        sk: &bls::SecretKey,
+        claim_id: u64,
+    ) -> Result<()> {
+        let claim = ClaimWithdrawal::new(sk, claim_id);
+        self.execute("claim_withdrawal", &claim, 0).await
+    }
+
+    /// Submits a transaction marking the key of the given secret key as
+    /// compromised.
+    pub async fn mark_compromised(
+        &mut self,
+        sk: &bls::SecretKey,
+    ) -> Result<()> {
+        self.execute("mark_compromised", &MarkCompromised::new(sk), 0)
+            .await
+    }
+
+    /// Submits a transaction marking the co-signer key of the given Ed25519
+    /// key as compromised.
+    pub async fn mark_cosigner_compromised(
+        &mut self,
+        sk: &ed25519::SigningKey,
+    ) -> Result<()> {
+        let mark = MarkCosignerCompromised::new_ed25519(sk);
+        self.execute("mark_cosigner_compromised", &mark, 0).await
+    }
+
+    /// Submits a transaction replacing the key of the given secret key with
+    /// a new key, in every account using it, with the signed change of each.
+    pub async fn replace_key(
+        &mut self,
+        sk: &bls::SecretKey,
+        new_key: bls::PublicKey,
+        changes: Vec<ChangeAccount>,
+    ) -> Result<()> {
+        let replace = ReplaceKey::new(sk, new_key, changes);
+        self.execute("replace_key", &replace, 0).await
+    }
+
+    /// Submits a transaction locking the transfers of an account, signed
+    /// with a single one of its keys.
+    pub async fn lock_account(
+        &mut self,
+        sk: &bls::SecretKey,
+        account_id: u64,
+    ) -> Result<()> {
+        let nonce = self.account(account_id).await?.nonce;
+        let lock = LockAccount::new(sk, account_id, nonce);
+        self.execute("lock_account", &lock, 0).await
+    }
+
+    /// Submits a transaction placing an account on hold, signed with its
+    /// compliance secret key.
+    pub async fn place_hold(
+        &mut self,
+        sk: &bls::SecretKey,
+        account_id: u64,
+    ) -> Result<()> {
+        let nonce = self.account(account_id).await?.nonce;
+        let hold = PlaceHold::new(sk, account_id, nonce);
+        self.execute("place_hold", &hold, 0).await
+    }
+
+    /// Submits a transaction attesting that a key of an account is alive as of
+    /// the given height, which may not be past the current one.
+    pub async fn attest_liveness(
+        &mut self,
+        sk: &bls::SecretKey,
+        account_id: u64,
+        block_height: u64,
+    ) -> Result<()> {
+        let attest = AttestLiveness::new(sk, account_id, block_height);
+        self.execute("attest_liveness", &attest, 0).await
+    }
+
+    /// Submits a transaction registering the delegation of a key of an
+    /// account, or revoking it if `None`, signed with the key's secret key.
+    pub async fn delegate_key(
+        &mut self,
+        sk: &bls::SecretKey,
+        account_id: u64,
+        delegation: Option<Delegation>,
+    ) -> Result<()> {
+        let key = bls::PublicKey::from(sk);
+        let nonce = self
+            .delegations(account_id)
+            .await?
+            .into_iter()
+            .find(|kd| kd.key == key)
+            .map_or(0, |kd| kd.nonce);
+        let delegate = DelegateKey::new(sk, account_id, delegation, nonce + 1);
+        self.execute("delegate_key", &delegate, 0).await
+    }
+
+    /// Submits a transaction taking the given action on an account, signed
+    /// with the secret key of one of its guardians.
+    pub async fn guard_account(
+        &mut self,
+        sk: &bls::SecretKey,
+        account_id: u64,
+        action: GuardianAction,
+    ) -> Result<()> {
+        let nonce = self.account(account_id).await?.nonce;
+        let guard = GuardAccount::new(sk, account_id, action, nonce);
+        self.execute("guard_account", &guard, 0).await
+    }
+
+    /// Submits a transaction cancelling the pending recovery of an account,
+    /// signed with one of its secret keys.
+    pub async fn cancel_recovery(
+        &mut self,
+        sk: &bls::SecretKey,
+        account_id: u64,
+    ) -> Result<()> {
+        let nonce = self.account(account_id).await?.recovery_nonce;
+        let cancel = CancelRecovery::new(sk, account_id, nonce);
+        self.execute("cancel_recovery", &cancel, 0).await
+    }
+
+    /// Submits a transaction finalizing the pending recovery of an account,
+    /// once its delay has passed.
+    pub async fn finalize_recovery(&mut self, account_id: u64) -> Result<()> {
+        let finalize = FinalizeRecovery { account_id };
+        self.execute("finalize_recovery", &finalize, 0).await
+    }
+
+    /// Submits a transaction claiming an account that performed no operation
+    /// for the period of its inactivity policy, signed with the secret key of
+    /// the policy's fallback key.
+    pub async fn claim_inactive(
+        &mut self,
+        sk: &bls::SecretKey,
+        account_id: u64,
+        claim: InactiveClaim,
+    ) -> Result<()> {
+        let nonce = self.account(account_id).await?.nonce + 1;
+        let claim = ClaimInactive::new(sk, account_id, claim, nonce);
+        self.execute("claim_inactive", &claim, 0).await
+    }
+
+    /// Submits a transaction transferring from an account out of the
+    /// allowance of one of its keys, signed with the key's secret key.
+    pub async fn transfer_with_allowance(
+        &mut self,
+        sk: &bls::SecretKey,
+        account_id: u64,
+        receiver: bls::PublicKey,
+        amount: u64,
+        memo: String,
+    ) -> Result<()> {
+        let nonce = self.account(account_id).await?.nonce + 1;
+        let transfer = AllowanceTransfer::new(
+            sk,
+            account_id,
+            receiver,
+            amount,
+            nonce,
+            memo,
+            Some(self.chain_id),
+        );
+        self.execute("transfer_with_allowance", &transfer, 0).await
+    }
+
+    /// Submits a transaction queuing a signed transfer, to be executed once
+    /// the delay of its account's queue policy has passed.
+    pub async fn queue_transfer(&mut self, transfer: &Transfer) -> Result<()> {
+        self.execute("queue_transfer", transfer, 0).await
+    }
+
+    /// Submits a transaction cancelling the transfer an account queued with
+    /// the given nonce, signed with one of its secret keys.
+    pub async fn cancel_queued(
+        &mut self,
+        sk: &bls::SecretKey,
+        account_id: u64,
+        nonce: u64,
+    ) -> Result<()> {
+        let cancel = CancelQueued::new(sk, account_id, nonce);
+        self.execute("cancel_queued", &cancel, 0).await
+    }
+
+    /// Submits a transaction executing the transfer an account queued with
+    /// the given nonce, once its delay has passed.
+    pub async fn execute_queued(
+        &mut self,
+        account_id: u64,
+        nonce: u64,
+    ) -> Result<()> {
+        let execute = ExecuteQueued { account_id, nonce };
+        self.execute("execute_queued", &execute, 0).await
+    }
+
+    /// Returns the ID of the account claiming an alias, if any.
+    pub async fn resolve_alias(&self, alias: &str) -> Result<Option<u64>> {
+        self.node
+            .query(self.contract, "resolve_alias", &String::from(alias))
+            .await
+    }
+
+    /// Returns the total balance of all accounts and of the withdrawals
+    /// awaiting claim, together with the balance the contract holds.
+    pub async fn custody(&self) -> Result<CustodyReport> {
+        self.node.query(self.contract, "custody", &()).await
+    }
+
+    /// Returns the account template with the given ID, if any.
+    pub async fn template(
+        &self,
+        template_id: u64,
+    ) -> Result<Option<AccountTemplate>> {
+        self.node
+            .query(self.contract, "template", &template_id)
+            .await
+    }
+
+    /// Returns whether a key is marked as compromised.
+    pub async fn is_compromised(&self, key: &bls::PublicKey) -> Result<bool> {
+        self.node.query(self.contract, "is_compromised", key).await
+    }
+
+    /// Returns the data of an account.
+    pub async fn account(&self, account_id: u64) -> Result<AccountData> {
+        self.node.query(self.contract, "account", &account_id).await
+    }
+
+    /// Returns the keys used by an account.
+    pub async fn account_keys(
+        &self,
+        account_id: u64,
+    ) -> Result<Vec<bls::PublicKey>> {
+        self.node
+            .feeder_query(self.contract, "account_keys", &account_id)
+            .await
+    }
+
+    /// Returns the observers of an account, which can't sign for it.
+    pub async fn observers(
+        &self,
+        account_id: u64,
+    ) -> Result<Vec<bls::PublicKey>> {
+        self.node
+            .feeder_query(self.contract, "observers", &account_id)
+            .await
+    }
+
+    /// Returns the movements of an account within a range of blocks, in the
+    /// order they happened.
+    pub async fn movements(
+        &self,
+        account_id: u64,
+        from_height: u64,
+        to_height: u64,
+    ) -> Result<Vec<Movement>> {
+        let query = MovementQuery {
+            account_id,
+            from_height,
+            to_height,
+        };
+        self.node
+            .feeder_query(self.contract, "movements", &query)
+            .await
+    }
+
+    /// Returns the movements of funds into or out of the contract of at least
+    /// an amount within a range of blocks, across all accounts in the order
+    /// they happened.
+    pub async fn large_transfers(
+        &self,
+        min_amount: u64,
+        from_height: u64,
+        to_height: u64,
+    ) -> Result<Vec<LargeTransfer>> {
+        let query = LargeTransferQuery {
+            min_amount,
+            from_height,
+            to_height,
+        };
+        self.node
+            .feeder_query(self.contract, "large_transfers", &query)
+            .await
+    }
+
+    /// Returns the IDs of the accounts using a key.
+    pub async fn key_accounts(&self, key: &bls::PublicKey) -> Result<Vec<u64>> {
+        self.node
+            .feeder_query(self.contract, "key_accounts", key)
+            .await
+    }
+
+    /// Returns the last attestation of each key of an account, if it has a
+    /// liveness policy.
+    pub async fn key_liveness(
+        &self,
+        account_id: u64,
+    ) -> Result<Vec<KeyLiveness>> {
+        self.node
+            .feeder_query(self.contract, "key_liveness", &account_id)
+            .await
+    }
+
+    /// Returns the delegation registered by each key of an account, together
+    /// with its nonce.
+    pub async fn delegations(
+        &self,
+        account_id: u64,
+    ) -> Result<Vec<KeyDelegation>> {
+        self.node
+            .feeder_query(self.contract, "delegations", &account_id)
+            .await
+    }
+
+    /// Returns the keys of an account added under a delay that hasn't yet
+    /// passed, with the height they become usable from.
+    pub async fn pending_keys(
+        &self,
+        account_id: u64,
+    ) -> Result<Vec<PendingKey>> {
+        self.node
+            .feeder_query(self.contract, "pending_keys", &account_id)
+            .await
+    }
+
+    /// Returns the keys of an account that lapse, with the height of the last
+    /// block they can sign in, including those that already lapsed.
+    pub async fn key_expiries(
+        &self,
+        account_id: u64,
+    ) -> Result<Vec<KeyExpiry>> {
+        self.node
+            .feeder_query(self.contract, "key_expiries", &account_id)
+            .await
+    }
+
+    /// Returns the keys of an account weighing other than one, with their
+    /// weight.
+    pub async fn key_weights(&self, account_id: u64) -> Result<Vec<KeyWeight>> {
+        self.node
+            .feeder_query(self.contract, "key_weights", &account_id)
+            .await
+    }
+
+    /// Returns the keys of an account with a role other than admin, with
+    /// their role.
+    pub async fn key_roles(
+        &self,
+        account_id: u64,
+    ) -> Result<Vec<RoleAssignment>> {
+        self.node
+            .feeder_query(self.contract, "key_roles", &account_id)
+            .await
+    }
+
+    /// Returns the recovery pending on an account, if any.
+    pub async fn pending_recovery(
+        &self,
+        account_id: u64,
+    ) -> Result<Option<PendingRecovery>> {
+        self.node
+            .query(self.contract, "pending_recovery", &account_id)
+            .await
+    }
+
+    /// Returns the keys of an account with an allowance left, with what is
+    /// left of each.
+    pub async fn allowances(
+        &self,
+        account_id: u64,
+    ) -> Result<Vec<KeyAllowance>> {
+        self.node
+            .query(self.contract, "allowances", &account_id)
+            .await
+    }
+
+    /// Returns the transfers queued by an account, in the order of their
+    /// nonces.
+    pub async fn queued_transfers(
+        &self,
+        account_id: u64,
+    ) -> Result<Vec<QueuedTransfer>> {
+        self.node
+            .query(self.contract, "queued_transfers", &account_id)
+            .await
+    }
+
+    /// Returns the tiers of an account's transfers, in the order of their
+    /// ceilings.
+    pub async fn transfer_tiers(
+        &self,
+        account_id: u64,
+    ) -> Result<Vec<TransferTier>> {
+        self.node
+            .feeder_query(self.contract, "transfer_tiers", &account_id)
+            .await
+    }
+
+    /// Returns how many operations each key of an account signed, and the
+    /// height it last signed at, including the keys that never signed.
+    pub async fn key_usage(&self, account_id: u64) -> Result<Vec<KeyUsage>> {
+        self.node
+            .feeder_query(self.contract, "key_usage", &account_id)
+            .await
+    }
+
+    /// Returns the amount transferred under each spending category of an
+    /// account within the current window of its budget.
+    pub async fn category_spend(
+        &self,
+        account_id: u64,
+    ) -> Result<Vec<CategorySpend>> {
+        self.node
+            .feeder_query(self.contract, "category_spend", &account_id)
+            .await
+    }
+
+    /// Returns the draws of the children of an account on the budgets it
+    /// allocated them.
+    pub async fn budget_draws(
+        &self,
+        account_id: u64,
+    ) -> Result<Vec<BudgetDraw>> {
+        self.node
+            .feeder_query(self.contract, "budget_draws", &account_id)
+            .await
+    }
+
+    /// Returns the checkpoints recorded of an account, in the order they
+    /// were.
+    pub async fn checkpoints(
+        &self,
+        account_id: u64,
+    ) -> Result<Vec<Checkpoint>> {
+        self.node
+            .feeder_query(self.contract, "checkpoints", &account_id)
+            .await
+    }
+
+    /// Returns the withdrawals awaiting claim by a receiver, in the order of
+    /// their claim IDs.
+    pub async fn withdrawals(
+        &self,
+        receiver: bls::PublicKey,
+    ) -> Result<Vec<PendingWithdrawal>> {
+        self.node
+            .feeder_query(self.contract, "withdrawals", &receiver)
+            .await
+    }
+
+    /// Returns the IDs of the accounts carrying a tag.
+    pub async fn tagged_accounts(&self, tag: &str) -> Result<Vec<u64>> {
+        self.node
+            .feeder_query(self.contract, "tagged_accounts", &String::from(tag))
+            .await
+    }
+
+    /// Returns the nonce the next operation on the given account should use.
+    ///
+    /// Operations built by this wallet, but not yet executed, are taken into
+    /// account, so several can be prepared in a row.
+    pub async fn next_nonce(&mut self, account_id: u64) -> Result<u64> {
+        let on_chain = self.account(account_id).await?.nonce;
+
+        let nonce = self.account_nonces.entry(account_id).or_default();
+        *nonce = on_chain.max(*nonce) + 1;
+
+        Ok(*nonce)
+    }
+
+    /// Sends a Moonlight transaction calling the given function of the
+    /// contract, depositing the given amount to it.
+    async fn execute<A>(
+        &mut self,
+        fn_name: &str,
+        fn_arg: &A,
+        deposit: u64,
+    ) -> Result<()>
+    where
+        A: Serialize<AllocSerializer<1024>>,
+    {
+        let call = ContractCall {
+            contract: self.contract,
+            fn_name: String::from(fn_name),
+            fn_args: encode(fn_arg)?,
+        };
+
+        let nonce = match self.sender_nonce {
+            Some(nonce) => nonce + 1,
+            None => {
+                let sender = bls::PublicKey::from(&self.sender);
+                self.node.moonlight_nonce(&sender).await? + 1
+            }
+        };
+
+        let tx = Transaction::moonlight(
+            &self.sender,
+            None,
+            0,
+            deposit,
+            self.gas_limit,
+            self.gas_price,
+            nonce,
+            self.chain_id,
+            Some(call),
+        )?;
+
+        self.node.propagate(&tx).await?;
+        self.sender_nonce = Some(nonce);
+
+        Ok(())
+    }
+}
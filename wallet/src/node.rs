@@ -0,0 +1,165 @@
+//! Communication with a rusk node over its HTTP API.
+
+use execution_core::transfer::moonlight::AccountData as MoonlightAccountData;
+use execution_core::transfer::{Transaction, TRANSFER_CONTRACT};
+use execution_core::ContractId;
+
+use bytecheck::CheckBytes;
+use rkyv::de::deserializers::SharedDeserializeMap;
+use rkyv::ser::serializers::AllocSerializer;
+use rkyv::validation::validators::DefaultValidator;
+use rkyv::{AlignedVec, Archive, Deserialize, Serialize};
+
+use multisig_contract_types::{
+    bls, event_version, EventEnvelope, EVENT_VERSION,
+};
+
+use crate::{Error, Result};
+
+/// A connection to a rusk node.
+pub struct Node {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl Node {
+    /// Connects to the node at the given URL.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.into(),
+        }
+    }
+
+    /// Sends the given raw argument to a function of a contract, returning
+    /// the raw response.
+    async fn query_raw(
+        &self,
+        contract: ContractId,
+        fn_name: &str,
+        fn_arg: Vec<u8>,
+        feeder: bool,
+    ) -> Result<Vec<u8>> {
+        let contract = hex::encode(contract.to_bytes());
+        let url = format!("{}/on/contracts:{contract}/{fn_name}", self.url);
+
+        let mut request = self.client.post(url).body(fn_arg);
+        if feeder {
+            request = request.header("Rusk-Feeder", "true");
+        }
+
+        let response = request.send().await?.error_for_status()?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// Queries a function of a contract.
+    pub async fn query<A, R>(
+        &self,
+        contract: ContractId,
+        fn_name: &str,
+        fn_arg: &A,
+    ) -> Result<R>
+    where
+        A: Serialize<AllocSerializer<1024>>,
+        R: Archive,
+        R::Archived: Deserialize<R, SharedDeserializeMap>
+            + for<'b> CheckBytes<DefaultValidator<'b>>,
+    {
+        let bytes = self
+            .query_raw(contract, fn_name, encode(fn_arg)?, false)
+            .await?;
+        decode(&bytes)
+    }
+
+    /// Queries a feeder function of a contract, returning all the items it
+    /// fed.
+    ///
+    /// The node streams the items back to back, so this only works for types
+    /// whose archived representation has a fixed size, such as keys and IDs.
+    pub async fn feeder_query<A, R>(
+        &self,
+        contract: ContractId,
+        fn_name: &str,
+        fn_arg: &A,
+    ) -> Result<Vec<R>>
+    where
+        A: Serialize<AllocSerializer<1024>>,
+        R: Archive,
+        R::Archived: Deserialize<R, SharedDeserializeMap>
+            + for<'b> CheckBytes<DefaultValidator<'b>>,
+    {
+        let bytes = self
+            .query_raw(contract, fn_name, encode(fn_arg)?, true)
+            .await?;
+
+        let item_size = core::mem::size_of::<R::Archived>();
+        if bytes.len() % item_size != 0 {
+            return Err(Error::Deserialization);
+        }
+
+        bytes.chunks(item_size).map(decode).collect()
+    }
+
+    /// Returns the nonce of the given Moonlight account.
+    pub async fn moonlight_nonce(&self, key: &bls::PublicKey) -> Result<u64> {
+        let account: MoonlightAccountData =
+            self.query(TRANSFER_CONTRACT, "account", key).await?;
+        Ok(account.nonce)
+    }
+
+    /// Propagates the given transaction to the network.
+    pub async fn propagate(&self, tx: &Transaction) -> Result<()> {
+        let url = format!("{}/on/transactions/propagate", self.url);
+
+        self.client
+            .post(url)
+            .body(tx.to_var_bytes())
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+}
+
+/// Serializes a value into its archived bytes.
+pub fn encode<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: Serialize<AllocSerializer<1024>>,
+{
+    rkyv::to_bytes::<_, 1024>(value)
+        .map(|bytes| bytes.to_vec())
+        .map_err(|_| Error::Serialization)
+}
+
+/// Deserializes a value from its archived bytes.
+pub fn decode<T>(bytes: &[u8]) -> Result<T>
+where
+    T: Archive,
+    T::Archived: Deserialize<T, SharedDeserializeMap>
+        + for<'b> CheckBytes<DefaultValidator<'b>>,
+{
+    // Archived data must be aligned to be read
+    let mut aligned = AlignedVec::with_capacity(bytes.len());
+    aligned.extend_from_slice(bytes);
+
+    rkyv::from_bytes(&aligned).map_err(|_| Error::Deserialization)
+}
+
+/// Deserializes an event from the payload it was emitted with, checking the
+/// version of its envelope first.
+pub fn decode_event<T>(payload: &[u8]) -> Result<T>
+where
+    T: Archive,
+    EventEnvelope<T>: Archive,
+    <EventEnvelope<T> as Archive>::Archived: Deserialize<EventEnvelope<T>, SharedDeserializeMap>
+        + for<'b> CheckBytes<DefaultValidator<'b>>,
+{
+    match event_version(payload) {
+        Some(EVENT_VERSION) => {}
+        version => return Err(Error::UnsupportedEventVersion(version)),
+    }
+
+    let envelope: EventEnvelope<T> = decode(payload)?;
+    Ok(*envelope.event)
+}